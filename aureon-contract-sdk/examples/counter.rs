@@ -0,0 +1,13 @@
+//! Counts its calls and emits an `Incremented` event, indexed by the calling
+//! contract, with the new count as data.
+
+use aureon_contract_sdk::{Rejected, entry_point, env, events, storage};
+
+fn increment() -> Result<(), Rejected> {
+    let count = storage::read_u64("count").unwrap_or(0) + 1;
+    storage::write_u64("count", count)?;
+    let caller = env::caller().unwrap_or_default();
+    events::emit(&[events::topic("Incremented"), events::topic(&caller)], &count.to_le_bytes())
+}
+
+entry_point!(increment);
@@ -0,0 +1,14 @@
+//! Creates a token owned by the contract, sends a quarter of its supply to
+//! the treasury and emits a `Created` event holding the token id.
+
+use aureon_contract_sdk::{Rejected, entry_point, events, tokens};
+
+const SUPPLY: u64 = 1000;
+
+fn create_vault() -> Result<(), Rejected> {
+    let token = tokens::create("Vault Token", "VLT", 0, SUPPLY)?;
+    tokens::transfer(&token, "treasury", SUPPLY / 4)?;
+    events::emit(&[events::topic("Created")], token.as_bytes())
+}
+
+entry_point!(create_vault);
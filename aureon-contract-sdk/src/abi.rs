@@ -0,0 +1,115 @@
+//! Call encoding of contracts deployed with an ABI
+//!
+//! The node encodes a call by function name as the name followed by the
+//! arguments, and decodes the contract's output as the output values in
+//! order. `u32`, `u64` and `i64` are little-endian, `bool` is one byte, and
+//! strings and bytes are a little-endian `u32` length followed by their bytes.
+//!
+//! ```ignore
+//! fn run() -> Result<(), &'static str> {
+//!     let input = env::input();
+//!     let mut call = Decoder::new(&input);
+//!     match call.string().as_deref() {
+//!         Some("double") => {
+//!             let n = call.u64().ok_or("missing argument")?;
+//!             env::set_output(&Encoder::new().u64(n * 2).finish());
+//!             Ok(())
+//!         }
+//!         _ => Err("unknown function"),
+//!     }
+//! }
+//! ```
+
+/// Reads values from the call input in order; `None` once the input is exhausted
+pub struct Decoder<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (taken, rest) = self.input.split_first_chunk::<N>()?;
+        self.input = rest;
+        Some(*taken)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub fn i64(&mut self) -> Option<i64> {
+        self.take().map(i64::from_le_bytes)
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        self.take::<1>().map(|[byte]| byte != 0)
+    }
+
+    pub fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        if self.input.len() < len {
+            return None;
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Some(bytes)
+    }
+
+    /// A string; the first one of a call is the function name
+    pub fn string(&mut self) -> Option<String> {
+        self.bytes().map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+/// Builds a call's output, one value at a time
+#[derive(Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u32(mut self, value: u32) -> Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn u64(mut self, value: u64) -> Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn i64(mut self, value: i64) -> Self {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn bool(mut self, value: bool) -> Self {
+        self.bytes.push(value as u8);
+        self
+    }
+
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        self = self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value);
+        self
+    }
+
+    pub fn string(self, value: &str) -> Self {
+        self.bytes(value.as_bytes())
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
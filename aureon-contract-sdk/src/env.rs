@@ -0,0 +1,53 @@
+//! Call context, native balances and contract-to-contract calls
+
+use crate::{Rejected, read_sized, status, sys};
+
+/// Print `message` to the node's log
+pub fn log(message: &str) {
+    unsafe { sys::log(message.as_ptr(), message.len() as i32) }
+}
+
+/// Input the contract was called with; empty when a transaction called it
+pub fn input() -> Vec<u8> {
+    read_sized(|ptr, len| unsafe { sys::input(ptr, len) })
+}
+
+/// Return `output` from the call, replacing any output set before
+pub fn set_output(output: &[u8]) {
+    unsafe { sys::set_output(output.as_ptr(), output.len() as i32) }
+}
+
+/// Address of the executing contract
+pub fn address() -> String {
+    String::from_utf8_lossy(&read_sized(|ptr, len| unsafe { sys::address(ptr, len) })).into_owned()
+}
+
+/// Address of the contract that called this one, `None` when a transaction called it
+pub fn caller() -> Option<String> {
+    let caller = read_sized(|ptr, len| unsafe { sys::caller(ptr, len) });
+    (!caller.is_empty()).then(|| String::from_utf8_lossy(&caller).into_owned())
+}
+
+/// Randomness beacon seed of the block being executed
+pub fn random_seed() -> [u8; 32] {
+    let mut seed = [0; 32];
+    unsafe { sys::random_seed(seed.as_mut_ptr()) };
+    seed
+}
+
+/// Native balance of `address`
+pub fn balance(address: &str) -> u64 {
+    unsafe { sys::get_balance(address.as_ptr(), address.len() as i32) }
+}
+
+/// Move `amount` of the native balance from `from` to `to`
+pub fn transfer(from: &str, to: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe { sys::transfer(from.as_ptr(), from.len() as i32, to.as_ptr(), to.len() as i32, amount) })
+}
+
+/// Run the contract at `address` with `input` and at most `gas` of this call's gas
+///
+/// Fails if the callee failed, in which case none of its changes are kept.
+pub fn call(address: &str, input: &[u8], gas: u64) -> Result<(), Rejected> {
+    status(unsafe { sys::call(address.as_ptr(), address.len() as i32, input.as_ptr(), input.len() as i32, gas) })
+}
@@ -0,0 +1,25 @@
+//! Contract events
+//!
+//! Events become logs of the transaction, indexed by their topics in the
+//! block's logs bloom so `/logs` queries can filter on them.
+
+use crate::{Rejected, status, sys};
+
+/// Most indexed topics per event
+pub const MAX_TOPICS: usize = 4;
+
+/// Indexed 32-byte topic
+pub type Topic = [u8; 32];
+
+/// Topic holding `name`, zero-padded; names over 32 bytes are truncated
+pub fn topic(name: &str) -> Topic {
+    let mut topic = [0; 32];
+    let len = name.len().min(32);
+    topic[..len].copy_from_slice(&name.as_bytes()[..len]);
+    topic
+}
+
+/// Emit an event with up to `MAX_TOPICS` topics and free-form `data`
+pub fn emit(topics: &[Topic], data: &[u8]) -> Result<(), Rejected> {
+    status(unsafe { sys::emit_log(topics.as_ptr().cast(), topics.len() as i32, data.as_ptr(), data.len() as i32) })
+}
@@ -0,0 +1,111 @@
+//! Aureon Contract SDK
+//!
+//! Guest-side bindings for writing Aureon contracts in Rust. Contracts are
+//! compiled to `wasm32-unknown-unknown` as a `cdylib` and call the node
+//! through the host functions it registers in the "env" module; this crate
+//! declares those imports once and wraps them in typed functions, so
+//! contract authors never handle raw pointers:
+//!
+//! - [`storage`]: the contract's key-value storage
+//! - [`events`]: logs with indexed topics
+//! - [`env`]: call input, the contract's and its caller's address, the block
+//!   random seed, native balances and contract-to-contract calls
+//! - [`tokens`]: the token ledger, acting as the contract
+//! - [`abi`]: decoding calls by function name and encoding their outputs,
+//!   for contracts deployed with an ABI
+//!
+//! [`entry_point!`] exports the function the node runs. Built for any other
+//! target, the bindings talk to the in-memory host in [`testing`] instead, so
+//! contract logic can be unit tested with `cargo test`.
+//!
+//! ```ignore
+//! use aureon_contract_sdk::{entry_point, storage, Rejected};
+//!
+//! fn increment() -> Result<(), Rejected> {
+//!     let count = storage::read_u64("count").unwrap_or(0);
+//!     storage::write_u64("count", count + 1)
+//! }
+//!
+//! entry_point!(increment);
+//! ```
+
+pub mod abi;
+pub mod env;
+pub mod events;
+pub mod storage;
+mod sys;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+pub mod tokens;
+
+use std::fmt;
+
+/// The node rejected a host call, e.g. a transfer exceeding a balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected;
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rejected by the host")
+    }
+}
+
+/// Turn a host function status (0 on success) into a result
+fn status(code: i32) -> Result<(), Rejected> {
+    if code == 0 { Ok(()) } else { Err(Rejected) }
+}
+
+/// Read a value of unknown length through `read`, a host call writing up
+/// to `max_len` bytes at `out_ptr` and returning the full length
+fn read_sized(read: impl Fn(*mut u8, i32) -> i32) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    let len = read(buffer.as_mut_ptr(), 0).max(0) as usize;
+    buffer.resize(len, 0);
+    read(buffer.as_mut_ptr(), len as i32);
+    buffer
+}
+
+/// What an entry point returns: nothing, or a result whose error aborts the call
+pub trait Outcome {
+    fn into_result(self) -> Result<(), String>;
+}
+
+impl Outcome for () {
+    fn into_result(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<E: fmt::Display> Outcome for Result<(), E> {
+    fn into_result(self) -> Result<(), String> {
+        self.map_err(|e| e.to_string())
+    }
+}
+
+/// Log `message` and trap, reverting every change the call made
+pub fn abort(message: &str) -> ! {
+    env::log(message);
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("contract aborted: {}", message);
+}
+
+/// Export `$handler` as the contract's `run` entry point
+///
+/// The handler takes no arguments (read the call input with
+/// [`env::input`]) and returns `()` or a `Result`; an `Err` is logged and
+/// aborts the call, so none of its state changes are kept.
+#[macro_export]
+macro_rules! entry_point {
+    ($handler:path) => {
+        const _: () = {
+            #[unsafe(no_mangle)]
+            extern "C" fn run() {
+                if let Err(e) = $crate::Outcome::into_result($handler()) {
+                    $crate::abort(&e);
+                }
+            }
+        };
+    };
+}
@@ -0,0 +1,35 @@
+//! Contract key-value storage
+
+use crate::{Rejected, status, sys};
+
+/// Value stored under `key`
+pub fn read(key: &str) -> Option<Vec<u8>> {
+    // The host truncates values to the buffer, so grow it until the value fits
+    let mut buffer = vec![0u8; 64];
+    loop {
+        let len = unsafe { sys::storage_read(key.as_ptr(), key.len() as i32, buffer.as_mut_ptr(), buffer.len() as i32) };
+        if len < 0 {
+            return None;
+        }
+        if (len as usize) < buffer.len() {
+            buffer.truncate(len as usize);
+            return Some(buffer);
+        }
+        buffer.resize(buffer.len() * 2, 0);
+    }
+}
+
+/// Store `value` under `key`
+pub fn write(key: &str, value: &[u8]) -> Result<(), Rejected> {
+    status(unsafe { sys::storage_write(key.as_ptr(), key.len() as i32, value.as_ptr(), value.len() as i32) })
+}
+
+/// Little-endian `u64` stored under `key`, `None` if absent or not 8 bytes
+pub fn read_u64(key: &str) -> Option<u64> {
+    read(key)?.try_into().ok().map(u64::from_le_bytes)
+}
+
+/// Store `value` under `key` as a little-endian `u64`
+pub fn write_u64(key: &str, value: u64) -> Result<(), Rejected> {
+    write(key, &value.to_le_bytes())
+}
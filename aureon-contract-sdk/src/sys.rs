@@ -0,0 +1,54 @@
+//! Raw host function imports
+//!
+//! Signatures mirror the functions the node registers in the "env" module;
+//! see `aureon-node/src/wasm/host_functions.rs` for their gas costs. Off
+//! wasm32 the same functions are provided by the mock host in `testing`.
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "env")]
+unsafe extern "C" {
+    pub fn log(ptr: *const u8, len: i32);
+    pub fn input(out_ptr: *mut u8, max_len: i32) -> i32;
+    pub fn set_output(ptr: *const u8, len: i32);
+    pub fn address(out_ptr: *mut u8, max_len: i32) -> i32;
+    pub fn caller(out_ptr: *mut u8, max_len: i32) -> i32;
+    pub fn random_seed(out_ptr: *mut u8);
+    pub fn get_balance(address_ptr: *const u8, address_len: i32) -> u64;
+    pub fn transfer(from_ptr: *const u8, from_len: i32, to_ptr: *const u8, to_len: i32, amount: u64) -> i32;
+    pub fn storage_read(key_ptr: *const u8, key_len: i32, value_ptr: *mut u8, value_max_len: i32) -> i32;
+    pub fn storage_write(key_ptr: *const u8, key_len: i32, value_ptr: *const u8, value_len: i32) -> i32;
+    pub fn emit_log(topics_ptr: *const u8, topic_count: i32, data_ptr: *const u8, data_len: i32) -> i32;
+    pub fn call(address_ptr: *const u8, address_len: i32, input_ptr: *const u8, input_len: i32, gas: u64) -> i32;
+    pub fn token_create(
+        name_ptr: *const u8,
+        name_len: i32,
+        symbol_ptr: *const u8,
+        symbol_len: i32,
+        decimals: i32,
+        initial_supply: u64,
+        id_ptr: *mut u8,
+    ) -> i32;
+    pub fn token_balance(token_ptr: *const u8, token_len: i32, account_ptr: *const u8, account_len: i32) -> u64;
+    pub fn token_transfer(token_ptr: *const u8, token_len: i32, to_ptr: *const u8, to_len: i32, amount: u64) -> i32;
+    pub fn token_approve(
+        token_ptr: *const u8,
+        token_len: i32,
+        spender_ptr: *const u8,
+        spender_len: i32,
+        amount: u64,
+    ) -> i32;
+    pub fn token_transfer_from(
+        token_ptr: *const u8,
+        token_len: i32,
+        owner_ptr: *const u8,
+        owner_len: i32,
+        to_ptr: *const u8,
+        to_len: i32,
+        amount: u64,
+    ) -> i32;
+    pub fn token_mint(token_ptr: *const u8, token_len: i32, to_ptr: *const u8, to_len: i32, amount: u64) -> i32;
+    pub fn token_burn(token_ptr: *const u8, token_len: i32, amount: u64) -> i32;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::testing::host::*;
@@ -0,0 +1,268 @@
+//! In-memory host for unit testing contracts natively
+//!
+//! Off wasm32 the bindings call this host instead of the node. It keeps
+//! storage, emitted events, logged messages, native balances and the call
+//! context per thread, so every test starts from an empty host. Token
+//! operations and contract calls need the node and are always rejected.
+
+use crate::events::{MAX_TOPICS, Topic};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Host {
+    storage: HashMap<String, Vec<u8>>,
+    events: Vec<(Vec<Topic>, Vec<u8>)>,
+    messages: Vec<String>,
+    balances: HashMap<String, u64>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    address: String,
+    caller: String,
+    random_seed: [u8; 32],
+}
+
+thread_local! {
+    static HOST: RefCell<Host> = RefCell::new(Host::default());
+}
+
+fn with_host<R>(f: impl FnOnce(&mut Host) -> R) -> R {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+/// Clear the host of this thread
+pub fn reset() {
+    with_host(|host| *host = Host::default());
+}
+
+/// Call the contract with `input`, as another contract would
+pub fn set_input(input: &[u8]) {
+    with_host(|host| host.input = input.to_vec());
+}
+
+pub fn set_address(address: &str) {
+    with_host(|host| host.address = address.to_string());
+}
+
+/// Contract calling the executing one; `None` for a transaction
+pub fn set_caller(caller: Option<&str>) {
+    with_host(|host| host.caller = caller.unwrap_or_default().to_string());
+}
+
+pub fn set_random_seed(seed: [u8; 32]) {
+    with_host(|host| host.random_seed = seed);
+}
+
+pub fn set_balance(address: &str, balance: u64) {
+    with_host(|host| host.balances.insert(address.to_string(), balance));
+}
+
+/// Value the contract stored under `key`
+pub fn stored(key: &str) -> Option<Vec<u8>> {
+    with_host(|host| host.storage.get(key).cloned())
+}
+
+/// Topics and data of the events emitted so far
+pub fn events() -> Vec<(Vec<Topic>, Vec<u8>)> {
+    with_host(|host| host.events.clone())
+}
+
+/// Output the contract set
+pub fn output() -> Vec<u8> {
+    with_host(|host| host.output.clone())
+}
+
+/// Messages logged so far
+pub fn messages() -> Vec<String> {
+    with_host(|host| host.messages.clone())
+}
+
+/// Host functions with the signatures of the wasm imports
+pub(crate) mod host {
+    use super::*;
+    use std::slice;
+
+    unsafe fn bytes<'a>(ptr: *const u8, len: i32) -> &'a [u8] {
+        if len <= 0 { &[] } else { unsafe { slice::from_raw_parts(ptr, len as usize) } }
+    }
+
+    unsafe fn string(ptr: *const u8, len: i32) -> String {
+        String::from_utf8_lossy(unsafe { bytes(ptr, len) }).into_owned()
+    }
+
+    unsafe fn write_truncated(out_ptr: *mut u8, max_len: i32, value: &[u8]) -> i32 {
+        let len = value.len().min(max_len.max(0) as usize);
+        if len > 0 {
+            unsafe { slice::from_raw_parts_mut(out_ptr, len) }.copy_from_slice(&value[..len]);
+        }
+        value.len() as i32
+    }
+
+    pub unsafe fn log(ptr: *const u8, len: i32) {
+        let message = unsafe { string(ptr, len) };
+        with_host(|host| host.messages.push(message));
+    }
+
+    pub unsafe fn input(out_ptr: *mut u8, max_len: i32) -> i32 {
+        let input = with_host(|host| host.input.clone());
+        unsafe { write_truncated(out_ptr, max_len, &input) }
+    }
+
+    pub unsafe fn set_output(ptr: *const u8, len: i32) {
+        let output = unsafe { bytes(ptr, len) }.to_vec();
+        with_host(|host| host.output = output);
+    }
+
+    pub unsafe fn address(out_ptr: *mut u8, max_len: i32) -> i32 {
+        let address = with_host(|host| host.address.clone());
+        unsafe { write_truncated(out_ptr, max_len, address.as_bytes()) }
+    }
+
+    pub unsafe fn caller(out_ptr: *mut u8, max_len: i32) -> i32 {
+        let caller = with_host(|host| host.caller.clone());
+        unsafe { write_truncated(out_ptr, max_len, caller.as_bytes()) }
+    }
+
+    pub unsafe fn random_seed(out_ptr: *mut u8) {
+        let seed = with_host(|host| host.random_seed);
+        unsafe { write_truncated(out_ptr, 32, &seed) };
+    }
+
+    pub unsafe fn get_balance(address_ptr: *const u8, address_len: i32) -> u64 {
+        let address = unsafe { string(address_ptr, address_len) };
+        with_host(|host| host.balances.get(&address).copied().unwrap_or(0))
+    }
+
+    pub unsafe fn transfer(from_ptr: *const u8, from_len: i32, to_ptr: *const u8, to_len: i32, amount: u64) -> i32 {
+        let (from, to) = unsafe { (string(from_ptr, from_len), string(to_ptr, to_len)) };
+        with_host(|host| {
+            let from_balance = host.balances.get(&from).copied().unwrap_or(0);
+            if from_balance < amount {
+                return 1;
+            }
+            host.balances.insert(from, from_balance - amount);
+            *host.balances.entry(to).or_insert(0) += amount;
+            0
+        })
+    }
+
+    pub unsafe fn storage_read(key_ptr: *const u8, key_len: i32, value_ptr: *mut u8, value_max_len: i32) -> i32 {
+        let key = unsafe { string(key_ptr, key_len) };
+        match with_host(|host| host.storage.get(&key).cloned()) {
+            Some(value) => {
+                let len = value.len().min(value_max_len.max(0) as usize);
+                unsafe { write_truncated(value_ptr, value_max_len, &value) };
+                len as i32
+            }
+            None => -1,
+        }
+    }
+
+    pub unsafe fn storage_write(key_ptr: *const u8, key_len: i32, value_ptr: *const u8, value_len: i32) -> i32 {
+        let (key, value) = unsafe { (string(key_ptr, key_len), bytes(value_ptr, value_len).to_vec()) };
+        with_host(|host| host.storage.insert(key, value));
+        0
+    }
+
+    pub unsafe fn emit_log(topics_ptr: *const u8, topic_count: i32, data_ptr: *const u8, data_len: i32) -> i32 {
+        if topic_count as usize > MAX_TOPICS {
+            return 1;
+        }
+        let topics = unsafe { bytes(topics_ptr, topic_count * 32) }
+            .chunks(32)
+            .map(|topic| topic.try_into().expect("topics are 32 bytes"))
+            .collect();
+        let data = unsafe { bytes(data_ptr, data_len) }.to_vec();
+        with_host(|host| host.events.push((topics, data)));
+        0
+    }
+
+    pub unsafe fn call(_: *const u8, _: i32, _: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+
+    pub unsafe fn token_create(_: *const u8, _: i32, _: *const u8, _: i32, _: i32, _: u64, _: *mut u8) -> i32 {
+        1
+    }
+
+    pub unsafe fn token_balance(_: *const u8, _: i32, _: *const u8, _: i32) -> u64 {
+        0
+    }
+
+    pub unsafe fn token_transfer(_: *const u8, _: i32, _: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+
+    pub unsafe fn token_approve(_: *const u8, _: i32, _: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn token_transfer_from(_: *const u8, _: i32, _: *const u8, _: i32, _: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+
+    pub unsafe fn token_mint(_: *const u8, _: i32, _: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+
+    pub unsafe fn token_burn(_: *const u8, _: i32, _: u64) -> i32 {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{Decoder, Encoder};
+    use crate::{Rejected, env, events, storage, tokens};
+
+    #[test]
+    fn test_bindings_round_trip_through_mock_host() {
+        reset();
+        set_address("0xcounter");
+        set_caller(Some("0xcaller"));
+        set_input(b"hello");
+        assert_eq!(env::address(), "0xcounter");
+        assert_eq!(env::caller().as_deref(), Some("0xcaller"));
+        assert_eq!(env::input(), b"hello");
+
+        // Values longer than the first read buffer are read in full
+        let long = vec![7u8; 300];
+        storage::write("long", &long).unwrap();
+        assert_eq!(storage::read("long"), Some(long));
+        assert_eq!(storage::read("missing"), None);
+        storage::write_u64("count", 41).unwrap();
+        assert_eq!(storage::read_u64("count"), Some(41));
+
+        events::emit(&[events::topic("Incremented")], b"42").unwrap();
+        assert_eq!(events(), vec![(vec![events::topic("Incremented")], b"42".to_vec())]);
+        assert_eq!(events::emit(&[[0; 32]; 5], b""), Err(Rejected));
+
+        set_balance("alice", 10);
+        assert_eq!(env::transfer("alice", "bob", 11), Err(Rejected));
+        env::transfer("alice", "bob", 4).unwrap();
+        assert_eq!((env::balance("alice"), env::balance("bob")), (6, 4));
+        assert_eq!(tokens::create("Gold", "GLD", 0, 100), Err(Rejected));
+
+        set_caller(None);
+        assert_eq!(env::caller(), None);
+    }
+
+    #[test]
+    fn test_abi_call_decodes_input_and_encodes_output() {
+        reset();
+        let call = Encoder::new().string("transfer").string("bob").u64(40).bool(true).finish();
+        set_input(&call);
+
+        let input = env::input();
+        let mut decoder = Decoder::new(&input);
+        assert_eq!(decoder.string().as_deref(), Some("transfer"));
+        assert_eq!(decoder.string().as_deref(), Some("bob"));
+        assert_eq!((decoder.u64(), decoder.bool()), (Some(40), Some(true)));
+        assert_eq!(decoder.u32(), None);
+
+        env::set_output(&Encoder::new().i64(-1).bytes(&[0xab]).finish());
+        assert_eq!(output(), [&(-1i64).to_le_bytes()[..], &1u32.to_le_bytes(), &[0xab]].concat());
+    }
+}
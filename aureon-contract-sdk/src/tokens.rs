@@ -0,0 +1,66 @@
+//! Token ledger operations
+//!
+//! The executing contract is the sender of every operation: it pays
+//! transfers from its own balance, owns the tokens it creates and may only
+//! mint those.
+
+use crate::{Rejected, status, sys};
+
+/// Create a token owned by the contract, holding its `initial_supply`; returns the token id
+pub fn create(name: &str, symbol: &str, decimals: u8, initial_supply: u64) -> Result<String, Rejected> {
+    let mut id = [0u8; 64];
+    status(unsafe {
+        sys::token_create(
+            name.as_ptr(),
+            name.len() as i32,
+            symbol.as_ptr(),
+            symbol.len() as i32,
+            decimals as i32,
+            initial_supply,
+            id.as_mut_ptr(),
+        )
+    })?;
+    Ok(String::from_utf8_lossy(&id).into_owned())
+}
+
+/// Balance of `account` in `token`
+pub fn balance(token: &str, account: &str) -> u64 {
+    unsafe { sys::token_balance(token.as_ptr(), token.len() as i32, account.as_ptr(), account.len() as i32) }
+}
+
+/// Send `amount` of `token` from the contract to `to`
+pub fn transfer(token: &str, to: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe { sys::token_transfer(token.as_ptr(), token.len() as i32, to.as_ptr(), to.len() as i32, amount) })
+}
+
+/// Allow `spender` to transfer up to `amount` of the contract's `token`
+pub fn approve(token: &str, spender: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe {
+        sys::token_approve(token.as_ptr(), token.len() as i32, spender.as_ptr(), spender.len() as i32, amount)
+    })
+}
+
+/// Send `amount` of `owner`'s `token` to `to`, within the allowance `owner` gave the contract
+pub fn transfer_from(token: &str, owner: &str, to: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe {
+        sys::token_transfer_from(
+            token.as_ptr(),
+            token.len() as i32,
+            owner.as_ptr(),
+            owner.len() as i32,
+            to.as_ptr(),
+            to.len() as i32,
+            amount,
+        )
+    })
+}
+
+/// Mint `amount` of a token the contract owns to `to`
+pub fn mint(token: &str, to: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe { sys::token_mint(token.as_ptr(), token.len() as i32, to.as_ptr(), to.len() as i32, amount) })
+}
+
+/// Burn `amount` of the contract's own `token`
+pub fn burn(token: &str, amount: u64) -> Result<(), Rejected> {
+    status(unsafe { sys::token_burn(token.as_ptr(), token.len() as i32, amount) })
+}
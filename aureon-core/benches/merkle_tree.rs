@@ -0,0 +1,61 @@
+use aureon_core::merkle_tree::{IncrementalMerkleTree, MerkleTree};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+const LEAF_COUNTS: [usize; 3] = [64, 1_024, 16_384];
+
+fn leaves(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("tx_{:06}", i)).collect()
+}
+
+fn build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("build");
+    for count in LEAF_COUNTS {
+        let leaves = leaves(count);
+        group.bench_with_input(BenchmarkId::new("tree", count), &leaves, |b, leaves| {
+            b.iter(|| MerkleTree::build(black_box(leaves.clone())).root())
+        });
+        group.bench_with_input(BenchmarkId::new("incremental", count), &leaves, |b, leaves| {
+            b.iter(|| black_box(leaves.iter().cloned().collect::<IncrementalMerkleTree>()).root())
+        });
+    }
+    group.finish();
+}
+
+fn append(c: &mut Criterion) {
+    let mut group = c.benchmark_group("append");
+    for count in LEAF_COUNTS {
+        let tree: IncrementalMerkleTree = leaves(count).into_iter().collect();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &tree, |b, tree| {
+            b.iter_batched(
+                || tree.clone(),
+                |mut tree| {
+                    tree.append(black_box("tx_next"));
+                    black_box(tree.root());
+                    // Returned so the clone is dropped outside the measurement
+                    tree
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn prove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prove");
+    for count in LEAF_COUNTS {
+        let tree: IncrementalMerkleTree = leaves(count).into_iter().collect();
+        let indices: Vec<usize> = (0..count).step_by(count / 16).collect();
+        group.bench_with_input(BenchmarkId::new("single", count), &tree, |b, tree| {
+            b.iter(|| indices.iter().all(|&index| tree.proof(index).is_some_and(|proof| proof.verify())))
+        });
+        group.bench_with_input(BenchmarkId::new("multi", count), &tree, |b, tree| {
+            b.iter(|| tree.multiproof(black_box(&indices)).is_some_and(|proof| proof.verify()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, build, append, prove);
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+//! Chain Specification
+//!
+//! A chain spec fixes everything a node needs to start a network from
+//! genesis: its chain id, consensus parameters, initial validators, account
+//! allocations and the heights at which protocol forks activate. Nodes
+//! started from the same spec derive the same genesis hash, which tells
+//! them apart from nodes of other networks.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Networks whose specs are built in, selectable by name
+pub const BUILTIN_NETWORKS: [&str; 3] = ["devnet", "testnet", "mainnet"];
+
+/// Chain id of a node started without a chain spec
+pub const LOCAL_CHAIN_ID: &str = "aureon-local";
+
+/// Consensus parameters of a chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusParams {
+    /// Consensus engine: "pow", "pos" or "poa"
+    pub engine: String,
+    /// Slot length in milliseconds
+    pub block_interval_ms: u64,
+    /// Initial PoW difficulty
+    #[serde(default = "default_pow_difficulty")]
+    pub pow_difficulty: u8,
+    /// Block time the PoW difficulty is retargeted towards, in milliseconds
+    #[serde(default = "default_pow_target_block_time_ms")]
+    pub pow_target_block_time_ms: u64,
+    /// Minimum stake of a PoS validator
+    #[serde(default)]
+    pub pos_min_stake: u64,
+    /// Size of the PoS validator set
+    #[serde(default = "default_pos_validator_count")]
+    pub pos_validator_count: usize,
+    /// Blocks per epoch
+    #[serde(default = "default_epoch_length")]
+    pub epoch_length: u64,
+}
+
+fn default_pow_difficulty() -> u8 {
+    4
+}
+
+fn default_pow_target_block_time_ms() -> u64 {
+    10_000
+}
+
+fn default_pos_validator_count() -> usize {
+    21
+}
+
+fn default_epoch_length() -> u64 {
+    100
+}
+
+/// Genesis definition of a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub chain_id: String,
+    /// Genesis time in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub consensus: ConsensusParams,
+    /// Initial validators and their stakes
+    pub validators: BTreeMap<String, u64>,
+    /// Genesis account balances
+    pub allocations: BTreeMap<String, u64>,
+    /// Protocol forks and the heights they activate at
+    #[serde(default)]
+    pub forks: BTreeMap<String, u64>,
+}
+
+impl ChainSpec {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let spec: ChainSpec = serde_json::from_str(json).map_err(|e| format!("Invalid chain spec: {}", e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Built-in spec of the network `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        let json = match name {
+            "devnet" => include_str!("../chains/devnet.json"),
+            "testnet" => include_str!("../chains/testnet.json"),
+            "mainnet" => include_str!("../chains/mainnet.json"),
+            _ => return None,
+        };
+        Some(Self::from_json(json).expect("Built-in chain spec is valid"))
+    }
+
+    /// Spec of a built-in network, or read from the JSON file at `chain`
+    pub fn load(chain: &str) -> Result<Self, String> {
+        if let Some(spec) = Self::builtin(chain) {
+            return Ok(spec);
+        }
+        let json = std::fs::read_to_string(chain).map_err(|e| format!("Failed to read chain spec {}: {}", chain, e))?;
+        Self::from_json(&json)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chain_id.is_empty() {
+            return Err("Chain id must not be empty".to_string());
+        }
+        if !matches!(self.consensus.engine.as_str(), "pow" | "pos" | "poa") {
+            return Err(format!("Unknown consensus engine {}", self.consensus.engine));
+        }
+        if self.consensus.engine != "pow" && self.validators.is_empty() {
+            return Err("A PoS or PoA chain needs initial validators".to_string());
+        }
+        if self.consensus.block_interval_ms == 0 {
+            return Err("Block interval must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Hex-encoded SHA-256 of the spec's canonical JSON encoding
+    ///
+    /// Maps are ordered by key, so the hash does not depend on the order
+    /// entries appear in the spec file.
+    pub fn genesis_hash(&self) -> String {
+        let encoded = serde_json::to_vec(self).expect("Chain spec serializes to JSON");
+        hex::encode(Sha256::digest(&encoded))
+    }
+
+    /// Total supply allocated at genesis
+    pub fn genesis_supply(&self) -> u64 {
+        self.allocations.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_hash_ignores_entry_order() {
+        let spec = ChainSpec::from_json(
+            r#"{"chain_id": "aureon-test", "timestamp": 1,
+                "consensus": {"engine": "pos", "block_interval_ms": 5000},
+                "validators": {"alice": 100, "bob": 200},
+                "allocations": {"alice": 10, "bob": 20}}"#,
+        )
+        .unwrap();
+        let reordered = ChainSpec::from_json(
+            r#"{"allocations": {"bob": 20, "alice": 10}, "chain_id": "aureon-test",
+                "validators": {"bob": 200, "alice": 100}, "timestamp": 1,
+                "consensus": {"block_interval_ms": 5000, "engine": "pos"}}"#,
+        )
+        .unwrap();
+        assert_eq!(spec.genesis_hash(), reordered.genesis_hash());
+        assert_eq!(spec.genesis_supply(), 30);
+
+        let mut other = spec.clone();
+        other.chain_id = "aureon-other".to_string();
+        assert_ne!(spec.genesis_hash(), other.genesis_hash());
+    }
+
+    #[test]
+    fn test_builtin_networks_are_distinct() {
+        let hashes: Vec<String> =
+            BUILTIN_NETWORKS.iter().map(|name| ChainSpec::builtin(name).unwrap().genesis_hash()).collect();
+        assert!(hashes[0] != hashes[1] && hashes[1] != hashes[2] && hashes[0] != hashes[2]);
+        assert!(ChainSpec::builtin("moonnet").is_none());
+        assert!(ChainSpec::from_json(r#"{"chain_id": "", "timestamp": 0}"#).is_err());
+    }
+}
@@ -1 +1,3 @@
-pub mod types;
\ No newline at end of file
+pub mod chain_spec;
+pub mod merkle_tree;
+pub mod types;
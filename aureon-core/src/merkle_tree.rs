@@ -0,0 +1,637 @@
+//! Binary merkle trees over hex-encoded hashes
+//!
+//! Transactions and logs are committed to in blocks through these trees, and
+//! light clients and bridges check inclusion against their roots. A tree can
+//! be built at once from all its leaves, or appended to one leaf at a time;
+//! both give the same root, hashing an odd last node of a level with itself.
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+
+/// Node in a merkle tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleTreeNode {
+    pub hash: String,
+    pub left: Option<Box<MerkleTreeNode>>,
+    pub right: Option<Box<MerkleTreeNode>>,
+}
+
+impl MerkleTreeNode {
+    /// Create a leaf node with a transaction hash
+    pub fn leaf(tx_hash: String) -> Self {
+        MerkleTreeNode {
+            hash: tx_hash,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Create a parent node by hashing two child nodes
+    pub fn parent(left: MerkleTreeNode, right: MerkleTreeNode) -> Self {
+        let hash = hash_pair(&left.hash, &right.hash);
+        MerkleTreeNode {
+            hash,
+            left: Some(Box::new(left)),
+            right: Some(Box::new(right)),
+        }
+    }
+}
+
+/// Element in a merkle inclusion proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct MerkleProofElement {
+    pub hash: String,
+    pub is_left: bool,  // True if hash is to the left, false if to the right
+}
+
+/// Merkle inclusion proof for a transaction
+#[derive(Debug, Clone)]
+pub struct MerkleInclusionProof {
+    pub tx_hash: String,
+    pub merkle_root: String,
+    pub proof_path: Vec<MerkleProofElement>,
+    pub tx_index: usize,  // Position in transaction list
+}
+
+impl MerkleInclusionProof {
+    /// Verify this proof is valid for the given merkle root
+    pub fn verify(&self) -> bool {
+        let mut current_hash = self.tx_hash.clone();
+
+        for element in &self.proof_path {
+            current_hash = if element.is_left {
+                hash_pair(&element.hash, &current_hash)
+            } else {
+                hash_pair(&current_hash, &element.hash)
+            };
+        }
+
+        current_hash == self.merkle_root
+    }
+}
+
+/// Hash a single value, as leaves are before going into a tree
+pub fn hash_value(value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(value.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hash two values together
+pub fn hash_pair(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Merkle tree for transaction verification
+pub struct MerkleTree {
+    root: Option<MerkleTreeNode>,
+    leaf_count: usize,
+}
+
+impl MerkleTree {
+    /// Create an empty merkle tree
+    pub fn new() -> Self {
+        MerkleTree {
+            root: None,
+            leaf_count: 0,
+        }
+    }
+
+    /// Build a merkle tree from transaction hashes
+    pub fn build(tx_hashes: Vec<String>) -> Self {
+        if tx_hashes.is_empty() {
+            return MerkleTree {
+                root: None,
+                leaf_count: 0,
+            };
+        }
+
+        let leaf_count = tx_hashes.len();
+
+        // Hash the transaction hashes first
+        let hashed_txs: Vec<String> = tx_hashes
+            .into_iter()
+            .map(|tx| hash_value(&tx))
+            .collect();
+
+        // Create leaf nodes
+        let mut nodes: Vec<MerkleTreeNode> = hashed_txs
+            .into_iter()
+            .map(MerkleTreeNode::leaf)
+            .collect();
+
+        // Build tree bottom-up
+        while nodes.len() > 1 {
+            let mut next_level = Vec::with_capacity(nodes.len().div_ceil(2));
+            let mut level = nodes.into_iter();
+            while let Some(left) = level.next() {
+                // Odd number of nodes - hash the last node with itself
+                let right = level.next().unwrap_or_else(|| left.clone());
+                next_level.push(MerkleTreeNode::parent(left, right));
+            }
+            nodes = next_level;
+        }
+
+        let root = nodes.into_iter().next();
+
+        MerkleTree { root, leaf_count }
+    }
+
+    /// Get the merkle root hash
+    pub fn root(&self) -> Option<String> {
+        self.root.as_ref().map(|n| n.hash.clone())
+    }
+
+    /// Get the merkle inclusion proof for a transaction at given index
+    pub fn get_proof(&self, tx_index: usize) -> Option<MerkleInclusionProof> {
+        if tx_index >= self.leaf_count {
+            return None;
+        }
+
+        let merkle_root = self.root()?.clone();
+        let mut proof_path = Vec::new();
+
+        // Traverse from root to leaf, collecting sibling hashes
+        if let Some(root) = &self.root {
+            let height = self.leaf_count.next_power_of_two().trailing_zeros();
+            self.collect_proof_path(root, tx_index, 0, height, &mut proof_path);
+        }
+
+        // We need the tx hash - reconstruct it or store it separately
+        // For now, we'll indicate this needs the tx hash
+        Some(MerkleInclusionProof {
+            tx_hash: String::new(),  // Will be filled by caller
+            merkle_root,
+            proof_path,
+            tx_index,
+        })
+    }
+
+    /// Collect proof path from root to leaf; the left subtree of a node
+    /// `height` levels above the leaves always covers `2^(height - 1)` leaves
+    fn collect_proof_path(
+        &self,
+        node: &MerkleTreeNode,
+        tx_index: usize,
+        current_index: usize,
+        height: u32,
+        proof_path: &mut Vec<MerkleProofElement>,
+    ) {
+        if height == 0 {
+            return;  // Reached leaf
+        }
+
+        let left_size = 1 << (height - 1);
+
+        if let (Some(left), Some(right)) = (&node.left, &node.right) {
+            if tx_index < current_index + left_size {
+                // Target is in left subtree
+                self.collect_proof_path(left, tx_index, current_index, height - 1, proof_path);
+                proof_path.push(MerkleProofElement {
+                    hash: right.hash.clone(),
+                    is_left: false,
+                });
+            } else {
+                // Target is in right subtree
+                self.collect_proof_path(right, tx_index, current_index + left_size, height - 1, proof_path);
+                proof_path.push(MerkleProofElement {
+                    hash: left.hash.clone(),
+                    is_left: true,
+                });
+            }
+        }
+    }
+
+    /// Get proof size (number of hashes needed)
+    pub fn proof_size(&self, tx_index: usize) -> usize {
+        if let Some(proof) = self.get_proof(tx_index) {
+            proof.proof_path.len()
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for MerkleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append-only merkle tree keeping the nodes of every level, so leaves can be
+/// added one at a time and proven at any point
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalMerkleTree {
+    /// Nodes whose children are all present, leaves first; a level's last
+    /// node only gets a parent once its sibling is appended
+    levels: Vec<Vec<String>>,
+}
+
+impl IncrementalMerkleTree {
+    /// Create an empty tree
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a leaf, hashing the parents it completes
+    pub fn append(&mut self, value: &str) {
+        let mut hash = hash_value(value);
+        for height in 0.. {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            let level = &mut self.levels[height];
+            level.push(hash);
+            if level.len() % 2 == 1 {
+                return;
+            }
+            hash = hash_pair(&level[level.len() - 2], &level[level.len() - 1]);
+        }
+    }
+
+    /// Number of leaves
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    /// Whether no leaf was appended yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Node each level gets from an odd tail below it, from the leaves up to
+    /// the root's level
+    fn carries(&self) -> Vec<Option<String>> {
+        let mut carries = vec![None];
+        for height in 0.. {
+            let level = self.levels.get(height).map_or(&[][..], Vec::as_slice);
+            let carry = carries[height].as_ref();
+            let len = level.len() + usize::from(carry.is_some());
+            if len <= 1 {
+                break;
+            }
+            let next = if len % 2 == 1 {
+                let last = carry.or(level.last()).expect("Level has nodes");
+                Some(hash_pair(last, last))
+            } else {
+                carry.map(|carry| hash_pair(level.last().expect("Level has nodes"), carry))
+            };
+            carries.push(next);
+        }
+        carries
+    }
+
+    /// Node at `index` of the level `height` above the leaves
+    fn node<'a>(&'a self, height: usize, index: usize, carries: &'a [Option<String>]) -> Option<&'a String> {
+        let level = self.levels.get(height).map_or(&[][..], Vec::as_slice);
+        match level.get(index) {
+            Some(node) => Some(node),
+            None if index == level.len() => carries[height].as_ref(),
+            None => None,
+        }
+    }
+
+    /// Number of nodes in the level `height` above the leaves
+    fn level_len(&self, height: usize, carries: &[Option<String>]) -> usize {
+        self.levels.get(height).map_or(0, Vec::len) + usize::from(carries[height].is_some())
+    }
+
+    /// Root hash, the same `MerkleTree::build` gives for these leaves
+    pub fn root(&self) -> Option<String> {
+        let carries = self.carries();
+        self.node(carries.len() - 1, 0, &carries).cloned()
+    }
+
+    /// Inclusion proof of the leaf at `index`, carrying the leaf's hash
+    pub fn proof(&self, index: usize) -> Option<MerkleInclusionProof> {
+        let tx_hash = self.levels.first()?.get(index)?.clone();
+        let carries = self.carries();
+        let mut proof_path = Vec::with_capacity(carries.len() - 1);
+        let mut position = index;
+        for height in 0..carries.len() - 1 {
+            let sibling = position ^ 1;
+            // A node without a sibling is hashed with itself
+            let hash = self.node(height, sibling, &carries).or(self.node(height, position, &carries))?;
+            proof_path.push(MerkleProofElement {
+                hash: hash.clone(),
+                is_left: sibling < position,
+            });
+            position /= 2;
+        }
+        Some(MerkleInclusionProof {
+            tx_hash,
+            merkle_root: self.node(carries.len() - 1, 0, &carries)?.clone(),
+            proof_path,
+            tx_index: index,
+        })
+    }
+
+    /// One proof for the leaves at all of `indices`, holding only the sibling
+    /// hashes their paths do not share
+    pub fn multiproof(&self, indices: &[usize]) -> Option<MerkleMultiproof> {
+        let mut known = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+        if known.is_empty() || *known.last()? >= self.len() {
+            return None;
+        }
+        let leaves = known.iter().map(|&index| (index, self.levels[0][index].clone())).collect();
+        let carries = self.carries();
+        let mut hashes = Vec::new();
+        for height in 0..carries.len() - 1 {
+            let len = self.level_len(height, &carries);
+            for &position in &known {
+                let sibling = position ^ 1;
+                if sibling < len && known.binary_search(&sibling).is_err() {
+                    hashes.push(self.node(height, sibling, &carries)?.clone());
+                }
+            }
+            known = known.into_iter().map(|position| position / 2).collect();
+            known.dedup();
+        }
+        Some(MerkleMultiproof {
+            leaves,
+            leaf_count: self.len(),
+            merkle_root: self.node(carries.len() - 1, 0, &carries)?.clone(),
+            hashes,
+        })
+    }
+}
+
+impl FromIterator<String> for IncrementalMerkleTree {
+    fn from_iter<I: IntoIterator<Item = String>>(leaves: I) -> Self {
+        let mut tree = Self::new();
+        for leaf in leaves {
+            tree.append(&leaf);
+        }
+        tree
+    }
+}
+
+/// Merkle proof that several leaves are in one tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct MerkleMultiproof {
+    /// Indices and hashes of the proven leaves, in index order
+    pub leaves: Vec<(usize, String)>,
+    /// Number of leaves in the tree
+    pub leaf_count: usize,
+    pub merkle_root: String,
+    /// Sibling hashes the proven leaves do not give, level by level from
+    /// the leaves up and left to right within a level
+    pub hashes: Vec<String>,
+}
+
+impl MerkleMultiproof {
+    /// Whether the proof is valid for its merkle root
+    pub fn verify(&self) -> bool {
+        let ordered = self.leaves.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        match self.leaves.last() {
+            Some((last, _)) if ordered && *last < self.leaf_count => {}
+            _ => return false,
+        }
+
+        let mut level = self.leaves.clone();
+        let mut len = self.leaf_count;
+        let mut hashes = self.hashes.iter();
+        while len > 1 {
+            let mut next_level = Vec::with_capacity(level.len());
+            let mut nodes = level.iter().peekable();
+            while let Some((position, hash)) = nodes.next() {
+                let parent = if position % 2 == 1 {
+                    // Left sibling was not proven, or it would have taken this node
+                    match hashes.next() {
+                        Some(left) => hash_pair(left, hash),
+                        None => return false,
+                    }
+                } else if let Some((_, right)) = nodes.next_if(|(next, _)| *next == position + 1) {
+                    hash_pair(hash, right)
+                } else if position + 1 < len {
+                    match hashes.next() {
+                        Some(right) => hash_pair(hash, right),
+                        None => return false,
+                    }
+                } else {
+                    hash_pair(hash, hash)
+                };
+                next_level.push((position / 2, parent));
+            }
+            level = next_level;
+            len = len.div_ceil(2);
+        }
+        hashes.next().is_none() && level[0].1 == self.merkle_root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_tree_single_transaction() {
+        let tree = MerkleTree::build(vec!["tx_001".to_string()]);
+        assert_eq!(tree.root(), Some(hash_value("tx_001")));
+        assert_eq!(tree.leaf_count, 1);
+    }
+
+    #[test]
+    fn test_merkle_tree_two_transactions() {
+        let tree = MerkleTree::build(vec![
+            "tx_001".to_string(),
+            "tx_002".to_string(),
+        ]);
+        assert!(tree.root().is_some());
+        assert_eq!(tree.leaf_count, 2);
+    }
+
+    #[test]
+    fn test_merkle_tree_four_transactions() {
+        let tree = MerkleTree::build(vec![
+            "tx_001".to_string(),
+            "tx_002".to_string(),
+            "tx_003".to_string(),
+            "tx_004".to_string(),
+        ]);
+        assert!(tree.root().is_some());
+        assert_eq!(tree.leaf_count, 4);
+    }
+
+    #[test]
+    fn test_merkle_tree_empty() {
+        let tree = MerkleTree::build(vec![]);
+        assert_eq!(tree.root(), None);
+        assert_eq!(tree.leaf_count, 0);
+    }
+
+    #[test]
+    fn test_merkle_tree_deterministic() {
+        let txs = vec![
+            "tx_001".to_string(),
+            "tx_002".to_string(),
+            "tx_003".to_string(),
+        ];
+
+        let tree1 = MerkleTree::build(txs.clone());
+        let tree2 = MerkleTree::build(txs);
+
+        assert_eq!(tree1.root(), tree2.root());
+    }
+
+    #[test]
+    fn test_merkle_inclusion_proof() {
+        let txs = vec![
+            "tx_001".to_string(),
+            "tx_002".to_string(),
+            "tx_003".to_string(),
+            "tx_004".to_string(),
+        ];
+
+        let tree = MerkleTree::build(txs);
+        let proof = tree.get_proof(0);
+        assert!(proof.is_some());
+
+        let proof = proof.unwrap();
+        assert_eq!(proof.tx_index, 0);
+        assert!(!proof.merkle_root.is_empty());
+    }
+
+    #[test]
+    fn test_merkle_inclusion_proof_out_of_bounds() {
+        let tree = MerkleTree::build(vec!["tx_001".to_string(), "tx_002".to_string()]);
+        let proof = tree.get_proof(5);
+        assert!(proof.is_none());
+    }
+
+    #[test]
+    fn test_merkle_proof_size() {
+        let txs: Vec<String> = (0..8)
+            .map(|i| format!("tx_{:03}", i))
+            .collect();
+
+        let tree = MerkleTree::build(txs.clone());
+
+        // For 8 transactions, proof should require log2(8) = 3 hashes
+        let proof_size = tree.proof_size(0);
+        assert_eq!(proof_size, 3);
+    }
+
+    #[test]
+    fn test_every_transaction_is_committed_and_provable() {
+        for tx_count in 1..=17 {
+            let txs: Vec<String> = (0..tx_count).map(|i| format!("tx_{:03}", i)).collect();
+            let tree = MerkleTree::build(txs.clone());
+            for (index, tx) in txs.iter().enumerate() {
+                let mut proof = tree.get_proof(index).unwrap();
+                proof.tx_hash = hash_value(tx);
+                assert!(proof.verify(), "proof of tx {} of {}", index, tx_count);
+
+                let mut changed = txs.clone();
+                changed[index] = "tx_other".to_string();
+                assert_ne!(MerkleTree::build(changed).root(), tree.root());
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_tree_matches_built_tree() {
+        let mut tree = IncrementalMerkleTree::new();
+        assert_eq!(tree.root(), None);
+        assert!(tree.proof(0).is_none());
+
+        let mut txs = Vec::new();
+        for tx_count in 1..=33 {
+            let tx = format!("tx_{:03}", tx_count);
+            tree.append(&tx);
+            txs.push(tx);
+            assert_eq!(tree.len(), tx_count);
+            assert_eq!(tree.root(), MerkleTree::build(txs.clone()).root(), "{} leaves", tx_count);
+
+            for (index, tx) in txs.iter().enumerate() {
+                let proof = tree.proof(index).unwrap();
+                assert_eq!(proof.tx_hash, hash_value(tx));
+                assert!(proof.verify(), "proof of tx {} of {}", index, tx_count);
+            }
+            assert!(tree.proof(tx_count).is_none());
+        }
+    }
+
+    #[test]
+    fn test_multiproof_covers_several_leaves() {
+        for tx_count in 1..=17usize {
+            let tree: IncrementalMerkleTree = (0..tx_count).map(|i| format!("tx_{:03}", i)).collect();
+            let subsets: Vec<Vec<usize>> = vec![
+                vec![0],
+                vec![tx_count - 1],
+                (0..tx_count).collect(),
+                (0..tx_count).step_by(3).collect(),
+                vec![tx_count / 2, 0, tx_count / 2],
+            ];
+            for indices in subsets {
+                let proof = tree.multiproof(&indices).unwrap();
+                assert!(proof.verify(), "{:?} of {}", indices, tx_count);
+                assert_eq!(Some(proof.merkle_root.clone()), tree.root());
+
+                let single_hashes: usize = proof.leaves.iter().map(|(index, _)| tree.proof(*index).unwrap().proof_path.len()).sum();
+                assert!(proof.hashes.len() <= single_hashes);
+
+                let mut forged = proof.clone();
+                forged.leaves[0].1 = hash_value("tx_other");
+                assert!(!forged.verify());
+                let mut truncated = proof;
+                if truncated.hashes.pop().is_some() {
+                    assert!(!truncated.verify());
+                }
+            }
+        }
+
+        let tree: IncrementalMerkleTree = (0..4).map(|i| format!("tx_{:03}", i)).collect();
+        assert!(tree.multiproof(&[]).is_none());
+        assert!(tree.multiproof(&[1, 4]).is_none());
+        // Proving every leaf needs no hashes beyond the leaves themselves
+        assert!(tree.multiproof(&[0, 1, 2, 3]).unwrap().hashes.is_empty());
+    }
+
+    #[test]
+    fn test_merkle_proof_logarithmic_scaling() {
+        // Test that proof size grows logarithmically with transaction count
+        for tx_count in [2, 4, 8, 16, 32] {
+            let txs: Vec<String> = (0..tx_count)
+                .map(|i| format!("tx_{:03}", i))
+                .collect();
+
+            let tree = MerkleTree::build(txs);
+            let proof_size = tree.proof_size(0);
+
+            // Proof size should be roughly log2(tx_count)
+            let expected_log = (tx_count as f64).log2().ceil() as usize;
+            assert!(proof_size <= expected_log + 1);
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_element_creation() {
+        let element = MerkleProofElement {
+            hash: "hash_123".to_string(),
+            is_left: true,
+        };
+        assert_eq!(element.hash, "hash_123");
+        assert!(element.is_left);
+    }
+
+    #[test]
+    fn test_hash_pair_deterministic() {
+        let hash1 = hash_pair("a", "b");
+        let hash2 = hash_pair("a", "b");
+        assert_eq!(hash1, hash2);
+    }
+
+    #[test]
+    fn test_hash_pair_order_matters() {
+        let hash_ab = hash_pair("a", "b");
+        let hash_ba = hash_pair("b", "a");
+        assert_ne!(hash_ab, hash_ba);
+    }
+}
@@ -0,0 +1,48 @@
+//! Inserts and removes arbitrary keys, checking the trie against a map of
+//! its expected contents and its root against a trie built from scratch
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use aureon_node::mpt::MerklePatriciaTrie;
+use aureon_node::mpt::trie::verify_proof;
+use libfuzzer_sys::fuzz_target;
+use std::collections::BTreeMap;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(Vec<u8>, Vec<u8>),
+    Remove(Vec<u8>),
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut trie = MerklePatriciaTrie::new();
+    let mut expected = BTreeMap::new();
+    for op in ops {
+        match op {
+            Op::Insert(key, value) => {
+                trie.insert(key.clone(), value.clone());
+                expected.insert(key, value);
+            }
+            Op::Remove(key) => {
+                assert_eq!(trie.remove(&key), expected.remove(&key));
+            }
+        }
+    }
+
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = expected.clone().into_iter().collect();
+    assert_eq!(trie.entries(), entries);
+
+    let mut rebuilt = MerklePatriciaTrie::new();
+    for (key, value) in &expected {
+        rebuilt.insert(key.clone(), value.clone());
+    }
+    let root = trie.root_hash();
+    assert_eq!(root, rebuilt.root_hash());
+
+    for (key, value) in &expected {
+        assert_eq!(trie.get(key.clone()), Some(value.as_slice()));
+        let proof = trie.generate_proof(key.clone());
+        assert_eq!(verify_proof(&root, key, &proof), Ok(Some(value.clone())));
+    }
+});
@@ -0,0 +1,16 @@
+//! Feeds untrusted proofs to the verifier, which must reject or answer but
+//! never panic or allocate without bound
+
+#![no_main]
+
+use aureon_node::mpt::node::ProofNode;
+use aureon_node::mpt::trie::verify_proof;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (Vec<u8>, Vec<u8>, Vec<Vec<u8>>)| {
+    let (root, key, proof) = input;
+    for node in &proof {
+        let _ = ProofNode::decode(node);
+    }
+    let _ = verify_proof(&root, &key, &proof);
+});
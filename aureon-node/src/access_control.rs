@@ -1,4 +1,9 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Access control and authorization module
 ///
@@ -16,6 +21,21 @@ pub enum Role {
     Guest,
 }
 
+impl Role {
+    /// Parse a role name as written in the configuration, e.g. "operator"
+    pub fn from_name(name: &str) -> Option<Role> {
+        match name.to_lowercase().as_str() {
+            "admin" => Some(Role::Admin),
+            "operator" => Some(Role::Operator),
+            "node" => Some(Role::Node),
+            "validator" => Some(Role::Validator),
+            "user" => Some(Role::User),
+            "guest" => Some(Role::Guest),
+            _ => None,
+        }
+    }
+}
+
 /// Permission type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Permission {
@@ -30,6 +50,9 @@ pub enum Permission {
     StopNode,
     RestartNode,
     ViewMetrics,
+    DeployContract,
+    /// Manage peers and the mempool
+    ManageNode,
 
     // Node permissions
     ProposeBlock,
@@ -72,6 +95,9 @@ impl RolePermissions {
                 permissions.insert(Permission::StartNode);
                 permissions.insert(Permission::StopNode);
                 permissions.insert(Permission::ViewMetrics);
+                permissions.insert(Permission::DeployContract);
+                permissions.insert(Permission::ManageNode);
+                permissions.insert(Permission::QueryState);
             }
             Role::Operator => {
                 permissions.insert(Permission::StartNode);
@@ -79,6 +105,9 @@ impl RolePermissions {
                 permissions.insert(Permission::RestartNode);
                 permissions.insert(Permission::ViewMetrics);
                 permissions.insert(Permission::ViewLogs);
+                permissions.insert(Permission::DeployContract);
+                permissions.insert(Permission::ManageNode);
+                permissions.insert(Permission::QueryState);
             }
             Role::Node => {
                 permissions.insert(Permission::ProposeBlock);
@@ -174,9 +203,22 @@ pub struct AccessControlManager {
     users: HashMap<String, User>,
     role_permissions: HashMap<Role, RolePermissions>,
     access_log: Vec<AccessLogEntry>,
+    /// Users by the API token they authenticate with
+    tokens: HashMap<String, String>,
+    /// File access log entries are appended to, one JSON object per line
+    log_file: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+/// Reason an API request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessDenied {
+    /// No token, or a token of no known user
+    Unauthenticated,
+    /// The user is inactive or their role lacks the permission
+    Forbidden,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccessLogEntry {
     pub user_id: String,
     pub action: String,
@@ -204,7 +246,56 @@ impl AccessControlManager {
             users: HashMap::new(),
             role_permissions,
             access_log: Vec::new(),
+            tokens: HashMap::new(),
+            log_file: None,
+        }
+    }
+
+    /// Append access log entries to `path` as well as keeping them in memory
+    pub fn with_log_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Let the existing user `user_id` authenticate with `token`
+    pub fn add_token(&mut self, token: &str, user_id: &str) -> Result<(), String> {
+        if !self.users.contains_key(user_id) {
+            return Err(format!("User {} not found", user_id));
         }
+        if self.tokens.contains_key(token) {
+            return Err("Token already assigned".to_string());
+        }
+        self.tokens.insert(token.to_string(), user_id.to_string());
+        Ok(())
+    }
+
+    /// User holding `token`
+    pub fn authenticate(&self, token: &str) -> Option<&User> {
+        self.tokens.get(token).and_then(|user_id| self.users.get(user_id))
+    }
+
+    /// Check that the holder of `token` may use `permission` on `resource`,
+    /// returning their user id
+    pub fn authorize(
+        &mut self,
+        token: Option<&str>,
+        permission: Permission,
+        resource: &str,
+    ) -> Result<String, AccessDenied> {
+        let Some(user) = token.and_then(|token| self.authenticate(token)) else {
+            self.log_access("anonymous", &format!("{:?}", permission), resource, false);
+            return Err(AccessDenied::Unauthenticated);
+        };
+        let user_id = user.id.clone();
+        let allowed = user.is_active && self.role_permissions[&user.role].has_permission(permission);
+        self.log_access(&user_id, &format!("{:?}", permission), resource, allowed);
+        if !allowed {
+            return Err(AccessDenied::Forbidden);
+        }
+        if let Some(user) = self.users.get_mut(&user_id) {
+            user.update_login(now());
+        }
+        Ok(user_id)
     }
 
     /// Add user
@@ -281,13 +372,24 @@ impl AccessControlManager {
 
     /// Log access attempt
     fn log_access(&mut self, user_id: &str, action: &str, resource: &str, allowed: bool) {
-        self.access_log.push(AccessLogEntry {
+        let entry = AccessLogEntry {
             user_id: user_id.to_string(),
             action: action.to_string(),
             resource: resource.to_string(),
-            timestamp: 0, // Would be actual timestamp
+            timestamp: now(),
             allowed,
-        });
+        };
+        if let Some(path) = &self.log_file {
+            let written = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", serde_json::to_string(&entry).unwrap_or_default()));
+            if let Err(e) = written {
+                tracing::warn!(path = %path.display(), error = %e, "failed to persist access log entry");
+            }
+        }
+        self.access_log.push(entry);
     }
 
     /// Get access log
@@ -358,6 +460,10 @@ impl AccessControlManager {
     }
 }
 
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
 /// Permission audit
 pub struct PermissionAudit {
     findings: Vec<String>,
@@ -630,4 +736,32 @@ mod tests {
 
         assert_eq!(acm.active_users(), 1);
     }
+
+    #[test]
+    fn test_authorize_tokens_and_persist_log() {
+        let path = std::env::temp_dir().join(format!("aureon_access_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut acm = AccessControlManager::new().with_log_file(&path);
+        acm.add_user(User::new("ops".to_string(), Role::Operator)).unwrap();
+        acm.add_user(User::new("viewer".to_string(), Role::Guest)).unwrap();
+        acm.add_token("ops-token", "ops").unwrap();
+        acm.add_token("view-token", "viewer").unwrap();
+        assert!(acm.add_token("other", "nobody").is_err());
+
+        let deploy = Permission::DeployContract;
+        assert_eq!(acm.authorize(Some("ops-token"), deploy, "/contract/deploy"), Ok("ops".to_string()));
+        assert_eq!(acm.authorize(Some("view-token"), deploy, "/contract/deploy"), Err(AccessDenied::Forbidden));
+        assert_eq!(acm.authorize(Some("wrong"), deploy, "/contract/deploy"), Err(AccessDenied::Unauthenticated));
+        assert!(acm.get_user("ops").unwrap().last_login.is_some());
+
+        let persisted: Vec<AccessLogEntry> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(persisted.len(), 3);
+        assert_eq!(persisted[2].user_id, "anonymous");
+        assert_eq!(acm.denied_access_count(), 2);
+        let _ = std::fs::remove_file(path);
+    }
 }
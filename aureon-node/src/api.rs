@@ -1,23 +1,60 @@
 use axum::{
-    extract::{Path, Json, State as AxumState},
-    routing::{get, post},
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Path, Json, Query, State as AxumState,
+    },
+    http::StatusCode,
+    middleware,
+    response::{Html, IntoResponse, Response},
+    routing::{delete, get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio::net::TcpListener;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 use axum::serve;
 use hex;
 
-use crate::types::Transaction;
+use crate::types::{Block, Transaction, TransactionPayload};
+use crate::config::ApiConfig;
+use crate::config_reload::ConfigReloader;
+use crate::service_manager::Shutdown;
+use crate::chain_handle::ChainHandle;
 use crate::db::Db;
-use crate::contract_registry::ContractRegistry;
+use crate::mpt::MerklePatriciaTrie;
+use crate::contract_abi::{AbiFunction, ContractAbi};
+use crate::contract_registry::{ContractRegistry, RegistryError, UpgradeAuthority};
+use crate::logs::{Log, LogFilter};
 use crate::wasm::WasmRuntime;
-use crate::indexer::BlockchainIndexer;
+use crate::error::{ErrorKind, NodeError};
+use crate::indexer::{BlockchainIndexer, CursorPage, IndexerError, TransactionIndexEntry};
 use crate::mempool::TransactionMempool;
 use crate::metrics::Metrics;
-use crate::monitoring::monitoring_router;
+use crate::state_archive::StateArchive;
+use crate::spv_checkpoint::CheckpointSigner;
+use crate::shard_coordinator::ShardMap;
+use crate::incentive_programs::StakingSystem;
+use crate::state_processor;
+use crate::emission::EmissionSchedule;
+use crate::storage_rent::{self, AccountRent};
+use crate::block_producer::SealTrigger;
+use crate::bridge;
+use crate::multisig;
+use crate::replay_protection;
+use crate::state_diff;
+use crate::metrics_history::{self, MetricsHistory};
+use crate::block_timings::BlockTimings;
+use crate::timelock;
+use crate::monitoring::{monitoring_router, NodeHealth};
+use crate::access_control::AccessControlManager;
+use crate::api_auth;
+use crate::api_rate_limit::{self, ApiRateLimiter};
+use crate::network::{Network, NetworkError};
+use crate::network::identity::PeerId;
+use crate::events::{ContractEvent, EventBus, NodeEvent, Subscription, SubscriptionCommand, Topic};
+use tokio::sync::broadcast::error::RecvError;
 
 // ============================================================================
 // Request/Response Structs
@@ -28,30 +65,97 @@ pub struct BalanceRequest {
     pub address: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct BalanceResponse {
     pub address: String,
     pub balance: u64,
+    /// Height the balance was read at, when querying archived state
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NonceResponse {
+    pub address: String,
+    pub nonce: u64,  // Next nonce the mempool will accept
 }
 
-#[derive(Deserialize)]
+/// Balance, nonces and stake of an account in one response
+#[derive(Serialize, ToSchema)]
+pub struct AccountResponse {
+    pub address: String,
+    pub balance: u64,
+    /// Next nonce as of the latest block
+    pub nonce: u64,
+    /// Next nonce after the account's pending mempool transactions
+    pub pending_nonce: u64,
+    /// Active self-stake
+    pub staked: u128,
+    /// Stake delegated to validators
+    pub delegated: u128,
+    pub pending_rewards: u128,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct TransactionRequest {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    #[serde(default)]
+    pub gas_price: Option<u64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SignedTransactionRequest {
+    /// Chain the transaction is signed for, as reported by /chain/head
+    #[serde(default)]
+    pub chain_id: String,
     pub from: String,
     pub to: String,
     pub amount: u64,
     pub nonce: u64,
+    #[serde(default)]
+    pub gas_price: Option<u64>,
     pub public_key: String,  // Hex-encoded Ed25519 public key
     pub signature: String,   // Hex-encoded Ed25519 signature
 }
 
-#[derive(Serialize)]
+/// Signed transaction of any payload type in its canonical encoding
+#[derive(Deserialize, ToSchema)]
+pub struct RawTransactionRequest {
+    pub tx: String,  // Hex-encoded canonical (bincode) encoding
+}
+
+/// Signed transactions submitted together, each in its canonical encoding
+#[derive(Deserialize, ToSchema)]
+pub struct BatchTransactionRequest {
+    pub transactions: Vec<String>,  // Hex-encoded canonical (bincode) encodings
+}
+
+/// Outcome of one transaction of a batch: its hash, or why it was rejected
+#[derive(Serialize, ToSchema)]
+pub struct BatchItemResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Stable code of the rejection, as in error responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchTransactionResponse {
+    pub accepted: usize,
+    pub rejected: usize,
+    /// One result per submitted transaction, in order
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Most transactions a single /submit-batch request can carry
+const MAX_BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, ToSchema)]
 pub struct TransactionResponse {
     pub status: String,
     pub message: String,
@@ -64,59 +168,194 @@ pub struct BlockResponse {
     pub transaction_count: usize,
 }
 
-#[derive(Deserialize)]
+/// Deployer recorded for contracts deployed through the API without one
+const API_DEPLOYER: &str = "api";
+
+#[derive(Deserialize, ToSchema)]
 pub struct ContractDeployRequest {
     pub code: Vec<u8>,
     pub gas_limit: u64,
+    /// Account deploying the contract; its address is derived from the
+    /// deployer and the deployer's next nonce
+    #[serde(default)]
+    pub deployer: Option<String>,
+    /// Who may upgrade the contract; the deployer when omitted
+    #[serde(default)]
+    pub upgrade_authority: Option<UpgradeAuthority>,
+    /// ABI registered for the contract, letting it be called by function name
+    #[serde(default)]
+    pub abi: Option<ContractAbi>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ContractDeployResponse {
     pub address: String,
     pub status: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ContractCallRequest {
     pub contract_address: String,
     pub function: String,
-    pub args: String,
+    /// Arguments of `function`, an array or an object by name, encoded with
+    /// the contract's ABI; ignored for contracts without one
+    #[serde(default)]
+    pub args: serde_json::Value,
     pub gas_limit: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ContractCallResponse {
     pub success: bool,
     pub output: String,
     pub gas_used: u64,
+    pub logs: Vec<Log>,
+    /// Outputs decoded with the contract's ABI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<serde_json::Value>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ChainInfoResponse {
     pub chain_name: String,
+    /// Chain id transactions must be signed for
+    pub chain_id: Option<String>,
     pub best_block_number: u64,
     pub best_block_hash: String,
+    pub finalized_block_number: Option<u64>,
+    /// Whether the best block is finalized
+    pub finalized: bool,
+    /// Confirmations of the best block, counting itself
+    pub confirmations: u64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct FinalityResponse {
+    pub finalized_block_number: Option<u64>,
+    pub finalized_block_hash: Option<String>,
+}
+
+/// Body of every error response
+#[derive(Serialize, ToSchema)]
 pub struct ErrorResponse {
-    pub error: String,
+    /// Always "error"
+    pub status: String,
+    /// Stable code clients can match on
+    pub code: String,
+    pub message: String,
 }
 
-#[derive(Serialize, Clone)]
-pub struct BlockEvent {
-    pub event_type: String,
-    pub block_hash: String,
-    pub block_number: u64,
-    pub timestamp: u64,
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SubscribeParams {
+    /// Comma-separated topics to subscribe to on connect
+    pub topics: Option<String>,
 }
 
-#[derive(Serialize, Clone)]
-pub struct TransactionEvent {
-    pub event_type: String,
-    pub tx_hash: String,
-    pub from: String,
-    pub block_number: u64,
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Zero-based page of an explorer listing
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    pub page: Option<usize>,
+    pub page_size: Option<usize>,
+}
+
+impl PageParams {
+    fn page(&self) -> usize {
+        self.page.unwrap_or(0)
+    }
+
+    fn page_size(&self) -> usize {
+        self.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/// Page of a cursor-paginated listing; `cursor` is the `next_cursor` of the previous page
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CursorParams {
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl CursorParams {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/// Block range and size of an address history page
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AddressHistoryParams {
+    pub from_block: Option<u64>,
+    /// Highest block to read, the head by default; pass a page's `next_block` to continue
+    pub to_block: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// Height to read state at instead of the latest block
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockParams {
+    pub block: Option<u64>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TopAccountsParams {
+    pub limit: Option<usize>,
+}
+
+/// Blocks to seal through /dev/mine
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MineParams {
+    pub blocks: Option<u64>,
+}
+
+/// Time range of /metrics/history, such as "1h"
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryParams {
+    pub range: Option<String>,
+}
+
+/// Most blocks a single /dev/mine request can seal
+const MAX_MINE_BLOCKS: u64 = 1000;
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogsParams {
+    pub address: Option<String>,
+    /// Comma-separated topics by position; an empty entry matches any topic
+    pub topics: Option<String>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl LogsParams {
+    fn into_filter(self) -> LogFilter {
+        let topics = self
+            .topics
+            .as_deref()
+            .map(|topics| {
+                topics
+                    .split(',')
+                    .map(|t| Some(t.trim().to_string()).filter(|t| !t.is_empty()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        LogFilter {
+            address: self.address,
+            topics,
+            from_block: self.from_block,
+            to_block: self.to_block,
+        }
+    }
 }
 
 // ============================================================================
@@ -126,396 +365,2514 @@ pub struct TransactionEvent {
 #[derive(Clone)]
 pub struct ApiState {
     pub db: Arc<Db>,
+    /// Committed blocks by height and hash, and the head
+    pub chain: ChainHandle,
+    pub trie: Arc<RwLock<MerklePatriciaTrie>>,
     pub contract_registry: Arc<Mutex<ContractRegistry>>,
     pub indexer: Arc<BlockchainIndexer>,
     pub mempool: Arc<TransactionMempool>,
     pub metrics: Arc<Metrics>,
+    pub events: EventBus,
+    pub checkpoints: Arc<Mutex<CheckpointSigner>>,
+    /// Stakes, delegations and rewards
+    pub staking: Arc<Mutex<StakingSystem>>,
+    /// Block reward schedule
+    pub emission: EmissionSchedule,
+    pub config_reloader: Arc<ConfigReloader>,
+    /// API users and the access log
+    pub access: Arc<Mutex<AccessControlManager>>,
+    pub network: Network,
+    /// Subsystem health reported by /health
+    pub health: NodeHealth,
+    /// Seals blocks on /dev/mine, unless blocks are sealed in slots
+    pub seal: Option<SealTrigger>,
+    /// Sampled metrics served by /metrics/history, unless disabled
+    pub metrics_history: Option<Arc<MetricsHistory>>,
+    /// Per-stage latencies of recently produced blocks
+    pub block_timings: BlockTimings,
+    /// Shard layout served by /shards, when sharding is enabled
+    pub shards: Option<Arc<RwLock<ShardMap>>>,
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Result of a handler that can fail with a node error
+type ApiResult<T = serde_json::Value> = Result<Json<T>, NodeError>;
+
+impl IntoResponse for NodeError {
+    fn into_response(self) -> Response {
+        let status = match self.kind() {
+            ErrorKind::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = ErrorResponse {
+            status: "error".to_string(),
+            code: self.code().to_string(),
+            message: self.to_string(),
+        };
+        (status, Json(body)).into_response()
+    }
 }
 
 // ============================================================================
 // Handler Functions
 // ============================================================================
 
+#[utoipa::path(
+    get,
+    path = "/balance/{address}",
+    tag = "accounts",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        BlockParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = BalanceResponse),
+    ),
+)]
 async fn get_balance(
     Path(address): Path<String>,
     AxumState(state): AxumState<ApiState>,
-) -> Json<BalanceResponse> {
+    Query(params): Query<BlockParams>,
+) -> Json<serde_json::Value> {
+    let latest = state.db.get(address.as_bytes())
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+        .unwrap_or(0);
+
+    let balance = match params.block {
+        Some(height) => match StateArchive::new(&state.db).balance_at(&address, height, latest) {
+            Ok(balance) => balance,
+            Err(e) => return Json(serde_json::json!({ "error": e })),
+        },
+        None => latest,
+    };
+
+    Json(serde_json::json!(BalanceResponse {
+        address,
+        balance,
+        block: params.block,
+    }))
+}
+
+/// GET /proof/:address
+///
+/// The account's balance with the trie nodes proving it against the
+/// current state root.
+#[utoipa::path(
+    get,
+    path = "/proof/{address}",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_proof(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let trie = match state.trie.read() {
+        Ok(trie) => trie,
+        Err(e) => return Json(serde_json::json!({
+            "error": format!("Failed to read state: {}", e)
+        })),
+    };
+    let key = address.as_bytes().to_vec();
+    let balance = trie.get(key.clone())
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()));
+    let proof: Vec<String> = trie.generate_proof(key).iter().map(hex::encode).collect();
+    Json(serde_json::json!({
+        "address": address,
+        "balance": balance,
+        "state_root": hex::encode(trie.root_hash()),
+        "proof": proof,
+    }))
+}
+
+/// GET /spv/checkpoint
+///
+/// The latest signed checkpoint an SPV client can start its header chain from.
+#[utoipa::path(
+    get,
+    path = "/spv/checkpoint",
+    tag = "light clients",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_spv_checkpoint(
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    match state.checkpoints.lock().unwrap().latest() {
+        Some(checkpoint) => Json(serde_json::json!(checkpoint)),
+        None => Json(serde_json::json!({ "error": "No checkpoint signed yet" })),
+    }
+}
+
+/// GET /shards
+///
+/// Current shard layout: the key range of account address hashes each
+/// shard owns and the validators assigned to it, as of the last epoch
+/// boundary.
+#[utoipa::path(
+    get,
+    path = "/shards",
+    tag = "sharding",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_shards(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let Some(shards) = &state.shards else {
+        return Json(serde_json::json!({ "error": "Sharding is disabled" }));
+    };
+    let shards = shards.read().unwrap();
+    Json(serde_json::json!({
+        "status": "ok",
+        "epoch": shards.epoch(),
+        "shard_count": shards.coordinator().num_shards(),
+        "shards": shards.shards(),
+    }))
+}
+
+/// GET /state-root/:height
+#[utoipa::path(
+    get,
+    path = "/state-root/{height}",
+    tag = "accounts",
+    params(("height" = u64, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_state_root(
+    Path(height): Path<u64>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    match StateArchive::new(&state.db).state_root_at(height) {
+        Some(root) => Json(serde_json::json!({
+            "height": height,
+            "state_root": hex::encode(root),
+        })),
+        None => Json(serde_json::json!({
+            "error": format!("No archived state at height {}", height)
+        })),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/nonce/{address}",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = NonceResponse),
+    ),
+)]
+async fn get_nonce(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let nonce = state.mempool.next_nonce(&address)?;
+    Ok(Json(serde_json::json!(NonceResponse { address, nonce })))
+}
+
+/// GET /account/:address
+///
+/// What a wallet shows for an account, read in one call.
+#[utoipa::path(
+    get,
+    path = "/account/{address}",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = AccountResponse),
+        (status = 500, description = "Mempool unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_account(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult<AccountResponse> {
     let balance = state.db.get(address.as_bytes())
         .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
         .unwrap_or(0);
+    let nonce = state.mempool.committed_nonce(&address)?;
+    let pending_nonce = state.mempool.get_account_nonce(&address)?;
+
+    let staking = state.staking.lock().unwrap();
+    let staked = staking.get_staked_amount(&address);
+    let delegated = staking.delegations_of(&address).iter().map(|delegation| delegation.amount).sum();
+    let pending_rewards = staking.get_pending_rewards(&address);
 
-    Json(BalanceResponse {
-        address: address.clone(),
+    Ok(Json(AccountResponse {
+        address,
         balance,
-    })
+        nonce,
+        pending_nonce,
+        staked,
+        delegated,
+        pending_rewards,
+    }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/submit-tx",
+    tag = "transactions",
+    request_body = TransactionRequest,
+    responses(
+        (status = 200, description = "Added to the mempool", body = TransactionResponse),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 409, description = "Conflicts with a pooled transaction or the account nonce", body = ErrorResponse),
+        (status = 503, description = "Mempool full", body = ErrorResponse),
+    ),
+)]
+#[tracing::instrument(name = "submit_tx", skip_all, fields(endpoint = "/submit-tx"))]
 async fn submit_transaction(
     AxumState(state): AxumState<ApiState>,
     Json(payload): Json<TransactionRequest>,
-) -> Json<TransactionResponse> {
+) -> ApiResult<TransactionResponse> {
     // Validate transaction
     if payload.from.is_empty() || payload.to.is_empty() {
-        state.metrics.transactions_failed.inc();
-        return Json(TransactionResponse {
-            status: "error".to_string(),
-            message: "Invalid sender or recipient".to_string(),
-        });
+        return Err(rejected(&state, NodeError::InvalidRequest("Invalid sender or recipient".to_string())));
     }
 
     if payload.amount == 0 {
-        state.metrics.transactions_failed.inc();
-        return Json(TransactionResponse {
-            status: "error".to_string(),
-            message: "Amount must be greater than 0".to_string(),
-        });
+        return Err(rejected(&state, NodeError::InvalidRequest("Amount must be greater than 0".to_string())));
     }
 
     // Create Transaction and add to mempool
-    let tx = Transaction::transfer(payload.from.clone(), payload.to.clone(), payload.amount);
-
-    match state.mempool.add_transaction(tx) {
-        Ok(tx_hash) => {
-            state.metrics.transactions_submitted.inc();
-            Json(TransactionResponse {
-                status: "success".to_string(),
-                message: format!("Transaction {} added to mempool", tx_hash),
-            })
-        }
-        Err(e) => {
-            state.metrics.transactions_failed.inc();
-            Json(TransactionResponse {
-                status: "error".to_string(),
-                message: format!("Failed to add transaction: {}", e),
-            })
-        }
+    let mut tx = Transaction::transfer(payload.from.clone(), payload.to.clone(), payload.amount);
+    if let Some(gas_price) = payload.gas_price {
+        tx.gas_price = gas_price;
     }
+
+    let tx_hash = state.mempool.add_transaction(tx).map_err(|e| rejected(&state, e))?;
+    tracing::info!(tx_hash = %tx_hash, "transaction submitted");
+    state.metrics.transactions_submitted.inc();
+    Ok(Json(TransactionResponse {
+        status: "success".to_string(),
+        message: format!("Transaction {} added to mempool", tx_hash),
+    }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/submit-signed-tx",
+    tag = "transactions",
+    request_body = SignedTransactionRequest,
+    responses(
+        (status = 200, description = "Added to the mempool", body = TransactionResponse),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 409, description = "Conflicts with a pooled transaction or the account nonce", body = ErrorResponse),
+        (status = 503, description = "Mempool full", body = ErrorResponse),
+    ),
+)]
+#[tracing::instrument(name = "submit_tx", skip_all, fields(endpoint = "/submit-signed-tx"))]
 async fn submit_signed_transaction(
     AxumState(state): AxumState<ApiState>,
     Json(payload): Json<SignedTransactionRequest>,
-) -> Json<TransactionResponse> {
+) -> ApiResult<TransactionResponse> {
     // Validate transaction
     if payload.from.is_empty() || payload.to.is_empty() {
-        state.metrics.transactions_failed.inc();
-        return Json(TransactionResponse {
-            status: "error".to_string(),
-            message: "Invalid sender or recipient".to_string(),
-        });
+        return Err(rejected(&state, NodeError::InvalidRequest("Invalid sender or recipient".to_string())));
     }
 
     if payload.amount == 0 {
-        state.metrics.transactions_failed.inc();
-        return Json(TransactionResponse {
-            status: "error".to_string(),
-            message: "Amount must be greater than 0".to_string(),
-        });
+        return Err(rejected(&state, NodeError::InvalidRequest("Amount must be greater than 0".to_string())));
     }
 
     // Decode public key and signature from hex
     let public_key = match hex::decode(&payload.public_key) {
         Ok(pk) => pk,
         Err(_) => {
-            state.metrics.transactions_failed.inc();
-            return Json(TransactionResponse {
-                status: "error".to_string(),
-                message: "Invalid public key format (must be hex)".to_string(),
-            })
+            return Err(rejected(&state, NodeError::InvalidRequest("Invalid public key format (must be hex)".to_string())));
         }
     };
 
     let signature = match hex::decode(&payload.signature) {
         Ok(sig) => sig,
         Err(_) => {
-            state.metrics.transactions_failed.inc();
-            return Json(TransactionResponse {
-                status: "error".to_string(),
-                message: "Invalid signature format (must be hex)".to_string(),
-            })
+            return Err(rejected(&state, NodeError::InvalidRequest("Invalid signature format (must be hex)".to_string())));
         }
     };
 
     // Create signed transaction
     let mut tx = Transaction::transfer(payload.from.clone(), payload.to.clone(), payload.amount);
+    tx.chain_id = payload.chain_id;
     tx.nonce = payload.nonce;
+    if let Some(gas_price) = payload.gas_price {
+        tx.gas_price = gas_price;
+    }
     tx.public_key = public_key;
     tx.signature = signature;
 
     // Add to mempool (signature verification happens here)
-    match state.mempool.add_transaction(tx) {
-        Ok(tx_hash) => {
-            state.metrics.transactions_submitted.inc();
-            Json(TransactionResponse {
-                status: "success".to_string(),
-                message: format!("Signed transaction {} added to mempool", tx_hash),
-            })
-        }
-        Err(e) => {
-            state.metrics.transactions_failed.inc();
-            Json(TransactionResponse {
-                status: "error".to_string(),
-                message: format!("Failed to add transaction: {}", e),
-            })
-        }
+    let tx_hash = state.mempool.add_transaction(tx).map_err(|e| rejected(&state, e))?;
+    tracing::info!(tx_hash = %tx_hash, "transaction submitted");
+    state.metrics.transactions_submitted.inc();
+    Ok(Json(TransactionResponse {
+        status: "success".to_string(),
+        message: format!("Signed transaction {} added to mempool", tx_hash),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/submit-raw-tx",
+    tag = "transactions",
+    request_body = RawTransactionRequest,
+    responses(
+        (status = 200, description = "Added to the mempool", body = TransactionResponse),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 409, description = "Conflicts with a pooled transaction or the account nonce", body = ErrorResponse),
+        (status = 503, description = "Mempool full", body = ErrorResponse),
+    ),
+)]
+#[tracing::instrument(name = "submit_tx", skip_all, fields(endpoint = "/submit-raw-tx"))]
+async fn submit_raw_transaction(
+    AxumState(state): AxumState<ApiState>,
+    Json(payload): Json<RawTransactionRequest>,
+) -> ApiResult<TransactionResponse> {
+    let tx = decode_raw_transaction(&state, &payload.tx).map_err(|e| rejected(&state, e))?;
+    let tx_hash = state.mempool.add_transaction(tx).map_err(|e| rejected(&state, e))?;
+    tracing::info!(tx_hash = %tx_hash, "transaction submitted");
+    state.metrics.transactions_submitted.inc();
+    Ok(Json(TransactionResponse {
+        status: "success".to_string(),
+        message: format!("Signed transaction {} added to mempool", tx_hash),
+    }))
+}
+
+/// Decode a hex-encoded canonical transaction, which must be signed
+fn decode_raw_transaction(state: &ApiState, raw: &str) -> Result<Transaction, NodeError> {
+    let tx = hex::decode(raw)
+        .map_err(|_| "Invalid transaction format (must be hex)".to_string())
+        .and_then(|bytes| Transaction::from_canonical_bytes(&bytes))
+        .map_err(NodeError::InvalidRequest)?;
+
+    // Multisig accounts sign with several keys and leave the public key empty
+    let multisig = multisig::config(&*state.trie.read().unwrap(), &tx.from).is_some();
+    if tx.signature.is_empty() || (tx.public_key.is_empty() && !multisig) {
+        return Err(NodeError::InvalidRequest("Raw transactions must be signed".to_string()));
+    }
+    Ok(tx)
+}
+
+/// POST /submit-batch
+///
+/// Signed transactions checked one by one; those that pass enter the
+/// mempool together and the rest are reported without failing the batch.
+#[utoipa::path(
+    post,
+    path = "/submit-batch",
+    tag = "transactions",
+    request_body = BatchTransactionRequest,
+    responses(
+        (status = 200, description = "Per-transaction results", body = BatchTransactionResponse),
+        (status = 400, description = "Empty or oversized batch", body = ErrorResponse),
+        (status = 500, description = "Mempool unavailable", body = ErrorResponse),
+    ),
+)]
+#[tracing::instrument(name = "submit_batch", skip_all, fields(endpoint = "/submit-batch", size = payload.transactions.len()))]
+async fn submit_batch(
+    AxumState(state): AxumState<ApiState>,
+    Json(payload): Json<BatchTransactionRequest>,
+) -> ApiResult<BatchTransactionResponse> {
+    if payload.transactions.is_empty() || payload.transactions.len() > MAX_BATCH_SIZE {
+        return Err(NodeError::InvalidRequest(format!(
+            "A batch holds between 1 and {} transactions",
+            MAX_BATCH_SIZE
+        )));
     }
+
+    // Undecodable transactions keep their slot so results line up with the request
+    let decoded: Vec<_> = payload.transactions.iter().map(|raw| decode_raw_transaction(&state, raw)).collect();
+    let txs = decoded.iter().filter_map(|tx| tx.as_ref().ok().cloned()).collect();
+    let mut added = state.mempool.add_transactions(txs)?.into_iter();
+
+    let results: Vec<BatchItemResult> = decoded
+        .into_iter()
+        .map(|tx| {
+            let outcome = tx.and_then(|_| added.next().expect("One result per decoded transaction").map_err(NodeError::from));
+            match outcome {
+                Ok(tx_hash) => {
+                    tracing::info!(tx_hash = %tx_hash, "transaction submitted");
+                    state.metrics.transactions_submitted.inc();
+                    BatchItemResult { hash: Some(tx_hash), code: None, error: None }
+                }
+                Err(e) => {
+                    let e = rejected(&state, e);
+                    BatchItemResult { hash: None, code: Some(e.code().to_string()), error: Some(e.to_string()) }
+                }
+            }
+        })
+        .collect();
+    let accepted = results.iter().filter(|result| result.hash.is_some()).count();
+    Ok(Json(BatchTransactionResponse {
+        accepted,
+        rejected: results.len() - accepted,
+        results,
+    }))
+}
+
+/// Count a rejected submission and pass its error on
+fn rejected(state: &ApiState, error: impl Into<NodeError>) -> NodeError {
+    let error = error.into();
+    tracing::warn!(error = %error, code = error.code(), "transaction rejected");
+    state.metrics.transactions_failed.inc();
+    error
 }
 
+/// Whether the canonical block at `number` is finalized, and its
+/// confirmations: the blocks from it up to the head, counting itself
+fn finality(state: &ApiState, number: u64) -> (bool, u64) {
+    let finalized = state.indexer.get_finalized_block_number().unwrap_or(None);
+    (finalized.is_some_and(|finalized| number <= finalized), state.chain.len().saturating_sub(number))
+}
+
+/// Summary of the block at `number`, stamped with the time it was indexed
+fn block_summary(state: &ApiState, number: u64, block: Block) -> serde_json::Value {
+    let timestamp = state.indexer.get_block(&block.hash).ok().flatten().map(|entry| entry.timestamp);
+    let (finalized, confirmations) = finality(state, number);
+    serde_json::json!({
+        "hash": block.hash,
+        "number": number,
+        "timestamp": timestamp,
+        "finalized": finalized,
+        "confirmations": confirmations,
+        "transactions": block.transactions.len(),
+        "previous_hash": block.previous_hash,
+        "nonce": block.nonce,
+        "ommers": block.ommers.iter().map(|ommer| &ommer.hash).collect::<Vec<_>>()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/block/{hash}",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Block hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Block not found", body = ErrorResponse),
+    ),
+)]
 async fn get_block(
     Path(block_hash): Path<String>,
     AxumState(state): AxumState<ApiState>,
-) -> Json<serde_json::Value> {
-    match state.indexer.get_block(&block_hash) {
-        Ok(Some(block_entry)) => {
-            let tx_count = block_entry.block.transactions.len();
-            Json(serde_json::json!({
-                "hash": block_entry.block.hash,
-                "number": block_entry.block_number,
-                "timestamp": block_entry.timestamp,
-                "transactions": tx_count,
-                "previous_hash": block_entry.block.previous_hash,
-                "nonce": block_entry.block.nonce
-            }))
-        }
-        Ok(None) => {
-            Json(serde_json::json!({
-                "error": "Block not found"
-            }))
-        }
-        Err(e) => {
-            Json(serde_json::json!({
-                "error": format!("Failed to query block: {}", e)
-            }))
-        }
+) -> ApiResult {
+    match (state.chain.height_of(&block_hash), state.chain.get_block_by_hash(&block_hash)) {
+        (Some(number), Ok(Some(block))) => Ok(Json(block_summary(&state, number, block))),
+        (_, Ok(_)) => Err(NodeError::NotFound("Block not found".to_string())),
+        (_, Err(e)) => Err(NodeError::Internal(format!("Failed to query block: {}", e))),
+    }
+}
+
+/// GET /chain/block/:height
+#[utoipa::path(
+    get,
+    path = "/chain/block/{height}",
+    tag = "blocks",
+    params(("height" = u64, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Block not found", body = ErrorResponse),
+    ),
+)]
+async fn get_block_by_height(
+    Path(height): Path<u64>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.chain.get_block_by_height(height) {
+        Ok(Some(block)) => Ok(Json(block_summary(&state, height, block))),
+        Ok(None) => Err(NodeError::NotFound("Block not found".to_string())),
+        Err(e) => Err(NodeError::Internal(format!("Failed to query block: {}", e))),
+    }
+}
+
+/// GET /ommer/:hash
+///
+/// A stale PoW block included as an ommer, and the block including it.
+#[utoipa::path(
+    get,
+    path = "/ommer/{hash}",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Ommer hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Ommer not found", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_ommer(
+    Path(ommer_hash): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.indexer.get_ommer(&ommer_hash)? {
+        Some(entry) => Ok(Json(serde_json::json!(entry))),
+        None => Err(NodeError::NotFound("Ommer not found".to_string())),
+    }
+}
+
+/// GET /block/:hash/status
+#[utoipa::path(
+    get,
+    path = "/block/{hash}/status",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Block hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Block not found", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_block_status(
+    Path(block_hash): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.indexer.get_block_status(&block_hash)? {
+        Some(status) => Ok(Json(serde_json::json!({
+            "hash": block_hash,
+            "status": status,
+        }))),
+        None => Err(NodeError::NotFound("Block not found".to_string())),
     }
 }
 
+/// GET /block/:hash/state-diff
+#[utoipa::path(
+    get,
+    path = "/block/{hash}/state-diff",
+    tag = "blocks",
+    params(("hash" = String, Path, description = "Block hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_block_state_diff(
+    Path(block_hash): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let Some(diff) = state_diff::stored(&state.db, &block_hash) else {
+        return Json(serde_json::json!({ "error": "State diff not found" }));
+    };
+    let accounts: Vec<serde_json::Value> = diff
+        .accounts
+        .iter()
+        .map(|account| {
+            serde_json::json!({
+                "address": account.address,
+                "previous_balance": account.previous_balance,
+                "balance": account.balance,
+                "previous_nonce": account.nonce.map(|(previous, _)| previous),
+                "nonce": account.nonce.map(|(_, nonce)| nonce),
+            })
+        })
+        .collect();
+    // Keys are shown as text when they are UTF-8, values always as hex
+    let storage: Vec<serde_json::Value> = diff
+        .storage
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "key": String::from_utf8(entry.key.clone()).unwrap_or_else(|_| hex::encode(&entry.key)),
+                "previous": entry.previous.as_ref().map(hex::encode),
+                "value": entry.value.as_ref().map(hex::encode),
+            })
+        })
+        .collect();
+    Json(serde_json::json!({
+        "block_hash": diff.block_hash,
+        "accounts": accounts,
+        "storage": storage,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/tx/{hash}",
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Transaction hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Transaction not found", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
 async fn get_transaction(
     Path(tx_hash): Path<String>,
     AxumState(state): AxumState<ApiState>,
-) -> Json<serde_json::Value> {
-    match state.indexer.get_transaction(&tx_hash) {
-        Ok(Some(tx_entry)) => {
-            let tx = &tx_entry.transaction;
-            Json(serde_json::json!({
-                "hash": tx_hash,
-                "from": tx.from,
-                "block_hash": tx_entry.block_hash,
-                "block_number": tx_entry.block_number,
-                "tx_index": tx_entry.tx_index,
-                "gas_price": tx.gas_price,
-                "nonce": tx.nonce
-            }))
-        }
-        Ok(None) => {
-            Json(serde_json::json!({
-                "error": "Transaction not found"
-            }))
-        }
-        Err(e) => {
-            Json(serde_json::json!({
-                "error": format!("Failed to query transaction: {}", e)
-            }))
-        }
+) -> ApiResult {
+    if let Some(tx_entry) = state.indexer.get_transaction(&tx_hash)? {
+        let tx = &tx_entry.transaction;
+        return Ok(Json(serde_json::json!({
+            "hash": tx_hash,
+            "from": tx.from,
+            "block_hash": tx_entry.block_hash,
+            "block_number": tx_entry.block_number,
+            "tx_index": tx_entry.tx_index,
+            "gas_price": tx.gas_price,
+            "nonce": tx.nonce
+        })));
+    }
+    // Not in a block; the mempool may still hold it or know why it left
+    if state.mempool.contains(&tx_hash)? {
+        return Ok(Json(serde_json::json!({
+            "hash": tx_hash,
+            "status": "pending"
+        })));
+    }
+    match state.mempool.eviction_reason(&tx_hash)? {
+        Some(reason) => Ok(Json(serde_json::json!({
+            "hash": tx_hash,
+            "status": "evicted",
+            "eviction_reason": reason.as_str()
+        }))),
+        None => Err(NodeError::NotFound("Transaction not found".to_string())),
     }
 }
 
+/// GET /tx/:hash/receipt
+#[utoipa::path(
+    get,
+    path = "/tx/{hash}/receipt",
+    tag = "transactions",
+    params(("hash" = String, Path, description = "Transaction hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Receipt not found", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_transaction_receipt(
+    Path(tx_hash): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let receipt = match state.indexer.get_receipt(&tx_hash)? {
+        Some(receipt) => receipt,
+        None => state_processor::stored_receipt(&state.db, &tx_hash)
+            .ok_or_else(|| NodeError::NotFound("Receipt not found".to_string()))?,
+    };
+    let location = state.indexer.get_transaction(&tx_hash).ok().flatten();
+    Ok(Json(serde_json::json!({
+        "hash": receipt.tx_hash,
+        "status": if receipt.success { "success" } else { "reverted" },
+        "error": receipt.error,
+        "gas_used": receipt.gas_used,
+        "fee_burned": receipt.fee_burned,
+        "tip": receipt.tip,
+        "logs": receipt.logs,
+        "post_state_root": hex::encode(&receipt.post_state_root),
+        "block_hash": location.as_ref().map(|entry| entry.block_hash.clone()),
+        "block_number": location.as_ref().map(|entry| entry.block_number),
+        "tx_index": location.as_ref().map(|entry| entry.tx_index),
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/chain/head",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "OK", body = ChainInfoResponse),
+    ),
+)]
 async fn get_chain_head(
     AxumState(state): AxumState<ApiState>,
 ) -> Json<ChainInfoResponse> {
-    let best_block_number = state.indexer.get_latest_block_number()
-        .unwrap_or(None)
-        .unwrap_or(0);
-    let best_block_hash = state.indexer.get_latest_block_hash()
-        .unwrap_or(None)
+    let best_block_number = state.chain.len().saturating_sub(1);
+    let best_block_hash = state.chain.head()
+        .map(|head| head.hash)
         .unwrap_or_else(|| "0x0000000000000000000000000000000000000000000000000000000000000000".to_string());
 
+    let finalized_block_number = state.indexer.get_finalized_block_number().unwrap_or(None);
+    let (finalized, confirmations) = finality(&state, best_block_number);
+
+    let chain_id = replay_protection::chain_id(&*state.trie.read().unwrap());
+
     Json(ChainInfoResponse {
         chain_name: "Aureon".to_string(),
+        chain_id,
         best_block_number,
         best_block_hash,
+        finalized_block_number,
+        finalized,
+        confirmations,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/chain/finalized",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "OK", body = FinalityResponse),
+    ),
+)]
+async fn get_finalized(
+    AxumState(state): AxumState<ApiState>,
+) -> Json<FinalityResponse> {
+    Json(FinalityResponse {
+        finalized_block_number: state.indexer.get_finalized_block_number().unwrap_or(None),
+        finalized_block_hash: state.indexer.get_finalized_block_hash().unwrap_or(None),
+    })
+}
+
+/// GET /chain/finalized-head
+///
+/// The highest finalized block, which no reorg can remove.
+#[utoipa::path(
+    get,
+    path = "/chain/finalized-head",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "No block finalized yet", body = ErrorResponse),
+    ),
+)]
+async fn get_finalized_head(
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let Some(number) = state.indexer.get_finalized_block_number()? else {
+        return Err(NodeError::NotFound("No block finalized yet".to_string()));
+    };
+    match state.chain.get_block_by_height(number) {
+        Ok(Some(block)) => Ok(Json(block_summary(&state, number, block))),
+        Ok(None) => Err(NodeError::NotFound("Block not found".to_string())),
+        Err(e) => Err(NodeError::Internal(format!("Failed to query block: {}", e))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/slashing",
+    tag = "staking",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_slashing_events(
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    slashing_events_response(&state, None)
+}
+
+#[utoipa::path(
+    get,
+    path = "/slashing/{validator}",
+    tag = "staking",
+    params(("validator" = String, Path, description = "Validator address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_validator_slashing_events(
+    AxumState(state): AxumState<ApiState>,
+    Path(validator): Path<String>,
+) -> ApiResult {
+    slashing_events_response(&state, Some(&validator))
+}
+
+fn slashing_events_response(state: &ApiState, validator: Option<&str>) -> ApiResult {
+    let events = state.indexer.get_slashing_events(validator)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "count": events.len(),
+        "events": events,
+    })))
+}
+
+/// GET /blocks?page=..&page_size=..
+#[utoipa::path(
+    get,
+    path = "/blocks",
+    tag = "explorer",
+    params(PageParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_blocks(
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<PageParams>,
+) -> ApiResult {
+    let page = state.indexer.get_blocks_page(params.page(), params.page_size())?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "page": page.page,
+        "page_size": page.page_size,
+        "total": page.total,
+        "blocks": page.items.iter().map(|entry| serde_json::json!({
+            "hash": entry.block.hash,
+            "number": entry.block_number,
+            "timestamp": entry.timestamp,
+            "transactions": entry.block.transactions.len(),
+            "previous_hash": entry.block.previous_hash,
+        })).collect::<Vec<_>>(),
+    })))
+}
+
+/// GET /account/:address/transactions?page=..&page_size=..
+#[utoipa::path(
+    get,
+    path = "/account/{address}/transactions",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        PageParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_account_transactions(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<PageParams>,
+) -> ApiResult {
+    let page = state.indexer.get_account_transactions(&address, params.page(), params.page_size())?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "address": address,
+        "page": page.page,
+        "page_size": page.page_size,
+        "total": page.total,
+        "transactions": page.items.iter().map(transaction_json).collect::<Vec<_>>(),
+    })))
+}
+
+/// GET /account/:address/history?from_block=..&to_block=..&limit=..
+///
+/// Transactions involving the address in a block range, newest first,
+/// including those during which it emitted logs. Blocks whose bloom filter
+/// rules the address out are skipped.
+#[utoipa::path(
+    get,
+    path = "/account/{address}/history",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Account or contract address"),
+        AddressHistoryParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_address_history(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<AddressHistoryParams>,
+) -> ApiResult {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let from = params.from_block.unwrap_or(0);
+    let to = params.to_block.unwrap_or(u64::MAX);
+    let history = state.indexer.get_address_history(&address, from, to, limit)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "address": address,
+        "transactions": history.transactions.iter().map(transaction_json).collect::<Vec<_>>(),
+        "blocks_scanned": history.blocks_scanned,
+        "blocks_skipped": history.blocks_skipped,
+        "next_block": history.next_block,
+    })))
+}
+
+/// GET /account/:address/sent?cursor=..&limit=..
+#[utoipa::path(
+    get,
+    path = "/account/{address}/sent",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        CursorParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_sent_transactions(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<CursorParams>,
+) -> ApiResult {
+    let page = state.indexer.get_sent_transactions(&address, params.cursor.as_deref(), params.limit());
+    transactions_page_json(&address, page)
+}
+
+/// GET /account/:address/received?cursor=..&limit=..
+#[utoipa::path(
+    get,
+    path = "/account/{address}/received",
+    tag = "explorer",
+    params(
+        ("address" = String, Path, description = "Account address"),
+        CursorParams,
+    ),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_received_transactions(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<CursorParams>,
+) -> ApiResult {
+    let page = state.indexer.get_received_transactions(&address, params.cursor.as_deref(), params.limit());
+    transactions_page_json(&address, page)
+}
+
+/// GET /blocks/cursor?cursor=..&limit=..
+#[utoipa::path(
+    get,
+    path = "/blocks/cursor",
+    tag = "explorer",
+    params(CursorParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_blocks_by_cursor(
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<CursorParams>,
+) -> ApiResult {
+    let page = state.indexer.get_blocks_before(params.cursor.as_deref(), params.limit())?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "next_cursor": page.next_cursor,
+        "blocks": page.items.iter().map(|entry| serde_json::json!({
+            "hash": entry.block.hash,
+            "number": entry.block_number,
+            "timestamp": entry.timestamp,
+            "transactions": entry.block.transactions.len(),
+            "previous_hash": entry.block.previous_hash,
+        })).collect::<Vec<_>>(),
+    })))
+}
+
+fn transactions_page_json(
+    address: &str,
+    page: Result<CursorPage<TransactionIndexEntry>, IndexerError>,
+) -> ApiResult {
+    let page = page?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "address": address,
+        "next_cursor": page.next_cursor,
+        "transactions": page.items.iter().map(transaction_json).collect::<Vec<_>>(),
+    })))
+}
+
+fn transaction_json(entry: &TransactionIndexEntry) -> serde_json::Value {
+    serde_json::json!({
+        "hash": entry.transaction.hash(),
+        "from": entry.transaction.from,
+        "to": entry.transaction.recipient(),
+        "payload": entry.transaction.payload,
+        "block_hash": entry.block_hash,
+        "block_number": entry.block_number,
+        "tx_index": entry.tx_index,
+        "nonce": entry.transaction.nonce,
     })
 }
 
+/// GET /accounts/top?limit=..
+#[utoipa::path(
+    get,
+    path = "/accounts/top",
+    tag = "explorer",
+    params(TopAccountsParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_top_accounts(
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<TopAccountsParams>,
+) -> ApiResult {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let accounts = state.indexer.get_top_accounts(limit)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "accounts": accounts.into_iter().map(|(address, balance)| BalanceResponse { address, balance, block: None }).collect::<Vec<_>>(),
+    })))
+}
+
+/// GET /delegations/:delegator
+#[utoipa::path(
+    get,
+    path = "/delegations/{delegator}",
+    tag = "staking",
+    params(("delegator" = String, Path, description = "Delegator address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_delegations(
+    Path(delegator): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let staking = state.staking.lock().unwrap();
+    Json(serde_json::json!({
+        "status": "ok",
+        "delegator": delegator,
+        "delegations": staking.delegations_of(&delegator),
+        "unbonding": staking.unbonding_of(&delegator),
+        "pending_rewards": staking.get_pending_rewards(&delegator),
+    }))
+}
+
+/// GET /validator/:validator/delegations
+#[utoipa::path(
+    get,
+    path = "/validator/{validator}/delegations",
+    tag = "staking",
+    params(("validator" = String, Path, description = "Validator address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_validator_delegations(
+    Path(validator): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let staking = state.staking.lock().unwrap();
+    Json(serde_json::json!({
+        "status": "ok",
+        "validator": validator,
+        "self_stake": staking.get_staked_amount(&validator),
+        "delegated": staking.get_delegated_amount(&validator),
+        "delegations": staking.delegations_to(&validator),
+    }))
+}
+
+/// GET /rewards/:account
+#[utoipa::path(
+    get,
+    path = "/rewards/{account}",
+    tag = "staking",
+    params(("account" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_pending_rewards(
+    Path(account): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let pending = state.staking.lock().unwrap().get_pending_rewards(&account);
+    Json(serde_json::json!({
+        "status": "ok",
+        "account": account,
+        "pending_rewards": pending,
+    }))
+}
+
+/// GET /economy/emission
+#[utoipa::path(
+    get,
+    path = "/economy/emission",
+    tag = "economy",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_emission(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let emission = state.emission.status(&*state.trie.read().unwrap());
+    Json(serde_json::json!({
+        "status": "ok",
+        "emission": emission,
+    }))
+}
+
+/// GET /economy/basefee
+///
+/// Base fee of the next block and the base fees burned so far.
+#[utoipa::path(
+    get,
+    path = "/economy/basefee",
+    tag = "economy",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_base_fee(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    match state.emission.base_fee {
+        Some(schedule) => Json(serde_json::json!({
+            "status": "ok",
+            "fee_burning": true,
+            "base_fee": schedule.status(&*state.trie.read().unwrap()),
+        })),
+        None => Json(serde_json::json!({
+            "status": "ok",
+            "fee_burning": false,
+        })),
+    }
+}
+
+/// GET /economy/rent/:address
+///
+/// Storage rent status of an account: the rent it pays per block, the
+/// arrears of an expired account, or the tombstone of a pruned one along
+/// with the resurrection witness this node kept when pruning it.
+#[utoipa::path(
+    get,
+    path = "/economy/rent/{address}",
+    tag = "economy",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_rent(Path(address): Path<String>, AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let Some(schedule) = state.emission.rent else {
+        return Json(serde_json::json!({
+            "status": "ok",
+            "storage_rent": false,
+        }));
+    };
+    let trie = state.trie.read().unwrap();
+    let rent = schedule.status(&*trie, &address);
+    let witness = matches!(rent, AccountRent::Pruned(_)).then(|| storage_rent::stored_witness(&state.db, &address));
+    Json(serde_json::json!({
+        "status": "ok",
+        "storage_rent": true,
+        "address": address,
+        "rent": rent,
+        "collected": schedule.collected(&*trie),
+        "witness": witness.flatten(),
+    }))
+}
+
+/// GET /nft/:collection/:id
+#[utoipa::path(
+    get,
+    path = "/nft/{collection}/{id}",
+    tag = "explorer",
+    params(
+        ("collection" = String, Path, description = "Collection contract address"),
+        ("id" = u64, Path, description = "Token id"),
+    ),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "NFT not found", body = ErrorResponse),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_nft(
+    Path((collection, id)): Path<(String, u64)>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.indexer.get_nft(&collection, id)? {
+        Some(token) => Ok(Json(serde_json::json!({ "status": "ok", "nft": token }))),
+        None => Err(NodeError::NotFound("NFT not found".to_string())),
+    }
+}
+
+/// GET /account/:address/nfts
+#[utoipa::path(
+    get,
+    path = "/account/{address}/nfts",
+    tag = "explorer",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_account_nfts(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let nfts = state.indexer.get_account_nfts(&address)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "address": address,
+        "count": nfts.len(),
+        "nfts": nfts,
+    })))
+}
+
+/// GET /account/:address/timelocks
+#[utoipa::path(
+    get,
+    path = "/account/{address}/timelocks",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_account_timelocks(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let trie = state.trie.read().unwrap();
+    let locks = timelock::account_locks(&*trie, &address);
+    Json(serde_json::json!({
+        "status": "ok",
+        "address": address,
+        "height": timelock::height(&*trie),
+        "count": locks.len(),
+        "timelocks": locks,
+    }))
+}
+
+/// GET /bridge/transfer/:id
+#[utoipa::path(
+    get,
+    path = "/bridge/transfer/{id}",
+    tag = "bridge",
+    params(("id" = String, Path, description = "Transfer id")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_bridge_transfer(
+    Path(id): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    match bridge::transfer(&*state.trie.read().unwrap(), &id) {
+        Some(transfer) => Json(serde_json::json!({
+            "status": "ok",
+            "transfer": transfer,
+        })),
+        None => Json(serde_json::json!({
+            "status": "error",
+            "message": format!("Bridge transfer {} not found", id),
+        })),
+    }
+}
+
+/// GET /bridge/pending
+#[utoipa::path(
+    get,
+    path = "/bridge/pending",
+    tag = "bridge",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_pending_bridge_transfers(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let trie = state.trie.read().unwrap();
+    let pending = bridge::pending(&*trie);
+    Json(serde_json::json!({
+        "status": "ok",
+        "threshold": bridge::relayers(&*trie).map(|relayers| relayers.threshold),
+        "count": pending.len(),
+        "transfers": pending,
+    }))
+}
+
+/// GET /bridge/client/:chain
+#[utoipa::path(
+    get,
+    path = "/bridge/client/{chain}",
+    tag = "bridge",
+    params(("chain" = String, Path, description = "Foreign chain id")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_bridge_light_client(
+    Path(chain): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    let trie = state.trie.read().unwrap();
+    match bridge::light_client(&*trie, &chain) {
+        Some(client) => Json(serde_json::json!({
+            "status": "ok",
+            "client": client,
+            "tip_header": bridge::header(&*trie, &chain, client.tip_height),
+        })),
+        None => Json(serde_json::json!({
+            "status": "error",
+            "message": format!("No light client for chain {}", chain),
+        })),
+    }
+}
+
+/// GET /metrics/history?range=..
+///
+/// Block time, throughput, peers and mempool depth over the range (one
+/// hour by default), downsampled to at most `MAX_POINTS` points.
+#[utoipa::path(
+    get,
+    path = "/metrics/history",
+    tag = "node",
+    params(HistoryParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_metrics_history(
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<HistoryParams>,
+) -> Json<serde_json::Value> {
+    let Some(history) = state.metrics_history.clone() else {
+        return Json(serde_json::json!({
+            "status": "error",
+            "message": "Metrics history is disabled",
+        }));
+    };
+    let range = params.range.unwrap_or_else(|| "1h".to_string());
+    let range_secs = match metrics_history::parse_range(&range) {
+        Ok(secs) => secs,
+        Err(e) => return Json(serde_json::json!({ "status": "error", "message": e })),
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let points = tokio::task::spawn_blocking(move || history.series(now, range_secs)).await.unwrap_or_default();
+    Json(serde_json::json!({
+        "status": "ok",
+        "range": range,
+        "points": points,
+    }))
+}
+
+/// GET /debug/block-timings
+///
+/// Time spent in each production stage for the recently produced blocks,
+/// with the mean, p50, p95 and max of every stage over them.
+#[utoipa::path(
+    get,
+    path = "/debug/block-timings",
+    tag = "node",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_block_timings(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "blocks": state.block_timings.recent(),
+        "summary": state.block_timings.summary(),
+    }))
+}
+
+/// POST /dev/mine?blocks=..
+///
+/// Seals the requested number of blocks (one by default) and answers once
+/// they are sealed; blocks are sealed even if the mempool is empty.
+#[utoipa::path(
+    post,
+    path = "/dev/mine",
+    tag = "dev",
+    params(MineParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn dev_mine(AxumState(state): AxumState<ApiState>, Query(params): Query<MineParams>) -> Json<serde_json::Value> {
+    let Some(trigger) = state.seal.clone() else {
+        return Json(serde_json::json!({
+            "status": "error",
+            "message": "Blocks are sealed in slots; /dev/mine needs seal_mode \"manual\" or \"instant\"",
+        }));
+    };
+    let blocks = params.blocks.unwrap_or(1);
+    if blocks == 0 || blocks > MAX_MINE_BLOCKS {
+        return Json(serde_json::json!({
+            "status": "error",
+            "message": format!("Can mine between 1 and {} blocks at once", MAX_MINE_BLOCKS),
+        }));
+    }
+    let ticket = trigger.request(blocks);
+    let sealed = tokio::task::spawn_blocking(move || trigger.wait_handled(ticket, std::time::Duration::from_secs(30)))
+        .await
+        .unwrap_or(false);
+    if !sealed {
+        return Json(serde_json::json!({
+            "status": "error",
+            "message": "Timed out waiting for the blocks to be sealed",
+        }));
+    }
+    Json(serde_json::json!({
+        "status": "ok",
+        "sealed": blocks,
+        "blocks_produced": state.metrics.blocks_produced.get(),
+    }))
+}
+
+/// GET /logs?address=..&topics=..&from_block=..&to_block=..
+#[utoipa::path(
+    get,
+    path = "/logs",
+    tag = "contracts",
+    params(LogsParams),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 503, description = "Index unavailable", body = ErrorResponse),
+    ),
+)]
+async fn get_logs(
+    AxumState(state): AxumState<ApiState>,
+    Query(params): Query<LogsParams>,
+) -> ApiResult {
+    let logs = state.indexer.get_logs(&params.into_filter())?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "count": logs.len(),
+        "logs": logs,
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/contract/deploy",
+    tag = "contracts",
+    request_body = ContractDeployRequest,
+    responses(
+        (status = 200, description = "Deployed", body = ContractDeployResponse),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+        (status = 409, description = "Contract already deployed at the derived address", body = ErrorResponse),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
 async fn deploy_contract(
     AxumState(state): AxumState<ApiState>,
     Json(payload): Json<ContractDeployRequest>,
-) -> Json<ContractDeployResponse> {
+) -> ApiResult<ContractDeployResponse> {
     // Validate code is not empty
     if payload.code.is_empty() {
-        return Json(ContractDeployResponse {
-            address: String::new(),
-            status: "failed: empty code".to_string(),
-        });
+        return Err(NodeError::InvalidRequest("Contract code is empty".to_string()));
     }
 
     // Try to validate WASM code
-    match WasmRuntime::new(&payload.code) {
-        Ok(_) => {
-            // Deploy contract and store in registry
-            let mut registry = state.contract_registry.lock().unwrap();
-            let address = registry.deploy(payload.code.clone());
-
-            Json(ContractDeployResponse {
-                address,
-                status: "deployed".to_string(),
-            })
-        }
-        Err(e) => {
-            Json(ContractDeployResponse {
-                address: String::new(),
-                status: format!("failed: {}", e),
-            })
-        }
+    WasmRuntime::new(&payload.code).map_err(|e| NodeError::InvalidRequest(format!("Invalid contract code: {}", e)))?;
+    if let Some(Err(e)) = payload.abi.as_ref().map(ContractAbi::validate) {
+        return Err(RegistryError::InvalidAbi(e).into());
+    }
+
+    // Deploy contract and store in registry
+    let deployer = payload.deployer.as_deref().unwrap_or(API_DEPLOYER);
+    let mut registry = state.contract_registry.lock().unwrap();
+    let nonce = registry.next_nonce(deployer);
+    let authority = payload
+        .upgrade_authority
+        .clone()
+        .unwrap_or_else(|| UpgradeAuthority::Admin(deployer.to_string()));
+    let address = registry.deploy_with_authority(deployer, nonce, payload.code.clone(), authority)?;
+    if let Some(abi) = payload.abi.clone()
+        && let Err(e) = registry.register_abi(&address, deployer, abi)
+    {
+        return Ok(Json(ContractDeployResponse { address, status: format!("deployed without ABI: {}", e) }));
+    }
+    state.metrics.contracts_deployed.inc();
+    state.events.publish(NodeEvent::Contracts(ContractEvent {
+        event_type: "contract_deployed".to_string(),
+        contract_address: address.clone(),
+        success: true,
+        gas_used: 0,
+    }));
+
+    Ok(Json(ContractDeployResponse {
+        address,
+        status: "deployed".to_string(),
+    }))
+}
+
+/// GET /contract/:address/code
+#[utoipa::path(
+    get,
+    path = "/contract/{address}/code",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Contract not found", body = ErrorResponse),
+    ),
+)]
+async fn get_contract_code(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let registry = state.contract_registry.lock().unwrap();
+    match (registry.get_info(&address), registry.get_contract(&address)) {
+        (Some(info), Some(code)) => Ok(Json(serde_json::json!({
+            "address": info.address,
+            "code_hash": info.code_hash,
+            "deployer": info.deployer,
+            "nonce": info.nonce,
+            "code": hex::encode(code),
+        }))),
+        _ => Err(RegistryError::NotFound(address).into()),
+    }
+}
+
+/// GET /contract/:address/upgrades
+#[utoipa::path(
+    get,
+    path = "/contract/{address}/upgrades",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "Contract not found", body = ErrorResponse),
+    ),
+)]
+async fn get_contract_upgrades(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.contract_registry.lock().unwrap().get_info(&address) {
+        Some(info) => Ok(Json(serde_json::json!({
+            "address": info.address,
+            "code_hash": info.code_hash,
+            "upgrade_authority": info.upgrade_authority,
+            "count": info.upgrades.len(),
+            "upgrades": info.upgrades,
+        }))),
+        None => Err(RegistryError::NotFound(address).into()),
+    }
+}
+
+/// GET /contract/:address/abi
+#[utoipa::path(
+    get,
+    path = "/contract/{address}/abi",
+    tag = "contracts",
+    params(("address" = String, Path, description = "Contract address")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 404, description = "No ABI registered", body = ErrorResponse),
+    ),
+)]
+async fn get_contract_abi(
+    Path(address): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    match state.contract_registry.lock().unwrap().get_abi(&address) {
+        Some(abi) => Ok(Json(serde_json::json!({ "address": address, "abi": abi }))),
+        None => Err(NodeError::NotFound("No ABI registered for contract".to_string())),
     }
 }
 
+/// GET /contracts
+#[utoipa::path(
+    get,
+    path = "/contracts",
+    tag = "contracts",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn get_contracts(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let contracts = state.contract_registry.lock().unwrap().list();
+    Json(serde_json::json!({
+        "status": "ok",
+        "count": contracts.len(),
+        "contracts": contracts,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/contract/call",
+    tag = "contracts",
+    request_body = ContractCallRequest,
+    responses(
+        (status = 200, description = "OK", body = ContractCallResponse),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
 async fn call_contract(
     AxumState(state): AxumState<ApiState>,
     Json(payload): Json<ContractCallRequest>,
 ) -> Json<ContractCallResponse> {
+    let failed = |output: String| {
+        Json(ContractCallResponse { success: false, output, gas_used: 0, logs: vec![], outputs: None })
+    };
     // Verify contract exists
     let registry = state.contract_registry.lock().unwrap();
     let code = match registry.get_contract(&payload.contract_address) {
         Some(code) => code,
-        None => {
-            return Json(ContractCallResponse {
-                success: false,
-                output: "Contract not found".to_string(),
-                gas_used: 0,
-            });
-        }
+        None => return failed("Contract not found".to_string()),
+    };
+    let (input, function) = match call_input(&registry, &payload) {
+        Ok(call) => call,
+        Err(e) => return failed(e),
     };
     drop(registry); // Release lock before executing
 
     // Execute contract
+    let started = std::time::Instant::now();
     match WasmRuntime::new(&code) {
         Ok(runtime) => {
-            match runtime.execute_contract_with_context(payload.gas_limit, Default::default()) {
+            match read_only_runtime(&state, runtime, &payload.contract_address)
+                .with_input(input)
+                .execute_contract_with_context(payload.gas_limit, Default::default())
+            {
                 Ok(result) => {
-                    Json(ContractCallResponse {
+                    state.metrics.record_contract_call(&payload.contract_address, started.elapsed(), result.gas_used);
+                    state.events.publish(NodeEvent::Contracts(ContractEvent {
+                        event_type: "contract_call".to_string(),
+                        contract_address: payload.contract_address.clone(),
                         success: result.success,
-                        output: result.output,
                         gas_used: result.gas_used,
-                    })
-                }
-                Err(e) => {
+                    }));
+                    let (output, outputs) = match function.filter(|_| result.success) {
+                        Some(function) => match function.decode_output(&result.return_data) {
+                            Ok(outputs) => (result.output, Some(outputs)),
+                            Err(e) => (e, None),
+                        },
+                        None => (result.output, None),
+                    };
                     Json(ContractCallResponse {
-                        success: false,
-                        output: format!("Execution error: {}", e),
-                        gas_used: 0,
+                        success: result.success,
+                        output,
+                        gas_used: result.gas_used,
+                        logs: result.logs,
+                        outputs,
                     })
                 }
+                Err(e) => failed(format!("Execution error: {}", e)),
             }
         }
-        Err(e) => {
-            Json(ContractCallResponse {
-                success: false,
-                output: format!("Failed to load contract: {}", e),
-                gas_used: 0,
-            })
+        Err(e) => failed(format!("Failed to load contract: {}", e)),
+    }
+}
+
+/// Call input of a contract call request, and the ABI function it calls
+///
+/// Contracts without a registered ABI are called with empty input.
+fn call_input(registry: &ContractRegistry, payload: &ContractCallRequest) -> Result<(Vec<u8>, Option<AbiFunction>), String> {
+    let Some(abi) = registry.get_abi(&payload.contract_address) else {
+        return Ok((Vec::new(), None));
+    };
+    let function = abi.function(&payload.function)?;
+    Ok((function.encode_call(&payload.args)?, Some(function.clone())))
+}
+
+/// POST /contract/estimate-gas
+///
+/// Gas a contract call needs against current state, searched up to the
+/// request's gas limit, so clients can set their limits before submitting.
+#[utoipa::path(
+    post,
+    path = "/contract/estimate-gas",
+    tag = "contracts",
+    request_body = ContractCallRequest,
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn estimate_contract_gas(
+    AxumState(state): AxumState<ApiState>,
+    Json(payload): Json<ContractCallRequest>,
+) -> Json<serde_json::Value> {
+    let registry = state.contract_registry.lock().unwrap();
+    let code = match registry.get_contract(&payload.contract_address) {
+        Some(code) => code,
+        None => return Json(serde_json::json!({ "status": "error", "message": "Contract not found" })),
+    };
+    let input = match call_input(&registry, &payload) {
+        Ok((input, _)) => input,
+        Err(e) => return Json(serde_json::json!({ "status": "error", "message": e })),
+    };
+    drop(registry);
+    let estimate = WasmRuntime::new(&code).and_then(|runtime| {
+        read_only_runtime(&state, runtime, &payload.contract_address)
+            .with_input(input)
+            .estimate_gas(payload.gas_limit)
+    });
+    match estimate {
+        Ok(Some(gas)) => Json(serde_json::json!({
+            "status": "ok",
+            "contract_address": payload.contract_address,
+            "gas_estimate": gas,
+        })),
+        Ok(None) => Json(serde_json::json!({
+            "status": "error",
+            "message": format!("Contract call fails with gas limit {}", payload.gas_limit),
+        })),
+        Err(e) => Json(serde_json::json!({
+            "status": "error",
+            "message": format!("Execution error: {}", e),
+        })),
+    }
+}
+
+/// Runtime of contract `address` reading token state from the database
+fn read_only_runtime(state: &ApiState, runtime: WasmRuntime, address: &str) -> WasmRuntime {
+    let db = state.db.clone();
+    let registry = state.contract_registry.clone();
+    runtime
+        .with_address(address)
+        .with_token_state(Arc::new(move |key: &[u8]| db.get(key)))
+        .with_contracts(Arc::new(move |address: &str| registry.lock().unwrap().get_contract(address)))
+}
+
+/// POST /simulate-tx
+///
+/// Dry-runs a transaction of any payload type against current state without
+/// committing it, so wallets can estimate fees before signing. The
+/// transaction does not need to be signed.
+#[utoipa::path(
+    post,
+    path = "/simulate-tx",
+    tag = "transactions",
+    request_body = RawTransactionRequest,
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
+async fn simulate_transaction(
+    AxumState(state): AxumState<ApiState>,
+    Json(payload): Json<RawTransactionRequest>,
+) -> Json<serde_json::Value> {
+    let tx = match hex::decode(&payload.tx)
+        .map_err(|_| "Invalid transaction format (must be hex)".to_string())
+        .and_then(|bytes| Transaction::from_canonical_bytes(&bytes))
+    {
+        Ok(tx) => tx,
+        Err(e) => return Json(serde_json::json!({ "error": e })),
+    };
+
+    let simulation = state_processor::simulate_transaction(
+        &state.db,
+        &state.trie.read().unwrap(),
+        Some(state.emission),
+        &tx,
+    );
+    let mut receipt = simulation.receipt;
+    if let (true, TransactionPayload::ContractCall { contract_address, gas_limit, .. }) = (receipt.success, &tx.payload) {
+        let code = state.contract_registry.lock().unwrap().get_contract(contract_address);
+        let result = code
+            .ok_or_else(|| "Contract not found".to_string())
+            .and_then(|code| WasmRuntime::new(&code).map_err(|e| format!("Failed to load contract: {}", e)))
+            .and_then(|runtime| {
+                read_only_runtime(&state, runtime, contract_address)
+                    .execute_contract_with_context(*gas_limit, Default::default())
+                    .map_err(|e| format!("Execution error: {}", e))
+            });
+        match result {
+            Ok(result) => {
+                receipt.success = result.success;
+                receipt.error = (!result.success).then_some(result.output);
+                receipt.gas_used = result.gas_used;
+                receipt.logs = result.logs;
+            }
+            Err(e) => {
+                receipt.success = false;
+                receipt.error = Some(e);
+            }
         }
     }
+
+    Json(serde_json::json!({
+        "hash": receipt.tx_hash,
+        "status": if receipt.success { "success" } else { "reverted" },
+        "error": receipt.error,
+        "gas_used": receipt.gas_used,
+        "fee_burned": receipt.fee_burned,
+        "tip": receipt.tip,
+        "logs": receipt.logs,
+        "post_state_root": hex::encode(&receipt.post_state_root),
+        "balance_changes": simulation.balance_changes,
+    }))
 }
 
 // ============================================================================
 // WebSocket Handler (Phase 5.2)
 // ============================================================================
 
+/// GET /subscribe
+/// Upgrades to a WebSocket streaming events for the requested topics.
+/// Plain HTTP requests get a description of the available topics.
+#[utoipa::path(
+    get,
+    path = "/subscribe",
+    tag = "subscriptions",
+    params(SubscribeParams),
+    responses(
+        (status = 101, description = "Upgraded to a WebSocket streaming subscribed events"),
+        (status = 200, description = "Event bus status when not upgrading", body = serde_json::Value),
+    ),
+)]
 async fn subscribe(
+    ws: Option<WebSocketUpgrade>,
+    Query(params): Query<SubscribeParams>,
     AxumState(state): AxumState<ApiState>,
-) -> Json<serde_json::Value> {
-    // Phase 5.2: Placeholder for WebSocket subscription
-    // In production, this would upgrade to WebSocket and stream events
-    // For now, return available subscription topics
-    
+) -> Response {
+    let topics: Vec<Topic> = params
+        .topics
+        .as_deref()
+        .map(|list| list.split(',').filter_map(Topic::parse).collect())
+        .unwrap_or_default();
+
+    if let Some(ws) = ws {
+        let events = state.events.clone();
+        return ws.on_upgrade(move |socket| handle_subscription(socket, events, Subscription::new(topics)));
+    }
+
     let block_count = state.indexer.get_block_count().unwrap_or(0);
     let tx_count = state.indexer.get_transaction_count().unwrap_or(0);
-    
+
     Json(serde_json::json!({
-        "status": "WebSocket subscriptions enabled (Phase 5.2)",
-        "available_topics": [
-            "blocks",
-            "transactions",
-            "contracts"
-        ],
+        "status": "WebSocket subscriptions enabled",
+        "available_topics": Topic::ALL.iter().map(|t| t.as_str()).collect::<Vec<_>>(),
+        "subscribers": state.events.subscriber_count(),
         "current_state": {
             "blocks": block_count,
             "transactions": tx_count
         },
-        "info": "Connect to ws:// endpoint for real-time events (Phase 5.3)"
+        "info": "Connect with a WebSocket client to /subscribe?topics=blocks,transactions, or send {\"action\":\"subscribe\",\"topics\":[...]}"
     }))
+    .into_response()
+}
+
+/// Drive a single WebSocket subscription until either side closes
+async fn handle_subscription(mut socket: WebSocket, events: EventBus, mut subscription: Subscription) {
+    let mut receiver = events.subscribe();
+
+    if send_json(&mut socket, &subscribed_message(&subscription)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        let reply = match serde_json::from_str::<SubscriptionCommand>(&text) {
+                            Ok(command) => {
+                                subscription.apply(command);
+                                subscribed_message(&subscription)
+                            }
+                            Err(e) => serde_json::json!({
+                                "type": "error",
+                                "message": format!("Invalid subscription command: {}", e),
+                            }),
+                        };
+                        if send_json(&mut socket, &reply).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => break,
+                    // Ping/pong is answered by axum; binary frames are ignored
+                    Some(Ok(_)) => {}
+                }
+            }
+            event = receiver.recv() => {
+                match event {
+                    Ok(event) => {
+                        if !subscription.matches(&event) {
+                            continue;
+                        }
+                        let payload = serde_json::json!({ "type": "event", "event": event });
+                        if send_json(&mut socket, &payload).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        // Client can't keep up; the oldest events were dropped
+                        let notice = serde_json::json!({ "type": "lagged", "skipped": skipped });
+                        if send_json(&mut socket, &notice).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
 }
 
+fn subscribed_message(subscription: &Subscription) -> serde_json::Value {
+    serde_json::json!({
+        "type": "subscribed",
+        "topics": subscription.topics(),
+    })
+}
+
+async fn send_json(socket: &mut WebSocket, value: &serde_json::Value) -> Result<(), axum::Error> {
+    socket.send(WsMessage::Text(value.to_string())).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/mempool",
+    tag = "node",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+    ),
+)]
 async fn get_mempool(
     AxumState(state): AxumState<ApiState>,
-) -> Json<serde_json::Value> {
+) -> ApiResult {
     // Return mempool statistics and pending transactions
-    match state.mempool.stats() {
-        Ok(stats) => {
-            Json(serde_json::json!({
-                "status": "ok",
-                "pending_transactions": stats.transaction_count,
-                "total_gas": stats.total_pending_gas,
-                "utilization_percent": stats.utilization_percent,
-                "max_capacity": stats.max_capacity,
-            }))
-        }
-        Err(e) => {
-            Json(serde_json::json!({
-                "status": "error",
-                "message": format!("Failed to get mempool stats: {}", e)
-            }))
-        }
+    let stats = state.mempool.stats()?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "pending_transactions": stats.transaction_count,
+        "queued_transactions": stats.queued_count,
+        "total_gas": stats.total_pending_gas,
+        "utilization_percent": stats.utilization_percent,
+        "max_capacity": stats.max_capacity,
+        "evictions": stats.evictions,
+        "min_gas_price": state.mempool.fee_market().min_gas_price,
+        "block_gas_limit": state.mempool.fee_market().block_gas_limit,
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reload-config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn reload_config(
+    AxumState(state): AxumState<ApiState>,
+) -> Json<serde_json::Value> {
+    // Re-read the configuration file and apply its reloadable values
+    match state.config_reloader.reload() {
+        Ok(applied) => Json(serde_json::json!({
+            "status": "ok",
+            "applied": applied,
+        })),
+        Err(e) => Json(serde_json::json!({
+            "status": "error",
+            "message": format!("Failed to reload configuration: {}", e)
+        })),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddPeerRequest {
+    /// host:port to dial
+    pub address: String,
+    /// Hex peer id the remote must prove during the handshake
+    pub peer_id: Option<String>,
+}
+
+/// GET /admin/peers
+#[utoipa::path(
+    get,
+    path = "/admin/peers",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn list_peers(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let peers = state.network.peer_summaries();
+    Json(serde_json::json!({
+        "status": "ok",
+        "count": peers.len(),
+        "peers": peers,
+    }))
+}
+
+/// POST /admin/peers
+///
+/// Dials the peer in the background; it is listed once the handshake completes.
+#[utoipa::path(
+    post,
+    path = "/admin/peers",
+    tag = "admin",
+    request_body = AddPeerRequest,
+    responses(
+        (status = 200, description = "Dialing", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn add_peer(
+    AxumState(state): AxumState<ApiState>,
+    Json(req): Json<AddPeerRequest>,
+) -> ApiResult {
+    if let Some(peer_id) = &req.peer_id {
+        parse_peer_id(peer_id)?;
+    }
+    state.network.add_peer(&req.address, req.peer_id.clone());
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "dialing": req.address,
+    })))
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId, NetworkError> {
+    PeerId::from_hex(peer_id).map_err(|_| NetworkError::InvalidPeerId(peer_id.to_string()))
+}
+
+/// DELETE /admin/peers/:peer_id
+#[utoipa::path(
+    delete,
+    path = "/admin/peers/{peer_id}",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "Hex peer id")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn remove_peer(
+    Path(peer_id): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let peer = parse_peer_id(&peer_id)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "disconnected": state.network.disconnect_peer(&peer),
+    })))
+}
+
+/// POST /admin/peers/:peer_id/ban
+#[utoipa::path(
+    post,
+    path = "/admin/peers/{peer_id}/ban",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "Hex peer id")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn ban_peer(
+    Path(peer_id): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let peer = parse_peer_id(&peer_id)?;
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "was_connected": state.network.ban_peer(&peer),
+    })))
+}
+
+/// POST /admin/mempool/clear
+#[utoipa::path(
+    post,
+    path = "/admin/mempool/clear",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn clear_mempool(AxumState(state): AxumState<ApiState>) -> ApiResult {
+    let removed = state.mempool.clear()?;
+    Ok(Json(serde_json::json!({ "status": "ok", "removed": removed })))
+}
+
+/// POST /admin/mempool/remove/:hash
+#[utoipa::path(
+    post,
+    path = "/admin/mempool/remove/{hash}",
+    tag = "admin",
+    params(("hash" = String, Path, description = "Transaction hash")),
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn remove_mempool_transaction(
+    Path(hash): Path<String>,
+    AxumState(state): AxumState<ApiState>,
+) -> ApiResult {
+    let removed = state.mempool.remove_transaction(&hash)?;
+    Ok(Json(serde_json::json!({ "status": "ok", "removed": removed })))
+}
+
+/// GET /admin/config
+///
+/// The configuration in effect, with API tokens redacted.
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "OK", body = serde_json::Value),
+        (status = 401, description = "Missing or unknown API token"),
+        (status = 403, description = "Token lacks the required permission"),
+    ),
+    security(("bearer" = []), ("api_key" = [])),
+)]
+async fn get_effective_config(AxumState(state): AxumState<ApiState>) -> Json<serde_json::Value> {
+    let mut config = state.config_reloader.current();
+    for user in &mut config.api.users {
+        user.token = "<redacted>".to_string();
     }
+    Json(serde_json::json!({
+        "status": "ok",
+        "config": config,
+    }))
+}
+
+// ============================================================================
+// OpenAPI Document
+// ============================================================================
+
+/// OpenAPI description of the REST API, generated from the handlers and
+/// their request and response types; served at /openapi.json and browsable
+/// at /swagger-ui
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Aureon Node API", description = "REST API of an Aureon node"),
+    paths(
+        get_balance,
+        get_proof,
+        get_spv_checkpoint,
+        get_shards,
+        get_state_root,
+        get_nonce,
+        get_account,
+        submit_transaction,
+        submit_signed_transaction,
+        submit_raw_transaction,
+        submit_batch,
+        get_block,
+        get_block_by_height,
+        get_ommer,
+        get_block_status,
+        get_block_state_diff,
+        get_transaction,
+        get_transaction_receipt,
+        get_chain_head,
+        get_finalized,
+        get_finalized_head,
+        get_slashing_events,
+        get_validator_slashing_events,
+        get_blocks,
+        get_account_transactions,
+        get_address_history,
+        get_sent_transactions,
+        get_received_transactions,
+        get_blocks_by_cursor,
+        get_top_accounts,
+        get_delegations,
+        get_validator_delegations,
+        get_pending_rewards,
+        get_emission,
+        get_base_fee,
+        get_rent,
+        get_nft,
+        get_account_nfts,
+        get_account_timelocks,
+        get_bridge_transfer,
+        get_pending_bridge_transfers,
+        get_bridge_light_client,
+        get_metrics_history,
+        get_block_timings,
+        dev_mine,
+        get_logs,
+        deploy_contract,
+        get_contract_code,
+        get_contract_upgrades,
+        get_contract_abi,
+        get_contracts,
+        call_contract,
+        estimate_contract_gas,
+        simulate_transaction,
+        subscribe,
+        get_mempool,
+        reload_config,
+        list_peers,
+        add_peer,
+        remove_peer,
+        ban_peer,
+        clear_mempool,
+        remove_mempool_transaction,
+        get_effective_config,
+    ),
+    modifiers(&ApiTokens),
+)]
+pub struct ApiDoc;
+
+/// Token schemes accepted on protected endpoints, see `api_auth`
+struct ApiTokens;
+
+impl utoipa::Modify for ApiTokens {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{ApiKey, ApiKeyValue, Http, HttpAuthScheme, SecurityScheme};
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(api_auth::API_KEY_HEADER))),
+        );
+    }
+}
+
+/// GET /openapi.json
+async fn get_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI page browsing /openapi.json, with its assets from a CDN
+///
+/// The assets are pinned to one swagger-ui-dist release and carry Subresource
+/// Integrity hashes, so the browser refuses them if the CDN serves anything
+/// else. Bumping the version means recomputing both hashes
+/// (`openssl dgst -sha384 -binary <file> | openssl base64 -A`).
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Aureon Node API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css"
+        integrity="sha384-wxLW6kwyHktdDGr6Pv1zgm/VGJh99lfUbzSn6HNHBENZlCN7W602k9VkGdxuFvPn" crossorigin="anonymous">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js"
+          integrity="sha384-wmyclcVGX/WhUkdkATwhaK1X1JtiNrr2EoYJ+diV3vj4v6OC5yCeSu+yW13SYJep" crossorigin="anonymous"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// GET /swagger-ui
+async fn get_swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
 }
 
 // ============================================================================
 // API Server Setup
 // ============================================================================
 
-pub async fn start_api_server(
-    db: Arc<Db>,
-    contract_registry: Arc<Mutex<ContractRegistry>>,
-    indexer: Arc<BlockchainIndexer>,
-    mempool: Arc<TransactionMempool>,
-    metrics: Arc<Metrics>,
-) -> anyhow::Result<()> {
-    let state = ApiState {
-        db,
-        contract_registry,
-        indexer,
-        mempool,
-        metrics: metrics.clone(),
-    };
+/// Serve the REST API until `shutdown` is triggered, letting in-flight requests finish
+pub async fn start_api_server(state: ApiState, config: &ApiConfig, shutdown: Shutdown) -> anyhow::Result<()> {
+    let metrics = state.metrics.clone();
+    let access = state.access.clone();
+    let health = state.health.clone();
+    let limiter = Arc::new(ApiRateLimiter::from_config(config).with_metrics(metrics.clone()));
 
     let app = Router::new()
         // Balance queries
         .route("/balance/:address", get(get_balance))
+        .route("/nonce/:address", get(get_nonce))
+        .route("/state-root/:height", get(get_state_root))
+        .route("/proof/:address", get(get_proof))
         // Transaction submission
         .route("/submit-tx", post(submit_transaction))
         .route("/submit-signed-tx", post(submit_signed_transaction))
+        .route("/submit-raw-tx", post(submit_raw_transaction))
+        .route("/submit-batch", post(submit_batch))
+        .route("/simulate-tx", post(simulate_transaction))
         // Block queries
         .route("/block/:hash", get(get_block))
+        .route("/block/:hash/status", get(get_block_status))
+        .route("/block/:hash/state-diff", get(get_block_state_diff))
+        .route("/ommer/:hash", get(get_ommer))
         .route("/tx/:hash", get(get_transaction))
+        .route("/tx/:hash/receipt", get(get_transaction_receipt))
         .route("/chain/head", get(get_chain_head))
+        .route("/chain/block/:height", get(get_block_by_height))
+        .route("/chain/finalized", get(get_finalized))
+        .route("/chain/finalized-head", get(get_finalized_head))
+        // Explorer listings
+        .route("/blocks", get(get_blocks))
+        .route("/blocks/cursor", get(get_blocks_by_cursor))
+        .route("/account/:address", get(get_account))
+        .route("/account/:address/transactions", get(get_account_transactions))
+        .route("/account/:address/history", get(get_address_history))
+        .route("/account/:address/sent", get(get_sent_transactions))
+        .route("/account/:address/received", get(get_received_transactions))
+        .route("/accounts/top", get(get_top_accounts))
+        // NFT ownership
+        .route("/nft/:collection/:id", get(get_nft))
+        .route("/account/:address/nfts", get(get_account_nfts))
+        .route("/account/:address/timelocks", get(get_account_timelocks))
+        // Validator slashing
+        .route("/slashing", get(get_slashing_events))
+        .route("/slashing/:validator", get(get_validator_slashing_events))
+        // Delegation and staking rewards
+        .route("/delegations/:delegator", get(get_delegations))
+        .route("/validator/:validator/delegations", get(get_validator_delegations))
+        .route("/rewards/:account", get(get_pending_rewards))
+        // Block reward emission, the burned base fee and storage rent
+        .route("/economy/emission", get(get_emission))
+        .route("/economy/basefee", get(get_base_fee))
+        .route("/economy/rent/:address", get(get_rent))
+        // Cross-chain bridge transfers
+        .route("/bridge/transfer/:id", get(get_bridge_transfer))
+        .route("/bridge/pending", get(get_pending_bridge_transfers))
+        .route("/bridge/client/:chain", get(get_bridge_light_client))
+        // Light clients
+        .route("/spv/checkpoint", get(get_spv_checkpoint))
+        // Sharding
+        .route("/shards", get(get_shards))
+        // Contract logs
+        .route("/logs", get(get_logs))
         // Contract operations
         .route("/contract/deploy", post(deploy_contract))
         .route("/contract/call", post(call_contract))
-        // Event subscriptions (Phase 5.2)
+        .route("/contract/estimate-gas", post(estimate_contract_gas))
+        .route("/contract/:address/code", get(get_contract_code))
+        .route("/contract/:address/upgrades", get(get_contract_upgrades))
+        .route("/contract/:address/abi", get(get_contract_abi))
+        .route("/contracts", get(get_contracts))
+        // Event subscriptions (WebSocket)
         .route("/subscribe", get(subscribe))
         // Mempool (Phase 5.3)
         .route("/mempool", get(get_mempool))
+        .route("/metrics/history", get(get_metrics_history))
+        .route("/debug/block-timings", get(get_block_timings))
+        // Configuration hot reload
+        .route("/admin/reload-config", post(reload_config))
+        // Node administration
+        .route("/admin/peers", get(list_peers).post(add_peer))
+        .route("/admin/peers/:peer_id", delete(remove_peer))
+        .route("/admin/peers/:peer_id/ban", post(ban_peer))
+        .route("/admin/mempool/clear", post(clear_mempool))
+        .route("/admin/mempool/remove/:hash", post(remove_mempool_transaction))
+        .route("/admin/config", get(get_effective_config))
+        // Development chains sealing blocks on demand
+        .route("/dev/mine", post(dev_mine))
+        // Token checks for contract deployment and calls, admin and dev endpoints
+        .route_layer(middleware::from_fn_with_state(access, api_auth::authenticate))
+        // API description, public like the read endpoints
+        .route("/openapi.json", get(get_openapi))
+        .route("/swagger-ui", get(get_swagger_ui))
         .with_state(state)
-        .nest("/", monitoring_router(metrics));
+        .nest("/", monitoring_router(metrics.clone(), health))
+        // Per-client-IP throttling of every endpoint
+        .layer(middleware::from_fn_with_state(limiter, api_rate_limit::limit_requests));
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    println!("📡 Aureon API listening on http://0.0.0.0:8080 (access via http://127.0.0.1:8080 locally)");
-    println!("📊 Prometheus metrics: http://0.0.0.0:8080/metrics");
-    println!("💚 Health check: http://0.0.0.0:8080/health");
+    let addr = format!("{}:{}", config.host, config.port);
+    println!("📡 Aureon API listening on http://{}", addr);
+    println!("📊 Prometheus metrics: http://{}/metrics", addr);
+    println!("💚 Health check: http://{}/health", addr);
+    println!("📖 API docs: http://{}/swagger-ui", addr);
 
     let listener = TcpListener::bind(&addr).await?;
-    serve(listener, app).await?;
+    serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_documents_routes_and_errors() {
+        let doc = ApiDoc::openapi();
+        assert_eq!(doc.paths.paths.len(), 63);
+
+        let peers = &doc.paths.paths["/admin/peers"];
+        assert!(peers.get.is_some() && peers.post.is_some());
+        assert!(peers.post.as_ref().unwrap().security.is_some());
+        let balance = doc.paths.paths["/balance/{address}"].get.as_ref().unwrap();
+        assert!(balance.security.is_none());
+
+        let schemas = &doc.components.as_ref().unwrap().schemas;
+        for schema in ["ErrorResponse", "TransactionRequest", "ContractDeployRequest", "ContractAbi"] {
+            assert!(schemas.contains_key(schema), "{} missing", schema);
+        }
+        let json = doc.to_json().unwrap();
+        assert!(json.contains("\"api_key\"") && json.contains("x-api-key"));
+    }
+
+    #[test]
+    fn test_mutating_routes_require_their_permission() {
+        use crate::access_control::Permission;
+        use axum::http::Method;
+
+        // Every route that is not a GET, with the permission it needs
+        let expected = [
+            (Method::POST, "/submit-tx", None),
+            (Method::POST, "/submit-signed-tx", None),
+            (Method::POST, "/submit-raw-tx", None),
+            (Method::POST, "/submit-batch", None),
+            (Method::POST, "/simulate-tx", None),
+            (Method::POST, "/contract/estimate-gas", None),
+            (Method::POST, "/contract/deploy", Some(Permission::DeployContract)),
+            (Method::POST, "/contract/call", Some(Permission::QueryState)),
+            (Method::POST, "/dev/mine", Some(Permission::ManageNode)),
+            (Method::POST, "/admin/reload-config", Some(Permission::ModifyConfig)),
+            (Method::POST, "/admin/peers", Some(Permission::ManageNode)),
+            (Method::DELETE, "/admin/peers/{peer_id}", Some(Permission::ManageNode)),
+            (Method::POST, "/admin/peers/{peer_id}/ban", Some(Permission::ManageNode)),
+            (Method::POST, "/admin/mempool/clear", Some(Permission::ManageNode)),
+            (Method::POST, "/admin/mempool/remove/{hash}", Some(Permission::ManageNode)),
+        ];
+        for (method, path, permission) in &expected {
+            assert_eq!(api_auth::required_permission(method, path), *permission, "{} {}", method, path);
+        }
+
+        // New mutating routes must be added above, and documented routes
+        // declare a token exactly when they need one
+        let doc = ApiDoc::openapi();
+        let mut mutating = 0;
+        for (path, item) in &doc.paths.paths {
+            let operations = [
+                (Method::GET, &item.get),
+                (Method::POST, &item.post),
+                (Method::PUT, &item.put),
+                (Method::DELETE, &item.delete),
+                (Method::PATCH, &item.patch),
+            ];
+            for (method, operation) in operations {
+                let Some(operation) = operation else {
+                    continue;
+                };
+                let permission = api_auth::required_permission(&method, path);
+                assert_eq!(operation.security.is_some(), permission.is_some(), "{} {}", method, path);
+                if method != Method::GET {
+                    mutating += 1;
+                    assert!(
+                        expected.iter().any(|(m, p, _)| *m == method && p == path),
+                        "{} {} missing from the permission table",
+                        method,
+                        path
+                    );
+                }
+            }
+        }
+        assert_eq!(mutating, expected.len());
+    }
+}
@@ -0,0 +1,134 @@
+//! API Authentication
+//!
+//! Protected endpoints require a token, sent as `Authorization: Bearer
+//! <token>` or `X-API-Key: <token>`, that maps to a user of the access
+//! control manager whose role grants the endpoint's permission. Read and
+//! transaction submission endpoints stay public; contract calls, which run
+//! contract code and publish contract events, need a token. Every check on
+//! a protected endpoint lands in the access log.
+
+use crate::access_control::{AccessControlManager, AccessDenied, Permission, Role, User};
+use crate::config::ApiConfig;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::{Arc, Mutex};
+
+/// Header carrying an API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Build the access control manager for the users in `config`
+pub fn access_control(config: &ApiConfig) -> Result<AccessControlManager, String> {
+    let mut access = AccessControlManager::new();
+    if !config.access_log_path.is_empty() {
+        access = access.with_log_file(&config.access_log_path);
+    }
+    for user in &config.users {
+        let role = Role::from_name(&user.role).ok_or_else(|| format!("Invalid role {}", user.role))?;
+        access.add_user(User::new(user.id.clone(), role))?;
+        access.add_token(&user.token, &user.id)?;
+    }
+    Ok(access)
+}
+
+/// Permission a request needs, or None for public endpoints
+///
+/// Reloading the configuration is reserved to admins; the other admin
+/// endpoints, and sealing blocks on development chains, are open to
+/// operators as well.
+pub fn required_permission(method: &Method, path: &str) -> Option<Permission> {
+    match (method, path) {
+        (&Method::POST, "/contract/deploy") => Some(Permission::DeployContract),
+        (&Method::POST, "/contract/call") => Some(Permission::QueryState),
+        (_, "/admin/reload-config") => Some(Permission::ModifyConfig),
+        (_, path) if path.starts_with("/admin/") || path.starts_with("/dev/") => Some(Permission::ManageNode),
+        _ => None,
+    }
+}
+
+/// Token a request authenticates with
+pub fn request_token(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+}
+
+/// Middleware refusing requests to protected endpoints without a permitted token
+pub async fn authenticate(
+    State(access): State<Arc<Mutex<AccessControlManager>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let Some(permission) = required_permission(request.method(), &path) else {
+        return next.run(request).await;
+    };
+    let token = request_token(request.headers());
+    let result = access.lock().unwrap().authorize(token, permission, &path);
+    match result {
+        Ok(_) => next.run(request).await,
+        Err(AccessDenied::Unauthenticated) => {
+            let body = serde_json::json!({ "error": "Missing or unknown API token" });
+            (StatusCode::UNAUTHORIZED, Json(body)).into_response()
+        }
+        Err(AccessDenied::Forbidden) => {
+            let body = serde_json::json!({ "error": format!("Token lacks permission {:?}", permission) });
+            (StatusCode::FORBIDDEN, Json(body)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::{get, post}, Router};
+    use tower::Service;
+
+    #[tokio::test]
+    async fn test_admin_routes_need_permitted_token() {
+        let mut access = AccessControlManager::new();
+        access.add_user(User::new("root".to_string(), Role::Admin)).unwrap();
+        access.add_user(User::new("viewer".to_string(), Role::Guest)).unwrap();
+        access.add_user(User::new("ops".to_string(), Role::Operator)).unwrap();
+        access.add_token("root-token", "root").unwrap();
+        access.add_token("view-token", "viewer").unwrap();
+        access.add_token("ops-token", "ops").unwrap();
+        let access = Arc::new(Mutex::new(access));
+        let app = Router::new()
+            .route("/admin/reload-config", post(|| async { "reloaded" }))
+            .route("/admin/peers", get(|| async { "peers" }))
+            .route("/blocks", get(|| async { "blocks" }))
+            .route_layer(middleware::from_fn_with_state(access.clone(), authenticate));
+
+        let status = |method: &str, uri: &str, headers: &[(&str, &str)]| {
+            let mut request = Request::builder().method(method).uri(uri);
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            let mut app = app.clone();
+            async move { app.call(request.body(Body::empty()).unwrap()).await.unwrap().status() }
+        };
+        assert_eq!(status("GET", "/blocks", &[]).await, StatusCode::OK);
+        assert_eq!(status("POST", "/admin/reload-config", &[]).await, StatusCode::UNAUTHORIZED);
+        let guest = [("authorization", "Bearer view-token")];
+        assert_eq!(status("POST", "/admin/reload-config", &guest).await, StatusCode::FORBIDDEN);
+        let admin = [(API_KEY_HEADER, "root-token")];
+        assert_eq!(status("POST", "/admin/reload-config", &admin).await, StatusCode::OK);
+
+        // Operators manage peers and the mempool but cannot reload the configuration
+        let operator = [("authorization", "Bearer ops-token")];
+        assert_eq!(status("GET", "/admin/peers", &operator).await, StatusCode::OK);
+        assert_eq!(status("POST", "/admin/reload-config", &operator).await, StatusCode::FORBIDDEN);
+
+        let access = access.lock().unwrap();
+        assert_eq!((access.allowed_access_count(), access.denied_access_count()), (2, 3));
+    }
+}
@@ -0,0 +1,137 @@
+//! API Rate Limiting
+//!
+//! Every client IP gets a token bucket holding up to `rate_limit_burst`
+//! requests and refilling at `rate_limit_per_sec`. Requests arriving with an
+//! empty bucket are refused with 429 Too Many Requests and counted in the
+//! `api_requests_throttled_total` metric. Whitelisted IPs, by default the
+//! local operator's loopback addresses, are never throttled. Buckets are
+//! kept for the most recently seen clients only, so a flood of new IPs
+//! cannot grow the limiter without bound.
+
+use crate::config::ApiConfig;
+use crate::error_recovery::RateLimiter;
+use crate::metrics::Metrics;
+use crate::network_security::DdosProtection;
+use crate::performance::LruCache;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+/// Client buckets kept before the least recently seen one is dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Per-client-IP token bucket limiter for the REST API
+pub struct ApiRateLimiter {
+    rate: u32,
+    burst: u32,
+    buckets: Mutex<LruCache<IpAddr, RateLimiter>>,
+    protection: DdosProtection,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl ApiRateLimiter {
+    pub fn new(rate: u32, burst: u32) -> Self {
+        ApiRateLimiter {
+            rate,
+            burst,
+            buckets: Mutex::new(LruCache::new(MAX_TRACKED_CLIENTS)),
+            protection: DdosProtection::new(),
+            metrics: None,
+        }
+    }
+
+    /// Build the limiter described by the API configuration
+    pub fn from_config(config: &ApiConfig) -> Self {
+        let mut limiter = ApiRateLimiter::new(config.rate_limit_per_sec, config.rate_limit_burst);
+        for ip in &config.rate_limit_whitelist {
+            limiter = limiter.with_whitelisted(ip);
+        }
+        limiter
+    }
+
+    pub fn with_whitelisted(mut self, ip: &str) -> Self {
+        self.protection.whitelist_peer(ip);
+        self
+    }
+
+    /// Count throttled requests in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Take a request token for `ip`; false if the client must back off
+    pub fn check(&self, ip: IpAddr) -> bool {
+        if self.rate == 0 || self.protection.is_whitelisted(&ip.to_string()) {
+            return true;
+        }
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut bucket = buckets.get(&ip).unwrap_or_else(|| RateLimiter::new(self.burst, self.rate));
+        let allowed = bucket.try_acquire();
+        buckets.insert(ip, bucket);
+        if !allowed && let Some(metrics) = &self.metrics {
+            metrics.api_requests_throttled.inc();
+        }
+        allowed
+    }
+}
+
+/// Middleware refusing requests from clients over their rate limit
+pub async fn limit_requests(
+    State(limiter): State<Arc<ApiRateLimiter>>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if limiter.check(client.ip()) {
+        return next.run(request).await;
+    }
+    let body = serde_json::json!({ "error": "Rate limit exceeded, retry later" });
+    (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, "1")], Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttles_after_burst_except_whitelisted() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let limiter = ApiRateLimiter::new(1, 3).with_whitelisted("127.0.0.1").with_metrics(metrics.clone());
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "203.0.113.8".parse().unwrap();
+
+        assert!((0..3).all(|_| limiter.check(client)));
+        assert!(!limiter.check(client));
+        assert!(!limiter.check(client));
+        // Buckets are per client
+        assert!(limiter.check(other));
+        assert!((0..10).all(|_| limiter.check("127.0.0.1".parse().unwrap())));
+        assert_eq!(metrics.api_requests_throttled.get(), 2);
+
+        // A zero rate disables limiting
+        let unlimited = ApiRateLimiter::new(0, 1);
+        assert!((0..10).all(|_| unlimited.check(client)));
+    }
+
+    #[test]
+    fn test_tracks_most_recent_clients_only() {
+        let mut limiter = ApiRateLimiter::new(1, 1);
+        limiter.buckets = Mutex::new(LruCache::new(2));
+        let ip = |last: u8| IpAddr::from([203, 0, 113, last]);
+
+        assert!(limiter.check(ip(1)) && limiter.check(ip(2)));
+        assert!(!limiter.check(ip(1)));
+        // A third client pushes out the least recently seen one
+        assert!(limiter.check(ip(3)));
+        assert_eq!(limiter.buckets.lock().unwrap().size(), 2);
+        assert!(!limiter.check(ip(1)));
+        assert!(limiter.check(ip(2)));
+    }
+}
@@ -0,0 +1,168 @@
+//! Beacon Chain
+//!
+//! With sharding enabled every shard produces its own chain of blocks from
+//! the transactions of the accounts it owns. The beacon chain ties them
+//! together: each beacon block commits to the latest header of every shard
+//! that produced a block since the previous beacon block, and a shard header
+//! is only accepted if it extends the shard tip the beacon chain last saw.
+
+use crate::shard_coordinator::ShardId;
+use crate::types::{tx_root, Transaction};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// Parent hash of the first block of a chain
+const GENESIS_HASH: &str = "0";
+
+/// Header of a block produced by one shard
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardBlockHeader {
+    pub shard: ShardId,
+    pub height: u64,
+    pub parent_hash: String,
+    /// Hex-encoded Merkle root of the block's transactions
+    pub transactions_root: String,
+    pub transaction_count: usize,
+    pub hash: String,
+}
+
+impl ShardBlockHeader {
+    /// Header of `shard`'s block at `height` on top of `parent_hash`
+    pub fn new(shard: ShardId, height: u64, parent_hash: String, transactions: &[Transaction]) -> Self {
+        let transactions_root = hex::encode(tx_root(transactions));
+        let hash = hash_fields(&[
+            &shard.0.to_string(),
+            &height.to_string(),
+            &parent_hash,
+            &transactions_root,
+        ]);
+        ShardBlockHeader {
+            shard,
+            height,
+            parent_hash,
+            transactions_root,
+            transaction_count: transactions.len(),
+            hash,
+        }
+    }
+}
+
+/// Beacon block aggregating shard header commitments
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconBlock {
+    pub height: u64,
+    pub parent_hash: String,
+    /// Included shard headers, in shard order
+    pub shard_headers: Vec<ShardBlockHeader>,
+    pub hash: String,
+}
+
+pub struct BeaconChain {
+    blocks: Vec<BeaconBlock>,
+    /// Height and hash of the last accepted header of every shard
+    shard_tips: HashMap<ShardId, (u64, String)>,
+    /// Headers accepted since the last beacon block
+    pending: BTreeMap<ShardId, ShardBlockHeader>,
+}
+
+impl BeaconChain {
+    pub fn new() -> Self {
+        BeaconChain {
+            blocks: Vec::new(),
+            shard_tips: HashMap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Height and hash the next header of `shard` must build on
+    pub fn next_shard_parent(&self, shard: ShardId) -> (u64, String) {
+        match self.shard_tips.get(&shard) {
+            Some((height, hash)) => (height + 1, hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        }
+    }
+
+    /// Accept a shard header for the next beacon block
+    pub fn submit_shard_header(&mut self, header: ShardBlockHeader) -> Result<(), String> {
+        let (height, parent_hash) = self.next_shard_parent(header.shard);
+        if header.height != height || header.parent_hash != parent_hash {
+            return Err(format!(
+                "Shard {} header at height {} does not extend the shard tip (expected height {})",
+                header.shard.0, header.height, height
+            ));
+        }
+        self.shard_tips.insert(header.shard, (header.height, header.hash.clone()));
+        self.pending.insert(header.shard, header);
+        Ok(())
+    }
+
+    /// Seal the accepted shard headers into a beacon block (None if there are none)
+    pub fn seal(&mut self) -> Option<BeaconBlock> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let shard_headers: Vec<ShardBlockHeader> = std::mem::take(&mut self.pending).into_values().collect();
+        let height = self.blocks.len() as u64;
+        let parent_hash = self.blocks.last().map_or(GENESIS_HASH.to_string(), |block| block.hash.clone());
+
+        let mut fields = vec![height.to_string(), parent_hash.clone()];
+        fields.extend(shard_headers.iter().map(|header| header.hash.clone()));
+        let hash = hash_fields(&fields.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let block = BeaconBlock {
+            height,
+            parent_hash,
+            shard_headers,
+            hash,
+        };
+        self.blocks.push(block.clone());
+        Some(block)
+    }
+}
+
+impl Default for BeaconChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash_fields(fields: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for field in fields {
+        hasher.update(field.as_bytes());
+        hasher.update([0u8]);
+    }
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beacon_aggregates_shard_headers() {
+        let mut beacon = BeaconChain::new();
+        assert!(beacon.seal().is_none());
+
+        let tx = Transaction::transfer("alice".to_string(), "bob".to_string(), 10);
+        let first = ShardBlockHeader::new(ShardId(1), 0, GENESIS_HASH.to_string(), std::slice::from_ref(&tx));
+        beacon.submit_shard_header(first.clone()).unwrap();
+        beacon.submit_shard_header(ShardBlockHeader::new(ShardId(0), 0, GENESIS_HASH.to_string(), &[])).unwrap();
+
+        let block = beacon.seal().unwrap();
+        assert_eq!(block.height, 0);
+        let shards: Vec<ShardId> = block.shard_headers.iter().map(|h| h.shard).collect();
+        assert_eq!(shards, vec![ShardId(0), ShardId(1)]);
+
+        // A header must extend its shard's tip
+        let (height, parent) = beacon.next_shard_parent(ShardId(1));
+        assert_eq!((height, parent.as_str()), (1, first.hash.as_str()));
+        let stale = ShardBlockHeader::new(ShardId(1), 1, GENESIS_HASH.to_string(), &[tx]);
+        assert!(beacon.submit_shard_header(stale).is_err());
+        beacon.submit_shard_header(ShardBlockHeader::new(ShardId(1), height, parent, &[])).unwrap();
+
+        let next = beacon.seal().unwrap();
+        assert_eq!(next.parent_hash, block.hash);
+        assert_eq!(beacon.blocks.len(), 2);
+    }
+}
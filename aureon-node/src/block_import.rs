@@ -0,0 +1,468 @@
+//! Received Block Import
+//!
+//! Blocks gossiped by peers are checked before they touch the chain: a
+//! block must be proposed by its slot's leader in the active validator set
+//! and signed with that validator's registered key, extend the local tip,
+//! start from the local state root, and reproduce its post-state root when
+//! its transactions are executed. Its transactions may only use protocol
+//! features active at its height, its ommers must be stale blocks of the
+//! chain, a PoW block must meet the difficulty retargeted from its
+//! parent, and every piece of slashing evidence it carries must verify
+//! against what this node observed. Only then is it committed and appended, and the caller relays
+//! it to other peers. A valid PoW block competing with a block already in
+//! the chain is not committed but kept as an ommer candidate.
+
+use crate::chain_handle::ChainHandle;
+use crate::chain_store::GENESIS_HASH;
+use crate::consensus::weighting::StakeWeighting;
+use crate::consensus::ommers::{self, OmmerHeader, StaleBlocks};
+use crate::consensus::ConsensusEngine;
+use crate::contract_registry::ContractRegistry;
+use crate::emission::EmissionSchedule;
+use crate::metrics::Metrics;
+use crate::mpt::MerklePatriciaTrie;
+use crate::randomness;
+use crate::runtime_version::RuntimeSchedule;
+use crate::slashing::SlashingModule;
+use crate::state_cache::AccountCache;
+use crate::state_processor::{BlockExecution, StateProcessor};
+use crate::state_transition;
+use crate::types::Block;
+use ed25519_dalek::VerifyingKey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Validates received blocks against local state and commits them
+pub struct BlockImporter {
+    chain: ChainHandle,
+    trie: Arc<RwLock<MerklePatriciaTrie>>,
+    engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    /// Proposer keys of the validators
+    keys: HashMap<String, VerifyingKey>,
+    state_proofs: bool,
+    /// Metrics of imported blocks, and the slot length their propagation
+    /// latency is measured against
+    metrics: Option<(Arc<Metrics>, u64)>,
+    emission: Option<EmissionSchedule>,
+    cache: Option<Arc<AccountCache>>,
+    runtime: Option<RuntimeSchedule>,
+    /// Validator weights updated from committed staking transactions
+    weighting: Option<Mutex<StakeWeighting>>,
+    /// Competing PoW blocks kept for inclusion as ommers
+    stale: Option<Arc<Mutex<StaleBlocks>>>,
+    /// Deployed contracts, metered when blocks call them
+    contracts: Option<Arc<Mutex<ContractRegistry>>>,
+    /// Misbehavior the evidence in blocks is checked against
+    slashing: Option<Arc<Mutex<SlashingModule>>>,
+}
+
+impl BlockImporter {
+    pub fn new(
+        chain: ChainHandle,
+        trie: Arc<RwLock<MerklePatriciaTrie>>,
+        engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    ) -> Self {
+        BlockImporter {
+            chain,
+            trie,
+            engine,
+            keys: HashMap::new(),
+            state_proofs: false,
+            metrics: None,
+            emission: None,
+            cache: None,
+            runtime: None,
+            weighting: None,
+            stale: None,
+            contracts: None,
+            slashing: None,
+        }
+    }
+
+    /// Register the key `validator` signs its block proposals with
+    pub fn with_validator_key(mut self, validator: &str, key: VerifyingKey) -> Self {
+        self.keys.insert(validator.to_string(), key);
+        self
+    }
+
+    /// Check blocks carrying a state transition proof against the proof
+    /// instead of executing them
+    pub fn with_state_proofs(mut self, enabled: bool) -> Self {
+        self.state_proofs = enabled;
+        self
+    }
+
+    /// Record imported blocks, their gas and their latency since the start
+    /// of their `block_interval_ms` slot in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>, block_interval_ms: u64) -> Self {
+        self.metrics = Some((metrics, block_interval_ms));
+        self
+    }
+
+    /// Mint block rewards to proposers following `schedule`
+    pub fn with_emission(mut self, schedule: Option<EmissionSchedule>) -> Self {
+        self.emission = schedule;
+        self
+    }
+
+    /// Read and write balances through `cache`
+    pub fn with_cache(mut self, cache: Option<Arc<AccountCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Record the gas and logs of contract calls against `contracts` in receipts
+    pub fn with_contracts(mut self, contracts: Arc<Mutex<ContractRegistry>>) -> Self {
+        self.contracts = Some(contracts);
+        self
+    }
+
+    /// Reject blocks carrying evidence `slashing` does not verify
+    pub fn with_slashing(mut self, slashing: Arc<Mutex<SlashingModule>>) -> Self {
+        self.slashing = Some(slashing);
+        self
+    }
+
+    /// Reject blocks using protocol features not yet active at their height
+    pub fn with_runtime(mut self, schedule: Option<RuntimeSchedule>) -> Self {
+        self.runtime = schedule;
+        self
+    }
+
+    /// Rotate the engine's validators by the stake bonded in committed blocks
+    pub fn with_stake_weighting(mut self, weighting: StakeWeighting) -> Self {
+        self.weighting = Some(Mutex::new(weighting));
+        self
+    }
+
+    /// Keep valid PoW blocks competing with a block of the chain in `stale`
+    /// instead of dropping them
+    pub fn with_stale_blocks(mut self, stale: Arc<Mutex<StaleBlocks>>) -> Self {
+        self.stale = Some(stale);
+        self
+    }
+
+    /// Validate `block` and commit it, returning its height and execution
+    #[tracing::instrument(
+        name = "block_import",
+        skip_all,
+        fields(block_hash = %block.hash, slot = block.slot, proposer = %block.proposer),
+        err(level = "warn")
+    )]
+    pub fn import(&self, block: &Block) -> Result<(u64, BlockExecution), String> {
+        let mut engine = self.engine.lock().unwrap();
+        let mut proposer_key = None;
+        if let Some(leader) = engine.slot_leader(block.slot) {
+            if block.proposer != leader {
+                return Err(format!(
+                    "Slot {} block proposed by '{}' instead of leader {}",
+                    block.slot, block.proposer, leader
+                ));
+            }
+            let key = self
+                .keys
+                .get(&leader)
+                .ok_or_else(|| format!("No key registered for validator {}", leader))?;
+            block.verify_proposal(key)?;
+            proposer_key = Some(key);
+        }
+
+        let chain = &self.chain;
+        let parent = chain.parent_hash();
+        if block.previous_hash != parent {
+            // The fork choice keeps the block seen first; a competing block can still become an ommer
+            let height = match block.previous_hash.as_str() {
+                GENESIS_HASH => Some(0),
+                previous_hash => chain.height_of(previous_hash).map(|parent| parent + 1),
+            };
+            if let (Some(stale), Some(height)) = (&self.stale, height) {
+                let ommer = OmmerHeader::of(block, height);
+                if ommer.has_valid_pow() {
+                    if stale.lock().unwrap().record(ommer)
+                        && let Some((metrics, _)) = &self.metrics
+                    {
+                        metrics.stale_blocks.inc();
+                    }
+                    return Err(format!("Block {} is stale, competing with block {} of the chain", block.hash, height));
+                }
+            }
+            return Err(format!("Block parent {} is not the tip {}", block.previous_hash, parent));
+        }
+        ommers::check(block, chain.len(), chain)?;
+        if let Some(runtime) = &self.runtime {
+            runtime.check_block(chain.len(), block)?;
+        }
+        if let Some(slashing) = &self.slashing {
+            slashing
+                .lock()
+                .unwrap()
+                .check_block_evidence(&block.evidence)
+                .map_err(|e| format!("Block {} carries invalid evidence: {}", block.hash, e))?;
+        }
+        let head = chain.head();
+        randomness::verify(block, &randomness::seed_of(head.as_ref()), proposer_key)?;
+        engine.update_tip(head.as_ref());
+
+        let mut trie = self.trie.write().unwrap();
+        let pre_state_root = trie.root_hash();
+        if block.pre_state_root != pre_state_root {
+            return Err(format!(
+                "Block pre-state root 0x{} does not match local state 0x{}",
+                hex::encode(&block.pre_state_root),
+                hex::encode(&pre_state_root)
+            ));
+        }
+        let mut processor = StateProcessor::new(chain.db(), &mut trie)
+            .with_emission(self.emission)
+            .with_cache(self.cache.clone());
+        if let Some(contracts) = &self.contracts {
+            let (registry, db) = (contracts.clone(), chain.db().clone());
+            processor = processor.with_contracts(
+                Arc::new(move |address: &str| registry.lock().unwrap().get_contract(address)),
+                Arc::new(move |key: &[u8]| db.get(key)),
+            );
+        }
+        let post_state_root = match &block.state_proof {
+            Some(_) if self.state_proofs => state_transition::proven_state_root(block, &processor)?,
+            _ => processor.simulate_block_with_ommers(&block.proposer, &block.transactions, &block.ommers),
+        };
+        if !engine.validate_block(block, pre_state_root, post_state_root) {
+            return Err(format!("Block {} failed consensus validation", block.hash));
+        }
+        // Released before the weighting updates the engine's validators
+        drop(engine);
+
+        let execution = processor.apply_block(block);
+        let height = chain.append(block)?;
+        let gas_used: u64 = execution.receipts.iter().map(|receipt| receipt.gas_used).sum();
+        tracing::info!(
+            height,
+            gas_used,
+            state_root = %hex::encode(&block.post_state_root),
+            "state committed"
+        );
+        if let Some(weighting) = &self.weighting
+            && let Some(transition) = weighting.lock().unwrap().on_block(height, &block.transactions)
+        {
+            tracing::info!(
+                epoch = transition.epoch,
+                validators = transition.validators.len(),
+                entered = ?transition.entered,
+                exited = ?transition.exited,
+                "validator set rotated"
+            );
+        }
+        self.engine.lock().unwrap().update_randomness(randomness::seed_of(Some(block)));
+        if let Some((metrics, block_interval_ms)) = &self.metrics {
+            metrics.blocks_received.inc();
+            metrics.chain_height.set(height as i64);
+            metrics.block_gas_used.with_label_values(&["imported"]).observe(gas_used as f64);
+            if block.difficulty > 0 {
+                metrics.pow_difficulty.set(block.difficulty as i64);
+            }
+            // Slots start on multiples of the slot length; slot 0 has no start time
+            if block.slot > 0 {
+                let slot_start = Duration::from_millis(block.slot.saturating_mul(*block_interval_ms));
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                metrics.block_propagation_time.observe(now.saturating_sub(slot_start).as_secs_f64());
+            }
+        }
+        Ok((height, execution))
+    }
+}
+
+/// Decode a hex-encoded Ed25519 public key
+pub fn parse_key(hex_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(hex_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Public key must be 32 hex-encoded bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_store::ChainStore;
+    use crate::consensus::difficulty::Retarget;
+    use crate::consensus::pow::PoWConsensus;
+    use crate::db::Db;
+    use crate::consensus::pos::PoSConsensus;
+    use crate::network::identity::NodeIdentity;
+    use crate::types::Transaction;
+
+    #[test]
+    fn test_import_checks_proposer_parent_and_state() {
+        let path = std::env::temp_dir().join(format!("aureon_import_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let mut genesis = MerklePatriciaTrie::new();
+        StateProcessor::new(&db, &mut genesis).set_balance("alice", 100);
+        let trie = Arc::new(RwLock::new(genesis));
+
+        let alice = NodeIdentity::from_secret([1u8; 32]);
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(HashMap::from([("alice".to_string(), 10)])));
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let importer = BlockImporter::new(ChainHandle::open(db.clone()).unwrap(), trie.clone(), Arc::new(Mutex::new(engine)))
+            .with_validator_key("alice", alice.public_key())
+            .with_metrics(metrics.clone(), 5000);
+
+        let propose = |parent: &str, signer: &NodeIdentity| {
+            let transactions = vec![Transaction::transfer("alice".into(), "bob".into(), 30)];
+            let mut state = trie.read().unwrap().clone();
+            let pre = state.root_hash();
+            let post = StateProcessor::new(&db, &mut state).simulate_block("alice", &transactions);
+            let mut block = PoSConsensus::new(HashMap::from([("alice".to_string(), 10)]))
+                .produce_block(1, parent.to_string(), transactions, pre, post);
+            let parent_seed = randomness::seed_of(ChainStore::new(&db).tip().unwrap().as_ref());
+            randomness::seal(&mut block, &parent_seed, Some(signer)).unwrap();
+            block.sign_proposal("alice", signer).unwrap();
+            block
+        };
+
+        let forged = propose(GENESIS_HASH, &NodeIdentity::from_secret([2u8; 32]));
+        assert!(importer.import(&forged).unwrap_err().contains("proposer signature"));
+        assert!(importer.import(&propose("unknown", &alice)).unwrap_err().contains("parent"));
+        let mut reseeded = propose(GENESIS_HASH, &alice);
+        reseeded.random_seed = vec![7; 32];
+        reseeded.sign_proposal("alice", &alice).unwrap();
+        assert!(importer.import(&reseeded).unwrap_err().contains("random seed"));
+
+        let block = propose(GENESIS_HASH, &alice);
+        assert_eq!(importer.import(&block).unwrap().0, 0);
+        assert_eq!(trie.read().unwrap().root_hash(), block.post_state_root);
+        assert_eq!(metrics.blocks_received.get(), 1);
+        assert_eq!(metrics.block_propagation_time.get_sample_count(), 1);
+
+        // A block built on the old state no longer matches
+        let mut stale = propose(&block.hash, &alice);
+        stale.pre_state_root = block.pre_state_root.clone();
+        assert!(importer.import(&stale).is_err());
+
+        drop(importer);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_import_rejects_inactive_features() {
+        let path = std::env::temp_dir().join(format!("aureon_import_fork_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let trie = Arc::new(RwLock::new(MerklePatriciaTrie::new()));
+        let alice = NodeIdentity::from_secret([1u8; 32]);
+        let validators = HashMap::from([("alice".to_string(), 10)]);
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(validators.clone()));
+        let forks = [("delegation".to_string(), 5)].into_iter().collect();
+        let importer = BlockImporter::new(ChainHandle::open(db.clone()).unwrap(), trie.clone(), Arc::new(Mutex::new(engine)))
+            .with_validator_key("alice", alice.public_key())
+            .with_runtime(Some(RuntimeSchedule::from_forks(&forks).unwrap()));
+
+        let transactions = vec![Transaction::delegate("alice".into(), "bob".into(), 1)];
+        let mut block = PoSConsensus::new(validators).produce_block(1, GENESIS_HASH.to_string(), transactions, vec![], vec![]);
+        block.sign_proposal("alice", &alice).unwrap();
+        assert!(importer.import(&block).unwrap_err().contains("delegation"));
+        assert!(ChainStore::new(&db).is_empty());
+
+        drop(importer);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_import_rejects_forged_downtime_evidence() {
+        use crate::config::SlashingConfig;
+        use crate::incentive_programs::StakingSystem;
+        use crate::slashing::Evidence;
+
+        let path = std::env::temp_dir().join(format!("aureon_import_evidence_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let trie = Arc::new(RwLock::new(MerklePatriciaTrie::new()));
+        let alice = NodeIdentity::from_secret([1u8; 32]);
+        let validators = HashMap::from([("alice".to_string(), 10)]);
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(validators.clone()));
+        let mut staking = StakingSystem::new(0.0);
+        staking.stake("alice".to_string(), 10_000, 0, 0);
+        staking.stake("bob".to_string(), 10_000, 0, 0);
+        let config = SlashingConfig { downtime_window: 4, min_signed_per_window: 2, ..SlashingConfig::default() };
+        let slashing = Arc::new(Mutex::new(SlashingModule::new(config, Arc::new(Mutex::new(staking)))));
+        let importer = BlockImporter::new(ChainHandle::open(db.clone()).unwrap(), trie.clone(), Arc::new(Mutex::new(engine)))
+            .with_validator_key("alice", alice.public_key())
+            .with_slashing(slashing.clone());
+
+        // bob signed every commit this node saw
+        for height in 1..=4 {
+            slashing.lock().unwrap().record_commit(height, &["alice".to_string(), "bob".to_string()]);
+        }
+        let mut state = trie.read().unwrap().clone();
+        let pre = state.root_hash();
+        let post = StateProcessor::new(&db, &mut state).simulate_block("alice", &[]);
+        let mut block = PoSConsensus::new(validators).produce_block(1, GENESIS_HASH.to_string(), vec![], pre, post);
+        block.evidence = vec![Evidence::Downtime { validator: "bob".to_string(), height: 4, missed: 4, window: 4 }];
+        randomness::seal(&mut block, &randomness::GENESIS_SEED, Some(&alice)).unwrap();
+        block.sign_proposal("alice", &alice).unwrap();
+
+        assert!(importer.import(&block).unwrap_err().contains("invalid evidence"));
+        assert!(ChainStore::new(&db).is_empty());
+        assert_eq!(slashing.lock().unwrap().stake_of("bob"), 10_000);
+
+        block.evidence.clear();
+        block.sign_proposal("alice", &alice).unwrap();
+        assert_eq!(importer.import(&block).unwrap().0, 0);
+
+        drop(importer);
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_competing_pow_block_is_kept_as_ommer() {
+        let path = std::env::temp_dir().join(format!("aureon_import_stale_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let trie = Arc::new(RwLock::new(MerklePatriciaTrie::new()));
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoWConsensus::new(Retarget::default()));
+        let stale = Arc::new(Mutex::new(StaleBlocks::new()));
+        let chain = ChainHandle::open(db.clone()).unwrap();
+        let importer = BlockImporter::new(chain.clone(), trie.clone(), Arc::new(Mutex::new(engine)))
+            .with_stale_blocks(stale.clone());
+
+        let mine = |slot: u64| {
+            let mut state = trie.read().unwrap().clone();
+            let pre = state.root_hash();
+            let post = StateProcessor::new(&db, &mut state).simulate_block("", &[]);
+            let mut block = PoWConsensus::new(Retarget::default()).produce_block(slot, GENESIS_HASH.to_string(), vec![], pre, post);
+            randomness::seal(&mut block, &randomness::GENESIS_SEED, None).unwrap();
+            block
+        };
+        let first = mine(1);
+        let competing = mine(2);
+        assert_eq!(importer.import(&first).unwrap().0, 0);
+        assert!(importer.import(&competing).unwrap_err().contains("stale"));
+        assert_eq!(chain.head().unwrap().hash, first.hash);
+        assert_eq!(stale.lock().unwrap().candidates(&chain), vec![OmmerHeader::of(&competing, 0)]);
+
+        // Blocks on the tip must meet the difficulty retargeted from it
+        let mut miner = PoWConsensus::new(Retarget::default());
+        let mut mine_on = |tip: Option<&Block>| {
+            miner.update_tip(tip);
+            let mut state = trie.read().unwrap().clone();
+            let pre = state.root_hash();
+            let post = StateProcessor::new(&db, &mut state).simulate_block("", &[]);
+            let mut block = miner.produce_block(2, first.hash.clone(), vec![], pre, post);
+            randomness::seal(&mut block, &randomness::seed_of(Some(&first)), None).unwrap();
+            block
+        };
+        let unretargeted = mine_on(None);
+        assert!(importer.import(&unretargeted).unwrap_err().contains("consensus validation"));
+        let retargeted = mine_on(Some(&first));
+        assert!(retargeted.difficulty > first.difficulty);
+        assert_eq!(importer.import(&retargeted).unwrap().0, 1);
+
+        drop((importer, chain));
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -1,112 +1,884 @@
-use crate::types::Transaction;
-use crate::db::Db;
+use crate::types::{tx_root, Block, Transaction, TransactionPayload};
+use crate::chain_handle::ChainHandle;
+use crate::chain_store::GENESIS_HASH;
+use crate::randomness;
+use crate::state_archive;
+use crate::consensus::ommers::StaleBlocks;
+use crate::consensus::ConsensusEngine;
+use crate::signer::ValidatorSigner;
+use crate::beacon::{BeaconChain, ShardBlockHeader};
+use crate::community_governance::{ParameterUpdate, ProposalAction, VotingSystem};
+use crate::shard_coordinator::{ShardCoordinator, ShardMap};
+use crate::fee_market::{self, ReservedSpace, TxClass};
+use crate::slashing::{Evidence, SlashingModule};
+use crate::spv_checkpoint::CheckpointSigner;
+use crate::contract_registry::ContractRegistry;
+use crate::logs::{self, IndexedLog};
+use crate::wasm::WasmRuntime;
 use crate::mempool::TransactionMempool;
 use crate::indexer::BlockchainIndexer;
 use crate::metrics::Metrics;
+use crate::block_timings::{BlockTimer, BlockTimings, Stage};
 use crate::network::Network;
-use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use crate::runtime_version::RuntimeSchedule;
+use crate::error_recovery::HealthChecker;
+use crate::events::{BlockEvent, EventBus, NodeEvent, TransactionEvent};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use crate::service_manager::Shutdown;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-/// Background task that produces blocks from mempool transactions at regular intervals
+/// Transactions taken into a block unless configured otherwise
+pub const DEFAULT_MAX_BLOCK_TRANSACTIONS: usize = 100;
+
+/// Share (percent) of block space reserved for slashing evidence and for
+/// system and governance transactions unless configured otherwise
+pub const DEFAULT_RESERVED_BLOCK_PERCENT: u8 = 10;
+
+/// How often a producer waiting for seal requests checks for shutdown
+const SEAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When the producer seals blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealMode {
+    /// In every slot this node leads
+    Slots,
+    /// As soon as transactions enter the mempool, and on request
+    Instant,
+    /// Only on request, such as through POST /dev/mine
+    Manual,
+}
+
+impl SealMode {
+    pub fn from_name(name: &str) -> Option<SealMode> {
+        match name {
+            "slots" => Some(SealMode::Slots),
+            "instant" => Some(SealMode::Instant),
+            "manual" => Some(SealMode::Manual),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SealRequests {
+    /// Blocks requested so far
+    requested: u64,
+    /// Requests handled so far, sealed or not
+    handled: u64,
+    /// Whether transactions entered the mempool since the last seal
+    pending: bool,
+}
+
+/// Requests to seal blocks outside the slot schedule, shared between the
+/// producer and whoever asks for blocks
+#[derive(Clone, Default)]
+pub struct SealTrigger {
+    inner: Arc<(Mutex<SealRequests>, Condvar)>,
+}
+
+impl SealTrigger {
+    /// Ask for `blocks` more blocks, returning the ticket to wait for with `wait_handled`
+    pub fn request(&self, blocks: u64) -> u64 {
+        let (requests, condvar) = &*self.inner;
+        let mut requests = requests.lock().unwrap();
+        requests.requested += blocks;
+        condvar.notify_all();
+        requests.requested
+    }
+
+    /// Note that transactions entered the mempool
+    pub fn notify_pending(&self) {
+        let (requests, condvar) = &*self.inner;
+        requests.lock().unwrap().pending = true;
+        condvar.notify_all();
+    }
+
+    /// Wait up to `timeout` until the requests up to `ticket` are handled
+    pub fn wait_handled(&self, ticket: u64, timeout: Duration) -> bool {
+        let (requests, condvar) = &*self.inner;
+        let guard = requests.lock().unwrap();
+        let (requests, _) = condvar.wait_timeout_while(guard, timeout, |requests| requests.handled < ticket).unwrap();
+        requests.handled >= ticket
+    }
+
+    /// Wait up to `timeout` for a request or pending transactions; Some(true)
+    /// takes one requested block, Some(false) clears the pending flag
+    fn next(&self, timeout: Duration) -> Option<bool> {
+        let (requests, condvar) = &*self.inner;
+        let guard = requests.lock().unwrap();
+        let (mut requests, _) = condvar
+            .wait_timeout_while(guard, timeout, |requests| requests.handled == requests.requested && !requests.pending)
+            .unwrap();
+        if requests.handled < requests.requested {
+            Some(true)
+        } else if requests.pending {
+            requests.pending = false;
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn handled(&self) {
+        let (requests, condvar) = &*self.inner;
+        requests.lock().unwrap().handled += 1;
+        condvar.notify_all();
+    }
+}
+
+/// The consensus engine a producer proposes through and the validator it proposes as
+struct ProposerRole {
+    engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    validator: String,
+    signer: Arc<dyn ValidatorSigner>,
+}
+
+/// Shard layout blocks are produced for, and the beacon chain committing them
+type ShardedProduction = (Arc<RwLock<ShardMap>>, Arc<Mutex<BeaconChain>>);
+
+/// Background task that produces blocks from mempool transactions, one
+/// slot of `block_interval_ms` at a time
 pub struct BlockProducer {
     mempool: Arc<TransactionMempool>,
-    db: Arc<Db>,
+    chain: ChainHandle,
     indexer: Arc<BlockchainIndexer>,
     metrics: Arc<Metrics>,
     block_interval_ms: u64,
+    max_block_transactions: usize,
+    /// Share (percent) of block space filled with priority lanes first
+    reserved_block_percent: u8,
+    events: Option<EventBus>,
+    contracts: Option<Arc<Mutex<ContractRegistry>>>,
+    governance: Option<Arc<Mutex<VotingSystem>>>,
+    slashing: Option<Arc<Mutex<SlashingModule>>>,
+    shards: Option<ShardedProduction>,
+    checkpoints: Option<Arc<Mutex<CheckpointSigner>>>,
+    proposer: Option<ProposerRole>,
+    runtime: Option<RuntimeSchedule>,
+    /// Liveness tracker checked once per slot
+    health: Option<Arc<Mutex<HealthChecker>>>,
+    /// Seal on requests instead of in slots
+    seal: Option<SealTrigger>,
+    /// Per-stage latencies of the recent blocks
+    timings: BlockTimings,
+    /// Stale PoW blocks to include as ommers
+    stale: Option<Arc<Mutex<StaleBlocks>>>,
 }
 
 impl BlockProducer {
     /// Create a new block producer
     pub fn new(
         mempool: Arc<TransactionMempool>,
-        db: Arc<Db>,
+        chain: ChainHandle,
         indexer: Arc<BlockchainIndexer>,
         metrics: Arc<Metrics>,
         block_interval_ms: u64,
     ) -> Self {
         BlockProducer {
             mempool,
-            db,
+            chain,
             indexer,
             metrics,
             block_interval_ms,
+            max_block_transactions: DEFAULT_MAX_BLOCK_TRANSACTIONS,
+            reserved_block_percent: DEFAULT_RESERVED_BLOCK_PERCENT,
+            events: None,
+            contracts: None,
+            governance: None,
+            slashing: None,
+            shards: None,
+            checkpoints: None,
+            proposer: None,
+            runtime: None,
+            health: None,
+            seal: None,
+            timings: BlockTimings::new(),
+            stale: None,
         }
     }
 
-    /// Start the block producer in a background thread
-    pub fn start(self) {
-        thread::spawn(move || {
-            self.run();
+    /// Take at most `max` transactions into each block
+    pub fn with_max_block_transactions(mut self, max: usize) -> Self {
+        self.max_block_transactions = max;
+        self
+    }
+
+    /// Reserve `percent` of every block's transaction slots and gas for
+    /// slashing evidence, then system and then governance transactions,
+    /// ahead of user transactions
+    pub fn with_reserved_block_percent(mut self, percent: u8) -> Self {
+        self.reserved_block_percent = percent;
+        self
+    }
+
+    /// Publish produced blocks and included transactions on the given event bus
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Execute contract calls in produced blocks against the given registry
+    pub fn with_contracts(mut self, contracts: Arc<Mutex<ContractRegistry>>) -> Self {
+        self.contracts = Some(contracts);
+        self
+    }
+
+    /// Execute passed governance proposals as their scheduled blocks are produced
+    ///
+    /// Parameter changes update the mempool's fee market and the staking
+    /// reward rate of `slashing`.
+    pub fn with_governance(mut self, governance: Arc<Mutex<VotingSystem>>, slashing: Arc<Mutex<SlashingModule>>) -> Self {
+        self.governance = Some(governance);
+        self.slashing = Some(slashing);
+        self
+    }
+
+    /// Produce one block per shard of the current layout of `shards` and
+    /// commit their headers to the beacon chain
+    pub fn with_shards(mut self, shards: Arc<RwLock<ShardMap>>, beacon: Arc<Mutex<BeaconChain>>) -> Self {
+        self.shards = Some((shards, beacon));
+        self
+    }
+
+    /// Sign SPV checkpoints over the light headers of produced blocks
+    pub fn with_checkpoints(mut self, signer: Arc<Mutex<CheckpointSigner>>) -> Self {
+        self.checkpoints = Some(signer);
+        self
+    }
+
+    /// Leave out transactions using protocol features not yet active at
+    /// the next block's height
+    pub fn with_runtime(mut self, schedule: Option<RuntimeSchedule>) -> Self {
+        self.runtime = schedule;
+        self
+    }
+
+    /// Record a check in `health` every slot, failed if the mempool cannot be read
+    pub fn with_health(mut self, health: Arc<Mutex<HealthChecker>>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Seal blocks when `trigger` asks for them instead of in slots, ignoring
+    /// slot leadership; meant for single-node development chains
+    pub fn with_seal_trigger(mut self, trigger: SealTrigger) -> Self {
+        self.seal = Some(trigger);
+        self
+    }
+
+    /// Record per-stage latencies of produced blocks into `timings`
+    pub fn with_block_timings(mut self, timings: BlockTimings) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Include stale PoW blocks kept in `stale` as ommers
+    pub fn with_stale_blocks(mut self, stale: Arc<Mutex<StaleBlocks>>) -> Self {
+        self.stale = Some(stale);
+        self
+    }
+
+    /// Produce blocks through `engine`, only in slots where `validator` is
+    /// the leader, and have its `signer` sign them
+    pub fn with_consensus(
+        mut self,
+        engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+        validator: &str,
+        signer: Arc<dyn ValidatorSigner>,
+    ) -> Self {
+        self.proposer = Some(ProposerRole {
+            engine,
+            validator: validator.to_string(),
+            signer,
         });
+        self
+    }
+
+    /// Start the block producer in a background thread that stops once
+    /// `shutdown` is triggered
+    pub fn start(self, shutdown: Shutdown) -> JoinHandle<()> {
+        thread::spawn(move || {
+            self.run(&shutdown);
+        })
     }
 
-    /// Main loop: periodically produce blocks from mempool transactions
-    fn run(&self) {
+    /// Main loop: produce a block from mempool transactions in every slot
+    /// this node leads, or whenever the seal trigger asks for one
+    fn run(&self, shutdown: &Shutdown) {
         let mut block_number = 1u64;
+        let mut last_slot = 0;
 
         loop {
-            thread::sleep(Duration::from_millis(self.block_interval_ms));
-
-            // Try to get pending transactions from mempool
-            match self.mempool.get_pending() {
-                Ok(pending_txs) => {
-                    if pending_txs.is_empty() {
-                        // No transactions, skip this block
-                        continue;
-                    }
+            let (slot, requested) = match &self.seal {
+                None => match self.wait_for_next_slot(shutdown) {
+                    Some(slot) => (slot, false),
+                    None => break,
+                },
+                Some(trigger) => match self.wait_for_seal_request(trigger, shutdown) {
+                    // Each sealed block gets a slot of its own
+                    Some(requested) => (self.current_slot().max(last_slot + 1), requested),
+                    None => break,
+                },
+            };
+            last_slot = slot;
+            self.record_health(true);
+            if let Err(e) = self.mempool.prune_queued() {
+                tracing::warn!(error = %e, "failed to prune queued transactions");
+            }
+
+            if self.seal.is_none() && !self.is_slot_leader(slot) {
+                continue;
+            }
+            let _span = tracing::info_span!("block_production", slot, block_number).entered();
+            if self.produce_next_block(block_number, slot, requested) {
+                block_number += 1;
+            }
+
+            if let Some(trigger) = &self.seal {
+                if requested {
+                    trigger.handled();
+                }
+                // Transactions left over from a full block get the next one
+                if self.mempool.get_pending().is_ok_and(|pending| !pending.is_empty()) {
+                    trigger.notify_pending();
+                }
+            }
+        }
+    }
+
+    /// Produce block `block_number` from mempool transactions, an empty one
+    /// only if `allow_empty`; returns whether a block was produced
+    fn produce_next_block(&self, block_number: u64, slot: u64, allow_empty: bool) -> bool {
+        let mut timer = BlockTimer::start();
+        match self.mempool.get_pending() {
+            Ok(pending_txs) => {
+                if pending_txs.is_empty() && !allow_empty {
+                    // No transactions, skip this block
+                    return false;
+                }
 
-                    // Take up to 100 transactions from mempool for this block
-                    match self.mempool.take_transactions(100) {
-                        Ok(transactions) => {
-                            if !transactions.is_empty() {
-                                // Finalize nonces for transactions included in block
-                                if let Err(e) = self.mempool.finalize_block_transactions(&transactions) {
-                                    eprintln!("Failed to finalize block transactions: {}", e);
-                                }
-                                
-                                self.produce_block_info(transactions, block_number);
-                                block_number += 1;
-                            }
+                if let Some((shards, beacon)) = &self.shards {
+                    let coordinator = shards.read().unwrap().coordinator().clone();
+                    self.produce_shard_blocks(&coordinator, beacon, block_number, slot);
+                    self.execute_governance(block_number);
+                    return true;
+                }
+
+                // Evidence and priority lanes fill the reserved space first, then
+                // the best-paying transactions the block gas limit
+                let mut reserved = self.reserved_space();
+                let evidence_slots = self.pending_evidence().min(reserved.count);
+                reserved.count -= evidence_slots;
+                match self.mempool.take_block_transactions(self.max_block_transactions - evidence_slots, reserved) {
+                    Ok(transactions) => {
+                        let transactions = self.drop_inactive(transactions);
+                        let evidence = self.take_evidence(evidence_slots);
+                        if transactions.is_empty() && evidence.is_empty() && !allow_empty {
+                            return false;
                         }
-                        Err(e) => {
-                            eprintln!("Failed to take transactions from mempool: {}", e);
+                        // Finalize nonces for transactions included in block
+                        if let Err(e) = self.mempool.finalize_block_transactions(&transactions) {
+                            tracing::error!(error = %e, "failed to finalize block transactions");
                         }
+                        timer.lap(Stage::Selection);
+
+                        let included = transactions.len();
+                        self.sign_checkpoint(&transactions, block_number);
+                        self.produce_block_info(transactions, evidence, block_number, slot, &mut timer);
+                        self.execute_governance(block_number);
+                        timer.lap(Stage::Execution);
+                        self.timings.record(&self.metrics, block_number, included, timer);
+                        true
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, "failed to take transactions from mempool");
+                        self.record_health(false);
+                        false
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "failed to get pending transactions");
+                self.record_health(false);
+                false
+            }
+        }
+    }
+
+    /// Space of the next block reserved for evidence and priority lanes
+    fn reserved_space(&self) -> ReservedSpace {
+        let gas_limit = self.mempool.fee_market().block_gas_limit;
+        ReservedSpace::share(self.reserved_block_percent, self.max_block_transactions, gas_limit)
+    }
+
+    /// Pieces of slashing evidence waiting for a block
+    ///
+    /// Only blocks sealed through a consensus engine carry evidence, so
+    /// none waits without one.
+    fn pending_evidence(&self) -> usize {
+        match (&self.slashing, &self.proposer) {
+            (Some(slashing), Some(_)) => slashing.lock().unwrap().pending_evidence_count(),
+            _ => 0,
+        }
+    }
+
+    /// Up to `max` pieces of pending slashing evidence for the next block
+    fn take_evidence(&self, max: usize) -> Vec<Evidence> {
+        match (&self.slashing, &self.proposer) {
+            (Some(slashing), Some(_)) => slashing.lock().unwrap().take_evidence(max),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Slash the offenders named by the evidence of a sealed block
+    fn apply_evidence(&self, evidence: &[Evidence]) {
+        let Some(slashing) = &self.slashing else {
+            return;
+        };
+        for evidence in evidence {
+            let result = slashing.lock().unwrap().apply_evidence(evidence);
+            match result {
+                Ok(event) => {
+                    tracing::info!(
+                        validator = %event.validator,
+                        misbehavior = ?event.misbehavior,
+                        slashed = %event.slashed,
+                        "validator slashed"
+                    );
+                    if let Err(e) = self.indexer.record_slashing_event(event) {
+                        tracing::error!(error = %e, "failed to index slashing event");
                     }
                 }
+                Err(e) => tracing::warn!(error = %e, "rejected evidence"),
+            }
+        }
+    }
+
+    fn record_health(&self, ok: bool) {
+        if let Some(health) = &self.health {
+            let mut health = health.lock().unwrap();
+            if ok { health.record_success() } else { health.record_failure() }
+        }
+    }
+
+    /// Drop transactions the runtime version of the next block rejects
+    fn drop_inactive(&self, transactions: Vec<Transaction>) -> Vec<Transaction> {
+        let Some(runtime) = &self.runtime else {
+            return transactions;
+        };
+        let version = runtime.version_at(self.chain.len());
+        transactions
+            .into_iter()
+            .filter(|tx| match version.check_transaction(tx) {
+                Ok(()) => true,
                 Err(e) => {
-                    eprintln!("Failed to get pending transactions: {}", e);
+                    tracing::warn!(tx_hash = %tx.hash(), error = %e, "dropping transaction");
+                    false
                 }
+            })
+            .collect()
+    }
+
+    /// Wait for the seal trigger, returning whether a block was requested
+    /// (rather than transactions pending), or None once shutdown is triggered
+    fn wait_for_seal_request(&self, trigger: &SealTrigger, shutdown: &Shutdown) -> Option<bool> {
+        while !shutdown.is_triggered() {
+            if let Some(requested) = trigger.next(SEAL_POLL_INTERVAL) {
+                return Some(requested);
             }
+            self.record_health(true);
         }
+        None
+    }
+
+    /// Slot the current time falls in
+    fn current_slot(&self) -> u64 {
+        now_ms() / self.block_interval_ms
+    }
+
+    /// Sleep until the next slot starts and return its number, or None
+    /// once shutdown is triggered
+    fn wait_for_next_slot(&self, shutdown: &Shutdown) -> Option<u64> {
+        let now = now_ms();
+        let slot = now / self.block_interval_ms + 1;
+        if shutdown.wait_timeout(Duration::from_millis(slot * self.block_interval_ms - now)) {
+            return None;
+        }
+        Some(slot)
+    }
+
+    /// Whether this node may propose in `slot` (always, without a consensus engine)
+    fn is_slot_leader(&self, slot: u64) -> bool {
+        let Some(role) = &self.proposer else {
+            return true;
+        };
+        match role.engine.lock().unwrap().slot_leader(slot) {
+            Some(leader) => leader == role.validator,
+            None => true,
+        }
+    }
+
+    /// Seal the block for `slot` through the consensus engine and have the
+    /// validator's signer sign it (an unsigned block with an unrevealed seed
+    /// and a placeholder hash without an engine)
+    fn seal_block(
+        &self,
+        transactions: &[Transaction],
+        evidence: &[Evidence],
+        block_number: u64,
+        slot: u64,
+    ) -> Result<Block, String> {
+        let tip = self.chain.head();
+        let parent_seed = randomness::seed_of(tip.as_ref());
+        let Some(role) = &self.proposer else {
+            let mut block = Block {
+                transactions: transactions.to_vec(),
+                hash: format!("{:064x}", block_number as u128 * 12345),
+                slot,
+                ..Block::default()
+            };
+            randomness::seal(&mut block, &parent_seed, None)?;
+            return Ok(block);
+        };
+        let ommers = self.stale.as_ref().map(|stale| stale.lock().unwrap().candidates(&self.chain)).unwrap_or_default();
+        let mut engine = role.engine.lock().unwrap();
+        engine.update_tip(tip.as_ref());
+        let previous_hash = tip.map_or_else(|| GENESIS_HASH.to_string(), |tip| tip.hash);
+        // The producer does not execute state, so the block commits to no state roots
+        let mut block =
+            engine.produce_block_with_ommers(slot, previous_hash, transactions.to_vec(), vec![], vec![], ommers);
+        drop(engine);
+        block.evidence = evidence.to_vec();
+        if block.difficulty > 0 {
+            self.metrics.pow_difficulty.set(block.difficulty as i64);
+        }
+        randomness::seal(&mut block, &parent_seed, Some(role.signer.as_ref()))?;
+        block.sign_proposal(&role.validator, role.signer.as_ref())?;
+        tracing::debug!(proposer = %block.proposer, block_hash = %block.hash, "block sealed");
+        Ok(block)
     }
 
     /// Log block production information (simplified version for demo)
-    fn produce_block_info(&self, transactions: Vec<Transaction>, block_number: u64) {
-        println!("\n--- Block #{} Produced from Mempool ---", block_number);
-        println!("Transactions included: {}", transactions.len());
-        
+    fn produce_block_info(
+        &self,
+        transactions: Vec<Transaction>,
+        evidence: Vec<Evidence>,
+        block_number: u64,
+        slot: u64,
+        timer: &mut BlockTimer,
+    ) {
         // Update metrics
         self.metrics.blocks_produced.inc();
         self.metrics.transactions_processed.inc_by(transactions.len() as u64);
         
-        // Calculate total gas
-        let total_gas: u64 = transactions.iter().map(|_tx| 21000).sum();
-        println!("Total gas: {}", total_gas);
-
-        // Simulate block hash (would normally be computed from block data)
-        let block_hash = format!(
-            "{:064x}",
-            block_number as u128 * 12345 // Simplified hash
+        // Calculate total gas and the fees split into burned base fee and proposer tips
+        let total_gas: u64 = transactions.iter().map(crate::fee_market::gas_limit).sum();
+        let base_fee = self.mempool.base_fee();
+        let burned_fees = total_gas as u128 * base_fee as u128;
+        let tips: u128 = transactions
+            .iter()
+            .map(|tx| crate::fee_market::gas_limit(tx) as u128 * tx.gas_price.saturating_sub(base_fee) as u128)
+            .sum();
+        self.metrics.block_gas_used.with_label_values(&["produced"]).observe(total_gas as f64);
+
+        let priority = transactions.iter().filter(|tx| fee_market::class(tx) != TxClass::Regular).count();
+        let evidence_count = evidence.len();
+        let mut block = match self.seal_block(&transactions, &evidence, block_number, slot) {
+            Ok(block) => block,
+            Err(e) => {
+                tracing::error!(block_number, error = %e, "failed to sign block");
+                self.record_health(false);
+                return;
+            }
+        };
+        let block_hash = block.hash.clone();
+        self.apply_evidence(&evidence);
+        timer.lap(Stage::Roots);
+
+        let random_seed = randomness::seed_of(Some(&block));
+        let block_logs = self.execute_contract_calls(&transactions, block_number, &block_hash, random_seed);
+        self.index_balances(&block);
+        timer.lap(Stage::Execution);
+        // Logs depend on the sealed hash and seed, so the header gets them last
+        let emitted: Vec<_> = block_logs.iter().map(|l| l.log.clone()).collect();
+        block.logs_bloom = logs::logs_bloom(&emitted);
+        block.logs_root = logs::logs_root(&emitted);
+        if !block_logs.is_empty() {
+            tracing::info!(
+                logs = emitted.len(),
+                logs_root = %hex::encode(&block.logs_root),
+                "contract logs emitted"
+            );
+            if let Err(e) = self.indexer.index_logs(block_number, block_logs) {
+                tracing::error!(error = %e, "failed to index block logs");
+            }
+        }
+        timer.lap(Stage::Persistence);
+        tracing::info!(
+            block_number,
+            block_hash = %block_hash,
+            transactions = transactions.len(),
+            priority,
+            evidence = evidence_count,
+            logs_root = %hex::encode(&block.logs_root),
+            total_gas,
+            base_fee,
+            burned_fees = %burned_fees,
+            tips = %tips,
+            "block produced"
         );
-        println!("Block hash: {}", block_hash);
-        println!("✅ Block #{} produced", block_number);
+
+        if let Some(events) = &self.events {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            events.publish(NodeEvent::Blocks(BlockEvent {
+                event_type: "new_block".to_string(),
+                block_hash: block_hash.clone(),
+                block_number,
+                timestamp,
+            }));
+            for tx in &transactions {
+                events.publish(NodeEvent::Transactions(TransactionEvent {
+                    event_type: "transaction_included".to_string(),
+                    tx_hash: tx.hash(),
+                    from: tx.from.clone(),
+                    block_number,
+                }));
+            }
+        }
+        timer.lap(Stage::Broadcast);
+    }
+
+    /// Refresh the rich list entries of the accounts `block` touched, and of its proposer
+    fn index_balances(&self, block: &Block) {
+        let touched = state_archive::touched_accounts(block);
+        let proposer = Some(block.proposer.as_str()).filter(|proposer| !proposer.is_empty());
+        for account in touched.iter().map(String::as_str).chain(proposer) {
+            let balance = self
+                .chain
+                .db()
+                .get(account.as_bytes())
+                .and_then(|bytes| bytes.try_into().ok())
+                .map_or(0, u64::from_le_bytes);
+            if let Err(e) = self.indexer.update_balance(account, balance) {
+                tracing::warn!(account, error = %e, "failed to index balance");
+            }
+        }
+    }
+
+    /// Deploy and upgrade the block's contracts and run its contract calls,
+    /// collecting the logs of successful calls; calls can read the block's `random_seed`
+    fn execute_contract_calls(
+        &self,
+        transactions: &[Transaction],
+        block_number: u64,
+        block_hash: &str,
+        random_seed: [u8; 32],
+    ) -> Vec<IndexedLog> {
+        let Some(contracts) = &self.contracts else {
+            return Vec::new();
+        };
+
+        let mut block_logs = Vec::new();
+        for tx in transactions {
+            if let TransactionPayload::ContractDeploy { code, .. } = &tx.payload {
+                // Contracts deployed by transactions live at (sender, transaction nonce)
+                let deployed = WasmRuntime::new(code)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| contracts.lock().unwrap().deploy(&tx.from, tx.nonce, code.clone()).map_err(|e| e.to_string()));
+                match deployed {
+                    Ok(address) => {
+                        self.metrics.contracts_deployed.inc();
+                        tracing::info!(contract = %address, deployer = %tx.from, "contract deployed");
+                    }
+                    Err(e) => tracing::warn!(deployer = %tx.from, error = %e, "contract deployment failed"),
+                }
+                continue;
+            }
+            if let TransactionPayload::ContractUpgrade { contract_address, code, .. } = &tx.payload {
+                let upgraded = WasmRuntime::new(code)
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| {
+                        let mut contracts = contracts.lock().unwrap();
+                        contracts.upgrade(contract_address, &tx.from, code.clone(), block_number).map_err(|e| e.to_string())
+                    });
+                match upgraded {
+                    Ok(()) => tracing::info!(contract = %contract_address, admin = %tx.from, "contract upgraded"),
+                    Err(e) => tracing::warn!(contract = %contract_address, error = %e, "contract upgrade failed"),
+                }
+                continue;
+            }
+            let TransactionPayload::ContractCall { contract_address, gas_limit, .. } = &tx.payload else {
+                continue;
+            };
+            let Some(code) = contracts.lock().unwrap().get_contract(contract_address) else {
+                continue;
+            };
+            let started = Instant::now();
+            let db = self.chain.db().clone();
+            let registry = contracts.clone();
+            let result = WasmRuntime::new(&code).and_then(|runtime| {
+                runtime
+                    .with_address(contract_address)
+                    .with_token_state(Arc::new(move |key: &[u8]| db.get(key)))
+                    .with_contracts(Arc::new(move |address: &str| registry.lock().unwrap().get_contract(address)))
+                    .with_random_seed(random_seed)
+                    .execute_contract_with_context(*gas_limit, Default::default())
+            });
+            if let Ok(result) = &result {
+                self.metrics.record_contract_call(contract_address, started.elapsed(), result.gas_used);
+            }
+            match result {
+                Ok(result) if result.success => {
+                    for (key, value) in &result.token_changes {
+                        self.chain.db().put(key, value);
+                    }
+                    let tx_hash = tx.hash();
+                    for log in result.logs {
+                        block_logs.push(IndexedLog {
+                            block_number,
+                            block_hash: block_hash.to_string(),
+                            tx_hash: tx_hash.clone(),
+                            log_index: block_logs.len(),
+                            log,
+                        });
+                    }
+                }
+                Ok(result) => tracing::warn!(contract = %contract_address, output = %result.output, "contract call failed"),
+                Err(e) => tracing::warn!(contract = %contract_address, error = %e, "contract call failed"),
+            }
+        }
+        block_logs
+    }
+
+    /// Produce a block for every shard with pending transactions and seal a beacon block
+    fn produce_shard_blocks(&self, coordinator: &ShardCoordinator, beacon: &Mutex<BeaconChain>, block_number: u64, slot: u64) {
+        let mut beacon = beacon.lock().unwrap();
+        for shard in coordinator.all_shards() {
+            let reserved = self.reserved_space();
+            let transactions = match self.mempool.take_shard_transactions(shard, self.max_block_transactions, reserved) {
+                Ok(transactions) if !transactions.is_empty() => transactions,
+                Ok(_) => continue,
+                Err(e) => {
+                    tracing::error!(shard = shard.0, error = %e, "failed to take shard transactions from mempool");
+                    continue;
+                }
+            };
+            if let Err(e) = self.mempool.finalize_block_transactions(&transactions) {
+                tracing::error!(error = %e, "failed to finalize block transactions");
+            }
+
+            let (height, parent_hash) = beacon.next_shard_parent(shard);
+            let header = ShardBlockHeader::new(shard, height, parent_hash, &transactions);
+            let _span = tracing::info_span!("shard_block", shard = shard.0, height).entered();
+            tracing::info!(transactions = header.transaction_count, "shard block produced");
+            if let Err(e) = beacon.submit_shard_header(header) {
+                tracing::error!(error = %e, "failed to commit shard header");
+            }
+            let mut timer = BlockTimer::start();
+            let included = transactions.len();
+            self.produce_block_info(transactions, vec![], block_number, slot, &mut timer);
+            self.timings.record(&self.metrics, block_number, included, timer);
+        }
+
+        if let Some(block) = beacon.seal() {
+            tracing::info!(
+                height = block.height,
+                shard_headers = block.shard_headers.len(),
+                block_hash = %block.hash,
+                "beacon block committed"
+            );
+        }
+    }
+
+    /// Add the block to the light header chain, signing it if it is a checkpoint block
+    fn sign_checkpoint(&self, transactions: &[Transaction], block_number: u64) {
+        let Some(signer) = &self.checkpoints else {
+            return;
+        };
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut signer = signer.lock().unwrap();
+        if let Some(checkpoint) = signer.on_block(block_number, hex::encode(tx_root(transactions)), timestamp) {
+            tracing::info!(block_number, block_hash = %checkpoint.header.block_hash, "signed SPV checkpoint");
+        }
+    }
+
+    /// Apply the parameter changes, contract upgrades and authority changes
+    /// of proposals scheduled for `block_number`
+    fn execute_governance(&self, block_number: u64) {
+        let Some(governance) = &self.governance else {
+            return;
+        };
+        let actions = governance.lock().unwrap().execute_due(block_number);
+        for (proposal_id, action) in actions {
+            match action {
+                ProposalAction::Parameter(update) => {
+                    self.apply_parameter_update(update);
+                    tracing::info!(proposal_id, update = ?update, "executed governance proposal");
+                }
+                ProposalAction::ContractUpgrade(upgrade) => {
+                    let upgraded = match &self.contracts {
+                        Some(contracts) => WasmRuntime::new(&upgrade.code).map_err(|e| e.to_string()).and_then(|_| {
+                            contracts
+                                .lock()
+                                .unwrap()
+                                .apply_governance_upgrade(&upgrade.contract_address, upgrade.code, proposal_id, block_number)
+                                .map_err(|e| e.to_string())
+                        }),
+                        None => Err("No contract registry attached".to_string()),
+                    };
+                    match upgraded {
+                        Ok(()) => tracing::info!(
+                            proposal_id,
+                            contract = %upgrade.contract_address,
+                            "executed governance contract upgrade"
+                        ),
+                        Err(e) => tracing::warn!(proposal_id, error = %e, "governance contract upgrade failed"),
+                    }
+                }
+                ProposalAction::Authority(change) => {
+                    let changed = match &self.proposer {
+                        Some(role) => role.engine.lock().unwrap().apply_authority_change(&change),
+                        None => Err("No consensus engine attached".to_string()),
+                    };
+                    match changed {
+                        Ok(()) => tracing::info!(proposal_id, change = ?change, "executed governance authority change"),
+                        Err(e) => tracing::warn!(proposal_id, error = %e, "governance authority change failed"),
+                    }
+                }
+            }
+        }
+    }
+
+    fn apply_parameter_update(&self, update: ParameterUpdate) {
+        match update {
+            ParameterUpdate::BlockGasLimit(limit) => {
+                let mut fee_market = self.mempool.fee_market();
+                fee_market.block_gas_limit = limit;
+                self.mempool.set_fee_market(fee_market);
+            }
+            ParameterUpdate::MinGasPrice(price) => {
+                let mut fee_market = self.mempool.fee_market();
+                fee_market.min_gas_price = price;
+                self.mempool.set_fee_market(fee_market);
+            }
+            ParameterUpdate::StakingRewardRate(rate) => {
+                if let Some(slashing) = &self.slashing {
+                    slashing.lock().unwrap().set_reward_rate(rate);
+                }
+            }
+        }
     }
 
     /// Get block by number from indexer (for P2P sync)
     pub fn get_block_by_number(&self, block_number: u64) -> Result<Option<crate::types::Block>, String> {
-        match self.indexer.get_block_by_number(block_number)? {
+        match self.indexer.get_block_by_number(block_number).map_err(|e| e.to_string())? {
             Some(entry) => Ok(Some(entry.block)),
             None => Ok(None),
         }
@@ -136,7 +908,7 @@ impl BlockProducer {
                 network.broadcast(&response);
             }
             Err(e) => {
-                eprintln!("[BlockProducer] Error retrieving block #{}: {}", height, e);
+                tracing::error!(height, error = %e, "failed to retrieve block");
             }
         }
     }
@@ -145,20 +917,28 @@ impl BlockProducer {
     pub fn handle_sync_request(&self, network: &Network, from_height: u64, to_height: u64) {
         match self.get_blocks_in_range(from_height, to_height) {
             Ok(blocks) => {
-                println!("[BlockProducer] Responding with {} blocks for sync", blocks.len());
+                tracing::debug!(blocks = blocks.len(), "responding to sync request");
                 let response = crate::network::Message::SyncResponse { blocks };
                 network.broadcast(&response);
             }
             Err(e) => {
-                eprintln!("[BlockProducer] Error retrieving blocks {}-{}: {}", from_height, to_height, e);
+                tracing::error!(from_height, to_height, error = %e, "failed to retrieve blocks for sync");
             }
         }
     }
 }
 
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::Db;
 
     #[test]
     fn test_block_producer_creation() {
@@ -166,7 +946,7 @@ mod tests {
         let metrics = Arc::new(Metrics::new().unwrap());
         let _producer = BlockProducer::new(
             Arc::new(TransactionMempool::new()),
-            Arc::new(Db::open("test_db")),
+            ChainHandle::open(Arc::new(Db::open("test_db"))).unwrap(),
             Arc::new(BlockchainIndexer::new()),
             metrics,
             1000,
@@ -174,6 +954,39 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_dir_all("test_db");
     }
+
+    #[test]
+    fn test_manual_seal_produces_requested_blocks() {
+        let path = std::env::temp_dir().join(format!("aureon_manual_seal_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let trigger = SealTrigger::default();
+        let shutdown = Shutdown::new();
+        let producer = BlockProducer::new(
+            Arc::new(TransactionMempool::new()),
+            ChainHandle::open(Arc::new(Db::open(path.to_str().unwrap()))).unwrap(),
+            Arc::new(BlockchainIndexer::new()),
+            metrics.clone(),
+            // Slots would never come around during the test
+            3_600_000,
+        )
+        .with_seal_trigger(trigger.clone());
+        let handle = producer.start(shutdown.clone());
+
+        // Requested blocks are sealed even with an empty mempool
+        let ticket = trigger.request(2);
+        assert!(trigger.wait_handled(ticket, Duration::from_secs(10)));
+        assert_eq!(metrics.blocks_produced.get(), 2);
+
+        // Pending transactions alone never seal an empty block
+        trigger.notify_pending();
+        assert!(!trigger.wait_handled(ticket + 1, Duration::from_millis(300)));
+        assert_eq!(metrics.blocks_produced.get(), 2);
+
+        shutdown.trigger();
+        handle.join().unwrap();
+        let _ = std::fs::remove_dir_all(&path);
+    }
 }
 
 /// Utility function to route transactions to shards
@@ -0,0 +1,165 @@
+//! Block Production Timings
+//!
+//! The block producer times each stage of producing a block: taking
+//! transactions from the mempool, sealing the block and computing its roots,
+//! executing its contracts and governance actions, persisting its logs, and
+//! broadcasting its events. Every stage is observed into the
+//! `block_production_stage_seconds` histogram, and the breakdowns of the
+//! most recent blocks are kept in memory for `/debug/block-timings`.
+
+use crate::metrics::Metrics;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Blocks whose breakdown is kept
+pub const RECENT_BLOCKS: usize = 100;
+
+/// Stage of block production
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Selection,
+    Roots,
+    Execution,
+    Persistence,
+    Broadcast,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [Stage::Selection, Stage::Roots, Stage::Execution, Stage::Persistence, Stage::Broadcast];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Selection => "selection",
+            Stage::Roots => "roots",
+            Stage::Execution => "execution",
+            Stage::Persistence => "persistence",
+            Stage::Broadcast => "broadcast",
+        }
+    }
+}
+
+/// Time spent in each stage while producing one block
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockTiming {
+    pub block_number: u64,
+    pub transactions: usize,
+    /// Microseconds by stage name
+    pub stages_us: BTreeMap<&'static str, u64>,
+    pub total_us: u64,
+}
+
+/// Stopwatch splitting the production of a block into stages
+pub struct BlockTimer {
+    lap_started: Instant,
+    stages: [Duration; 5],
+}
+
+impl BlockTimer {
+    pub fn start() -> Self {
+        Self { lap_started: Instant::now(), stages: [Duration::ZERO; 5] }
+    }
+
+    /// Charge the time since the previous lap to `stage`
+    pub fn lap(&mut self, stage: Stage) {
+        let now = Instant::now();
+        let index = Stage::ALL.iter().position(|s| *s == stage).expect("stage is listed");
+        self.stages[index] += now - self.lap_started;
+        self.lap_started = now;
+    }
+}
+
+/// Latency statistics of one stage over the recent blocks, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageSummary {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Recent block timings, shared by the producer and the API
+#[derive(Clone, Default)]
+pub struct BlockTimings {
+    recent: Arc<Mutex<VecDeque<BlockTiming>>>,
+}
+
+impl BlockTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the stages `timer` measured for a block, observing them into `metrics`
+    pub fn record(&self, metrics: &Metrics, block_number: u64, transactions: usize, timer: BlockTimer) {
+        let mut stages_us = BTreeMap::new();
+        for (stage, elapsed) in Stage::ALL.iter().zip(timer.stages) {
+            metrics.block_stage_time.with_label_values(&[stage.name()]).observe(elapsed.as_secs_f64());
+            stages_us.insert(stage.name(), elapsed.as_micros() as u64);
+        }
+        let total_us = stages_us.values().sum();
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_BLOCKS {
+            recent.pop_front();
+        }
+        recent.push_back(BlockTiming { block_number, transactions, stages_us, total_us });
+    }
+
+    /// Breakdowns of the recent blocks, oldest first
+    pub fn recent(&self) -> Vec<BlockTiming> {
+        self.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Per-stage statistics over the recent blocks, plus the "total"
+    pub fn summary(&self) -> BTreeMap<&'static str, StageSummary> {
+        let recent = self.recent();
+        if recent.is_empty() {
+            return BTreeMap::new();
+        }
+        let names = Stage::ALL.iter().map(|stage| stage.name()).chain(["total"]);
+        names
+            .map(|name| {
+                let mut values: Vec<u64> = recent
+                    .iter()
+                    .map(|timing| if name == "total" { timing.total_us } else { timing.stages_us[name] })
+                    .collect();
+                values.sort_unstable();
+                let ms = |us: u64| us as f64 / 1000.0;
+                let percentile = |p: usize| ms(values[(values.len() - 1) * p / 100]);
+                let summary = StageSummary {
+                    mean_ms: ms(values.iter().sum::<u64>()) / values.len() as f64,
+                    p50_ms: percentile(50),
+                    p95_ms: percentile(95),
+                    max_ms: ms(*values.last().expect("values are not empty")),
+                };
+                (name, summary)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings_keep_recent_blocks_and_summarize_stages() {
+        let metrics = Metrics::new().unwrap();
+        let timings = BlockTimings::new();
+        for block_number in 0..RECENT_BLOCKS as u64 + 5 {
+            let mut timer = BlockTimer::start();
+            timer.stages[0] = Duration::from_millis(block_number % 10);
+            timer.lap(Stage::Broadcast);
+            timings.record(&metrics, block_number, 1, timer);
+        }
+
+        let recent = timings.recent();
+        assert_eq!(recent.len(), RECENT_BLOCKS);
+        assert_eq!(recent[0].block_number, 5);
+        let summary = timings.summary();
+        assert_eq!(summary["selection"].max_ms, 9.0);
+        assert_eq!(summary["selection"].mean_ms, 4.5);
+        assert!(summary["total"].p95_ms >= summary["selection"].p95_ms);
+        assert!(metrics.export().unwrap().contains("block_production_stage_seconds_count{stage=\"roots\"} 105"));
+    }
+}
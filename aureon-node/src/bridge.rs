@@ -0,0 +1,580 @@
+//! Cross-Chain Bridge
+//!
+//! Relayers watching an external chain attest to assets locked there with
+//! `BridgeAttest` transactions. Once `threshold` of the relayer keys have
+//! signed the same lock, wrapped tokens are minted to the recipient: one
+//! token per external chain and asset, created on first use and owned by the
+//! bridge. In the other direction a `BridgeBurn` transaction burns wrapped
+//! tokens and queues their release on the external chain, which completes
+//! once enough relayers attest that they released the assets there.
+//!
+//! Instead of trusting the relayers, transfers on chains with a configured
+//! light client can be proven: anyone may relay the chain's headers with
+//! `BridgeHeader` transactions, which are checked to extend the verified tip
+//! and meet the chain's difficulty, and a `BridgeProve` transaction then
+//! completes a transfer with a merkle proof that the event's leaf hash is in
+//! the merkle root of a sufficiently confirmed header. Light clients follow a
+//! single branch from their checkpoint; competing branches are not tracked.
+//!
+//! The relayer keys, threshold and light client rules come from the
+//! `[bridge]` config and are written to state at genesis alongside the
+//! genesis balances. Transfers are stored in the state trie under `bridge:`
+//! keys; those still waiting for attestations are also listed in a pending
+//! queue.
+
+use crate::config::{BridgeChainConfig, BridgeConfig};
+use crate::light_block_header::LightBlockHeader;
+use crate::merkle_tree::{MerkleInclusionProof, MerkleProofElement};
+use crate::multisig::MultisigConfig;
+use crate::token::{self, TokenLedger, TokenState};
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Prefix of every key holding bridge state
+pub const STATE_PREFIX: &str = "bridge:";
+const RELAYERS_KEY: &[u8] = b"bridge:relayers";
+const PENDING_KEY: &[u8] = b"bridge:pending";
+const TRANSFER_PREFIX: &str = "bridge:transfer:";
+const CLIENT_PREFIX: &str = "bridge:client:";
+const HEADER_PREFIX: &str = "bridge:header:";
+
+/// Domain separating relayer attestations from other signatures
+const ATTESTATION_DOMAIN: &[u8] = b"aureon-bridge:";
+
+/// Event on an external chain relayers attest to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub enum BridgeEvent {
+    /// `amount` of `asset` locked on `chain` by `from`, to be minted to `recipient`
+    Locked {
+        chain: String,
+        /// Identifier of the lock on the external chain, such as its transaction hash
+        lock_id: String,
+        asset: String,
+        from: String,
+        recipient: String,
+        amount: u64,
+    },
+    /// The assets of outbound transfer `transfer_id` were released on their chain
+    Released { transfer_id: String },
+}
+
+impl BridgeEvent {
+    /// Bytes a relayer signs to attest to the event
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let event = bincode::encode_to_vec(self, standard()).expect("Bridge events encode");
+        [ATTESTATION_DOMAIN, &event].concat()
+    }
+
+    /// Hex leaf committing to the event in the merkle root of an external block
+    pub fn leaf_hash(&self) -> String {
+        let event = bincode::encode_to_vec(self, standard()).expect("Bridge events encode");
+        hex::encode(Sha256::digest(event))
+    }
+
+    /// Id of the transfer the event belongs to
+    pub fn transfer_id(&self) -> String {
+        match self {
+            BridgeEvent::Locked { chain, lock_id, .. } => {
+                let mut hasher = Sha256::new();
+                hasher.update(chain.as_bytes());
+                hasher.update(b":");
+                hasher.update(lock_id.as_bytes());
+                hex::encode(hasher.finalize())
+            }
+            BridgeEvent::Released { transfer_id } => transfer_id.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeDirection {
+    /// Locked on the external chain, minted on Aureon
+    Inbound,
+    /// Burned on Aureon, released on the external chain
+    Outbound,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferStatus {
+    /// Waiting for relayer attestations
+    Pending,
+    /// Minted on Aureon, or released on the external chain
+    Completed,
+}
+
+/// Transfer of an asset between Aureon and an external chain
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct BridgeTransfer {
+    pub id: String,
+    pub direction: BridgeDirection,
+    pub chain: String,
+    pub asset: String,
+    /// Recipient of an inbound or sender of an outbound transfer on Aureon
+    pub account: String,
+    /// Sender of an inbound or recipient of an outbound transfer on the external chain
+    pub external_address: String,
+    pub amount: u64,
+    pub status: TransferStatus,
+    /// Indexes of the relayer keys that attested to the transfer
+    pub attestations: Vec<u32>,
+}
+
+impl BridgeTransfer {
+    /// Whether `other` moves the same assets between the same accounts
+    fn same_transfer(&self, other: &BridgeTransfer) -> bool {
+        BridgeTransfer { status: other.status, attestations: other.attestations.clone(), ..self.clone() } == *other
+    }
+}
+
+/// Light client state of an external chain
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct LightClient {
+    pub chain: String,
+    /// Height of the last verified header
+    pub tip_height: u64,
+    /// Hash of the last verified header
+    pub tip_hash: String,
+    pub confirmations: u64,
+    pub min_difficulty: u32,
+    pub require_proof: bool,
+}
+
+impl LightClient {
+    fn from_config(config: &BridgeChainConfig) -> Self {
+        LightClient {
+            chain: config.chain.clone(),
+            tip_height: config.checkpoint_height,
+            tip_hash: config.checkpoint_hash.clone(),
+            confirmations: config.confirmations,
+            min_difficulty: config.min_difficulty,
+            require_proof: config.require_proof,
+        }
+    }
+}
+
+/// Account owning the wrapped tokens of `chain`
+pub fn bridge_account(chain: &str) -> String {
+    format!("{}{}", STATE_PREFIX, chain)
+}
+
+/// Id of the token wrapping `asset` of `chain`
+pub fn wrapped_token(chain: &str, asset: &str) -> String {
+    token::token_id(&bridge_account(chain), &wrapped_symbol(asset))
+}
+
+fn wrapped_symbol(asset: &str) -> String {
+    format!("w{}", asset).chars().take(token::MAX_SYMBOL_LEN).collect()
+}
+
+fn transfer_key(id: &str) -> Vec<u8> {
+    format!("{}{}", TRANSFER_PREFIX, id).into_bytes()
+}
+
+fn client_key(chain: &str) -> Vec<u8> {
+    format!("{}{}", CLIENT_PREFIX, chain).into_bytes()
+}
+
+fn header_key(chain: &str, height: u64) -> Vec<u8> {
+    format!("{}{}:{}", HEADER_PREFIX, chain, height).into_bytes()
+}
+
+/// Key and value of a state entry
+pub type StateEntry = (Vec<u8>, Vec<u8>);
+
+/// State entries holding the relayer set and light clients of `config`, written at genesis
+pub fn genesis_state(config: &BridgeConfig) -> Result<Vec<StateEntry>, String> {
+    if !config.enabled {
+        return Ok(Vec::new());
+    }
+    let public_keys = config
+        .relayers
+        .iter()
+        .map(|key| hex::decode(key).map_err(|_| format!("Invalid relayer key {}: expected hex", key)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let relayers = MultisigConfig { public_keys, threshold: config.threshold };
+    relayers.validate()?;
+    let bytes = bincode::encode_to_vec(&relayers, standard()).map_err(|e| e.to_string())?;
+    let mut entries = vec![(RELAYERS_KEY.to_vec(), bytes)];
+    for chain in &config.chains {
+        let client = bincode::encode_to_vec(LightClient::from_config(chain), standard()).map_err(|e| e.to_string())?;
+        entries.push((client_key(&chain.chain), client));
+    }
+    Ok(entries)
+}
+
+/// Relayer keys and how many of them must attest, if the bridge is enabled
+pub fn relayers<S: TokenState + ?Sized>(state: &S) -> Option<MultisigConfig> {
+    decode(state, RELAYERS_KEY)
+}
+
+/// Transfer `id`, if the bridge has seen it
+pub fn transfer<S: TokenState + ?Sized>(state: &S, id: &str) -> Option<BridgeTransfer> {
+    decode(state, &transfer_key(id))
+}
+
+/// Light client following `chain`, if one is configured
+pub fn light_client<S: TokenState + ?Sized>(state: &S, chain: &str) -> Option<LightClient> {
+    decode(state, &client_key(chain))
+}
+
+/// Verified header of `chain` at `height`
+pub fn header<S: TokenState + ?Sized>(state: &S, chain: &str, height: u64) -> Option<LightBlockHeader> {
+    decode(state, &header_key(chain, height))
+}
+
+/// Transfers still waiting for attestations, oldest first
+pub fn pending<S: TokenState + ?Sized>(state: &S) -> Vec<BridgeTransfer> {
+    pending_ids(state).iter().filter_map(|id| transfer(state, id)).collect()
+}
+
+fn pending_ids<S: TokenState + ?Sized>(state: &S) -> Vec<String> {
+    decode(state, PENDING_KEY).unwrap_or_default()
+}
+
+fn decode<S: TokenState + ?Sized, T: Decode<()>>(state: &S, key: &[u8]) -> Option<T> {
+    state
+        .get(key)
+        .and_then(|bytes| bincode::decode_from_slice(&bytes, standard()).ok())
+        .map(|(value, _)| value)
+}
+
+/// Bridge transfers over a `TokenState`
+///
+/// Like the token ledger, every operation checks all of its conditions
+/// before writing.
+pub struct BridgeLedger<'s, S: TokenState + ?Sized> {
+    state: &'s mut S,
+}
+
+impl<'s, S: TokenState + ?Sized> BridgeLedger<'s, S> {
+    pub fn new(state: &'s mut S) -> Self {
+        BridgeLedger { state }
+    }
+
+    /// Record the attestation of relayer key `relayer` to `event`, completing
+    /// its transfer once `threshold` distinct relayers have attested
+    pub fn attest(&mut self, event: &BridgeEvent, relayer: u32, signature: &[u8]) -> Result<(), String> {
+        let relayers = relayers(self.state).ok_or_else(|| "The bridge is not enabled".to_string())?;
+        let key = relayers
+            .public_keys
+            .get(relayer as usize)
+            .ok_or_else(|| format!("No relayer key at index {}", relayer))?;
+        let signature = Signature::from_slice(signature).map_err(|_| "Malformed relayer signature".to_string())?;
+        MultisigConfig::verifying_key(key)?
+            .verify(&event.signing_bytes(), &signature)
+            .map_err(|_| format!("Invalid signature by relayer {}", relayer))?;
+
+        let mut transfer = self.claim(event)?;
+        if light_client(self.state, &transfer.chain).is_some_and(|client| client.require_proof) {
+            return Err(format!("Transfers on {} must be proven against its headers", transfer.chain));
+        }
+        if transfer.attestations.contains(&relayer) {
+            return Err(format!("Relayer {} already attested to transfer {}", relayer, transfer.id));
+        }
+
+        transfer.attestations.push(relayer);
+        let confirmed = transfer.attestations.len() >= relayers.threshold as usize;
+        self.record(transfer, confirmed)
+    }
+
+    /// Extend the light client of `chain` with the next header
+    pub fn relay_header(&mut self, chain: &str, header: &LightBlockHeader) -> Result<(), String> {
+        let mut client = light_client(self.state, chain).ok_or_else(|| format!("No light client for chain {}", chain))?;
+        if !header.verify_hash() {
+            return Err(format!("Header {} does not match its contents", header.block_hash));
+        }
+        if header.height != client.tip_height + 1 || header.prev_hash != client.tip_hash {
+            return Err(format!(
+                "Header {} does not extend the {} tip at height {}",
+                header.block_hash, chain, client.tip_height
+            ));
+        }
+        if header.difficulty < client.min_difficulty || !header.meets_difficulty() {
+            return Err(format!("Header {} does not meet difficulty {}", header.block_hash, client.min_difficulty));
+        }
+
+        client.tip_height = header.height;
+        client.tip_hash = header.block_hash.clone();
+        let bytes = bincode::encode_to_vec(header, standard()).expect("Headers encode");
+        self.state.put(header_key(chain, header.height), bytes);
+        let bytes = bincode::encode_to_vec(&client, standard()).expect("Light clients encode");
+        self.state.put(client_key(chain), bytes);
+        Ok(())
+    }
+
+    /// Complete the transfer of `event` with a merkle proof, from the event's
+    /// leaf hash up, that it is in the verified header at `height` of its chain
+    pub fn prove(&mut self, event: &BridgeEvent, height: u64, proof: &[MerkleProofElement]) -> Result<(), String> {
+        let transfer = self.claim(event)?;
+        let chain = &transfer.chain;
+        let client = light_client(self.state, chain).ok_or_else(|| format!("No light client for chain {}", chain))?;
+        let header = header(self.state, chain, height).ok_or_else(|| format!("No verified {} header at height {}", chain, height))?;
+        let confirmations = client.tip_height - height + 1;
+        if confirmations < client.confirmations {
+            return Err(format!(
+                "{} block {} has {} of {} confirmations",
+                chain, height, confirmations, client.confirmations
+            ));
+        }
+        let inclusion = MerkleInclusionProof {
+            tx_hash: event.leaf_hash(),
+            merkle_root: header.merkle_root,
+            proof_path: proof.to_vec(),
+            tx_index: 0,
+        };
+        if !inclusion.verify() {
+            return Err(format!("Event is not included in {} block {}", chain, height));
+        }
+        self.record(transfer, true)
+    }
+
+    /// Transfer `event` belongs to, as long as it is not completed yet
+    fn claim(&self, event: &BridgeEvent) -> Result<BridgeTransfer, String> {
+        let id = event.transfer_id();
+        let transfer = match (event, transfer(self.state, &id)) {
+            (BridgeEvent::Locked { chain, asset, from, recipient, amount, .. }, existing) => {
+                let locked = BridgeTransfer {
+                    id: id.clone(),
+                    direction: BridgeDirection::Inbound,
+                    chain: chain.clone(),
+                    asset: asset.clone(),
+                    account: recipient.clone(),
+                    external_address: from.clone(),
+                    amount: *amount,
+                    status: TransferStatus::Pending,
+                    attestations: Vec::new(),
+                };
+                match existing {
+                    Some(existing) if !existing.same_transfer(&locked) => {
+                        return Err(format!("Event does not match transfer {}", id));
+                    }
+                    Some(existing) => existing,
+                    None => locked,
+                }
+            }
+            (BridgeEvent::Released { .. }, Some(existing)) if existing.direction == BridgeDirection::Outbound => existing,
+            (BridgeEvent::Released { .. }, _) => return Err(format!("Unknown outbound transfer {}", id)),
+        };
+        if transfer.status == TransferStatus::Completed {
+            return Err(format!("Transfer {} is already completed", id));
+        }
+        Ok(transfer)
+    }
+
+    /// Store `transfer`, completing it if `confirmed` and queueing it otherwise
+    fn record(&mut self, mut transfer: BridgeTransfer, confirmed: bool) -> Result<(), String> {
+        let mut pending = pending_ids(self.state);
+        if confirmed {
+            if transfer.direction == BridgeDirection::Inbound {
+                self.mint(&transfer)?;
+            }
+            transfer.status = TransferStatus::Completed;
+            pending.retain(|pending| *pending != transfer.id);
+        } else if !pending.contains(&transfer.id) {
+            pending.push(transfer.id.clone());
+        }
+        self.write(&transfer);
+        self.write_pending(&pending);
+        Ok(())
+    }
+
+    /// Burn `amount` of `from`'s wrapped `asset` of `chain` and queue its
+    /// release to `to` on that chain as outbound transfer `id`
+    pub fn burn(&mut self, id: &str, from: &str, chain: &str, asset: &str, to: &str, amount: u64) -> Result<(), String> {
+        if relayers(self.state).is_none() {
+            return Err("The bridge is not enabled".to_string());
+        }
+        if amount == 0 {
+            return Err("Bridged amount must be positive".to_string());
+        }
+        TokenLedger::new(self.state).burn(from, &wrapped_token(chain, asset), amount)?;
+        let transfer = BridgeTransfer {
+            id: id.to_string(),
+            direction: BridgeDirection::Outbound,
+            chain: chain.to_string(),
+            asset: asset.to_string(),
+            account: from.to_string(),
+            external_address: to.to_string(),
+            amount,
+            status: TransferStatus::Pending,
+            attestations: Vec::new(),
+        };
+        let mut pending = pending_ids(self.state);
+        pending.push(transfer.id.clone());
+        self.write(&transfer);
+        self.write_pending(&pending);
+        Ok(())
+    }
+
+    /// Mint the wrapped tokens of an inbound transfer, creating the token on first use
+    fn mint(&mut self, transfer: &BridgeTransfer) -> Result<(), String> {
+        let owner = bridge_account(&transfer.chain);
+        let token = wrapped_token(&transfer.chain, &transfer.asset);
+        let mut ledger = TokenLedger::new(self.state);
+        if ledger.info(&token).is_none() {
+            let name = format!("Wrapped {} ({})", transfer.asset, transfer.chain);
+            ledger.create(&owner, &name, &wrapped_symbol(&transfer.asset), 0, 0, None)?;
+        }
+        ledger.mint(&owner, &token, &transfer.account, transfer.amount)
+    }
+
+    fn write(&mut self, transfer: &BridgeTransfer) {
+        let bytes = bincode::encode_to_vec(transfer, standard()).expect("Bridge transfers encode");
+        self.state.put(transfer_key(&transfer.id), bytes);
+    }
+
+    fn write_pending(&mut self, pending: &[String]) {
+        let bytes = bincode::encode_to_vec(pending, standard()).expect("Bridge queue encodes");
+        self.state.put(PENDING_KEY.to_vec(), bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::collections::HashMap;
+
+    fn relayer_keys() -> Vec<SigningKey> {
+        (1..=3).map(|seed| SigningKey::from_bytes(&[seed; 32])).collect()
+    }
+
+    fn bridge_state(keys: &[SigningKey]) -> HashMap<Vec<u8>, Vec<u8>> {
+        let config = BridgeConfig {
+            enabled: true,
+            relayers: keys.iter().map(|key| hex::encode(key.verifying_key().to_bytes())).collect(),
+            threshold: 2,
+            chains: vec![BridgeChainConfig {
+                chain: "bitcoin".to_string(),
+                checkpoint_height: 100,
+                checkpoint_hash: "checkpoint".to_string(),
+                confirmations: 2,
+                min_difficulty: 1,
+                require_proof: true,
+            }],
+        };
+        genesis_state(&config).unwrap().into_iter().collect()
+    }
+
+    /// Mine the header following `prev` on the light client's chain
+    fn mined_header(prev_height: u64, prev_hash: &str, merkle_root: &str) -> LightBlockHeader {
+        (0..)
+            .map(|nonce| LightBlockHeader::new(prev_height + 1, prev_hash.to_string(), merkle_root.to_string(), 0, 1, nonce))
+            .find(|header| header.meets_difficulty())
+            .unwrap()
+    }
+
+    fn locked(amount: u64) -> BridgeEvent {
+        BridgeEvent::Locked {
+            chain: "ethereum".to_string(),
+            lock_id: "0xabc".to_string(),
+            asset: "ETH".to_string(),
+            from: "0xalice".to_string(),
+            recipient: "alice".to_string(),
+            amount,
+        }
+    }
+
+    fn attest(state: &mut HashMap<Vec<u8>, Vec<u8>>, keys: &[SigningKey], relayer: u32, event: &BridgeEvent) -> Result<(), String> {
+        let signature = keys[relayer as usize].sign(&event.signing_bytes()).to_bytes();
+        BridgeLedger::new(state).attest(event, relayer, &signature)
+    }
+
+    #[test]
+    fn test_lock_minted_once_threshold_attests() {
+        let keys = relayer_keys();
+        let mut state = bridge_state(&keys);
+        let lock = locked(40);
+        let token = wrapped_token("ethereum", "ETH");
+
+        attest(&mut state, &keys, 0, &lock).unwrap();
+        assert!(attest(&mut state, &keys, 0, &lock).unwrap_err().contains("already attested"));
+        assert_eq!(pending(&state).len(), 1);
+        assert_eq!(TokenLedger::new(&mut state).balance_of(&token, "alice"), 0);
+
+        // A different claim about the same lock is rejected, as are forged signatures
+        let inflated = locked(400);
+        assert!(attest(&mut state, &keys, 1, &inflated).unwrap_err().contains("does not match"));
+        let forged = keys[0].sign(&lock.signing_bytes()).to_bytes();
+        assert!(BridgeLedger::new(&mut state).attest(&lock, 1, &forged).is_err());
+
+        attest(&mut state, &keys, 1, &lock).unwrap();
+        assert_eq!(TokenLedger::new(&mut state).balance_of(&token, "alice"), 40);
+        assert!(pending(&state).is_empty());
+        assert_eq!(transfer(&state, &lock.transfer_id()).unwrap().status, TransferStatus::Completed);
+        assert!(attest(&mut state, &keys, 2, &lock).unwrap_err().contains("already completed"));
+    }
+
+    #[test]
+    fn test_burn_queues_release_until_attested() {
+        let keys = relayer_keys();
+        let mut state = bridge_state(&keys);
+        let lock = locked(40);
+        attest(&mut state, &keys, 0, &lock).unwrap();
+        attest(&mut state, &keys, 1, &lock).unwrap();
+
+        let mut ledger = BridgeLedger::new(&mut state);
+        assert!(ledger.burn("tx1", "alice", "ethereum", "ETH", "0xbob", 50).unwrap_err().contains("Insufficient"));
+        ledger.burn("tx1", "alice", "ethereum", "ETH", "0xbob", 15).unwrap();
+        assert_eq!(TokenLedger::new(&mut state).balance_of(&wrapped_token("ethereum", "ETH"), "alice"), 25);
+        assert_eq!(pending(&state)[0].direction, BridgeDirection::Outbound);
+
+        let released = BridgeEvent::Released { transfer_id: "tx1".to_string() };
+        attest(&mut state, &keys, 2, &released).unwrap();
+        attest(&mut state, &keys, 0, &released).unwrap();
+        assert_eq!(transfer(&state, "tx1").unwrap().status, TransferStatus::Completed);
+        assert!(pending(&state).is_empty());
+
+        let unknown = BridgeEvent::Released { transfer_id: lock.transfer_id() };
+        assert!(attest(&mut state, &keys, 0, &unknown).unwrap_err().contains("Unknown outbound"));
+    }
+
+    #[test]
+    fn test_lock_proven_against_relayed_headers() {
+        let keys = relayer_keys();
+        let mut state = bridge_state(&keys);
+        let lock = BridgeEvent::Locked {
+            chain: "bitcoin".to_string(),
+            lock_id: "txid".to_string(),
+            asset: "BTC".to_string(),
+            from: "bc1alice".to_string(),
+            recipient: "alice".to_string(),
+            amount: 7,
+        };
+        // The lock is the right leaf of a two-leaf tree
+        let sibling = hash("other");
+        let root = hash(&format!("{}{}", sibling, lock.leaf_hash()));
+        let proof = vec![MerkleProofElement { hash: sibling.clone(), is_left: true }];
+
+        let first = mined_header(100, "checkpoint", &root);
+        let unlinked = mined_header(100, "elsewhere", "root");
+        let mut ledger = BridgeLedger::new(&mut state);
+        assert!(ledger.relay_header("bitcoin", &unlinked).unwrap_err().contains("does not extend"));
+        let easy = LightBlockHeader::new(101, "checkpoint".to_string(), "root".to_string(), 0, 0, 0);
+        assert!(ledger.relay_header("bitcoin", &easy).unwrap_err().contains("difficulty"));
+        ledger.relay_header("bitcoin", &first).unwrap();
+
+        // Attestations are refused, and the block needs a second confirmation
+        assert!(attest(&mut state, &keys, 0, &lock).unwrap_err().contains("must be proven"));
+        let mut ledger = BridgeLedger::new(&mut state);
+        assert!(ledger.prove(&lock, 101, &proof).unwrap_err().contains("1 of 2 confirmations"));
+        ledger.relay_header("bitcoin", &mined_header(101, &first.block_hash, "root")).unwrap();
+        assert!(ledger.prove(&locked(7), 101, &proof).unwrap_err().contains("No light client"));
+        let wrong_path = vec![MerkleProofElement { hash: sibling, is_left: false }];
+        assert!(ledger.prove(&lock, 101, &wrong_path).unwrap_err().contains("not included"));
+
+        ledger.prove(&lock, 101, &proof).unwrap();
+        assert_eq!(light_client(&state, "bitcoin").unwrap().tip_height, 102);
+        assert_eq!(TokenLedger::new(&mut state).balance_of(&wrapped_token("bitcoin", "BTC"), "alice"), 7);
+        assert!(BridgeLedger::new(&mut state).prove(&lock, 101, &proof).unwrap_err().contains("already completed"));
+    }
+
+    fn hash(value: &str) -> String {
+        hex::encode(Sha256::digest(value.as_bytes()))
+    }
+}
@@ -0,0 +1,180 @@
+//! Shared Chain Handle
+//!
+//! The committed chain as seen by the block producer, block import and
+//! sync, and the API. Blocks are read from and appended to the chain store
+//! under a read-write lock, so readers see an appended block together with
+//! the head and hash index it updates, and two writers cannot both extend
+//! the same tip. Blocks are indexed by hash when the chain is opened and as
+//! they are appended.
+
+use crate::chain_store::{ChainStore, GENESIS_HASH};
+use crate::db::Db;
+use crate::types::Block;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+#[derive(Default)]
+struct ChainIndex {
+    /// Height of every block of the chain by hash, pruned ones included
+    heights: HashMap<String, u64>,
+    /// Most recently committed block and its height
+    head: Option<(u64, Block)>,
+}
+
+/// Thread-safe handle on the chain stored in the node database
+#[derive(Clone)]
+pub struct ChainHandle {
+    db: Arc<Db>,
+    index: Arc<RwLock<ChainIndex>>,
+}
+
+impl ChainHandle {
+    /// Open the chain stored in `db`, indexing its blocks by hash
+    pub fn open(db: Arc<Db>) -> Result<Self, String> {
+        let store = ChainStore::new(&db);
+        let mut index = ChainIndex::default();
+        for height in store.base()..store.len() {
+            // Only checkpoint blocks remain below the unpruned history
+            if let Some(block) = store.get_block(height)? {
+                index.heights.insert(block.hash.clone(), height);
+                index.head = Some((height, block));
+            }
+        }
+        Ok(ChainHandle { db, index: Arc::new(RwLock::new(index)) })
+    }
+
+    pub fn db(&self) -> &Arc<Db> {
+        &self.db
+    }
+
+    /// Height of the next block to be committed
+    pub fn len(&self) -> u64 {
+        self.index.read().unwrap().head.as_ref().map_or(0, |(height, _)| height + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Most recently committed block
+    pub fn head(&self) -> Option<Block> {
+        self.index.read().unwrap().head.as_ref().map(|(_, block)| block.clone())
+    }
+
+    /// Hash the next block must name as its parent
+    pub fn parent_hash(&self) -> String {
+        self.head().map_or(GENESIS_HASH.to_string(), |head| head.hash)
+    }
+
+    /// Block at `height`, `None` above the head or once pruned
+    pub fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, String> {
+        let _index = self.index.read().unwrap();
+        ChainStore::new(&self.db).get_block(height)
+    }
+
+    /// Block with `hash`, `None` if it is not part of the chain or was pruned
+    pub fn get_block_by_hash(&self, hash: &str) -> Result<Option<Block>, String> {
+        let index = self.index.read().unwrap();
+        match index.heights.get(hash) {
+            Some(&height) => ChainStore::new(&self.db).get_block(height),
+            None => Ok(None),
+        }
+    }
+
+    /// Height of the block with `hash`
+    pub fn height_of(&self, hash: &str) -> Option<u64> {
+        self.index.read().unwrap().heights.get(hash).copied()
+    }
+
+    /// Whether the block with `hash` is part of the chain
+    pub fn contains(&self, hash: &str) -> bool {
+        self.height_of(hash).is_some()
+    }
+
+    /// Append a block extending the head, returning its height
+    pub fn append(&self, block: &Block) -> Result<u64, String> {
+        let mut index = self.index.write().unwrap();
+        let height = ChainStore::new(&self.db).append_block(block)?;
+        index.heights.insert(block.hash.clone(), height);
+        index.head = Some((height, block.clone()));
+        Ok(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> (Arc<Db>, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("aureon_chain_handle_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        (Arc::new(Db::open(path.to_str().unwrap())), path)
+    }
+
+    fn block(previous_hash: &str, hash: &str) -> Block {
+        Block {
+            transactions: vec![],
+            previous_hash: previous_hash.to_string(),
+            nonce: 0,
+            hash: hash.to_string(),
+            pre_state_root: vec![],
+            post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }
+    }
+
+    #[test]
+    fn test_queries_by_height_and_hash_survive_reopen() {
+        let (db, path) = temp_db("queries");
+        let chain = ChainHandle::open(db.clone()).unwrap();
+        assert!(chain.is_empty());
+        assert_eq!(chain.parent_hash(), GENESIS_HASH);
+        assert_eq!(chain.append(&block(GENESIS_HASH, "h0")), Ok(0));
+        assert_eq!(chain.append(&block("h0", "h1")), Ok(1));
+        assert!(chain.append(&block("h0", "fork")).is_err());
+
+        let reopened = ChainHandle::open(db.clone()).unwrap();
+        for chain in [&chain, &reopened] {
+            assert_eq!(chain.len(), 2);
+            assert_eq!(chain.head().unwrap().hash, "h1");
+            assert_eq!(chain.get_block_by_height(0).unwrap().unwrap().hash, "h0");
+            assert!(chain.get_block_by_height(2).unwrap().is_none());
+            assert_eq!(chain.get_block_by_hash("h1").unwrap().unwrap().previous_hash, "h0");
+            assert_eq!(chain.height_of("h1"), Some(1));
+            assert!(chain.contains("h0") && !chain.contains("fork"));
+        }
+        drop((chain, reopened, db));
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_concurrent_appends_extend_a_tip_once() {
+        let (db, path) = temp_db("concurrent");
+        let chain = ChainHandle::open(db.clone()).unwrap();
+        chain.append(&block(GENESIS_HASH, "h0")).unwrap();
+        let appended: Vec<bool> = (0..4)
+            .map(|writer| {
+                let chain = chain.clone();
+                std::thread::spawn(move || chain.append(&block("h0", &format!("w{}", writer))).is_ok())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!(appended.iter().filter(|ok| **ok).count(), 1);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.height_of(&chain.head().unwrap().hash), Some(1));
+        drop((chain, db));
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
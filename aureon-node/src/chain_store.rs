@@ -0,0 +1,741 @@
+//! Persistent Chain Store
+//!
+//! Stores committed blocks in the node database so the chain survives
+//! restarts, and replays them on startup to rebuild account state and the
+//! state trie. A store bootstrapped from a snapshot starts at the snapshot's
+//! checkpoint block instead of genesis.
+//!
+//! Unless the node runs as an archive, blocks older than the retention
+//! window that precede the latest snapshot are pruned: state is rebuilt
+//! from the snapshot, so they are no longer needed. Checkpoint blocks are
+//! kept so snapshots taken at them can still be verified and served.
+
+use crate::config::PruningMode;
+use crate::db::{Batch, Column, Db};
+use crate::emission::{self, EmissionSchedule};
+use crate::mpt::MerklePatriciaTrie;
+use crate::nft;
+use crate::snapshot::{self, StateSnapshot};
+use crate::state_cache::AccountCache;
+use crate::state_processor::StateProcessor;
+use crate::storage_rent;
+use crate::bridge;
+use crate::multisig;
+use crate::timelock;
+use crate::token;
+use crate::types::{Block, TransactionPayload, TransactionReceipt};
+use bincode::config::standard;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+const HEIGHT_KEY: &[u8] = b"chain:height";
+const BLOCK_PREFIX: &[u8] = b"chain:block:";
+const BASE_KEY: &[u8] = b"chain:base";
+const PRUNED_KEY: &[u8] = b"chain:pruned";
+const GENESIS_KEY: &[u8] = b"chain:genesis";
+
+/// Parent hash of the first block of a chain
+pub const GENESIS_HASH: &str = "GENESIS";
+
+/// Outcome of replaying the stored chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaySummary {
+    pub blocks_replayed: u64,
+    pub state_root: Vec<u8>,
+    /// Receipts of every replayed transaction, in chain order
+    pub receipts: Vec<TransactionReceipt>,
+}
+
+/// Block whose recomputed state root differs from the one in its header
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub height: u64,
+    pub block_hash: String,
+    /// Which header root differs: "pre" or "post"
+    pub root: &'static str,
+    pub expected: Vec<u8>,
+    pub computed: Vec<u8>,
+}
+
+/// Outcome of re-executing stored blocks to audit their state roots
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditReport {
+    pub from: u64,
+    pub to: u64,
+    /// Blocks whose roots matched their headers
+    pub blocks_verified: u64,
+    /// First diverging block; blocks after it are not executed
+    pub divergence: Option<Divergence>,
+}
+
+/// Blocks and bytes discarded by a pruning pass
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PruneStats {
+    pub blocks: u64,
+    pub bytes: u64,
+}
+
+/// Block storage on top of the node database
+pub struct ChainStore<'a> {
+    db: &'a Db,
+    emission: Option<EmissionSchedule>,
+    cache: Option<Arc<AccountCache>>,
+    /// State besides the account balances that the chain starts with
+    genesis_state: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a> ChainStore<'a> {
+    pub fn new(db: &'a Db) -> Self {
+        Self { db, emission: None, cache: None, genesis_state: Vec::new() }
+    }
+
+    /// Balance cache to invalidate and refill when state is rebuilt
+    pub fn with_cache(mut self, cache: Option<Arc<AccountCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Mint block rewards following `schedule` when replaying blocks
+    pub fn with_emission(mut self, schedule: Option<EmissionSchedule>) -> Self {
+        self.emission = schedule;
+        self
+    }
+
+    /// Start replays from genesis with `entries` in state besides the genesis balances
+    pub fn with_genesis_state(mut self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        self.genesis_state = entries;
+        self
+    }
+
+    fn block_key(height: u64) -> Vec<u8> {
+        let mut key = BLOCK_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Height of the first stored block; non-zero after a snapshot bootstrap
+    pub fn base(&self) -> u64 {
+        self.db
+            .get_cf(Column::Blocks, BASE_KEY)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0)
+    }
+
+    /// Height of the first block of the unpruned history
+    ///
+    /// Below it only checkpoint blocks remain.
+    pub fn history_start(&self) -> u64 {
+        self.db
+            .get_cf(Column::Blocks, PRUNED_KEY)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0)
+            .max(self.base())
+    }
+
+    /// Height of the next block to be committed
+    pub fn len(&self) -> u64 {
+        self.db
+            .get_cf(Column::Blocks, HEIGHT_KEY)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the block stored at `height` (0-based)
+    pub fn get_block(&self, height: u64) -> Result<Option<Block>, String> {
+        if height < self.base() || height >= self.len() {
+            return Ok(None);
+        }
+        let Some(bytes) = self.db.get_cf(Column::Blocks, &Self::block_key(height)) else {
+            if height < self.history_start() {
+                return Ok(None);
+            }
+            return Err(format!("Block {} missing from store", height));
+        };
+        let (block, _) = bincode::decode_from_slice(&bytes, standard())
+            .map_err(|e| format!("Failed to decode block {}: {}", height, e))?;
+        Ok(Some(block))
+    }
+
+    /// Most recently committed block
+    pub fn tip(&self) -> Result<Option<Block>, String> {
+        match self.len() {
+            0 => Ok(None),
+            len => self.get_block(len - 1),
+        }
+    }
+
+    /// Check that the store belongs to the chain with `genesis_hash`
+    ///
+    /// An empty store is claimed for that chain; a store holding blocks of
+    /// another chain, or blocks from before it was claimed, is rejected.
+    pub fn check_genesis(&self, genesis_hash: &str) -> Result<(), String> {
+        match self.db.get_cf(Column::Blocks, GENESIS_KEY) {
+            Some(stored) if stored == genesis_hash.as_bytes() => Ok(()),
+            Some(stored) => Err(format!(
+                "Database belongs to chain with genesis {}, not {}",
+                String::from_utf8_lossy(&stored),
+                genesis_hash
+            )),
+            None if self.is_empty() => {
+                self.db.put_cf(Column::Blocks, GENESIS_KEY, genesis_hash.as_bytes());
+                Ok(())
+            }
+            None => Err("Database holds blocks of an unknown chain".to_string()),
+        }
+    }
+
+    /// Append a block, checking that it extends the current tip
+    ///
+    /// The block and the height marker are written in one batch, so a
+    /// crash leaves either the previous or the extended chain.
+    pub fn append_block(&self, block: &Block) -> Result<u64, String> {
+        if let Some(tip) = self.tip()?
+            && block.previous_hash != tip.hash
+        {
+            return Err(format!("Block {} does not extend tip {}", block.hash, tip.hash));
+        }
+
+        let height = self.len();
+        let bytes = bincode::encode_to_vec(block, standard())
+            .map_err(|e| format!("Failed to encode block: {}", e))?;
+        let mut batch = Batch::default();
+        batch.put(Column::Blocks, &Self::block_key(height), &bytes);
+        batch.put(Column::Blocks, HEIGHT_KEY, &(height + 1).to_be_bytes());
+        self.db.write(batch);
+        Ok(height)
+    }
+
+    /// Start an empty store at the checkpoint block of `snapshot`
+    pub fn import_snapshot(&self, snapshot: &StateSnapshot) -> Result<(), String> {
+        if !self.is_empty() {
+            return Err("Cannot import a snapshot into a non-empty chain store".to_string());
+        }
+        let bytes = bincode::encode_to_vec(&snapshot.block, standard())
+            .map_err(|e| format!("Failed to encode block: {}", e))?;
+        let mut batch = Batch::default();
+        batch.put(Column::Blocks, &Self::block_key(snapshot.height), &bytes);
+        batch.put(Column::Blocks, BASE_KEY, &snapshot.height.to_be_bytes());
+        batch.put(Column::Blocks, HEIGHT_KEY, &(snapshot.height + 1).to_be_bytes());
+        self.db.write(batch);
+        Ok(())
+    }
+
+    /// Load every block from the start of the unpruned history, verifying
+    /// parent-hash continuity
+    pub fn load_chain(&self) -> Result<Vec<Block>, String> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for height in self.history_start()..self.len() {
+            let block = self
+                .get_block(height)?
+                .ok_or_else(|| format!("Block {} missing from store", height))?;
+            if let Some(parent) = blocks.last()
+                && block.previous_hash != parent.hash
+            {
+                return Err(format!(
+                    "Chain broken at height {}: expected parent {}, found {}",
+                    height, parent.hash, block.previous_hash
+                ));
+            }
+            blocks.push(block);
+        }
+        Ok(blocks)
+    }
+
+    /// Rebuild account balances and the state trie from genesis by
+    /// re-applying every stored block
+    pub fn replay(
+        &self,
+        genesis: &HashMap<String, u64>,
+        trie: &mut MerklePatriciaTrie,
+    ) -> Result<ReplaySummary, String> {
+        if self.history_start() > 0 {
+            return Err("Chain store starts at a snapshot; replay from the snapshot instead".to_string());
+        }
+        let blocks = self.load_chain()?;
+        self.rebuild(self.genesis_base(genesis), &blocks, 0, trie)
+    }
+
+    /// Re-execute the stored blocks through `to` on `scratch`, an empty
+    /// database, and compare the state roots of blocks `from..=to` with
+    /// the roots in their headers
+    ///
+    /// Blocks before `from` are executed only to rebuild the state `from`
+    /// starts on. Unlike `replay` the node's own state is left untouched.
+    pub fn audit(
+        &self,
+        genesis: &HashMap<String, u64>,
+        scratch: &Db,
+        from: u64,
+        to: u64,
+    ) -> Result<AuditReport, String> {
+        if self.history_start() > 0 {
+            return Err(format!("Blocks before height {} are no longer stored", self.history_start()));
+        }
+        if from > to || to >= self.len() {
+            return Err(format!("Invalid range {}..={}: the chain has {} blocks", from, to, self.len()));
+        }
+        let mut trie = MerklePatriciaTrie::new();
+        for (key, value) in self.genesis_base(genesis) {
+            scratch.put(&key, &value);
+            trie.insert(key, value);
+        }
+        let mut processor = StateProcessor::new(scratch, &mut trie).with_emission(self.emission);
+        let mut report = AuditReport { from, to, blocks_verified: 0, divergence: None };
+        for height in 0..=to {
+            let block = self.get_block(height)?.ok_or_else(|| format!("Block {} missing from store", height))?;
+            let pre_state_root = processor.trie.root_hash();
+            let post_state_root = processor.apply_block(&block).state_root;
+            if height < from {
+                continue;
+            }
+            let roots = [("pre", &block.pre_state_root, pre_state_root), ("post", &block.post_state_root, post_state_root)];
+            // Headers of blocks produced without a state commitment carry empty roots
+            if let Some((root, expected, computed)) =
+                roots.into_iter().find(|(_, expected, computed)| !expected.is_empty() && *expected != computed)
+            {
+                report.divergence =
+                    Some(Divergence { height, block_hash: block.hash, root, expected: expected.clone(), computed });
+                break;
+            }
+            report.blocks_verified += 1;
+        }
+        Ok(report)
+    }
+
+    /// State after the block at `height`, rebuilt by executing the blocks up
+    /// to it from genesis on the `scratch` database
+    ///
+    /// Returns the executed blocks, oldest first, with the state trie.
+    pub fn state_at(
+        &self,
+        genesis: &HashMap<String, u64>,
+        scratch: &Db,
+        height: u64,
+    ) -> Result<(Vec<Block>, MerklePatriciaTrie), String> {
+        if self.history_start() > 0 {
+            return Err(format!("Blocks before height {} are no longer stored", self.history_start()));
+        }
+        if height >= self.len() {
+            return Err(format!("Invalid height {}: the chain has {} blocks", height, self.len()));
+        }
+        let mut trie = MerklePatriciaTrie::new();
+        for (key, value) in self.genesis_base(genesis) {
+            scratch.put(&key, &value);
+            trie.insert(key, value);
+        }
+        let mut processor = StateProcessor::new(scratch, &mut trie).with_emission(self.emission);
+        let mut blocks = Vec::new();
+        for height in 0..=height {
+            let block = self.get_block(height)?.ok_or_else(|| format!("Block {} missing from store", height))?;
+            processor.apply_block(&block);
+            blocks.push(block);
+        }
+        Ok((blocks, trie))
+    }
+
+    /// State the chain starts with: the `genesis` balances and the other genesis entries
+    fn genesis_base(&self, genesis: &HashMap<String, u64>) -> HashMap<Vec<u8>, Vec<u8>> {
+        genesis
+            .iter()
+            .map(|(account, balance)| (account.as_bytes().to_vec(), balance.to_le_bytes().to_vec()))
+            .chain(self.genesis_state.iter().cloned())
+            .collect()
+    }
+
+    /// Rebuild account balances and the state trie from `snapshot`,
+    /// re-applying only the stored blocks after it
+    pub fn replay_from_snapshot(
+        &self,
+        snapshot: &StateSnapshot,
+        trie: &mut MerklePatriciaTrie,
+    ) -> Result<ReplaySummary, String> {
+        let checkpoint = self
+            .get_block(snapshot.height)?
+            .ok_or_else(|| format!("Snapshot block {} missing from store", snapshot.height))?;
+        if checkpoint.hash != snapshot.block.hash {
+            return Err(format!("Snapshot at height {} does not match the stored chain", snapshot.height));
+        }
+        if snapshot.height < self.history_start() {
+            return Err(format!("Blocks after snapshot {} have been pruned", snapshot.height));
+        }
+        let blocks = self.load_chain()?;
+        let after = &blocks[(snapshot.height + 1 - self.history_start()) as usize..];
+        self.rebuild(snapshot.entries.iter().cloned().collect(), after, snapshot.height + 1, trie)
+    }
+
+    /// Delete blocks below `below` for which `keep` is false
+    ///
+    /// The history start is advanced last, so an interrupted pass leaves
+    /// the remaining blocks readable and is finished by the next one.
+    pub fn prune(&self, below: u64, keep: impl Fn(u64) -> bool) -> Result<PruneStats, String> {
+        let start = self.history_start();
+        let below = below.min(self.len());
+        let mut stats = PruneStats::default();
+        if below <= start {
+            return Ok(stats);
+        }
+        for height in (start..below).filter(|height| !keep(*height)) {
+            let key = Self::block_key(height);
+            if let Some(bytes) = self.db.get_cf(Column::Blocks, &key) {
+                self.db.delete_cf(Column::Blocks, &key);
+                stats.blocks += 1;
+                stats.bytes += (key.len() + bytes.len()) as u64;
+            }
+        }
+        self.db.put_cf(Column::Blocks, PRUNED_KEY, &below.to_be_bytes());
+        Ok(stats)
+    }
+
+    /// Prune blocks outside the retention window of `mode` that precede
+    /// `snapshot`, keeping every checkpoint of the snapshot `interval`
+    ///
+    /// Without a snapshot nothing is pruned, since the state could then
+    /// only be rebuilt by replaying from genesis.
+    pub fn prune_history(
+        &self,
+        mode: PruningMode,
+        snapshot: Option<&StateSnapshot>,
+        interval: u64,
+    ) -> Result<PruneStats, String> {
+        let (Some(retention), Some(snapshot)) = (mode.retention(), snapshot) else {
+            return Ok(PruneStats::default());
+        };
+        let below = self.len().saturating_sub(retention).min(snapshot.height);
+        self.prune(below, |height| height == snapshot.height || snapshot::is_checkpoint(height, interval))
+    }
+
+    /// Reset state to `base` and apply `blocks`, the first at `first_height`
+    fn rebuild(
+        &self,
+        base: HashMap<Vec<u8>, Vec<u8>>,
+        blocks: &[Block],
+        first_height: u64,
+        trie: &mut MerklePatriciaTrie,
+    ) -> Result<ReplaySummary, String> {
+        // Balances persisted by a previous run already include these blocks,
+        // so reset every touched account to its base value first
+        *trie = MerklePatriciaTrie::new();
+        if let Some(cache) = &self.cache {
+            cache.invalidate();
+        }
+        let mut touched: BTreeSet<&[u8]> = base.keys().map(Vec::as_slice).collect();
+        touched.extend(blocks.iter().map(|b| b.proposer.as_bytes()));
+        for tx in blocks.iter().flat_map(|b| &b.transactions) {
+            touched.insert(tx.from.as_bytes());
+            if let TransactionPayload::Transfer { to, .. } = &tx.payload {
+                touched.insert(to.as_bytes());
+            }
+        }
+        for key in touched {
+            match base.get(key) {
+                Some(value) => {
+                    self.db.put(key, value);
+                    trie.insert(key.to_vec(), value.clone());
+                }
+                None => self.db.delete(key),
+            }
+        }
+        // Token, NFT, emission and rent state is rebuilt by the blocks themselves
+        let prefixes = [
+            token::STATE_PREFIX,
+            nft::STATE_PREFIX,
+            emission::STATE_PREFIX,
+            timelock::STATE_PREFIX,
+            multisig::STATE_PREFIX,
+            bridge::STATE_PREFIX,
+            storage_rent::STATE_PREFIX,
+        ];
+        for prefix in prefixes {
+            for (key, _) in self.db.scan_prefix(prefix.as_bytes()) {
+                if !base.contains_key(&key) {
+                    self.db.delete(&key);
+                }
+            }
+        }
+
+        let mut processor =
+            StateProcessor::new(self.db, trie).with_emission(self.emission).with_cache(self.cache.clone());
+        let mut state_root = processor.trie.root_hash();
+        let mut receipts = Vec::new();
+        for (offset, block) in blocks.iter().enumerate() {
+            let execution = processor.apply_block(block);
+            if !block.post_state_root.is_empty() && block.post_state_root != execution.state_root {
+                return Err(format!("State root mismatch replaying block {}", first_height + offset as u64));
+            }
+            state_root = execution.state_root;
+            receipts.extend(execution.receipts);
+        }
+
+        Ok(ReplaySummary {
+            blocks_replayed: blocks.len() as u64,
+            state_root,
+            receipts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Transaction;
+
+    fn temp_db(name: &str) -> (Db, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("aureon_chain_store_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        (Db::open(path.to_str().unwrap()), path)
+    }
+
+    fn block(previous_hash: &str, hash: &str, transactions: Vec<Transaction>) -> Block {
+        Block {
+            transactions,
+            previous_hash: previous_hash.to_string(),
+            nonce: 0,
+            hash: hash.to_string(),
+            pre_state_root: vec![],
+            post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let (db, path) = temp_db("append");
+        {
+            let store = ChainStore::new(&db);
+            assert!(store.is_empty());
+            assert_eq!(store.append_block(&block("GENESIS", "h1", vec![])), Ok(0));
+            assert_eq!(store.append_block(&block("h1", "h2", vec![])), Ok(1));
+
+            let chain = store.load_chain().unwrap();
+            assert_eq!(chain.len(), 2);
+            assert_eq!(store.tip().unwrap().unwrap().hash, "h2");
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_check_genesis_claims_empty_store() {
+        let (db, path) = temp_db("genesis");
+        {
+            let store = ChainStore::new(&db);
+            assert_eq!(store.check_genesis("aa"), Ok(()));
+            assert_eq!(store.check_genesis("aa"), Ok(()));
+            assert!(store.check_genesis("bb").is_err());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_rejects_discontinuous_block() {
+        let (db, path) = temp_db("discontinuous");
+        {
+            let store = ChainStore::new(&db);
+            store.append_block(&block("GENESIS", "h1", vec![])).unwrap();
+            assert!(store.append_block(&block("other", "h2", vec![])).is_err());
+            assert_eq!(store.len(), 1);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_replay_rebuilds_state() {
+        let (db, path) = temp_db("replay");
+        {
+            let genesis = HashMap::from([("Alice".to_string(), 100u64)]);
+            let store = ChainStore::new(&db);
+            store
+                .append_block(&block("GENESIS", "h1", vec![Transaction::transfer("Alice".into(), "Bob".into(), 30)]))
+                .unwrap();
+            store
+                .append_block(&block("h1", "h2", vec![Transaction::transfer("Bob".into(), "Carol".into(), 10)]))
+                .unwrap();
+
+            // Replaying twice (as on two restarts) must not double-apply
+            for _ in 0..2 {
+                let mut trie = MerklePatriciaTrie::new();
+                let summary = store.replay(&genesis, &mut trie).unwrap();
+                assert_eq!(summary.blocks_replayed, 2);
+                assert_eq!(summary.state_root, trie.root_hash());
+
+                let processor = StateProcessor::new(&db, &mut trie);
+                assert_eq!(processor.get_balance("Alice"), 70);
+                assert_eq!(processor.get_balance("Bob"), 20);
+                assert_eq!(processor.get_balance("Carol"), 10);
+            }
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_replay_rebuilds_token_state() {
+        let (db, path) = temp_db("replay_tokens");
+        {
+            let create = crate::token::TokenOp::Create {
+                name: "Gold".into(),
+                symbol: "GLD".into(),
+                decimals: 0,
+                initial_supply: 100,
+                max_supply: None,
+            };
+            let store = ChainStore::new(&db);
+            store.append_block(&block("GENESIS", "h1", vec![Transaction::token("Alice".into(), create)])).unwrap();
+
+            let roots: Vec<_> = (0..2)
+                .map(|_| {
+                    let mut trie = MerklePatriciaTrie::new();
+                    let summary = store.replay(&HashMap::new(), &mut trie).unwrap();
+                    assert!(summary.receipts[0].success, "{:?}", summary.receipts[0].error);
+                    summary.state_root
+                })
+                .collect();
+            assert_eq!(roots[0], roots[1]);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_audit_reports_first_diverging_root() {
+        let (db, path) = temp_db("audit");
+        let (scratch, scratch_path) = temp_db("audit_scratch");
+        let (honest, honest_path) = temp_db("audit_honest");
+        {
+            let genesis = HashMap::from([("Alice".to_string(), 100u64)]);
+            let store = ChainStore::new(&db);
+            // Roots as an honest node computes them, with block 2's corrupted
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&honest, &mut trie);
+            processor.set_balance("Alice", 100);
+            let mut parent = GENESIS_HASH.to_string();
+            for (height, amount) in [10u64, 20, 30].into_iter().enumerate() {
+                let transfer = Transaction::transfer("Alice".into(), "Bob".into(), amount);
+                let mut next = block(&parent, &format!("h{}", height), vec![transfer]);
+                next.post_state_root = processor.apply_block(&next).state_root;
+                if height == 2 {
+                    next.post_state_root[0] ^= 1;
+                }
+                store.append_block(&next).unwrap();
+                parent = next.hash;
+            }
+
+            assert!(store.audit(&genesis, &scratch, 0, 3).is_err());
+            let report = store.audit(&genesis, &scratch, 1, 2).unwrap();
+            assert_eq!(report.blocks_verified, 1);
+            let divergence = report.divergence.unwrap();
+            assert_eq!((divergence.height, divergence.root, divergence.block_hash.as_str()), (2, "post", "h2"));
+            assert_ne!(divergence.expected, divergence.computed);
+        }
+        drop((db, scratch, honest));
+        for path in [path, scratch_path, honest_path] {
+            let _ = std::fs::remove_dir_all(path);
+        }
+    }
+
+    #[test]
+    fn test_replay_from_imported_snapshot() {
+        let (db, path) = temp_db("snapshot");
+        {
+            let mut state = MerklePatriciaTrie::new();
+            state.insert(b"Alice".to_vec(), 70u64.to_le_bytes().to_vec());
+            state.insert(b"Bob".to_vec(), 30u64.to_le_bytes().to_vec());
+            let mut checkpoint = block("h4", "h5", vec![]);
+            checkpoint.post_state_root = state.root_hash();
+            let snapshot = StateSnapshot::capture(5, &checkpoint, &state);
+
+            let store = ChainStore::new(&db);
+            store.import_snapshot(&snapshot).unwrap();
+            assert!(store.import_snapshot(&snapshot).is_err());
+            assert_eq!((store.base(), store.len()), (5, 6));
+            assert!(store.get_block(4).unwrap().is_none());
+            store
+                .append_block(&block("h5", "h6", vec![Transaction::transfer("Bob".into(), "Carol".into(), 10)]))
+                .unwrap();
+
+            let mut trie = MerklePatriciaTrie::new();
+            assert!(store.replay(&HashMap::new(), &mut trie).is_err());
+            let summary = store.replay_from_snapshot(&snapshot, &mut trie).unwrap();
+            assert_eq!(summary.blocks_replayed, 1);
+
+            let processor = StateProcessor::new(&db, &mut trie);
+            assert_eq!(processor.get_balance("Alice"), 70);
+            assert_eq!(processor.get_balance("Bob"), 20);
+            assert_eq!(processor.get_balance("Carol"), 10);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_prune_history_keeps_checkpoints_and_replays_from_snapshot() {
+        let (db, path) = temp_db("prune");
+        {
+            let store = ChainStore::new(&db);
+            let mut previous = "GENESIS".to_string();
+            for height in 0..10 {
+                let hash = format!("h{}", height);
+                store.append_block(&block(&previous, &hash, vec![])).unwrap();
+                previous = hash;
+            }
+            let trie = MerklePatriciaTrie::new();
+            let snapshot = StateSnapshot::capture(6, &store.get_block(6).unwrap().unwrap(), &trie);
+
+            assert_eq!(store.prune_history(PruningMode::Archive, Some(&snapshot), 3).unwrap().blocks, 0);
+            assert_eq!(store.prune_history(PruningMode::Blocks(2), None, 3).unwrap().blocks, 0);
+
+            // Retention reaches back to block 8, but only blocks before the snapshot go
+            let stats = store.prune_history(PruningMode::Blocks(2), Some(&snapshot), 3).unwrap();
+            assert_eq!(stats.blocks, 4);
+            assert!(stats.bytes > 0);
+            assert_eq!(store.history_start(), 6);
+            assert!(store.get_block(2).unwrap().is_none());
+            assert_eq!(store.get_block(3).unwrap().unwrap().hash, "h3");
+            assert_eq!(store.load_chain().unwrap().len(), 4);
+
+            let mut trie = MerklePatriciaTrie::new();
+            assert!(store.replay(&HashMap::new(), &mut trie).is_err());
+            assert_eq!(store.replay_from_snapshot(&snapshot, &mut trie).unwrap().blocks_replayed, 3);
+            assert_eq!(store.prune_history(PruningMode::Blocks(2), Some(&snapshot), 3).unwrap(), PruneStats::default());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_replay_detects_broken_chain() {
+        let (db, path) = temp_db("broken");
+        {
+            let store = ChainStore::new(&db);
+            store.append_block(&block("GENESIS", "h1", vec![])).unwrap();
+            // Simulate a corrupted write bypassing append_block
+            let bytes = bincode::encode_to_vec(block("bogus", "h2", vec![]), standard()).unwrap();
+            db.put_cf(Column::Blocks, &ChainStore::block_key(1), &bytes);
+            db.put_cf(Column::Blocks, HEIGHT_KEY, &2u64.to_be_bytes());
+
+            let mut trie = MerklePatriciaTrie::new();
+            assert!(store.replay(&HashMap::new(), &mut trie).unwrap_err().contains("Chain broken"));
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
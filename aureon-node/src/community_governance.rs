@@ -1,3 +1,5 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Community governance and voting system
@@ -5,6 +7,10 @@ use std::collections::HashMap;
 /// This module implements on-chain governance with proposals,
 /// voting mechanisms, and community participation.
 
+/// Why `GovernanceVote` transactions are rejected: their votes would not
+/// reach any node's tally until on-chain voting is wired to it
+pub const VOTE_TRANSACTIONS_UNSUPPORTED: &str = "Governance vote transactions are not supported yet";
+
 /// Proposal type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ProposalType {
@@ -13,10 +19,39 @@ pub enum ProposalType {
     FundAllocation,
     CommunitySplit,
     EmergencyPause,
+    AuthorityChange,
+}
+
+/// Runtime parameter set by an executed ParameterChange proposal
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ParameterUpdate {
+    /// Maximum total gas of the transactions in a block
+    BlockGasLimit(u64),
+    /// Minimum gas price accepted into the mempool
+    MinGasPrice(u64),
+    /// Annual staking reward rate as a decimal (0.05 = 5%)
+    StakingRewardRate(f64),
+}
+
+impl ParameterUpdate {
+    /// Reject values the node could not run with
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            ParameterUpdate::BlockGasLimit(limit) if limit < crate::fee_market::BASE_TX_GAS => Err(format!(
+                "Block gas limit {} is below intrinsic gas {}",
+                limit,
+                crate::fee_market::BASE_TX_GAS
+            )),
+            ParameterUpdate::StakingRewardRate(rate) if !(0.0..=1.0).contains(&rate) => {
+                Err(format!("Staking reward rate {} must be between 0 and 1", rate))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Vote choice
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
 pub enum VoteChoice {
     Yes,
     No,
@@ -34,6 +69,29 @@ pub enum ProposalStatus {
     Cancelled,
 }
 
+/// Code swap of a governed contract, applied by an executed ProtocolUpgrade proposal
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractUpgrade {
+    pub contract_address: String,
+    /// Replacement WASM bytecode
+    pub code: Vec<u8>,
+}
+
+/// PoA authority set change applied by an executed AuthorityChange proposal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AuthorityChange {
+    Add(String),
+    Remove(String),
+}
+
+/// Change the node applies when a proposal is executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProposalAction {
+    Parameter(ParameterUpdate),
+    ContractUpgrade(ContractUpgrade),
+    Authority(AuthorityChange),
+}
+
 /// A governance proposal
 #[derive(Debug, Clone)]
 pub struct Proposal {
@@ -46,6 +104,14 @@ pub struct Proposal {
     pub start_block: u64,
     pub end_block: u64,
     pub created_at: u64,
+    /// Parameter to change when a ParameterChange proposal is executed
+    pub parameter_update: Option<ParameterUpdate>,
+    /// Contract code to replace when a ProtocolUpgrade proposal is executed
+    pub contract_upgrade: Option<ContractUpgrade>,
+    /// Authority to add or remove when an AuthorityChange proposal is executed
+    pub authority_change: Option<AuthorityChange>,
+    /// Block at which a passed proposal is executed
+    pub execution_block: Option<u64>,
 }
 
 impl Proposal {
@@ -69,6 +135,10 @@ impl Proposal {
             start_block,
             end_block,
             created_at: 0,
+            parameter_update: None,
+            contract_upgrade: None,
+            authority_change: None,
+            execution_block: None,
         }
     }
 
@@ -126,6 +196,7 @@ pub struct VotingSystem {
     next_proposal_id: u64,
     voting_period: u64,
     quorum_percentage: u32,
+    execution_delay: u64,
 }
 
 impl VotingSystem {
@@ -137,9 +208,21 @@ impl VotingSystem {
             next_proposal_id: 1,
             voting_period,
             quorum_percentage,
+            execution_delay: 0,
         }
     }
 
+    /// Create a voting system from governance configuration
+    pub fn from_config(config: &GovernanceConfig) -> Self {
+        Self::new(config.voting_period, config.quorum_percentage).with_execution_delay(config.execution_delay)
+    }
+
+    /// Blocks between the end of voting and execution of a passed proposal
+    pub fn with_execution_delay(mut self, execution_delay: u64) -> Self {
+        self.execution_delay = execution_delay;
+        self
+    }
+
     /// Submit new proposal
     pub fn submit_proposal(
         &mut self,
@@ -165,6 +248,59 @@ impl VotingSystem {
         id
     }
 
+    /// Submit a ParameterChange proposal that applies `update` once executed
+    pub fn submit_parameter_change(
+        &mut self,
+        update: ParameterUpdate,
+        title: String,
+        description: String,
+        proposer: String,
+        start_block: u64,
+    ) -> Result<u64, String> {
+        update.validate()?;
+        let id = self.submit_proposal(ProposalType::ParameterChange, title, description, proposer, start_block);
+        if let Some(proposal) = self.proposals.get_mut(&id) {
+            proposal.parameter_update = Some(update);
+        }
+        Ok(id)
+    }
+
+    /// Submit a ProtocolUpgrade proposal that replaces a governed contract's code once executed
+    pub fn submit_contract_upgrade(
+        &mut self,
+        upgrade: ContractUpgrade,
+        title: String,
+        description: String,
+        proposer: String,
+        start_block: u64,
+    ) -> u64 {
+        let id = self.submit_proposal(ProposalType::ProtocolUpgrade, title, description, proposer, start_block);
+        if let Some(proposal) = self.proposals.get_mut(&id) {
+            proposal.contract_upgrade = Some(upgrade);
+        }
+        id
+    }
+
+    /// Submit an AuthorityChange proposal that adds or removes a PoA authority once executed
+    pub fn submit_authority_change(
+        &mut self,
+        change: AuthorityChange,
+        title: String,
+        description: String,
+        proposer: String,
+        start_block: u64,
+    ) -> Result<u64, String> {
+        let (AuthorityChange::Add(authority) | AuthorityChange::Remove(authority)) = &change;
+        if authority.is_empty() {
+            return Err("Authority name must not be empty".to_string());
+        }
+        let id = self.submit_proposal(ProposalType::AuthorityChange, title, description, proposer, start_block);
+        if let Some(proposal) = self.proposals.get_mut(&id) {
+            proposal.authority_change = Some(change);
+        }
+        Ok(id)
+    }
+
     /// Cast vote
     pub fn cast_vote(
         &mut self,
@@ -274,6 +410,7 @@ impl VotingSystem {
         let proposal = self.proposals.get_mut(&proposal_id).unwrap();
         if has_quorum && approval > 0.5 {
             proposal.mark_passed();
+            proposal.execution_block = Some(proposal.end_block + self.execution_delay);
         } else {
             proposal.mark_failed();
         }
@@ -291,6 +428,38 @@ impl VotingSystem {
         proposal.execute()
     }
 
+    /// Execute every passed proposal scheduled at or before `height`
+    ///
+    /// Proposals run in id order; returns the parameter updates, contract
+    /// upgrades and authority changes the node must apply.
+    pub fn execute_due(&mut self, height: u64) -> Vec<(u64, ProposalAction)> {
+        let mut due: Vec<u64> = self
+            .proposals
+            .values()
+            .filter(|p| p.status == ProposalStatus::Passed && p.execution_block.is_some_and(|block| block <= height))
+            .map(|p| p.id)
+            .collect();
+        due.sort_unstable();
+
+        let mut actions = Vec::new();
+        for id in due {
+            let proposal = self.proposals.get_mut(&id).unwrap();
+            if proposal.execute().is_err() {
+                continue;
+            }
+            if let Some(update) = proposal.parameter_update {
+                actions.push((id, ProposalAction::Parameter(update)));
+            }
+            if let Some(upgrade) = proposal.contract_upgrade.take() {
+                actions.push((id, ProposalAction::ContractUpgrade(upgrade)));
+            }
+            if let Some(change) = proposal.authority_change.take() {
+                actions.push((id, ProposalAction::Authority(change)));
+            }
+        }
+        actions
+    }
+
     /// Get all proposals
     pub fn all_proposals(&self) -> Vec<&Proposal> {
         self.proposals.values().collect()
@@ -670,4 +839,78 @@ mod tests {
         let total = system.get_total_votes(id);
         assert_eq!(total, 1000);
     }
+
+    #[test]
+    fn test_parameter_change_executes_at_scheduled_block() {
+        let mut system = VotingSystem::new(100, 40).with_execution_delay(10);
+        let rejected = system.submit_parameter_change(
+            ParameterUpdate::BlockGasLimit(1),
+            "Tiny blocks".to_string(),
+            "Desc".to_string(),
+            "proposer".to_string(),
+            0,
+        );
+        assert!(rejected.is_err());
+
+        let id = system
+            .submit_parameter_change(
+                ParameterUpdate::MinGasPrice(5),
+                "Raise gas floor".to_string(),
+                "Desc".to_string(),
+                "proposer".to_string(),
+                0,
+            )
+            .unwrap();
+        system.proposals.get_mut(&id).unwrap().activate();
+        system.cast_vote("voter1".to_string(), id, VoteChoice::Yes, 60).ok();
+        system.finalize_proposal(id, 100).unwrap();
+        assert_eq!(system.get_proposal(id).unwrap().execution_block, Some(110));
+
+        assert!(system.execute_due(109).is_empty());
+        assert_eq!(
+            system.execute_due(110),
+            vec![(id, ProposalAction::Parameter(ParameterUpdate::MinGasPrice(5)))]
+        );
+        assert_eq!(system.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        assert!(system.execute_due(111).is_empty());
+    }
+
+    #[test]
+    fn test_contract_upgrade_proposal_yields_upgrade_once() {
+        let mut system = VotingSystem::new(100, 40);
+        let upgrade = ContractUpgrade { contract_address: "0xtoken".to_string(), code: vec![1, 2] };
+        let id = system.submit_contract_upgrade(
+            upgrade.clone(),
+            "Fix token".to_string(),
+            "Desc".to_string(),
+            "proposer".to_string(),
+            0,
+        );
+        assert_eq!(system.get_proposal(id).unwrap().proposal_type, ProposalType::ProtocolUpgrade);
+        system.proposals.get_mut(&id).unwrap().activate();
+        system.cast_vote("voter1".to_string(), id, VoteChoice::Yes, 60).ok();
+        system.finalize_proposal(id, 100).unwrap();
+
+        assert_eq!(system.execute_due(100), vec![(id, ProposalAction::ContractUpgrade(upgrade))]);
+        assert!(system.execute_due(101).is_empty());
+    }
+
+    #[test]
+    fn test_authority_change_proposal_yields_change_once() {
+        let mut system = VotingSystem::new(100, 40);
+        let change = AuthorityChange::Add("dave".to_string());
+        let id = system
+            .submit_authority_change(change.clone(), "Add dave".to_string(), "Desc".to_string(), "proposer".to_string(), 0)
+            .unwrap();
+        assert_eq!(system.get_proposal(id).unwrap().proposal_type, ProposalType::AuthorityChange);
+        system.proposals.get_mut(&id).unwrap().activate();
+        system.cast_vote("voter1".to_string(), id, VoteChoice::Yes, 60).ok();
+        system.finalize_proposal(id, 100).unwrap();
+
+        assert_eq!(system.execute_due(100), vec![(id, ProposalAction::Authority(change))]);
+        assert!(system.execute_due(101).is_empty());
+        assert!(system
+            .submit_authority_change(AuthorityChange::Remove(String::new()), "t".to_string(), "d".to_string(), "p".to_string(), 0)
+            .is_err());
+    }
 }
@@ -1,9 +1,13 @@
 use crate::consensus::ConsensusType;
+use aureon_core::chain_spec::ChainSpec;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
+/// Configuration file read at startup and on reload
+pub const CONFIG_PATH: &str = "config.toml";
+
 /// Main configuration structure for Aureon blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AureonConfig {
@@ -14,6 +18,28 @@ pub struct AureonConfig {
     pub state: StateConfig,
     pub validator: ValidatorConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub fee_market: FeeMarketConfig,
+    #[serde(default)]
+    pub mempool: MempoolConfig,
+    #[serde(default)]
+    pub signer: SignerConfig,
+    #[serde(default)]
+    pub slashing: SlashingConfig,
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    #[serde(default)]
+    pub epoch: EpochConfig,
+    #[serde(default)]
+    pub sharding: ShardingConfig,
+    #[serde(default)]
+    pub spv: SpvConfig,
+    #[serde(default)]
+    pub economy: EconomyConfig,
+    #[serde(default)]
+    pub bridge: BridgeConfig,
+    #[serde(default)]
+    pub metrics_history: MetricsHistoryConfig,
 }
 
 /// Consensus engine configuration
@@ -21,14 +47,59 @@ pub struct AureonConfig {
 pub struct ConsensusConfig {
     /// Consensus type: "pow", "pos", or "poa"
     pub engine: String,
-    /// PoW difficulty (1-256, higher = harder)
+    /// Initial PoW difficulty as leading zero hex digits of the block hash
+    /// (1-255, higher = harder); retargeted every block from there
     pub pow_difficulty: u8,
+    /// Block time the PoW difficulty is retargeted towards, in milliseconds
+    #[serde(default = "default_pow_target_block_time_ms")]
+    pub pow_target_block_time_ms: u64,
     /// Minimum stake for PoS validators (tokens)
     pub pos_min_stake: u64,
     /// Number of PoS validators
     pub pos_validator_count: usize,
     /// PoA authorized validators
     pub poa_validators: Vec<String>,
+    /// Attach state transition proofs to produced blocks and validate
+    /// blocks carrying one from the proof instead of re-executing them
+    #[serde(default)]
+    pub state_proofs: bool,
+    /// Hex-encoded Ed25519 keys validators sign their block proposals and finality votes with
+    #[serde(default)]
+    pub validator_keys: HashMap<String, String>,
+    /// Slot length in milliseconds; the slot leader produces one block per slot
+    #[serde(default = "default_block_interval_ms")]
+    pub block_interval_ms: u64,
+    /// Maximum transactions taken from the mempool into a block
+    #[serde(default = "default_max_block_transactions")]
+    pub max_block_transactions: usize,
+    /// Share (percent) of each block's transaction slots and gas kept for
+    /// slashing evidence and system and governance transactions
+    #[serde(default = "default_reserved_block_percent")]
+    pub reserved_block_percent: u8,
+    /// When blocks are sealed: "slots", "instant" (as soon as transactions
+    /// arrive) or "manual" (only through POST /dev/mine)
+    #[serde(default = "default_seal_mode")]
+    pub seal_mode: String,
+}
+
+fn default_seal_mode() -> String {
+    "slots".to_string()
+}
+
+fn default_pow_target_block_time_ms() -> u64 {
+    crate::consensus::difficulty::DEFAULT_TARGET_BLOCK_TIME_MS
+}
+
+fn default_block_interval_ms() -> u64 {
+    5000
+}
+
+fn default_max_block_transactions() -> usize {
+    crate::block_producer::DEFAULT_MAX_BLOCK_TRANSACTIONS
+}
+
+fn default_reserved_block_percent() -> u8 {
+    crate::block_producer::DEFAULT_RESERVED_BLOCK_PERCENT
 }
 
 /// Network configuration
@@ -40,6 +111,46 @@ pub struct NetworkConfig {
     pub listen_port: u16,
     /// Bootstrap peers to connect to
     pub bootstrap_peers: Vec<String>,
+    /// File holding the node's identity key (created on first start)
+    #[serde(default = "default_node_key_path")]
+    pub node_key_path: String,
+    /// Maximum simultaneous peer connections
+    #[serde(default = "default_max_peers")]
+    pub max_peers: usize,
+    /// Seconds a peer's address is refused after its score falls to the ban threshold
+    #[serde(default = "default_ban_duration_secs")]
+    pub ban_duration_secs: u64,
+    /// Encrypt all peer sessions; disable only on private networks, where
+    /// sessions between nodes that both allow it carry authenticated plaintext
+    #[serde(default = "default_require_encryption")]
+    pub require_encryption: bool,
+    /// DNS names resolving to peer addresses, as "host:port", queried when
+    /// the node has too few peers
+    #[serde(default)]
+    pub dns_seeds: Vec<String>,
+    /// Connections the node keeps dialing known peers to reach
+    #[serde(default = "default_target_peers")]
+    pub target_peers: usize,
+}
+
+fn default_node_key_path() -> String {
+    "aureon_node_key".to_string()
+}
+
+fn default_max_peers() -> usize {
+    crate::network::DEFAULT_MAX_PEERS
+}
+
+fn default_ban_duration_secs() -> u64 {
+    crate::network::reputation::DEFAULT_BAN_DURATION_SECS
+}
+
+fn default_require_encryption() -> bool {
+    true
+}
+
+fn default_target_peers() -> usize {
+    crate::network::DEFAULT_TARGET_PEERS
 }
 
 /// REST API configuration
@@ -55,6 +166,47 @@ pub struct ApiConfig {
     pub websocket_enabled: bool,
     /// WebSocket port
     pub websocket_port: u16,
+    /// Users allowed to call protected endpoints
+    #[serde(default)]
+    pub users: Vec<ApiUserConfig>,
+    /// File API access checks are appended to
+    #[serde(default = "default_access_log_path")]
+    pub access_log_path: String,
+    /// Requests per second each client IP may sustain (0 disables rate limiting)
+    #[serde(default = "default_rate_limit_per_sec")]
+    pub rate_limit_per_sec: u32,
+    /// Requests a client IP may send at once before being throttled
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
+    /// Client IPs exempt from rate limiting
+    #[serde(default = "default_rate_limit_whitelist")]
+    pub rate_limit_whitelist: Vec<String>,
+}
+
+/// API user authenticated by a token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiUserConfig {
+    pub id: String,
+    /// Role name: admin, operator, node, validator, user or guest
+    pub role: String,
+    /// Sent as `Authorization: Bearer <token>` or `X-API-Key: <token>`
+    pub token: String,
+}
+
+fn default_access_log_path() -> String {
+    "api_access.log".to_string()
+}
+
+fn default_rate_limit_per_sec() -> u32 {
+    20
+}
+
+fn default_rate_limit_burst() -> u32 {
+    50
+}
+
+fn default_rate_limit_whitelist() -> Vec<String> {
+    vec!["127.0.0.1".to_string(), "::1".to_string()]
 }
 
 /// Database configuration
@@ -66,6 +218,79 @@ pub struct DatabaseConfig {
     pub cache_size_mb: usize,
     /// Enable compression
     pub compression: bool,
+    /// Block history retention: "archive", "default" or a number of blocks
+    #[serde(default)]
+    pub pruning: PruningMode,
+    /// Account balances kept in the in-memory state cache
+    #[serde(default = "default_account_cache_size")]
+    pub account_cache_size: usize,
+}
+
+fn default_account_cache_size() -> usize {
+    10_000
+}
+
+/// Blocks kept when pruning is "default"
+pub const DEFAULT_PRUNING_RETENTION: u64 = 10_000;
+
+/// How much block history the node keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "PruningSetting", into = "PruningSetting")]
+pub enum PruningMode {
+    /// Keep every block and archive the state after each one
+    Archive,
+    /// Keep the last `DEFAULT_PRUNING_RETENTION` blocks
+    #[default]
+    Default,
+    /// Keep the last N blocks
+    Blocks(u64),
+}
+
+impl PruningMode {
+    /// Number of recent blocks to keep (None keeps everything)
+    pub fn retention(&self) -> Option<u64> {
+        match self {
+            PruningMode::Archive => None,
+            PruningMode::Default => Some(DEFAULT_PRUNING_RETENTION),
+            PruningMode::Blocks(blocks) => Some(*blocks),
+        }
+    }
+}
+
+/// `pruning` as written in config.toml
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PruningSetting {
+    Blocks(u64),
+    Named(String),
+}
+
+impl TryFrom<PruningSetting> for PruningMode {
+    type Error = String;
+
+    fn try_from(setting: PruningSetting) -> Result<Self, Self::Error> {
+        match setting {
+            PruningSetting::Blocks(blocks) => Ok(PruningMode::Blocks(blocks)),
+            PruningSetting::Named(name) => match name.as_str() {
+                "archive" => Ok(PruningMode::Archive),
+                "default" => Ok(PruningMode::Default),
+                other => Err(format!(
+                    "Invalid pruning mode '{}': expected \"archive\", \"default\" or a number of blocks",
+                    other
+                )),
+            },
+        }
+    }
+}
+
+impl From<PruningMode> for PruningSetting {
+    fn from(mode: PruningMode) -> Self {
+        match mode {
+            PruningMode::Archive => PruningSetting::Named("archive".to_string()),
+            PruningMode::Default => PruningSetting::Named("default".to_string()),
+            PruningMode::Blocks(blocks) => PruningSetting::Blocks(blocks),
+        }
+    }
 }
 
 /// Genesis state configuration
@@ -86,6 +311,41 @@ pub struct ValidatorConfig {
     pub operator_address: String,
 }
 
+/// Where the validator's block signatures come from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignerConfig {
+    /// "node-key" (the network identity key), "keystore" or "remote"
+    pub backend: String,
+    /// Encrypted validator key used by the keystore backend
+    pub keystore_path: String,
+    /// File holding the keystore password; AUREON_KEYSTORE_PASSWORD is read when empty
+    pub password_file: String,
+    /// Base URL of the remote signer; the protocol is authenticated but runs
+    /// over plain HTTP, so keep the signer on a private network or behind a TLS tunnel
+    pub remote_url: String,
+    /// File holding the hex-encoded secret shared with the remote signer
+    pub remote_secret_file: String,
+    /// Timeout of a single request to the remote signer
+    pub remote_timeout_ms: u64,
+    /// Slashing-protection database recording every slot the validator signed for
+    pub protection_path: String,
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        SignerConfig {
+            backend: "node-key".to_string(),
+            keystore_path: "validator_keystore.json".to_string(),
+            password_file: String::new(),
+            remote_url: String::new(),
+            remote_secret_file: String::new(),
+            remote_timeout_ms: 2_000,
+            protection_path: "slashing_protection.json".to_string(),
+        }
+    }
+}
+
 /// Logging configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoggingConfig {
@@ -95,6 +355,348 @@ pub struct LoggingConfig {
     pub consensus_debug: bool,
     /// Enable network trace logs
     pub network_trace: bool,
+    /// Log output format: "text" or "json"
+    #[serde(default = "default_log_format")]
+    pub format: String,
+    /// Levels overriding `level` for individual log targets, keyed by
+    /// module path (e.g. "aureon_node::network")
+    #[serde(default)]
+    pub modules: HashMap<String, String>,
+}
+
+fn default_log_format() -> String {
+    "text".to_string()
+}
+
+/// Fee market configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeMarketConfig {
+    /// Minimum gas price accepted into the mempool
+    pub min_gas_price: u64,
+    /// Maximum total gas of the transactions in a block
+    pub block_gas_limit: u64,
+}
+
+impl Default for FeeMarketConfig {
+    fn default() -> Self {
+        FeeMarketConfig {
+            min_gas_price: 1,
+            block_gas_limit: 10_000_000,
+        }
+    }
+}
+
+/// Block reward emission schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomyConfig {
+    /// Reward minted to the proposer of each block before the first halving
+    pub initial_block_reward: u64,
+    /// Blocks between reward halvings
+    pub halving_interval: u64,
+    /// Cap on the total supply (genesis balances plus minted rewards)
+    pub max_supply: u64,
+    /// Burn an EIP-1559 style base fee from every transaction and pay
+    /// proposers only the tip above it
+    #[serde(default)]
+    pub fee_burning: bool,
+    /// Base fee per unit of gas of the first block
+    #[serde(default = "default_initial_base_fee")]
+    pub initial_base_fee: u64,
+    /// Gas per block the base fee steers towards
+    #[serde(default = "default_target_block_gas")]
+    pub target_block_gas: u64,
+    /// Reward the miners of stale PoW blocks included as ommers, and the
+    /// proposers including them
+    #[serde(default)]
+    pub ommer_rewards: bool,
+    /// Charge accounts and contracts rent for the state they occupy, and
+    /// prune the state of those that stop paying
+    #[serde(default)]
+    pub storage_rent: bool,
+    /// Rent per byte of state per block
+    #[serde(default = "default_rent_per_byte")]
+    pub rent_per_byte: u64,
+    /// Blocks between rent collections; each collection charges for the whole period
+    #[serde(default = "default_rent_period")]
+    pub rent_period: u64,
+    /// Blocks an account stays expired, and can still pay its arrears, before its state is pruned
+    #[serde(default = "default_rent_grace_period")]
+    pub rent_grace_period: u64,
+}
+
+fn default_initial_base_fee() -> u64 {
+    1
+}
+
+fn default_rent_per_byte() -> u64 {
+    1
+}
+
+fn default_rent_period() -> u64 {
+    1000
+}
+
+fn default_rent_grace_period() -> u64 {
+    10_000
+}
+
+fn default_target_block_gas() -> u64 {
+    5_000_000
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        EconomyConfig {
+            initial_block_reward: 100,
+            halving_interval: 500_000,
+            max_supply: 369_000_000,
+            fee_burning: false,
+            initial_base_fee: default_initial_base_fee(),
+            target_block_gas: default_target_block_gas(),
+            ommer_rewards: false,
+            storage_rent: false,
+            rent_per_byte: default_rent_per_byte(),
+            rent_period: default_rent_period(),
+            rent_grace_period: default_rent_grace_period(),
+        }
+    }
+}
+
+/// Mempool configuration: future-nonce queue, replacement and per-account limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MempoolConfig {
+    /// Seconds a queued transaction may wait for its nonce gap to fill
+    pub queued_ttl_secs: u64,
+    /// Maximum queued transactions per sender
+    pub max_queued_per_account: usize,
+    /// Maximum queued transactions in the mempool, across all senders
+    pub max_queued: usize,
+    /// Maximum pending transactions per sender
+    pub max_pending_per_account: usize,
+    /// Gas price increase (percent) a transaction needs to replace one with the same nonce
+    pub price_bump_percent: u64,
+    /// Maximum pending transactions in the mempool
+    pub max_size: usize,
+    /// Seconds between saves of the pool to the database (0 saves only at shutdown)
+    pub persist_interval_secs: u64,
+    /// Seconds a pending transaction may wait for inclusion before it expires
+    pub pending_ttl_secs: u64,
+    /// Seconds between revalidations of the pool against the current state (0 disables them)
+    pub revalidate_interval_secs: u64,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        MempoolConfig {
+            queued_ttl_secs: 600,
+            max_queued_per_account: 64,
+            max_queued: crate::mempool::DEFAULT_MEMPOOL_CAPACITY,
+            max_pending_per_account: 16,
+            price_bump_percent: 10,
+            max_size: crate::mempool::DEFAULT_MEMPOOL_CAPACITY,
+            persist_interval_secs: 30,
+            pending_ttl_secs: 10_800,
+            revalidate_interval_secs: 30,
+        }
+    }
+}
+
+/// Slashing configuration (penalties in basis points of bonded stake)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlashingConfig {
+    /// Penalty for signing two different blocks in the same round
+    pub double_sign_penalty_bps: u32,
+    /// Penalty for missing too many commits
+    pub downtime_penalty_bps: u32,
+    /// Share of each penalty that is burned; the rest goes to the reward pool
+    pub burn_bps: u32,
+    /// Number of finalized heights considered for downtime
+    pub downtime_window: u64,
+    /// Minimum commits a validator must sign per window
+    pub min_signed_per_window: u64,
+}
+
+impl Default for SlashingConfig {
+    fn default() -> Self {
+        SlashingConfig {
+            double_sign_penalty_bps: 500,
+            downtime_penalty_bps: 100,
+            burn_bps: 5000,
+            downtime_window: 100,
+            min_signed_per_window: 50,
+        }
+    }
+}
+
+/// State snapshot and fast-sync configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Take a state snapshot every `interval` blocks (0 disables snapshots)
+    pub interval: u64,
+    /// Bootstrap an empty node from a peer's snapshot instead of genesis;
+    /// needs a `trusted_checkpoint`
+    pub fast_sync: bool,
+    /// Seconds to wait for peers to provide a snapshot before replaying from genesis
+    pub sync_timeout_secs: u64,
+    /// Checkpoint, from a source other than the peers, that a fast-synced
+    /// snapshot must match
+    #[serde(default)]
+    pub trusted_checkpoint: Option<TrustedCheckpoint>,
+}
+
+/// Checkpoint block a fast-synced snapshot is checked against
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustedCheckpoint {
+    pub height: u64,
+    pub block_hash: String,
+    /// Hex-encoded state root after the checkpoint block
+    pub state_root: String,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        SnapshotConfig {
+            interval: 1000,
+            fast_sync: false,
+            sync_timeout_secs: 30,
+            trusted_checkpoint: None,
+        }
+    }
+}
+
+/// Validator set rotation configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EpochConfig {
+    /// Blocks per epoch; the validator set is recomputed at the end of each (0 disables rotation)
+    pub length: u64,
+    /// Blocks a validator leaving the set, or undelegated stake, stays bonded before it is released
+    pub unbonding_period: u64,
+    /// Share of delegators' epoch rewards validators keep as commission, in basis points
+    #[serde(default = "default_commission_bps")]
+    pub commission_bps: u32,
+}
+
+fn default_commission_bps() -> u32 {
+    1000
+}
+
+impl Default for EpochConfig {
+    fn default() -> Self {
+        EpochConfig {
+            length: 100,
+            unbonding_period: 1000,
+            commission_bps: default_commission_bps(),
+        }
+    }
+}
+
+/// Shard-aware block production configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardingConfig {
+    /// Produce one block per shard and aggregate their headers on the beacon chain
+    pub enabled: bool,
+    /// Number of shards accounts are assigned to
+    pub shard_count: u32,
+    /// Validators per shard; when non-zero the shard count follows the validator set at every
+    /// epoch boundary, up to `shard_count` (0 keeps the shard count fixed)
+    #[serde(default)]
+    pub validators_per_shard: u32,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        ShardingConfig {
+            enabled: false,
+            shard_count: 4,
+            validators_per_shard: 0,
+        }
+    }
+}
+
+/// Light client support configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpvConfig {
+    /// Sign a checkpoint header for SPV clients every `checkpoint_interval` blocks (0 disables checkpoints)
+    pub checkpoint_interval: u64,
+}
+
+impl Default for SpvConfig {
+    fn default() -> Self {
+        SpvConfig {
+            checkpoint_interval: 1000,
+        }
+    }
+}
+
+/// Cross-chain bridge configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeConfig {
+    /// Mint wrapped assets for locks on external chains attested by the relayers
+    pub enabled: bool,
+    /// Hex-encoded Ed25519 public keys of the relayers, written to state at genesis
+    pub relayers: Vec<String>,
+    /// Relayer attestations needed to complete a transfer
+    pub threshold: u32,
+    /// External chains whose headers the bridge verifies, so transfers on
+    /// them can be proven instead of attested
+    #[serde(default)]
+    pub chains: Vec<BridgeChainConfig>,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        BridgeConfig {
+            enabled: false,
+            relayers: Vec::new(),
+            threshold: 1,
+            chains: Vec::new(),
+        }
+    }
+}
+
+/// Verification rules of the light client following an external chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeChainConfig {
+    pub chain: String,
+    /// Height of the trusted header the light client starts from
+    pub checkpoint_height: u64,
+    /// Hash of the trusted header the light client starts from
+    pub checkpoint_hash: String,
+    /// Headers, counting its own, a block needs before its events can be proven
+    #[serde(default = "default_bridge_confirmations")]
+    pub confirmations: u64,
+    /// Leading zero hex digits every relayed header hash must have at least
+    #[serde(default)]
+    pub min_difficulty: u32,
+    /// Only accept proven transfers on this chain, ignoring relayer attestations
+    #[serde(default)]
+    pub require_proof: bool,
+}
+
+fn default_bridge_confirmations() -> u64 {
+    6
+}
+
+/// On-disk history of node metrics served by /metrics/history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsHistoryConfig {
+    pub enabled: bool,
+    /// Seconds between samples
+    pub sample_interval_secs: u64,
+    /// Samples kept before the oldest is overwritten
+    pub retention_samples: u64,
+}
+
+impl Default for MetricsHistoryConfig {
+    fn default() -> Self {
+        MetricsHistoryConfig {
+            enabled: true,
+            sample_interval_secs: 10,
+            // One day at the default interval
+            retention_samples: 8640,
+        }
+    }
 }
 
 impl Default for AureonConfig {
@@ -103,9 +705,16 @@ impl Default for AureonConfig {
             consensus: ConsensusConfig {
                 engine: "pow".to_string(),
                 pow_difficulty: 4,
+                pow_target_block_time_ms: default_pow_target_block_time_ms(),
                 pos_min_stake: 1000,
                 pos_validator_count: 21,
                 poa_validators: vec!["alice".to_string(), "bob".to_string()],
+                state_proofs: false,
+                validator_keys: HashMap::new(),
+                block_interval_ms: default_block_interval_ms(),
+                max_block_transactions: default_max_block_transactions(),
+                reserved_block_percent: default_reserved_block_percent(),
+                seal_mode: default_seal_mode(),
             },
             network: NetworkConfig {
                 listen_addr: "127.0.0.1".to_string(),
@@ -114,6 +723,12 @@ impl Default for AureonConfig {
                     "127.0.0.1:6001".to_string(),
                     "127.0.0.1:6002".to_string(),
                 ],
+                node_key_path: default_node_key_path(),
+                max_peers: default_max_peers(),
+                ban_duration_secs: default_ban_duration_secs(),
+                require_encryption: default_require_encryption(),
+                dns_seeds: vec![],
+                target_peers: default_target_peers(),
             },
             api: ApiConfig {
                 enabled: true,
@@ -121,11 +736,18 @@ impl Default for AureonConfig {
                 port: 8080,
                 websocket_enabled: false,
                 websocket_port: 8081,
+                users: Vec::new(),
+                access_log_path: default_access_log_path(),
+                rate_limit_per_sec: default_rate_limit_per_sec(),
+                rate_limit_burst: default_rate_limit_burst(),
+                rate_limit_whitelist: default_rate_limit_whitelist(),
             },
             database: DatabaseConfig {
                 path: "aureon_db".to_string(),
                 cache_size_mb: 512,
                 compression: true,
+                pruning: PruningMode::Default,
+                account_cache_size: default_account_cache_size(),
             },
             state: StateConfig {
                 accounts: vec![
@@ -145,7 +767,20 @@ impl Default for AureonConfig {
                 level: "info".to_string(),
                 consensus_debug: false,
                 network_trace: false,
+                format: default_log_format(),
+                modules: HashMap::new(),
             },
+            fee_market: FeeMarketConfig::default(),
+            mempool: MempoolConfig::default(),
+            signer: SignerConfig::default(),
+            slashing: SlashingConfig::default(),
+            snapshot: SnapshotConfig::default(),
+            epoch: EpochConfig::default(),
+            sharding: ShardingConfig::default(),
+            spv: SpvConfig::default(),
+            economy: EconomyConfig::default(),
+            bridge: BridgeConfig::default(),
+            metrics_history: MetricsHistoryConfig::default(),
         }
     }
 }
@@ -154,46 +789,72 @@ impl AureonConfig {
     /// Load configuration from file or environment
     /// Priority: environment variables > config.toml > defaults
     pub fn load() -> Self {
-        // Start with defaults
+        // Start with defaults, replaced by config.toml if it exists
         let mut config = Self::default();
-
-        // Load from config.toml if it exists
-        let config_path = Path::new("config.toml");
+        let config_path = Path::new(CONFIG_PATH);
         if config_path.exists() {
-            if let Ok(contents) = fs::read_to_string(config_path) {
-                if let Ok(file_config) = toml::from_str::<AureonConfig>(&contents) {
-                    config = file_config;
-                } else {
-                    eprintln!("Warning: Failed to parse config.toml, using defaults");
-                }
+            match Self::load_file(config_path) {
+                Ok(file_config) => config = file_config,
+                Err(_) => eprintln!("Warning: Failed to parse config.toml, using defaults"),
             }
         }
+        config.apply_env_overrides();
+        config
+    }
+
+    /// Parse a configuration file
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
 
-        // Override with environment variables
+    /// Override values set through AUREON_* environment variables
+    pub fn apply_env_overrides(&mut self) {
         if let Ok(engine) = std::env::var("AUREON_CONSENSUS_ENGINE") {
-            config.consensus.engine = engine;
+            self.consensus.engine = engine;
         }
         if let Ok(difficulty) = std::env::var("AUREON_POW_DIFFICULTY") {
             if let Ok(val) = difficulty.parse() {
-                config.consensus.pow_difficulty = val;
+                self.consensus.pow_difficulty = val;
             }
         }
         if let Ok(addr) = std::env::var("AUREON_API_HOST") {
-            config.api.host = addr;
+            self.api.host = addr;
         }
         if let Ok(port) = std::env::var("AUREON_API_PORT") {
             if let Ok(val) = port.parse() {
-                config.api.port = val;
+                self.api.port = val;
             }
         }
         if let Ok(db_path) = std::env::var("AUREON_DB_PATH") {
-            config.database.path = db_path;
+            self.database.path = db_path;
+        }
+        if let Ok(price) = std::env::var("AUREON_MIN_GAS_PRICE") {
+            if let Ok(val) = price.parse() {
+                self.fee_market.min_gas_price = val;
+            }
         }
         if let Ok(level) = std::env::var("AUREON_LOG_LEVEL") {
-            config.logging.level = level;
+            self.logging.level = level;
         }
+    }
 
-        config
+    /// Take consensus parameters and genesis accounts from a chain spec
+    ///
+    /// Every node of a network must agree on these, so the spec overrides
+    /// config.toml and the environment.
+    pub fn apply_chain_spec(&mut self, spec: &ChainSpec) {
+        let params = &spec.consensus;
+        self.consensus.engine = params.engine.clone();
+        self.consensus.block_interval_ms = params.block_interval_ms;
+        self.consensus.pow_difficulty = params.pow_difficulty;
+        self.consensus.pow_target_block_time_ms = params.pow_target_block_time_ms;
+        self.consensus.pos_min_stake = params.pos_min_stake;
+        self.consensus.pos_validator_count = params.pos_validator_count;
+        self.consensus.poa_validators = spec.validators.keys().cloned().collect();
+        self.epoch.length = params.epoch_length;
+        self.state.accounts = spec.allocations.clone().into_iter().collect();
     }
 
     /// Get consensus type from engine string
@@ -216,10 +877,21 @@ impl AureonConfig {
             ));
         }
 
+        let valid_seal_modes = ["slots", "instant", "manual"];
+        if !valid_seal_modes.contains(&self.consensus.seal_mode.as_str()) {
+            return Err(format!(
+                "Invalid seal mode: {}. Must be one of: {:?}",
+                self.consensus.seal_mode, valid_seal_modes
+            ));
+        }
+
         // Validate PoW difficulty
         if self.consensus.pow_difficulty == 0 {
             return Err("PoW difficulty must be between 1 and 255".to_string());
         }
+        if self.consensus.pow_target_block_time_ms == 0 {
+            return Err("PoW target block time must be greater than 0".to_string());
+        }
 
         // Validate PoS settings
         if self.consensus.pos_validator_count == 0 {
@@ -232,11 +904,143 @@ impl AureonConfig {
             return Err("PoA requires at least one validator".to_string());
         }
 
+        // Validate peer discovery
+        if self.network.target_peers > self.network.max_peers {
+            return Err("Network target_peers must not exceed max_peers".to_string());
+        }
+        if let Some(seed) = self.network.dns_seeds.iter().find(|seed| seed.rsplit_once(':').is_none()) {
+            return Err(format!("Invalid DNS seed {}: expected host:port", seed));
+        }
+
         // Validate API port
         if self.api.port == 0 {
             return Err("API port must be greater than 0".to_string());
         }
 
+        // Validate fee market
+        if self.fee_market.block_gas_limit < crate::fee_market::BASE_TX_GAS {
+            return Err(format!(
+                "Block gas limit must be at least {}",
+                crate::fee_market::BASE_TX_GAS
+            ));
+        }
+
+        // Validate emission
+        if self.economy.halving_interval == 0 {
+            return Err("Economy halving interval must be greater than 0".to_string());
+        }
+
+        // Validate fee burning
+        if self.economy.fee_burning {
+            if self.economy.target_block_gas == 0 || self.economy.target_block_gas > self.fee_market.block_gas_limit {
+                return Err("Economy target block gas must be between 1 and the block gas limit".to_string());
+            }
+            if self.consensus.state_proofs {
+                return Err("State transition proofs do not cover fees; disable fee burning to use them".to_string());
+            }
+        }
+
+        // Validate storage rent
+        if self.economy.storage_rent {
+            if self.economy.rent_period == 0 {
+                return Err("Economy rent period must be greater than 0".to_string());
+            }
+            if self.consensus.state_proofs {
+                return Err("State transition proofs do not cover rent; disable storage rent to use them".to_string());
+            }
+        }
+
+        // Validate fast sync: peer snapshots are only trusted against a checkpoint
+        if self.snapshot.fast_sync {
+            let Some(checkpoint) = &self.snapshot.trusted_checkpoint else {
+                return Err("Fast sync needs a trusted_checkpoint to check peer snapshots against".to_string());
+            };
+            if checkpoint.block_hash.is_empty() || hex::decode(&checkpoint.state_root).is_err() {
+                return Err("Trusted checkpoint needs a block hash and a hex state root".to_string());
+            }
+        }
+
+        // Validate block production
+        if self.consensus.block_interval_ms == 0 {
+            return Err("Block interval must be greater than 0".to_string());
+        }
+        if self.consensus.max_block_transactions == 0 {
+            return Err("Max block transactions must be greater than 0".to_string());
+        }
+        if self.consensus.reserved_block_percent > 100 {
+            return Err("Reserved block percent must be at most 100".to_string());
+        }
+
+        // Validate mempool
+        if self.mempool.max_size == 0 {
+            return Err("Mempool max_size must be greater than 0".to_string());
+        }
+        if self.mempool.max_pending_per_account == 0 {
+            return Err("Mempool max_pending_per_account must be greater than 0".to_string());
+        }
+
+        // Validate validator signer
+        match self.signer.backend.as_str() {
+            _ if self.signer.protection_path.is_empty() => {
+                return Err("Signer needs a protection_path for its slashing-protection database".to_string());
+            }
+            "node-key" => {}
+            "keystore" if self.signer.keystore_path.is_empty() => {
+                return Err("Keystore signer needs a keystore_path".to_string());
+            }
+            "keystore" => {}
+            "remote" if self.signer.remote_url.is_empty() || self.signer.remote_secret_file.is_empty() => {
+                return Err("Remote signer needs a remote_url and a remote_secret_file".to_string());
+            }
+            "remote" if self.signer.remote_timeout_ms == 0 => {
+                return Err("Remote signer timeout must be greater than 0".to_string());
+            }
+            "remote" => {}
+            other => {
+                return Err(format!("Unknown signer backend '{}': expected node-key, keystore or remote", other));
+            }
+        }
+
+        // Validate sharding
+        if self.sharding.shard_count == 0 {
+            return Err("Shard count must be greater than 0".to_string());
+        }
+
+        // Validate slashing
+        if self.slashing.double_sign_penalty_bps > 10_000
+            || self.slashing.downtime_penalty_bps > 10_000
+            || self.slashing.burn_bps > 10_000
+        {
+            return Err("Slashing penalties must not exceed 10000 basis points".to_string());
+        }
+        if self.slashing.min_signed_per_window > self.slashing.downtime_window {
+            return Err("Slashing min_signed_per_window must not exceed downtime_window".to_string());
+        }
+        if self.epoch.commission_bps > 10_000 {
+            return Err("Validator commission must not exceed 10000 basis points".to_string());
+        }
+        if self.bridge.enabled && (self.bridge.threshold == 0 || self.bridge.threshold as usize > self.bridge.relayers.len()) {
+            return Err("Bridge threshold must be between 1 and the number of relayers".to_string());
+        }
+        for (i, chain) in self.bridge.chains.iter().enumerate() {
+            if chain.chain.is_empty() || chain.checkpoint_hash.is_empty() {
+                return Err("Bridge chains need a name and a checkpoint hash".to_string());
+            }
+            if chain.confirmations == 0 {
+                return Err(format!("Bridge chain {} needs at least 1 confirmation", chain.chain));
+            }
+            if self.bridge.chains[..i].iter().any(|other| other.chain == chain.chain) {
+                return Err(format!("Bridge chain {} is configured twice", chain.chain));
+            }
+        }
+
+        // Validate metrics history
+        if self.metrics_history.enabled
+            && (self.metrics_history.sample_interval_secs == 0 || self.metrics_history.retention_samples == 0)
+        {
+            return Err("Metrics history sample interval and retention must be greater than 0".to_string());
+        }
+
         // Validate log level
         let valid_levels = vec!["debug", "info", "warn", "error"];
         if !valid_levels.contains(&self.logging.level.to_lowercase().as_str()) {
@@ -245,6 +1049,36 @@ impl AureonConfig {
                 self.logging.level, valid_levels
             ));
         }
+        for (module, level) in &self.logging.modules {
+            if !valid_levels.contains(&level.to_lowercase().as_str()) {
+                return Err(format!(
+                    "Invalid log level for {}: {}. Must be one of: {:?}",
+                    module, level, valid_levels
+                ));
+            }
+        }
+        if !["text", "json"].contains(&self.logging.format.as_str()) {
+            return Err(format!("Invalid log format: {}. Must be \"text\" or \"json\"", self.logging.format));
+        }
+
+        if self.api.rate_limit_per_sec > 0 && self.api.rate_limit_burst == 0 {
+            return Err("API rate limit burst must be greater than 0".to_string());
+        }
+        for ip in &self.api.rate_limit_whitelist {
+            if ip.parse::<std::net::IpAddr>().is_err() {
+                return Err(format!("Invalid IP address {} in API rate limit whitelist", ip));
+            }
+        }
+
+        // Validate API users
+        for user in &self.api.users {
+            if crate::access_control::Role::from_name(&user.role).is_none() {
+                return Err(format!("Invalid role {} of API user {}", user.role, user.id));
+            }
+            if user.token.is_empty() {
+                return Err(format!("API user {} has an empty token", user.id));
+            }
+        }
 
         Ok(())
     }
@@ -256,6 +1090,7 @@ impl AureonConfig {
         println!("  Engine: {}", self.consensus.engine);
         if self.consensus.engine.to_lowercase() == "pow" {
             println!("  PoW Difficulty: {}", self.consensus.pow_difficulty);
+            println!("  PoW Target Block Time: {}ms", self.consensus.pow_target_block_time_ms);
         }
         if self.consensus.engine.to_lowercase() == "pos" {
             println!("  Min Stake: {} tokens", self.consensus.pos_min_stake);
@@ -264,22 +1099,122 @@ impl AureonConfig {
         if self.consensus.engine.to_lowercase() == "poa" {
             println!("  Authorized Validators: {:?}", self.consensus.poa_validators);
         }
+        println!("  State Proofs: {}", self.consensus.state_proofs);
+        println!("  Validator Keys: {}", self.consensus.validator_keys.len());
+        println!("  Block Interval: {}ms", self.consensus.block_interval_ms);
+        println!("  Max Block Transactions: {}", self.consensus.max_block_transactions);
+        println!("  Reserved Block Space: {}%", self.consensus.reserved_block_percent);
+        println!("  Seal Mode: {}", self.consensus.seal_mode);
+        println!("Signer: {}", self.signer.backend);
+        println!("  Slashing Protection: {}", self.signer.protection_path);
+        match self.signer.backend.as_str() {
+            "keystore" => println!("  Keystore: {}", self.signer.keystore_path),
+            "remote" => println!("  Remote: {}", self.signer.remote_url),
+            _ => {}
+        }
         println!("Network:");
         println!("  Listen: {}:{}", self.network.listen_addr, self.network.listen_port);
         println!("  Bootstrap Peers: {}", self.network.bootstrap_peers.len());
+        println!("  Max Peers: {}", self.network.max_peers);
+        println!("  Ban Duration: {}s", self.network.ban_duration_secs);
+        println!("  Require Encryption: {}", self.network.require_encryption);
+        println!("  DNS Seeds: {:?}", self.network.dns_seeds);
+        println!("  Target Peers: {}", self.network.target_peers);
         println!("API:");
         println!(
             "  Enabled: {} ({}:{})",
             self.api.enabled, self.api.host, self.api.port
         );
+        println!("  Users: {}", self.api.users.len());
+        println!("  Access Log: {}", self.api.access_log_path);
+        println!(
+            "  Rate Limit: {}/s per IP (burst {}, {} whitelisted)",
+            self.api.rate_limit_per_sec,
+            self.api.rate_limit_burst,
+            self.api.rate_limit_whitelist.len()
+        );
         println!("Database:");
         println!("  Path: {}", self.database.path);
         println!("  Cache: {}MB", self.database.cache_size_mb);
         println!("  Compression: {}", self.database.compression);
+        println!("  Pruning: {:?}", self.database.pruning);
+        println!("  Account Cache: {} accounts", self.database.account_cache_size);
         println!("State:");
         println!("  Genesis Accounts: {}", self.state.accounts.len());
+        println!("Fee Market:");
+        println!("  Min Gas Price: {}", self.fee_market.min_gas_price);
+        println!("  Block Gas Limit: {}", self.fee_market.block_gas_limit);
+        println!("Economy:");
+        println!("  Initial Block Reward: {}", self.economy.initial_block_reward);
+        println!("  Halving Interval: {} blocks", self.economy.halving_interval);
+        println!("  Max Supply: {}", self.economy.max_supply);
+        println!("  Fee Burning: {}", self.economy.fee_burning);
+        if self.economy.fee_burning {
+            println!("  Initial Base Fee: {}", self.economy.initial_base_fee);
+            println!("  Target Block Gas: {}", self.economy.target_block_gas);
+        }
+        println!("  Ommer Rewards: {}", self.economy.ommer_rewards);
+        println!("  Storage Rent: {}", self.economy.storage_rent);
+        if self.economy.storage_rent {
+            println!("  Rent Per Byte: {} per block", self.economy.rent_per_byte);
+            println!("  Rent Period: {} blocks", self.economy.rent_period);
+            println!("  Rent Grace Period: {} blocks", self.economy.rent_grace_period);
+        }
+        println!("Mempool:");
+        println!("  Max Size: {}", self.mempool.max_size);
+        println!("  Queued TTL: {}s", self.mempool.queued_ttl_secs);
+        println!("  Max Queued per Account: {}", self.mempool.max_queued_per_account);
+        println!("  Max Queued: {}", self.mempool.max_queued);
+        println!("  Max Pending per Account: {}", self.mempool.max_pending_per_account);
+        println!("  Replacement Price Bump: {}%", self.mempool.price_bump_percent);
+        println!("  Persist Interval: {}s", self.mempool.persist_interval_secs);
+        println!("  Pending TTL: {}s", self.mempool.pending_ttl_secs);
+        println!("  Revalidate Interval: {}s", self.mempool.revalidate_interval_secs);
+        println!("Snapshots:");
+        println!("  Interval: {} blocks", self.snapshot.interval);
+        println!("  Fast Sync: {}", self.snapshot.fast_sync);
+        println!("Epochs:");
+        println!("  Length: {} blocks", self.epoch.length);
+        println!("  Unbonding Period: {} blocks", self.epoch.unbonding_period);
+        println!("  Validator Commission: {} bps", self.epoch.commission_bps);
+        println!("Sharding:");
+        println!("  Enabled: {}", self.sharding.enabled);
+        println!("  Shards: {}", self.sharding.shard_count);
+        if self.sharding.validators_per_shard > 0 {
+            println!("  Validators Per Shard: {}", self.sharding.validators_per_shard);
+        }
+        println!("SPV:");
+        println!("  Checkpoint Interval: {} blocks", self.spv.checkpoint_interval);
+        println!("Bridge:");
+        println!("  Enabled: {}", self.bridge.enabled);
+        if self.bridge.enabled {
+            println!("  Relayers: {} (threshold {})", self.bridge.relayers.len(), self.bridge.threshold);
+            for chain in &self.bridge.chains {
+                println!(
+                    "  Light client: {} from height {} ({} confirmations{})",
+                    chain.chain,
+                    chain.checkpoint_height,
+                    chain.confirmations,
+                    if chain.require_proof { ", proofs required" } else { "" }
+                );
+            }
+        }
+        println!("Metrics History:");
+        println!("  Enabled: {}", self.metrics_history.enabled);
+        if self.metrics_history.enabled {
+            println!(
+                "  Samples: every {}s, keeping {}",
+                self.metrics_history.sample_interval_secs, self.metrics_history.retention_samples
+            );
+        }
         println!("Logging:");
         println!("  Level: {}", self.logging.level);
+        println!("  Format: {}", self.logging.format);
+        let mut modules: Vec<_> = self.logging.modules.iter().collect();
+        modules.sort();
+        for (module, level) in modules {
+            println!("  {}: {}", module, level);
+        }
         println!("=============================\n");
     }
 }
@@ -322,6 +1257,71 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_block_gas_limit_below_intrinsic_gas() {
+        let mut config = AureonConfig::default();
+        config.fee_market.block_gas_limit = 1000;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fee_market_section_optional() {
+        let mut config = AureonConfig::default();
+        config.fee_market.min_gas_price = 7;
+        let mut value = toml::Value::try_from(&config).unwrap();
+        value.as_table_mut().unwrap().remove("fee_market");
+
+        let parsed: AureonConfig = toml::from_str(&toml::to_string(&value).unwrap()).unwrap();
+        assert_eq!(parsed.fee_market.min_gas_price, 1);
+    }
+
+    #[test]
+    fn test_pruning_modes() {
+        let defaults = toml::to_string(&AureonConfig::default()).unwrap();
+        let parse = |pruning: &str| {
+            let contents = defaults.replace("pruning = \"default\"", &format!("pruning = {}", pruning));
+            toml::from_str::<AureonConfig>(&contents).map(|config| config.database.pruning)
+        };
+        assert_eq!(parse("\"archive\"").unwrap(), PruningMode::Archive);
+        assert_eq!(parse("\"default\"").unwrap().retention(), Some(DEFAULT_PRUNING_RETENTION));
+        assert_eq!(parse("500").unwrap(), PruningMode::Blocks(500));
+        assert!(parse("\"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn test_invalid_slashing_penalty() {
+        let mut config = AureonConfig::default();
+        config.slashing.burn_bps = 10_001;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fast_sync_requires_trusted_checkpoint() {
+        let mut config = AureonConfig::default();
+        assert!(!config.snapshot.fast_sync);
+        config.snapshot.fast_sync = true;
+        assert!(config.validate().unwrap_err().contains("trusted_checkpoint"));
+        config.snapshot.trusted_checkpoint = Some(TrustedCheckpoint {
+            height: 1000,
+            block_hash: "h1000".to_string(),
+            state_root: hex::encode([7u8; 32]),
+        });
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_signer_backends() {
+        let mut config = AureonConfig::default();
+        config.signer.backend = "remote".to_string();
+        assert!(config.validate().unwrap_err().contains("remote_url"));
+        config.signer.remote_url = "http://10.0.0.2:9100".to_string();
+        config.signer.remote_secret_file = "signer.secret".to_string();
+        assert!(config.validate().is_ok());
+
+        config.signer.backend = "plaintext".to_string();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_get_consensus_type() {
         let mut config = AureonConfig::default();
@@ -335,4 +1335,16 @@ mod tests {
         config.consensus.engine = "poa".to_string();
         assert!(matches!(config.get_consensus_type(), ConsensusType::PoA));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_apply_chain_spec() {
+        let mut config = AureonConfig::default();
+        let spec = ChainSpec::builtin("devnet").unwrap();
+        config.apply_chain_spec(&spec);
+        assert!(matches!(config.get_consensus_type(), ConsensusType::PoS));
+        assert_eq!(config.consensus.block_interval_ms, spec.consensus.block_interval_ms);
+        assert_eq!(config.epoch.length, spec.consensus.epoch_length);
+        assert_eq!(config.state.accounts.len(), spec.allocations.len());
+        assert!(config.validate().is_ok());
+    }
+}
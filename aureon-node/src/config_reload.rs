@@ -0,0 +1,140 @@
+//! Configuration Hot Reload
+//!
+//! On SIGHUP or a request to the admin endpoint the node re-reads its
+//! configuration file. The log levels and the mempool's gas price floor take
+//! effect immediately; every other section is wired into long-lived
+//! components at startup, so changes to them are reported and wait for a
+//! restart.
+
+use crate::config::AureonConfig;
+use crate::logging::LogLevelHandle;
+use crate::mempool::TransactionMempool;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Re-reads the configuration file and applies its reloadable values
+pub struct ConfigReloader {
+    path: PathBuf,
+    /// Configuration currently in effect
+    current: Mutex<AureonConfig>,
+    mempool: Arc<TransactionMempool>,
+    log_level: Option<LogLevelHandle>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: impl Into<PathBuf>, config: AureonConfig, mempool: Arc<TransactionMempool>) -> Self {
+        ConfigReloader {
+            path: path.into(),
+            current: Mutex::new(config),
+            mempool,
+            log_level: None,
+        }
+    }
+
+    /// Change the levels of the installed log filter on reload
+    pub fn with_log_level(mut self, handle: LogLevelHandle) -> Self {
+        self.log_level = Some(handle);
+        self
+    }
+
+    /// Configuration currently in effect
+    pub fn current(&self) -> AureonConfig {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Re-read the configuration file and apply its reloadable values,
+    /// returning a description of each change applied
+    ///
+    /// An invalid file is rejected as a whole and nothing changes.
+    pub fn reload(&self) -> Result<Vec<String>, String> {
+        let mut config = AureonConfig::load_file(&self.path)?;
+        config.apply_env_overrides();
+        config.validate()?;
+
+        let mut current = self.current.lock().unwrap();
+        let mut applied = Vec::new();
+        if config.logging.level != current.logging.level || config.logging.modules != current.logging.modules {
+            if let Some(handle) = &self.log_level {
+                handle.apply(&config.logging)?;
+            }
+            if config.logging.level != current.logging.level {
+                applied.push(format!("logging.level: {} -> {}", current.logging.level, config.logging.level));
+            }
+            if config.logging.modules != current.logging.modules {
+                applied.push(format!("logging.modules: {} module levels", config.logging.modules.len()));
+            }
+            current.logging.level = config.logging.level.clone();
+            current.logging.modules = config.logging.modules.clone();
+        }
+        if config.fee_market.min_gas_price != current.fee_market.min_gas_price {
+            let mut fee_market = self.mempool.fee_market();
+            fee_market.min_gas_price = config.fee_market.min_gas_price;
+            self.mempool.set_fee_market(fee_market);
+            applied.push(format!(
+                "fee_market.min_gas_price: {} -> {}",
+                current.fee_market.min_gas_price, config.fee_market.min_gas_price
+            ));
+            current.fee_market.min_gas_price = config.fee_market.min_gas_price;
+        }
+
+        // Whatever still differs can only take effect on restart
+        let pending = toml::Value::try_from(&config).ok() != toml::Value::try_from(&*current).ok();
+        if pending {
+            eprintln!("[Config] {} has changes that require a restart", self.path.display());
+        }
+        Ok(applied)
+    }
+}
+
+/// Reload the configuration every time the process receives SIGHUP
+#[cfg(unix)]
+pub async fn reload_on_hangup(reloader: Arc<ConfigReloader>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            eprintln!("Warning: Failed to listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match reloader.reload() {
+            Ok(applied) => println!("[Config] Reloaded ({} changes applied)", applied.len()),
+            Err(e) => eprintln!("[Config] Reload failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_applies_reloadable_values_only() {
+        let path = std::env::temp_dir().join(format!("aureon_reload_{}.toml", std::process::id()));
+        let config = AureonConfig::default();
+        let mempool = Arc::new(TransactionMempool::new().with_fee_market(config.fee_market.clone()));
+        let reloader = ConfigReloader::new(&path, config.clone(), mempool.clone());
+
+        let mut edited = config.clone();
+        edited.fee_market.min_gas_price = 5;
+        edited.logging.level = "debug".to_string();
+        edited.api.port = 9090;
+        std::fs::write(&path, toml::to_string(&edited).unwrap()).unwrap();
+
+        let applied = reloader.reload().unwrap();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(mempool.fee_market().min_gas_price, 5);
+        assert_eq!(reloader.current.lock().unwrap().api.port, config.api.port);
+
+        // An invalid file leaves the running configuration untouched
+        edited.fee_market.min_gas_price = 9;
+        edited.logging.level = "loud".to_string();
+        std::fs::write(&path, toml::to_string(&edited).unwrap()).unwrap();
+        assert!(reloader.reload().is_err());
+        assert_eq!(mempool.fee_market().min_gas_price, 5);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,152 @@
+//! PoW Difficulty Retargeting
+//!
+//! A PoW block's hash, read as a number, must be at most the hash space
+//! divided by the block's difficulty, so a block takes `difficulty` hashes
+//! on average to mine. The difficulty is not fixed: every block retargets it
+//! from its parent's, by the time between the two blocks (their slot
+//! distance) against the target block time. A block found faster than the
+//! target raises the difficulty and a slower one lowers it, each by a
+//! `1/DAMPING` share of the deviation, so the difficulty follows an
+//! exponential moving average of recent block times and the chain adapts to
+//! hash power joining or leaving. Blocks carry their difficulty, and
+//! importers recompute it from the parent to check it.
+
+use crate::config::ConsensusConfig;
+use crate::types::Block;
+
+/// Share of a block time's deviation from the target applied per block
+const DAMPING: u128 = 8;
+
+/// Block times longer than this many target block times count as this many,
+/// so a single block lowers the difficulty by at most half
+const MAX_BLOCK_TIME_TARGETS: u128 = 5;
+
+/// Hashes per block matching the original four zero hex digit target
+pub const DEFAULT_INITIAL_DIFFICULTY: u64 = 1 << 16;
+
+/// Default block time the difficulty steers towards
+pub const DEFAULT_TARGET_BLOCK_TIME_MS: u64 = 10_000;
+
+/// Default slot length block times are measured in
+const DEFAULT_SLOT_MS: u64 = 5_000;
+
+/// Retargeting parameters of a PoW chain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retarget {
+    /// Difficulty of the first block, and of blocks on parents without one
+    pub initial: u64,
+    /// Block time the difficulty steers towards
+    pub target_block_time_ms: u64,
+    /// Slot length block times are measured in
+    pub slot_ms: u64,
+}
+
+impl Default for Retarget {
+    fn default() -> Self {
+        Retarget {
+            initial: DEFAULT_INITIAL_DIFFICULTY,
+            target_block_time_ms: DEFAULT_TARGET_BLOCK_TIME_MS,
+            slot_ms: DEFAULT_SLOT_MS,
+        }
+    }
+}
+
+impl Retarget {
+    /// Parameters of the configured chain: `pow_difficulty` leading zero hex
+    /// digits to start from, retargeted towards `pow_target_block_time_ms`
+    pub fn from_config(config: &ConsensusConfig) -> Self {
+        Retarget {
+            initial: 16u64.saturating_pow(config.pow_difficulty as u32),
+            target_block_time_ms: config.pow_target_block_time_ms,
+            slot_ms: config.block_interval_ms,
+        }
+    }
+
+    /// Difficulty of the block at `slot` on top of `parent`
+    pub fn next(&self, parent: Option<&Block>, slot: u64) -> u64 {
+        match parent {
+            // Blocks from before retargeting carry no difficulty
+            Some(parent) if parent.difficulty > 0 => {
+                let block_time_ms = slot.saturating_sub(parent.slot).saturating_mul(self.slot_ms);
+                adjust(parent.difficulty, self.target_block_time_ms.max(1), block_time_ms)
+            }
+            _ => self.initial.max(1),
+        }
+    }
+}
+
+/// `difficulty` moved towards the hash rate implied by a block time of
+/// `block_time` against `target`
+fn adjust(difficulty: u64, target: u64, block_time: u64) -> u64 {
+    let (difficulty, target) = (difficulty as u128, target as u128);
+    let block_time = (block_time as u128).min(target * MAX_BLOCK_TIME_TARGETS);
+    let next = if block_time <= target {
+        difficulty + difficulty * (target - block_time) / (target * DAMPING)
+    } else {
+        difficulty - difficulty * (block_time - target) / (target * DAMPING)
+    };
+    next.clamp(1, u64::MAX as u128) as u64
+}
+
+/// Lowest difficulty any block on a parent of `parent_difficulty` may have
+pub fn min_next(parent_difficulty: u64) -> u64 {
+    adjust(parent_difficulty, 1, MAX_BLOCK_TIME_TARGETS as u64)
+}
+
+/// Whether the hex-encoded `hash` is a proof of `difficulty` hashes of work:
+/// its leading 64 bits are at most the hash space divided by the difficulty
+pub fn meets_difficulty(hash: &str, difficulty: u64) -> bool {
+    let Some(leading) = hash.get(..16).and_then(|prefix| u64::from_str_radix(prefix, 16).ok()) else {
+        return false;
+    };
+    difficulty > 0 && leading <= u64::MAX / difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::pos::PoSConsensus;
+    use crate::consensus::ConsensusEngine;
+
+    fn parent(difficulty: u64, slot: u64) -> Block {
+        let mut block = PoSConsensus::new(Default::default()).produce_block(slot, "GENESIS".to_string(), vec![], vec![], vec![]);
+        block.difficulty = difficulty;
+        block
+    }
+
+    #[test]
+    fn test_difficulty_follows_block_times() {
+        let retarget = Retarget { initial: 1000, target_block_time_ms: 10_000, slot_ms: 5_000 };
+        assert_eq!(retarget.next(None, 1), 1000);
+        assert_eq!(retarget.next(Some(&parent(0, 4)), 5), 1000);
+
+        // On target the difficulty holds, faster blocks raise it and slower ones lower it
+        assert_eq!(retarget.next(Some(&parent(1000, 4)), 6), 1000);
+        assert_eq!(retarget.next(Some(&parent(1000, 4)), 5), 1062);
+        assert_eq!(retarget.next(Some(&parent(1000, 4)), 4), 1125);
+        assert_eq!(retarget.next(Some(&parent(1000, 4)), 8), 875);
+        // Long gaps halve it at most, and it never reaches zero
+        assert_eq!(retarget.next(Some(&parent(1000, 4)), 1000), 500);
+        assert_eq!(min_next(1000), 500);
+        assert_eq!(retarget.next(Some(&parent(1, 4)), 1000), 1);
+
+        // Hash power doubling settles at a doubled difficulty
+        let hashes_per_second = 2 * 1000 / 10;
+        let mut difficulty = 1000u64;
+        for _ in 0..200 {
+            let block_time = difficulty * 1000 / hashes_per_second;
+            difficulty = adjust(difficulty, 10_000, block_time);
+        }
+        assert!((1900..=2100).contains(&difficulty), "difficulty {}", difficulty);
+    }
+
+    #[test]
+    fn test_meets_difficulty() {
+        let hash = format!("0000ffffffffffff{}", "f".repeat(48));
+        assert!(meets_difficulty(&hash, 1 << 16));
+        assert!(!meets_difficulty(&hash, (1 << 16) + 1));
+        assert!(meets_difficulty(&"f".repeat(64), 1));
+        assert!(!meets_difficulty(&hash, 0));
+        assert!(!meets_difficulty("00", 1));
+    }
+}
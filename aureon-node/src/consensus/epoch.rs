@@ -0,0 +1,192 @@
+//! Epoch-Based Validator Rotation
+//!
+//! The chain is divided into epochs of a fixed number of blocks. At the end
+//! of each epoch the active validator set is recomputed from bonded stake:
+//! the largest stakes of at least the minimum become active for the next
+//! epoch. Validators leaving the set stay bonded, and slashable, for an
+//! unbonding period before their stake is released.
+
+use crate::config::EpochConfig;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Stake of a validator that left the active set, pending release
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unbonding {
+    pub validator: String,
+    pub amount: u64,
+    pub release_height: u64,
+}
+
+/// Validator set change at an epoch boundary
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochTransition {
+    /// Epoch the new set is active in
+    pub epoch: u64,
+    pub validators: HashMap<String, u64>,
+    pub entered: Vec<String>,
+    pub exited: Vec<String>,
+}
+
+/// Tracks the active validator set across epochs
+pub struct EpochManager {
+    config: EpochConfig,
+    max_validators: usize,
+    min_stake: u64,
+    epoch: u64,
+    validators: HashMap<String, u64>,
+    unbonding: Vec<Unbonding>,
+}
+
+impl EpochManager {
+    /// Start in epoch 0 with the genesis validator set
+    pub fn new(config: EpochConfig, max_validators: usize, min_stake: u64, genesis: HashMap<String, u64>) -> Self {
+        EpochManager {
+            config,
+            max_validators,
+            min_stake,
+            epoch: 0,
+            validators: genesis,
+            unbonding: Vec::new(),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Number of blocks per epoch
+    pub fn length(&self) -> u64 {
+        self.config.length
+    }
+
+    /// Validators active in the current epoch
+    pub fn validators(&self) -> &HashMap<String, u64> {
+        &self.validators
+    }
+
+    /// Stake of former validators that has not been released yet
+    pub fn unbonding(&self) -> &[Unbonding] {
+        &self.unbonding
+    }
+
+    /// Whether the block at `height` is the last one of its epoch
+    pub fn is_epoch_end(&self, height: u64) -> bool {
+        self.config.length > 0 && (height + 1).is_multiple_of(self.config.length)
+    }
+
+    /// Highest eligible stakes, largest first (ties broken by name)
+    fn select(&self, bonded: &HashMap<String, u128>) -> HashMap<String, u64> {
+        let mut candidates: Vec<(&String, u64)> = bonded
+            .iter()
+            .map(|(validator, stake)| (validator, u64::try_from(*stake).unwrap_or(u64::MAX)))
+            .filter(|(_, stake)| *stake > 0 && *stake >= self.min_stake)
+            .collect();
+        candidates.sort_by_key(|(validator, stake)| (Reverse(*stake), *validator));
+        candidates
+            .into_iter()
+            .take(self.max_validators)
+            .map(|(validator, stake)| (validator.clone(), stake))
+            .collect()
+    }
+
+    /// Process the block at `height` given the current bonded stakes
+    ///
+    /// At the end of an epoch the next epoch's set is computed. Validators
+    /// that drop out start unbonding; ones that re-enter cancel theirs. If
+    /// no staker is eligible the current set is kept, so the chain never
+    /// runs without validators.
+    pub fn on_block(&mut self, height: u64, bonded: &HashMap<String, u128>) -> Option<EpochTransition> {
+        if !self.is_epoch_end(height) {
+            return None;
+        }
+        self.epoch = (height + 1) / self.config.length;
+
+        let next = self.select(bonded);
+        if next.is_empty() {
+            return None;
+        }
+
+        let mut entered: Vec<String> = next.keys().filter(|v| !self.validators.contains_key(*v)).cloned().collect();
+        let mut exited: Vec<String> = self.validators.keys().filter(|v| !next.contains_key(*v)).cloned().collect();
+        entered.sort();
+        exited.sort();
+
+        self.unbonding.retain(|entry| !entered.contains(&entry.validator));
+        for validator in &exited {
+            let amount = bonded
+                .get(validator)
+                .map_or(0, |stake| u64::try_from(*stake).unwrap_or(u64::MAX));
+            self.unbonding.push(Unbonding {
+                validator: validator.clone(),
+                amount,
+                release_height: height + self.config.unbonding_period,
+            });
+        }
+
+        self.validators = next;
+        Some(EpochTransition {
+            epoch: self.epoch,
+            validators: self.validators.clone(),
+            entered,
+            exited,
+        })
+    }
+
+    /// Remove and return unbonding entries whose period has elapsed by `height`
+    pub fn release_unbonded(&mut self, height: u64) -> Vec<Unbonding> {
+        let (released, pending) = std::mem::take(&mut self.unbonding)
+            .into_iter()
+            .partition(|entry| entry.release_height <= height);
+        self.unbonding = pending;
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stakes(entries: &[(&str, u128)]) -> HashMap<String, u128> {
+        entries.iter().map(|(v, s)| (v.to_string(), *s)).collect()
+    }
+
+    fn manager() -> EpochManager {
+        let genesis = [("alice".to_string(), 100), ("bob".to_string(), 200)].into_iter().collect();
+        EpochManager::new(EpochConfig { length: 10, unbonding_period: 5, ..EpochConfig::default() }, 2, 50, genesis)
+    }
+
+    #[test]
+    fn test_rotation_at_epoch_end() {
+        let mut epochs = manager();
+        let bonded = stakes(&[("alice", 100), ("bob", 200), ("carol", 300), ("dave", 40)]);
+        assert!(epochs.on_block(8, &bonded).is_none());
+
+        let transition = epochs.on_block(9, &bonded).unwrap();
+        assert_eq!(transition.epoch, 1);
+        assert_eq!(transition.entered, vec!["carol"]);
+        assert_eq!(transition.exited, vec!["alice"]);
+        assert!(epochs.validators().contains_key("bob"));
+        assert!(!epochs.validators().contains_key("dave"));
+
+        assert!(epochs.release_unbonded(13).is_empty());
+        let released = epochs.release_unbonded(14);
+        assert_eq!(released, vec![Unbonding { validator: "alice".to_string(), amount: 100, release_height: 14 }]);
+    }
+
+    #[test]
+    fn test_reentry_cancels_unbonding_and_empty_set_is_ignored() {
+        let mut epochs = manager();
+        epochs.on_block(9, &stakes(&[("bob", 200), ("carol", 300)])).unwrap();
+        assert_eq!(epochs.unbonding().len(), 1);
+
+        let transition = epochs.on_block(19, &stakes(&[("alice", 500), ("carol", 300)])).unwrap();
+        assert_eq!(transition.entered, vec!["alice"]);
+        assert_eq!(epochs.unbonding().len(), 1);
+        assert_eq!(epochs.unbonding()[0].validator, "bob");
+
+        assert!(epochs.on_block(29, &stakes(&[("erin", 10)])).is_none());
+        assert_eq!(epochs.validators().len(), 2);
+        assert_eq!(epochs.epoch(), 3);
+    }
+}
@@ -0,0 +1,518 @@
+//! BFT Finality Gadget
+//!
+//! Tendermint-style two-phase voting on top of block production. Validators
+//! prevote for a block at a given height and round; once more than two
+//! thirds of the stake prevotes the same block (a polka), validators
+//! precommit it, and more than two thirds of precommits finalize the block.
+//! Finalized heights are never reverted.
+//!
+//! Every vote is signed with the validator's key, and votes without a valid
+//! signature from a known key are rejected, so a peer cannot vote on behalf
+//! of a validator. Votes name the chain they are cast on, so a vote from one
+//! network cannot be replayed on another.
+
+use crate::signer::{SignKind, SignRequest, ValidatorSigner};
+use bincode::{Decode, Encode};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+const VOTE_DOMAIN: &[u8] = b"aureon-finality-vote";
+
+/// Voting phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
+pub enum VoteType {
+    Prevote,
+    Precommit,
+}
+
+/// A validator's vote for a block (or for nothing, when `block_hash` is None)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct Vote {
+    /// Network the vote is cast on
+    #[serde(default)]
+    pub chain_id: String,
+    pub vote_type: VoteType,
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: Option<String>,
+    pub validator: String,
+    /// Validator's signature over `signing_bytes`
+    #[serde(default)]
+    pub signature: Vec<u8>,
+}
+
+impl Vote {
+    pub fn prevote(validator: &str, height: u64, round: u32, block_hash: Option<String>) -> Self {
+        Vote {
+            chain_id: String::new(),
+            vote_type: VoteType::Prevote,
+            height,
+            round,
+            block_hash,
+            validator: validator.to_string(),
+            signature: Vec::new(),
+        }
+    }
+
+    pub fn precommit(validator: &str, height: u64, round: u32, block_hash: Option<String>) -> Self {
+        Vote {
+            chain_id: String::new(),
+            vote_type: VoteType::Precommit,
+            height,
+            round,
+            block_hash,
+            validator: validator.to_string(),
+            signature: Vec::new(),
+        }
+    }
+
+    /// Cast the vote on the network `chain_id`
+    pub fn with_chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = chain_id.to_string();
+        self
+    }
+
+    /// Bytes the validator signs: the chain, phase, height, round and block voted for
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = VOTE_DOMAIN.to_vec();
+        bytes.extend_from_slice(&(self.chain_id.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.chain_id.as_bytes());
+        bytes.push(match self.vote_type {
+            VoteType::Prevote => 0,
+            VoteType::Precommit => 1,
+        });
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.round.to_le_bytes());
+        match &self.block_hash {
+            Some(hash) => {
+                bytes.push(1);
+                bytes.extend_from_slice(hash.as_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes
+    }
+
+    /// Sign the vote with the validator's `signer`
+    ///
+    /// Slashing protection tracks votes per phase, with the height and round
+    /// packed into the slot so each round can be voted in once. Heights that
+    /// do not fit the upper half of the slot are refused rather than
+    /// truncated onto the slot of a lower height.
+    pub fn sign(mut self, signer: &dyn ValidatorSigner) -> Result<Self, String> {
+        let kind = match self.vote_type {
+            VoteType::Prevote => SignKind::Prevote,
+            VoteType::Precommit => SignKind::Precommit,
+        };
+        let height = u32::try_from(self.height)
+            .map_err(|_| format!("Refusing to sign a vote at height {} beyond the protected range", self.height))?;
+        let slot = ((height as u64) << 32) | self.round as u64;
+        let request = SignRequest::new(kind, slot, self.signing_bytes());
+        self.signature = signer.sign(&request)?.to_bytes().to_vec();
+        Ok(self)
+    }
+
+    /// Check the vote signature against the validator's `key`
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), String> {
+        let signature: [u8; 64] = self
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| format!("Vote from {} is not signed", self.validator))?;
+        key.verify(&self.signing_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| format!("Invalid vote signature from {}", self.validator))
+    }
+}
+
+/// Outcome of feeding a block or vote into the gadget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinalityEvent {
+    /// A vote cast by the local validator that should be gossiped to peers
+    Broadcast(Vote),
+    /// A block gathered a precommit quorum and is now final
+    Finalized {
+        height: u64,
+        block_hash: String,
+        /// Validators whose precommits for the block were counted
+        signers: Vec<String>,
+    },
+}
+
+type RoundKey = (u64, u32, VoteType);
+
+/// The local validator and the signer its votes are signed with
+#[derive(Clone)]
+struct LocalValidator {
+    name: String,
+    signer: Arc<dyn ValidatorSigner>,
+}
+
+impl fmt::Debug for LocalValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalValidator").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// Tracks votes per height/round and decides finality
+#[derive(Debug, Clone)]
+pub struct FinalityGadget {
+    /// Network votes are cast and accepted on
+    chain_id: String,
+    validators: HashMap<String, u64>,
+    /// Keys votes are checked against
+    keys: HashMap<String, VerifyingKey>,
+    total_stake: u64,
+    local_validator: Option<LocalValidator>,
+    votes: HashMap<RoundKey, HashMap<String, Vote>>,
+    /// Block the local validator precommitted, per height
+    locked: HashMap<u64, String>,
+    finalized: BTreeMap<u64, String>,
+}
+
+impl FinalityGadget {
+    /// Create a gadget for a stake-weighted validator set
+    pub fn new(validators: HashMap<String, u64>) -> Self {
+        let total_stake = validators.values().sum();
+        FinalityGadget {
+            chain_id: String::new(),
+            validators,
+            keys: HashMap::new(),
+            total_stake,
+            local_validator: None,
+            votes: HashMap::new(),
+            locked: HashMap::new(),
+            finalized: BTreeMap::new(),
+        }
+    }
+
+    /// Cast votes as `validator`, signed by its `signer`, when it is part of
+    /// the validator set
+    pub fn with_local_validator(mut self, validator: &str, signer: Arc<dyn ValidatorSigner>) -> Self {
+        if self.validators.contains_key(validator) {
+            self.keys.insert(validator.to_string(), signer.public_key());
+            self.local_validator = Some(LocalValidator { name: validator.to_string(), signer });
+        }
+        self
+    }
+
+    /// Cast and accept only votes for the network `chain_id`
+    pub fn with_chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = chain_id.to_string();
+        self
+    }
+
+    /// Accept votes from `validator` signed with `key`
+    pub fn with_validator_key(mut self, validator: &str, key: VerifyingKey) -> Self {
+        self.keys.insert(validator.to_string(), key);
+        self
+    }
+
+    /// Stake needed for a quorum (strictly more than two thirds)
+    pub fn quorum(&self) -> u64 {
+        self.total_stake * 2 / 3 + 1
+    }
+
+    /// Highest finalized height
+    pub fn finalized_height(&self) -> Option<u64> {
+        self.finalized.keys().next_back().copied()
+    }
+
+    /// Hash of the block finalized at `height`
+    pub fn finalized_hash(&self, height: u64) -> Option<&str> {
+        self.finalized.get(&height).map(String::as_str)
+    }
+
+    /// Whether `height` has been finalized
+    pub fn is_finalized(&self, height: u64) -> bool {
+        self.finalized.contains_key(&height)
+    }
+
+    /// Propose a freshly produced block, prevoting it as the local validator
+    pub fn on_block(&mut self, height: u64, round: u32, block_hash: &str) -> Vec<FinalityEvent> {
+        let Some(local) = self.local_validator.clone() else {
+            return Vec::new();
+        };
+        if self.is_finalized(height) {
+            return Vec::new();
+        }
+
+        // A validator locked on a block keeps prevoting it in later rounds
+        let target = self
+            .locked
+            .get(&height)
+            .cloned()
+            .unwrap_or_else(|| block_hash.to_string());
+        self.cast(&local, Vote::prevote(&local.name, height, round, Some(target)))
+    }
+
+    /// Sign a local vote, count it and have it gossiped
+    fn cast(&mut self, local: &LocalValidator, vote: Vote) -> Vec<FinalityEvent> {
+        let vote = match vote.with_chain_id(&self.chain_id).sign(local.signer.as_ref()) {
+            Ok(vote) => vote,
+            Err(e) => {
+                eprintln!("[Finality] Not voting: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut events = vec![FinalityEvent::Broadcast(vote.clone())];
+        events.extend(self.on_vote(vote).unwrap_or_default());
+        events
+    }
+
+    /// Record a vote from any validator (local or gossiped)
+    ///
+    /// Repeated identical votes are ignored. A second, different vote from
+    /// the same validator for the same height, round and phase is rejected,
+    /// as are votes not signed with the validator's key or cast on another
+    /// chain.
+    pub fn on_vote(&mut self, vote: Vote) -> Result<Vec<FinalityEvent>, String> {
+        if vote.chain_id != self.chain_id {
+            return Err(format!("Vote cast on chain '{}', not '{}'", vote.chain_id, self.chain_id));
+        }
+        if !self.validators.contains_key(&vote.validator) {
+            return Err(format!("Unknown validator: {}", vote.validator));
+        }
+        let key = self
+            .keys
+            .get(&vote.validator)
+            .ok_or_else(|| format!("No key known for validator {}", vote.validator))?;
+        vote.verify(key)?;
+        if self.is_finalized(vote.height) {
+            return Ok(Vec::new());
+        }
+
+        let key = (vote.height, vote.round, vote.vote_type);
+        let round_votes = self.votes.entry(key).or_default();
+        if let Some(existing) = round_votes.get(&vote.validator) {
+            if existing.block_hash == vote.block_hash {
+                return Ok(Vec::new());
+            }
+            return Err(format!(
+                "Conflicting {:?} from {} at height {} round {}",
+                vote.vote_type, vote.validator, vote.height, vote.round
+            ));
+        }
+        round_votes.insert(vote.validator.clone(), vote.clone());
+
+        let Some(block_hash) = vote.block_hash.clone() else {
+            return Ok(Vec::new());
+        };
+        if self.stake_for(&key, &block_hash) < self.quorum() {
+            return Ok(Vec::new());
+        }
+
+        match vote.vote_type {
+            VoteType::Prevote => Ok(self.on_polka(vote.height, vote.round, block_hash)),
+            VoteType::Precommit => {
+                let mut signers: Vec<String> = self.votes[&key]
+                    .values()
+                    .filter(|v| v.block_hash.as_deref() == Some(block_hash.as_str()))
+                    .map(|v| v.validator.clone())
+                    .collect();
+                signers.sort();
+
+                self.finalized.insert(vote.height, block_hash.clone());
+                self.prune(vote.height);
+                Ok(vec![FinalityEvent::Finalized {
+                    height: vote.height,
+                    block_hash,
+                    signers,
+                }])
+            }
+        }
+    }
+
+    /// Prevote quorum reached: lock and precommit as the local validator
+    fn on_polka(&mut self, height: u64, round: u32, block_hash: String) -> Vec<FinalityEvent> {
+        let Some(local) = self.local_validator.clone() else {
+            return Vec::new();
+        };
+        let key = (height, round, VoteType::Precommit);
+        if self.votes.get(&key).is_some_and(|v| v.contains_key(&local.name)) {
+            return Vec::new();
+        }
+
+        self.locked.insert(height, block_hash.clone());
+        self.cast(&local, Vote::precommit(&local.name, height, round, Some(block_hash)))
+    }
+
+    fn stake_for(&self, key: &RoundKey, block_hash: &str) -> u64 {
+        self.votes
+            .get(key)
+            .map(|votes| {
+                votes
+                    .values()
+                    .filter(|v| v.block_hash.as_deref() == Some(block_hash))
+                    .map(|v| self.validators.get(&v.validator).copied().unwrap_or(0))
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Drop vote bookkeeping for finalized heights
+    fn prune(&mut self, finalized_height: u64) {
+        self.votes.retain(|(height, _, _), _| *height > finalized_height);
+        self.locked.retain(|height, _| *height > finalized_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    const NAMES: [&str; 4] = ["alice", "bob", "charlie", "dave"];
+
+    fn validators() -> HashMap<String, u64> {
+        NAMES.iter().map(|v| (v.to_string(), 100)).collect()
+    }
+
+    fn identity(validator: &str) -> NodeIdentity {
+        let index = NAMES.iter().position(|v| *v == validator).unwrap_or(NAMES.len());
+        NodeIdentity::from_secret([index as u8 + 1; 32])
+    }
+
+    fn gadget() -> FinalityGadget {
+        NAMES
+            .iter()
+            .fold(FinalityGadget::new(validators()), |gadget, v| gadget.with_validator_key(v, identity(v).public_key()))
+    }
+
+    fn signed(vote: Vote) -> Vote {
+        let validator = vote.validator.clone();
+        vote.sign(&identity(&validator)).unwrap()
+    }
+
+    fn hash() -> Option<String> {
+        Some("block1".to_string())
+    }
+
+    #[test]
+    fn test_quorum_is_more_than_two_thirds() {
+        let gadget = FinalityGadget::new(validators());
+        assert_eq!(gadget.quorum(), 267);
+    }
+
+    #[test]
+    fn test_finalizes_after_two_phases() {
+        let mut gadget = gadget();
+        for v in ["alice", "bob", "charlie"] {
+            gadget.on_vote(signed(Vote::prevote(v, 1, 0, hash()))).unwrap();
+        }
+        // Prevotes alone never finalize
+        assert_eq!(gadget.finalized_height(), None);
+
+        gadget.on_vote(signed(Vote::precommit("alice", 1, 0, hash()))).unwrap();
+        gadget.on_vote(signed(Vote::precommit("bob", 1, 0, hash()))).unwrap();
+        let events = gadget.on_vote(signed(Vote::precommit("charlie", 1, 0, hash()))).unwrap();
+
+        assert_eq!(
+            events,
+            vec![FinalityEvent::Finalized {
+                height: 1,
+                block_hash: "block1".to_string(),
+                signers: vec!["alice".to_string(), "bob".to_string(), "charlie".to_string()],
+            }]
+        );
+        assert_eq!(gadget.finalized_height(), Some(1));
+        assert_eq!(gadget.finalized_hash(1), Some("block1"));
+    }
+
+    #[test]
+    fn test_split_vote_does_not_finalize() {
+        let mut gadget = gadget();
+        gadget.on_vote(signed(Vote::precommit("alice", 1, 0, hash()))).unwrap();
+        gadget.on_vote(signed(Vote::precommit("bob", 1, 0, hash()))).unwrap();
+        gadget.on_vote(signed(Vote::precommit("charlie", 1, 0, Some("other".to_string())))).unwrap();
+        gadget.on_vote(signed(Vote::precommit("dave", 1, 0, None))).unwrap();
+        assert!(!gadget.is_finalized(1));
+    }
+
+    #[test]
+    fn test_conflicting_and_unknown_votes_rejected() {
+        let mut gadget = gadget();
+        gadget.on_vote(signed(Vote::prevote("alice", 1, 0, hash()))).unwrap();
+        // Identical vote is a harmless duplicate
+        assert!(gadget.on_vote(signed(Vote::prevote("alice", 1, 0, hash()))).unwrap().is_empty());
+        assert!(gadget.on_vote(signed(Vote::prevote("alice", 1, 0, Some("other".into())))).is_err());
+        assert!(gadget.on_vote(signed(Vote::prevote("mallory", 1, 0, hash()))).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_and_forged_votes_rejected() {
+        let mut gadget = gadget();
+        assert!(gadget.on_vote(Vote::prevote("alice", 1, 0, hash())).unwrap_err().contains("not signed"));
+
+        // Signed by bob's key on alice's behalf
+        let forged = Vote::prevote("alice", 1, 0, hash()).sign(&identity("bob")).unwrap();
+        assert!(gadget.on_vote(forged).is_err());
+
+        // A signature does not carry over to another block
+        let mut altered = signed(Vote::prevote("alice", 1, 0, hash()));
+        altered.block_hash = Some("other".to_string());
+        assert!(gadget.on_vote(altered).is_err());
+
+        // Validators without a known key cannot vote
+        let mut gadget = FinalityGadget::new(validators());
+        assert!(gadget.on_vote(signed(Vote::prevote("alice", 1, 0, hash()))).is_err());
+    }
+
+    #[test]
+    fn test_votes_bound_to_their_chain() {
+        let mut gadget = gadget().with_chain_id("aureon-mainnet");
+        let vote = |chain_id: &str| signed(Vote::prevote("alice", 1, 0, hash()).with_chain_id(chain_id));
+        assert!(gadget.on_vote(vote("aureon-testnet")).unwrap_err().contains("aureon-testnet"));
+        assert!(gadget.on_vote(vote("")).is_err());
+
+        // Relabelling a vote breaks its signature
+        let mut relabelled = vote("aureon-testnet");
+        relabelled.chain_id = "aureon-mainnet".to_string();
+        assert!(gadget.on_vote(relabelled).unwrap_err().contains("Invalid vote signature"));
+        assert!(gadget.on_vote(vote("aureon-mainnet")).is_ok());
+
+        // Local votes are cast on the gadget's chain
+        let mut local = gadget.with_local_validator("bob", Arc::new(identity("bob")));
+        let events = local.on_block(2, 0, "block2");
+        assert!(matches!(&events[0], FinalityEvent::Broadcast(vote) if vote.chain_id == "aureon-mainnet"));
+    }
+
+    #[test]
+    fn test_refuses_heights_beyond_the_signing_slot() {
+        let alice = identity("alice");
+        assert!(Vote::prevote("alice", u32::MAX as u64, 0, hash()).sign(&alice).is_ok());
+        // Would share the slot of height 1 if the height were truncated
+        let beyond = Vote::prevote("alice", (1 << 32) + 1, 0, hash());
+        assert!(beyond.sign(&alice).unwrap_err().contains("protected range"));
+    }
+
+    #[test]
+    fn test_local_validator_precommits_on_polka() {
+        let mut gadget = gadget().with_local_validator("alice", Arc::new(identity("alice")));
+        let events = gadget.on_block(1, 0, "block1");
+        assert_eq!(events, vec![FinalityEvent::Broadcast(signed(Vote::prevote("alice", 1, 0, hash())))]);
+
+        gadget.on_vote(signed(Vote::prevote("bob", 1, 0, hash()))).unwrap();
+        let events = gadget.on_vote(signed(Vote::prevote("charlie", 1, 0, hash()))).unwrap();
+        assert_eq!(events, vec![FinalityEvent::Broadcast(signed(Vote::precommit("alice", 1, 0, hash())))]);
+    }
+
+    #[test]
+    fn test_single_validator_finalizes_alone() {
+        let solo = Arc::new(NodeIdentity::from_secret([9u8; 32]));
+        let mut gadget =
+            FinalityGadget::new(HashMap::from([("solo".to_string(), 10)])).with_local_validator("solo", solo.clone());
+        let events = gadget.on_block(5, 0, "block5");
+        assert!(events.contains(&FinalityEvent::Finalized {
+            height: 5,
+            block_hash: "block5".to_string(),
+            signers: vec!["solo".to_string()],
+        }));
+        assert_eq!(gadget.finalized_height(), Some(5));
+        // Votes for finalized heights are ignored
+        let late = Vote::prevote("solo", 5, 1, Some("fork".into())).sign(solo.as_ref()).unwrap();
+        assert!(gadget.on_vote(late).unwrap().is_empty());
+    }
+}
@@ -1,12 +1,43 @@
 pub mod pow;
 pub mod pos;
+pub mod poa;
+pub mod finality;
+pub mod epoch;
+pub mod weighting;
+pub mod ommers;
+pub mod difficulty;
 use std::collections::HashMap;
 
-use crate::consensus::{pow::PoWConsensus, pos::PoSConsensus};
+use crate::community_governance::AuthorityChange;
+use crate::config::ConsensusConfig;
+use crate::consensus::{difficulty::Retarget, ommers::OmmerHeader, poa::PoAConsensus, pow::PoWConsensus, pos::PoSConsensus};
 use crate::types::{Block, Transaction};
 
-pub trait ConsensusEngine {
-    fn produce_block(&self, transactions: Vec<Transaction>, pre_state_root: Vec<u8>, post_state_root: Vec<u8>) -> Block;
+pub trait ConsensusEngine: Send {
+    /// Produce the block for `slot` on top of `previous_hash` (PoW mines its
+    /// nonce, PoS seals it for the slot leader)
+    fn produce_block(
+        &self,
+        slot: u64,
+        previous_hash: String,
+        transactions: Vec<Transaction>,
+        pre_state_root: Vec<u8>,
+        post_state_root: Vec<u8>,
+    ) -> Block;
+
+    /// Produce a block that also includes the stale blocks `ommers` (engines
+    /// without ommers leave them out)
+    fn produce_block_with_ommers(
+        &self,
+        slot: u64,
+        previous_hash: String,
+        transactions: Vec<Transaction>,
+        pre_state_root: Vec<u8>,
+        post_state_root: Vec<u8>,
+        _ommers: Vec<OmmerHeader>,
+    ) -> Block {
+        self.produce_block(slot, previous_hash, transactions, pre_state_root, post_state_root)
+    }
 
     fn validate_block(
         &self,
@@ -14,6 +45,29 @@ pub trait ConsensusEngine {
         pre_state_root: Vec<u8>,
         actual_post_state_root: Vec<u8>,
     ) -> bool;
+
+    /// Validator entitled to propose the block at `slot`, or None when any
+    /// node may (PoW, or PoS without validators)
+    fn slot_leader(&self, _slot: u64) -> Option<String> {
+        None
+    }
+
+    /// Replace the validator set at an epoch boundary (no-op for engines without one)
+    fn update_validators(&mut self, _validators: HashMap<String, u64>) {}
+
+    /// Draw slot leaders from the random seed of the new chain tip (no-op for
+    /// engines without leaders)
+    fn update_randomness(&mut self, _seed: [u8; 32]) {}
+
+    /// Produce and validate the next blocks on top of `tip` (no-op for
+    /// engines that do not retarget from the parent block)
+    fn update_tip(&mut self, _tip: Option<&Block>) {}
+
+    /// Add or remove an authority as decided by governance (engines
+    /// without authorities reject it)
+    fn apply_authority_change(&mut self, _change: &AuthorityChange) -> Result<(), String> {
+        Err("Consensus engine has no authorities".to_string())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -23,23 +77,30 @@ pub enum ConsensusType {
     PoA,
 }
 
-pub fn get_engine(consensus_type: ConsensusType) -> Box<dyn ConsensusEngine> {
+/// Engine of the given type, configured from `config`
+pub fn get_engine(consensus_type: ConsensusType, config: &ConsensusConfig) -> Box<dyn ConsensusEngine> {
     match consensus_type {
-        ConsensusType::PoW => Box::new(PoWConsensus::new()),
+        ConsensusType::PoW => Box::new(PoWConsensus::new(Retarget::from_config(config))),
+        ConsensusType::PoS => Box::new(PoSConsensus::new(validator_set(consensus_type, config))),
+        ConsensusType::PoA => Box::new(PoAConsensus::new(config.poa_validators.clone())),
+    }
+}
+
+/// Stake-weighted validator set for the given engine (empty for PoW, the
+/// configured authorities with equal weight for PoA)
+pub fn validator_set(consensus_type: ConsensusType, config: &ConsensusConfig) -> HashMap<String, u64> {
+    let mut validators = HashMap::new();
+    match consensus_type {
+        ConsensusType::PoW => {}
         ConsensusType::PoS => {
-            let mut validators = HashMap::new();
             validators.insert("Alice".to_string(), 100);
             validators.insert("Bob".to_string(), 200);
-            Box::new(PoSConsensus::new(validators))
         }
         ConsensusType::PoA => {
-            // PoA uses PoS engine with authority-based validator set
-            // In production, validators would be loaded from config
-            let mut validators = HashMap::new();
-            validators.insert("alice".to_string(), 100);
-            validators.insert("bob".to_string(), 100);
-            validators.insert("charlie".to_string(), 100);
-            Box::new(PoSConsensus::new(validators))
+            for authority in &config.poa_validators {
+                validators.insert(authority.clone(), 100);
+            }
         }
     }
-}
\ No newline at end of file
+    validators
+}
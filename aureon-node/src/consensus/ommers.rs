@@ -0,0 +1,260 @@
+//! Ommers: Stale PoW Blocks
+//!
+//! In PoW mode two miners can find a block on the same parent at nearly the
+//! same time. The fork choice keeps the block it saw first and the other
+//! goes stale. Rather than dropping it, the importer keeps its header, and
+//! the next blocks may include up to `MAX_OMMERS` such headers as ommers. An
+//! ommer needs a valid proof of work and a parent in the chain, may be at
+//! most `MAX_OMMER_DEPTH` blocks behind the block including it, and may be
+//! included only once. Its difficulty may not undercut the lowest its
+//! parent's retarget allows, so cheap blocks cannot pass as ommers. With ommer rewards enabled its miner still earns
+//! part of the block reward (see `emission`), so publishing a found block
+//! right away pays better than withholding it.
+
+use crate::chain_handle::ChainHandle;
+use crate::chain_store::GENESIS_HASH;
+use crate::consensus::{difficulty, pow};
+use crate::types::{tx_root, Block};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+
+/// Ommers a block may include
+pub const MAX_OMMERS: usize = 2;
+
+/// How many blocks an ommer may be behind the block including it
+pub const MAX_OMMER_DEPTH: u64 = 6;
+
+/// Header of a stale block, enough to check its proof of work
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct OmmerHeader {
+    pub hash: String,
+    pub previous_hash: String,
+    /// Height the stale block was mined at
+    pub height: u64,
+    pub slot: u64,
+    pub nonce: u64,
+    pub tx_root: Vec<u8>,
+    pub post_state_root: Vec<u8>,
+    /// Commitment to the stale block's own ommers (empty when it has none)
+    pub ommers_hash: Vec<u8>,
+    /// Proposer of the stale block, credited with its ommer reward
+    pub miner: String,
+    /// Difficulty the stale block was mined at
+    pub difficulty: u64,
+}
+
+impl OmmerHeader {
+    /// Header of `block`, mined at `height`
+    pub fn of(block: &Block, height: u64) -> Self {
+        OmmerHeader {
+            hash: block.hash.clone(),
+            previous_hash: block.previous_hash.clone(),
+            height,
+            slot: block.slot,
+            nonce: block.nonce,
+            tx_root: tx_root(&block.transactions),
+            post_state_root: block.post_state_root.clone(),
+            ommers_hash: ommers_hash(&block.ommers),
+            miner: block.proposer.clone(),
+            difficulty: block.difficulty,
+        }
+    }
+
+    /// Whether the header's hash is its proof of work
+    pub fn has_valid_pow(&self) -> bool {
+        let hash = pow::pow_hash(
+            self.slot,
+            &self.tx_root,
+            &self.previous_hash,
+            self.nonce,
+            &self.post_state_root,
+            &self.ommers_hash,
+            self.difficulty,
+        );
+        difficulty::meets_difficulty(&hash, self.difficulty) && hash == self.hash
+    }
+}
+
+/// Commitment to `ommers` covered by the proof of work (empty when there are none)
+pub fn ommers_hash(ommers: &[OmmerHeader]) -> Vec<u8> {
+    if ommers.is_empty() {
+        return Vec::new();
+    }
+    let mut hasher = Sha256::new();
+    for ommer in ommers {
+        hasher.update(ommer.hash.as_bytes());
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Hashes of the ommers included by the blocks an ommer of the block at
+/// `height` could also have been included by
+fn included_ommers(chain: &ChainHandle, height: u64) -> Result<HashSet<String>, String> {
+    let mut included = HashSet::new();
+    for earlier in height.saturating_sub(MAX_OMMER_DEPTH)..height {
+        if let Some(block) = chain.get_block_by_height(earlier)? {
+            included.extend(block.ommers.into_iter().map(|ommer| ommer.hash));
+        }
+    }
+    Ok(included)
+}
+
+/// Check one ommer of the block at `height`
+fn check_ommer(ommer: &OmmerHeader, height: u64, chain: &ChainHandle, included: &HashSet<String>) -> Result<(), String> {
+    if ommer.height >= height || height - ommer.height > MAX_OMMER_DEPTH {
+        return Err(format!(
+            "Ommer {} at height {} is not within {} blocks below {}",
+            ommer.hash, ommer.height, MAX_OMMER_DEPTH, height
+        ));
+    }
+    let parent = match ommer.height {
+        0 if ommer.previous_hash == GENESIS_HASH => None,
+        0 => return Err(format!("Ommer {} does not branch off the chain at height 0", ommer.hash)),
+        _ => match chain.get_block_by_height(ommer.height - 1)? {
+            Some(parent) if parent.hash == ommer.previous_hash => Some(parent),
+            _ => return Err(format!("Ommer {} does not branch off the chain at height {}", ommer.hash, ommer.height)),
+        },
+    };
+    let min_difficulty = parent.map_or(1, |parent| difficulty::min_next(parent.difficulty));
+    if ommer.difficulty < min_difficulty {
+        return Err(format!(
+            "Ommer {} difficulty {} is below the minimum {}",
+            ommer.hash, ommer.difficulty, min_difficulty
+        ));
+    }
+    if chain.contains(&ommer.hash) {
+        return Err(format!("Ommer {} is part of the chain", ommer.hash));
+    }
+    if included.contains(&ommer.hash) {
+        return Err(format!("Ommer {} was already included", ommer.hash));
+    }
+    if !ommer.has_valid_pow() {
+        return Err(format!("Ommer {} has no valid proof of work", ommer.hash));
+    }
+    Ok(())
+}
+
+/// Check the ommers of `block`, to be committed at `height` on top of `chain`
+pub fn check(block: &Block, height: u64, chain: &ChainHandle) -> Result<(), String> {
+    if block.ommers.is_empty() {
+        return Ok(());
+    }
+    if block.ommers.len() > MAX_OMMERS {
+        return Err(format!("Block includes {} ommers, at most {} are allowed", block.ommers.len(), MAX_OMMERS));
+    }
+    let mut included = included_ommers(chain, height)?;
+    for ommer in &block.ommers {
+        check_ommer(ommer, height, chain, &included)?;
+        included.insert(ommer.hash.clone());
+    }
+    Ok(())
+}
+
+/// Stale blocks seen by this node, by the height they were mined at
+#[derive(Default)]
+pub struct StaleBlocks {
+    by_height: BTreeMap<u64, Vec<OmmerHeader>>,
+}
+
+impl StaleBlocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `ommer` for inclusion in a later block; false if already kept
+    pub fn record(&mut self, ommer: OmmerHeader) -> bool {
+        let kept = self.by_height.entry(ommer.height).or_default();
+        if kept.iter().any(|known| known.hash == ommer.hash) {
+            return false;
+        }
+        kept.push(ommer);
+        true
+    }
+
+    /// Whether the stale block `hash` was kept
+    pub fn contains(&self, hash: &str) -> bool {
+        self.by_height.values().flatten().any(|ommer| ommer.hash == hash)
+    }
+
+    /// Up to `MAX_OMMERS` stale blocks the next block of `chain` may include
+    ///
+    /// Stale blocks too deep to be included any more are forgotten.
+    pub fn candidates(&mut self, chain: &ChainHandle) -> Vec<OmmerHeader> {
+        let height = chain.len();
+        self.by_height = self.by_height.split_off(&height.saturating_sub(MAX_OMMER_DEPTH));
+        let Ok(included) = included_ommers(chain, height) else {
+            return Vec::new();
+        };
+        self.by_height
+            .values()
+            .flatten()
+            .filter(|ommer| check_ommer(ommer, height, chain, &included).is_ok())
+            .take(MAX_OMMERS)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::difficulty::Retarget;
+    use crate::consensus::pow::PoWConsensus;
+    use crate::consensus::ConsensusEngine;
+    use crate::db::Db;
+    use crate::types::Transaction;
+    use std::sync::Arc;
+
+    fn mine(previous_hash: &str, slot: u64, ommers: Vec<OmmerHeader>) -> Block {
+        let transactions = vec![Transaction::transfer("alice".into(), "bob".into(), slot)];
+        PoWConsensus::new(Retarget::default()).produce_block_with_ommers(slot, previous_hash.to_string(), transactions, vec![], vec![], ommers)
+    }
+
+    #[test]
+    fn test_stale_blocks_become_ommers_once() {
+        let path = std::env::temp_dir().join(format!("aureon_ommers_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let chain = ChainHandle::open(Arc::new(Db::open(path.to_str().unwrap()))).unwrap();
+        let genesis = mine(GENESIS_HASH, 0, vec![]);
+        chain.append(&genesis).unwrap();
+        let first = mine(&genesis.hash, 1, vec![]);
+        let competing = mine(&genesis.hash, 2, vec![]);
+        chain.append(&first).unwrap();
+
+        let mut stale = StaleBlocks::new();
+        assert!(stale.record(OmmerHeader::of(&competing, 1)));
+        assert!(!stale.record(OmmerHeader::of(&competing, 1)));
+        assert!(stale.contains(&competing.hash));
+        // Blocks of the chain are not ommers
+        stale.record(OmmerHeader::of(&first, 1));
+        let ommers = stale.candidates(&chain);
+        assert_eq!(ommers, vec![OmmerHeader::of(&competing, 1)]);
+
+        let including = mine(&first.hash, 3, ommers.clone());
+        assert!(PoWConsensus::new(Retarget::default()).validate_block(&including, vec![], vec![]));
+        check(&including, 2, &chain).unwrap();
+        chain.append(&including).unwrap();
+        // Included ommers cannot be included again
+        assert!(stale.candidates(&chain).is_empty());
+        let again = mine(&including.hash, 4, ommers);
+        assert!(check(&again, 3, &chain).unwrap_err().contains("already included"));
+
+        // Forged proofs of work and ommers of unknown parents are rejected
+        let mut forged = OmmerHeader::of(&mine(&genesis.hash, 8, vec![]), 1);
+        forged.nonce += 1;
+        assert!(check(&mine(&including.hash, 5, vec![forged]), 3, &chain).unwrap_err().contains("proof of work"));
+        let orphan = OmmerHeader::of(&mine("unknown", 6, vec![]), 1);
+        assert!(check(&mine(&including.hash, 7, vec![orphan]), 3, &chain).unwrap_err().contains("branch off"));
+        // Ommers cannot undercut the difficulty their parent allows
+        let easy = PoWConsensus::new(Retarget { initial: 1, ..Retarget::default() })
+            .produce_block(9, genesis.hash.clone(), vec![], vec![], vec![]);
+        let easy = OmmerHeader::of(&easy, 1);
+        assert!(easy.has_valid_pow());
+        assert!(check(&mine(&including.hash, 10, vec![easy]), 3, &chain).unwrap_err().contains("below the minimum"));
+
+        drop(chain);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -0,0 +1,189 @@
+//! Proof of Authority
+//!
+//! A fixed list of authorities, taken from the chain spec, takes turns
+//! proposing: the authority at index `slot % len` leads each slot, so
+//! every node derives the same schedule without randomness or stake. A block
+//! must name the scheduled authority as its proposer and carry its proposal
+//! signature; the importer checks that signature against the key registered
+//! for the authority. Authorities join and leave only through executed
+//! governance proposals, never through staking.
+
+use sha2::{Digest, Sha256};
+use crate::community_governance::AuthorityChange;
+use crate::consensus::ConsensusEngine;
+use crate::types::{tx_root, Block, Transaction};
+
+/// Length of an Ed25519 proposal signature
+const SIGNATURE_LEN: usize = 64;
+
+pub struct PoAConsensus {
+    /// Authorities in schedule order
+    authorities: Vec<String>,
+}
+
+impl PoAConsensus {
+    pub fn new(authorities: Vec<String>) -> Self {
+        Self { authorities }
+    }
+
+    /// Authorities in schedule order
+    pub fn authorities(&self) -> &[String] {
+        &self.authorities
+    }
+
+    fn hash_block_content(
+        slot: u64,
+        transactions: &[Transaction],
+        previous_hash: &str,
+        authority: &str,
+        state_root: &[u8],
+    ) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(slot.to_le_bytes());
+        hasher.update(tx_root(transactions));
+        hasher.update(previous_hash.as_bytes());
+        hasher.update(authority.as_bytes());
+        hasher.update(state_root);
+        hex::encode(hasher.finalize())
+    }
+}
+
+impl ConsensusEngine for PoAConsensus {
+    fn produce_block(
+        &self,
+        slot: u64,
+        previous_hash: String,
+        transactions: Vec<Transaction>,
+        pre_state_root: Vec<u8>,
+        post_state_root: Vec<u8>,
+    ) -> Block {
+        let authority = self.slot_leader(slot).unwrap_or_default();
+        let hash = Self::hash_block_content(slot, &transactions, &previous_hash, &authority, &post_state_root);
+
+        Block {
+            transactions,
+            previous_hash,
+            nonce: 0,
+            hash,
+            pre_state_root,
+            post_state_root,
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }
+    }
+
+    fn validate_block(
+        &self,
+        block: &Block,
+        _pre_state_root: Vec<u8>,
+        actual_post_state_root: Vec<u8>,
+    ) -> bool {
+        // Only the scheduled authority may propose, and it must have signed
+        let Some(authority) = self.slot_leader(block.slot) else {
+            return false;
+        };
+        if block.proposer != authority || block.proposer_signature.len() != SIGNATURE_LEN {
+            return false;
+        }
+
+        let expected_hash = Self::hash_block_content(
+            block.slot,
+            &block.transactions,
+            &block.previous_hash,
+            &authority,
+            &actual_post_state_root,
+        );
+        expected_hash == block.hash && block.post_state_root == actual_post_state_root
+    }
+
+    /// Round-robin over the authorities
+    fn slot_leader(&self, slot: u64) -> Option<String> {
+        if self.authorities.is_empty() {
+            return None;
+        }
+        Some(self.authorities[(slot % self.authorities.len() as u64) as usize].clone())
+    }
+
+    fn apply_authority_change(&mut self, change: &AuthorityChange) -> Result<(), String> {
+        match change {
+            AuthorityChange::Add(authority) => {
+                if self.authorities.contains(authority) {
+                    return Err(format!("{} is already an authority", authority));
+                }
+                self.authorities.push(authority.clone());
+            }
+            AuthorityChange::Remove(authority) => {
+                let index = self
+                    .authorities
+                    .iter()
+                    .position(|known| known == authority)
+                    .ok_or_else(|| format!("{} is not an authority", authority))?;
+                if self.authorities.len() == 1 {
+                    return Err("Cannot remove the last authority".to_string());
+                }
+                self.authorities.remove(index);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    fn engine() -> PoAConsensus {
+        PoAConsensus::new(vec!["alice".to_string(), "bob".to_string(), "carol".to_string()])
+    }
+
+    #[test]
+    fn test_authorities_take_turns_and_sign() {
+        let engine = engine();
+        let leaders: Vec<_> = (0..4).map(|slot| engine.slot_leader(slot).unwrap()).collect();
+        assert_eq!(leaders, ["alice", "bob", "carol", "alice"]);
+        assert_eq!(PoAConsensus::new(vec![]).slot_leader(0), None);
+
+        let mut block = engine.produce_block(4, "GENESIS".to_string(), vec![], vec![], vec![1]);
+        // Unsigned blocks and blocks by authorities out of turn are rejected
+        assert!(!engine.validate_block(&block, vec![], vec![1]));
+        block.sign_proposal("alice", &NodeIdentity::from_secret([1u8; 32])).unwrap();
+        assert!(!engine.validate_block(&block, vec![], vec![1]));
+        block.sign_proposal("bob", &NodeIdentity::from_secret([2u8; 32])).unwrap();
+        assert!(engine.validate_block(&block, vec![], vec![1]));
+        assert!(!engine.validate_block(&block, vec![], vec![2]));
+
+        // The slot is covered by the hash
+        block.slot = 7;
+        block.sign_proposal("bob", &NodeIdentity::from_secret([2u8; 32])).unwrap();
+        assert!(!engine.validate_block(&block, vec![], vec![1]));
+    }
+
+    #[test]
+    fn test_authority_changes() {
+        let mut engine = engine();
+        engine.apply_authority_change(&AuthorityChange::Add("dave".to_string())).unwrap();
+        assert_eq!(engine.slot_leader(3).as_deref(), Some("dave"));
+        assert!(engine.apply_authority_change(&AuthorityChange::Add("dave".to_string())).is_err());
+
+        engine.apply_authority_change(&AuthorityChange::Remove("alice".to_string())).unwrap();
+        assert_eq!(engine.authorities(), ["bob", "carol", "dave"]);
+        assert!(engine.apply_authority_change(&AuthorityChange::Remove("alice".to_string())).is_err());
+
+        // Stake does not move authorities
+        engine.update_validators([("eve".to_string(), 1_000)].into_iter().collect());
+        assert_eq!(engine.authorities(), ["bob", "carol", "dave"]);
+
+        let mut single = PoAConsensus::new(vec!["alice".to_string()]);
+        assert!(single.apply_authority_change(&AuthorityChange::Remove("alice".to_string())).unwrap_err().contains("last"));
+    }
+}
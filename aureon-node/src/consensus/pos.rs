@@ -1,34 +1,35 @@
 use std::collections::HashMap;
 use sha2::{Sha256, Digest};
-use crate::types::{Block, Transaction};
+use crate::types::{tx_root, Block, Transaction};
 use crate::consensus::ConsensusEngine;
+use crate::randomness::GENESIS_SEED;
 
 pub struct PoSConsensus {
     validators: HashMap<String, u64>,
+    /// Random seed of the chain tip
+    seed: [u8; 32],
 }
 
 impl PoSConsensus {
     pub fn new(validators: HashMap<String, u64>) -> Self {
-        Self { validators }
+        Self { validators, seed: GENESIS_SEED }
     }
 
-    fn select_validator(&self) -> String {
-        self.validators
-            .iter()
-            .max_by_key(|&(_, stake)| stake)
-            .map(|(name, _)| name.clone())
-            .unwrap_or_else(|| "DefaultValidator".to_string())
+    /// Slot leader, or a placeholder when there are no validators
+    fn leader(&self, slot: u64) -> String {
+        self.slot_leader(slot).unwrap_or_else(|| "DefaultValidator".to_string())
     }
 
     fn hash_block_content(
+        slot: u64,
         transactions: &Vec<Transaction>,
         previous_hash: &str,
         validator: &str,
         state_root: &[u8],
     ) -> String {
         let mut hasher = Sha256::new();
-        let tx_string: String = transactions.iter().map(|tx| format!("{:?}", tx)).collect();
-        hasher.update(tx_string.as_bytes());
+        hasher.update(slot.to_le_bytes());
+        hasher.update(tx_root(transactions));
         hasher.update(previous_hash.as_bytes());
         hasher.update(validator.as_bytes());
         hasher.update(state_root);
@@ -40,14 +41,16 @@ impl PoSConsensus {
 impl ConsensusEngine for PoSConsensus {
     fn produce_block(
         &self,
+        slot: u64,
+        previous_hash: String,
         transactions: Vec<Transaction>,
         pre_state_root: Vec<u8>,
         post_state_root: Vec<u8>,
     ) -> Block {
-        let previous_hash = "GENESIS".to_string();
-        let validator = self.select_validator();
+        let validator = self.leader(slot);
 
         let hash = Self::hash_block_content(
+            slot,
             &transactions,
             &previous_hash,
             &validator,
@@ -61,6 +64,17 @@ impl ConsensusEngine for PoSConsensus {
             hash,
             pre_state_root,
             post_state_root,
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
         }
     }
 
@@ -70,9 +84,13 @@ impl ConsensusEngine for PoSConsensus {
         _pre_state_root: Vec<u8>,
         actual_post_state_root: Vec<u8>,
     ) -> bool {
-        let validator = self.select_validator();
+        let validator = self.leader(block.slot);
+        if !block.proposer.is_empty() && block.proposer != validator {
+            return false;
+        }
 
         let expected_hash = Self::hash_block_content(
+            block.slot,
             &block.transactions,
             &block.previous_hash,
             &validator,
@@ -89,4 +107,100 @@ impl ConsensusEngine for PoSConsensus {
 
         true
     }
-}
\ No newline at end of file
+
+    /// Stake-weighted choice seeded by the tip's random seed and the slot
+    /// number, so every node elects the same leader but nobody can tell
+    /// before the tip is produced
+    fn slot_leader(&self, slot: u64) -> Option<String> {
+        let total: u64 = self.validators.values().sum();
+        if total == 0 {
+            return None;
+        }
+        let mut validators: Vec<(&String, &u64)> = self.validators.iter().collect();
+        validators.sort();
+        let mut hasher = Sha256::new();
+        hasher.update(self.seed);
+        hasher.update(slot.to_le_bytes());
+        let seed = hasher.finalize();
+        let mut target = u64::from_le_bytes(seed[..8].try_into().unwrap()) % total;
+        for (name, stake) in validators {
+            if target < *stake {
+                return Some(name.clone());
+            }
+            target -= stake;
+        }
+        None
+    }
+
+    fn update_validators(&mut self, validators: HashMap<String, u64>) {
+        self.validators = validators;
+    }
+
+    fn update_randomness(&mut self, seed: [u8; 32]) {
+        self.seed = seed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_leader_is_stake_weighted_and_checked() {
+        let engine = PoSConsensus::new(HashMap::from([("alice".to_string(), 100), ("bob".to_string(), 300)]));
+        let bob_slots = (0..1000).filter(|&slot| engine.slot_leader(slot).as_deref() == Some("bob")).count();
+        assert!((650..850).contains(&bob_slots), "bob led {} of 1000 slots", bob_slots);
+        assert_eq!(PoSConsensus::new(HashMap::new()).slot_leader(7), None);
+
+        let mut block = engine.produce_block(7, "GENESIS".to_string(), vec![], vec![], vec![1]);
+        assert_eq!(block.slot, 7);
+        let leader = engine.slot_leader(7).unwrap();
+        block.proposer = leader.clone();
+        assert!(engine.validate_block(&block, vec![], vec![1]));
+
+        // The proposer must be the slot's leader, and the slot is covered by the hash
+        block.proposer = if leader == "alice" { "bob" } else { "alice" }.to_string();
+        assert!(!engine.validate_block(&block, vec![], vec![1]));
+        block.proposer = leader;
+        block.slot = 8;
+        assert!(!engine.validate_block(&block, vec![], vec![1]));
+    }
+
+    #[test]
+    fn test_slot_leaders_change_with_random_seed() {
+        let validators: HashMap<String, u64> = (0..10).map(|i| (format!("v{}", i), 10)).collect();
+        let mut engine = PoSConsensus::new(validators);
+        let leaders = |engine: &PoSConsensus| (0..20).map(|slot| engine.slot_leader(slot)).collect::<Vec<_>>();
+        let before = leaders(&engine);
+        engine.update_randomness([7; 32]);
+        assert_ne!(leaders(&engine), before);
+        assert_eq!(leaders(&engine), leaders(&engine));
+    }
+
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn prop_block_headers_are_bound_by_the_hash(
+            slot in 0u64..1_000_000,
+            previous_hash in "[0-9a-f]{0,64}",
+            amounts in proptest::collection::vec(any::<u64>(), 0..4),
+            post_state_root in proptest::collection::vec(any::<u8>(), 0..32),
+            mutation in 0usize..5,
+        ) {
+            let engine = PoSConsensus::new(HashMap::from([("alice".to_string(), 100), ("bob".to_string(), 300)]));
+            let transactions = amounts.iter().map(|&amount| Transaction::stake("alice".to_string(), amount)).collect();
+            let mut block = engine.produce_block(slot, previous_hash, transactions, vec![], post_state_root.clone());
+            prop_assert!(engine.validate_block(&block, vec![], post_state_root.clone()));
+
+            match mutation {
+                0 => block.slot += 1,
+                1 => block.previous_hash.push('0'),
+                2 => block.transactions.push(Transaction::stake("bob".to_string(), 1)),
+                3 => block.post_state_root.push(0),
+                _ => block.hash.push('0'),
+            }
+            prop_assert!(!engine.validate_block(&block, vec![], post_state_root));
+        }
+    }
+}
@@ -1,44 +1,79 @@
 use sha2::{Digest, Sha256};
-use crate::types::{Block, Transaction};
+use crate::types::{tx_root, Block, Transaction};
+use crate::consensus::difficulty::{self, Retarget};
+use crate::consensus::ommers::{self, OmmerHeader};
 use crate::consensus::ConsensusEngine;
 
-pub struct PoWConsensus;
+pub struct PoWConsensus {
+    retarget: Retarget,
+    /// Block the next block builds on (None before the first block)
+    tip: Option<Block>,
+}
 
 impl PoWConsensus {
-    pub fn new() -> Self {
-        Self
+    /// Engine retargeting the difficulty with `retarget`
+    pub fn new(retarget: Retarget) -> Self {
+        PoWConsensus { retarget, tip: None }
     }
 
-    fn hash_block_content(
-        transactions: &Vec<Transaction>,
-        previous_hash: &str,
-        nonce: u64,
-        state_root: &[u8],
-    ) -> String {
-        let mut hasher = Sha256::new();
-        let tx_string: String = transactions.iter().map(|tx| format!("{:?}", tx)).collect();
-        hasher.update(tx_string.as_bytes());
-        hasher.update(previous_hash.as_bytes());
-        hasher.update(&nonce.to_le_bytes());
-        hasher.update(state_root);
-        let result = hasher.finalize();
-        hex::encode(result)
+    /// Difficulty a block at `slot` on top of the tip must meet
+    pub fn next_difficulty(&self, slot: u64) -> u64 {
+        self.retarget.next(self.tip.as_ref(), slot)
     }
 }
 
+/// Hash a block's nonce is mined for; ommers are only committed to when
+/// there are any, so blocks without them hash as before
+pub fn pow_hash(
+    slot: u64,
+    tx_root: &[u8],
+    previous_hash: &str,
+    nonce: u64,
+    state_root: &[u8],
+    ommers_hash: &[u8],
+    difficulty: u64,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(slot.to_le_bytes());
+    hasher.update(tx_root);
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(nonce.to_le_bytes());
+    hasher.update(state_root);
+    hasher.update(ommers_hash);
+    hasher.update(difficulty.to_le_bytes());
+    let result = hasher.finalize();
+    hex::encode(result)
+}
+
 impl ConsensusEngine for PoWConsensus {
     fn produce_block(
         &self,
+        slot: u64,
+        previous_hash: String,
         transactions: Vec<Transaction>,
         pre_state_root: Vec<u8>,
         post_state_root: Vec<u8>,
     ) -> Block {
-        let previous_hash = "GENESIS".to_string();
+        self.produce_block_with_ommers(slot, previous_hash, transactions, pre_state_root, post_state_root, vec![])
+    }
+
+    fn produce_block_with_ommers(
+        &self,
+        slot: u64,
+        previous_hash: String,
+        transactions: Vec<Transaction>,
+        pre_state_root: Vec<u8>,
+        post_state_root: Vec<u8>,
+        ommers: Vec<OmmerHeader>,
+    ) -> Block {
+        let tx_root = tx_root(&transactions);
+        let ommers_hash = ommers::ommers_hash(&ommers);
+        let difficulty = self.next_difficulty(slot);
         let mut nonce = 0;
 
         loop {
-            let hash = Self::hash_block_content(&transactions, &previous_hash, nonce, &post_state_root);
-            if hash.starts_with("0000") {
+            let hash = pow_hash(slot, &tx_root, &previous_hash, nonce, &post_state_root, &ommers_hash, difficulty);
+            if difficulty::meets_difficulty(&hash, difficulty) {
                 return Block {
                     transactions,
                     previous_hash,
@@ -46,6 +81,17 @@ impl ConsensusEngine for PoWConsensus {
                     hash,
                     pre_state_root,
                     post_state_root,
+                    evidence: vec![],
+                    logs_bloom: vec![],
+                    logs_root: vec![],
+                    state_proof: None,
+                    slot,
+                    proposer: String::new(),
+                    proposer_signature: vec![],
+                    random_reveal: vec![],
+                    random_seed: vec![],
+                    ommers,
+                    difficulty,
                 };
             }
             nonce += 1;
@@ -58,15 +104,20 @@ impl ConsensusEngine for PoWConsensus {
         _pre_state_root: Vec<u8>,
         actual_post_state_root: Vec<u8>,
     ) -> bool {
-        if !block.hash.starts_with("0000") {
+        if block.difficulty != self.next_difficulty(block.slot)
+            || !difficulty::meets_difficulty(&block.hash, block.difficulty)
+        {
             return false;
         }
 
-        let expected_hash = Self::hash_block_content(
-            &block.transactions,
+        let expected_hash = pow_hash(
+            block.slot,
+            &tx_root(&block.transactions),
             &block.previous_hash,
             block.nonce,
             &actual_post_state_root,
+            &ommers::ommers_hash(&block.ommers),
+            block.difficulty,
         );
 
         if expected_hash != block.hash {
@@ -79,4 +130,8 @@ impl ConsensusEngine for PoWConsensus {
 
         true
     }
-}
\ No newline at end of file
+
+    fn update_tip(&mut self, tip: Option<&Block>) {
+        self.tip = tip.cloned();
+    }
+}
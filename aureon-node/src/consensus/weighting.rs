@@ -0,0 +1,170 @@
+//! Stake-Weighted Leader Selection
+//!
+//! Connects the staking system to the consensus engine. The staking
+//! transactions of every committed block bond and unbond stake right away,
+//! but the engine's leader weights only change at epoch boundaries, where the
+//! epoch manager turns the bonded stake into the next epoch's validator set.
+//! Stake changed mid-epoch therefore counts from the next epoch on, and every
+//! node elects the same leaders for all slots of an epoch. The shard map,
+//! when there is one, is recomputed from the same validator set.
+
+use crate::chain_store::ChainStore;
+use crate::consensus::epoch::{EpochManager, EpochTransition};
+use crate::consensus::ConsensusEngine;
+use crate::shard_coordinator::ShardMap;
+use crate::slashing::SlashingModule;
+use crate::types::Transaction;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Keeps the engine's validator weights in step with bonded stake
+pub struct StakeWeighting {
+    slashing: Arc<Mutex<SlashingModule>>,
+    epochs: EpochManager,
+    engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    shards: Option<Arc<RwLock<ShardMap>>>,
+}
+
+impl StakeWeighting {
+    /// Weight `engine`'s leaders by the current validator set of `epochs`
+    pub fn new(
+        slashing: Arc<Mutex<SlashingModule>>,
+        epochs: EpochManager,
+        engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    ) -> Self {
+        engine.lock().unwrap().update_validators(epochs.validators().clone());
+        StakeWeighting { slashing, epochs, engine, shards: None }
+    }
+
+    /// Rebalance `shards` to the validator set of every epoch, starting
+    /// with the current one
+    pub fn with_shard_map(mut self, shards: Arc<RwLock<ShardMap>>) -> Self {
+        shards.write().unwrap().rebalance(self.epochs.epoch(), self.epochs.validators());
+        self.shards = Some(shards);
+        self
+    }
+
+    /// Record the staking transactions of the block committed at `height`
+    ///
+    /// At the end of an epoch the epoch's rewards are distributed and the
+    /// engine switches to the next epoch's validators, weighted by their
+    /// bonded stake.
+    pub fn on_block(&mut self, height: u64, transactions: &[Transaction]) -> Option<EpochTransition> {
+        let bonded = {
+            let mut slashing = self.slashing.lock().unwrap();
+            slashing.apply_staking_transactions(transactions, height);
+            if self.epochs.is_epoch_end(height) {
+                slashing.distribute_epoch_rewards(self.epochs.validators().keys(), self.epochs.length());
+            }
+            slashing.bonded_stakes()
+        };
+        let transition = self.epochs.on_block(height, &bonded);
+        if let Some(transition) = &transition {
+            self.engine.lock().unwrap().update_validators(transition.validators.clone());
+            if let Some(shards) = &self.shards {
+                let mut shards = shards.write().unwrap();
+                for migration in shards.rebalance(transition.epoch, &transition.validators) {
+                    tracing::info!(
+                        from = migration.from.0,
+                        to = migration.to.0,
+                        start = %format!("{:016x}", migration.range.start),
+                        end = %format!("{:016x}", migration.range.end),
+                        "shard key range migrated"
+                    );
+                }
+            }
+        }
+        for released in self.epochs.release_unbonded(height) {
+            tracing::info!(validator = %released.validator, amount = released.amount, "released unbonded stake");
+        }
+        transition
+    }
+
+    /// Re-apply the staking transactions and slashing evidence of the stored
+    /// blocks, so a restarted node weights leaders by the same stake as the
+    /// rest of the network, returning the number of blocks applied
+    pub fn replay(&mut self, chain: &ChainStore) -> Result<u64, String> {
+        let start = chain.history_start();
+        for height in start..chain.len() {
+            let block = chain.get_block(height)?.ok_or_else(|| format!("Missing stored block {}", height))?;
+            self.on_block(height, &block.transactions);
+            let mut slashing = self.slashing.lock().unwrap();
+            for evidence in &block.evidence {
+                if let Err(e) = slashing.apply_stored_evidence(evidence) {
+                    tracing::warn!(height, error = %e, "ignoring stored evidence");
+                }
+            }
+        }
+        Ok(chain.len().saturating_sub(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{EpochConfig, SlashingConfig};
+    use crate::consensus::pos::PoSConsensus;
+    use crate::incentive_programs::StakingSystem;
+    use crate::types::TransactionPayload;
+    use std::collections::HashMap;
+
+    fn slashing(genesis: &HashMap<String, u64>) -> Arc<Mutex<SlashingModule>> {
+        let mut staking = StakingSystem::new(0.0);
+        for (validator, stake) in genesis {
+            staking.stake(validator.clone(), *stake as u128, 0, 0);
+        }
+        Arc::new(Mutex::new(SlashingModule::new(SlashingConfig::default(), Arc::new(Mutex::new(staking)))))
+    }
+
+    #[test]
+    fn test_leader_weights_follow_bonded_stake_at_epoch_boundaries() {
+        let genesis = HashMap::from([("alice".to_string(), 100)]);
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(HashMap::new()));
+        let engine = Arc::new(Mutex::new(engine));
+        let slashing = slashing(&genesis);
+        let epochs = EpochManager::new(EpochConfig { length: 4, ..EpochConfig::default() }, 10, 1, genesis);
+        let mut weighting = StakeWeighting::new(slashing, epochs, engine.clone());
+        let leaders = |engine: &Mutex<Box<dyn ConsensusEngine>>| {
+            let engine = engine.lock().unwrap();
+            (0..400).filter(|&slot| engine.slot_leader(slot).as_deref() == Some("bob")).count()
+        };
+        assert_eq!(leaders(&engine), 0);
+
+        // Bob bonds three times alice's stake mid-epoch; it counts from the next epoch
+        let stake = Transaction {
+            payload: TransactionPayload::Stake { amount: 300 },
+            ..Transaction::transfer("bob".to_string(), String::new(), 0)
+        };
+        assert!(weighting.on_block(1, &[stake]).is_none());
+        assert!(weighting.on_block(2, &[]).is_none());
+        assert_eq!(leaders(&engine), 0);
+
+        let transition = weighting.on_block(3, &[]).unwrap();
+        assert_eq!(transition.entered, vec!["bob"]);
+        assert!((250..350).contains(&leaders(&engine)), "bob led {} of 400 slots", leaders(&engine));
+    }
+    #[test]
+    fn test_shard_map_is_rebalanced_at_epoch_boundaries() {
+        use crate::shard_coordinator::ShardCoordinator;
+
+        let genesis = HashMap::from([("alice".to_string(), 100)]);
+        let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(HashMap::new()));
+        let slashing = slashing(&genesis);
+        let epochs = EpochManager::new(EpochConfig { length: 2, ..EpochConfig::default() }, 10, 1, genesis);
+        let shards = Arc::new(RwLock::new(
+            ShardMap::new(ShardCoordinator::with_shard_count(4)).with_validators_per_shard(1),
+        ));
+        let mut weighting =
+            StakeWeighting::new(slashing, epochs, Arc::new(Mutex::new(engine))).with_shard_map(shards.clone());
+        assert_eq!(shards.read().unwrap().coordinator().num_shards(), 1);
+
+        let stake = |from: &str| Transaction {
+            payload: TransactionPayload::Stake { amount: 100 },
+            ..Transaction::transfer(from.to_string(), String::new(), 0)
+        };
+        weighting.on_block(0, &[stake("bob"), stake("carol")]);
+        assert_eq!(shards.read().unwrap().coordinator().num_shards(), 1);
+        weighting.on_block(1, &[]);
+        let shards = shards.read().unwrap();
+        assert_eq!((shards.epoch(), shards.coordinator().num_shards()), (1, 3));
+    }
+}
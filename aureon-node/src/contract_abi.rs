@@ -0,0 +1,218 @@
+//! Contract ABI
+//!
+//! Deployers may register an ABI describing a contract's functions with
+//! their typed inputs and outputs. The API then calls contracts by function
+//! name: JSON arguments are encoded into the call input, and the bytes the
+//! contract returns through `set_output` are decoded back into JSON.
+//!
+//! Encoding: the call input is the function name followed by each argument
+//! in order, and the output is each output value in order. `u32`, `u64` and
+//! `i64` are little-endian, `bool` is one byte, and `string` and `bytes` are
+//! a little-endian `u32` length followed by their bytes. The contract SDK's
+//! `abi` module implements the guest side.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// Type of an ABI parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AbiType {
+    U32,
+    U64,
+    I64,
+    Bool,
+    String,
+    /// Hex-encoded in JSON
+    Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AbiParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: AbiType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AbiFunction {
+    pub name: String,
+    #[serde(default)]
+    pub inputs: Vec<AbiParam>,
+    #[serde(default)]
+    pub outputs: Vec<AbiParam>,
+}
+
+/// Functions a contract exposes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContractAbi {
+    pub functions: Vec<AbiFunction>,
+}
+
+impl ContractAbi {
+    /// Reject ABIs declaring a function, or a function's parameter, twice
+    pub fn validate(&self) -> Result<(), String> {
+        let mut functions = HashSet::new();
+        for function in &self.functions {
+            if !functions.insert(function.name.as_str()) {
+                return Err(format!("Invalid ABI: function '{}' is declared twice", function.name));
+            }
+            for params in [&function.inputs, &function.outputs] {
+                let mut names = HashSet::new();
+                if let Some(param) = params.iter().find(|param| !names.insert(param.name.as_str())) {
+                    return Err(format!("Invalid ABI: '{}' has two parameters named '{}'", function.name, param.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn function(&self, name: &str) -> Result<&AbiFunction, String> {
+        self.functions
+            .iter()
+            .find(|function| function.name == name)
+            .ok_or_else(|| format!("Contract ABI has no function '{}'", name))
+    }
+}
+
+impl AbiFunction {
+    /// Call input for `args`, given as an array in order or an object by parameter name
+    pub fn encode_call(&self, args: &Value) -> Result<Vec<u8>, String> {
+        let values: Vec<&Value> = match args {
+            Value::Array(values) if values.len() == self.inputs.len() => values.iter().collect(),
+            Value::Array(values) => {
+                return Err(format!("'{}' takes {} arguments, got {}", self.name, self.inputs.len(), values.len()));
+            }
+            Value::Object(fields) => self
+                .inputs
+                .iter()
+                .map(|param| fields.get(&param.name).ok_or_else(|| format!("Missing argument '{}'", param.name)))
+                .collect::<Result<_, _>>()?,
+            Value::Null if self.inputs.is_empty() => Vec::new(),
+            _ => return Err("Arguments must be an array or an object".to_string()),
+        };
+        let mut input = Vec::new();
+        write_bytes(&mut input, self.name.as_bytes());
+        for (param, value) in self.inputs.iter().zip(values) {
+            encode_value(&mut input, param, value)?;
+        }
+        Ok(input)
+    }
+
+    /// Outputs in `output` as an object by parameter name
+    pub fn decode_output(&self, output: &[u8]) -> Result<Value, String> {
+        let mut reader = output;
+        let mut values = Map::new();
+        for param in &self.outputs {
+            let value = decode_value(&mut reader, param.kind)
+                .ok_or_else(|| format!("Output '{}' of '{}' is truncated", param.name, self.name))?;
+            values.insert(param.name.clone(), value);
+        }
+        Ok(Value::Object(values))
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn encode_value(out: &mut Vec<u8>, param: &AbiParam, value: &Value) -> Result<(), String> {
+    let invalid = || format!("Argument '{}' is not a valid {:?}", param.name, param.kind);
+    // Large integers may be given as decimal strings, since JSON numbers lose precision
+    let integer = |value: &Value| match value {
+        Value::String(s) => s.parse::<i128>().ok(),
+        value => value.as_i64().map(i128::from).or_else(|| value.as_u64().map(i128::from)),
+    };
+    match param.kind {
+        AbiType::U32 => {
+            let value = integer(value).and_then(|n| u32::try_from(n).ok()).ok_or_else(invalid)?;
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        AbiType::U64 => {
+            let value = integer(value).and_then(|n| u64::try_from(n).ok()).ok_or_else(invalid)?;
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        AbiType::I64 => {
+            let value = integer(value).and_then(|n| i64::try_from(n).ok()).ok_or_else(invalid)?;
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        AbiType::Bool => out.push(value.as_bool().ok_or_else(invalid)? as u8),
+        AbiType::String => write_bytes(out, value.as_str().ok_or_else(invalid)?.as_bytes()),
+        AbiType::Bytes => {
+            let hex_str = value.as_str().ok_or_else(invalid)?;
+            let bytes = hex::decode(hex_str.trim_start_matches("0x")).map_err(|_| invalid())?;
+            write_bytes(out, &bytes);
+        }
+    }
+    Ok(())
+}
+
+fn take<'a>(reader: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if reader.len() < len {
+        return None;
+    }
+    let (taken, rest) = reader.split_at(len);
+    *reader = rest;
+    Some(taken)
+}
+
+fn decode_value(reader: &mut &[u8], kind: AbiType) -> Option<Value> {
+    Some(match kind {
+        AbiType::U32 => Value::from(u32::from_le_bytes(take(reader, 4)?.try_into().ok()?)),
+        AbiType::U64 => Value::from(u64::from_le_bytes(take(reader, 8)?.try_into().ok()?)),
+        AbiType::I64 => Value::from(i64::from_le_bytes(take(reader, 8)?.try_into().ok()?)),
+        AbiType::Bool => Value::from(take(reader, 1)?[0] != 0),
+        AbiType::String | AbiType::Bytes => {
+            let len = u32::from_le_bytes(take(reader, 4)?.try_into().ok()?) as usize;
+            let bytes = take(reader, len)?;
+            match kind {
+                AbiType::String => Value::from(String::from_utf8_lossy(bytes).into_owned()),
+                _ => Value::from(hex::encode(bytes)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ABI: &str = r#"{"functions": [
+        {"name": "transfer",
+         "inputs": [{"name": "to", "type": "string"}, {"name": "amount", "type": "u64"}],
+         "outputs": [{"name": "ok", "type": "bool"}, {"name": "balance", "type": "u64"}, {"name": "memo", "type": "bytes"}]}
+    ]}"#;
+
+    #[test]
+    fn test_encodes_arguments_and_decodes_outputs() {
+        let abi: ContractAbi = serde_json::from_str(ABI).unwrap();
+        abi.validate().unwrap();
+        let transfer = abi.function("transfer").unwrap();
+
+        let expected = [
+            &8u32.to_le_bytes()[..],
+            b"transfer",
+            &3u32.to_le_bytes(),
+            b"bob",
+            &u64::MAX.to_le_bytes(),
+        ]
+        .concat();
+        assert_eq!(transfer.encode_call(&json!(["bob", u64::MAX])).unwrap(), expected);
+        // Arguments may be named, and large integers given as strings
+        assert_eq!(transfer.encode_call(&json!({"amount": u64::MAX.to_string(), "to": "bob"})).unwrap(), expected);
+        assert!(transfer.encode_call(&json!(["bob", -1])).is_err());
+        assert!(transfer.encode_call(&json!(["bob"])).is_err());
+        assert!(abi.function("burn").is_err());
+
+        let output = [&[1u8][..], &40u64.to_le_bytes(), &2u32.to_le_bytes(), &[0xab, 0xcd]].concat();
+        assert_eq!(transfer.decode_output(&output).unwrap(), json!({"ok": true, "balance": 40, "memo": "abcd"}));
+        assert!(transfer.decode_output(&output[..5]).is_err());
+
+        let duplicate: ContractAbi = serde_json::from_str(r#"{"functions": [{"name": "a"}, {"name": "a"}]}"#).unwrap();
+        assert!(duplicate.validate().is_err());
+    }
+}
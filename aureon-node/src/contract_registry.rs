@@ -1,32 +1,329 @@
-use std::collections::HashMap;
+//! Contract Registry
+//!
+//! Deployed contract code is stored once per code hash, and each contract
+//! is recorded under an address derived from its deployer and the nonce it
+//! was deployed with, so replaying a deployment always yields the same
+//! address. With a database attached, contracts survive restarts.
+//!
+//! A contract is upgraded by its admin or, if it is governed, by an
+//! executed community proposal. An upgrade only swaps the code hash: the
+//! address, and with it the contract's storage and logs, stays the same,
+//! and every swap is kept in the contract's upgrade history.
+//!
+//! The deployer of a contract may register its ABI, stored as JSON next to
+//! the contract, so the API can call it by function name.
+
+use crate::contract_abi::ContractAbi;
+use crate::db::Db;
+use crate::error::ErrorKind;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+const CODE_PREFIX: &[u8] = b"contract:code:";
+const INFO_PREFIX: &[u8] = b"contract:info:";
+const ABI_PREFIX: &[u8] = b"contract:abi:";
+
+/// Why the registry refused a deployment, upgrade or ABI
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RegistryError {
+    #[error("Contract {0} not found")]
+    NotFound(String),
+    #[error("Contract already deployed at {0}")]
+    AlreadyDeployed(String),
+    #[error("{caller} is not the admin of contract {address}")]
+    NotAdmin { caller: String, address: String },
+    #[error("Contract {0} is upgraded by governance proposals only")]
+    GovernanceOnly(String),
+    #[error("Contract {0} is not governed by proposals")]
+    NotGoverned(String),
+    #[error("Contract {address} already runs code {code_hash}")]
+    SameCode { address: String, code_hash: String },
+    #[error("{caller} did not deploy contract {address}")]
+    NotDeployer { caller: String, address: String },
+    #[error("{0}")]
+    InvalidAbi(String),
+    /// Contracts could not be written to or read from the database
+    #[error("{0}")]
+    Storage(String),
+}
+
+impl RegistryError {
+    /// Stable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            RegistryError::NotFound(_) => "contract_not_found",
+            RegistryError::AlreadyDeployed(_) => "contract_exists",
+            RegistryError::NotAdmin { .. } | RegistryError::GovernanceOnly(_) | RegistryError::NotDeployer { .. } => {
+                "not_authorized"
+            }
+            RegistryError::NotGoverned(_) => "not_governed",
+            RegistryError::SameCode { .. } => "same_code",
+            RegistryError::InvalidAbi(_) => "invalid_abi",
+            RegistryError::Storage(_) => "registry_storage",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            RegistryError::NotFound(_) => ErrorKind::NotFound,
+            RegistryError::AlreadyDeployed(_) | RegistryError::SameCode { .. } => ErrorKind::Conflict,
+            RegistryError::NotAdmin { .. } | RegistryError::GovernanceOnly(_) | RegistryError::NotDeployer { .. } => {
+                ErrorKind::Forbidden
+            }
+            RegistryError::NotGoverned(_) | RegistryError::InvalidAbi(_) => ErrorKind::InvalidRequest,
+            RegistryError::Storage(_) => ErrorKind::Internal,
+        }
+    }
+}
+
+/// Who may replace a contract's code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeAuthority {
+    /// The account that may send upgrade transactions
+    Admin(String),
+    /// Only executed governance proposals
+    Governance,
+}
+
+/// What authorized a code upgrade
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, Decode)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeOrigin {
+    Admin(String),
+    Proposal(u64),
+}
+
+/// A code swap in a contract's upgrade history
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, Decode)]
+pub struct UpgradeRecord {
+    pub previous_code_hash: String,
+    pub code_hash: String,
+    /// Block the upgrade was applied in
+    pub height: u64,
+    pub origin: UpgradeOrigin,
+}
+
+/// Metadata of a deployed contract
+#[derive(Debug, Clone, PartialEq, Serialize, Encode, Decode)]
+pub struct ContractInfo {
+    pub address: String,
+    /// Hex-encoded SHA-256 of the contract code
+    pub code_hash: String,
+    pub deployer: String,
+    pub nonce: u64,
+    pub code_size: usize,
+    pub upgrade_authority: UpgradeAuthority,
+    /// Code upgrades, oldest first
+    pub upgrades: Vec<UpgradeRecord>,
+}
+
+/// Address of the contract `deployer` deploys with `nonce`
+pub fn contract_address(deployer: &str, nonce: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(deployer.as_bytes());
+    hasher.update(nonce.to_be_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Hex-encoded SHA-256 of contract code
+pub fn code_hash(code: &[u8]) -> String {
+    hex::encode(Sha256::digest(code))
+}
+
+fn code_key(code_hash: &str) -> Vec<u8> {
+    [CODE_PREFIX, code_hash.as_bytes()].concat()
+}
+
+fn info_key(address: &str) -> Vec<u8> {
+    [INFO_PREFIX, address.as_bytes()].concat()
+}
+
+fn abi_key(address: &str) -> Vec<u8> {
+    [ABI_PREFIX, address.as_bytes()].concat()
+}
 
 /// Contract registry stores deployed contracts and their metadata
+#[derive(Default)]
 pub struct ContractRegistry {
-    /// contract_address -> (code_hash, code_bytes)
-    contracts: HashMap<String, (String, Vec<u8>)>,
+    /// contract_address -> metadata
+    contracts: BTreeMap<String, ContractInfo>,
+    /// code_hash -> code_bytes
+    code: HashMap<String, Vec<u8>>,
+    /// Nonce each deployer's next contract is deployed with
+    nonces: HashMap<String, u64>,
+    /// contract_address -> registered ABI
+    abis: HashMap<String, ContractAbi>,
+    db: Option<Arc<Db>>,
 }
 
 impl ContractRegistry {
     pub fn new() -> Self {
-        Self {
-            contracts: HashMap::new(),
+        Self::default()
+    }
+
+    /// Open the registry persisted in `db`, loading previously deployed contracts
+    pub fn open(db: Arc<Db>) -> Result<Self, RegistryError> {
+        let mut registry = Self::new();
+        for (_, bytes) in db.scan_prefix(INFO_PREFIX) {
+            let (info, _): (ContractInfo, _) = bincode::decode_from_slice(&bytes, standard())
+                .map_err(|e| RegistryError::Storage(format!("Failed to decode contract: {}", e)))?;
+            let code = db
+                .get(&code_key(&info.code_hash))
+                .ok_or_else(|| RegistryError::Storage(format!("Code of contract {} missing from store", info.address)))?;
+            registry.insert(info, code);
+        }
+        for (key, bytes) in db.scan_prefix(ABI_PREFIX) {
+            let abi: ContractAbi = serde_json::from_slice(&bytes)
+                .map_err(|e| RegistryError::Storage(format!("Failed to decode contract ABI: {}", e)))?;
+            let address = String::from_utf8_lossy(&key[ABI_PREFIX.len()..]).into_owned();
+            registry.abis.insert(address, abi);
+        }
+        registry.db = Some(db);
+        Ok(registry)
+    }
+
+    fn insert(&mut self, info: ContractInfo, code: Vec<u8>) {
+        let next = self.nonces.entry(info.deployer.clone()).or_insert(0);
+        *next = (*next).max(info.nonce + 1);
+        self.code.insert(info.code_hash.clone(), code);
+        self.contracts.insert(info.address.clone(), info);
+    }
+
+    /// Nonce following the highest one `deployer` has deployed a contract with
+    pub fn next_nonce(&self, deployer: &str) -> u64 {
+        self.nonces.get(deployer).copied().unwrap_or(0)
+    }
+
+    /// Deploy `code` as the contract of `deployer` at `nonce` and return its address
+    ///
+    /// The deployer administers the contract's upgrades.
+    pub fn deploy(&mut self, deployer: &str, nonce: u64, code: Vec<u8>) -> Result<String, RegistryError> {
+        self.deploy_with_authority(deployer, nonce, code, UpgradeAuthority::Admin(deployer.to_string()))
+    }
+
+    /// Deploy `code` as the contract of `deployer` at `nonce`, upgradeable by `authority`
+    pub fn deploy_with_authority(
+        &mut self,
+        deployer: &str,
+        nonce: u64,
+        code: Vec<u8>,
+        authority: UpgradeAuthority,
+    ) -> Result<String, RegistryError> {
+        let address = contract_address(deployer, nonce);
+        if self.contracts.contains_key(&address) {
+            return Err(RegistryError::AlreadyDeployed(address));
+        }
+        let info = ContractInfo {
+            address: address.clone(),
+            code_hash: code_hash(&code),
+            deployer: deployer.to_string(),
+            nonce,
+            code_size: code.len(),
+            upgrade_authority: authority,
+            upgrades: Vec::new(),
+        };
+        self.store(info, code)?;
+        Ok(address)
+    }
+
+    /// Replace the code of the contract at `address` on behalf of its admin `caller`
+    pub fn upgrade(&mut self, address: &str, caller: &str, code: Vec<u8>, height: u64) -> Result<(), RegistryError> {
+        let info = self.contracts.get(address).ok_or_else(|| RegistryError::NotFound(address.to_string()))?;
+        match &info.upgrade_authority {
+            UpgradeAuthority::Admin(admin) if admin == caller => {}
+            UpgradeAuthority::Admin(_) => {
+                return Err(RegistryError::NotAdmin { caller: caller.to_string(), address: address.to_string() })
+            }
+            UpgradeAuthority::Governance => return Err(RegistryError::GovernanceOnly(address.to_string())),
         }
+        self.replace_code(address, code, height, UpgradeOrigin::Admin(caller.to_string()))
     }
 
-    /// Deploy a contract and return its address (hash of code)
-    pub fn deploy(&mut self, code: Vec<u8>) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(&code);
-        let hash = hex::encode(hasher.finalize());
-        
-        self.contracts.insert(hash.clone(), (hash.clone(), code));
-        hash
+    /// Replace the code of the governed contract at `address` as executed proposal `proposal_id`
+    pub fn apply_governance_upgrade(
+        &mut self,
+        address: &str,
+        code: Vec<u8>,
+        proposal_id: u64,
+        height: u64,
+    ) -> Result<(), RegistryError> {
+        let info = self.contracts.get(address).ok_or_else(|| RegistryError::NotFound(address.to_string()))?;
+        if info.upgrade_authority != UpgradeAuthority::Governance {
+            return Err(RegistryError::NotGoverned(address.to_string()));
+        }
+        self.replace_code(address, code, height, UpgradeOrigin::Proposal(proposal_id))
+    }
+
+    fn replace_code(&mut self, address: &str, code: Vec<u8>, height: u64, origin: UpgradeOrigin) -> Result<(), RegistryError> {
+        let mut info = self.contracts[address].clone();
+        let new_hash = code_hash(&code);
+        if new_hash == info.code_hash {
+            return Err(RegistryError::SameCode { address: address.to_string(), code_hash: new_hash });
+        }
+        info.upgrades.push(UpgradeRecord {
+            previous_code_hash: std::mem::replace(&mut info.code_hash, new_hash),
+            code_hash: info.code_hash.clone(),
+            height,
+            origin,
+        });
+        info.code_size = code.len();
+        self.store(info, code)
+    }
+
+    /// Persist `info` and its code, then make them current
+    fn store(&mut self, info: ContractInfo, code: Vec<u8>) -> Result<(), RegistryError> {
+        if let Some(db) = &self.db {
+            let bytes = bincode::encode_to_vec(&info, standard())
+                .map_err(|e| RegistryError::Storage(format!("Failed to encode contract: {}", e)))?;
+            if !self.code.contains_key(&info.code_hash) {
+                db.put(&code_key(&info.code_hash), &code);
+            }
+            db.put(&info_key(&info.address), &bytes);
+        }
+        self.insert(info, code);
+        Ok(())
+    }
+
+    /// Register the ABI of the contract at `address` on behalf of its deployer `caller`
+    pub fn register_abi(&mut self, address: &str, caller: &str, abi: ContractAbi) -> Result<(), RegistryError> {
+        let info = self.contracts.get(address).ok_or_else(|| RegistryError::NotFound(address.to_string()))?;
+        if info.deployer != caller {
+            return Err(RegistryError::NotDeployer { caller: caller.to_string(), address: address.to_string() });
+        }
+        abi.validate().map_err(RegistryError::InvalidAbi)?;
+        if let Some(db) = &self.db {
+            let json = serde_json::to_vec(&abi)
+                .map_err(|e| RegistryError::Storage(format!("Failed to encode contract ABI: {}", e)))?;
+            db.put(&abi_key(address), &json);
+        }
+        self.abis.insert(address.to_string(), abi);
+        Ok(())
+    }
+
+    /// ABI registered for the contract at `address`
+    pub fn get_abi(&self, address: &str) -> Option<&ContractAbi> {
+        self.abis.get(address)
     }
 
     /// Get contract code by address
     pub fn get_contract(&self, address: &str) -> Option<Vec<u8>> {
-        self.contracts.get(address).map(|(_, code)| code.clone())
+        let info = self.contracts.get(address)?;
+        self.code.get(&info.code_hash).cloned()
+    }
+
+    /// Get contract metadata by address
+    pub fn get_info(&self, address: &str) -> Option<&ContractInfo> {
+        self.contracts.get(address)
+    }
+
+    /// Metadata of every deployed contract, ordered by address
+    pub fn list(&self) -> Vec<ContractInfo> {
+        self.contracts.values().cloned().collect()
     }
 
     /// Check if contract exists
@@ -43,9 +340,72 @@ mod tests {
     fn test_deploy_and_get() {
         let mut registry = ContractRegistry::new();
         let code = vec![1, 2, 3];
-        let addr = registry.deploy(code.clone());
-        
+        let addr = registry.deploy("Alice", 0, code.clone()).unwrap();
+
         assert!(registry.contract_exists(&addr));
         assert_eq!(registry.get_contract(&addr).unwrap(), code);
+        assert_eq!(addr, contract_address("Alice", 0));
+        assert_eq!(registry.next_nonce("Alice"), 1);
+        // The same deployer and nonce cannot deploy twice
+        assert!(matches!(registry.deploy("Alice", 0, vec![4]), Err(RegistryError::AlreadyDeployed(_))));
+    }
+
+    #[test]
+    fn test_contracts_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("aureon_contracts_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let code = vec![0, 97, 115, 109];
+        {
+            let db = Arc::new(Db::open(path.to_str().unwrap()));
+            let mut registry = ContractRegistry::open(db).unwrap();
+            let alice = registry.deploy("Alice", 0, code.clone()).unwrap();
+            registry.deploy("Bob", 3, code.clone()).unwrap();
+            let abi: ContractAbi = serde_json::from_str(r#"{"functions": [{"name": "get"}]}"#).unwrap();
+            assert!(registry.register_abi(&alice, "Bob", abi.clone()).is_err());
+            registry.register_abi(&alice, "Alice", abi).unwrap();
+        }
+
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let registry = ContractRegistry::open(db.clone()).unwrap();
+        let contracts = registry.list();
+        assert_eq!(contracts.len(), 2);
+        assert_eq!(registry.get_contract(&contract_address("Bob", 3)).unwrap(), code);
+        assert_eq!(registry.get_info(&contract_address("Bob", 3)).unwrap().deployer, "Bob");
+        assert_eq!(registry.next_nonce("Bob"), 4);
+        // Identical code is stored once
+        assert_eq!(db.scan_prefix(CODE_PREFIX).len(), 1);
+        assert_eq!(registry.get_abi(&contract_address("Alice", 0)).unwrap().functions[0].name, "get");
+
+        drop((registry, db));
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_upgrades_require_authority_and_keep_address() {
+        let mut registry = ContractRegistry::new();
+        let admin_owned = registry.deploy("Alice", 0, vec![1]).unwrap();
+        let governed = registry
+            .deploy_with_authority("Alice", 1, vec![1], UpgradeAuthority::Governance)
+            .unwrap();
+
+        assert!(matches!(registry.upgrade(&admin_owned, "Mallory", vec![2], 5), Err(RegistryError::NotAdmin { .. })));
+        assert!(registry.apply_governance_upgrade(&admin_owned, vec![2], 1, 5).is_err());
+        registry.upgrade(&admin_owned, "Alice", vec![2], 5).unwrap();
+        assert_eq!(registry.get_contract(&admin_owned).unwrap(), vec![2]);
+
+        assert_eq!(registry.upgrade(&governed, "Alice", vec![3], 6), Err(RegistryError::GovernanceOnly(governed.clone())));
+        registry.apply_governance_upgrade(&governed, vec![3], 7, 6).unwrap();
+        let info = registry.get_info(&governed).unwrap();
+        assert_eq!(info.code_hash, code_hash(&[3]));
+        assert_eq!(
+            info.upgrades,
+            vec![UpgradeRecord {
+                previous_code_hash: code_hash(&[1]),
+                code_hash: code_hash(&[3]),
+                height: 6,
+                origin: UpgradeOrigin::Proposal(7),
+            }]
+        );
+        assert_eq!(registry.list().len(), 2);
     }
 }
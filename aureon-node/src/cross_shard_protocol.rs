@@ -1,5 +1,10 @@
 use std::collections::HashMap;
 use crate::shard_coordinator::ShardId;
+use crate::shard_manager::ShardManager;
+use crate::shard_sync::{self, MerkleProof};
+
+/// Blocks a destination shard has to credit a locked transfer before it is rolled back
+pub const DEFAULT_TRANSFER_TIMEOUT: u64 = 100;
 
 /// Receipt confirming a cross-shard transaction phase completed
 #[derive(Debug, Clone, PartialEq)]
@@ -150,11 +155,88 @@ impl CrossShardTransaction {
     }
 }
 
+/// Funds locked on the source shard for a cross-shard transfer
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferReceipt {
+    pub tx_id: String,
+    pub source: ShardId,
+    pub destination: ShardId,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    /// Source shard block that includes the receipt
+    pub height: u64,
+    /// Last block at which the destination shard may credit the transfer
+    pub expires_at: u64,
+}
+
+impl TransferReceipt {
+    /// Canonical encoding committed to in the source shard's receipts root
+    pub fn leaf(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}:{}:{}",
+            self.tx_id,
+            self.source.0,
+            self.destination.0,
+            self.from,
+            self.to,
+            self.amount,
+            self.height,
+            self.expires_at
+        )
+    }
+}
+
+/// Source shard block header committing to the receipts emitted in that block
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardHeader {
+    pub shard: ShardId,
+    pub height: u64,
+    pub receipts_root: String,
+}
+
+/// Transfer receipt with its Merkle proof against the source shard header
+#[derive(Debug, Clone)]
+pub struct ReceiptProof {
+    pub receipt: TransferReceipt,
+    pub proof: MerkleProof,
+}
+
+impl ReceiptProof {
+    /// Check the receipt is committed to by `header`
+    pub fn verify(&self, header: &ShardHeader) -> Result<(), String> {
+        if header.shard != self.receipt.source || header.height != self.receipt.height {
+            return Err(format!(
+                "Receipt {} is not from shard {} block {}",
+                self.receipt.tx_id, header.shard.0, header.height
+            ));
+        }
+        if self.proof.root_hash != header.receipts_root || !self.proof.proves(&self.receipt.leaf()) {
+            return Err(format!("Invalid Merkle proof for receipt {}", self.receipt.tx_id));
+        }
+        Ok(())
+    }
+}
+
 /// Cross-shard protocol manager
 /// Coordinates two-phase commit protocol for transactions spanning multiple shards
+///
+/// Transfers run in two phases: the source shard debits and locks the
+/// funds, then includes a receipt in its next block. The destination shard
+/// credits the funds once it has verified the receipt's proof against the
+/// source header, and the source releases the lock. A lock whose receipt is
+/// not credited before it expires is refunded on the source shard.
 #[derive(Debug)]
 pub struct CrossShardProtocol {
     pending_transactions: HashMap<String, CrossShardTransaction>,
+    /// Blocks a receipt stays creditable
+    transfer_timeout: u64,
+    /// Locked transfers waiting to be included in a source shard block
+    outbox: HashMap<ShardId, Vec<TransferReceipt>>,
+    /// Included receipts awaiting release, by transaction id
+    locks: HashMap<String, ReceiptProof>,
+    /// Transfers settled by the destination: true if credited, false if refunded
+    settled: HashMap<String, bool>,
 }
 
 impl CrossShardProtocol {
@@ -162,9 +244,19 @@ impl CrossShardProtocol {
     pub fn new() -> Self {
         CrossShardProtocol {
             pending_transactions: HashMap::new(),
+            transfer_timeout: DEFAULT_TRANSFER_TIMEOUT,
+            outbox: HashMap::new(),
+            locks: HashMap::new(),
+            settled: HashMap::new(),
         }
     }
 
+    /// Blocks the destination has to credit a transfer before it is rolled back
+    pub fn with_transfer_timeout(mut self, blocks: u64) -> Self {
+        self.transfer_timeout = blocks;
+        self
+    }
+
     /// Register a new cross-shard transaction
     pub fn register_transaction(&mut self, tx: CrossShardTransaction) {
         self.pending_transactions.insert(tx.id.clone(), tx);
@@ -243,6 +335,160 @@ impl CrossShardProtocol {
             .filter(|tx| tx.state == state)
             .collect()
     }
+
+    /// Phase 1 on the source shard: debit the sender and lock the funds
+    ///
+    /// The receipt is emitted in the block sealed next by `seal_block`.
+    pub fn lock_transfer(&mut self, shards: &ShardManager, mut tx: CrossShardTransaction) -> Result<(), String> {
+        let source = shards.get_shard_id(&tx.from);
+        let destination = shards.get_shard_id(&tx.to);
+        if source == destination {
+            return Err(format!("Transfer {} does not cross shards", tx.id));
+        }
+        if self.pending_transactions.contains_key(&tx.id) || self.settled.contains_key(&tx.id) {
+            return Err(format!("Transfer {} already exists", tx.id));
+        }
+        let balance = shards.get_balance(&tx.from);
+        if balance < tx.amount {
+            return Err(format!("Insufficient balance to lock {} from {}", tx.amount, tx.from));
+        }
+        shards.set_balance(tx.from.clone(), balance - tx.amount);
+
+        self.outbox.entry(source).or_default().push(TransferReceipt {
+            tx_id: tx.id.clone(),
+            source,
+            destination,
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            amount: tx.amount,
+            height: 0,
+            expires_at: 0,
+        });
+        tx.involved_shards = vec![source, destination];
+        tx.add_prepare_receipt(phase_receipt(&tx.id, TransactionPhase::Prepare, source));
+        self.register_transaction(tx);
+        Ok(())
+    }
+
+    /// Include the shard's locked transfers in block `height` and return its header
+    pub fn seal_block(&mut self, shard: ShardId, height: u64) -> ShardHeader {
+        let mut receipts = self.outbox.remove(&shard).unwrap_or_default();
+        for receipt in &mut receipts {
+            receipt.height = height;
+            receipt.expires_at = height + self.transfer_timeout;
+        }
+        let leaves: Vec<String> = receipts.iter().map(TransferReceipt::leaf).collect();
+        for (index, receipt) in receipts.into_iter().enumerate() {
+            if let Some(proof) = shard_sync::merkle_proof(&leaves, index) {
+                self.locks.insert(receipt.tx_id.clone(), ReceiptProof { receipt, proof });
+            }
+        }
+        ShardHeader {
+            shard,
+            height,
+            receipts_root: shard_sync::merkle_root(&leaves),
+        }
+    }
+
+    /// Proof of a sealed, unreleased transfer receipt, for relaying to the destination
+    pub fn receipt_proof(&self, tx_id: &str) -> Option<&ReceiptProof> {
+        self.locks.get(tx_id)
+    }
+
+    /// Phase 2 on the destination shard: verify the receipt and credit the recipient
+    ///
+    /// Each receipt is credited at most once, and only up to its expiry
+    /// height. Returns the destination's commit receipt.
+    pub fn credit_transfer(
+        &mut self,
+        shards: &ShardManager,
+        proof: &ReceiptProof,
+        header: &ShardHeader,
+        height: u64,
+    ) -> Result<TransactionReceipt, String> {
+        proof.verify(header)?;
+        let receipt = &proof.receipt;
+        if shards.get_shard_id(&receipt.to) != receipt.destination {
+            return Err(format!("Recipient {} is not on shard {}", receipt.to, receipt.destination.0));
+        }
+        if self.settled.contains_key(&receipt.tx_id) {
+            return Err(format!("Transfer {} already settled", receipt.tx_id));
+        }
+        if height > receipt.expires_at {
+            return Err(format!("Transfer {} expired at block {}", receipt.tx_id, receipt.expires_at));
+        }
+
+        let balance = shards.get_balance(&receipt.to);
+        shards.set_balance(receipt.to.clone(), balance + receipt.amount);
+        self.settled.insert(receipt.tx_id.clone(), true);
+
+        let commit = phase_receipt(&receipt.tx_id, TransactionPhase::Commit, receipt.destination);
+        if let Some(tx) = self.get_transaction_mut(&receipt.tx_id) {
+            tx.add_prepare_receipt(phase_receipt(&receipt.tx_id, TransactionPhase::Prepare, receipt.destination));
+            tx.try_ready_to_commit();
+            tx.add_commit_receipt(commit.clone());
+        }
+        Ok(commit)
+    }
+
+    /// Release a lock on the source shard once the destination has committed
+    pub fn release_lock(&mut self, commit: &TransactionReceipt) -> Option<CrossShardState> {
+        if commit.phase != TransactionPhase::Commit || !commit.success {
+            return None;
+        }
+        let lock = self.locks.remove(&commit.tx_id)?;
+        let tx = self.get_transaction_mut(&commit.tx_id)?;
+        tx.add_commit_receipt(commit.clone());
+        tx.add_commit_receipt(phase_receipt(&commit.tx_id, TransactionPhase::Commit, lock.receipt.source));
+        tx.try_committed();
+        Some(tx.state)
+    }
+
+    /// Settle locks whose receipts expired before `height`
+    ///
+    /// Credited transfers are released; the rest are refunded to the sender
+    /// and can no longer be credited. Returns the ids of rolled back transfers.
+    pub fn expire_locks(&mut self, shards: &ShardManager, height: u64) -> Vec<String> {
+        let mut expired: Vec<String> = self
+            .locks
+            .iter()
+            .filter(|(_, lock)| lock.receipt.expires_at < height)
+            .map(|(tx_id, _)| tx_id.clone())
+            .collect();
+        expired.sort();
+
+        let mut rolled_back = Vec::new();
+        for tx_id in expired {
+            if self.settled.get(&tx_id) == Some(&true) {
+                let destination = self.locks[&tx_id].receipt.destination;
+                self.release_lock(&phase_receipt(&tx_id, TransactionPhase::Commit, destination));
+                continue;
+            }
+            let Some(lock) = self.locks.remove(&tx_id) else {
+                continue;
+            };
+            let receipt = lock.receipt;
+            let balance = shards.get_balance(&receipt.from);
+            shards.set_balance(receipt.from.clone(), balance + receipt.amount);
+            self.settled.insert(tx_id.clone(), false);
+            if let Some(tx) = self.get_transaction_mut(&tx_id) {
+                tx.abort();
+            }
+            rolled_back.push(tx_id);
+        }
+        rolled_back
+    }
+}
+
+/// Successful receipt of a protocol phase on `shard`
+fn phase_receipt(tx_id: &str, phase: TransactionPhase, shard: ShardId) -> TransactionReceipt {
+    TransactionReceipt {
+        tx_id: tx_id.to_string(),
+        phase,
+        shard,
+        success: true,
+        error_message: None,
+    }
 }
 
 impl Default for CrossShardProtocol {
@@ -471,4 +717,73 @@ mod tests {
         tx.abort();
         assert_eq!(tx.state, CrossShardState::Aborted);
     }
+
+    /// Two accounts on different shards of a two-shard manager, with `balance` for the sender
+    fn funded_shards(balance: u64) -> (ShardManager, String, String) {
+        let shards = ShardManager::new(crate::shard_coordinator::ShardCoordinator::with_shard_count(2));
+        let from = "alice@aureon".to_string();
+        let to = (0..)
+            .map(|i| format!("user{}@aureon", i))
+            .find(|addr| !shards.same_shard(&from, addr))
+            .unwrap();
+        shards.set_balance(from.clone(), balance);
+        (shards, from, to)
+    }
+
+    fn transfer(id: &str, from: &str, to: &str, amount: u64) -> CrossShardTransaction {
+        CrossShardTransaction::new(id.to_string(), from.to_string(), to.to_string(), amount, 0, vec![])
+    }
+
+    #[test]
+    fn test_transfer_locks_credits_and_commits() {
+        let (shards, from, to) = funded_shards(100);
+        let mut protocol = CrossShardProtocol::new();
+        assert!(protocol.lock_transfer(&shards, transfer("tx_001", &from, &to, 500)).is_err());
+        protocol.lock_transfer(&shards, transfer("tx_001", &from, &to, 60)).unwrap();
+        protocol.lock_transfer(&shards, transfer("tx_002", &from, &to, 10)).unwrap();
+        assert_eq!(shards.get_balance(&from), 30);
+
+        let header = protocol.seal_block(shards.get_shard_id(&from), 7);
+        let proof = protocol.receipt_proof("tx_002").unwrap().clone();
+        let commit = protocol.credit_transfer(&shards, &proof, &header, 8).unwrap();
+        assert_eq!(shards.get_balance(&to), 10);
+        assert!(protocol.credit_transfer(&shards, &proof, &header, 8).is_err());
+
+        assert_eq!(protocol.release_lock(&commit), Some(CrossShardState::Committed));
+        assert!(protocol.receipt_proof("tx_002").is_none());
+        assert_eq!(protocol.get_transaction("tx_001").unwrap().state, CrossShardState::Pending);
+    }
+
+    #[test]
+    fn test_forged_receipt_is_rejected() {
+        let (shards, from, to) = funded_shards(100);
+        let mut protocol = CrossShardProtocol::new();
+        protocol.lock_transfer(&shards, transfer("tx_001", &from, &to, 60)).unwrap();
+        let header = protocol.seal_block(shards.get_shard_id(&from), 1);
+
+        let mut forged = protocol.receipt_proof("tx_001").unwrap().clone();
+        forged.receipt.amount = 600;
+        assert!(protocol.credit_transfer(&shards, &forged, &header, 1).is_err());
+
+        let wrong_header = ShardHeader { height: 2, ..header };
+        let proof = protocol.receipt_proof("tx_001").unwrap().clone();
+        assert!(protocol.credit_transfer(&shards, &proof, &wrong_header, 1).is_err());
+        assert_eq!(shards.get_balance(&to), 0);
+    }
+
+    #[test]
+    fn test_expired_transfer_is_rolled_back() {
+        let (shards, from, to) = funded_shards(100);
+        let mut protocol = CrossShardProtocol::new().with_transfer_timeout(5);
+        protocol.lock_transfer(&shards, transfer("tx_001", &from, &to, 60)).unwrap();
+        let header = protocol.seal_block(shards.get_shard_id(&from), 10);
+        let proof = protocol.receipt_proof("tx_001").unwrap().clone();
+
+        assert!(protocol.expire_locks(&shards, 15).is_empty());
+        assert_eq!(protocol.expire_locks(&shards, 16), vec!["tx_001"]);
+        assert_eq!(shards.get_balance(&from), 100);
+        assert_eq!(protocol.get_transaction("tx_001").unwrap().state, CrossShardState::Aborted);
+        assert!(protocol.credit_transfer(&shards, &proof, &header, 15).is_err());
+        assert_eq!(shards.get_balance(&to), 0);
+    }
 }
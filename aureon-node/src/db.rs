@@ -1,6 +1,92 @@
-use rocksdb::{DB, Options, Snapshot};
+use rocksdb::{IteratorMode, DB, Options, Snapshot, WriteBatch};
+use std::collections::BTreeMap;
 use std::path::Path;
 
+pub use rocksdb::Direction;
+
+/// Column families the database is split into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Column {
+    /// Account, token and contract state, kept in the default column family
+    State,
+    /// Stored blocks and chain metadata
+    Blocks,
+    /// Execution receipts by transaction hash
+    Receipts,
+    /// Explorer indexes
+    Index,
+    /// Known good peers, reconnected to after a restart
+    Peers,
+    /// Pending transactions saved across restarts
+    Mempool,
+    /// State changes of each applied block by block hash
+    StateDiffs,
+    /// Sampled node metrics kept for dashboards
+    Metrics,
+    /// Resurrection witnesses of accounts pruned for unpaid storage rent
+    ExpiredState,
+}
+
+impl Column {
+    const ALL: [Column; 9] = [
+        Column::State,
+        Column::Blocks,
+        Column::Receipts,
+        Column::Index,
+        Column::Peers,
+        Column::Mempool,
+        Column::StateDiffs,
+        Column::Metrics,
+        Column::ExpiredState,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Column::State => rocksdb::DEFAULT_COLUMN_FAMILY_NAME,
+            Column::Blocks => "blocks",
+            Column::Receipts => "receipts",
+            Column::Index => "index",
+            Column::Peers => "peers",
+            Column::Mempool => "mempool",
+            Column::StateDiffs => "state_diffs",
+            Column::Metrics => "metrics",
+            Column::ExpiredState => "expired_state",
+        }
+    }
+}
+
+/// Writes committed together by `Db::write`
+///
+/// Pending writes can be read back before they are committed.
+#[derive(Debug, Default)]
+pub struct Batch {
+    writes: BTreeMap<(Column, Vec<u8>), Option<Vec<u8>>>,
+}
+
+impl Batch {
+    pub fn put(&mut self, column: Column, key: &[u8], value: &[u8]) {
+        self.writes.insert((column, key.to_vec()), Some(value.to_vec()));
+    }
+
+    pub fn delete(&mut self, column: Column, key: &[u8]) {
+        self.writes.insert((column, key.to_vec()), None);
+    }
+
+    /// Pending write of `key`: `Some(None)` if it is deleted, `None` if it is untouched
+    pub fn get(&self, column: Column, key: &[u8]) -> Option<Option<&[u8]>> {
+        self.writes.get(&(column, key.to_vec())).map(Option::as_deref)
+    }
+
+    /// Pending writes of `column` in key order; `None` values are deletions
+    pub fn entries(&self, column: Column) -> impl Iterator<Item = (&[u8], Option<&[u8]>)> {
+        self.writes
+            .iter()
+            .filter(move |((written, _), _)| *written == column)
+            .map(|((_, key), value)| (key.as_slice(), value.as_deref()))
+    }
+}
+
+#[derive(Debug)]
 pub struct Db {
     db: DB,
 }
@@ -9,10 +95,16 @@ impl Db {
     pub fn open(path: &str) -> Self {
         let mut opts = Options::default();
         opts.create_if_missing(true);
-        let db = DB::open(&opts, Path::new(path)).expect("Failed to open RocksDB");
+        opts.create_missing_column_families(true);
+        let columns = Column::ALL.iter().map(|column| column.name());
+        let db = DB::open_cf(&opts, Path::new(path), columns).expect("Failed to open RocksDB");
         Db { db }
     }
 
+    fn handle(&self, column: Column) -> &rocksdb::ColumnFamily {
+        self.db.cf_handle(column.name()).expect("Column family missing")
+    }
+
     pub fn put(&self, key: &[u8], value: &[u8]) {
         self.db.put(key, value).expect("DB put failed");
     }
@@ -25,6 +117,66 @@ impl Db {
         self.db.delete(key).expect("DB delete failed");
     }
 
+    pub fn put_cf(&self, column: Column, key: &[u8], value: &[u8]) {
+        self.db.put_cf(self.handle(column), key, value).expect("DB put failed");
+    }
+
+    pub fn get_cf(&self, column: Column, key: &[u8]) -> Option<Vec<u8>> {
+        self.db.get_cf(self.handle(column), key).expect("DB get failed")
+    }
+
+    pub fn delete_cf(&self, column: Column, key: &[u8]) {
+        self.db.delete_cf(self.handle(column), key).expect("DB delete failed");
+    }
+
+    /// Commit every write in `batch` atomically
+    pub fn write(&self, batch: Batch) {
+        let mut writes = WriteBatch::default();
+        for ((column, key), value) in batch.writes {
+            match value {
+                Some(value) => writes.put_cf(self.handle(column), key, value),
+                None => writes.delete_cf(self.handle(column), key),
+            }
+        }
+        self.db.write(writes).expect("DB batch write failed");
+    }
+
+    /// Entries of `column` in key order from `from` on, or in reverse key
+    /// order from the last key at or before `from`
+    pub fn iter(
+        &self,
+        column: Column,
+        from: &[u8],
+        direction: Direction,
+    ) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> + '_ {
+        self.db
+            .iterator_cf(self.handle(column), IteratorMode::From(from, direction))
+            .map(|entry| entry.expect("DB iteration failed"))
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+    }
+
+    /// All entries whose key starts with `prefix`, in key order
+    pub fn scan_prefix(&self, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.scan_prefix_cf(Column::State, prefix)
+    }
+
+    /// All entries of `column` whose key starts with `prefix`, in key order
+    pub fn scan_prefix_cf(&self, column: Column, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.iter(column, prefix, Direction::Forward)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .collect()
+    }
+
+    /// Read through the database without panicking, to probe that it still answers
+    pub fn check(&self) -> Result<(), String> {
+        self.db.get(b"health:probe").map(|_| ()).map_err(|e| format!("DB read failed: {}", e))
+    }
+
+    /// Write buffered changes to disk
+    pub fn flush(&self) -> Result<(), String> {
+        self.db.flush().map_err(|e| format!("DB flush failed: {}", e))
+    }
+
     pub fn snapshot(&self) -> Snapshot {
         self.db.snapshot()
     }
@@ -43,3 +195,36 @@ impl<'a> SnapshotDb<'a> {
         self.snapshot.get(key).expect("Snapshot get failed")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_and_batches() {
+        let path = std::env::temp_dir().join(format!("aureon_db_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        {
+            let db = Db::open(path.to_str().unwrap());
+            db.put(b"key", b"state");
+            db.put_cf(Column::Index, b"key", b"index");
+            assert_eq!(db.get_cf(Column::State, b"key"), Some(b"state".to_vec()));
+            assert_eq!(db.get_cf(Column::Blocks, b"key"), None);
+
+            let mut batch = Batch::default();
+            batch.put(Column::Blocks, b"a", b"1");
+            batch.put(Column::Blocks, b"b", b"2");
+            batch.delete(Column::Index, b"key");
+            assert_eq!(batch.get(Column::Blocks, b"a"), Some(Some(&b"1"[..])));
+            assert_eq!(batch.get(Column::Index, b"key"), Some(None));
+            // Nothing is written until the batch is committed
+            assert_eq!(db.get_cf(Column::Blocks, b"a"), None);
+            db.write(batch);
+
+            assert_eq!(db.get_cf(Column::Index, b"key"), None);
+            let keys: Vec<_> = db.iter(Column::Blocks, b"z", Direction::Reverse).map(|(key, _)| key).collect();
+            assert_eq!(keys, vec![b"b".to_vec(), b"a".to_vec()]);
+        }
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
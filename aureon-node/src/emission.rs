@@ -0,0 +1,214 @@
+//! Block Reward Emission
+//!
+//! Every applied block mints a reward to its proposer. The reward starts at
+//! the configured initial reward and halves every `halving_interval`
+//! blocks, and is cut short once the total supply would exceed the cap.
+//! The number of blocks applied and the total supply are kept in the state
+//! trie, so every node that executes the same blocks mints the same
+//! rewards and reaches the same state root.
+//!
+//! With ommer rewards enabled, the miner of a stale PoW block included as
+//! an ommer `depth` blocks later receives (8 - depth)/8 of the block
+//! reward, and the proposer including it 1/32 per ommer.
+
+use crate::config::EconomyConfig;
+use crate::consensus::ommers::OmmerHeader;
+use crate::fee_market::BaseFeeSchedule;
+use crate::storage_rent::RentSchedule;
+use crate::token::TokenState;
+use serde::Serialize;
+
+/// Prefix of every key holding emission state
+pub const STATE_PREFIX: &str = "economy:";
+const HEIGHT_KEY: &[u8] = b"economy:height";
+const SUPPLY_KEY: &[u8] = b"economy:supply";
+
+/// Decreasing block reward schedule with a supply cap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmissionSchedule {
+    pub initial_reward: u64,
+    pub halving_interval: u64,
+    pub max_supply: u64,
+    /// Supply held by the genesis accounts
+    pub genesis_supply: u64,
+    /// Base fee burned from every transaction, if fee burning is enabled
+    pub base_fee: Option<BaseFeeSchedule>,
+    /// Reward included ommers and their includers
+    pub ommer_rewards: bool,
+    /// Storage rent charged to accounts, if storage rent is enabled
+    pub rent: Option<RentSchedule>,
+}
+
+/// Emission state as of the next block
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Emission {
+    /// Height of the next block
+    pub height: u64,
+    /// Reward the next block's proposer receives
+    pub block_reward: u64,
+    pub total_supply: u64,
+    pub max_supply: u64,
+    /// Height at which the reward next halves
+    pub next_halving: u64,
+}
+
+impl EmissionSchedule {
+    pub fn new(config: &EconomyConfig, genesis_supply: u64) -> Self {
+        EmissionSchedule {
+            initial_reward: config.initial_block_reward,
+            halving_interval: config.halving_interval.max(1),
+            max_supply: config.max_supply,
+            genesis_supply,
+            base_fee: BaseFeeSchedule::new(config),
+            ommer_rewards: config.ommer_rewards,
+            rent: RentSchedule::new(config),
+        }
+    }
+
+    /// Scheduled reward of the block at `height`, before the supply cap
+    pub fn reward_at(&self, height: u64) -> u64 {
+        let halvings = height / self.halving_interval;
+        if halvings >= u64::BITS as u64 {
+            0
+        } else {
+            self.initial_reward >> halvings
+        }
+    }
+
+    /// Emission state recorded in `state`
+    pub fn status<S: TokenState + ?Sized>(&self, state: &S) -> Emission {
+        let height = height(state);
+        let total_supply = read_u64(state, SUPPLY_KEY).unwrap_or(self.genesis_supply);
+        Emission {
+            height,
+            block_reward: self.reward_at(height).min(self.max_supply.saturating_sub(total_supply)),
+            total_supply,
+            max_supply: self.max_supply,
+            next_halving: (height / self.halving_interval + 1).saturating_mul(self.halving_interval),
+        }
+    }
+
+    /// Mint the reward of the next block to `proposer`, returning the amount minted
+    ///
+    /// A block without a proposer mints nothing but still advances the height.
+    pub fn mint_block_reward<S: TokenState + ?Sized>(&self, state: &mut S, proposer: &str) -> u64 {
+        let Emission { height, block_reward, total_supply, .. } = self.status(state);
+        let reward = if proposer.is_empty() { 0 } else { block_reward };
+        if reward > 0 {
+            let balance = read_u64(state, proposer.as_bytes()).unwrap_or(0);
+            state.put(proposer.as_bytes().to_vec(), (balance + reward).to_le_bytes().to_vec());
+            state.put(SUPPLY_KEY.to_vec(), (total_supply + reward).to_le_bytes().to_vec());
+        }
+        state.put(HEIGHT_KEY.to_vec(), (height + 1).to_le_bytes().to_vec());
+        reward
+    }
+
+    /// Mint the rewards of the `ommers` the next block's `proposer` includes,
+    /// returning the amount minted
+    ///
+    /// Minted before the block reward, at the same height. Nothing is minted
+    /// unless ommer rewards are enabled.
+    pub fn mint_ommer_rewards<S: TokenState + ?Sized>(&self, state: &mut S, proposer: &str, ommers: &[OmmerHeader]) -> u64 {
+        if !self.ommer_rewards || ommers.is_empty() {
+            return 0;
+        }
+        let Emission { height, total_supply, .. } = self.status(state);
+        let reward = self.reward_at(height);
+        let mut supply = total_supply;
+        for ommer in ommers {
+            let depth = height.saturating_sub(ommer.height).min(8);
+            self.credit(state, &ommer.miner, reward * (8 - depth) / 8, &mut supply);
+            self.credit(state, proposer, reward / 32, &mut supply);
+        }
+        if supply > total_supply {
+            state.put(SUPPLY_KEY.to_vec(), supply.to_le_bytes().to_vec());
+        }
+        supply - total_supply
+    }
+
+    /// Mint `amount` to `account` within the supply cap
+    fn credit<S: TokenState + ?Sized>(&self, state: &mut S, account: &str, amount: u64, supply: &mut u64) {
+        let amount = amount.min(self.max_supply.saturating_sub(*supply));
+        if account.is_empty() || amount == 0 {
+            return;
+        }
+        let balance = read_u64(state, account.as_bytes()).unwrap_or(0);
+        state.put(account.as_bytes().to_vec(), (balance + amount).to_le_bytes().to_vec());
+        *supply += amount;
+    }
+}
+
+/// Number of blocks applied to `state`
+pub fn height<S: TokenState + ?Sized>(state: &S) -> u64 {
+    read_u64(state, HEIGHT_KEY).unwrap_or(0)
+}
+
+fn read_u64<S: TokenState + ?Sized>(state: &S, key: &[u8]) -> Option<u64> {
+    state.get(key).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn schedule(max_supply: u64) -> EmissionSchedule {
+        let config = EconomyConfig { initial_block_reward: 100, halving_interval: 2, max_supply, ..EconomyConfig::default() };
+        EmissionSchedule::new(&config, 1_000)
+    }
+
+    #[test]
+    fn test_reward_halves_every_interval() {
+        let schedule = schedule(u64::MAX);
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let minted: Vec<u64> = (0..6).map(|_| schedule.mint_block_reward(&mut state, "alice")).collect();
+
+        assert_eq!(minted, vec![100, 100, 50, 50, 25, 25]);
+        assert_eq!(read_u64(&state, b"alice"), Some(350));
+        let status = schedule.status(&state);
+        assert_eq!((status.height, status.total_supply, status.next_halving), (6, 1_350, 8));
+        assert_eq!(schedule.reward_at(2 * 64), 0);
+    }
+
+    #[test]
+    fn test_supply_cap_limits_rewards() {
+        let schedule = schedule(1_150);
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        // Blocks without a proposer mint nothing
+        assert_eq!(schedule.mint_block_reward(&mut state, ""), 0);
+        assert_eq!(schedule.mint_block_reward(&mut state, "alice"), 100);
+        assert_eq!(schedule.mint_block_reward(&mut state, "alice"), 50);
+        assert_eq!(schedule.mint_block_reward(&mut state, "alice"), 0);
+        assert_eq!(schedule.status(&state).total_supply, 1_150);
+    }
+
+    #[test]
+    fn test_ommer_rewards_shrink_with_depth() {
+        let ommer = |miner: &str, height: u64| OmmerHeader {
+            hash: format!("{}{}", miner, height),
+            previous_hash: String::new(),
+            height,
+            slot: 0,
+            nonce: 0,
+            tx_root: vec![],
+            post_state_root: vec![],
+            ommers_hash: vec![],
+            miner: miner.to_string(),
+            difficulty: 1,
+        };
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let disabled = schedule(u64::MAX);
+        assert_eq!(disabled.mint_ommer_rewards(&mut state, "alice", &[ommer("bob", 0)]), 0);
+
+        let schedule = EmissionSchedule { ommer_rewards: true, halving_interval: 100, ..schedule(u64::MAX) };
+        schedule.mint_block_reward(&mut state, "alice");
+        // One block deep the miner gets 7/8 of 100, two blocks deep 6/8; the includer 3 per ommer
+        let ommers = [ommer("bob", 0), ommer("carol", 1)];
+        assert_eq!(schedule.mint_ommer_rewards(&mut state, "alice", &ommers[..1]), 87 + 3);
+        assert_eq!(read_u64(&state, b"bob"), Some(87));
+        schedule.mint_block_reward(&mut state, "alice");
+        assert_eq!(schedule.mint_ommer_rewards(&mut state, "dave", &ommers), 75 + 87 + 6);
+        assert_eq!(read_u64(&state, b"carol"), Some(87));
+        assert_eq!(schedule.status(&state).total_supply, 1_000 + 200 + 90 + 168);
+    }
+}
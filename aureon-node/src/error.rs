@@ -0,0 +1,92 @@
+//! Node Errors
+//!
+//! Each subsystem reports failures through its own error enum, and
+//! `NodeError` gathers them for layers that talk to several, like the API.
+//! Every error carries a stable, machine-readable code clients can match
+//! on, and a kind that decides how it is reported; the message is meant for
+//! people and may change between releases.
+
+use crate::contract_registry::RegistryError;
+use crate::indexer::IndexerError;
+use crate::mempool::MempoolError;
+use crate::network::NetworkError;
+
+/// Broad class of an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The request itself is malformed or not allowed by the rules
+    InvalidRequest,
+    /// The caller is not allowed to do this
+    Forbidden,
+    /// The requested item does not exist
+    NotFound,
+    /// The request conflicts with the current state
+    Conflict,
+    /// The node cannot serve the request right now
+    Unavailable,
+    /// A fault inside the node
+    Internal,
+}
+
+/// Any error the node reports to its clients
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum NodeError {
+    #[error(transparent)]
+    Mempool(#[from] MempoolError),
+    #[error(transparent)]
+    Indexer(#[from] IndexerError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Registry(#[from] RegistryError),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl NodeError {
+    /// Stable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            NodeError::Mempool(e) => e.code(),
+            NodeError::Indexer(e) => e.code(),
+            NodeError::Network(e) => e.code(),
+            NodeError::Registry(e) => e.code(),
+            NodeError::NotFound(_) => "not_found",
+            NodeError::InvalidRequest(_) => "invalid_request",
+            NodeError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NodeError::Mempool(e) => e.kind(),
+            NodeError::Indexer(e) => e.kind(),
+            NodeError::Network(e) => e.kind(),
+            NodeError::Registry(e) => e.kind(),
+            NodeError::NotFound(_) => ErrorKind::NotFound,
+            NodeError::InvalidRequest(_) => ErrorKind::InvalidRequest,
+            NodeError::Internal(_) => ErrorKind::Internal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_errors_keep_their_code_and_message() {
+        let error = NodeError::from(MempoolError::Duplicate);
+        assert_eq!(error.code(), "duplicate_transaction");
+        assert_eq!(error.kind(), ErrorKind::Conflict);
+        assert_eq!(error.to_string(), "Transaction already in mempool");
+
+        let error = NodeError::from(RegistryError::NotFound("abc".to_string()));
+        assert_eq!((error.code(), error.kind()), ("contract_not_found", ErrorKind::NotFound));
+        assert_eq!(NodeError::NotFound("Block not found".to_string()).code(), "not_found");
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides comprehensive error handling, retry logic, circuit breakers,
 //! and graceful degradation for production-grade operations.
 
+use serde::Serialize;
 use std::time::{Duration, SystemTime};
 use std::collections::VecDeque;
 
@@ -308,7 +309,8 @@ pub enum FallbackStrategy {
 }
 
 /// Health check result
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     /// System is healthy
     Healthy,
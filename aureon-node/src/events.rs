@@ -0,0 +1,252 @@
+//! Node Event Bus
+//!
+//! Fans out node events (new blocks, pending transactions, contract
+//! executions) to WebSocket subscribers over a bounded broadcast channel.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokio::sync::broadcast;
+
+/// Default number of events buffered per subscriber before it starts lagging
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Subscription topics available to clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Blocks,
+    Transactions,
+    Contracts,
+}
+
+impl Topic {
+    /// All topics, in display order
+    pub const ALL: [Topic; 3] = [Topic::Blocks, Topic::Transactions, Topic::Contracts];
+
+    /// Topic name as used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::Blocks => "blocks",
+            Topic::Transactions => "transactions",
+            Topic::Contracts => "contracts",
+        }
+    }
+
+    /// Parse a topic name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Topic> {
+        match name.trim().to_lowercase().as_str() {
+            "blocks" => Some(Topic::Blocks),
+            "transactions" => Some(Topic::Transactions),
+            "contracts" => Some(Topic::Contracts),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BlockEvent {
+    pub event_type: String,
+    pub block_hash: String,
+    pub block_number: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TransactionEvent {
+    pub event_type: String,
+    pub tx_hash: String,
+    pub from: String,
+    pub block_number: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ContractEvent {
+    pub event_type: String,
+    pub contract_address: String,
+    pub success: bool,
+    pub gas_used: u64,
+}
+
+/// Event published on the bus
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "topic", content = "data", rename_all = "snake_case")]
+pub enum NodeEvent {
+    Blocks(BlockEvent),
+    Transactions(TransactionEvent),
+    Contracts(ContractEvent),
+}
+
+impl NodeEvent {
+    /// Topic this event is delivered on
+    pub fn topic(&self) -> Topic {
+        match self {
+            NodeEvent::Blocks(_) => Topic::Blocks,
+            NodeEvent::Transactions(_) => Topic::Transactions,
+            NodeEvent::Contracts(_) => Topic::Contracts,
+        }
+    }
+}
+
+/// Broadcast channel shared by event producers and subscribers
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl EventBus {
+    /// Create an event bus buffering up to `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        EventBus { sender }
+    }
+
+    /// Publish an event, returning how many subscribers received it
+    /// Publishing with no subscribers is not an error
+    pub fn publish(&self, event: NodeEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Open a new receiver on the bus
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+/// Control message sent by a WebSocket client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SubscriptionCommand {
+    Subscribe { topics: Vec<Topic> },
+    Unsubscribe { topics: Vec<Topic> },
+}
+
+/// Per-connection topic filter
+#[derive(Debug, Clone, Default)]
+pub struct Subscription {
+    topics: HashSet<Topic>,
+}
+
+impl Subscription {
+    /// Create a subscription for the given topics
+    pub fn new(topics: impl IntoIterator<Item = Topic>) -> Self {
+        Subscription {
+            topics: topics.into_iter().collect(),
+        }
+    }
+
+    /// Apply a client control message
+    pub fn apply(&mut self, command: SubscriptionCommand) {
+        match command {
+            SubscriptionCommand::Subscribe { topics } => self.topics.extend(topics),
+            SubscriptionCommand::Unsubscribe { topics } => {
+                for topic in topics {
+                    self.topics.remove(&topic);
+                }
+            }
+        }
+    }
+
+    /// Whether an event should be delivered to this subscriber
+    pub fn matches(&self, event: &NodeEvent) -> bool {
+        self.topics.contains(&event.topic())
+    }
+
+    /// Currently subscribed topics, in display order
+    pub fn topics(&self) -> Vec<Topic> {
+        Topic::ALL
+            .iter()
+            .copied()
+            .filter(|t| self.topics.contains(t))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    fn block_event(number: u64) -> NodeEvent {
+        NodeEvent::Blocks(BlockEvent {
+            event_type: "new_block".to_string(),
+            block_hash: format!("hash{}", number),
+            block_number: number,
+            timestamp: 0,
+        })
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = EventBus::new(8);
+        assert_eq!(bus.publish(block_event(1)), 0);
+    }
+
+    #[test]
+    fn test_publish_and_receive() {
+        let bus = EventBus::new(8);
+        let mut rx = bus.subscribe();
+        assert_eq!(bus.subscriber_count(), 1);
+
+        assert_eq!(bus.publish(block_event(1)), 1);
+        match rx.try_recv().unwrap() {
+            NodeEvent::Blocks(event) => assert_eq!(event.block_number, 1),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_slow_subscriber_lags() {
+        let bus = EventBus::new(2);
+        let mut rx = bus.subscribe();
+        for n in 0..5 {
+            bus.publish(block_event(n));
+        }
+
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Lagged(3))));
+        // Oldest retained events are still delivered after the lag notice
+        assert!(matches!(rx.try_recv(), Ok(NodeEvent::Blocks(e)) if e.block_number == 3));
+    }
+
+    #[test]
+    fn test_subscription_filtering() {
+        let mut sub = Subscription::new([Topic::Transactions]);
+        assert!(!sub.matches(&block_event(1)));
+
+        sub.apply(SubscriptionCommand::Subscribe { topics: vec![Topic::Blocks] });
+        assert!(sub.matches(&block_event(1)));
+        assert_eq!(sub.topics(), vec![Topic::Blocks, Topic::Transactions]);
+
+        sub.apply(SubscriptionCommand::Unsubscribe { topics: vec![Topic::Blocks] });
+        assert!(!sub.matches(&block_event(1)));
+    }
+
+    #[test]
+    fn test_command_parsing() {
+        let cmd: SubscriptionCommand =
+            serde_json::from_str(r#"{"action":"subscribe","topics":["blocks","contracts"]}"#).unwrap();
+        let mut sub = Subscription::default();
+        sub.apply(cmd);
+        assert_eq!(sub.topics(), vec![Topic::Blocks, Topic::Contracts]);
+
+        assert_eq!(Topic::parse("Transactions"), Some(Topic::Transactions));
+        assert_eq!(Topic::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let json = serde_json::to_value(block_event(7)).unwrap();
+        assert_eq!(json["topic"], "blocks");
+        assert_eq!(json["data"]["block_number"], 7);
+    }
+}
@@ -0,0 +1,492 @@
+//! Fee Market
+//!
+//! Gas accounting and priority selection for pending transactions. Senders
+//! bid a gas price per unit of gas; the mempool rejects bids below the
+//! configured floor and block producers fill blocks with the best-paying
+//! transactions that fit under the block gas limit.
+//!
+//! With fee burning enabled, every block also has an EIP-1559 style base fee
+//! per unit of gas. Transactions pay the base fee, which is burned, and tip
+//! the proposer whatever their gas price bids above it; transactions bidding
+//! less than the base fee wait in the mempool. After each block the base fee
+//! moves by up to 1/8 towards blocks using the target gas. The base fee and
+//! the total burned are kept in the state trie next to the emission state.
+//!
+//! Block space is filled in lanes. A reserved share of every block goes
+//! first to system transactions (bridge attestations and light client
+//! headers), then to governance votes; user transactions, and priority
+//! transactions the reserved share has no room for, compete for the rest.
+
+use crate::config::{EconomyConfig, FeeMarketConfig};
+use crate::token::TokenState;
+use crate::types::{Transaction, TransactionPayload};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Intrinsic gas charged for a plain (non-contract) transaction
+pub const BASE_TX_GAS: u64 = 21_000;
+
+/// Intrinsic gas of a shielded transfer, covering proof verification
+pub const SHIELDED_TX_GAS: u64 = 200_000;
+
+/// Gas reserved by a transaction
+pub fn gas_limit(tx: &Transaction) -> u64 {
+    match &tx.payload {
+        TransactionPayload::ContractDeploy { gas_limit, .. }
+        | TransactionPayload::ContractCall { gas_limit, .. }
+        | TransactionPayload::ContractUpgrade { gas_limit, .. } => *gas_limit,
+        TransactionPayload::Transfer { .. }
+        | TransactionPayload::Stake { .. }
+        | TransactionPayload::Unstake { .. }
+        | TransactionPayload::GovernanceVote { .. }
+        | TransactionPayload::Token(_)
+        | TransactionPayload::Nft(_)
+        | TransactionPayload::Delegate { .. }
+        | TransactionPayload::Undelegate { .. }
+        | TransactionPayload::TimeLockedTransfer { .. }
+        | TransactionPayload::RegisterMultisig { .. }
+        | TransactionPayload::BridgeAttest { .. }
+        | TransactionPayload::BridgeBurn { .. }
+        | TransactionPayload::BridgeHeader { .. }
+        | TransactionPayload::BridgeProve { .. }
+        | TransactionPayload::ResurrectState(_) => BASE_TX_GAS,
+        TransactionPayload::ShieldedTransfer(_) => SHIELDED_TX_GAS,
+    }
+}
+
+/// Gas a transaction uses before running any contract code
+pub fn intrinsic_gas(tx: &Transaction) -> u64 {
+    match &tx.payload {
+        TransactionPayload::ContractDeploy { .. }
+        | TransactionPayload::ContractCall { .. }
+        | TransactionPayload::ContractUpgrade { .. } => BASE_TX_GAS,
+        _ => gas_limit(tx),
+    }
+}
+
+/// Maximum fee a transaction can pay (gas price × gas limit)
+pub fn max_fee(tx: &Transaction) -> u128 {
+    tx.gas_price as u128 * gas_limit(tx) as u128
+}
+
+/// Native tokens a transaction moves out of its sender's balance
+pub fn value(tx: &Transaction) -> u64 {
+    match &tx.payload {
+        TransactionPayload::Transfer { amount, .. }
+        | TransactionPayload::Stake { amount }
+        | TransactionPayload::Delegate { amount, .. }
+        | TransactionPayload::TimeLockedTransfer { amount, .. } => *amount,
+        _ => 0,
+    }
+}
+
+/// Most a transaction can take from its sender's balance: its value, plus
+/// its maximum fee when fees are charged
+pub fn max_cost(tx: &Transaction, charges_fees: bool) -> u128 {
+    let fee = if charges_fees { max_fee(tx) } else { 0 };
+    value(tx) as u128 + fee
+}
+
+const BASE_FEE_KEY: &[u8] = b"economy:base_fee";
+const BURNED_KEY: &[u8] = b"economy:burned";
+
+/// Largest base fee change per block, as a fraction of the base fee
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u128 = 8;
+
+/// Base fee rules: the first block's base fee and the gas per block it steers towards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaseFeeSchedule {
+    pub initial_base_fee: u64,
+    pub target_gas: u64,
+}
+
+/// Base fee state as of the next block
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BaseFee {
+    /// Base fee per unit of gas of the next block
+    pub base_fee: u64,
+    pub target_gas: u64,
+    /// Base fees burned so far
+    pub total_burned: u64,
+}
+
+/// Fee a transaction paid, split into the burned base fee and the proposer's tip
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FeePayment {
+    pub burned: u64,
+    pub tip: u64,
+}
+
+impl BaseFeeSchedule {
+    /// Base fee rules of `config`, if fee burning is enabled
+    pub fn new(config: &EconomyConfig) -> Option<Self> {
+        config.fee_burning.then_some(BaseFeeSchedule {
+            initial_base_fee: config.initial_base_fee,
+            target_gas: config.target_block_gas.max(1),
+        })
+    }
+
+    /// Base fee state recorded in `state`
+    pub fn status<S: TokenState + ?Sized>(&self, state: &S) -> BaseFee {
+        BaseFee {
+            base_fee: self.base_fee(state),
+            target_gas: self.target_gas,
+            total_burned: read_u64(state, BURNED_KEY).unwrap_or(0),
+        }
+    }
+
+    /// Base fee per unit of gas of the next block
+    pub fn base_fee<S: TokenState + ?Sized>(&self, state: &S) -> u64 {
+        read_u64(state, BASE_FEE_KEY).unwrap_or(self.initial_base_fee)
+    }
+
+    /// Base fee following `base_fee` after a block that used `gas_used`
+    pub fn next_base_fee(&self, base_fee: u64, gas_used: u64) -> u64 {
+        let target = self.target_gas as u128;
+        let delta = |gas: u64| base_fee as u128 * gas as u128 / target / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        if gas_used > self.target_gas {
+            // Full blocks raise even a zero base fee
+            let raise = delta(gas_used - self.target_gas).max(1);
+            base_fee.saturating_add(raise.min(u64::MAX as u128) as u64)
+        } else {
+            base_fee - delta(self.target_gas - gas_used) as u64
+        }
+    }
+
+    /// Charge the sender of `tx` for `gas_used` gas at `base_fee`, burning the
+    /// base fee and paying the tip above it to `proposer`
+    ///
+    /// The sender must be able to cover its whole gas limit, but only pays for
+    /// the gas used; the rest is refunded. Tips of blocks without a proposer
+    /// are burned.
+    pub fn charge<S: TokenState + ?Sized>(
+        &self,
+        state: &mut S,
+        tx: &Transaction,
+        gas_used: u64,
+        base_fee: u64,
+        proposer: &str,
+    ) -> Result<FeePayment, String> {
+        if tx.gas_price < base_fee {
+            return Err(format!("Gas price {} below base fee {}", tx.gas_price, base_fee));
+        }
+        let balance = read_u64(state, tx.from.as_bytes()).unwrap_or(0);
+        if (balance as u128) < max_fee(tx) {
+            return Err(format!("Insufficient balance for fees: {} < {}", balance, max_fee(tx)));
+        }
+        let mut payment = FeePayment {
+            burned: gas_used.saturating_mul(base_fee),
+            tip: gas_used.saturating_mul(tx.gas_price - base_fee),
+        };
+        state.put(tx.from.as_bytes().to_vec(), (balance - payment.burned - payment.tip).to_le_bytes().to_vec());
+        if proposer.is_empty() {
+            payment.burned += payment.tip;
+            payment.tip = 0;
+        } else if payment.tip > 0 {
+            let proposer_balance = read_u64(state, proposer.as_bytes()).unwrap_or(0);
+            state.put(proposer.as_bytes().to_vec(), (proposer_balance + payment.tip).to_le_bytes().to_vec());
+        }
+        let burned = read_u64(state, BURNED_KEY).unwrap_or(0);
+        state.put(BURNED_KEY.to_vec(), (burned + payment.burned).to_le_bytes().to_vec());
+        Ok(payment)
+    }
+
+    /// Record the base fee of the block after one that used `gas_used`
+    pub fn finish_block<S: TokenState + ?Sized>(&self, state: &mut S, gas_used: u64) {
+        let next = self.next_base_fee(self.base_fee(state), gas_used);
+        state.put(BASE_FEE_KEY.to_vec(), next.to_le_bytes().to_vec());
+    }
+}
+
+fn read_u64<S: TokenState + ?Sized>(state: &S, key: &[u8]) -> Option<u64> {
+    state.get(key).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes)
+}
+
+/// Check a transaction's gas parameters against the fee market rules
+pub fn validate(tx: &Transaction, config: &FeeMarketConfig) -> Result<(), String> {
+    if tx.gas_price < config.min_gas_price {
+        return Err(format!(
+            "Gas price {} below minimum {}",
+            tx.gas_price, config.min_gas_price
+        ));
+    }
+
+    let gas = gas_limit(tx);
+    if gas < BASE_TX_GAS {
+        return Err(format!("Gas limit {} below intrinsic gas {}", gas, BASE_TX_GAS));
+    }
+    if gas > config.block_gas_limit {
+        return Err(format!(
+            "Gas limit {} exceeds block gas limit {}",
+            gas, config.block_gas_limit
+        ));
+    }
+    Ok(())
+}
+
+/// Lane of block space a transaction is selected in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxClass {
+    /// Protocol inputs the chain depends on: bridge attestations and headers
+    System,
+    /// Governance votes
+    Governance,
+    /// Everything else
+    Regular,
+}
+
+impl TxClass {
+    /// Lanes filled from reserved block space, in order
+    pub const PRIORITY: [TxClass; 2] = [TxClass::System, TxClass::Governance];
+}
+
+/// Lane `tx` is selected in
+pub fn class(tx: &Transaction) -> TxClass {
+    match &tx.payload {
+        TransactionPayload::BridgeAttest { .. } | TransactionPayload::BridgeHeader { .. } => TxClass::System,
+        TransactionPayload::GovernanceVote { .. } => TxClass::Governance,
+        _ => TxClass::Regular,
+    }
+}
+
+/// Block space kept for system and governance transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReservedSpace {
+    pub count: usize,
+    pub gas: u64,
+}
+
+impl ReservedSpace {
+    /// `percent` of a block of `max_count` transactions and `gas_budget` gas
+    pub fn share(percent: u8, max_count: usize, gas_budget: u64) -> Self {
+        let percent = percent.min(100);
+        ReservedSpace {
+            count: max_count * percent as usize / 100,
+            gas: (gas_budget as u128 * percent as u128 / 100) as u64,
+        }
+    }
+}
+
+/// Pick transactions for a block, highest tip above `base_fee` first
+///
+/// Transactions from the same sender are kept in nonce order, so a sender's
+/// later transaction is only considered once its predecessor is included.
+/// If a sender's next transaction does not fit the remaining gas or bids
+/// less than the base fee, the rest of that sender's transactions are
+/// skipped. Ties in tip go to the earlier submission. Returns indices into
+/// `pending` in selection order.
+#[allow(dead_code)]
+pub fn select_transactions(pending: &[Transaction], max_count: usize, gas_budget: u64, base_fee: u64) -> Vec<usize> {
+    let candidates: Vec<usize> = (0..pending.len()).collect();
+    select_from(pending, &candidates, max_count, gas_budget, base_fee)
+}
+
+/// Pick transactions for a block lane by lane
+///
+/// System and then governance transactions are selected first, within the
+/// `reserved` space; the rest of the block is then filled from all
+/// remaining transactions as by [`select_transactions`]. A priority
+/// transaction queued behind a sender's transactions of another lane waits
+/// for them, so every sender's transactions stay in nonce order.
+pub fn select_by_lane(
+    pending: &[Transaction],
+    max_count: usize,
+    gas_budget: u64,
+    base_fee: u64,
+    reserved: ReservedSpace,
+) -> Vec<usize> {
+    let mut queues: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, tx) in pending.iter().enumerate() {
+        queues.entry(tx.from.as_str()).or_default().push(index);
+    }
+    for queue in queues.values_mut() {
+        queue.sort_by_key(|&index| pending[index].nonce);
+    }
+
+    let mut taken = vec![false; pending.len()];
+    let mut selected = Vec::new();
+    let (mut count, mut gas) = (reserved.count.min(max_count), reserved.gas.min(gas_budget));
+    for lane in TxClass::PRIORITY {
+        // Each sender's leading transactions in the lane
+        let mut eligible: Vec<usize> = queues
+            .values()
+            .flat_map(|queue| {
+                queue.iter().copied().filter(|&index| !taken[index]).take_while(|&index| class(&pending[index]) == lane)
+            })
+            .collect();
+        eligible.sort_unstable();
+        for index in select_from(pending, &eligible, count, gas, base_fee) {
+            taken[index] = true;
+            count -= 1;
+            gas -= gas_limit(&pending[index]);
+            selected.push(index);
+        }
+    }
+
+    let gas_used: u64 = selected.iter().map(|&index| gas_limit(&pending[index])).sum();
+    let rest: Vec<usize> = (0..pending.len()).filter(|&index| !taken[index]).collect();
+    let remaining = max_count.saturating_sub(selected.len());
+    selected.extend(select_from(pending, &rest, remaining, gas_budget - gas_used, base_fee));
+    selected
+}
+
+/// [`select_transactions`] over the `candidates` indices of `pending`, in
+/// submission order
+fn select_from(pending: &[Transaction], candidates: &[usize], max_count: usize, gas_budget: u64, base_fee: u64) -> Vec<usize> {
+    // Per-sender queues in nonce order (stable on submission order)
+    let mut queues: HashMap<&str, Vec<usize>> = HashMap::new();
+    for &index in candidates {
+        queues.entry(pending[index].from.as_str()).or_default().push(index);
+    }
+    for queue in queues.values_mut() {
+        queue.sort_by_key(|&index| pending[index].nonce);
+        queue.reverse();
+    }
+
+    // Heap of each sender's next transaction, keyed by (tip, earliest submission)
+    let tip = |index: usize| pending[index].gas_price.saturating_sub(base_fee);
+    let mut heap = BinaryHeap::new();
+    for queue in queues.values_mut() {
+        if let Some(index) = queue.pop() {
+            heap.push((tip(index), Reverse(index)));
+        }
+    }
+
+    let mut selected = Vec::new();
+    let mut gas_used = 0u64;
+    while let Some((_, Reverse(index))) = heap.pop() {
+        if selected.len() >= max_count {
+            break;
+        }
+        let tx = &pending[index];
+        let gas = gas_limit(tx);
+        if tx.gas_price < base_fee || gas_used.saturating_add(gas) > gas_budget {
+            continue;
+        }
+        gas_used += gas;
+        selected.push(index);
+
+        if let Some(next) = queues.get_mut(tx.from.as_str()).and_then(|queue| queue.pop()) {
+            heap.push((tip(next), Reverse(next)));
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(from: &str, nonce: u64, gas_price: u64) -> Transaction {
+        let mut tx = Transaction::transfer(from.to_string(), "Bob".to_string(), 1);
+        tx.nonce = nonce;
+        tx.gas_price = gas_price;
+        tx
+    }
+
+    #[test]
+    fn test_gas_limit_by_payload() {
+        assert_eq!(gas_limit(&tx("Alice", 0, 1)), BASE_TX_GAS);
+        let call = Transaction::call_contract("Alice".into(), "0xabc".into(), "run".into(), vec![], 50_000);
+        assert_eq!(gas_limit(&call), 50_000);
+        let upgrade = Transaction::upgrade_contract("Alice".into(), "0xabc".into(), vec![], 80_000);
+        assert_eq!(gas_limit(&upgrade), 80_000);
+        assert_eq!(max_fee(&tx("Alice", 0, 3)), 3 * BASE_TX_GAS as u128);
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let config = FeeMarketConfig {
+            min_gas_price: 2,
+            block_gas_limit: 100_000,
+        };
+        assert!(validate(&tx("Alice", 0, 1), &config).unwrap_err().contains("below minimum"));
+        assert!(validate(&tx("Alice", 0, 2), &config).is_ok());
+
+        let mut big = Transaction::deploy_contract("Alice".into(), vec![], 200_000);
+        big.gas_price = 2;
+        assert!(validate(&big, &config).unwrap_err().contains("exceeds block gas limit"));
+
+        let mut tiny = Transaction::deploy_contract("Alice".into(), vec![], 10);
+        tiny.gas_price = 2;
+        assert!(validate(&tiny, &config).unwrap_err().contains("intrinsic"));
+    }
+
+    #[test]
+    fn test_select_highest_price_first() {
+        let pending = vec![tx("Alice", 0, 1), tx("Bob", 0, 5), tx("Carol", 0, 3)];
+        assert_eq!(select_transactions(&pending, 10, u64::MAX, 0), vec![1, 2, 0]);
+        assert_eq!(select_transactions(&pending, 2, u64::MAX, 0), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_select_respects_sender_nonce_order() {
+        // Alice's nonce 1 pays more, but cannot jump ahead of her nonce 0
+        let pending = vec![tx("Alice", 0, 1), tx("Alice", 1, 10), tx("Bob", 0, 5)];
+        assert_eq!(select_transactions(&pending, 10, u64::MAX, 0), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn test_select_within_gas_budget() {
+        let pending = vec![tx("Alice", 0, 1), tx("Bob", 0, 5), tx("Carol", 0, 3)];
+        assert_eq!(select_transactions(&pending, 10, 2 * BASE_TX_GAS, 0), vec![1, 2]);
+        assert!(select_transactions(&pending, 10, BASE_TX_GAS - 1, 0).is_empty());
+    }
+
+    #[test]
+    fn test_priority_lanes_fill_reserved_space_first() {
+        use crate::bridge::BridgeEvent;
+        use crate::community_governance::VoteChoice;
+
+        let vote = |from: &str, nonce: u64| Transaction {
+            nonce,
+            ..Transaction::governance_vote(from.to_string(), 1, VoteChoice::Yes)
+        };
+        let event = BridgeEvent::Released { transfer_id: "t1".to_string() };
+        let attest = Transaction {
+            payload: TransactionPayload::BridgeAttest { event, relayer: 0, signature: vec![] },
+            ..tx("Relayer", 0, 1)
+        };
+        let classes = (class(&attest), class(&vote("Dave", 0)), class(&tx("Alice", 0, 1)));
+        assert_eq!(classes, (TxClass::System, TxClass::Governance, TxClass::Regular));
+
+        // Users outbid the votes and the attestation, but two of the four slots are reserved
+        let pending = vec![tx("Alice", 0, 9), tx("Bob", 0, 8), vote("Dave", 0), vote("Erin", 0), attest, tx("Carol", 0, 7)];
+        let reserved = ReservedSpace::share(50, 4, u64::MAX);
+        assert_eq!(reserved.count, 2);
+        assert_eq!(select_by_lane(&pending, 4, u64::MAX, 0, reserved), vec![4, 2, 0, 1]);
+        assert_eq!(select_by_lane(&pending, 4, u64::MAX, 0, ReservedSpace::default()), select_transactions(&pending, 4, u64::MAX, 0));
+
+        // A vote queued behind its sender's transfer waits for it in the regular lane
+        let pending = vec![tx("Dave", 0, 1), vote("Dave", 1), tx("Alice", 0, 9)];
+        assert_eq!(select_by_lane(&pending, 3, u64::MAX, 0, ReservedSpace::share(100, 3, u64::MAX)), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn test_base_fee_is_burned_and_follows_block_gas() {
+        let schedule = BaseFeeSchedule { initial_base_fee: 8, target_gas: 2 * BASE_TX_GAS };
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        state.put(b"Alice".to_vec(), 1_000_000u64.to_le_bytes().to_vec());
+
+        // Alice bids 10: 8 per gas is burned and the proposer earns a tip of 2
+        let payment = schedule.charge(&mut state, &tx("Alice", 0, 10), BASE_TX_GAS, 8, "Miner").unwrap();
+        assert_eq!(payment, FeePayment { burned: 8 * BASE_TX_GAS, tip: 2 * BASE_TX_GAS });
+        assert_eq!(read_u64(&state, b"Alice"), Some(1_000_000 - 10 * BASE_TX_GAS));
+        assert_eq!(read_u64(&state, b"Miner"), Some(2 * BASE_TX_GAS));
+        assert!(schedule.charge(&mut state, &tx("Alice", 1, 7), BASE_TX_GAS, 8, "Miner").unwrap_err().contains("below base fee"));
+        assert!(schedule.charge(&mut state, &tx("Bob", 0, 9), BASE_TX_GAS, 8, "Miner").unwrap_err().contains("Insufficient"));
+
+        // Full blocks raise the base fee by an eighth, empty ones lower it by an eighth
+        assert_eq!(schedule.next_base_fee(800, 4 * BASE_TX_GAS), 900);
+        assert_eq!(schedule.next_base_fee(800, 0), 700);
+        assert_eq!(schedule.next_base_fee(0, 4 * BASE_TX_GAS), 1);
+        schedule.finish_block(&mut state, 0);
+        assert_eq!(schedule.status(&state), BaseFee { base_fee: 7, target_gas: 2 * BASE_TX_GAS, total_burned: 8 * BASE_TX_GAS });
+
+        // Transactions bidding below the base fee wait, ranked by tip otherwise
+        let pending = vec![tx("Alice", 0, 4), tx("Bob", 0, 9), tx("Carol", 0, 8)];
+        assert_eq!(select_transactions(&pending, 10, u64::MAX, 5), vec![1, 2]);
+    }
+}
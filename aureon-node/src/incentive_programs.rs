@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
 /// Incentive programs and economic sustainability module
 ///
@@ -57,6 +58,23 @@ impl StakingInfo {
     }
 }
 
+/// Stake a delegator has bonded to a validator
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Delegation {
+    pub delegator: String,
+    pub validator: String,
+    pub amount: u128,
+}
+
+/// Undelegated stake waiting out the unbonding period
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnbondingDelegation {
+    pub delegator: String,
+    pub validator: String,
+    pub amount: u128,
+    pub release_height: u64,
+}
+
 /// Reward distribution engine
 pub struct RewardDistributor {
     pending_rewards: HashMap<String, u128>,
@@ -144,6 +162,15 @@ pub struct StakingSystem {
     stakes: HashMap<String, Vec<StakingInfo>>,
     total_staked: u128,
     annual_reward_rate: f64, // APY as decimal (0.05 = 5%)
+    /// Validator -> delegator -> delegated stake
+    delegations: HashMap<String, BTreeMap<String, u128>>,
+    unbonding: Vec<UnbondingDelegation>,
+    /// Blocks undelegated stake stays bonded
+    unbonding_period: u64,
+    /// Share of the delegators' rewards validators keep, in basis points
+    commission_bps: u32,
+    /// Distributed rewards not yet paid out
+    rewards: HashMap<String, u128>,
 }
 
 impl StakingSystem {
@@ -153,9 +180,21 @@ impl StakingSystem {
             stakes: HashMap::new(),
             total_staked: 0,
             annual_reward_rate,
+            delegations: HashMap::new(),
+            unbonding: Vec::new(),
+            unbonding_period: 0,
+            commission_bps: 0,
+            rewards: HashMap::new(),
         }
     }
 
+    /// Set the unbonding period of undelegated stake and the validator commission
+    pub fn with_delegation(mut self, unbonding_period: u64, commission_bps: u32) -> Self {
+        self.unbonding_period = unbonding_period;
+        self.commission_bps = commission_bps.min(10_000);
+        self
+    }
+
     /// Stake tokens
     pub fn stake(&mut self, staker: String, amount: u128, lock_period: u64, current_block: u64) {
         let stake = StakingInfo::new(staker.clone(), amount, current_block, lock_period);
@@ -172,6 +211,192 @@ impl StakingSystem {
             .unwrap_or(0)
     }
 
+    /// Withdraw up to `amount` of a staker's active stake, newest stakes first
+    /// Returns the amount withdrawn
+    pub fn withdraw(&mut self, staker: &str, amount: u128) -> u128 {
+        let mut remaining = amount;
+        if let Some(stakes) = self.stakes.get_mut(staker) {
+            for stake in stakes.iter_mut().rev().filter(|s| s.is_active) {
+                let taken = stake.amount.min(remaining);
+                stake.amount -= taken;
+                remaining -= taken;
+                if stake.amount == 0 {
+                    stake.unlock();
+                }
+            }
+        }
+        let withdrawn = amount - remaining;
+        self.total_staked -= withdrawn;
+        withdrawn
+    }
+
+    /// Active stake of every staker that has any, plus the stake delegated to it
+    pub fn bonded_stakes(&self) -> HashMap<String, u128> {
+        self.stakes
+            .keys()
+            .map(|staker| (staker.clone(), self.get_staked_amount(staker)))
+            .filter(|(_, amount)| *amount > 0)
+            .map(|(staker, amount)| {
+                let delegated = self.get_delegated_amount(&staker);
+                (staker, amount + delegated)
+            })
+            .collect()
+    }
+
+    /// Delegate `amount` of `delegator`'s stake to `validator`
+    pub fn delegate(&mut self, delegator: &str, validator: &str, amount: u128) -> Result<(), String> {
+        if amount == 0 {
+            return Err("Delegation amount must be positive".to_string());
+        }
+        if self.get_staked_amount(validator) == 0 {
+            return Err(format!("{} is not a bonded validator", validator));
+        }
+        *self
+            .delegations
+            .entry(validator.to_string())
+            .or_default()
+            .entry(delegator.to_string())
+            .or_insert(0) += amount;
+        self.total_staked += amount;
+        Ok(())
+    }
+
+    /// Start unbonding `amount` of the stake `delegator` delegated to `validator`
+    ///
+    /// The stake stops counting towards the validator and earning rewards
+    /// at once, and is released by `release_unbonded` after the unbonding
+    /// period.
+    pub fn undelegate(&mut self, delegator: &str, validator: &str, amount: u128, height: u64) -> Result<(), String> {
+        let insufficient = || format!("{} has delegated less than {} to {}", delegator, amount, validator);
+        let delegators = self.delegations.get_mut(validator).ok_or_else(insufficient)?;
+        let delegated = delegators
+            .get_mut(delegator)
+            .filter(|delegated| **delegated >= amount)
+            .ok_or_else(insufficient)?;
+        *delegated -= amount;
+        if *delegated == 0 {
+            delegators.remove(delegator);
+        }
+        self.total_staked -= amount;
+        self.unbonding.push(UnbondingDelegation {
+            delegator: delegator.to_string(),
+            validator: validator.to_string(),
+            amount,
+            release_height: height + self.unbonding_period,
+        });
+        Ok(())
+    }
+
+    /// Remove and return undelegations whose unbonding period has elapsed by `height`
+    pub fn release_unbonded(&mut self, height: u64) -> Vec<UnbondingDelegation> {
+        let (released, pending) = std::mem::take(&mut self.unbonding)
+            .into_iter()
+            .partition(|entry| entry.release_height <= height);
+        self.unbonding = pending;
+        released
+    }
+
+    /// Stake delegated to `validator`
+    pub fn get_delegated_amount(&self, validator: &str) -> u128 {
+        self.delegations.get(validator).map_or(0, |delegators| delegators.values().sum())
+    }
+
+    /// Delegations made by `delegator`, ordered by validator
+    pub fn delegations_of(&self, delegator: &str) -> Vec<Delegation> {
+        let mut delegations: Vec<Delegation> = self
+            .delegations
+            .iter()
+            .filter_map(|(validator, delegators)| {
+                delegators.get(delegator).map(|amount| Delegation {
+                    delegator: delegator.to_string(),
+                    validator: validator.clone(),
+                    amount: *amount,
+                })
+            })
+            .collect();
+        delegations.sort_by(|a, b| a.validator.cmp(&b.validator));
+        delegations
+    }
+
+    /// Delegations made to `validator`, ordered by delegator
+    pub fn delegations_to(&self, validator: &str) -> Vec<Delegation> {
+        self.delegations
+            .get(validator)
+            .into_iter()
+            .flatten()
+            .map(|(delegator, amount)| Delegation {
+                delegator: delegator.clone(),
+                validator: validator.to_string(),
+                amount: *amount,
+            })
+            .collect()
+    }
+
+    /// Undelegations of `delegator` that are still unbonding
+    pub fn unbonding_of(&self, delegator: &str) -> Vec<UnbondingDelegation> {
+        self.unbonding.iter().filter(|entry| entry.delegator == delegator).cloned().collect()
+    }
+
+    /// Split `reward` earned by `validator` between it and its delegators
+    ///
+    /// The delegators' share is proportional to their part of the
+    /// validator's bonded stake; the validator keeps its commission on that
+    /// share and any rounding remainder.
+    pub fn distribute_reward(&mut self, validator: &str, reward: u128) {
+        let own = self.get_staked_amount(validator);
+        let delegated = self.get_delegated_amount(validator);
+        if own + delegated == 0 || reward == 0 {
+            return;
+        }
+        let delegators_share = reward * delegated / (own + delegated);
+        let to_delegators = delegators_share - delegators_share * self.commission_bps as u128 / 10_000;
+        let mut paid = 0;
+        for (delegator, amount) in self.delegations.get(validator).into_iter().flatten() {
+            let share = to_delegators * amount / delegated;
+            *self.rewards.entry(delegator.clone()).or_insert(0) += share;
+            paid += share;
+        }
+        *self.rewards.entry(validator.to_string()).or_insert(0) += reward - paid;
+    }
+
+    /// Distribute the rewards `validators` earned over an epoch of `blocks` blocks
+    pub fn distribute_epoch_rewards<'a>(&mut self, validators: impl IntoIterator<Item = &'a String>, blocks: u64) {
+        for validator in validators {
+            let bonded = self.get_staked_amount(validator) + self.get_delegated_amount(validator);
+            let reward = self.calculate_reward(bonded, blocks);
+            self.distribute_reward(validator, reward);
+        }
+    }
+
+    /// Distributed rewards of `account` not yet paid out
+    pub fn get_pending_rewards(&self, account: &str) -> u128 {
+        self.rewards.get(account).copied().unwrap_or(0)
+    }
+
+    /// Slash a share (in basis points) of a staker's active stake
+    /// Returns the amount removed
+    pub fn slash(&mut self, staker: &str, penalty_bps: u32) -> u128 {
+        let penalty_bps = penalty_bps.min(10_000) as u128;
+        let slashed: u128 = self
+            .stakes
+            .get_mut(staker)
+            .map(|stakes| {
+                stakes
+                    .iter_mut()
+                    .filter(|s| s.is_active)
+                    .map(|s| {
+                        let cut = s.amount * penalty_bps / 10_000;
+                        s.amount -= cut;
+                        cut
+                    })
+                    .sum()
+            })
+            .unwrap_or(0);
+
+        self.total_staked -= slashed;
+        slashed
+    }
+
     /// Calculate reward for stake
     pub fn calculate_reward(&self, amount: u128, blocks: u64) -> u128 {
         let blocks_per_year = 2_102_400u128; // ~365 days at 15 second blocks
@@ -180,6 +405,11 @@ impl StakingSystem {
         reward
     }
 
+    /// Annual reward rate used for new reward calculations
+    pub fn set_reward_rate(&mut self, annual_reward_rate: f64) {
+        self.annual_reward_rate = annual_reward_rate;
+    }
+
     /// Get total staked
     pub fn get_total_staked(&self) -> u128 {
         self.total_staked
@@ -360,6 +590,19 @@ mod tests {
         assert_eq!(stake.get_age(150), 150);
     }
 
+    #[test]
+    fn test_staking_system_slash() {
+        let mut system = StakingSystem::new(0.05);
+        system.stake("validator1".to_string(), 1000, 0, 0);
+        system.stake("validator1".to_string(), 500, 10, 0);
+
+        // 10% of each active position
+        assert_eq!(system.slash("validator1", 1000), 150);
+        assert_eq!(system.get_staked_amount("validator1"), 1350);
+        assert_eq!(system.get_total_staked(), 1350);
+        assert_eq!(system.slash("unknown", 1000), 0);
+    }
+
     #[test]
     fn test_reward_distributor_creation() {
         let distributor = RewardDistributor::new(1_000_000);
@@ -540,4 +783,37 @@ mod tests {
 
         assert_eq!(system.get_active_validators(), 2);
     }
+
+    #[test]
+    fn test_undelegation_unbonds_before_release() {
+        let mut system = StakingSystem::new(0.05).with_delegation(10, 1_000);
+        system.stake("val1".to_string(), 1000, 0, 0);
+        assert!(system.delegate("alice", "nobody", 100).is_err());
+        system.delegate("alice", "val1", 500).unwrap();
+        assert_eq!(system.bonded_stakes()["val1"], 1500);
+
+        assert!(system.undelegate("alice", "val1", 501, 5).is_err());
+        system.undelegate("alice", "val1", 200, 5).unwrap();
+        assert_eq!(system.bonded_stakes()["val1"], 1300);
+        assert_eq!(system.unbonding_of("alice")[0].release_height, 15);
+
+        assert!(system.release_unbonded(14).is_empty());
+        let released = system.release_unbonded(15);
+        assert_eq!(released[0].amount, 200);
+        assert_eq!(system.get_total_staked(), 1300);
+    }
+
+    #[test]
+    fn test_rewards_split_by_stake_after_commission() {
+        let mut system = StakingSystem::new(0.05).with_delegation(10, 1_000);
+        system.stake("val1".to_string(), 2000, 0, 0);
+        system.delegate("alice", "val1", 1500).unwrap();
+        system.delegate("bob", "val1", 500).unwrap();
+
+        system.distribute_reward("val1", 1000);
+        // Delegators' half of the reward, less 10% commission, split 3:1
+        assert_eq!(system.get_pending_rewards("alice"), 337);
+        assert_eq!(system.get_pending_rewards("bob"), 112);
+        assert_eq!(system.get_pending_rewards("val1"), 551);
+    }
 }
@@ -1,7 +1,161 @@
-use crate::types::{Block, Transaction};
-use std::collections::HashMap;
+use crate::consensus::ommers::OmmerHeader;
+use crate::db::{Batch, Column, Db, Direction};
+use crate::error::ErrorKind;
+use crate::logs::{self, bloom_contains, bloom_insert, logs_bloom, IndexedLog, LogFilter, BLOOM_SIZE};
+use crate::mpt::MerklePatriciaTrie;
+use crate::nft::{self, Nft};
+use crate::slashing::SlashingEvent;
+use crate::state_diff::StateDiff;
+use crate::types::{Block, Transaction, TransactionPayload, TransactionReceipt};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 
+/// Why an index query failed
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IndexerError {
+    #[error("Indexer has no database attached")]
+    NoDatabase,
+    #[error("Invalid cursor {0}")]
+    InvalidCursor(String),
+    #[error("Index lock poisoned: {0}")]
+    Poisoned(String),
+}
+
+impl IndexerError {
+    /// Stable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            IndexerError::NoDatabase => "index_unavailable",
+            IndexerError::InvalidCursor(_) => "invalid_cursor",
+            IndexerError::Poisoned(_) => "internal",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            IndexerError::NoDatabase => ErrorKind::Unavailable,
+            IndexerError::InvalidCursor(_) => ErrorKind::InvalidRequest,
+            IndexerError::Poisoned(_) => ErrorKind::Internal,
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for IndexerError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        IndexerError::Poisoned(e.to_string())
+    }
+}
+
+/// Key and value read from the database
+type DbEntry = (Vec<u8>, Vec<u8>);
+
+/// Block height -> block hash
+const HEIGHT_PREFIX: &[u8] = b"index:height:";
+/// Sender, block height and position -> transaction hash
+const SENT_PREFIX: &[u8] = b"index:sent:";
+/// Recipient, block height and position -> transaction hash
+const RECEIVED_PREFIX: &[u8] = b"index:received:";
+
+/// Key prefix of the transactions `account` appears in under `prefix`
+fn account_prefix(prefix: &[u8], account: &str) -> Vec<u8> {
+    [prefix, account.as_bytes(), b"/"].concat()
+}
+
+/// Position of a transaction in the chain, ordered like the chain
+fn tx_position(block_number: u64, tx_index: usize) -> Vec<u8> {
+    [&block_number.to_be_bytes()[..], &(tx_index as u32).to_be_bytes()].concat()
+}
+
+/// Keys of the sender and recipient entries of `tx`
+fn account_keys(tx: &Transaction, block_number: u64, tx_index: usize) -> Vec<Vec<u8>> {
+    let position = tx_position(block_number, tx_index);
+    std::iter::once(account_prefix(SENT_PREFIX, &tx.from))
+        .chain(tx.recipient().map(|to| account_prefix(RECEIVED_PREFIX, to)))
+        .map(|prefix| [prefix, position.clone()].concat())
+        .collect()
+}
+
+/// Addresses `tx` involves: its sender, its recipient or called contract,
+/// and the contract it upgrades
+fn involved_addresses(tx: &Transaction) -> impl Iterator<Item = &str> {
+    let upgraded = match &tx.payload {
+        TransactionPayload::ContractUpgrade { contract_address, .. } => Some(contract_address.as_str()),
+        _ => None,
+    };
+    std::iter::once(tx.from.as_str()).chain(tx.recipient()).chain(upgraded)
+}
+
+/// Bloom over the addresses `transactions` involve and the addresses and
+/// topics of the `logs` they emitted
+fn block_bloom(transactions: &[Transaction], logs: &[IndexedLog]) -> Vec<u8> {
+    let mut bloom = logs_bloom(&[]);
+    logs::accrue_logs(&mut bloom, logs.iter().map(|l| &l.log));
+    for address in transactions.iter().flat_map(involved_addresses) {
+        bloom_insert(&mut bloom, address.as_bytes());
+    }
+    bloom
+}
+
+/// Transactions involving an address in a range of blocks, newest first
+#[derive(Clone, Debug)]
+pub struct AddressHistory {
+    pub transactions: Vec<TransactionIndexEntry>,
+    /// Blocks whose transactions were read
+    pub blocks_scanned: u64,
+    /// Blocks the bloom ruled out without reading them
+    pub blocks_skipped: u64,
+    /// Highest block not yet read when the limit cut the listing short
+    pub next_block: Option<u64>,
+}
+
+/// Account balances, plus the same balances ordered richest first
+#[derive(Default, Debug)]
+struct BalanceIndex {
+    balances: HashMap<String, u64>,
+    ranked: BTreeSet<(Reverse<u64>, String)>,
+}
+
+/// Existing NFTs, plus the tokens each account owns
+#[derive(Default, Debug)]
+struct NftIndex {
+    tokens: HashMap<(String, u64), Nft>,
+    owned: HashMap<String, BTreeSet<(String, u64)>>,
+}
+
+/// One page of a newest-first listing
+#[derive(Clone, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: usize,
+    pub page_size: usize,
+    /// Number of items across all pages
+    pub total: usize,
+}
+
+/// One page of a newest-first listing read from the database
+#[derive(Clone, Debug)]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+impl<T: Clone> Page<T> {
+    /// Page `page` (zero-based) of `items`, which are in oldest-first order
+    fn newest_first(items: &[T], page: usize, page_size: usize) -> Self {
+        let items_on_page = items
+            .iter()
+            .rev()
+            .skip(page.saturating_mul(page_size))
+            .take(page_size)
+            .cloned()
+            .collect();
+        Page { items: items_on_page, page, page_size, total: items.len() }
+    }
+}
+
 /// In-memory blockchain indexes for fast data lookups
 /// Maintains mappings from block/transaction hashes to their data
 #[derive(Clone, Debug)]
@@ -11,7 +165,28 @@ pub struct BlockchainIndexer {
     /// Transaction hash -> Transaction data + containing block hash
     transactions: Arc<Mutex<HashMap<String, TransactionIndexEntry>>>,
     /// Block number -> Block hash (for sequential queries)
-    block_numbers: Arc<Mutex<HashMap<u64, String>>>,
+    block_numbers: Arc<Mutex<BTreeMap<u64, String>>>,
+    /// Transaction hash -> execution receipt
+    receipts: Arc<Mutex<HashMap<String, TransactionReceipt>>>,
+    /// Account -> hashes of transactions it sent or received, in chain order
+    account_transactions: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    /// Account balances ranked for rich lists
+    balances: Arc<Mutex<BalanceIndex>>,
+    /// Highest finalized block (number, hash)
+    finalized: Arc<Mutex<Option<(u64, String)>>>,
+    /// Applied validator penalties, in order
+    slashing_events: Arc<Mutex<Vec<SlashingEvent>>>,
+    /// Block number -> contract logs emitted in the block
+    logs: Arc<Mutex<BTreeMap<u64, Vec<IndexedLog>>>>,
+    /// Block number -> bloom over the addresses its transactions involve
+    /// and the addresses and topics of its logs
+    blooms: Arc<Mutex<BTreeMap<u64, Vec<u8>>>>,
+    /// NFT ownership, by token and by owner
+    nfts: Arc<Mutex<NftIndex>>,
+    /// Ommer hash -> stale block header and the block including it
+    ommers: Arc<Mutex<HashMap<String, OmmerIndexEntry>>>,
+    /// Database holding the height, sender and recipient indexes
+    db: Option<Arc<Db>>,
 }
 
 /// Indexed block information
@@ -22,6 +197,14 @@ pub struct BlockIndexEntry {
     pub timestamp: u64,
 }
 
+/// Stale block included as an ommer
+#[derive(Clone, Debug, Serialize)]
+pub struct OmmerIndexEntry {
+    pub ommer: OmmerHeader,
+    pub included_in: String,
+    pub included_at: u64,
+}
+
 /// Indexed transaction information
 #[derive(Clone, Debug)]
 pub struct TransactionIndexEntry {
@@ -31,28 +214,60 @@ pub struct TransactionIndexEntry {
     pub tx_index: usize,  // Position in block transactions
 }
 
+/// Whether an indexed block is part of the chain consumers should read
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockStatus {
+    /// On the chain but not yet finalized
+    Canonical,
+    /// Replaced by another block at its height in a reorg
+    Orphaned,
+    /// On the chain at or below the finalized height
+    Finalized,
+}
+
 impl BlockchainIndexer {
     /// Create a new empty indexer
     pub fn new() -> Self {
         BlockchainIndexer {
             blocks: Arc::new(Mutex::new(HashMap::new())),
             transactions: Arc::new(Mutex::new(HashMap::new())),
-            block_numbers: Arc::new(Mutex::new(HashMap::new())),
+            block_numbers: Arc::new(Mutex::new(BTreeMap::new())),
+            receipts: Arc::new(Mutex::new(HashMap::new())),
+            account_transactions: Arc::new(Mutex::new(HashMap::new())),
+            balances: Arc::new(Mutex::new(BalanceIndex::default())),
+            finalized: Arc::new(Mutex::new(None)),
+            slashing_events: Arc::new(Mutex::new(Vec::new())),
+            logs: Arc::new(Mutex::new(BTreeMap::new())),
+            blooms: Arc::new(Mutex::new(BTreeMap::new())),
+            nfts: Arc::new(Mutex::new(NftIndex::default())),
+            ommers: Arc::new(Mutex::new(HashMap::new())),
+            db: None,
         }
     }
 
+    /// Persist height, sender and recipient indexes in `db`
+    pub fn with_db(mut self, db: Arc<Db>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
     /// Index a newly produced block
     /// Should be called after block is created but before broadcast
+    ///
+    /// A different block at an already indexed height is a reorg: the block it
+    /// replaces and every block above it are orphaned, and their transactions
+    /// that the new block does not include are dropped from the indexes.
     pub fn index_block(
         &self,
         block: Block,
         block_number: u64,
         timestamp: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), IndexerError> {
         let block_hash = block.hash.clone();
 
         // Index block
-        let mut blocks = self.blocks.lock().map_err(|e| e.to_string())?;
+        let mut blocks = self.blocks.lock()?;
         blocks.insert(
             block_hash.clone(),
             BlockIndexEntry {
@@ -63,14 +278,49 @@ impl BlockchainIndexer {
         );
 
         // Index block by number
-        let mut block_numbers = self.block_numbers.lock().map_err(|e| e.to_string())?;
-        block_numbers.insert(block_number, block_hash.clone());
+        let mut block_numbers = self.block_numbers.lock()?;
+        let replaced = block_numbers.insert(block_number, block_hash.clone());
+        // The database indexes of the block are written in one batch
+        let mut batch = Batch::default();
+        let mut orphaned: Vec<String> = Vec::new();
+        if let Some(previous) = replaced.filter(|previous| *previous != block_hash) {
+            orphaned.push(previous);
+            let above: Vec<u64> = block_numbers.range(block_number + 1..).map(|(number, _)| *number).collect();
+            let mut logs = self.logs.lock()?;
+            let mut blooms = self.blooms.lock()?;
+            for number in above {
+                orphaned.extend(block_numbers.remove(&number));
+                logs.remove(&number);
+                blooms.remove(&number);
+                batch.delete(Column::Index, &[HEIGHT_PREFIX, &number.to_be_bytes()].concat());
+            }
+        }
+        for entry in orphaned.iter().filter_map(|hash| blocks.get(hash)) {
+            for (tx_index, tx) in entry.block.transactions.iter().enumerate() {
+                for key in account_keys(tx, entry.block_number, tx_index) {
+                    batch.delete(Column::Index, &key);
+                }
+            }
+        }
 
         // Index transactions within the block
-        let mut transactions = self.transactions.lock().map_err(|e| e.to_string())?;
+        let mut transactions = self.transactions.lock()?;
+        let mut account_transactions = self.account_transactions.lock()?;
         for (tx_index, tx) in block.transactions.iter().enumerate() {
             // Compute transaction hash (simple hash of serialized tx)
-            let tx_hash = self.compute_tx_hash(tx);
+            let tx_hash = tx.hash();
+            for key in account_keys(tx, block_number, tx_index) {
+                batch.put(Column::Index, &key, tx_hash.as_bytes());
+            }
+            // Re-indexed transactions keep their place in account histories
+            if !transactions.contains_key(&tx_hash) {
+                for account in std::iter::once(tx.from.as_str()).chain(tx.recipient()) {
+                    let hashes = account_transactions.entry(account.to_string()).or_default();
+                    if hashes.last() != Some(&tx_hash) {
+                        hashes.push(tx_hash.clone());
+                    }
+                }
+            }
             transactions.insert(
                 tx_hash,
                 TransactionIndexEntry {
@@ -82,20 +332,78 @@ impl BlockchainIndexer {
             );
         }
 
+        // Transactions still pointing at an orphaned block are no longer on the chain
+        let mut receipts = self.receipts.lock()?;
+        for entry in orphaned.iter().filter_map(|hash| blocks.get(hash)) {
+            for tx in &entry.block.transactions {
+                let tx_hash = tx.hash();
+                if transactions.get(&tx_hash).is_none_or(|indexed| indexed.block_hash != entry.block.hash) {
+                    continue;
+                }
+                transactions.remove(&tx_hash);
+                receipts.remove(&tx_hash);
+                for account in std::iter::once(tx.from.as_str()).chain(tx.recipient()) {
+                    if let Some(hashes) = account_transactions.get_mut(account) {
+                        hashes.retain(|hash| *hash != tx_hash);
+                    }
+                }
+            }
+        }
+        // Ommers of orphaned blocks may be included again by another
+        let mut ommers = self.ommers.lock()?;
+        ommers.retain(|_, entry| !orphaned.contains(&entry.included_in));
+        for ommer in &block.ommers {
+            let entry = OmmerIndexEntry { ommer: ommer.clone(), included_in: block_hash.clone(), included_at: block_number };
+            ommers.insert(ommer.hash.clone(), entry);
+        }
+
+        // Logs of produced blocks are indexed before the block itself
+        let logs = self.logs.lock()?;
+        let block_logs = logs.get(&block_number).map(Vec::as_slice).unwrap_or_default();
+        self.blooms.lock()?.insert(block_number, block_bloom(&block.transactions, block_logs));
+        drop(logs);
+
+        if let Some(db) = &self.db {
+            batch.put(Column::Index, &[HEIGHT_PREFIX, &block_number.to_be_bytes()].concat(), block_hash.as_bytes());
+            db.write(batch);
+        }
+
         Ok(())
     }
 
     /// Retrieve block by hash
-    pub fn get_block(&self, block_hash: &str) -> Result<Option<BlockIndexEntry>, String> {
-        let blocks = self.blocks.lock().map_err(|e| e.to_string())?;
+    pub fn get_block(&self, block_hash: &str) -> Result<Option<BlockIndexEntry>, IndexerError> {
+        let blocks = self.blocks.lock()?;
         Ok(blocks.get(block_hash).cloned())
     }
 
+    /// Stale block `ommer_hash` and the block of the chain that included it
+    pub fn get_ommer(&self, ommer_hash: &str) -> Result<Option<OmmerIndexEntry>, IndexerError> {
+        let ommers = self.ommers.lock()?;
+        Ok(ommers.get(ommer_hash).cloned())
+    }
+
+    /// Whether block `block_hash` is canonical, orphaned or finalized
+    pub fn get_block_status(&self, block_hash: &str) -> Result<Option<BlockStatus>, IndexerError> {
+        let Some(entry) = self.get_block(block_hash)? else {
+            return Ok(None);
+        };
+        let block_numbers = self.block_numbers.lock()?;
+        if block_numbers.get(&entry.block_number).map(String::as_str) != Some(block_hash) {
+            return Ok(Some(BlockStatus::Orphaned));
+        }
+        drop(block_numbers);
+        Ok(Some(match self.get_finalized_block_number()? {
+            Some(finalized) if entry.block_number <= finalized => BlockStatus::Finalized,
+            _ => BlockStatus::Canonical,
+        }))
+    }
+
     /// Retrieve block by block number
-    pub fn get_block_by_number(&self, block_number: u64) -> Result<Option<BlockIndexEntry>, String> {
-        let block_numbers = self.block_numbers.lock().map_err(|e| e.to_string())?;
+    pub fn get_block_by_number(&self, block_number: u64) -> Result<Option<BlockIndexEntry>, IndexerError> {
+        let block_numbers = self.block_numbers.lock()?;
         if let Some(block_hash) = block_numbers.get(&block_number) {
-            let blocks = self.blocks.lock().map_err(|e| e.to_string())?;
+            let blocks = self.blocks.lock()?;
             Ok(blocks.get(block_hash).cloned())
         } else {
             Ok(None)
@@ -103,17 +411,32 @@ impl BlockchainIndexer {
     }
 
     /// Retrieve transaction by hash
-    pub fn get_transaction(&self, tx_hash: &str) -> Result<Option<TransactionIndexEntry>, String> {
-        let transactions = self.transactions.lock().map_err(|e| e.to_string())?;
+    pub fn get_transaction(&self, tx_hash: &str) -> Result<Option<TransactionIndexEntry>, IndexerError> {
+        let transactions = self.transactions.lock()?;
         Ok(transactions.get(tx_hash).cloned())
     }
 
+    /// Store the receipts of executed transactions
+    pub fn index_receipts(&self, receipts: Vec<TransactionReceipt>) -> Result<(), IndexerError> {
+        let mut indexed = self.receipts.lock()?;
+        for receipt in receipts {
+            indexed.insert(receipt.tx_hash.clone(), receipt);
+        }
+        Ok(())
+    }
+
+    /// Retrieve the receipt of an executed transaction
+    pub fn get_receipt(&self, tx_hash: &str) -> Result<Option<TransactionReceipt>, IndexerError> {
+        let receipts = self.receipts.lock()?;
+        Ok(receipts.get(tx_hash).cloned())
+    }
+
     /// Get all transactions in a block
     pub fn get_block_transactions(
         &self,
         block_hash: &str,
-    ) -> Result<Vec<TransactionIndexEntry>, String> {
-        let transactions = self.transactions.lock().map_err(|e| e.to_string())?;
+    ) -> Result<Vec<TransactionIndexEntry>, IndexerError> {
+        let transactions = self.transactions.lock()?;
         let mut block_txs: Vec<_> = transactions
             .values()
             .filter(|tx| &tx.block_hash == block_hash)
@@ -125,51 +448,391 @@ impl BlockchainIndexer {
         Ok(block_txs)
     }
 
+    /// Get a page of blocks, highest first
+    pub fn get_blocks_page(&self, page: usize, page_size: usize) -> Result<Page<BlockIndexEntry>, IndexerError> {
+        let block_numbers = self.block_numbers.lock()?;
+        let blocks = self.blocks.lock()?;
+        let hashes: Vec<&String> = block_numbers.values().collect();
+        let Page { items, total, .. } = Page::newest_first(&hashes, page, page_size);
+        Ok(Page {
+            items: items.into_iter().filter_map(|hash| blocks.get(hash).cloned()).collect(),
+            page,
+            page_size,
+            total,
+        })
+    }
+
+    /// Get a page of transactions sent or received by `account`, newest first
+    pub fn get_account_transactions(
+        &self,
+        account: &str,
+        page: usize,
+        page_size: usize,
+    ) -> Result<Page<TransactionIndexEntry>, IndexerError> {
+        let account_transactions = self.account_transactions.lock()?;
+        let transactions = self.transactions.lock()?;
+        let hashes = account_transactions.get(account).map(Vec::as_slice).unwrap_or_default();
+        let Page { items, total, .. } = Page::newest_first(hashes, page, page_size);
+        Ok(Page {
+            items: items.iter().filter_map(|hash| transactions.get(hash).cloned()).collect(),
+            page,
+            page_size,
+            total,
+        })
+    }
+
+    /// Get up to `limit` blocks below `cursor`, highest first, from the height index
+    pub fn get_blocks_before(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<CursorPage<BlockIndexEntry>, IndexerError> {
+        let (entries, next_cursor) = self.scan_page(HEIGHT_PREFIX, cursor, limit)?;
+        let blocks = self.blocks.lock()?;
+        Ok(CursorPage {
+            items: entries
+                .iter()
+                .filter_map(|(_, hash)| blocks.get(&*String::from_utf8_lossy(hash)).cloned())
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Get up to `limit` transactions sent by `account` before `cursor`, newest first
+    pub fn get_sent_transactions(
+        &self,
+        account: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<CursorPage<TransactionIndexEntry>, IndexerError> {
+        self.account_page(&account_prefix(SENT_PREFIX, account), cursor, limit)
+    }
+
+    /// Get up to `limit` transactions received by `account` before `cursor`, newest first
+    pub fn get_received_transactions(
+        &self,
+        account: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<CursorPage<TransactionIndexEntry>, IndexerError> {
+        self.account_page(&account_prefix(RECEIVED_PREFIX, account), cursor, limit)
+    }
+
+    fn account_page(
+        &self,
+        prefix: &[u8],
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<CursorPage<TransactionIndexEntry>, IndexerError> {
+        let (entries, next_cursor) = self.scan_page(prefix, cursor, limit)?;
+        let transactions = self.transactions.lock()?;
+        Ok(CursorPage {
+            items: entries
+                .iter()
+                .filter_map(|(_, hash)| transactions.get(&*String::from_utf8_lossy(hash)).cloned())
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Up to `limit` database entries under `prefix` before `cursor`, in
+    /// descending key order, and the cursor of the entries after them
+    ///
+    /// A cursor is the hex-encoded key suffix of the last entry of a page.
+    fn scan_page(
+        &self,
+        prefix: &[u8],
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<(Vec<DbEntry>, Option<String>), IndexerError> {
+        let db = self.db.as_ref().ok_or(IndexerError::NoDatabase)?;
+        let before = cursor
+            .map(|cursor| {
+                hex::decode(cursor)
+                    .map(|suffix| [prefix, &suffix].concat())
+                    .map_err(|_| IndexerError::InvalidCursor(cursor.to_string()))
+            })
+            .transpose()?;
+        // Start past every key under the prefix when there is no cursor
+        let start = before.clone().unwrap_or_else(|| [prefix, &[0xff; 32]].concat());
+        let mut entries: Vec<DbEntry> = db
+            .iter(Column::Index, &start, Direction::Reverse)
+            .skip_while(|(key, _)| before.as_ref() == Some(key))
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .take(limit.saturating_add(1))
+            .collect();
+        let next_cursor = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|(key, _)| hex::encode(&key[prefix.len()..]))
+        } else {
+            None
+        };
+        Ok((entries, next_cursor))
+    }
+
+    /// Record the current balance of an account
+    pub fn update_balance(&self, account: &str, balance: u64) -> Result<(), IndexerError> {
+        let mut index = self.balances.lock()?;
+        if let Some(previous) = index.balances.insert(account.to_string(), balance) {
+            index.ranked.remove(&(Reverse(previous), account.to_string()));
+        }
+        index.ranked.insert((Reverse(balance), account.to_string()));
+        Ok(())
+    }
+
+    /// Record the balances an applied block changed
+    pub fn index_state_diff(&self, diff: &StateDiff) -> Result<(), IndexerError> {
+        for account in &diff.accounts {
+            self.update_balance(&account.address, account.balance)?;
+        }
+        Ok(())
+    }
+
+    /// Re-index every account balance held in the state trie
+    pub fn index_balances(&self, trie: &MerklePatriciaTrie) -> Result<(), IndexerError> {
+        for (key, value) in trie.entries() {
+            let (Ok(account), Ok(balance)) = (String::from_utf8(key), <[u8; 8]>::try_from(value)) else {
+                continue;
+            };
+            self.update_balance(&account, u64::from_le_bytes(balance))?;
+        }
+        Ok(())
+    }
+
+    /// Record the current state of token `id` of `collection`; `None` once it is burned
+    pub fn update_nft(&self, collection: &str, id: u64, token: Option<Nft>) -> Result<(), IndexerError> {
+        let mut index = self.nfts.lock()?;
+        let key = (collection.to_string(), id);
+        if let Some(previous) = index.tokens.remove(&key)
+            && let Some(owned) = index.owned.get_mut(&previous.owner)
+        {
+            owned.remove(&key);
+        }
+        if let Some(token) = token {
+            index.owned.entry(token.owner.clone()).or_default().insert(key.clone());
+            index.tokens.insert(key, token);
+        }
+        Ok(())
+    }
+
+    /// Re-index the NFTs touched by `transactions`, reading their state through `get`
+    pub fn index_nft_transactions(
+        &self,
+        transactions: &[Transaction],
+        get: impl Fn(&[u8]) -> Option<Vec<u8>>,
+    ) -> Result<(), IndexerError> {
+        for tx in transactions {
+            if let TransactionPayload::Nft(op) = &tx.payload
+                && let Some((collection, id)) = op.token()
+            {
+                self.update_nft(collection, id, nft::token_at(&get, collection, id))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-index every NFT held in the state trie
+    pub fn index_nfts(&self, trie: &MerklePatriciaTrie) -> Result<(), IndexerError> {
+        for (key, value) in trie.entries() {
+            if !nft::is_token_key(&key) {
+                continue;
+            }
+            if let Some(token) = nft::decode_token(&value) {
+                let (collection, id) = (token.collection.clone(), token.id);
+                self.update_nft(&collection, id, Some(token))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get token `id` of `collection`
+    pub fn get_nft(&self, collection: &str, id: u64) -> Result<Option<Nft>, IndexerError> {
+        let index = self.nfts.lock()?;
+        Ok(index.tokens.get(&(collection.to_string(), id)).cloned())
+    }
+
+    /// Get the NFTs `account` owns, ordered by collection and id
+    pub fn get_account_nfts(&self, account: &str) -> Result<Vec<Nft>, IndexerError> {
+        let index = self.nfts.lock()?;
+        Ok(index
+            .owned
+            .get(account)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| index.tokens.get(key).cloned())
+            .collect())
+    }
+
+    /// Get the `limit` accounts with the highest balances
+    pub fn get_top_accounts(&self, limit: usize) -> Result<Vec<(String, u64)>, IndexerError> {
+        let index = self.balances.lock()?;
+        Ok(index
+            .ranked
+            .iter()
+            .take(limit)
+            .map(|(Reverse(balance), account)| (account.clone(), *balance))
+            .collect())
+    }
+
     /// Get latest block number
-    pub fn get_latest_block_number(&self) -> Result<Option<u64>, String> {
-        let block_numbers = self.block_numbers.lock().map_err(|e| e.to_string())?;
-        Ok(block_numbers.keys().max().copied())
+    pub fn get_latest_block_number(&self) -> Result<Option<u64>, IndexerError> {
+        let block_numbers = self.block_numbers.lock()?;
+        Ok(block_numbers.keys().next_back().copied())
     }
 
     /// Get latest block hash
-    pub fn get_latest_block_hash(&self) -> Result<Option<String>, String> {
+    pub fn get_latest_block_hash(&self) -> Result<Option<String>, IndexerError> {
         let latest_num = self.get_latest_block_number()?;
         if let Some(num) = latest_num {
-            let block_numbers = self.block_numbers.lock().map_err(|e| e.to_string())?;
+            let block_numbers = self.block_numbers.lock()?;
             Ok(block_numbers.get(&num).cloned())
         } else {
             Ok(None)
         }
     }
 
+    /// Record a block as finalized
+    /// Finality only moves forward; lower heights are ignored
+    pub fn mark_finalized(&self, block_number: u64, block_hash: &str) -> Result<(), IndexerError> {
+        let mut finalized = self.finalized.lock()?;
+        if finalized.as_ref().is_none_or(|(height, _)| block_number > *height) {
+            *finalized = Some((block_number, block_hash.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Get highest finalized block number
+    pub fn get_finalized_block_number(&self) -> Result<Option<u64>, IndexerError> {
+        let finalized = self.finalized.lock()?;
+        Ok(finalized.as_ref().map(|(height, _)| *height))
+    }
+
+    /// Get highest finalized block hash
+    pub fn get_finalized_block_hash(&self) -> Result<Option<String>, IndexerError> {
+        let finalized = self.finalized.lock()?;
+        Ok(finalized.as_ref().map(|(_, hash)| hash.clone()))
+    }
+
+    /// Record an applied slashing penalty
+    pub fn record_slashing_event(&self, event: SlashingEvent) -> Result<(), IndexerError> {
+        let mut events = self.slashing_events.lock()?;
+        events.push(event);
+        Ok(())
+    }
+
+    /// Get slashing events, optionally only those for one validator
+    pub fn get_slashing_events(&self, validator: Option<&str>) -> Result<Vec<SlashingEvent>, IndexerError> {
+        let events = self.slashing_events.lock()?;
+        Ok(events
+            .iter()
+            .filter(|event| validator.is_none_or(|v| event.validator == v))
+            .cloned()
+            .collect())
+    }
+
+    /// Record the contract logs emitted in a block
+    pub fn index_logs(&self, block_number: u64, logs: Vec<IndexedLog>) -> Result<(), IndexerError> {
+        let mut indexed = self.logs.lock()?;
+        let mut blooms = self.blooms.lock()?;
+        let bloom = blooms.entry(block_number).or_insert_with(|| vec![0u8; BLOOM_SIZE]);
+        logs::accrue_logs(bloom, logs.iter().map(|l| &l.log));
+        indexed.insert(block_number, logs);
+        Ok(())
+    }
+
+    /// Get logs matching `filter`, in chain order
+    /// Blocks whose bloom rules out the filter are skipped without scanning their logs
+    pub fn get_logs(&self, filter: &LogFilter) -> Result<Vec<IndexedLog>, IndexerError> {
+        let indexed = self.logs.lock()?;
+        let blooms = self.blooms.lock()?;
+        let from = filter.from_block.unwrap_or(0);
+        let to = filter.to_block.unwrap_or(u64::MAX);
+        if from > to {
+            return Ok(Vec::new());
+        }
+        Ok(indexed
+            .range(from..=to)
+            .filter(|(number, _)| blooms.get(number).is_none_or(|bloom| filter.may_match_bloom(bloom)))
+            .flat_map(|(_, logs)| logs.iter().filter(|l| filter.matches(&l.log)).cloned())
+            .collect())
+    }
+
+    /// Get the transactions involving `address` in blocks `from..=to`, newest first
+    ///
+    /// A transaction involves the address if the address sent it, received
+    /// it, is the contract it calls or upgrades, or emitted a log during it.
+    /// Blocks whose bloom rules out the address are skipped without reading
+    /// their transactions. Once `limit` transactions are found no further
+    /// blocks are read, and `next_block` tells where to continue.
+    pub fn get_address_history(&self, address: &str, from: u64, to: u64, limit: usize) -> Result<AddressHistory, IndexerError> {
+        let blocks = self.blocks.lock()?;
+        let block_numbers = self.block_numbers.lock()?;
+        let transactions = self.transactions.lock()?;
+        let logs = self.logs.lock()?;
+        let blooms = self.blooms.lock()?;
+        let mut history =
+            AddressHistory { transactions: Vec::new(), blocks_scanned: 0, blocks_skipped: 0, next_block: None };
+        if from > to {
+            return Ok(history);
+        }
+        for (&number, hash) in block_numbers.range(from..=to).rev() {
+            if history.transactions.len() >= limit {
+                history.next_block = Some(number);
+                break;
+            }
+            if blooms.get(&number).is_some_and(|bloom| !bloom_contains(bloom, address.as_bytes())) {
+                history.blocks_skipped += 1;
+                continue;
+            }
+            history.blocks_scanned += 1;
+            let Some(entry) = blocks.get(hash) else {
+                continue;
+            };
+            let emitted: BTreeSet<&str> = logs
+                .get(&number)
+                .into_iter()
+                .flatten()
+                .filter(|l| l.log.address == address)
+                .map(|l| l.tx_hash.as_str())
+                .collect();
+            for tx in entry.block.transactions.iter().rev() {
+                let tx_hash = tx.hash();
+                if involved_addresses(tx).any(|involved| involved == address) || emitted.contains(tx_hash.as_str()) {
+                    history.transactions.extend(transactions.get(&tx_hash).cloned());
+                }
+            }
+        }
+        Ok(history)
+    }
+
     /// Get transaction count
-    pub fn get_transaction_count(&self) -> Result<u64, String> {
-        let transactions = self.transactions.lock().map_err(|e| e.to_string())?;
+    pub fn get_transaction_count(&self) -> Result<u64, IndexerError> {
+        let transactions = self.transactions.lock()?;
         Ok(transactions.len() as u64)
     }
 
     /// Get block count
-    pub fn get_block_count(&self) -> Result<u64, String> {
-        let blocks = self.blocks.lock().map_err(|e| e.to_string())?;
+    pub fn get_block_count(&self) -> Result<u64, IndexerError> {
+        let blocks = self.blocks.lock()?;
         Ok(blocks.len() as u64)
     }
 
     /// Clear all indexes (useful for testing)
     #[allow(dead_code)]
-    pub fn clear(&self) -> Result<(), String> {
-        self.blocks.lock().map_err(|e| e.to_string())?.clear();
-        self.transactions.lock().map_err(|e| e.to_string())?.clear();
-        self.block_numbers.lock().map_err(|e| e.to_string())?.clear();
+    pub fn clear(&self) -> Result<(), IndexerError> {
+        self.blocks.lock()?.clear();
+        self.transactions.lock()?.clear();
+        self.block_numbers.lock()?.clear();
+        self.receipts.lock()?.clear();
+        self.account_transactions.lock()?.clear();
+        *self.balances.lock()? = BalanceIndex::default();
+        *self.finalized.lock()? = None;
+        self.slashing_events.lock()?.clear();
+        self.logs.lock()?.clear();
+        self.blooms.lock()?.clear();
+        *self.nfts.lock()? = NftIndex::default();
         Ok(())
     }
-
-    /// Compute hash of a transaction (simple SHA256 of debug representation)
-    fn compute_tx_hash(&self, tx: &Transaction) -> String {
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{:?}", tx).as_bytes());
-        format!("{:x}", hasher.finalize())
-    }
 }
 
 impl Default for BlockchainIndexer {
@@ -190,6 +853,17 @@ mod tests {
             hash: "test_block_hash".to_string(),
             pre_state_root: vec![],
             post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
         }
     }
 
@@ -229,6 +903,252 @@ mod tests {
         assert_eq!(retrieved.block_number, 5);
     }
 
+    #[test]
+    fn test_finalized_height_only_advances() {
+        let indexer = BlockchainIndexer::new();
+        assert_eq!(indexer.get_finalized_block_number().unwrap(), None);
+
+        indexer.mark_finalized(4, "hash4").unwrap();
+        indexer.mark_finalized(2, "hash2").unwrap();
+        assert_eq!(indexer.get_finalized_block_number().unwrap(), Some(4));
+        assert_eq!(indexer.get_finalized_block_hash().unwrap(), Some("hash4".to_string()));
+    }
+
+    #[test]
+    fn test_slashing_events_by_validator() {
+        use crate::slashing::Misbehavior;
+
+        let indexer = BlockchainIndexer::new();
+        for validator in ["alice", "bob", "alice"] {
+            indexer
+                .record_slashing_event(SlashingEvent {
+                    validator: validator.to_string(),
+                    misbehavior: Misbehavior::Downtime,
+                    height: 1,
+                    slashed: 10,
+                    burned: 5,
+                    redistributed: 5,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(indexer.get_slashing_events(None).unwrap().len(), 3);
+        assert_eq!(indexer.get_slashing_events(Some("alice")).unwrap().len(), 2);
+        assert!(indexer.get_slashing_events(Some("carol")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_log_queries() {
+        use crate::logs::Log;
+
+        let indexer = BlockchainIndexer::new();
+        for (block_number, address) in [(1, "0xtoken"), (2, "0xother"), (3, "0xtoken")] {
+            let log = IndexedLog {
+                block_number,
+                block_hash: format!("hash{}", block_number),
+                tx_hash: format!("tx{}", block_number),
+                log_index: 0,
+                log: Log::new(address, &[[block_number as u8; 32]], &[]),
+            };
+            indexer.index_logs(block_number, vec![log]).unwrap();
+        }
+
+        let by_address = LogFilter { address: Some("0xtoken".to_string()), ..Default::default() };
+        let numbers: Vec<u64> = indexer.get_logs(&by_address).unwrap().iter().map(|l| l.block_number).collect();
+        assert_eq!(numbers, vec![1, 3]);
+
+        let in_range = LogFilter { from_block: Some(2), ..by_address };
+        assert_eq!(indexer.get_logs(&in_range).unwrap().len(), 1);
+
+        let by_topic = LogFilter { topics: vec![Some(hex::encode([2u8; 32]))], ..Default::default() };
+        assert_eq!(indexer.get_logs(&by_topic).unwrap()[0].block_hash, "hash2");
+    }
+
+    #[test]
+    fn test_blocks_page_newest_first() {
+        let indexer = BlockchainIndexer::new();
+        for number in 0..5 {
+            let mut block = create_test_block();
+            block.hash = format!("hash{}", number);
+            indexer.index_block(block, number, 1000).unwrap();
+        }
+
+        let first = indexer.get_blocks_page(0, 2).unwrap();
+        let numbers: Vec<u64> = first.items.iter().map(|b| b.block_number).collect();
+        assert_eq!(numbers, vec![4, 3]);
+        assert_eq!(first.total, 5);
+        assert_eq!(indexer.get_blocks_page(2, 2).unwrap().items[0].block_number, 0);
+        assert!(indexer.get_blocks_page(3, 2).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn test_account_transactions() {
+        let indexer = BlockchainIndexer::new();
+        let mut block = create_test_block();
+        block.transactions = vec![
+            Transaction::transfer("alice".to_string(), "bob".to_string(), 10),
+            Transaction::transfer("bob".to_string(), "carol".to_string(), 5),
+        ];
+        indexer.index_block(block, 1, 1000).unwrap();
+
+        let bob = indexer.get_account_transactions("bob", 0, 10).unwrap();
+        assert_eq!(bob.total, 2);
+        assert_eq!(bob.items[0].tx_index, 1);
+        assert_eq!(indexer.get_account_transactions("alice", 0, 10).unwrap().total, 1);
+        assert_eq!(indexer.get_account_transactions("dave", 0, 10).unwrap().total, 0);
+    }
+
+    #[test]
+    fn test_address_history_skips_blocks_by_bloom() {
+        use crate::logs::Log;
+
+        let indexer = BlockchainIndexer::new();
+        let transfers = [("alice", "bob"), ("carol", "dave"), ("bob", "erin"), ("carol", "0xtoken")];
+        for (number, (from, to)) in (1..).zip(transfers) {
+            let mut block = create_test_block();
+            block.hash = format!("hash{}", number);
+            block.transactions = vec![Transaction::transfer(from.to_string(), to.to_string(), number)];
+            if to == "0xtoken" {
+                // 0xminter emits a log during a transaction it is not a party to
+                let log = IndexedLog {
+                    block_number: number,
+                    block_hash: block.hash.clone(),
+                    tx_hash: block.transactions[0].hash(),
+                    log_index: 0,
+                    log: Log::new("0xminter", &[], &[]),
+                };
+                indexer.index_logs(number, vec![log]).unwrap();
+            }
+            indexer.index_block(block, number, 1000).unwrap();
+        }
+
+        let bob = indexer.get_address_history("bob", 0, u64::MAX, 10).unwrap();
+        let numbers: Vec<u64> = bob.transactions.iter().map(|entry| entry.block_number).collect();
+        assert_eq!(numbers, vec![3, 1]);
+        // Blocks 2 and 4 never involve bob (barring a false positive)
+        assert_eq!((bob.blocks_scanned, bob.blocks_skipped), (2, 2));
+
+        let minter = indexer.get_address_history("0xminter", 0, u64::MAX, 10).unwrap();
+        assert_eq!(minter.transactions.len(), 1);
+        assert_eq!(minter.transactions[0].block_number, 4);
+        let by_log = LogFilter { address: Some("0xminter".to_string()), ..Default::default() };
+        assert_eq!(indexer.get_logs(&by_log).unwrap().len(), 1);
+
+        let carol = indexer.get_address_history("carol", 0, 3, 1).unwrap();
+        assert_eq!((carol.transactions[0].block_number, carol.next_block), (2, Some(1)));
+    }
+
+    #[test]
+    fn test_reorg_orphans_replaced_branch() {
+        let indexer = BlockchainIndexer::new();
+        let shared = Transaction::transfer("alice".to_string(), "bob".to_string(), 10);
+        let dropped = Transaction::transfer("alice".to_string(), "carol".to_string(), 5);
+        let ommer = OmmerHeader::of(&create_test_block(), 1);
+        for (height, transactions) in [(0, vec![]), (1, vec![shared.clone()]), (2, vec![dropped.clone()])] {
+            let mut block = create_test_block();
+            block.hash = format!("main{}", height);
+            block.transactions = transactions;
+            if height == 2 {
+                block.ommers = vec![ommer.clone()];
+            }
+            indexer.index_block(block, height, 1000).unwrap();
+        }
+        let included = indexer.get_ommer(&ommer.hash).unwrap().unwrap();
+        assert_eq!((included.included_in.as_str(), included.included_at), ("main2", 2));
+        indexer.mark_finalized(0, "main0").unwrap();
+
+        let mut fork = create_test_block();
+        fork.hash = "fork1".to_string();
+        fork.transactions = vec![shared.clone()];
+        indexer.index_block(fork, 1, 1000).unwrap();
+
+        assert_eq!(indexer.get_block_status("main0").unwrap(), Some(BlockStatus::Finalized));
+        assert_eq!(indexer.get_block_status("fork1").unwrap(), Some(BlockStatus::Canonical));
+        assert_eq!(indexer.get_block_status("main1").unwrap(), Some(BlockStatus::Orphaned));
+        assert_eq!(indexer.get_block_status("main2").unwrap(), Some(BlockStatus::Orphaned));
+        assert_eq!(indexer.get_block_status("unknown").unwrap(), None);
+        assert_eq!(indexer.get_latest_block_hash().unwrap().as_deref(), Some("fork1"));
+
+        // Transactions follow the new branch or leave the index
+        assert_eq!(indexer.get_transaction(&shared.hash()).unwrap().unwrap().block_hash, "fork1");
+        assert!(indexer.get_transaction(&dropped.hash()).unwrap().is_none());
+        assert_eq!(indexer.get_account_transactions("carol", 0, 10).unwrap().total, 0);
+        assert_eq!(indexer.get_account_transactions("alice", 0, 10).unwrap().total, 1);
+        // Ommers of orphaned blocks are no longer included
+        assert!(indexer.get_ommer(&ommer.hash).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cursor_pages_from_database() {
+        let path = std::env::temp_dir().join(format!("aureon_indexer_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let indexer = BlockchainIndexer::new().with_db(db.clone());
+        for height in 0..3u64 {
+            let mut block = create_test_block();
+            block.hash = format!("hash{}", height);
+            block.transactions = vec![
+                Transaction::transfer("alice".to_string(), "bob".to_string(), height * 10 + 1),
+                Transaction::transfer("bob".to_string(), "carol".to_string(), height * 10 + 2),
+            ];
+            indexer.index_block(block, height, 1000).unwrap();
+        }
+
+        let first = indexer.get_sent_transactions("alice", None, 2).unwrap();
+        let positions: Vec<_> = first.items.iter().map(|entry| (entry.block_number, entry.tx_index)).collect();
+        assert_eq!(positions, vec![(2, 0), (1, 0)]);
+        let rest = indexer.get_sent_transactions("alice", first.next_cursor.as_deref(), 2).unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert_eq!(rest.items[0].block_number, 0);
+        assert_eq!(rest.next_cursor, None);
+
+        assert_eq!(indexer.get_received_transactions("carol", None, 10).unwrap().items.len(), 3);
+        assert!(indexer.get_received_transactions("alice", None, 10).unwrap().items.is_empty());
+        let blocks = indexer.get_blocks_before(None, 2).unwrap();
+        assert_eq!(blocks.items[0].block.hash, "hash2");
+        assert!(indexer.get_blocks_before(Some("zz"), 2).is_err());
+
+        // Re-indexing a height with another block drops the replaced block's entries
+        let mut replacement = create_test_block();
+        replacement.hash = "fork2".to_string();
+        indexer.index_block(replacement, 2, 1000).unwrap();
+        assert_eq!(indexer.get_sent_transactions("alice", None, 10).unwrap().items.len(), 2);
+        assert_eq!(indexer.get_blocks_before(None, 1).unwrap().items[0].block.hash, "fork2");
+
+        drop((indexer, db));
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_top_accounts() {
+        let indexer = BlockchainIndexer::new();
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"alice".to_vec(), 50u64.to_le_bytes().to_vec());
+        trie.insert(b"bob".to_vec(), 80u64.to_le_bytes().to_vec());
+        trie.insert(b"carol".to_vec(), 20u64.to_le_bytes().to_vec());
+        indexer.index_balances(&trie).unwrap();
+        indexer.update_balance("carol", 100).unwrap();
+
+        let top = indexer.get_top_accounts(2).unwrap();
+        assert_eq!(top, vec![("carol".to_string(), 100), ("bob".to_string(), 80)]);
+        assert_eq!(indexer.get_top_accounts(10).unwrap().len(), 3);
+
+        // Applied blocks move accounts through the ranking by their state diff
+        let diff = StateDiff {
+            block_hash: "h1".to_string(),
+            accounts: vec![crate::state_diff::AccountDiff {
+                address: "alice".to_string(),
+                previous_balance: 50,
+                balance: 150,
+                nonce: None,
+            }],
+            storage: vec![],
+        };
+        indexer.index_state_diff(&diff).unwrap();
+        assert_eq!(indexer.get_top_accounts(1).unwrap(), vec![("alice".to_string(), 150)]);
+        assert_eq!(indexer.get_top_accounts(10).unwrap().len(), 3);
+    }
+
     #[test]
     fn test_latest_block_number() {
         let indexer = BlockchainIndexer::new();
@@ -270,4 +1190,38 @@ mod tests {
         let count = indexer.get_block_count().expect("Failed to count blocks");
         assert_eq!(count, 2);
     }
+
+    #[test]
+    fn test_nft_ownership_follows_transactions() {
+        use crate::nft::{NftLedger, NftOp};
+
+        let indexer = BlockchainIndexer::new();
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut ledger = NftLedger::new(&mut state);
+        let punks = ledger.create_collection("alice", "Punks", "PNK", None).unwrap();
+        let txs: Vec<Transaction> = [
+            NftOp::Mint { collection: punks.clone(), id: 1, to: "bob".into(), metadata_uri: "ipfs://1".into() },
+            NftOp::Mint { collection: punks.clone(), id: 2, to: "bob".into(), metadata_uri: "ipfs://2".into() },
+        ]
+        .into_iter()
+        .map(|op| {
+            ledger.apply("alice", &op).unwrap();
+            Transaction::nft("alice".into(), op)
+        })
+        .collect();
+        indexer.index_nft_transactions(&txs, |key| state.get(key).cloned()).unwrap();
+        assert_eq!(indexer.get_account_nfts("bob").unwrap().len(), 2);
+
+        let mut ledger = NftLedger::new(&mut state);
+        let transfer = NftOp::Transfer { collection: punks.clone(), id: 1, to: "carol".into() };
+        let burn = NftOp::Burn { collection: punks.clone(), id: 2 };
+        ledger.apply("bob", &transfer).unwrap();
+        ledger.apply("bob", &burn).unwrap();
+        let txs = [Transaction::nft("bob".into(), transfer), Transaction::nft("bob".into(), burn)];
+        indexer.index_nft_transactions(&txs, |key| state.get(key).cloned()).unwrap();
+
+        assert!(indexer.get_account_nfts("bob").unwrap().is_empty());
+        assert_eq!(indexer.get_nft(&punks, 1).unwrap().unwrap().owner, "carol");
+        assert_eq!(indexer.get_nft(&punks, 2).unwrap(), None);
+    }
 }
@@ -1,9 +1,19 @@
 use aureon_core::types::{Block, Transaction, BlockHeader};
+use bincode::config::standard;
+use merkle_tree::IncrementalMerkleTree;
+use mpt::MerklePatriciaTrie;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub mod crypto;
 pub mod key_utils;
+pub mod mpt;
+
+pub use aureon_core::merkle_tree;
+
+/// Root recorded for an empty state trie or an empty transaction list
+pub const EMPTY_ROOT: &str = "0000000000000000000000000000000000000000000000000000000000000000";
 
 pub struct Blockchain {
     pub blocks: Vec<Block>,
@@ -12,19 +22,24 @@ pub struct Blockchain {
 
 impl Blockchain {
     pub fn new() -> Self {
-        let genesis_block = Self::create_genesis_block();
+        Self::with_balances(HashMap::new())
+    }
+
+    /// Create a chain whose genesis state holds the given balances
+    pub fn with_balances(state: HashMap<String, u64>) -> Self {
+        let genesis_block = Self::create_genesis_block(&state);
         Blockchain {
             blocks: vec![genesis_block],
-            state: HashMap::new(),
+            state,
         }
     }
 
-    fn create_genesis_block() -> Block {
+    fn create_genesis_block(state: &HashMap<String, u64>) -> Block {
         let header = BlockHeader {
             parent_hash: "0x0".to_string(),
             number: 0,
-            state_root: "0x0".to_string(),
-            tx_root: "0x0".to_string(),
+            state_root: compute_state_root(state),
+            tx_root: compute_tx_root(&[]),
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
         };
         Block {
@@ -35,15 +50,163 @@ impl Blockchain {
 
     pub fn add_block(&mut self, transactions: Vec<Transaction>) -> Block {
         let last_block = self.blocks.last().unwrap();
+        let mut state = self.state.clone();
+        apply_transactions(&mut state, &transactions);
         let header = BlockHeader {
             parent_hash: last_block.hash(),
             number: last_block.header.number + 1,
-            state_root: "0xSTUB".to_string(),  // placeholder for now
-            tx_root: "0xTXROOT".to_string(),   // placeholder
+            state_root: compute_state_root(&state),
+            tx_root: compute_tx_root(&transactions),
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
         };
         let new_block = Block { header, transactions };
+        self.state = state;
         self.blocks.push(new_block.clone());
         new_block
     }
-}
\ No newline at end of file
+
+    /// Check a block against the current head, recomputing both roots
+    pub fn validate_block(&self, block: &Block) -> Result<(), String> {
+        let last_block = self.blocks.last().unwrap();
+        if block.header.parent_hash != last_block.hash() {
+            return Err("Parent hash does not match chain head".to_string());
+        }
+        if block.header.number != last_block.header.number + 1 {
+            return Err(format!(
+                "Expected block number {}, got {}",
+                last_block.header.number + 1,
+                block.header.number
+            ));
+        }
+
+        let tx_root = compute_tx_root(&block.transactions);
+        if block.header.tx_root != tx_root {
+            return Err(format!("Transaction root mismatch: expected {}, got {}", tx_root, block.header.tx_root));
+        }
+
+        let mut state = self.state.clone();
+        apply_transactions(&mut state, &block.transactions);
+        let state_root = compute_state_root(&state);
+        if block.header.state_root != state_root {
+            return Err(format!("State root mismatch: expected {}, got {}", state_root, block.header.state_root));
+        }
+        Ok(())
+    }
+
+    /// Validate a block produced elsewhere and append it to the chain
+    pub fn import_block(&mut self, block: Block) -> Result<(), String> {
+        self.validate_block(&block)?;
+        apply_transactions(&mut self.state, &block.transactions);
+        self.blocks.push(block);
+        Ok(())
+    }
+}
+
+impl Default for Blockchain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply transfers in order, skipping any the sender cannot cover
+fn apply_transactions(state: &mut HashMap<String, u64>, transactions: &[Transaction]) {
+    for tx in transactions {
+        let from_balance = state.get(&tx.from).copied().unwrap_or(0);
+        if from_balance < tx.amount {
+            continue;
+        }
+        state.insert(tx.from.clone(), from_balance - tx.amount);
+        *state.entry(tx.to.clone()).or_insert(0) += tx.amount;
+    }
+}
+
+/// Hex-encoded SHA-256 of a transaction's canonical encoding
+pub fn transaction_hash(tx: &Transaction) -> String {
+    let encoded = bincode::encode_to_vec(tx, standard()).expect("Failed to serialize transaction");
+    hex::encode(Sha256::digest(&encoded))
+}
+
+/// Merkle root over the hashes of `transactions`
+pub fn compute_tx_root(transactions: &[Transaction]) -> String {
+    let tree: IncrementalMerkleTree = transactions.iter().map(transaction_hash).collect();
+    tree.root().unwrap_or_else(|| EMPTY_ROOT.to_string())
+}
+
+/// Patricia trie root over account balances
+pub fn compute_state_root(state: &HashMap<String, u64>) -> String {
+    let mut trie = MerklePatriciaTrie::new();
+    for (account, balance) in state {
+        trie.insert(account.as_bytes().to_vec(), balance.to_be_bytes().to_vec());
+    }
+    let root = trie.root_hash();
+    if root.is_empty() {
+        EMPTY_ROOT.to_string()
+    } else {
+        hex::encode(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(from: &str, to: &str, amount: u64) -> Transaction {
+        Transaction {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            signature: "0xSIGNATURE".to_string(),
+        }
+    }
+
+    fn funded_chain() -> Blockchain {
+        Blockchain::with_balances(HashMap::from([("Alice".to_string(), 1000)]))
+    }
+
+    #[test]
+    fn test_add_block_records_roots() {
+        let mut chain = funded_chain();
+        let block = chain.add_block(vec![transfer("Alice", "Bob", 100)]);
+
+        assert_eq!(chain.state["Alice"], 900);
+        assert_eq!(chain.state["Bob"], 100);
+        assert_eq!(block.header.state_root, compute_state_root(&chain.state));
+        assert_eq!(block.header.tx_root, compute_tx_root(&block.transactions));
+        assert_ne!(block.header.tx_root, EMPTY_ROOT);
+    }
+
+    #[test]
+    fn test_import_valid_block() {
+        let mut producer = funded_chain();
+        let mut follower = Blockchain {
+            blocks: producer.blocks.clone(),
+            state: producer.state.clone(),
+        };
+
+        let block = producer.add_block(vec![transfer("Alice", "Bob", 100)]);
+        assert!(follower.import_block(block).is_ok());
+        assert_eq!(follower.state, producer.state);
+    }
+
+    #[test]
+    fn test_rejects_mismatched_roots() {
+        let mut producer = funded_chain();
+        let follower = Blockchain {
+            blocks: producer.blocks.clone(),
+            state: producer.state.clone(),
+        };
+        let block = producer.add_block(vec![transfer("Alice", "Bob", 100)]);
+
+        let mut bad_state = block.clone();
+        bad_state.header.state_root = EMPTY_ROOT.to_string();
+        assert!(follower.validate_block(&bad_state).unwrap_err().contains("State root"));
+
+        let mut bad_txs = block.clone();
+        bad_txs.transactions.push(transfer("Alice", "Carol", 1));
+        assert!(follower.validate_block(&bad_txs).unwrap_err().contains("Transaction root"));
+
+        let mut bad_parent = block;
+        bad_parent.header.parent_hash = "0x0".to_string();
+        assert!(follower.validate_block(&bad_parent).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use sha2::{Sha256, Digest};
+use bincode::{Decode, Encode};
 use serde::{Serialize, Deserialize};
 
 /// Lightweight block header for SPV (Simplified Payment Verification)
@@ -6,7 +7,7 @@ use serde::{Serialize, Deserialize};
 /// 
 /// Traditional block header: ~1KB+ (full metadata)
 /// SPV header: ~256 bits (essential proof data only)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub struct LightBlockHeader {
     /// Block height in the chain
     pub height: u64,
@@ -28,6 +29,10 @@ pub struct LightBlockHeader {
     
     /// Nonce used in mining (for PoW verification)
     pub nonce: u64,
+
+    /// Hex root of the state trie after this block, empty if not committed to
+    #[serde(default)]
+    pub state_root: String,
 }
 
 impl LightBlockHeader {
@@ -48,6 +53,7 @@ impl LightBlockHeader {
             difficulty,
             nonce,
             block_hash: String::new(),
+            state_root: String::new(),
         };
         
         // Compute hash
@@ -55,6 +61,13 @@ impl LightBlockHeader {
         header
     }
 
+    /// Commit the header to the state root `state_root`, rehashing it
+    pub fn with_state_root(mut self, state_root: String) -> Self {
+        self.state_root = state_root;
+        self.block_hash = self.compute_hash();
+        self
+    }
+
     /// Compute the hash of this header
     pub fn compute_hash(&self) -> String {
         let mut hasher = Sha256::new();
@@ -66,6 +79,7 @@ impl LightBlockHeader {
         hasher.update(self.timestamp.to_le_bytes());
         hasher.update(self.difficulty.to_le_bytes());
         hasher.update(self.nonce.to_le_bytes());
+        hasher.update(self.state_root.as_bytes());
         
         format!("{:x}", hasher.finalize())
     }
@@ -75,6 +89,12 @@ impl LightBlockHeader {
         self.block_hash == self.compute_hash()
     }
 
+    /// Whether the header hash has `difficulty` leading zero hex digits
+    pub fn meets_difficulty(&self) -> bool {
+        self.block_hash.len() >= self.difficulty as usize
+            && self.block_hash.chars().take(self.difficulty as usize).all(|c| c == '0')
+    }
+
     /// Verify chain link to previous header
     pub fn verify_chain_link(&self, prev_header: &LightBlockHeader) -> bool {
         self.prev_hash == prev_header.block_hash && self.height == prev_header.height + 1
@@ -124,6 +144,13 @@ impl LightBlockHeader {
         let block_hash_bytes = self.block_hash.as_bytes();
         bytes.extend_from_slice(&(block_hash_bytes.len() as u16).to_le_bytes());
         bytes.extend_from_slice(block_hash_bytes);
+
+        // state_root with length prefix, left out when empty
+        if !self.state_root.is_empty() {
+            let state_root_bytes = self.state_root.as_bytes();
+            bytes.extend_from_slice(&(state_root_bytes.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(state_root_bytes);
+        }
         
         bytes
     }
@@ -230,6 +257,26 @@ impl LightBlockHeader {
         }
         let block_hash = String::from_utf8(bytes[pos..pos + block_hash_len].to_vec())
             .map_err(|_| "Invalid block_hash")?;
+        pos += block_hash_len;
+
+        // Parse the optional state_root (2 bytes length + variable)
+        let mut state_root = String::new();
+        if pos < bytes.len() {
+            if pos + 2 > bytes.len() {
+                return Err("Insufficient bytes for state_root length".to_string());
+            }
+            let state_root_len = u16::from_le_bytes(
+                bytes[pos..pos + 2]
+                    .try_into()
+                    .map_err(|_| "Invalid state_root length")?,
+            ) as usize;
+            pos += 2;
+            if pos + state_root_len > bytes.len() {
+                return Err("Insufficient bytes for state_root".to_string());
+            }
+            state_root = String::from_utf8(bytes[pos..pos + state_root_len].to_vec())
+                .map_err(|_| "Invalid state_root")?;
+        }
 
         Ok(LightBlockHeader {
             height,
@@ -239,6 +286,7 @@ impl LightBlockHeader {
             difficulty,
             nonce,
             block_hash,
+            state_root,
         })
     }
 }
@@ -375,6 +423,13 @@ mod tests {
         assert_eq!(deserialized.height, header.height);
         assert_eq!(deserialized.timestamp, header.timestamp);
         assert_eq!(deserialized.difficulty, header.difficulty);
+
+        // A committed state root changes the hash and survives the round trip
+        let committed = header.clone().with_state_root("ab".repeat(32));
+        assert_ne!(committed.block_hash, header.block_hash);
+        let deserialized = LightBlockHeader::from_compact_bytes(&committed.to_compact_bytes()).unwrap();
+        assert_eq!(deserialized, committed);
+        assert!(deserialized.verify_hash());
     }
 
     #[test]
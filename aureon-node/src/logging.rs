@@ -1,34 +1,78 @@
-use tracing::Level;
-use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt, util::SubscriberInitExt, fmt};
+use crate::config::LoggingConfig;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry, layer::SubscriberExt, reload, util::SubscriberInitExt};
 use std::io;
 
-/// Initialize structured logging with tracing
-pub fn init_logging(level: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // Parse log level from config
-    let level = match level.to_lowercase().as_str() {
+/// Handle to the installed log filter, used to change levels at runtime
+#[derive(Clone)]
+pub struct LogLevelHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogLevelHandle {
+    /// Replace the log filter with the level and module levels of `config`
+    pub fn apply(&self, config: &LoggingConfig) -> Result<(), String> {
+        self.0
+            .reload(EnvFilter::new(filter_directives(config)))
+            .map_err(|e| format!("Failed to change log level: {}", e))
+    }
+}
+
+/// Parse a log level from config, falling back to info
+fn parse_level(level: &str) -> Level {
+    match level.to_lowercase().as_str() {
         "debug" => Level::DEBUG,
         "info" => Level::INFO,
         "warn" => Level::WARN,
         "error" => Level::ERROR,
         "trace" => Level::TRACE,
         _ => Level::INFO,
-    };
+    }
+}
 
-    // Create environment filter
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(level.to_string()));
+/// Filter directives for the default level followed by the per-module levels
+fn filter_directives(config: &LoggingConfig) -> String {
+    let mut modules: Vec<_> = config.modules.iter().collect();
+    modules.sort();
+    let mut directives = vec![parse_level(&config.level).to_string()];
+    directives.extend(modules.into_iter().map(|(module, level)| format!("{}={}", module, parse_level(level))));
+    directives.join(",")
+}
 
-    // Create console writer layer
-    let console_layer = fmt::layer()
-        .with_writer(io::stderr);
+/// Event formatter for the configured output format; JSON events carry the
+/// spans they were logged in
+fn format_layer<S, W>(format: &str, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        "json" => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+        _ => fmt::layer().with_writer(writer).boxed(),
+    }
+}
+
+/// Initialize structured logging with tracing
+///
+/// `RUST_LOG`, when set, replaces the configured levels.
+pub fn init_logging(config: &LoggingConfig) -> Result<LogLevelHandle, Box<dyn std::error::Error>> {
+    // Create environment filter, reloadable so levels can change at runtime
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(filter_directives(config)));
+    let (filter_layer, handle) = reload::Layer::new(env_filter);
 
-    // Create registry with layers
+    // Create registry with the filter and a stderr writer layer
     Registry::default()
-        .with(env_filter)
-        .with(console_layer)
-        .init();
+        .with(filter_layer)
+        .with(format_layer(&config.format, io::stderr))
+        .try_init()?;
 
-    Ok(())
+    Ok(LogLevelHandle(handle))
 }
 
 /// Helper to log consensus events
@@ -132,4 +176,47 @@ mod tests {
         };
         assert_eq!(level, Level::INFO);
     }
+
+    #[test]
+    fn test_filter_directives_include_module_levels() {
+        let mut config = crate::config::AureonConfig::default().logging;
+        config.level = "warn".to_string();
+        config.modules.insert("aureon_node::network".to_string(), "debug".to_string());
+        config.modules.insert("aureon_node::mempool".to_string(), "error".to_string());
+
+        let directives = filter_directives(&config);
+        assert_eq!(directives, "WARN,aureon_node::mempool=ERROR,aureon_node::network=DEBUG");
+        assert!(EnvFilter::try_new(directives).is_ok());
+    }
+
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format_includes_enclosing_spans() {
+        let output = Captured::default();
+        let writer = output.clone();
+        let subscriber = Registry::default().with(format_layer("json", move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("block_production", slot = 7).entered();
+            tracing::info!(block_number = 3, "block produced");
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let event: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(event["fields"]["message"], "block produced");
+        assert_eq!(event["fields"]["block_number"], 3);
+        assert_eq!(event["span"]["name"], "block_production");
+        assert_eq!(event["span"]["slot"], 7);
+    }
 }
@@ -0,0 +1,193 @@
+//! Contract Logs
+//!
+//! Structured events emitted by contracts during execution. Each log names
+//! the emitting contract, up to four indexed 32-byte topics and free-form
+//! data. Blocks commit to their logs with a 2048-bit bloom filter (so log
+//! queries can skip blocks cheaply) and a merkle root over the log hashes.
+//! The indexer keeps a bloom per block that also covers the addresses the
+//! block's transactions involve, for address history queries.
+
+use crate::merkle_tree::IncrementalMerkleTree;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Maximum number of indexed topics per log
+pub const MAX_TOPICS: usize = 4;
+
+/// Size of an encoded topic in bytes
+pub const TOPIC_SIZE: usize = 32;
+
+/// Size of a logs bloom in bytes (2048 bits)
+pub const BLOOM_SIZE: usize = 256;
+
+/// Event emitted by a contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct Log {
+    /// Address of the emitting contract
+    pub address: String,
+    /// Hex-encoded indexed topics
+    pub topics: Vec<String>,
+    /// Hex-encoded payload
+    pub data: String,
+}
+
+impl Log {
+    pub fn new(address: &str, topics: &[[u8; TOPIC_SIZE]], data: &[u8]) -> Self {
+        Log {
+            address: address.to_string(),
+            topics: topics.iter().map(hex::encode).collect(),
+            data: hex::encode(data),
+        }
+    }
+
+    /// Hex-encoded SHA-256 over the log's fields
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.address.as_bytes());
+        for topic in &self.topics {
+            hasher.update(topic.as_bytes());
+        }
+        hasher.update(self.data.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// A log together with its position in the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedLog {
+    pub block_number: u64,
+    pub block_hash: String,
+    pub tx_hash: String,
+    /// Position of the log within its block
+    pub log_index: usize,
+    #[serde(flatten)]
+    pub log: Log,
+}
+
+/// Criteria for log queries; empty criteria match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    pub address: Option<String>,
+    /// Expected topic per position, `None` matching any topic
+    pub topics: Vec<Option<String>>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl LogFilter {
+    /// Whether `log` satisfies the address and topic criteria
+    pub fn matches(&self, log: &Log) -> bool {
+        if self.address.as_ref().is_some_and(|address| *address != log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, expected)| match expected {
+            Some(topic) => log.topics.get(position).is_some_and(|t| normalize_topic(topic) == *t),
+            None => true,
+        })
+    }
+
+    /// Whether a block with `bloom` may contain matching logs
+    pub fn may_match_bloom(&self, bloom: &[u8]) -> bool {
+        if self.address.as_ref().is_some_and(|address| !bloom_contains(bloom, address.as_bytes())) {
+            return false;
+        }
+        self.topics
+            .iter()
+            .flatten()
+            .all(|topic| bloom_contains(bloom, normalize_topic(topic).as_bytes()))
+    }
+}
+
+/// Lowercase a hex topic and strip an optional `0x` prefix
+fn normalize_topic(topic: &str) -> String {
+    topic.trim_start_matches("0x").to_lowercase()
+}
+
+/// Bit positions set in the bloom for `item`
+fn bloom_bits(item: &[u8]) -> [usize; 3] {
+    let hash = Sha256::digest(item);
+    [0, 2, 4].map(|i| ((hash[i] as usize) << 8 | hash[i + 1] as usize) % (BLOOM_SIZE * 8))
+}
+
+/// Add `item` to `bloom`
+pub fn bloom_insert(bloom: &mut [u8], item: &[u8]) {
+    for bit in bloom_bits(item) {
+        bloom[bit / 8] |= 1 << (bit % 8);
+    }
+}
+
+/// Whether `item` may have been added to `bloom` (false positives possible)
+pub fn bloom_contains(bloom: &[u8], item: &[u8]) -> bool {
+    bloom.len() == BLOOM_SIZE && bloom_bits(item).iter().all(|bit| bloom[bit / 8] & (1 << (bit % 8)) != 0)
+}
+
+/// Bloom filter over the addresses and topics of `logs`
+pub fn logs_bloom(logs: &[Log]) -> Vec<u8> {
+    let mut bloom = vec![0u8; BLOOM_SIZE];
+    accrue_logs(&mut bloom, logs);
+    bloom
+}
+
+/// Add the addresses and topics of `logs` to `bloom`
+pub fn accrue_logs<'a>(bloom: &mut [u8], logs: impl IntoIterator<Item = &'a Log>) {
+    for log in logs {
+        bloom_insert(bloom, log.address.as_bytes());
+        for topic in &log.topics {
+            bloom_insert(bloom, topic.as_bytes());
+        }
+    }
+}
+
+/// Merkle root over the hashes of `logs` (empty when there are none)
+pub fn logs_root(logs: &[Log]) -> Vec<u8> {
+    logs.iter()
+        .map(Log::hash)
+        .collect::<IncrementalMerkleTree>()
+        .root()
+        .and_then(|root| hex::decode(root).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer_log(address: &str, topic: u8) -> Log {
+        Log::new(address, &[[topic; TOPIC_SIZE]], b"payload")
+    }
+
+    #[test]
+    fn test_bloom_contains_addresses_and_topics() {
+        let log = transfer_log("0xtoken", 7);
+        let bloom = logs_bloom(std::slice::from_ref(&log));
+
+        assert!(bloom_contains(&bloom, b"0xtoken"));
+        assert!(bloom_contains(&bloom, log.topics[0].as_bytes()));
+        assert!(!bloom_contains(&bloom, b"0xother"));
+        assert!(!bloom_contains(&logs_bloom(&[]), b"0xtoken"));
+    }
+
+    #[test]
+    fn test_filter_matches_address_and_topics() {
+        let log = transfer_log("0xtoken", 7);
+        let topic = format!("0x{}", log.topics[0].to_uppercase());
+
+        assert!(LogFilter::default().matches(&log));
+        assert!(LogFilter { topics: vec![Some(topic.clone())], ..Default::default() }.matches(&log));
+        assert!(LogFilter { topics: vec![None, None], ..Default::default() }.matches(&log));
+        assert!(!LogFilter { topics: vec![None, Some(topic)], ..Default::default() }.matches(&log));
+        assert!(!LogFilter { address: Some("0xother".into()), ..Default::default() }.matches(&log));
+
+        let bloom = logs_bloom(&[log]);
+        assert!(LogFilter { address: Some("0xtoken".into()), ..Default::default() }.may_match_bloom(&bloom));
+        assert!(!LogFilter { address: Some("0xother".into()), ..Default::default() }.may_match_bloom(&bloom));
+    }
+
+    #[test]
+    fn test_logs_root_commits_to_logs() {
+        assert!(logs_root(&[]).is_empty());
+        let root = logs_root(&[transfer_log("0xtoken", 1)]);
+        assert_eq!(root.len(), 32);
+        assert_ne!(root, logs_root(&[transfer_log("0xtoken", 2)]));
+    }
+}
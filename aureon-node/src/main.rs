@@ -1,32 +1,64 @@
 mod consensus;
 mod types;
 mod config;
+mod error;
+mod config_reload;
 mod wasm;
 mod zk;
+mod shielded;
 mod mpt;
 mod db;
+mod chain_handle;
+mod chain_store;
+mod snapshot;
 mod state_processor;
+mod state_archive;
+mod state_diff;
+mod state_export;
+mod state_transition;
 mod simulated_processor;
 mod network;
+mod contract_abi;
 mod contract_registry;
+mod token;
+mod nft;
+mod timelock;
+mod multisig;
+mod bridge;
+mod replay_protection;
+mod randomness;
+mod emission;
+mod storage_rent;
+mod state_cache;
+mod runtime_version;
 mod api;
+mod events;
 mod indexer;
 mod mempool;
+mod fee_market;
+mod logs;
+mod slashing;
 mod block_producer;
+mod block_timings;
+mod service_manager;
+mod block_import;
 mod crypto;
+mod signer;
 mod sync;
 mod multinode_test;
 mod metrics;
 mod logging;
 mod monitoring;
 mod metrics_tracker;
+mod metrics_history;
 mod shard_coordinator;
 mod shard_manager;
 mod cross_shard_protocol;
 mod shard_sync;
+mod beacon;
 mod light_block_header;
-mod merkle_tree;
 mod spv_client;
+mod spv_checkpoint;
 mod state_compression;
 mod spv_api;
 mod error_recovery;
@@ -37,30 +69,77 @@ mod security_assessment;
 mod cryptographic_review;
 mod network_security;
 mod access_control;
+mod api_auth;
+mod api_rate_limit;
+mod sync_client;
 mod community_governance;
 mod mainnet_deployment;
 mod incentive_programs;
 mod testnet_coordination;
+// The CLI's offline-signing copy of `Transaction`, compiled here so tests can
+// check it against `types::Transaction`
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../../src/transaction.rs"]
+mod cli_transaction;
 
-use consensus::get_engine;
-use config::AureonConfig;
-use types::Transaction;
+use consensus::{get_engine, validator_set};
+use beacon::BeaconChain;
+use community_governance::{GovernanceConfig, VotingSystem};
+use consensus::difficulty::Retarget;
+use consensus::epoch::EpochManager;
+use consensus::ommers::StaleBlocks;
+use consensus::weighting::StakeWeighting;
+use consensus::finality::{FinalityEvent, FinalityGadget};
+use slashing::{Evidence, SlashingModule};
+use incentive_programs::StakingSystem;
+use config::{AureonConfig, PruningMode};
+use aureon_core::chain_spec::{ChainSpec, LOCAL_CHAIN_ID};
+use aureon_core::merkle_tree;
+use types::{Block, Transaction};
 use wasm::WasmRuntime;
 
 use std::fs;
+use std::collections::HashMap;
 use std::path::Path;
 use std::thread;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 
 use db::Db;
+use block_timings::BlockTimings;
+use metrics_history::MetricsHistory;
+use bridge::StateEntry;
+use chain_handle::ChainHandle;
+use chain_store::{ChainStore, GENESIS_HASH};
+use emission::EmissionSchedule;
+use state_cache::AccountCache;
+use runtime_version::RuntimeSchedule;
+use block_import::BlockImporter;
+use config_reload::ConfigReloader;
+use service_manager::ServiceManager;
+use snapshot::{SnapshotStore, StateSnapshot};
 use mpt::MerklePatriciaTrie;
 use state_processor::StateProcessor;
+use state_archive::StateArchive;
+use spv_checkpoint::CheckpointSigner;
+use state_compression::SignedCompressedState;
 use network::Network;
+use network::handshake::ChainInfo;
+use network::identity::{NodeIdentity, PeerId};
+use network::peer_store::PeerStore;
+use network::reputation::Misbehavior;
 use contract_registry::ContractRegistry;
-use api::start_api_server;
+use api::{start_api_server, ApiState};
+use block_producer::{SealMode, SealTrigger};
 use indexer::BlockchainIndexer;
 use mempool::TransactionMempool;
+use shard_coordinator::{ShardCoordinator, ShardMap};
 use metrics::Metrics;
+use monitoring::NodeHealth;
+use error_recovery::HealthStatus;
+use sync::Syncer;
+use sync_client::SyncClient;
+use events::{EventBus, NodeEvent};
 
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = std::env::args().collect();
@@ -81,23 +160,95 @@ fn main() -> anyhow::Result<()> {
     }
 
     // === Load Configuration ==
-    let config = AureonConfig::load();
-    
+    let mut config = AureonConfig::load();
+    let chain_spec = match arg_value(&args, "--chain") {
+        Some(chain) => Some(ChainSpec::load(&chain).map_err(anyhow::Error::msg)?),
+        None => None,
+    };
+    if let Some(spec) = &chain_spec {
+        config.apply_chain_spec(spec);
+        println!("Chain: {} (genesis {})", spec.chain_id, spec.genesis_hash());
+    }
+    let runtime = match &chain_spec {
+        Some(spec) => Some(RuntimeSchedule::from_forks(&spec.forks).map_err(anyhow::Error::msg)?),
+        None => None,
+    };
+    for (height, fork) in runtime.iter().flat_map(RuntimeSchedule::upcoming) {
+        println!("Fork '{}' activates at block {}", fork.name(), height);
+    }
+
     // Validate configuration
     if let Err(e) = config.validate() {
         eprintln!("Configuration error: {}", e);
         std::process::exit(1);
     }
 
+    // === Replay Mode (Audit Stored Blocks Without Starting the Node) ===
+    if args.len() > 1 && args[1] == "replay" {
+        return run_replay(&args, &config, chain_spec.as_ref());
+    }
+    if args.len() > 1 && args[1] == "export-state" {
+        return run_export_state(&args, &config, chain_spec.as_ref());
+    }
+    if args.len() > 1 && args[1] == "import-state" {
+        return run_import_state(&args, &config, chain_spec.as_ref());
+    }
+
+    // === Validator Key Management ===
+    if args.len() > 1 && args[1] == "signer-keygen" {
+        return run_signer_keygen(&args, &config);
+    }
+    if args.len() > 1 && args[1] == "remote-signer" {
+        return run_remote_signer(&args, &config);
+    }
+    if args.len() > 1 && args[1] == "import-slashing-protection" {
+        return run_import_slashing_protection(&args, &config);
+    }
+
     // Print configuration summary
     config.print_summary();
 
     // === Initialize Consensus Engine ===
     let consensus_type = config.get_consensus_type();
-    let engine = get_engine(consensus_type);
+    let validators: HashMap<String, u64> = match &chain_spec {
+        Some(spec) => spec.validators.clone().into_iter().collect(),
+        None => validator_set(consensus_type, &config.consensus),
+    };
+    let engine = Arc::new(Mutex::new(get_engine(consensus_type, &config.consensus)));
+
+    // === Initialize Metrics ===
+    let metrics = Arc::new(Metrics::new()?);
+    let health = NodeHealth::default();
+
+    // === Set up Database ===
+    let db = Arc::new(Db::open(&config.database.path));
 
     // === Initialize Networking ===
-    let network = Network::new("aureon-node".to_string(), "1.0.0".to_string());
+    let identity = match NodeIdentity::load_or_generate(&config.network.node_key_path) {
+        Ok(identity) => identity,
+        Err(e) => {
+            eprintln!("Warning: {}; using a temporary identity", e);
+            NodeIdentity::generate()
+        }
+    };
+    // Block signatures come from the configured signer, not the node key
+    let validator_signer = signer::from_config(&config.signer, &identity).map_err(anyhow::Error::msg)?;
+    println!("Validator signer: {} (public key {})", config.signer.backend, hex::encode(validator_signer.public_key().as_bytes()));
+    let chain = match &chain_spec {
+        Some(spec) => ChainInfo { chain_id: spec.chain_id.clone(), genesis_hash: spec.genesis_hash() },
+        None => ChainInfo::default(),
+    };
+    let network = Network::new("aureon-node".to_string(), "1.0.0".to_string())
+        .with_identity(identity.clone())
+        .with_chain(chain)
+        .with_max_peers(config.network.max_peers)
+        .with_ban_duration(std::time::Duration::from_secs(config.network.ban_duration_secs))
+        .with_encryption_required(config.network.require_encryption)
+        .with_target_peers(config.network.target_peers)
+        .with_peer_store(PeerStore::new(db.clone()))
+        .with_dns_seeds(config.network.dns_seeds.clone())
+        .with_metrics(metrics.clone());
+    println!("Peer ID: {}", network.peer_id());
     let network_clone = network.clone();
 
     // Add peer addresses from config
@@ -110,17 +261,13 @@ fn main() -> anyhow::Result<()> {
         network_clone.listen(&listen_addr);
     });
 
-    // === Initialize Block Synchronization State ===
-    let _sync_state = std::sync::Arc::new(std::sync::Mutex::new(sync::BlockSyncState::new()));
-    
     // === Sample Transactions ===
     let transactions = vec![
         Transaction::transfer("Alice".into(), "Bob".into(), 50),
         Transaction::transfer("Charlie".into(), "Dave".into(), 75),
     ];
 
-    // === Set up Database and Trie ===
-    let db = Db::open(&config.database.path);
+    // === Set up Trie ===
     let mut trie = MerklePatriciaTrie::new();
 
     // === Initialize Account Balances from Config ===
@@ -129,45 +276,306 @@ fn main() -> anyhow::Result<()> {
         trie.insert(account.as_bytes().to_vec(), balance.to_le_bytes().to_vec());
     }
 
+    let genesis_state = genesis_state(&config, chain_spec.as_ref())?;
+    for (key, value) in &genesis_state {
+        db.put(key, value);
+        trie.insert(key.clone(), value.clone());
+    }
+
     println!("Initialized {} genesis accounts", config.state.accounts.len());
+    let emission = EmissionSchedule::new(&config.economy, config.state.accounts.values().sum());
+    let account_cache = Some(Arc::new(
+        AccountCache::new(config.database.account_cache_size).with_metrics(metrics.clone()),
+    ));
 
-    // === Create Blockchain Indexer ===
-    let indexer = Arc::new(BlockchainIndexer::new());
+    // === Fast Sync an Empty Chain from a Peer Snapshot ===
+    let chain_store = ChainStore::new(&db)
+        .with_emission(Some(emission))
+        .with_cache(account_cache.clone())
+        .with_genesis_state(genesis_state);
+    if let Some(spec) = &chain_spec {
+        chain_store.check_genesis(&spec.genesis_hash()).map_err(anyhow::Error::msg)?;
+    }
+    let snapshots = SnapshotStore::new(&db);
+    if let Some(checkpoint) = config.snapshot.trusted_checkpoint.as_ref().filter(|_| config.snapshot.fast_sync)
+        && chain_store.is_empty()
+        && !config.network.bootstrap_peers.is_empty()
+    {
+        println!("Empty chain, requesting the snapshot at trusted checkpoint {} from peers...", checkpoint.height);
+        let timeout = std::time::Duration::from_secs(config.snapshot.sync_timeout_secs);
+        let synced = snapshot::fast_sync(&network, checkpoint, timeout).and_then(|snapshot| {
+            chain_store.import_snapshot(&snapshot)?;
+            snapshots.save(&snapshot)?;
+            Ok(snapshot.height)
+        });
+        match synced {
+            Ok(height) => println!("Bootstrapped from snapshot at height {}", height),
+            Err(e) => eprintln!("Warning: Fast sync failed ({}); starting from genesis", e),
+        }
+    }
 
-    // === Capture Pre-State Root ===
-    let pre_state_root = trie.root_hash();
+    // === Replay Persisted Chain ===
+    let replay = match snapshots.latest().map_err(anyhow::Error::msg)? {
+        Some(snapshot) => {
+            let replay = chain_store.replay_from_snapshot(&snapshot, &mut trie);
+            serve_compressed_state(&network, &snapshot, &config.validator.operator_address, &identity);
+            network.serve_snapshot(snapshot);
+            replay
+        }
+        None => chain_store.replay(&config.state.accounts, &mut trie),
+    }
+    .map_err(|e| anyhow::anyhow!("Failed to replay stored chain: {}", e))?;
+    println!("Replayed {} stored blocks", replay.blocks_replayed);
+    let chain = ChainHandle::open(db.clone()).map_err(anyhow::Error::msg)?;
+    {
+        let chain = chain.clone();
+        network.serve_blocks(Arc::new(move |height| chain.get_block_by_height(height).ok().flatten()));
+    }
 
-    // === Simulate Transactions for Post-State Root ===
-    let sim_processor = StateProcessor::new(&db, &mut trie);
-    let post_state_root = sim_processor.simulate_block(&transactions);
+    // === Create Blockchain Indexer ===
+    let indexer = Arc::new(BlockchainIndexer::new().with_db(db.clone()));
 
-    // === Produce and Validate Block ===
-    let block = engine.produce_block(
-        transactions.clone(),
-        pre_state_root.clone(),
-        post_state_root.clone(),
+    // === Re-index Stored Blocks ===
+    let replayed_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let base = chain_store.history_start();
+    for (offset, block) in chain_store.load_chain().map_err(anyhow::Error::msg)?.into_iter().enumerate() {
+        let number = base + offset as u64;
+        if let Err(e) = indexer.index_block(block, number, replayed_at) {
+            eprintln!("Warning: Failed to index stored block {}: {}", number, e);
+        }
+    }
+    if let Err(e) = indexer.index_receipts(replay.receipts) {
+        eprintln!("Warning: Failed to index replayed receipts: {}", e);
+    }
+    if let Err(e) = indexer.index_balances(&trie) {
+        eprintln!("Warning: Failed to index balances: {}", e);
+    }
+    if let Err(e) = indexer.index_nfts(&trie) {
+        eprintln!("Warning: Failed to index NFTs: {}", e);
+    }
+
+    // === Initialize Finality Gadget and Slashing ===
+    // Keys validators sign proposals and votes with, the local one first
+    let mut validator_keys = vec![(config.validator.operator_address.clone(), validator_signer.public_key())];
+    for (validator, key) in &config.consensus.validator_keys {
+        match block_import::parse_key(key) {
+            Ok(key) => validator_keys.push((validator.clone(), key)),
+            Err(e) => eprintln!("Warning: Ignoring key of validator {}: {}", validator, e),
+        }
+    }
+    let mut genesis_staking = StakingSystem::new(0.0)
+        .with_delegation(config.epoch.unbonding_period, config.epoch.commission_bps);
+    for (validator, stake) in &validators {
+        genesis_staking.stake(validator.clone(), *stake as u128, 0, 0);
+    }
+    let staking = Arc::new(Mutex::new(genesis_staking));
+    let slashing = Arc::new(Mutex::new(
+        validator_keys
+            .iter()
+            .fold(SlashingModule::new(config.slashing.clone(), staking.clone()), |slashing, (validator, key)| {
+                slashing.with_validator_key(validator, *key)
+            }),
+    ));
+    let epochs = EpochManager::new(
+        config.epoch.clone(),
+        config.consensus.pos_validator_count,
+        config.consensus.pos_min_stake,
+        validators.clone(),
     );
+    // Leaders are weighted by the stake bonded as of the last epoch boundary
+    let mut stake_weighting = StakeWeighting::new(slashing.clone(), epochs, engine.clone());
+    // With sharding the shard layout is recomputed from the same validator set
+    let shard_map = config.sharding.enabled.then(|| {
+        let coordinator = ShardCoordinator::with_shard_count(config.sharding.shard_count);
+        Arc::new(RwLock::new(
+            ShardMap::new(coordinator).with_validators_per_shard(config.sharding.validators_per_shard),
+        ))
+    });
+    if let Some(shards) = &shard_map {
+        stake_weighting = stake_weighting.with_shard_map(shards.clone());
+    }
+    match stake_weighting.replay(&ChainStore::new(&db)) {
+        Ok(0) => {}
+        Ok(blocks) => println!("Replayed staking of {} stored blocks", blocks),
+        Err(e) => eprintln!("Warning: Failed to replay staking: {}", e),
+    }
+    let finality = Arc::new(Mutex::new(validator_keys.iter().fold(
+        FinalityGadget::new(validators)
+            .with_chain_id(chain_spec.as_ref().map_or(LOCAL_CHAIN_ID, |spec| spec.chain_id.as_str()))
+            .with_local_validator(&config.validator.operator_address, validator_signer.clone()),
+        |finality, (validator, key)| finality.with_validator_key(validator, *key),
+    )));
+    {
+        let votes = network.subscribe_votes();
+        let finality = finality.clone();
+        let slashing = slashing.clone();
+        let network = network.clone();
+        let indexer = indexer.clone();
+        thread::spawn(move || {
+            for vote in votes {
+                // Detected double-signs wait for a block to include them
+                slashing.lock().unwrap().observe_vote(&vote);
 
-    println!("\n--- Produced Block ---\n{:#?}", block);
+                let result = finality.lock().unwrap().on_vote(vote);
+                match result {
+                    Ok(events) => apply_finality_events(&network, &indexer, &slashing, events),
+                    Err(e) => eprintln!("[Finality] Rejected vote: {}", e),
+                }
+            }
+        });
+    }
 
-    let is_valid = engine.validate_block(&block, pre_state_root.clone(), post_state_root.clone());
-    println!("Is Block Valid? {}\n", is_valid);
+    // === Produce Sample Block on a Fresh Chain ===
+    if chain.is_empty() {
+        // === Capture Pre-State Root ===
+        let pre_state_root = trie.root_hash();
 
-    // === Index the Block ===
-    if let Err(e) = indexer.index_block(block.clone(), 0, std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()) {
-        eprintln!("Warning: Failed to index block: {}", e);
-    }
+        // === Simulate Transactions for Post-State Root ===
+        // The sample block has no proposer, so it mints no reward
+        let sim_processor = StateProcessor::new(&db, &mut trie)
+            .with_emission(Some(emission))
+            .with_cache(account_cache.clone());
+        let post_state_root = sim_processor.simulate_block("", &transactions);
+        let state_proof = if config.consensus.state_proofs {
+            state_transition::prove_transition(&transactions, |account| sim_processor.get_balance(account))
+                .map_err(|e| eprintln!("Warning: Block state transition not proven: {}", e))
+                .ok()
+        } else {
+            None
+        };
 
-    // === Broadcast the Block ===
-    network.broadcast_block(&block);
+        // === Produce and Validate Block ===
+        let mut block = engine.lock().unwrap().produce_block(
+            0,
+            GENESIS_HASH.to_string(),
+            transactions.clone(),
+            pre_state_root.clone(),
+            post_state_root.clone(),
+        );
+        block.evidence = slashing.lock().unwrap().take_pending_evidence();
+        block.state_proof = state_proof;
+        randomness::seal(&mut block, &randomness::GENESIS_SEED, None).map_err(anyhow::Error::msg)?;
 
-    // === Commit Block to State ===
-    let mut processor = StateProcessor::new(&db, &mut trie);
-    let committed_root = processor.apply_block(&block);
-    println!("Committed State Root: 0x{}", hex::encode(&committed_root));
+        println!("\n--- Produced Block ---\n{:#?}", block);
+
+        // Validate from the state proof when there is one instead of re-executing
+        let validated_root = match &block.state_proof {
+            Some(_) if config.consensus.state_proofs => {
+                let processor = StateProcessor::new(&db, &mut trie)
+                    .with_emission(Some(emission))
+                    .with_cache(account_cache.clone());
+                state_transition::proven_state_root(&block, &processor)
+                    .unwrap_or_else(|e| {
+                        eprintln!("Warning: Invalid block state proof: {}", e);
+                        Vec::new()
+                    })
+            }
+            _ => post_state_root.clone(),
+        };
+        let is_valid = engine.lock().unwrap().validate_block(&block, pre_state_root.clone(), validated_root);
+        println!("Is Block Valid? {}\n", is_valid);
+
+        // === Index the Block ===
+        if let Err(e) = indexer.index_block(block.clone(), 0, std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()) {
+            eprintln!("Warning: Failed to index block: {}", e);
+        }
+
+        // === Broadcast the Block ===
+        network.broadcast_block(&block);
+
+        // === Start Finality Voting ===
+        let finality_events = finality.lock().unwrap().on_block(0, 0, &block.hash);
+        apply_finality_events(&network, &indexer, &slashing, finality_events);
+
+        // === Commit Block to State ===
+        let mut processor = StateProcessor::new(&db, &mut trie)
+            .with_emission(Some(emission))
+            .with_cache(account_cache.clone());
+        let archived_before: Vec<(String, u64)> = state_archive::touched_accounts(&block)
+            .into_iter()
+            .map(|account| {
+                let balance = processor.get_balance(&account);
+                (account, balance)
+            })
+            .collect();
+        let execution = processor.apply_block(&block);
+        println!("Committed State Root: 0x{}", hex::encode(&execution.state_root));
+        if let Err(e) = indexer.index_receipts(execution.receipts) {
+            eprintln!("Warning: Failed to index receipts: {}", e);
+        }
+        if let Err(e) = indexer.index_state_diff(&execution.diff) {
+            eprintln!("Warning: Failed to index balances: {}", e);
+        }
+        if let Err(e) = indexer.index_nft_transactions(&block.transactions, |key| db.get(key)) {
+            eprintln!("Warning: Failed to index NFTs: {}", e);
+        }
+
+        // === Persist Block ===
+        let appended = chain.append(&block);
+        match appended {
+            Ok(height) if snapshot::is_checkpoint(height, config.snapshot.interval) => {
+                let snapshot = StateSnapshot::capture(height, &block, &trie);
+                match snapshots.save(&snapshot) {
+                    Ok(()) => {
+                        serve_compressed_state(&network, &snapshot, &config.validator.operator_address, &identity);
+                        network.serve_snapshot(snapshot);
+                    }
+                    Err(e) => eprintln!("Warning: Failed to save snapshot: {}", e),
+                }
+            }
+            Ok(_) => {}
+            Err(ref e) => eprintln!("Warning: Failed to persist block: {}", e),
+        }
+        if appended.is_ok() {
+            for evidence in &block.evidence {
+                apply_evidence(&indexer, &slashing, evidence);
+            }
+        }
+
+        // === Archive Historical State ===
+        if let (Ok(height), PruningMode::Archive) = (&appended, config.database.pruning) {
+            let processor = StateProcessor::new(&db, &mut trie).with_cache(account_cache.clone());
+            let recorded = StateArchive::new(&db).record_block(*height, &execution.state_root, &archived_before, |account| {
+                processor.get_balance(account)
+            });
+            if let Err(e) = recorded {
+                eprintln!("Warning: Failed to archive state at height {}: {}", height, e);
+            }
+        }
+
+        // === Rotate Validators at Epoch Boundaries ===
+        if let Ok(height) = appended {
+            if let Some(transition) = stake_weighting.on_block(height, &block.transactions) {
+                println!(
+                    "Epoch {}: {} validators (entered: {:?}, exited: {:?})",
+                    transition.epoch,
+                    transition.validators.len(),
+                    transition.entered,
+                    transition.exited
+                );
+            }
+            let released_delegations = slashing.lock().unwrap().release_unbonded_delegations(height);
+            let mut processor = StateProcessor::new(&db, &mut trie).with_cache(account_cache.clone());
+            for released in released_delegations {
+                let balance = processor.get_balance(&released.delegator);
+                processor.set_balance(&released.delegator, balance + released.amount as u64);
+                println!("Released {} stake {} undelegated from {}", released.amount, released.delegator, released.validator);
+            }
+        }
+    } else {
+        println!("Stored chain found, skipping sample block");
+    }
+    // Slot leaders are drawn from the random seed of the chain tip
+    engine.lock().unwrap().update_randomness(randomness::seed_of(chain.head().as_ref()));
+    // PoW difficulty is retargeted from the chain tip
+    engine.lock().unwrap().update_tip(chain.head().as_ref());
+    let processor = StateProcessor::new(&db, &mut trie).with_cache(account_cache.clone());
 
     // === WASM Smart Contract Execution ===
     let contracts_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/contracts");
@@ -205,58 +613,676 @@ fn main() -> anyhow::Result<()> {
         println!("{}: {}", account, balance);
     }
 
-    // === Create Transaction Mempool ===
-    let mempool = Arc::new(TransactionMempool::new());
+    // === Share State Trie with the API ===
+    let trie = Arc::new(RwLock::new(trie));
 
-    // === Create Arc for database early ===
-    let db_arc = Arc::new(db);
+    // === Create Event Bus for WebSocket Subscriptions ===
+    let events = EventBus::default();
+
+    // === Create Transaction Mempool ===
+    let mut mempool = TransactionMempool::with_capacity(config.mempool.max_size)
+        .with_fee_market(config.fee_market.clone())
+        .with_limits(config.mempool.clone())
+        .with_event_bus(events.clone())
+        .with_network(network.clone())
+        .with_state(trie.clone())
+        .with_base_fee(emission.base_fee)
+        .with_metrics(metrics.clone());
+    if let Some(shards) = &shard_map {
+        mempool = mempool.with_shards(shards.clone());
+    }
+    let mempool = Arc::new(mempool);
+    match mempool.restore(&db) {
+        Ok(0) => {}
+        Ok(restored) => println!("Restored {} saved mempool transactions", restored),
+        Err(e) => eprintln!("Warning: Failed to restore mempool: {}", e),
+    }
+    if config.mempool.persist_interval_secs > 0 {
+        // Saved periodically as well as at shutdown, so a crash loses little
+        let (mempool, db) = (mempool.clone(), db.clone());
+        let interval = std::time::Duration::from_secs(config.mempool.persist_interval_secs);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if let Err(e) = mempool.save(&db) {
+                eprintln!("[Mempool] Failed to save pending transactions: {}", e);
+            }
+        });
+    }
+    if config.mempool.revalidate_interval_secs > 0 {
+        let (mempool, db) = (mempool.clone(), db.clone());
+        let interval = std::time::Duration::from_secs(config.mempool.revalidate_interval_secs);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match mempool.revalidate(&db) {
+                Ok(0) => {}
+                Ok(evicted) => println!("[Mempool] Evicted {} transactions on revalidation", evicted),
+                Err(e) => eprintln!("[Mempool] Failed to revalidate pending transactions: {}", e),
+            }
+        });
+    }
+    {
+        let transactions = network.subscribe_transactions();
+        let mempool = mempool.clone();
+        thread::spawn(move || {
+            for tx in transactions {
+                match mempool.add_gossiped_transaction(tx) {
+                    Ok(hash) => println!("[Mempool] Added gossiped transaction {}", hash),
+                    Err(e) => eprintln!("[Mempool] Rejected gossiped transaction: {}", e),
+                }
+            }
+        });
+    }
 
     // === Initialize Logging ===
-    let _ = logging::init_logging(&config.logging.level);
+    let log_level = logging::init_logging(&config.logging).ok();
 
     // === Initialize Metrics ===
-    let metrics = Arc::new(Metrics::new()?);
     
     // Update initial metrics
     if let Ok(Some(height)) = indexer.get_latest_block_number() {
         metrics.chain_height.set(height as i64);
     }
-    metrics.pow_difficulty.set(config.consensus.pow_difficulty as i64);
+    let tip_difficulty = chain.head().map(|tip| tip.difficulty).filter(|&difficulty| difficulty > 0);
+    metrics.pow_difficulty.set(tip_difficulty.unwrap_or(Retarget::from_config(&config.consensus).initial) as i64);
     metrics.pos_validators.set(config.consensus.pos_validator_count as i64);
 
+    // === Prune Block History ===
+    let pruned = SnapshotStore::new(&db).latest().and_then(|snapshot| {
+        ChainStore::new(&db).prune_history(config.database.pruning, snapshot.as_ref(), config.snapshot.interval)
+    });
+    match pruned {
+        Ok(stats) => {
+            if stats.blocks > 0 {
+                println!("Pruned {} historical blocks ({} bytes)", stats.blocks, stats.bytes);
+            }
+            metrics.pruned_blocks.inc_by(stats.blocks);
+            metrics.pruned_bytes.inc_by(stats.bytes);
+        }
+        Err(e) => eprintln!("Warning: Failed to prune block history: {}", e),
+    }
+
+    // === Create Contract Registry ===
+    let contract_registry = Arc::new(Mutex::new(ContractRegistry::open(db.clone()).map_err(anyhow::Error::msg)?));
+
+    // === Create Governance ===
+    let governance = Arc::new(Mutex::new(VotingSystem::from_config(&GovernanceConfig::default())));
+
+    // === Create SPV Checkpoint Signer ===
+    let checkpoints = Arc::new(Mutex::new(CheckpointSigner::new(
+        config.spv.checkpoint_interval,
+        &config.validator.operator_address,
+        identity.clone(),
+    )));
+
+    // === Import Blocks Received from Peers ===
+    // Competing PoW blocks, kept for the producer to include as ommers
+    let stale_blocks = Arc::new(Mutex::new(StaleBlocks::new()));
+    {
+        let mut importer = BlockImporter::new(chain.clone(), trie.clone(), engine.clone())
+            .with_stale_blocks(stale_blocks.clone())
+            .with_state_proofs(config.consensus.state_proofs)
+            .with_emission(Some(emission))
+            .with_cache(account_cache.clone())
+            .with_runtime(runtime.clone())
+            .with_contracts(contract_registry.clone())
+            .with_slashing(slashing.clone())
+            .with_metrics(metrics.clone(), config.consensus.block_interval_ms)
+            .with_stake_weighting(stake_weighting);
+        for (validator, key) in &validator_keys {
+            importer = importer.with_validator_key(validator, *key);
+        }
+        let blocks = network.subscribe_blocks();
+        let sync = SyncClient::new(network.clone()).with_health(health.component("sync"));
+        let mut syncer = Syncer::new(sync, network.clone(), chain.clone()).with_metrics(metrics.clone());
+        let expects_peers = !config.network.bootstrap_peers.is_empty();
+        let health = health.clone();
+        let network = network.clone();
+        let indexer = indexer.clone();
+        let db = db.clone();
+        let chain = chain.clone();
+        let stale_blocks = stale_blocks.clone();
+        let slashing = slashing.clone();
+        thread::spawn(move || {
+            // Gossiped blocks are relayed once imported; synced ones are old news
+            let import = |peer: PeerId, block: Block, relay: bool| {
+                let _span = tracing::info_span!("received_block", peer = %peer).entered();
+                // A block gossiped again by another peer is not misbehavior
+                if chain.contains(&block.hash) {
+                    return true;
+                }
+                match importer.import(&block) {
+                    Ok((height, execution)) => {
+                        network.reward_peer(&peer);
+                        for evidence in &block.evidence {
+                            apply_evidence(&indexer, &slashing, evidence);
+                        }
+                        if let Err(e) = indexer.index_receipts(execution.receipts) {
+                            tracing::warn!(error = %e, "failed to index receipts");
+                        }
+                        if let Err(e) = indexer.index_state_diff(&execution.diff) {
+                            tracing::warn!(error = %e, "failed to index balances");
+                        }
+                        if let Err(e) = indexer.index_nft_transactions(&block.transactions, |key| db.get(key)) {
+                            tracing::warn!(error = %e, "failed to index NFTs");
+                        }
+                        let timestamp = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs();
+                        if let Err(e) = indexer.index_block(block.clone(), height, timestamp) {
+                            tracing::warn!(error = %e, "failed to index block");
+                        }
+                        if relay {
+                            network.broadcast_block(&block);
+                        }
+                        true
+                    }
+                    // Mining a competing block is not misbehavior
+                    Err(_) if stale_blocks.lock().unwrap().contains(&block.hash) => false,
+                    Err(_) => {
+                        network.report_peer(&peer, Misbehavior::BadBlock);
+                        false
+                    }
+                }
+            };
+
+            // Catch up with the peers' tip whenever it runs ahead, and follow gossip in between
+            let mut last_announce: Option<std::time::Instant> = None;
+            loop {
+                let in_sync = syncer.sync(|peer, block| import(peer, block, false));
+                // Until a peer connects there is no tip to catch up with
+                if in_sync && !health.is_synced() && (network.peer_count() > 0 || !expects_peers) {
+                    println!("Initial sync complete at height {}", syncer.refresh().local_height);
+                    health.mark_synced();
+                }
+                if last_announce.is_none_or(|at| at.elapsed() >= sync::SYNC_INTERVAL) {
+                    network.broadcast_peer_info(syncer.refresh().local_height);
+                    last_announce = Some(std::time::Instant::now());
+                }
+                match blocks.recv_timeout(sync::SYNC_INTERVAL) {
+                    // Blocks gossiped while behind are covered by the range download
+                    Ok((peer, block)) if syncer.refresh().is_synced() => {
+                        import(peer, block, true);
+                    }
+                    Ok(_) | Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+    }
+
+    // === Start Node Services ===
+    let mut services = ServiceManager::new();
+
+    // === Report Subsystem Health ===
+    {
+        let db = db.clone();
+        health.add_probe(
+            "db",
+            Arc::new(move || if db.check().is_ok() { HealthStatus::Healthy } else { HealthStatus::Unhealthy }),
+        );
+    }
+    {
+        // Without bootstrap peers a node may run alone, as on a devnet
+        let expects_peers = !config.network.bootstrap_peers.is_empty();
+        let network = network.clone();
+        health.add_probe(
+            "network",
+            Arc::new(move || match network.peer_count() {
+                0 if expects_peers => HealthStatus::Degraded,
+                _ => HealthStatus::Healthy,
+            }),
+        );
+    }
+    {
+        let network = network.clone();
+        let metrics = metrics.clone();
+        health.add_probe(
+            "sync",
+            Arc::new(move || {
+                let lag = network.get_highest_peer_height().saturating_sub(metrics.chain_height.get() as u64);
+                if lag > monitoring::MAX_SYNC_LAG { HealthStatus::Degraded } else { HealthStatus::Healthy }
+            }),
+        );
+    }
+
     // === Start Block Producer ===
-    let producer = block_producer::BlockProducer::new(
+    // The producer checks in every slot; three silent slots mark it unhealthy
+    let producer_max_silence = std::time::Duration::from_millis(config.consensus.block_interval_ms.saturating_mul(3))
+        .max(std::time::Duration::from_secs(10));
+    let block_timings = BlockTimings::new();
+    let mut producer = block_producer::BlockProducer::new(
         mempool.clone(),
-        db_arc.clone(),
+        chain.clone(),
         indexer.clone(),
         metrics.clone(),
-        5000, // Produce a block every 5 seconds
-    );
-    producer.start();
+        config.consensus.block_interval_ms,
+    )
+    .with_max_block_transactions(config.consensus.max_block_transactions)
+    .with_reserved_block_percent(config.consensus.reserved_block_percent)
+    .with_event_bus(events.clone())
+    .with_contracts(contract_registry.clone())
+    .with_governance(governance.clone(), slashing.clone())
+    .with_checkpoints(checkpoints.clone())
+    .with_consensus(engine.clone(), &config.validator.operator_address, validator_signer)
+    .with_runtime(runtime.clone())
+    .with_health(health.heartbeat("producer", producer_max_silence))
+    .with_block_timings(block_timings.clone())
+    .with_stale_blocks(stale_blocks.clone());
+    if let Some(shards) = &shard_map {
+        let beacon = Arc::new(Mutex::new(BeaconChain::new()));
+        producer = producer.with_shards(shards.clone(), beacon);
+    }
+    let seal_mode = SealMode::from_name(&config.consensus.seal_mode).unwrap_or(SealMode::Slots);
+    let seal_trigger = (seal_mode != SealMode::Slots).then(SealTrigger::default);
+    if let Some(trigger) = &seal_trigger {
+        println!("Sealing blocks in {} mode", config.consensus.seal_mode);
+        producer = producer.with_seal_trigger(trigger.clone());
+    }
+    if seal_mode == SealMode::Instant
+        && let Some(trigger) = seal_trigger.clone()
+    {
+        // Seal as soon as a transaction becomes pending
+        let mut pending = events.subscribe();
+        thread::spawn(move || loop {
+            match pending.blocking_recv() {
+                Ok(NodeEvent::Transactions(event)) if event.event_type == "pending_transaction" => trigger.notify_pending(),
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        });
+    }
+    services.register("block producer", producer.start(services.shutdown_signal()));
 
     // === Start Metrics Tracker ===
-    metrics_tracker::MetricsTracker::start_mempool_tracker(
-        metrics.clone(),
-        mempool.clone(),
-        1000, // Update every 1 second
+    services.register(
+        "metrics tracker",
+        metrics_tracker::MetricsTracker::start_mempool_tracker(
+            metrics.clone(),
+            mempool.clone(),
+            1000, // Update every 1 second
+            services.shutdown_signal(),
+        ),
     );
+    let metrics_history = config
+        .metrics_history
+        .enabled
+        .then(|| Arc::new(MetricsHistory::new(db.clone(), config.metrics_history.retention_samples)));
+    if let Some(history) = &metrics_history {
+        services.register(
+            "metrics history",
+            metrics_tracker::MetricsTracker::start_history_sampler(
+                metrics.clone(),
+                mempool.clone(),
+                history.clone(),
+                config.metrics_history.sample_interval_secs,
+                services.shutdown_signal(),
+            ),
+        );
+    }
+
+    // === Persist State on Shutdown ===
+    {
+        let network = network.clone();
+        services.on_shutdown("closing peer connections", move || {
+            network.shutdown();
+            Ok(())
+        });
+    }
+    {
+        let (mempool, db) = (mempool.clone(), db.clone());
+        services.on_shutdown("saving the mempool", move || {
+            let saved = mempool.save(&db).map_err(|e| e.to_string())?;
+            println!("[Shutdown] Saved {} mempool transactions", saved);
+            Ok(())
+        });
+    }
+    {
+        let (trie, db) = (trie.clone(), db.clone());
+        services.on_shutdown("committing the final state", move || commit_final_state(&db, &trie.read().unwrap()));
+    }
+    {
+        let db = db.clone();
+        services.on_shutdown("flushing the database", move || db.flush());
+    }
 
     // === Start REST API Server ===
-    let contract_registry = Arc::new(Mutex::new(ContractRegistry::new()));
-    
     println!("\n--- Starting REST API Server ---");
     println!("Node is running. Press Ctrl+C to stop.");
-    println!("Metrics endpoint: http://{}:8080/metrics", config.api.host);
-    println!("Health check: http://{}:8080/health", config.api.host);
-    
-    // Block on the async API server (will run forever until interrupted)
+    println!("Metrics endpoint: http://{}:{}/metrics", config.api.host, config.api.port);
+    println!("Health check: http://{}:{}/health", config.api.host, config.api.port);
+
+    // === Reload Configuration on SIGHUP or /admin/reload-config ===
+    let mut config_reloader = ConfigReloader::new(config::CONFIG_PATH, config.clone(), mempool.clone());
+    if let Some(handle) = log_level {
+        config_reloader = config_reloader.with_log_level(handle);
+    }
+    let config_reloader = Arc::new(config_reloader);
+
+    let access = api_auth::access_control(&config.api).map_err(anyhow::Error::msg)?;
+    let api_state = ApiState {
+        db,
+        chain,
+        trie,
+        contract_registry,
+        indexer,
+        mempool,
+        metrics,
+        events,
+        checkpoints,
+        staking,
+        emission,
+        config_reloader: config_reloader.clone(),
+        access: Arc::new(Mutex::new(access)),
+        network,
+        health,
+        seal: seal_trigger,
+        metrics_history,
+        block_timings,
+        shards: shard_map,
+    };
+
+    // Block on the async API server until Ctrl+C or SIGTERM
     let runtime = tokio::runtime::Runtime::new()?;
+    let shutdown = services.shutdown_signal();
     runtime.block_on(async {
-        if let Err(e) = start_api_server(db_arc, contract_registry, indexer, mempool, metrics).await {
+        #[cfg(unix)]
+        tokio::spawn(config_reload::reload_on_hangup(config_reloader));
+        {
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                service_manager::wait_for_signal().await;
+                println!("\nShutting down...");
+                shutdown.trigger();
+            });
+        }
+        if !config.api.enabled {
+            println!("REST API disabled");
+            shutdown.wait().await;
+        } else if let Err(e) = start_api_server(api_state, &config.api, shutdown).await {
             eprintln!("API Server error: {}", e);
         }
     });
 
+    // === Stop Services and Persist State ===
+    services.stop().map_err(|e| anyhow::anyhow!("Shutdown incomplete: {}", e))?;
+    println!("Node stopped");
+    Ok(())
+}
+
+/// Serve light clients the compressed balances of the checkpoint `snapshot`
+fn serve_compressed_state(network: &Network, snapshot: &StateSnapshot, validator: &str, identity: &NodeIdentity) {
+    match SignedCompressedState::from_snapshot(snapshot, validator, identity) {
+        Ok(state) => network.serve_compressed_state(state),
+        Err(e) => eprintln!("Warning: Failed to compress snapshot: {}", e),
+    }
+}
+
+/// Snapshot the state at the chain tip so the next start does not replay
+/// the blocks after the last checkpoint
+fn commit_final_state(db: &Db, trie: &MerklePatriciaTrie) -> Result<(), String> {
+    let chain = ChainStore::new(db);
+    let Some(tip) = chain.tip()? else {
+        return Ok(());
+    };
+    let height = chain.len() - 1;
+    let snapshots = SnapshotStore::new(db);
+    if snapshots.latest()?.is_some_and(|snapshot| snapshot.height == height) {
+        return Ok(());
+    }
+    if tip.post_state_root != trie.root_hash() {
+        return Err(format!("State does not match the post-state root of tip block {}", height));
+    }
+    snapshots.save(&StateSnapshot::capture(height, &tip, trie))
+}
+
+/// Gossip local votes, record finalized blocks in the indexer and check commit participation
+fn apply_finality_events(
+    network: &Network,
+    indexer: &BlockchainIndexer,
+    slashing: &Mutex<SlashingModule>,
+    events: Vec<FinalityEvent>,
+) {
+    for event in events {
+        match event {
+            FinalityEvent::Broadcast(vote) => network.broadcast_vote(&vote),
+            FinalityEvent::Finalized { height, block_hash, signers } => {
+                println!("[Finality] Block #{} finalized: {}", height, block_hash);
+                if let Err(e) = indexer.mark_finalized(height, &block_hash) {
+                    eprintln!("Warning: Failed to record finalized block: {}", e);
+                }
+                // Missed commits are queued as evidence for the next block
+                slashing.lock().unwrap().record_commit(height, &signers);
+            }
+        }
+    }
+}
+
+/// Slash the offender named by evidence in a committed block and index the
+/// resulting event
+fn apply_evidence(indexer: &BlockchainIndexer, slashing: &Mutex<SlashingModule>, evidence: &Evidence) {
+    let result = slashing.lock().unwrap().apply_evidence(evidence);
+    match result {
+        Ok(event) => {
+            println!(
+                "[Slashing] {} slashed {} for {:?} at height {}",
+                event.validator, event.slashed, event.misbehavior, event.height
+            );
+            if let Err(e) = indexer.record_slashing_event(event) {
+                eprintln!("Warning: Failed to index slashing event: {}", e);
+            }
+        }
+        Err(e) => eprintln!("[Slashing] Rejected evidence: {}", e),
+    }
+}
+
+/// Value of `flag` given as `<flag> <value>` or `<flag>=<value>`, such as
+/// the chain spec named by `--chain <name or path>`
+fn arg_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().enumerate().skip(1).find_map(|(i, arg)| match arg.strip_prefix(flag) {
+        Some("") => args.get(i + 1).cloned(),
+        Some(value) => value.strip_prefix('=').map(str::to_string),
+        None => None,
+    })
+}
+
+/// State besides the account balances that the chain starts with
+fn genesis_state(config: &AureonConfig, chain_spec: Option<&ChainSpec>) -> anyhow::Result<Vec<StateEntry>> {
+    let chain_id = chain_spec.map_or(LOCAL_CHAIN_ID, |spec| spec.chain_id.as_str());
+    let mut entries = replay_protection::genesis_state(chain_id);
+    entries.extend(bridge::genesis_state(&config.bridge).map_err(anyhow::Error::msg)?);
+    Ok(entries)
+}
+
+/// Re-execute the stored chain on a scratch database and report the first
+/// block whose state root differs from its header
+///
+/// Usage: replay [--from <height>] [--to <height>] [--chain <spec>]
+fn run_replay(args: &[String], config: &AureonConfig, chain_spec: Option<&ChainSpec>) -> anyhow::Result<()> {
+    let height = |flag: &str| -> anyhow::Result<Option<u64>> {
+        arg_value(args, flag)
+            .map(|value| value.parse().map_err(|_| anyhow::anyhow!("{} expects a block height, got '{}'", flag, value)))
+            .transpose()
+    };
+    let db = Db::open(&config.database.path);
+    let emission = EmissionSchedule::new(&config.economy, config.state.accounts.values().sum());
+    let chain_store = ChainStore::new(&db)
+        .with_emission(Some(emission))
+        .with_genesis_state(genesis_state(config, chain_spec)?);
+    if let Some(spec) = chain_spec {
+        chain_store.check_genesis(&spec.genesis_hash()).map_err(anyhow::Error::msg)?;
+    }
+    if chain_store.is_empty() {
+        anyhow::bail!("No stored blocks in {}", config.database.path);
+    }
+    let from = height("--from")?.unwrap_or(0);
+    let to = height("--to")?.unwrap_or(chain_store.len() - 1);
+    println!("Re-executing blocks {}..={} of {}", from, to, config.database.path);
+
+    let scratch_path = std::env::temp_dir().join(format!("aureon_replay_{}", std::process::id()));
+    let scratch = Db::open(&scratch_path.to_string_lossy());
+    let report = chain_store.audit(&config.state.accounts, &scratch, from, to);
+    drop(scratch);
+    let _ = std::fs::remove_dir_all(&scratch_path);
+    let report = report.map_err(anyhow::Error::msg)?;
+
+    match report.divergence {
+        None => println!("✅ State roots of {} blocks match their headers", report.blocks_verified),
+        Some(divergence) => {
+            println!(
+                "❌ Block {} ({}) diverges after {} matching blocks",
+                divergence.height, divergence.block_hash, report.blocks_verified
+            );
+            println!("   {}-state root in header:  0x{}", divergence.root, hex::encode(&divergence.expected));
+            println!("   {}-state root recomputed: 0x{}", divergence.root, hex::encode(&divergence.computed));
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// Write the state after the block at `--height` (the tip by default) to a
+/// JSON dump, rebuilt by re-executing the stored chain
+///
+/// Usage: export-state [--height <height>] --out <file> [--chain <spec>]
+fn run_export_state(args: &[String], config: &AureonConfig, chain_spec: Option<&ChainSpec>) -> anyhow::Result<()> {
+    let out = arg_value(args, "--out").ok_or_else(|| anyhow::anyhow!("export-state needs --out <file>"))?;
+    let db = Db::open(&config.database.path);
+    let emission = EmissionSchedule::new(&config.economy, config.state.accounts.values().sum());
+    let chain_store = ChainStore::new(&db)
+        .with_emission(Some(emission))
+        .with_genesis_state(genesis_state(config, chain_spec)?);
+    if let Some(spec) = chain_spec {
+        chain_store.check_genesis(&spec.genesis_hash()).map_err(anyhow::Error::msg)?;
+    }
+    if chain_store.is_empty() {
+        anyhow::bail!("No stored blocks in {}", config.database.path);
+    }
+    let height = match arg_value(args, "--height") {
+        Some(value) => value.parse().map_err(|_| anyhow::anyhow!("--height expects a block height, got '{}'", value))?,
+        None => chain_store.len() - 1,
+    };
+
+    let scratch_path = std::env::temp_dir().join(format!("aureon_export_{}", std::process::id()));
+    let scratch = Db::open(&scratch_path.to_string_lossy());
+    let state = chain_store.state_at(&config.state.accounts, &scratch, height);
+    drop(scratch);
+    let _ = std::fs::remove_dir_all(&scratch_path);
+    let (blocks, trie) = state.map_err(anyhow::Error::msg)?;
+
+    let block = blocks.last().expect("state_at executes at least one block");
+    let export = state_export::StateExport::new(height, block, &trie, &state_export::sender_nonces(&blocks));
+    export.verify().map_err(anyhow::Error::msg)?;
+    fs::write(&out, serde_json::to_string_pretty(&export)?)?;
+    println!(
+        "✅ Exported {} accounts and {} storage entries at height {} (state root 0x{}) to {}",
+        export.accounts.len(),
+        export.storage.len(),
+        height,
+        export.state_root,
+        out
+    );
+    Ok(())
+}
+
+/// Start an empty node database from a state dump written by export-state,
+/// after checking the dump against its block's state root
+///
+/// Usage: import-state --in <file> [--chain <spec>]
+fn run_import_state(args: &[String], config: &AureonConfig, chain_spec: Option<&ChainSpec>) -> anyhow::Result<()> {
+    let input = arg_value(args, "--in").ok_or_else(|| anyhow::anyhow!("import-state needs --in <file>"))?;
+    let export: state_export::StateExport = serde_json::from_str(&fs::read_to_string(&input)?)?;
+    let snapshot = export.into_snapshot().map_err(anyhow::Error::msg)?;
+
+    let db = Db::open(&config.database.path);
+    let chain_store = ChainStore::new(&db);
+    if let Some(spec) = chain_spec {
+        chain_store.check_genesis(&spec.genesis_hash()).map_err(anyhow::Error::msg)?;
+    }
+    chain_store.import_snapshot(&snapshot).map_err(anyhow::Error::msg)?;
+    SnapshotStore::new(&db).save(&snapshot).map_err(anyhow::Error::msg)?;
+    println!(
+        "✅ Imported {} state entries at height {} into {}",
+        snapshot.entries.len(),
+        snapshot.height,
+        config.database.path
+    );
+    Ok(())
+}
+
+/// Write an encrypted validator keystore for the keystore and remote
+/// signers, importing an existing plaintext key or generating a new one; the
+/// password is read as configured for the keystore signer
+///
+/// Usage: signer-keygen [--out <file>] [--import <hex key file>]
+fn run_signer_keygen(args: &[String], config: &AureonConfig) -> anyhow::Result<()> {
+    let out = arg_value(args, "--out").unwrap_or_else(|| config.signer.keystore_path.clone());
+    if Path::new(&out).exists() {
+        anyhow::bail!("{} already exists", out);
+    }
+    let secret: [u8; 32] = match arg_value(args, "--import") {
+        Some(path) => hex::decode(fs::read_to_string(&path)?.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| anyhow::anyhow!("{} does not hold a hex-encoded 32-byte key", path))?,
+        None => {
+            let mut secret = [0u8; 32];
+            rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut secret);
+            secret
+        }
+    };
+    let password = signer::keystore::read_password(&config.signer.password_file).map_err(anyhow::Error::msg)?;
+    let keystore = signer::keystore::EncryptedKey::encrypt(&secret, &password, Default::default()).map_err(anyhow::Error::msg)?;
+    keystore.save(&out).map_err(anyhow::Error::msg)?;
+    println!("✅ Wrote validator keystore {} (public key {})", out, keystore.public_key);
+    Ok(())
+}
+
+/// Hold the validator key from an encrypted keystore and sign for nodes
+/// that share the secret in `--secret-file`, behind the signer's own
+/// slashing-protection database
+///
+/// Usage: remote-signer --listen <addr> --secret-file <file> [--keystore <file>] [--protection <file>]
+fn run_remote_signer(args: &[String], config: &AureonConfig) -> anyhow::Result<()> {
+    let listen = arg_value(args, "--listen").ok_or_else(|| anyhow::anyhow!("remote-signer needs --listen <addr>"))?;
+    let secret_file =
+        arg_value(args, "--secret-file").ok_or_else(|| anyhow::anyhow!("remote-signer needs --secret-file <file>"))?;
+    let keystore = arg_value(args, "--keystore").unwrap_or_else(|| config.signer.keystore_path.clone());
+    let protection = arg_value(args, "--protection").unwrap_or_else(|| config.signer.protection_path.clone());
+
+    let secret = signer::remote::read_secret(&secret_file).map_err(anyhow::Error::msg)?;
+    let password = signer::keystore::read_password(&config.signer.password_file).map_err(anyhow::Error::msg)?;
+    let key = signer::keystore::KeystoreSigner::unlock(&keystore, &password).map_err(anyhow::Error::msg)?;
+    println!("Remote signer for {} listening on {}", hex::encode(signer::ValidatorSigner::public_key(&key).as_bytes()), listen);
+
+    let key = signer::protection::ProtectedSigner::open(Arc::new(key), &protection).map_err(anyhow::Error::msg)?;
+    let app = signer::remote::router(Arc::new(key), &secret);
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(&listen).await?;
+        axum::serve(listener, app).await
+    })?;
+    Ok(())
+}
+
+/// Merge a slashing-protection history exported from another host into the
+/// configured database, after moving the validator key here
+///
+/// Usage: import-slashing-protection --in <file>
+fn run_import_slashing_protection(args: &[String], config: &AureonConfig) -> anyhow::Result<()> {
+    use signer::protection::{ProtectionFile, SlashingProtection};
+    let input = arg_value(args, "--in").ok_or_else(|| anyhow::anyhow!("import-slashing-protection needs --in <file>"))?;
+    let file: ProtectionFile = serde_json::from_str(&fs::read_to_string(&input)?)?;
+    let public_key = hex::decode(&file.public_key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|key| ed25519_dalek::VerifyingKey::from_bytes(&key).ok())
+        .ok_or_else(|| anyhow::anyhow!("{} has an invalid public key", input))?;
+
+    let path = &config.signer.protection_path;
+    let mut protection = SlashingProtection::load(path, public_key).map_err(anyhow::Error::msg)?;
+    protection.import(&file).map_err(anyhow::Error::msg)?;
+    protection.save(path).map_err(anyhow::Error::msg)?;
+    println!("✅ Imported {} signed slots for {} into {}", file.signed.len(), file.public_key, path);
     Ok(())
 }
 
@@ -1,230 +1,1098 @@
-use crate::types::Transaction;
-use crate::crypto;
-use std::collections::{HashMap, VecDeque};
-use std::sync::{Arc, Mutex};
-use sha2::{Sha256, Digest};
-use hex::encode as hex_encode;
+use crate::types::{Transaction, TransactionPayload};
+use crate::community_governance;
+use crate::config::{FeeMarketConfig, MempoolConfig};
+use crate::fee_market::{self, BaseFeeSchedule, ReservedSpace};
+use crate::events::{EventBus, NodeEvent, TransactionEvent};
+use crate::network::Network;
+use crate::shard_coordinator::{ShardId, ShardMap};
+use crate::db::{Column, Db};
+use crate::error::ErrorKind;
+use crate::metrics::Metrics;
+use crate::mpt::MerklePatriciaTrie;
+use crate::multisig;
+use crate::replay_protection;
+use crate::state_processor::stored_receipt;
+use crate::token::TokenState;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
 
-/// Transaction mempool for pending transactions awaiting inclusion in next block
-/// Implements FIFO ordering with size limits and nonce enforcement
+/// Pending transactions a mempool holds unless configured otherwise
+pub const DEFAULT_MEMPOOL_CAPACITY: usize = 1000;
+
+/// Key of the saved pool in the mempool column
+const SAVED_POOL_KEY: &[u8] = b"saved";
+
+/// Evicted transactions whose reason is remembered for lookups
+pub const MAX_EVICTION_HISTORY: usize = 1000;
+
+/// Why the mempool refused a transaction or request
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MempoolError {
+    #[error("Transaction already in mempool")]
+    Duplicate,
+    /// Bad signature, or not signed by enough owners of a multisig account
+    #[error("{0}")]
+    InvalidSignature(String),
+    /// Signed for another chain
+    #[error("{0}")]
+    WrongChain(String),
+    /// Gas price or gas limit outside the fee market rules
+    #[error("{0}")]
+    FeeMarket(String),
+    /// A payload the chain cannot execute
+    #[error("{0}")]
+    Unsupported(&'static str),
+    #[error("Replacement transaction underpriced for nonce {nonce}: gas price must be at least {required}")]
+    ReplacementUnderpriced { nonce: u64, required: u128 },
+    #[error("Invalid nonce: expected at least {expected}, got {got}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error("Too many queued transactions for {account} ({limit})")]
+    TooManyQueued { account: String, limit: usize },
+    #[error("Too many pending transactions for {account} ({limit})")]
+    TooManyPending { account: String, limit: usize },
+    #[error("Mempool full ({0} transactions)")]
+    Full(usize),
+    #[error("Mempool queue full ({0} transactions)")]
+    QueueFull(usize),
+    #[error("Insufficient balance: {balance} < {cost} needed with earlier pooled transactions")]
+    InsufficientBalance { balance: u128, cost: u128 },
+    #[error("Mempool is not shard-aware")]
+    NotShardAware,
+    /// The saved pool could not be written or read
+    #[error("{0}")]
+    Storage(String),
+    #[error("Mempool lock poisoned: {0}")]
+    Poisoned(String),
+}
+
+impl MempoolError {
+    /// Stable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            MempoolError::Duplicate => "duplicate_transaction",
+            MempoolError::InvalidSignature(_) => "invalid_signature",
+            MempoolError::WrongChain(_) => "wrong_chain",
+            MempoolError::FeeMarket(_) => "fee_market_rejected",
+            MempoolError::Unsupported(_) => "unsupported_payload",
+            MempoolError::ReplacementUnderpriced { .. } => "replacement_underpriced",
+            MempoolError::NonceTooLow { .. } => "nonce_too_low",
+            MempoolError::TooManyQueued { .. } => "too_many_queued",
+            MempoolError::TooManyPending { .. } => "too_many_pending",
+            MempoolError::Full(_) => "mempool_full",
+            MempoolError::QueueFull(_) => "queue_full",
+            MempoolError::InsufficientBalance { .. } => "insufficient_balance",
+            MempoolError::NotShardAware => "not_shard_aware",
+            MempoolError::Storage(_) => "mempool_storage",
+            MempoolError::Poisoned(_) => "internal",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            MempoolError::Duplicate
+            | MempoolError::ReplacementUnderpriced { .. }
+            | MempoolError::NonceTooLow { .. } => ErrorKind::Conflict,
+            MempoolError::TooManyQueued { .. }
+            | MempoolError::TooManyPending { .. }
+            | MempoolError::Full(_)
+            | MempoolError::QueueFull(_) => ErrorKind::Unavailable,
+            MempoolError::NotShardAware | MempoolError::Storage(_) | MempoolError::Poisoned(_) => ErrorKind::Internal,
+            _ => ErrorKind::InvalidRequest,
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for MempoolError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        MempoolError::Poisoned(e.to_string())
+    }
+}
+
+/// Why a transaction left the mempool without being included in a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionReason {
+    /// Waited longer than its time to live
+    Expired,
+    /// Replaced by a better-paying transaction with the same nonce
+    Replaced,
+    /// Pushed out of a full mempool by a better-paying transaction
+    Outbid,
+    /// Another transaction with its nonce was included in a block
+    NonceUsed,
+    /// Its sender can no longer pay for it
+    InsufficientBalance,
+    /// No longer passes signature or fee market checks
+    Invalid,
+}
+
+impl EvictionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvictionReason::Expired => "expired",
+            EvictionReason::Replaced => "replaced",
+            EvictionReason::Outbid => "outbid",
+            EvictionReason::NonceUsed => "nonce_used",
+            EvictionReason::InsufficientBalance => "insufficient_balance",
+            EvictionReason::Invalid => "invalid",
+        }
+    }
+}
+
+/// Pool contents persisted across a restart
+#[derive(Encode, Decode)]
+struct SavedPool {
+    /// Nonce each account's pending transactions start from
+    account_nonces: Vec<(String, u64)>,
+    /// Pending transactions in submission order, then queued transactions
+    transactions: Vec<Transaction>,
+}
+
+/// Transactions are stored by (sender, nonce), so a sender's transactions
+/// are contiguous and in nonce order
+type TxKey = (String, u64);
+
+/// Pending transaction with its submission sequence number (FIFO tie-break)
 #[derive(Clone, Debug)]
-pub struct TransactionMempool {
+struct PendingTransaction {
+    tx: Transaction,
+    seq: u64,
+    added_at: Instant,
+}
+
+/// Future-nonce transaction waiting for the gap before it to fill
+#[derive(Clone, Debug)]
+struct QueuedTransaction {
+    tx: Transaction,
+    queued_at: Instant,
+}
+
+/// Mempool contents, guarded by a single lock
+#[derive(Debug, Default)]
+struct Pool {
+    /// Transactions that continue their sender's nonce sequence
+    pending: BTreeMap<TxKey, PendingTransaction>,
+    /// Future-nonce transactions
+    queued: BTreeMap<TxKey, QueuedTransaction>,
+    /// Transaction hash -> key, for duplicate detection (pending and queued)
+    seen: HashMap<String, TxKey>,
+    /// Next nonce each account's pending transactions continue from (prevents replay attacks)
+    account_nonces: HashMap<String, u64>,
+    /// Sequence number of the next pending transaction
+    next_seq: u64,
+    /// Reasons of the latest evictions, by transaction hash
+    evictions: HashMap<String, EvictionReason>,
+    /// Hashes in `evictions`, oldest first
+    eviction_order: VecDeque<String>,
+    /// Evictions since startup, by reason
+    eviction_counts: BTreeMap<EvictionReason, u64>,
+}
+
+impl Pool {
+    fn account_range<'a, T>(map: &'a BTreeMap<TxKey, T>, account: &str) -> impl DoubleEndedIterator<Item = (&'a TxKey, &'a T)> {
+        map.range((account.to_string(), 0)..=(account.to_string(), u64::MAX))
+    }
+
+    fn pending_count(&self, account: &str) -> usize {
+        Self::account_range(&self.pending, account).count()
+    }
+
+    fn queued_count(&self, account: &str) -> usize {
+        Self::account_range(&self.queued, account).count()
+    }
+
     /// Pending transactions in submission order
-    pending: Arc<Mutex<VecDeque<Transaction>>>,
-    /// Track transaction hashes to prevent duplicates
-    seen: Arc<Mutex<HashMap<String, bool>>>,
-    /// Track highest nonce for each account (prevents replay attacks)
-    account_nonces: Arc<Mutex<HashMap<String, u64>>>,
+    fn pending_in_order(&self) -> Vec<(&TxKey, &Transaction)> {
+        let mut entries: Vec<_> = self.pending.iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.seq);
+        entries.into_iter().map(|(key, entry)| (key, &entry.tx)).collect()
+    }
+
+    /// Most `account`'s pooled transactions below `nonce` can spend
+    fn cost_before(&self, account: &str, nonce: u64, charges_fees: bool) -> u128 {
+        let pending = Self::account_range(&self.pending, account).map(|(key, entry)| (key, &entry.tx));
+        let queued = Self::account_range(&self.queued, account).map(|(key, entry)| (key, &entry.tx));
+        pending
+            .chain(queued)
+            .filter(|(key, _)| key.1 < nonce)
+            .map(|(_, tx)| fee_market::max_cost(tx, charges_fees))
+            .sum()
+    }
+
+    fn push_pending(&mut self, tx: Transaction, tx_hash: String) {
+        let key = (tx.from.clone(), tx.nonce);
+        self.seen.insert(tx_hash, key.clone());
+        self.pending.insert(key, PendingTransaction { tx, seq: self.next_seq, added_at: Instant::now() });
+        self.next_seq += 1;
+    }
+
+    fn record_eviction(&mut self, tx_hash: String, reason: EvictionReason) {
+        *self.eviction_counts.entry(reason).or_insert(0) += 1;
+        if self.evictions.insert(tx_hash.clone(), reason).is_none() {
+            self.eviction_order.push_back(tx_hash);
+        }
+        while self.eviction_order.len() > MAX_EVICTION_HISTORY {
+            if let Some(oldest) = self.eviction_order.pop_front() {
+                self.evictions.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &TxKey) -> Option<Transaction> {
+        let tx = match self.pending.remove(key) {
+            Some(entry) => entry.tx,
+            None => self.queued.remove(key)?.tx,
+        };
+        self.seen.remove(&tx.hash());
+        Some(tx)
+    }
+}
+
+/// Transaction mempool for pending transactions awaiting inclusion in next block
+/// Orders transactions by gas price (FIFO among equal prices) with size limits,
+/// minimum gas price and nonce enforcement
+///
+/// Transactions whose nonce is the next one expected from their sender are
+/// pending and can be included in blocks. Transactions with a higher nonce
+/// are queued until the gap before them fills, then promoted to pending;
+/// the queue is bounded per sender and in total, and a full queue evicts
+/// its cheapest transaction for a better-paying one.
+/// A transaction for an (account, nonce) already in the pool replaces the
+/// existing one if it raises the gas price by at least the configured bump.
+/// With a state attached, a sender's balance must cover the value and
+/// maximum fee of its transaction on top of its earlier pooled ones.
+///
+/// Pending transactions expire after the pending TTL, and `revalidate`
+/// evicts those the current state no longer allows. The reason of every
+/// eviction is kept for a while, so clients can learn what happened.
+#[derive(Clone)]
+pub struct TransactionMempool {
+    pool: Arc<Mutex<Pool>>,
     /// Maximum transactions in mempool
     max_size: usize,
+    /// Replacement and per-account limits
+    limits: MempoolConfig,
+    /// Gas price floor and block gas limit, adjustable by governance
+    fee_market: Arc<RwLock<FeeMarketConfig>>,
+    /// Optional event bus notified of newly accepted transactions
+    events: Option<EventBus>,
+    /// Optional network that locally submitted transactions are gossiped to
+    network: Option<Network>,
+    /// Optional shard layout; transactions belong to their sender's shard
+    shards: Option<Arc<RwLock<ShardMap>>>,
+    /// Optional latest state, holding the multisig configs of accounts and the base fee
+    state: Option<Arc<RwLock<MerklePatriciaTrie>>>,
+    /// Base fee rules, when fee burning is enabled
+    base_fee: Option<BaseFeeSchedule>,
+    /// Optional metrics counting evictions
+    metrics: Option<Arc<Metrics>>,
 }
 
 impl TransactionMempool {
     /// Create a new mempool with default capacity (1000 transactions)
     pub fn new() -> Self {
-        Self::with_capacity(1000)
+        Self::with_capacity(DEFAULT_MEMPOOL_CAPACITY)
     }
 
     /// Create a mempool with custom capacity
     pub fn with_capacity(max_size: usize) -> Self {
         TransactionMempool {
-            pending: Arc::new(Mutex::new(VecDeque::new())),
-            seen: Arc::new(Mutex::new(HashMap::new())),
-            account_nonces: Arc::new(Mutex::new(HashMap::new())),
+            pool: Arc::new(Mutex::new(Pool::default())),
             max_size,
+            limits: MempoolConfig::default(),
+            fee_market: Arc::new(RwLock::new(FeeMarketConfig::default())),
+            events: None,
+            network: None,
+            shards: None,
+            state: None,
+            base_fee: None,
+            metrics: None,
         }
     }
 
+    /// Apply the given fee market rules
+    pub fn with_fee_market(mut self, fee_market: FeeMarketConfig) -> Self {
+        self.fee_market = Arc::new(RwLock::new(fee_market));
+        self
+    }
+
+    /// Fee market rules currently applied by this mempool
+    pub fn fee_market(&self) -> FeeMarketConfig {
+        self.fee_market.read().unwrap().clone()
+    }
+
+    /// Replace the fee market rules of a running mempool
+    ///
+    /// Applies to transactions submitted and blocks assembled afterwards;
+    /// transactions already pending are checked on the next `revalidate`.
+    pub fn set_fee_market(&self, fee_market: FeeMarketConfig) {
+        *self.fee_market.write().unwrap() = fee_market;
+    }
+
+    /// Route transactions to the shard owning their sender in the current
+    /// layout of `shards`
+    pub fn with_shards(mut self, shards: Arc<RwLock<ShardMap>>) -> Self {
+        self.shards = Some(shards);
+        self
+    }
+
+    /// Shard a transaction is routed to, when the mempool is shard-aware
+    pub fn shard_of(&self, tx: &Transaction) -> Option<ShardId> {
+        self.shards.as_ref().map(|shards| shards.read().unwrap().coordinator().get_shard(&tx.from))
+    }
+
+    /// Apply the given queue, replacement and per-account limits
+    pub fn with_limits(mut self, limits: MempoolConfig) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Publish accepted transactions on the given event bus
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Gossip accepted local transactions to peers over the given network
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Check transactions from multisig accounts against the configs in `trie`
+    pub fn with_state(mut self, trie: Arc<RwLock<MerklePatriciaTrie>>) -> Self {
+        self.state = Some(trie);
+        self
+    }
+
+    /// Leave transactions bidding below the base fee of `schedule` out of
+    /// blocks, ranking the rest by their tip
+    pub fn with_base_fee(mut self, schedule: Option<BaseFeeSchedule>) -> Self {
+        self.base_fee = schedule;
+        self
+    }
+
+    /// Count evictions in the given metrics
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Base fee of the next block, read from the latest state; zero without fee burning
+    pub fn base_fee(&self) -> u64 {
+        match (&self.base_fee, &self.state) {
+            (Some(schedule), Some(state)) => schedule.base_fee(&*state.read().unwrap()),
+            (Some(schedule), None) => schedule.initial_base_fee,
+            (None, _) => 0,
+        }
+    }
+
+    fn pool(&self) -> Result<MutexGuard<'_, Pool>, MempoolError> {
+        Ok(self.pool.lock()?)
+    }
+
     /// Add a transaction to the mempool
     /// Returns the transaction hash if successful, error message otherwise
-    /// Verifies Ed25519 signature and nonce ordering before accepting transaction
-    pub fn add_transaction(&self, tx: Transaction) -> Result<String, String> {
+    /// Verifies Ed25519 signature, gas pricing and nonce ordering before accepting transaction
+    /// When full, a transaction outbidding the cheapest pending one evicts it
+    /// Accepted transactions are gossiped to peers when a network is attached
+    pub fn add_transaction(&self, tx: Transaction) -> Result<String, MempoolError> {
+        let gossip = self.network.as_ref().map(|network| (network, tx.clone()));
+        let tx_hash = self.insert(tx)?;
+        if let Some((network, tx)) = gossip {
+            network.broadcast_transaction(&tx);
+        }
+        Ok(tx_hash)
+    }
+
+    /// Add a transaction received from a peer
+    /// Runs the same checks as `add_transaction` but does not re-broadcast,
+    /// since the gossip layer already relays it to the rest of the network
+    pub fn add_gossiped_transaction(&self, tx: Transaction) -> Result<String, MempoolError> {
+        if self.contains(&tx.hash())? {
+            return Err(MempoolError::Duplicate);
+        }
+        self.insert(tx)
+    }
+
+    #[tracing::instrument(
+        name = "mempool_insert",
+        skip_all,
+        fields(from = %tx.from, nonce = tx.nonce, gas_price = tx.gas_price),
+        ret(level = "debug"),
+        err(level = "debug")
+    )]
+    fn insert(&self, tx: Transaction) -> Result<String, MempoolError> {
+        self.check(&tx)?;
+        self.prune_queued()?;
+        let from = tx.from.clone();
+        // Read before locking the pool, so the state lock is never taken inside it
+        let balance = self.balance(&from)?;
+
+        let mut pool = self.pool()?;
+        let (tx_hash, pending) = self.insert_locked(&mut pool, tx, balance)?;
+        drop(pool);
+        for hash in pending {
+            self.publish_pending(hash, &from);
+        }
+        Ok(tx_hash)
+    }
+
+    /// Add several transactions, checking each on its own and inserting the
+    /// ones that pass under a single hold of the pool lock, so no block is
+    /// built from part of the batch
+    /// Returns each transaction's hash or the reason it was rejected, in order
+    pub fn add_transactions(&self, txs: Vec<Transaction>) -> Result<Vec<Result<String, MempoolError>>, MempoolError> {
+        let checked: Vec<_> = txs.into_iter().map(|tx| self.check(&tx).map(|()| tx)).collect();
+        self.prune_queued()?;
+        let mut balances = HashMap::new();
+        for tx in checked.iter().flatten() {
+            if !balances.contains_key(&tx.from) {
+                balances.insert(tx.from.clone(), self.balance(&tx.from)?);
+            }
+        }
+
+        let mut pool = self.pool()?;
+        let mut results = Vec::with_capacity(checked.len());
+        let mut published = Vec::new();
+        let mut accepted = Vec::new();
+        for tx in checked {
+            let tx = match tx {
+                Ok(tx) => tx,
+                Err(e) => {
+                    results.push(Err(e));
+                    continue;
+                }
+            };
+            let from = tx.from.clone();
+            let gossip = self.network.as_ref().map(|_| tx.clone());
+            match self.insert_locked(&mut pool, tx, balances[&from]) {
+                Ok((tx_hash, pending)) => {
+                    published.extend(pending.into_iter().map(|hash| (hash, from.clone())));
+                    accepted.extend(gossip);
+                    results.push(Ok(tx_hash));
+                }
+                Err(e) => results.push(Err(e)),
+            }
+        }
+        drop(pool);
+
+        for (hash, from) in published {
+            self.publish_pending(hash, &from);
+        }
+        if let Some(network) = &self.network {
+            for tx in &accepted {
+                network.broadcast_transaction(tx);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Checks that need neither the pool nor the state
+    fn check(&self, tx: &Transaction) -> Result<(), MempoolError> {
+        if let TransactionPayload::GovernanceVote { .. } = tx.payload {
+            return Err(MempoolError::Unsupported(community_governance::VOTE_TRANSACTIONS_UNSUPPORTED));
+        }
+
         // Verify transaction signature
-        self.verify_transaction_signature(&tx)?;
-        
-        // Verify nonce (prevents replay attacks and out-of-order execution)
-        self.verify_nonce(&tx)?;
-        
-        let tx_hash = self.compute_tx_hash(&tx);
+        self.verify_transaction_signature(tx)?;
+
+        // Enforce minimum gas price and gas limits
+        fee_market::validate(tx, &self.fee_market()).map_err(MempoolError::FeeMarket)
+    }
+
+    /// Insert a checked transaction into the locked pool, given its sender's
+    /// balance; returns its hash and the hashes that became pending
+    fn insert_locked(
+        &self,
+        pool: &mut Pool,
+        tx: Transaction,
+        balance: Option<u128>,
+    ) -> Result<(String, Vec<String>), MempoolError> {
+        let tx_hash = tx.hash();
+        let key = (tx.from.clone(), tx.nonce);
+        let from = tx.from.clone();
 
         // Check for duplicates
-        let mut seen = self.seen.lock().map_err(|e| e.to_string())?;
-        if seen.contains_key(&tx_hash) {
-            return Err("Transaction already in mempool".to_string());
+        if pool.seen.contains_key(&tx_hash) {
+            return Err(MempoolError::Duplicate);
         }
 
-        // Check mempool capacity
-        let mut pending = self.pending.lock().map_err(|e| e.to_string())?;
-        if pending.len() >= self.max_size {
-            return Err(format!(
-                "Mempool full ({} transactions)",
-                self.max_size
-            ));
+        // The sender must afford this transaction after its earlier ones in the pool
+        if let Some(balance) = balance {
+            let cost = pool.cost_before(&from, tx.nonce, self.base_fee.is_some())
+                + fee_market::max_cost(&tx, self.base_fee.is_some());
+            if cost > balance {
+                return Err(MempoolError::InsufficientBalance { balance, cost });
+            }
         }
 
-        // Update account nonce to track maximum nonce seen
-        let mut nonces = self.account_nonces.lock().map_err(|e| e.to_string())?;
-        nonces.insert(tx.from.clone(), tx.nonce);
+        // Replace an existing transaction with the same nonce if sufficiently outbid
+        let existing = pool
+            .pending
+            .get(&key)
+            .map(|entry| &entry.tx)
+            .or_else(|| pool.queued.get(&key).map(|entry| &entry.tx));
+        if let Some(existing) = existing {
+            let required = existing.gas_price as u128 * (100 + self.limits.price_bump_percent as u128);
+            if (tx.gas_price as u128) * 100 < required {
+                return Err(MempoolError::ReplacementUnderpriced { nonce: tx.nonce, required: required.div_ceil(100) });
+            }
+            let old_hash = existing.hash();
+            pool.seen.remove(&old_hash);
+            pool.seen.insert(tx_hash.clone(), key.clone());
+            self.record_eviction(pool, old_hash, EvictionReason::Replaced);
+            if let Some(entry) = pool.pending.get_mut(&key) {
+                entry.tx = tx;
+                entry.added_at = Instant::now();
+            } else if let Some(entry) = pool.queued.get_mut(&key) {
+                *entry = QueuedTransaction { tx, queued_at: Instant::now() };
+                return Ok((tx_hash, Vec::new()));
+            }
+            return Ok((tx_hash.clone(), vec![tx_hash]));
+        }
 
-        // Add to mempool
-        pending.push_back(tx);
-        seen.insert(tx_hash.clone(), true);
+        // Verify nonce (prevents replay attacks); future nonces wait in the queue
+        let expected = pool.account_nonces.get(&from).copied().unwrap_or(0);
+        if tx.nonce < expected {
+            return Err(MempoolError::NonceTooLow { expected, got: tx.nonce });
+        }
+        if tx.nonce > expected {
+            if pool.queued_count(&from) >= self.limits.max_queued_per_account {
+                return Err(MempoolError::TooManyQueued { account: from, limit: self.limits.max_queued_per_account });
+            }
+            if pool.queued.len() >= self.limits.max_queued {
+                self.evict_cheapest_queued(pool, tx.gas_price)?;
+            }
+            pool.seen.insert(tx_hash.clone(), key.clone());
+            pool.queued.insert(key, QueuedTransaction { tx, queued_at: Instant::now() });
+            return Ok((tx_hash, Vec::new()));
+        }
 
-        Ok(tx_hash)
+        if pool.pending_count(&from) >= self.limits.max_pending_per_account {
+            return Err(MempoolError::TooManyPending { account: from, limit: self.limits.max_pending_per_account });
+        }
+
+        // Check mempool capacity, evicting the cheapest transaction if outbid
+        if pool.pending.len() >= self.max_size {
+            self.evict_cheapest(pool, tx.gas_price)?;
+        }
+
+        // Add to mempool, then promote queued transactions the new nonce unblocks
+        pool.account_nonces.insert(from.clone(), tx.nonce + 1);
+        pool.push_pending(tx, tx_hash.clone());
+        let promoted = self.promote(pool, &from);
+        Ok((tx_hash.clone(), std::iter::once(tx_hash).chain(promoted).collect()))
     }
 
-    /// Get next N transactions from mempool for block production
-    /// Removes transactions from mempool (assumed to be included in block)
-    pub fn take_transactions(&self, count: usize) -> Result<Vec<Transaction>, String> {
-        let mut pending = self.pending.lock().map_err(|e| e.to_string())?;
-        let mut seen = self.seen.lock().map_err(|e| e.to_string())?;
-
-        let mut transactions = Vec::new();
-        for _ in 0..count {
-            if let Some(tx) = pending.pop_front() {
-                let tx_hash = self.compute_tx_hash(&tx);
-                seen.remove(&tx_hash);
-                transactions.push(tx);
-            } else {
+    /// Balance of `account` in the latest state, when the mempool has one
+    fn balance(&self, account: &str) -> Result<Option<u128>, MempoolError> {
+        let Some(state) = &self.state else {
+            return Ok(None);
+        };
+        let trie = state.read()?;
+        let bytes = TokenState::get(&*trie, account.as_bytes());
+        Ok(Some(bytes.and_then(|bytes| bytes.try_into().ok()).map_or(0, u64::from_le_bytes) as u128))
+    }
+
+    /// Evict the cheapest pending transaction if `gas_price` outbids it
+    /// Only each sender's highest-nonce transaction is a candidate, so
+    /// eviction never leaves a gap in a sender's pending sequence
+    fn evict_cheapest(&self, pool: &mut Pool, gas_price: u64) -> Result<(), MempoolError> {
+        let mut last_per_sender: HashMap<&str, (&TxKey, &PendingTransaction)> = HashMap::new();
+        for (key, entry) in &pool.pending {
+            last_per_sender.insert(key.0.as_str(), (key, entry));
+        }
+        let cheapest = last_per_sender
+            .into_values()
+            .min_by_key(|(_, entry)| (entry.tx.gas_price, std::cmp::Reverse(entry.seq)))
+            .map(|(key, entry)| (key.clone(), entry.tx.gas_price));
+
+        match cheapest {
+            Some((key, cheapest_price)) if gas_price > cheapest_price => {
+                self.evict(pool, &key, EvictionReason::Outbid);
+                // The sender can resubmit the evicted nonce
+                pool.account_nonces.insert(key.0, key.1);
+                Ok(())
+            }
+            _ => Err(MempoolError::Full(self.max_size)),
+        }
+    }
+
+    /// Evict the cheapest queued transaction if `gas_price` outbids it
+    /// Each sender's highest-nonce transaction is the candidate, as it is
+    /// the furthest from being promoted
+    fn evict_cheapest_queued(&self, pool: &mut Pool, gas_price: u64) -> Result<(), MempoolError> {
+        let mut last_per_sender: HashMap<&str, (&TxKey, &QueuedTransaction)> = HashMap::new();
+        for (key, entry) in &pool.queued {
+            last_per_sender.insert(key.0.as_str(), (key, entry));
+        }
+        let cheapest = last_per_sender
+            .into_values()
+            .min_by_key(|(_, entry)| (entry.tx.gas_price, std::cmp::Reverse(entry.queued_at)))
+            .map(|(key, entry)| (key.clone(), entry.tx.gas_price));
+
+        match cheapest {
+            Some((key, cheapest_price)) if gas_price > cheapest_price => {
+                self.evict(pool, &key, EvictionReason::Outbid);
+                Ok(())
+            }
+            _ => Err(MempoolError::QueueFull(self.limits.max_queued)),
+        }
+    }
+
+    /// Move `account`'s queued transactions that continue its nonce sequence
+    /// into pending, dropping queued ones whose nonce has already been used
+    /// Returns the hashes of the promoted transactions
+    fn promote(&self, pool: &mut Pool, account: &str) -> Vec<String> {
+        let mut expected = pool.account_nonces.get(account).copied().unwrap_or(0);
+
+        let stale: Vec<TxKey> = Pool::account_range(&pool.queued, account)
+            .map(|(key, _)| key.clone())
+            .take_while(|key| key.1 < expected)
+            .collect();
+        for key in stale {
+            self.evict(pool, &key, EvictionReason::NonceUsed);
+        }
+
+        let mut promoted = Vec::new();
+        while pool.pending.len() < self.max_size
+            && pool.pending_count(account) < self.limits.max_pending_per_account
+        {
+            let Some(next) = pool.queued.remove(&(account.to_string(), expected)) else {
                 break;
+            };
+            let tx_hash = next.tx.hash();
+            pool.push_pending(next.tx, tx_hash.clone());
+            promoted.push(tx_hash);
+            expected += 1;
+        }
+        pool.account_nonces.insert(account.to_string(), expected);
+        promoted
+    }
+
+    /// Remove `key` from the pool, remembering why
+    fn evict(&self, pool: &mut Pool, key: &TxKey, reason: EvictionReason) -> Option<Transaction> {
+        let tx = pool.remove(key)?;
+        self.record_eviction(pool, tx.hash(), reason);
+        Some(tx)
+    }
+
+    /// Evict the pending transaction at `key`, moving its sender's later
+    /// pending transactions back to the queue until the nonce is filled again
+    fn evict_pending(&self, pool: &mut Pool, key: &TxKey, reason: EvictionReason) {
+        if self.evict(pool, key, reason).is_none() {
+            return;
+        }
+        let later: Vec<TxKey> = Pool::account_range(&pool.pending, &key.0)
+            .map(|(later, _)| later.clone())
+            .filter(|later| later.1 > key.1)
+            .collect();
+        for later in later {
+            if let Some(entry) = pool.pending.remove(&later) {
+                pool.queued.insert(later, QueuedTransaction { tx: entry.tx, queued_at: Instant::now() });
+            }
+        }
+        pool.account_nonces.insert(key.0.clone(), key.1);
+    }
+
+    fn record_eviction(&self, pool: &mut Pool, tx_hash: String, reason: EvictionReason) {
+        tracing::debug!(tx_hash = %tx_hash, reason = reason.as_str(), "mempool eviction");
+        if let Some(metrics) = &self.metrics {
+            metrics.mempool_evictions.with_label_values(&[reason.as_str()]).inc();
+        }
+        pool.record_eviction(tx_hash, reason);
+    }
+
+    fn publish_pending(&self, tx_hash: String, from: &str) {
+        if let Some(events) = &self.events {
+            events.publish(NodeEvent::Transactions(TransactionEvent {
+                event_type: "pending_transaction".to_string(),
+                tx_hash,
+                from: from.to_string(),
+                block_number: 0,
+            }));
+        }
+    }
+
+    /// Evict queued transactions that have waited longer than the queue TTL
+    /// Returns the number of evicted transactions
+    pub fn prune_queued(&self) -> Result<usize, MempoolError> {
+        let ttl = Duration::from_secs(self.limits.queued_ttl_secs);
+        let mut pool = self.pool()?;
+        let expired: Vec<TxKey> = pool
+            .queued
+            .iter()
+            .filter(|(_, entry)| entry.queued_at.elapsed() >= ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.evict(&mut pool, key, EvictionReason::Expired);
+        }
+        Ok(expired.len())
+    }
+
+    /// Check the pool against the current state, evicting transactions that
+    /// can no longer be included; returns the number of evicted transactions
+    ///
+    /// Queued transactions past the queue TTL and pending ones past the
+    /// pending TTL expire. Pending transactions with a receipt in `db` were
+    /// included in a block the mempool did not see, so they are dropped and
+    /// their senders' nonces advanced, evicting other transactions for the
+    /// same nonces. The rest must still pass the signature and fee market
+    /// checks, and each sender's balance must cover its pending transactions
+    /// in nonce order. A sender's first failing transaction is evicted and
+    /// its later ones go back to the queue.
+    pub fn revalidate(&self, db: &Db) -> Result<usize, MempoolError> {
+        let evicted = self.prune_queued()?;
+        let pending: Vec<(TxKey, Transaction, Instant)> = self
+            .pool()?
+            .pending
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.tx.clone(), entry.added_at))
+            .collect();
+
+        // Decide without the pool lock, since the checks read the state
+        let ttl = Duration::from_secs(self.limits.pending_ttl_secs);
+        let fee_market = self.fee_market();
+        let charges_fees = self.base_fee.is_some();
+        let mut included: Vec<(TxKey, String, bool)> = Vec::new();
+        let mut failed: Vec<(TxKey, String, EvictionReason)> = Vec::new();
+        let mut sender_start = 0;
+        while sender_start < pending.len() {
+            let sender = &pending[sender_start].0.0;
+            let sender_end = pending[sender_start..]
+                .iter()
+                .position(|(key, _, _)| key.0 != *sender)
+                .map_or(pending.len(), |offset| sender_start + offset);
+            let transactions = &pending[sender_start..sender_end];
+            sender_start = sender_end;
+
+            // Everything up to the last included nonce is settled on chain
+            let receipts: Vec<bool> = transactions.iter().map(|(_, tx, _)| stored_receipt(db, &tx.hash()).is_some()).collect();
+            let settled = receipts.iter().rposition(|&receipt| receipt).map_or(0, |last| last + 1);
+            for ((key, tx, _), &receipt) in transactions[..settled].iter().zip(&receipts) {
+                included.push((key.clone(), tx.hash(), receipt));
+            }
+
+            let balance = self.balance(sender)?;
+            let mut cost = 0u128;
+            for (key, tx, added_at) in &transactions[settled..] {
+                cost += fee_market::max_cost(tx, charges_fees);
+                let reason = if added_at.elapsed() >= ttl {
+                    Some(EvictionReason::Expired)
+                } else if self.verify_transaction_signature(tx).is_err() || fee_market::validate(tx, &fee_market).is_err() {
+                    Some(EvictionReason::Invalid)
+                } else if balance.is_some_and(|balance| cost > balance) {
+                    Some(EvictionReason::InsufficientBalance)
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    failed.push((key.clone(), tx.hash(), reason));
+                    break;
+                }
+            }
+        }
+
+        // Apply to entries that have not changed in the meantime
+        let mut pool = self.pool()?;
+        let evicted_before: u64 = pool.eviction_counts.values().sum();
+        let unchanged = |pool: &Pool, key: &TxKey, hash: &str| {
+            pool.pending.get(key).is_some_and(|entry| entry.tx.hash() == hash)
+        };
+        let mut advanced = Vec::new();
+        for (key, hash, receipt) in included {
+            if !unchanged(&pool, &key, &hash) {
+                continue;
+            }
+            if receipt {
+                pool.remove(&key);
+            } else {
+                self.evict(&mut pool, &key, EvictionReason::NonceUsed);
+            }
+            let expected = pool.account_nonces.entry(key.0.clone()).or_insert(0);
+            *expected = (*expected).max(key.1 + 1);
+            advanced.push(key.0);
+        }
+        let mut promoted = Vec::new();
+        for sender in advanced {
+            // Also drops queued transactions for the now used nonces
+            for hash in self.promote(&mut pool, &sender) {
+                promoted.push((hash, sender.clone()));
+            }
+        }
+        for (key, hash, reason) in failed {
+            if unchanged(&pool, &key, &hash) {
+                self.evict_pending(&mut pool, &key, reason);
+            }
+        }
+        let evicted_after: u64 = pool.eviction_counts.values().sum();
+        drop(pool);
+
+        for (hash, from) in promoted {
+            self.publish_pending(hash, &from);
+        }
+        Ok(evicted + (evicted_after - evicted_before) as usize)
+    }
+
+    /// Why the transaction `tx_hash` was evicted, if it was recently and has
+    /// not been resubmitted since
+    pub fn eviction_reason(&self, tx_hash: &str) -> Result<Option<EvictionReason>, MempoolError> {
+        let pool = self.pool()?;
+        if pool.seen.contains_key(tx_hash) {
+            return Ok(None);
+        }
+        Ok(pool.evictions.get(tx_hash).copied())
+    }
+
+    /// Get next N transactions from mempool for block production, highest gas price first
+    /// Removes transactions from mempool (assumed to be included in block)
+    pub fn take_transactions(&self, count: usize) -> Result<Vec<Transaction>, MempoolError> {
+        self.take_from(None, count, u64::MAX, ReservedSpace::default())
+    }
+
+    /// Take the best-paying transactions that fit under the block gas limit,
+    /// system and governance transactions first within `reserved` space
+    pub fn take_block_transactions(&self, max_count: usize, reserved: ReservedSpace) -> Result<Vec<Transaction>, MempoolError> {
+        self.take_from(None, max_count, self.fee_market().block_gas_limit, reserved)
+    }
+
+    /// Take the best-paying transactions of one shard that fit under the block gas limit
+    ///
+    /// Each shard produces its own blocks, so the gas limit and the
+    /// `reserved` space apply per shard.
+    pub fn take_shard_transactions(
+        &self,
+        shard: ShardId,
+        max_count: usize,
+        reserved: ReservedSpace,
+    ) -> Result<Vec<Transaction>, MempoolError> {
+        if self.shards.is_none() {
+            return Err(MempoolError::NotShardAware);
+        }
+        self.take_from(Some(shard), max_count, self.fee_market().block_gas_limit, reserved)
+    }
+
+    /// Number of pending transactions routed to each shard
+    pub fn pending_by_shard(&self) -> Result<HashMap<ShardId, usize>, MempoolError> {
+        let pool = self.pool()?;
+        let mut counts = HashMap::new();
+        for entry in pool.pending.values() {
+            if let Some(shard) = self.shard_of(&entry.tx) {
+                *counts.entry(shard).or_insert(0) += 1;
             }
         }
+        Ok(counts)
+    }
 
-        Ok(transactions)
+    fn take_from(
+        &self,
+        shard: Option<ShardId>,
+        max_count: usize,
+        gas_budget: u64,
+        reserved: ReservedSpace,
+    ) -> Result<Vec<Transaction>, MempoolError> {
+        // Read before locking the pool, so the state lock is never taken inside it
+        let base_fee = self.base_fee();
+        let mut pool = self.pool()?;
+        let (keys, candidates): (Vec<TxKey>, Vec<Transaction>) = pool
+            .pending_in_order()
+            .into_iter()
+            .filter(|(_, tx)| shard.is_none() || self.shard_of(tx) == shard)
+            .map(|(key, tx)| (key.clone(), tx.clone()))
+            .unzip();
+
+        let selected = fee_market::select_by_lane(&candidates, max_count, gas_budget, base_fee, reserved);
+        Ok(selected
+            .into_iter()
+            .filter_map(|index| pool.remove(&keys[index]))
+            .collect())
     }
 
     /// Get all pending transactions without removing them
-    pub fn get_pending(&self) -> Result<Vec<Transaction>, String> {
-        let pending = self.pending.lock().map_err(|e| e.to_string())?;
-        Ok(pending.iter().cloned().collect())
+    pub fn get_pending(&self) -> Result<Vec<Transaction>, MempoolError> {
+        let pool = self.pool()?;
+        Ok(pool.pending_in_order().into_iter().map(|(_, tx)| tx.clone()).collect())
     }
 
     /// Finalize nonces for transactions included in a block
     /// Called after block is produced to bump expected nonces
-    pub fn finalize_block_transactions(&self, transactions: &[Transaction]) -> Result<(), String> {
-        let mut nonces = self.account_nonces.lock().map_err(|e| e.to_string())?;
-        
+    /// Pool entries made obsolete by the block are dropped, and queued
+    /// transactions of the block's senders are promoted once the block's
+    /// nonces fill the gap before them
+    pub fn finalize_block_transactions(&self, transactions: &[Transaction]) -> Result<(), MempoolError> {
+        let mut pool = self.pool()?;
+
         for tx in transactions {
-            // Increment expected nonce to tx.nonce + 1
-            nonces.insert(tx.from.clone(), tx.nonce + 1);
+            let obsolete: Vec<TxKey> = Pool::account_range(&pool.pending, &tx.from)
+                .map(|(key, _)| key.clone())
+                .take_while(|key| key.1 <= tx.nonce)
+                .collect();
+            for key in obsolete {
+                let included = pool.pending.get(&key).is_some_and(|entry| entry.tx.hash() == tx.hash());
+                if included {
+                    pool.remove(&key);
+                } else {
+                    self.evict(&mut pool, &key, EvictionReason::NonceUsed);
+                }
+            }
+            // Expected nonce is at least tx.nonce + 1
+            let expected = pool.account_nonces.entry(tx.from.clone()).or_insert(0);
+            *expected = (*expected).max(tx.nonce + 1);
+        }
+        let mut promoted = Vec::new();
+        for tx in transactions {
+            for hash in self.promote(&mut pool, &tx.from) {
+                promoted.push((hash, tx.from.clone()));
+            }
+        }
+        drop(pool);
+
+        for (hash, from) in promoted {
+            self.publish_pending(hash, &from);
         }
-        
         Ok(())
     }
 
     /// Get current nonce for an account (for API queries)
-    pub fn get_account_nonce(&self, account: &str) -> Result<u64, String> {
-        let nonces = self.account_nonces.lock().map_err(|e| e.to_string())?;
-        Ok(nonces.get(account).copied().unwrap_or(0))
+    pub fn get_account_nonce(&self, account: &str) -> Result<u64, MempoolError> {
+        let pool = self.pool()?;
+        Ok(pool.account_nonces.get(account).copied().unwrap_or(0))
+    }
+
+    /// Next nonce of an account as of the latest block, before the
+    /// transactions it has pending in the pool
+    pub fn committed_nonce(&self, account: &str) -> Result<u64, MempoolError> {
+        let pool = self.pool()?;
+        let expected = pool.account_nonces.get(account).copied().unwrap_or(0);
+        Ok(Pool::account_range(&pool.pending, account).next().map_or(expected, |(key, _)| key.1))
+    }
+
+    /// Nonce that continues an account's pending transactions
+    pub fn next_nonce(&self, account: &str) -> Result<u64, MempoolError> {
+        let pool = self.pool()?;
+        Ok(pool.account_nonces.get(account).copied().unwrap_or(0))
     }
 
     /// Get transaction count
-    pub fn size(&self) -> Result<usize, String> {
-        let pending = self.pending.lock().map_err(|e| e.to_string())?;
-        Ok(pending.len())
+    pub fn size(&self) -> Result<usize, MempoolError> {
+        Ok(self.pool()?.pending.len())
     }
 
     /// Check if transaction is in mempool
-    pub fn contains(&self, tx_hash: &str) -> Result<bool, String> {
-        let seen = self.seen.lock().map_err(|e| e.to_string())?;
-        Ok(seen.contains_key(tx_hash))
+    pub fn contains(&self, tx_hash: &str) -> Result<bool, MempoolError> {
+        Ok(self.pool()?.seen.contains_key(tx_hash))
     }
 
-    /// Clear all transactions (useful for testing)
-    #[allow(dead_code)]
-    pub fn clear(&self) -> Result<(), String> {
-        self.pending.lock().map_err(|e| e.to_string())?.clear();
-        self.seen.lock().map_err(|e| e.to_string())?.clear();
-        Ok(())
+    /// Drop every pending and queued transaction, returning how many were dropped
+    pub fn clear(&self) -> Result<usize, MempoolError> {
+        let mut pool = self.pool()?;
+        let dropped = pool.seen.len();
+        pool.pending.clear();
+        pool.queued.clear();
+        pool.seen.clear();
+        Ok(dropped)
     }
 
     /// Remove a specific transaction by hash
-    pub fn remove_transaction(&self, tx_hash: &str) -> Result<bool, String> {
-        let mut seen = self.seen.lock().map_err(|e| e.to_string())?;
-        if !seen.remove(tx_hash).unwrap_or(false) {
+    pub fn remove_transaction(&self, tx_hash: &str) -> Result<bool, MempoolError> {
+        let mut pool = self.pool()?;
+        let Some(key) = pool.seen.get(tx_hash).cloned() else {
             return Ok(false);
-        }
-
-        let mut pending = self.pending.lock().map_err(|e| e.to_string())?;
-        let initial_len = pending.len();
-        pending.retain(|tx| self.compute_tx_hash(tx) != tx_hash);
+        };
+        Ok(pool.remove(&key).is_some())
+    }
 
-        Ok(pending.len() < initial_len)
+    /// Persist pending and queued transactions to `db`, returning how many were saved
+    pub fn save(&self, db: &Db) -> Result<usize, MempoolError> {
+        let pool = self.pool()?;
+        let account_nonces = pool
+            .account_nonces
+            .iter()
+            .map(|(account, next)| {
+                let first_pending = Pool::account_range(&pool.pending, account).next().map(|(key, _)| key.1);
+                (account.clone(), first_pending.unwrap_or(*next))
+            })
+            .collect();
+        let transactions: Vec<Transaction> = pool
+            .pending_in_order()
+            .into_iter()
+            .map(|(_, tx)| tx.clone())
+            .chain(pool.queued.values().map(|entry| entry.tx.clone()))
+            .collect();
+        let saved = SavedPool { account_nonces, transactions };
+        let bytes = bincode::encode_to_vec(&saved, standard())
+            .map_err(|e| MempoolError::Storage(format!("Failed to encode mempool: {}", e)))?;
+        db.put_cf(Column::Mempool, SAVED_POOL_KEY, &bytes);
+        Ok(saved.transactions.len())
     }
 
-    /// Compute hash of a transaction
-    fn compute_tx_hash(&self, tx: &Transaction) -> String {
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{:?}", tx).as_bytes());
-        format!("{:x}", hasher.finalize())
+    /// Re-admit the transactions saved by `save`, returning how many were accepted
+    ///
+    /// Transactions with a receipt in `db` were included in a block since the
+    /// pool was saved; they are dropped and their senders' nonces advanced.
+    /// The rest go through the usual signature, nonce, fee market and limit
+    /// checks, and those rejected are dropped.
+    pub fn restore(&self, db: &Db) -> Result<usize, MempoolError> {
+        let Some(bytes) = db.get_cf(Column::Mempool, SAVED_POOL_KEY) else {
+            return Ok(0);
+        };
+        let (saved, _): (SavedPool, _) = bincode::decode_from_slice(&bytes, standard())
+            .map_err(|e| MempoolError::Storage(format!("Failed to decode saved mempool: {}", e)))?;
+        let (included, transactions): (Vec<Transaction>, Vec<Transaction>) =
+            saved.transactions.into_iter().partition(|tx| stored_receipt(db, &tx.hash()).is_some());
+        {
+            let mut pool = self.pool()?;
+            let next_nonces = included.iter().map(|tx| (tx.from.clone(), tx.nonce + 1));
+            for (account, nonce) in saved.account_nonces.into_iter().chain(next_nonces) {
+                let expected = pool.account_nonces.entry(account).or_insert(0);
+                *expected = (*expected).max(nonce);
+            }
+        }
+        let restored = transactions.into_iter().filter(|tx| self.insert(tx.clone()).is_ok()).count();
+        // Kept until restored, so a pool that fails to decode can be recovered
+        db.delete_cf(Column::Mempool, SAVED_POOL_KEY);
+        Ok(restored)
     }
 
     /// Get mempool statistics
-    pub fn stats(&self) -> Result<MempoolStats, String> {
-        let pending = self.pending.lock().map_err(|e| e.to_string())?;
-        let tx_count = pending.len();
-        let total_gas = pending
-            .iter()
-            .map(|tx| 21000) // Standard gas per transaction
-            .sum::<u64>();
+    pub fn stats(&self) -> Result<MempoolStats, MempoolError> {
+        let pool = self.pool()?;
+        let tx_count = pool.pending.len();
+        let total_gas = pool.pending.values().map(|entry| fee_market::gas_limit(&entry.tx)).sum::<u64>();
 
         Ok(MempoolStats {
             transaction_count: tx_count,
+            queued_count: pool.queued.len(),
             total_pending_gas: total_gas,
             max_capacity: self.max_size,
             utilization_percent: (tx_count as f64 / self.max_size as f64) * 100.0,
+            evictions: pool.eviction_counts.iter().map(|(reason, count)| (reason.as_str(), *count)).collect(),
         })
     }
 
-    /// Verify nonce ordering to prevent replay attacks
-    fn verify_nonce(&self, tx: &Transaction) -> Result<(), String> {
-        let nonces = self.account_nonces.lock().map_err(|e| e.to_string())?;
-        
-        // Get the highest nonce seen for this account (not seen yet starts at -1, represented as None)
-        // For first tx, we check if nonce is at least 0
-        if let Some(max_nonce_seen) = nonces.get(&tx.from) {
-            // Nonce must be greater than the highest nonce seen
-            if tx.nonce <= *max_nonce_seen {
-                return Err(format!(
-                    "Invalid nonce: expected higher than {}, got {}",
-                    max_nonce_seen, tx.nonce
-                ));
+    /// Verify Ed25519 signature on transaction, or the M-of-N signatures of
+    /// a multisig account
+    fn verify_transaction_signature(&self, tx: &Transaction) -> Result<(), MempoolError> {
+        if let Some(state) = &self.state {
+            let trie = state.read()?;
+            replay_protection::verify_transaction(&*trie, tx).map_err(MempoolError::WrongChain)?;
+            if let Some(config) = multisig::config(&*trie, &tx.from) {
+                return config.verify(tx).map_err(MempoolError::InvalidSignature);
             }
         }
-        // If account not seen before, any nonce >= 0 is allowed (which is always true for u64)
-        
-        Ok(())
-    }
 
-    /// Verify Ed25519 signature on transaction
-    fn verify_transaction_signature(&self, tx: &Transaction) -> Result<(), String> {
         // Skip verification for transactions without signature (for backward compatibility)
         if tx.signature.is_empty() || tx.public_key.is_empty() {
             return Ok(());
         }
 
-        // Compute the transaction hash for signing (without the signature field)
-        let mut tx_for_hash = tx.clone();
-        tx_for_hash.signature = vec![];
-        
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{:?}", tx_for_hash).as_bytes());
-        let tx_hash = hex_encode(hasher.finalize());
-
-        // Convert signature and public key from bytes to hex
-        let signature_hex = hex::encode(&tx.signature);
-        let public_key_hex = hex::encode(&tx.public_key);
-
-        // Verify the signature
-        crypto::verify_signature(tx_hash.as_bytes(), &signature_hex, &public_key_hex)
-            .and_then(|is_valid| {
-                if is_valid {
-                    Ok(())
-                } else {
-                    Err("Invalid transaction signature".to_string())
-                }
-            })
+        tx.verify_signature().map_err(MempoolError::InvalidSignature)
     }
 }
 
@@ -238,18 +1106,23 @@ impl Default for TransactionMempool {
 #[derive(Debug, Clone)]
 pub struct MempoolStats {
     pub transaction_count: usize,
+    /// Future-nonce transactions waiting for a gap to fill
+    pub queued_count: usize,
     pub total_pending_gas: u64,
     pub max_capacity: usize,
     pub utilization_percent: f64,
+    /// Evictions since startup, by reason
+    pub evictions: BTreeMap<&'static str, u64>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::TransactionPayload;
+    use crate::shard_coordinator::ShardCoordinator;
 
     fn create_test_tx(from: &str, to: &str, amount: u64) -> Transaction {
         Transaction {
+            chain_id: String::new(),
             from: from.to_string(),
             nonce: 0,
             gas_price: 1,
@@ -273,6 +1146,62 @@ mod tests {
         assert_eq!(pending.len(), 1);
     }
 
+    #[test]
+    fn test_signed_transaction_verification() {
+        let mempool = TransactionMempool::new();
+        let mut tx = create_test_tx("Alice", "Bob", 100);
+        tx.sign(&[3u8; 32]);
+        assert_eq!(mempool.add_transaction(tx.clone()).unwrap(), tx.hash());
+
+        let mut forged = tx.clone();
+        forged.nonce = 1;
+        forged.payload = TransactionPayload::Transfer { to: "Mallory".to_string(), amount: 100 };
+        assert!(matches!(mempool.add_transaction(forged), Err(MempoolError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_transaction_for_other_chain_rejected() {
+        let mut trie = MerklePatriciaTrie::new();
+        for (key, value) in replay_protection::genesis_state("aureon-testnet") {
+            trie.insert(key, value);
+        }
+        trie.insert(b"Alice".to_vec(), 100u64.to_le_bytes().to_vec());
+        let mempool = TransactionMempool::new().with_state(Arc::new(RwLock::new(trie)));
+        let mut replayed = Transaction { chain_id: "aureon-mainnet".to_string(), ..create_test_tx("Alice", "Bob", 100) };
+        replayed.sign(&[3u8; 32]);
+        assert!(matches!(mempool.add_transaction(replayed), Err(MempoolError::WrongChain(e)) if e.contains("aureon-mainnet")));
+
+        let mut tx = Transaction { chain_id: "aureon-testnet".to_string(), ..create_test_tx("Alice", "Bob", 100) };
+        tx.sign(&[3u8; 32]);
+        assert_eq!(mempool.add_transaction(tx.clone()).unwrap(), tx.hash());
+    }
+
+    #[test]
+    fn test_batch_inserts_valid_subset_with_per_item_results() {
+        let mempool = TransactionMempool::new();
+        let mut first = create_test_tx("Alice", "Bob", 100);
+        first.sign(&[3u8; 32]);
+        let mut forged = create_test_tx("Carol", "Bob", 100);
+        forged.sign(&[4u8; 32]);
+        forged.payload = TransactionPayload::Transfer { to: "Mallory".to_string(), amount: 100 };
+        let mut second = Transaction { nonce: 1, ..create_test_tx("Alice", "Dave", 50) };
+        second.sign(&[3u8; 32]);
+
+        // Later transactions of the batch see the earlier ones: the second
+        // nonce follows the first and the repeated first is a duplicate
+        let results = mempool
+            .add_transactions(vec![first.clone(), forged, second.clone(), first.clone()])
+            .unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &first.hash());
+        assert!(matches!(results[1], Err(MempoolError::InvalidSignature(_))));
+        assert_eq!(results[2].as_ref().unwrap(), &second.hash());
+        assert!(matches!(results[3], Err(MempoolError::Duplicate)));
+
+        let pending: Vec<String> = mempool.get_pending().unwrap().iter().map(Transaction::hash).collect();
+        assert_eq!(pending, vec![first.hash(), second.hash()]);
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 2);
+    }
+
     #[test]
     fn test_duplicate_rejection() {
         let mempool = TransactionMempool::new();
@@ -349,27 +1278,154 @@ mod tests {
         // Second transaction with same nonce rejected
         let result = mempool.add_transaction(tx2);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("nonce"));
+        assert!(matches!(result, Err(MempoolError::ReplacementUnderpriced { nonce: 0, .. })));
     }
 
     #[test]
     fn test_nonce_enforcement_ordering() {
-        // Test that lower nonces are rejected after higher nonce is accepted
+        // Test that nonces below the expected one are rejected
         let mempool = TransactionMempool::new();
-        
-        let mut tx1 = create_test_tx("Alice", "Bob", 100);
-        tx1.nonce = 5;
-        
-        let mut tx2 = create_test_tx("Alice", "Charlie", 50);
-        tx2.nonce = 3; // Lower nonce after higher nonce submitted
-        
-        // Higher nonce accepted first
-        assert!(mempool.add_transaction(tx1).is_ok());
-        
-        // Lower nonce rejected
-        let result = mempool.add_transaction(tx2);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("nonce"));
+
+        for nonce in 0..3 {
+            let mut tx = create_test_tx("Alice", "Bob", 100 + nonce);
+            tx.nonce = nonce;
+            assert!(mempool.add_transaction(tx).is_ok());
+        }
+
+        let mut stale = create_test_tx("Alice", "Charlie", 50);
+        stale.nonce = 1;
+        let result = mempool.add_transaction(stale);
+        assert!(matches!(result, Err(MempoolError::ReplacementUnderpriced { nonce: 1, .. })));
+    }
+
+    #[test]
+    fn test_future_nonce_queued_until_gap_fills() {
+        let mempool = TransactionMempool::new();
+
+        let mut tx2 = create_test_tx("Alice", "Bob", 20);
+        tx2.nonce = 2;
+        let mut tx1 = create_test_tx("Alice", "Bob", 10);
+        tx1.nonce = 1;
+        mempool.add_transaction(tx2.clone()).unwrap();
+        mempool.add_transaction(tx1).unwrap();
+        assert_eq!(mempool.size().unwrap(), 0);
+        assert_eq!(mempool.stats().unwrap().queued_count, 2);
+        assert!(mempool.contains(&tx2.hash()).unwrap());
+        assert!(mempool.add_transaction(tx2).is_err());
+
+        mempool.add_transaction(create_test_tx("Alice", "Bob", 5)).unwrap();
+        let nonces: Vec<u64> = mempool.get_pending().unwrap().iter().map(|tx| tx.nonce).collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert_eq!(mempool.stats().unwrap().queued_count, 0);
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_block_nonces_promote_queued() {
+        let mempool = TransactionMempool::new();
+        let mut queued = create_test_tx("Alice", "Bob", 10);
+        queued.nonce = 1;
+        mempool.add_transaction(queued).unwrap();
+
+        // Nonce 0 was included by another node's block
+        mempool.finalize_block_transactions(&[create_test_tx("Alice", "Carol", 5)]).unwrap();
+        assert_eq!(mempool.get_pending().unwrap()[0].nonce, 1);
+        assert_eq!(mempool.stats().unwrap().queued_count, 0);
+    }
+
+    #[test]
+    fn test_queue_limits() {
+        let mut first = create_test_tx("Alice", "Bob", 10);
+        first.nonce = 5;
+        let mut second = create_test_tx("Alice", "Bob", 10);
+        second.nonce = 6;
+
+        let mempool = TransactionMempool::new().with_limits(MempoolConfig {
+            max_queued_per_account: 1,
+            ..Default::default()
+        });
+        mempool.add_transaction(first.clone()).unwrap();
+        assert!(mempool.add_transaction(second).unwrap_err().code() == "too_many_queued");
+        assert_eq!(mempool.prune_queued().unwrap(), 0);
+
+        // A zero TTL expires queued transactions on the next prune
+        let mempool = TransactionMempool::new().with_limits(MempoolConfig {
+            queued_ttl_secs: 0,
+            ..Default::default()
+        });
+        mempool.add_transaction(first.clone()).unwrap();
+        assert_eq!(mempool.prune_queued().unwrap(), 1);
+        assert_eq!(mempool.stats().unwrap().queued_count, 0);
+        assert!(!mempool.contains(&first.hash()).unwrap());
+
+        // A full queue takes a transaction only if it outbids the cheapest queued one
+        let mempool = TransactionMempool::new().with_limits(MempoolConfig {
+            max_queued: 2,
+            ..Default::default()
+        });
+        let queued = |from: &str, gas_price: u64| Transaction {
+            nonce: 5,
+            gas_price,
+            ..create_test_tx(from, "Bob", 10)
+        };
+        let cheap = queued("Alice", 2);
+        mempool.add_transaction(cheap.clone()).unwrap();
+        mempool.add_transaction(queued("Carol", 3)).unwrap();
+        assert_eq!(mempool.add_transaction(queued("Dave", 2)).unwrap_err().code(), "queue_full");
+        mempool.add_transaction(queued("Erin", 4)).unwrap();
+        assert_eq!(mempool.stats().unwrap().queued_count, 2);
+        assert!(!mempool.contains(&cheap.hash()).unwrap());
+    }
+
+    #[test]
+    fn test_replacement_by_fee() {
+        let mempool = TransactionMempool::new();
+        let original = create_test_tx("Alice", "Bob", 100);
+        mempool.add_transaction(original.clone()).unwrap();
+
+        let mut underpriced = create_test_tx("Alice", "Carol", 100);
+        underpriced.gas_price = 1;
+        assert_eq!(mempool.add_transaction(underpriced), Err(MempoolError::ReplacementUnderpriced { nonce: 0, required: 2 }));
+
+        let mut replacement = create_test_tx("Alice", "Carol", 100);
+        replacement.gas_price = 2;
+        mempool.add_transaction(replacement.clone()).unwrap();
+        assert!(!mempool.contains(&original.hash()).unwrap());
+        assert_eq!(mempool.eviction_reason(&original.hash()).unwrap(), Some(EvictionReason::Replaced));
+        assert_eq!(mempool.get_pending().unwrap()[0].hash(), replacement.hash());
+        assert_eq!(mempool.size().unwrap(), 1);
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 1);
+
+        // Queued transactions can be replaced too
+        let mut queued = create_test_tx("Alice", "Bob", 1);
+        queued.nonce = 5;
+        queued.gas_price = 10;
+        mempool.add_transaction(queued).unwrap();
+        let mut bumped = create_test_tx("Alice", "Dave", 1);
+        bumped.nonce = 5;
+        bumped.gas_price = 11;
+        mempool.add_transaction(bumped.clone()).unwrap();
+        assert_eq!(mempool.stats().unwrap().queued_count, 1);
+        assert!(mempool.contains(&bumped.hash()).unwrap());
+    }
+
+    #[test]
+    fn test_pending_limit_per_account() {
+        let mempool = TransactionMempool::new().with_limits(MempoolConfig {
+            max_pending_per_account: 2,
+            ..Default::default()
+        });
+        for nonce in 0..2 {
+            let mut tx = create_test_tx("Alice", "Bob", 1);
+            tx.nonce = nonce;
+            mempool.add_transaction(tx).unwrap();
+        }
+        let mut third = create_test_tx("Alice", "Bob", 1);
+        third.nonce = 2;
+        assert!(mempool.add_transaction(third).unwrap_err().code() == "too_many_pending");
+
+        // Other senders are unaffected
+        assert!(mempool.add_transaction(create_test_tx("Bob", "Alice", 1)).is_ok());
     }
 
     #[test]
@@ -387,6 +1443,39 @@ mod tests {
         assert!(mempool.add_transaction(tx2).is_ok());
     }
 
+    #[test]
+    fn test_next_nonce() {
+        let mempool = TransactionMempool::new();
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 0);
+
+        let mut tx = create_test_tx("Alice", "Bob", 100);
+        tx.nonce = 0;
+        mempool.add_transaction(tx).unwrap();
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 1);
+
+        let mut next = create_test_tx("Alice", "Bob", 10);
+        next.nonce = mempool.next_nonce("Alice").unwrap();
+        assert!(mempool.add_transaction(next).is_ok());
+    }
+
+    #[test]
+    fn test_committed_nonce_excludes_pending_transactions() {
+        let mempool = TransactionMempool::new();
+        for nonce in 0..3 {
+            mempool.add_transaction(Transaction { nonce, ..create_test_tx("Alice", "Bob", 10) }).unwrap();
+        }
+        // A queued future nonce changes neither
+        mempool.add_transaction(Transaction { nonce: 5, ..create_test_tx("Alice", "Bob", 10) }).unwrap();
+        assert_eq!(mempool.committed_nonce("Alice").unwrap(), 0);
+        assert_eq!(mempool.get_account_nonce("Alice").unwrap(), 3);
+
+        let included = mempool.take_transactions(2).unwrap();
+        mempool.finalize_block_transactions(&included).unwrap();
+        assert_eq!(mempool.committed_nonce("Alice").unwrap(), 2);
+        assert_eq!(mempool.get_account_nonce("Alice").unwrap(), 3);
+        assert_eq!(mempool.committed_nonce("Carol").unwrap(), 0);
+    }
+
     #[test]
     fn test_nonce_finalization() {
         // Test that nonces are incremented when block is finalized
@@ -412,6 +1501,137 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_accepted_transaction_published() {
+        let events = EventBus::new(8);
+        let mut rx = events.subscribe();
+        let mempool = TransactionMempool::new().with_event_bus(events);
+
+        let tx_hash = mempool.add_transaction(create_test_tx("Alice", "Bob", 100)).unwrap();
+        match rx.try_recv().unwrap() {
+            NodeEvent::Transactions(event) => {
+                assert_eq!(event.tx_hash, tx_hash);
+                assert_eq!(event.from, "Alice");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // Rejected duplicates are not announced
+        assert!(mempool.add_transaction(create_test_tx("Alice", "Bob", 100)).is_err());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_min_gas_price_rejected() {
+        let mempool = TransactionMempool::new().with_fee_market(FeeMarketConfig {
+            min_gas_price: 5,
+            block_gas_limit: 1_000_000,
+        });
+        let mut tx = create_test_tx("Alice", "Bob", 100);
+        tx.gas_price = 4;
+
+        let result = mempool.add_transaction(tx);
+        assert!(matches!(result, Err(MempoolError::FeeMarket(e)) if e.contains("Gas price")));
+        assert_eq!(mempool.size().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_governance_votes_rejected() {
+        let mempool = TransactionMempool::new();
+        let tx = Transaction::governance_vote("Alice".into(), 1, community_governance::VoteChoice::Yes);
+
+        let result = mempool.add_transaction(tx);
+        assert!(matches!(result, Err(MempoolError::Unsupported(_))));
+        assert_eq!(mempool.size().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_priority_ordering() {
+        let mempool = TransactionMempool::new();
+        let mut cheap = create_test_tx("Alice", "Bob", 100);
+        cheap.gas_price = 1;
+        let mut rich = create_test_tx("Bob", "Charlie", 50);
+        rich.gas_price = 10;
+
+        mempool.add_transaction(cheap).unwrap();
+        mempool.add_transaction(rich).unwrap();
+
+        let txs = mempool.take_transactions(1).unwrap();
+        assert_eq!(txs[0].from, "Bob");
+        assert_eq!(mempool.get_pending().unwrap()[0].from, "Alice");
+    }
+
+    #[test]
+    fn test_full_mempool_evicts_cheapest() {
+        let mempool = TransactionMempool::with_capacity(2);
+        mempool.add_transaction(create_test_tx("Alice", "Bob", 100)).unwrap();
+        let cheapest = mempool.add_transaction(create_test_tx("Bob", "Charlie", 50)).unwrap();
+
+        let mut rich = create_test_tx("Charlie", "Dave", 25);
+        rich.gas_price = 3;
+        let rich_hash = mempool.add_transaction(rich).unwrap();
+
+        let pending = mempool.get_pending().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(mempool.contains(&rich_hash).unwrap());
+        // The most recent of the cheapest transactions is evicted
+        assert_eq!(pending[0].from, "Alice");
+        assert_eq!(mempool.eviction_reason(&cheapest).unwrap(), Some(EvictionReason::Outbid));
+        assert_eq!(mempool.stats().unwrap().evictions.get("outbid"), Some(&1));
+    }
+
+    #[test]
+    fn test_block_gas_limit_selection() {
+        let mempool = TransactionMempool::new().with_fee_market(FeeMarketConfig {
+            min_gas_price: 1,
+            block_gas_limit: 2 * fee_market::BASE_TX_GAS,
+        });
+        for (from, price) in [("Alice", 1), ("Bob", 3), ("Charlie", 2)] {
+            let mut tx = create_test_tx(from, "Dave", 10);
+            tx.gas_price = price;
+            mempool.add_transaction(tx).unwrap();
+        }
+
+        let txs = mempool.take_block_transactions(100, ReservedSpace::default()).unwrap();
+        let senders: Vec<&str> = txs.iter().map(|tx| tx.from.as_str()).collect();
+        assert_eq!(senders, vec!["Bob", "Charlie"]);
+        assert_eq!(mempool.size().unwrap(), 1);
+        assert_eq!(mempool.stats().unwrap().total_pending_gas, fee_market::BASE_TX_GAS);
+    }
+
+    #[test]
+    fn test_shard_routing() {
+        let coordinator = ShardCoordinator::with_shard_count(2);
+        let shards = Arc::new(RwLock::new(ShardMap::new(coordinator.clone())));
+        let mempool = TransactionMempool::new().with_shards(shards);
+        for from in ["Alice", "Bob", "Charlie", "Dave"] {
+            mempool.add_transaction(create_test_tx(from, "Erin", 10)).unwrap();
+        }
+
+        let counts = mempool.pending_by_shard().unwrap();
+        assert_eq!(counts.values().sum::<usize>(), 4);
+        for shard in coordinator.all_shards() {
+            let txs = mempool.take_shard_transactions(shard, 100, ReservedSpace::default()).unwrap();
+            assert_eq!(txs.len(), counts.get(&shard).copied().unwrap_or(0));
+            assert!(txs.iter().all(|tx| coordinator.get_shard(&tx.from) == shard));
+        }
+        assert_eq!(mempool.size().unwrap(), 0);
+        assert!(TransactionMempool::new().take_shard_transactions(ShardId(0), 1, ReservedSpace::default()).is_err());
+    }
+
+    #[test]
+    fn test_fee_market_update_applies_to_new_transactions() {
+        let mempool = TransactionMempool::new();
+        mempool.set_fee_market(FeeMarketConfig {
+            min_gas_price: 5,
+            block_gas_limit: fee_market::BASE_TX_GAS,
+        });
+
+        let result = mempool.add_transaction(create_test_tx("Alice", "Bob", 10));
+        assert!(result.unwrap_err().to_string().contains("below minimum 5"));
+        assert_eq!(mempool.fee_market().block_gas_limit, fee_market::BASE_TX_GAS);
+    }
+
     #[test]
     fn test_nonce_different_accounts() {
         // Test that nonces are tracked per account
@@ -427,4 +1647,195 @@ mod tests {
         assert!(mempool.add_transaction(tx1).is_ok());
         assert!(mempool.add_transaction(tx2).is_ok());
     }
+
+    #[test]
+    fn test_saved_pool_restored_after_restart() {
+        let path = std::env::temp_dir().join(format!("aureon_mempool_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+
+        let mempool = TransactionMempool::new();
+        let alice: Vec<Transaction> = (0..3)
+            .map(|nonce| Transaction { nonce, ..create_test_tx("Alice", "Bob", 10) })
+            .collect();
+        for tx in &alice {
+            mempool.add_transaction(tx.clone()).unwrap();
+        }
+        mempool.finalize_block_transactions(&alice[..1]).unwrap();
+        mempool.add_transaction(Transaction { nonce: 2, ..create_test_tx("Bob", "Alice", 5) }).unwrap();
+        assert_eq!(mempool.save(&db).unwrap(), 3);
+
+        let restarted = TransactionMempool::new();
+        assert_eq!(restarted.restore(&db).unwrap(), 3);
+        assert_eq!(restarted.size().unwrap(), 2);
+        assert_eq!(restarted.stats().unwrap().queued_count, 1);
+        assert_eq!(restarted.next_nonce("Alice").unwrap(), 3);
+        // The included transaction cannot be replayed, and the pool is restored once
+        assert!(restarted.add_transaction(alice[0].clone()).is_err());
+        assert_eq!(TransactionMempool::new().restore(&db).unwrap(), 0);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_corrupt_saved_pool_is_kept() {
+        let path = std::env::temp_dir().join(format!("aureon_mempool_corrupt_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+
+        let mempool = TransactionMempool::new();
+        mempool.add_transaction(create_test_tx("Alice", "Bob", 10)).unwrap();
+        mempool.save(&db).unwrap();
+        let saved = db.get_cf(Column::Mempool, SAVED_POOL_KEY).unwrap();
+        db.put_cf(Column::Mempool, SAVED_POOL_KEY, &saved[..saved.len() / 2]);
+
+        let restarted = TransactionMempool::new();
+        assert!(matches!(restarted.restore(&db), Err(MempoolError::Storage(_))));
+        assert_eq!(db.get_cf(Column::Mempool, SAVED_POOL_KEY).unwrap(), saved[..saved.len() / 2]);
+        assert_eq!(restarted.size().unwrap(), 0);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_restore_drops_transactions_included_since_save() {
+        use crate::types::TransactionReceipt;
+
+        let path = std::env::temp_dir().join(format!("aureon_mempool_included_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+
+        let mempool = TransactionMempool::new();
+        let alice: Vec<Transaction> = (0..3)
+            .map(|nonce| Transaction { nonce, ..create_test_tx("Alice", "Bob", 10) })
+            .collect();
+        for tx in &alice {
+            mempool.add_transaction(tx.clone()).unwrap();
+        }
+        assert_eq!(mempool.save(&db).unwrap(), 3);
+
+        // The first two transactions land in a block before the node restarts
+        for tx in &alice[..2] {
+            let receipt = TransactionReceipt {
+                tx_hash: tx.hash(),
+                success: true,
+                error: None,
+                gas_used: 0,
+                fee_burned: 0,
+                tip: 0,
+                logs: vec![],
+                post_state_root: vec![],
+            };
+            db.put_cf(Column::Receipts, tx.hash().as_bytes(), &serde_json::to_vec(&receipt).unwrap());
+        }
+        let restarted = TransactionMempool::new();
+        assert_eq!(restarted.restore(&db).unwrap(), 1);
+        assert_eq!(restarted.get_pending().unwrap()[0].hash(), alice[2].hash());
+        assert_eq!(restarted.next_nonce("Alice").unwrap(), 3);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_revalidate_evicts_transactions_state_no_longer_allows() {
+        use crate::types::TransactionReceipt;
+
+        let path = std::env::temp_dir().join(format!("aureon_mempool_revalidate_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"Alice".to_vec(), 210u64.to_le_bytes().to_vec());
+        trie.insert(b"Bob".to_vec(), 5u64.to_le_bytes().to_vec());
+        trie.insert(b"Carol".to_vec(), 100u64.to_le_bytes().to_vec());
+        let state = Arc::new(RwLock::new(trie));
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let mempool = TransactionMempool::new().with_state(state.clone()).with_metrics(metrics.clone());
+
+        let alice: Vec<Transaction> = [100, 100, 10]
+            .into_iter()
+            .enumerate()
+            .map(|(nonce, amount)| Transaction { nonce: nonce as u64, ..create_test_tx("Alice", "Bob", amount) })
+            .collect();
+        let carol: Vec<Transaction> = (0..2)
+            .map(|nonce| Transaction { nonce, ..create_test_tx("Carol", "Bob", 10) })
+            .collect();
+        let broke = create_test_tx("Bob", "Alice", 5);
+        for tx in alice.iter().chain(&carol).chain([&broke]) {
+            mempool.add_transaction(tx.clone()).unwrap();
+        }
+
+        // Alice and Bob spend part of their balances elsewhere
+        state.write().unwrap().insert(b"Alice".to_vec(), 150u64.to_le_bytes().to_vec());
+        state.write().unwrap().insert(b"Bob".to_vec(), 0u64.to_le_bytes().to_vec());
+
+        // Carol's first transaction lands in a block the mempool did not see
+        let receipt = TransactionReceipt {
+            tx_hash: carol[0].hash(),
+            success: true,
+            error: None,
+            gas_used: 0,
+            fee_burned: 0,
+            tip: 0,
+            logs: vec![],
+            post_state_root: vec![],
+        };
+        db.put_cf(Column::Receipts, carol[0].hash().as_bytes(), &serde_json::to_vec(&receipt).unwrap());
+
+        assert_eq!(mempool.revalidate(&db).unwrap(), 2);
+        let pending: Vec<String> = mempool.get_pending().unwrap().iter().map(Transaction::hash).collect();
+        assert_eq!(pending, vec![alice[0].hash(), carol[1].hash()]);
+        assert_eq!(mempool.eviction_reason(&alice[1].hash()).unwrap(), Some(EvictionReason::InsufficientBalance));
+        assert_eq!(mempool.eviction_reason(&broke.hash()).unwrap(), Some(EvictionReason::InsufficientBalance));
+        assert_eq!(mempool.eviction_reason(&carol[0].hash()).unwrap(), None);
+        assert_eq!(mempool.next_nonce("Carol").unwrap(), 2);
+
+        // Alice's last transaction waits for the evicted nonce to be resubmitted
+        assert_eq!(mempool.stats().unwrap().queued_count, 1);
+        assert_eq!(mempool.next_nonce("Alice").unwrap(), 1);
+        let resubmitted = Transaction { nonce: 1, ..create_test_tx("Alice", "Bob", 40) };
+        mempool.add_transaction(resubmitted).unwrap();
+        assert_eq!(mempool.size().unwrap(), 4);
+        assert_eq!(mempool.revalidate(&db).unwrap(), 0);
+        assert!(metrics.export().unwrap().contains("mempool_evictions_total{reason=\"insufficient_balance\"} 2"));
+
+        // Pending transactions expire after the pending TTL
+        let expiring = TransactionMempool::new().with_limits(MempoolConfig { pending_ttl_secs: 0, ..Default::default() });
+        expiring.add_transaction(alice[0].clone()).unwrap();
+        assert_eq!(expiring.revalidate(&db).unwrap(), 1);
+        assert_eq!(expiring.eviction_reason(&alice[0].hash()).unwrap(), Some(EvictionReason::Expired));
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_admission_requires_balance_for_pooled_transactions() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"Alice".to_vec(), 100u64.to_le_bytes().to_vec());
+        let state = Arc::new(RwLock::new(trie));
+        let mempool = TransactionMempool::new().with_state(state.clone());
+
+        mempool.add_transaction(create_test_tx("Alice", "Bob", 60)).unwrap();
+        let overdraft = Transaction { nonce: 1, ..create_test_tx("Alice", "Bob", 50) };
+        assert!(mempool.add_transaction(overdraft).unwrap_err().code() == "insufficient_balance");
+        mempool.add_transaction(Transaction { nonce: 1, ..create_test_tx("Alice", "Bob", 40) }).unwrap();
+
+        // Queued transactions count the transactions before them too
+        let queued = Transaction { nonce: 3, ..create_test_tx("Alice", "Bob", 1) };
+        assert!(mempool.add_transaction(queued).is_err());
+        assert!(mempool.add_transaction(create_test_tx("Bob", "Alice", 1)).is_err());
+
+        // With fee burning, the maximum fee must be covered as well
+        let mempool = TransactionMempool::new()
+            .with_state(state)
+            .with_base_fee(BaseFeeSchedule::new(&crate::config::EconomyConfig {
+                fee_burning: true,
+                ..Default::default()
+            }));
+        assert!(mempool.add_transaction(create_test_tx("Alice", "Bob", 100)).unwrap_err().code() == "insufficient_balance");
+    }
 }
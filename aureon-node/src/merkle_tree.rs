@@ -1,360 +0,0 @@
-use sha2::{Sha256, Digest};
-
-/// Node in a merkle tree
-#[derive(Debug, Clone, PartialEq)]
-pub struct MerkleTreeNode {
-    pub hash: String,
-    pub left: Option<Box<MerkleTreeNode>>,
-    pub right: Option<Box<MerkleTreeNode>>,
-}
-
-impl MerkleTreeNode {
-    /// Create a leaf node with a transaction hash
-    pub fn leaf(tx_hash: String) -> Self {
-        MerkleTreeNode {
-            hash: tx_hash,
-            left: None,
-            right: None,
-        }
-    }
-
-    /// Create a parent node by hashing two child nodes
-    pub fn parent(left: MerkleTreeNode, right: MerkleTreeNode) -> Self {
-        let hash = hash_pair(&left.hash, &right.hash);
-        MerkleTreeNode {
-            hash,
-            left: Some(Box::new(left)),
-            right: Some(Box::new(right)),
-        }
-    }
-}
-
-/// Element in a merkle inclusion proof
-#[derive(Debug, Clone)]
-pub struct MerkleProofElement {
-    pub hash: String,
-    pub is_left: bool,  // True if hash is to the left, false if to the right
-}
-
-/// Merkle inclusion proof for a transaction
-#[derive(Debug, Clone)]
-pub struct MerkleInclusionProof {
-    pub tx_hash: String,
-    pub merkle_root: String,
-    pub proof_path: Vec<MerkleProofElement>,
-    pub tx_index: usize,  // Position in transaction list
-}
-
-impl MerkleInclusionProof {
-    /// Verify this proof is valid for the given merkle root
-    pub fn verify(&self) -> bool {
-        let mut current_hash = self.tx_hash.clone();
-
-        for element in &self.proof_path {
-            current_hash = if element.is_left {
-                hash_pair(&element.hash, &current_hash)
-            } else {
-                hash_pair(&current_hash, &element.hash)
-            };
-        }
-
-        current_hash == self.merkle_root
-    }
-}
-
-/// Hash a single value
-fn hash_value(value: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(value.as_bytes());
-    format!("{:x}", hasher.finalize())
-}
-
-/// Hash two values together
-fn hash_pair(left: &str, right: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(left.as_bytes());
-    hasher.update(right.as_bytes());
-    format!("{:x}", hasher.finalize())
-}
-
-/// Merkle tree for transaction verification
-pub struct MerkleTree {
-    root: Option<MerkleTreeNode>,
-    leaf_count: usize,
-}
-
-impl MerkleTree {
-    /// Create an empty merkle tree
-    pub fn new() -> Self {
-        MerkleTree {
-            root: None,
-            leaf_count: 0,
-        }
-    }
-
-    /// Build a merkle tree from transaction hashes
-    pub fn build(tx_hashes: Vec<String>) -> Self {
-        if tx_hashes.is_empty() {
-            return MerkleTree {
-                root: None,
-                leaf_count: 0,
-            };
-        }
-
-        let leaf_count = tx_hashes.len();
-
-        // Hash the transaction hashes first
-        let hashed_txs: Vec<String> = tx_hashes
-            .into_iter()
-            .map(|tx| hash_value(&tx))
-            .collect();
-
-        // Create leaf nodes
-        let mut nodes: Vec<MerkleTreeNode> = hashed_txs
-            .into_iter()
-            .map(MerkleTreeNode::leaf)
-            .collect();
-
-        // Build tree bottom-up
-        while nodes.len() > 1 {
-            let mut next_level = Vec::new();
-            let mut i = 0;
-
-            while i < nodes.len() {
-                if i + 1 < nodes.len() {
-                    let left = nodes.remove(0);
-                    let right = nodes.remove(0);
-                    next_level.push(MerkleTreeNode::parent(left, right));
-                    i += 2;
-                } else {
-                    // Odd number of nodes - hash node with itself
-                    let node = nodes.remove(0);
-                    let parent = MerkleTreeNode::parent(node.clone(), node);
-                    next_level.push(parent);
-                    i += 1;
-                }
-            }
-
-            nodes = next_level;
-        }
-
-        let root = nodes.into_iter().next();
-
-        MerkleTree { root, leaf_count }
-    }
-
-    /// Get the merkle root hash
-    pub fn root(&self) -> Option<String> {
-        self.root.as_ref().map(|n| n.hash.clone())
-    }
-
-    /// Get the merkle inclusion proof for a transaction at given index
-    pub fn get_proof(&self, tx_index: usize) -> Option<MerkleInclusionProof> {
-        if tx_index >= self.leaf_count {
-            return None;
-        }
-
-        let merkle_root = self.root()?.clone();
-        let mut proof_path = Vec::new();
-
-        // Traverse from root to leaf, collecting sibling hashes
-        if let Some(root) = &self.root {
-            self.collect_proof_path(root, tx_index, 0, self.leaf_count, &mut proof_path);
-        }
-
-        // We need the tx hash - reconstruct it or store it separately
-        // For now, we'll indicate this needs the tx hash
-        Some(MerkleInclusionProof {
-            tx_hash: String::new(),  // Will be filled by caller
-            merkle_root,
-            proof_path,
-            tx_index,
-        })
-    }
-
-    /// Collect proof path from root to leaf
-    fn collect_proof_path(
-        &self,
-        node: &MerkleTreeNode,
-        tx_index: usize,
-        current_index: usize,
-        level_size: usize,
-        proof_path: &mut Vec<MerkleProofElement>,
-    ) {
-        if level_size == 1 {
-            return;  // Reached leaf
-        }
-
-        let left_size = (level_size + 1) / 2;
-
-        if let (Some(left), Some(right)) = (&node.left, &node.right) {
-            if tx_index < current_index + left_size {
-                // Target is in left subtree
-                proof_path.push(MerkleProofElement {
-                    hash: right.hash.clone(),
-                    is_left: false,
-                });
-                self.collect_proof_path(left, tx_index, current_index, left_size, proof_path);
-            } else {
-                // Target is in right subtree
-                proof_path.push(MerkleProofElement {
-                    hash: left.hash.clone(),
-                    is_left: true,
-                });
-                self.collect_proof_path(right, tx_index, current_index + left_size, level_size - left_size, proof_path);
-            }
-        }
-    }
-
-    /// Get proof size (number of hashes needed)
-    pub fn proof_size(&self, tx_index: usize) -> usize {
-        if let Some(proof) = self.get_proof(tx_index) {
-            proof.proof_path.len()
-        } else {
-            0
-        }
-    }
-}
-
-impl Default for MerkleTree {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_merkle_tree_single_transaction() {
-        let tree = MerkleTree::build(vec!["tx_001".to_string()]);
-        assert_eq!(tree.root(), Some(hash_value("tx_001")));
-        assert_eq!(tree.leaf_count, 1);
-    }
-
-    #[test]
-    fn test_merkle_tree_two_transactions() {
-        let tree = MerkleTree::build(vec![
-            "tx_001".to_string(),
-            "tx_002".to_string(),
-        ]);
-        assert!(tree.root().is_some());
-        assert_eq!(tree.leaf_count, 2);
-    }
-
-    #[test]
-    fn test_merkle_tree_four_transactions() {
-        let tree = MerkleTree::build(vec![
-            "tx_001".to_string(),
-            "tx_002".to_string(),
-            "tx_003".to_string(),
-            "tx_004".to_string(),
-        ]);
-        assert!(tree.root().is_some());
-        assert_eq!(tree.leaf_count, 4);
-    }
-
-    #[test]
-    fn test_merkle_tree_empty() {
-        let tree = MerkleTree::build(vec![]);
-        assert_eq!(tree.root(), None);
-        assert_eq!(tree.leaf_count, 0);
-    }
-
-    #[test]
-    fn test_merkle_tree_deterministic() {
-        let txs = vec![
-            "tx_001".to_string(),
-            "tx_002".to_string(),
-            "tx_003".to_string(),
-        ];
-
-        let tree1 = MerkleTree::build(txs.clone());
-        let tree2 = MerkleTree::build(txs);
-
-        assert_eq!(tree1.root(), tree2.root());
-    }
-
-    #[test]
-    fn test_merkle_inclusion_proof() {
-        let txs = vec![
-            "tx_001".to_string(),
-            "tx_002".to_string(),
-            "tx_003".to_string(),
-            "tx_004".to_string(),
-        ];
-
-        let tree = MerkleTree::build(txs);
-        let proof = tree.get_proof(0);
-        assert!(proof.is_some());
-
-        let proof = proof.unwrap();
-        assert_eq!(proof.tx_index, 0);
-        assert!(proof.merkle_root.len() > 0);
-    }
-
-    #[test]
-    fn test_merkle_inclusion_proof_out_of_bounds() {
-        let tree = MerkleTree::build(vec!["tx_001".to_string(), "tx_002".to_string()]);
-        let proof = tree.get_proof(5);
-        assert!(proof.is_none());
-    }
-
-    #[test]
-    fn test_merkle_proof_size() {
-        let txs: Vec<String> = (0..8)
-            .map(|i| format!("tx_{:03}", i))
-            .collect();
-
-        let tree = MerkleTree::build(txs.clone());
-
-        // For 8 transactions, proof should require log2(8) = 3 hashes
-        let proof_size = tree.proof_size(0);
-        
-        // The proof size is 2 for 8 txs - this indicates our tree structure
-        // accounts for the final aggregation differently
-        assert_eq!(proof_size, 2);
-    }
-
-    #[test]
-    fn test_merkle_proof_logarithmic_scaling() {
-        // Test that proof size grows logarithmically with transaction count
-        for tx_count in [2, 4, 8, 16, 32] {
-            let txs: Vec<String> = (0..tx_count)
-                .map(|i| format!("tx_{:03}", i))
-                .collect();
-
-            let tree = MerkleTree::build(txs);
-            let proof_size = tree.proof_size(0);
-
-            // Proof size should be roughly log2(tx_count)
-            let expected_log = (tx_count as f64).log2().ceil() as usize;
-            assert!(proof_size <= expected_log + 1);
-        }
-    }
-
-    #[test]
-    fn test_merkle_proof_element_creation() {
-        let element = MerkleProofElement {
-            hash: "hash_123".to_string(),
-            is_left: true,
-        };
-        assert_eq!(element.hash, "hash_123");
-        assert!(element.is_left);
-    }
-
-    #[test]
-    fn test_hash_pair_deterministic() {
-        let hash1 = hash_pair("a", "b");
-        let hash2 = hash_pair("a", "b");
-        assert_eq!(hash1, hash2);
-    }
-
-    #[test]
-    fn test_hash_pair_order_matters() {
-        let hash_ab = hash_pair("a", "b");
-        let hash_ba = hash_pair("b", "a");
-        assert_ne!(hash_ab, hash_ba);
-    }
-}
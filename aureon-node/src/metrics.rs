@@ -1,8 +1,9 @@
 use prometheus::{
-    Counter, GaugeVec, HistogramOpts, HistogramVec, IntCounter,
+    exponential_buckets, Counter, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter,
     IntCounterVec, IntGauge, Opts, Registry, TextEncoder, Encoder,
 };
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Prometheus metrics registry and counters
 #[derive(Clone)]
@@ -12,13 +13,19 @@ pub struct Metrics {
     // Block metrics
     pub blocks_produced: IntCounter,
     pub blocks_received: IntCounter,
+    /// Competing PoW blocks kept as ommer candidates
+    pub stale_blocks: IntCounter,
     pub block_production_time: HistogramVec,
+    pub block_stage_time: HistogramVec,
+    pub block_propagation_time: Histogram,
+    pub block_gas_used: HistogramVec,
 
     // Transaction metrics
     pub transactions_submitted: IntCounter,
     pub transactions_processed: IntCounter,
     pub transactions_failed: IntCounter,
     pub mempool_size: IntGauge,
+    pub mempool_evictions: IntCounterVec,
 
     // Consensus metrics
     pub consensus_rounds: IntCounter,
@@ -31,6 +38,15 @@ pub struct Metrics {
     pub messages_sent: IntCounterVec,
     pub messages_received: IntCounterVec,
     pub peer_heights: GaugeVec,
+    pub peer_penalties: IntCounterVec,
+    pub peer_bans: IntCounter,
+    pub banned_peers: IntGauge,
+
+    // Sync metrics
+    pub sync_target_height: IntGauge,
+    pub sync_blocks_received: IntCounter,
+    pub sync_blocks_applied: IntCounter,
+    pub syncing: IntGauge,
 
     // State metrics
     pub chain_height: IntGauge,
@@ -41,6 +57,7 @@ pub struct Metrics {
     pub http_requests: IntCounterVec,
     pub http_request_duration: HistogramVec,
     pub http_errors: IntCounterVec,
+    pub api_requests_throttled: IntCounter,
 
     // Smart contract metrics
     pub contracts_deployed: IntCounter,
@@ -52,6 +69,9 @@ pub struct Metrics {
     pub db_operations: IntCounterVec,
     pub db_operation_time: HistogramVec,
     pub db_key_count: IntGauge,
+    pub pruned_blocks: IntCounter,
+    pub pruned_bytes: IntCounter,
+    pub state_cache_lookups: IntCounterVec,
 }
 
 impl Metrics {
@@ -62,10 +82,25 @@ impl Metrics {
         // Block metrics
         let blocks_produced = IntCounter::new("blocks_produced_total", "Total blocks produced")?;
         let blocks_received = IntCounter::new("blocks_received_total", "Total blocks received")?;
+        let stale_blocks = IntCounter::new("stale_blocks_total", "Competing blocks that lost the fork choice")?;
         let block_production_time = HistogramVec::new(
             HistogramOpts::new("block_production_time_seconds", "Block production time"),
             &["type"],
         )?;
+        let block_stage_time = HistogramVec::new(
+            HistogramOpts::new("block_production_stage_seconds", "Time spent in each stage of producing a block")
+                .buckets(exponential_buckets(0.0001, 4.0, 10)?),
+            &["stage"],
+        )?;
+        let block_propagation_time = Histogram::with_opts(HistogramOpts::new(
+            "block_propagation_seconds",
+            "Time from the start of a received block's slot until it was imported",
+        ))?;
+        let block_gas_used = HistogramVec::new(
+            HistogramOpts::new("block_gas_used", "Gas used per block")
+                .buckets(exponential_buckets(21_000.0, 2.0, 10)?),
+            &["source"],
+        )?;
 
         // Transaction metrics
         let transactions_submitted =
@@ -75,6 +110,10 @@ impl Metrics {
         let transactions_failed =
             IntCounter::new("transactions_failed_total", "Total failed transactions")?;
         let mempool_size = IntGauge::new("mempool_size", "Current mempool size")?;
+        let mempool_evictions = IntCounterVec::new(
+            Opts::new("mempool_evictions_total", "Transactions evicted from the mempool by reason"),
+            &["reason"],
+        )?;
 
         // Consensus metrics
         let consensus_rounds =
@@ -101,6 +140,21 @@ impl Metrics {
             Opts::new("peer_heights", "Height of connected peers"),
             &["peer_id"],
         )?;
+        let peer_penalties = IntCounterVec::new(
+            Opts::new("peer_penalties_total", "Peer score penalties by misbehavior"),
+            &["reason"],
+        )?;
+        let peer_bans = IntCounter::new("peer_bans_total", "Total peers banned for low reputation")?;
+        let banned_peers = IntGauge::new("banned_peers", "Number of currently banned peer addresses")?;
+
+        // Sync metrics
+        let sync_target_height =
+            IntGauge::new("sync_target_height", "Height the node last requested blocks up to")?;
+        let sync_blocks_received =
+            IntCounter::new("sync_blocks_received_total", "Total blocks received in sync responses")?;
+        let sync_blocks_applied =
+            IntCounter::new("sync_blocks_applied_total", "Total synced blocks imported into the chain")?;
+        let syncing = IntGauge::new("node_syncing", "1 while the node is catching up with its peers")?;
 
         // State metrics
         let chain_height = IntGauge::new("chain_height", "Current blockchain height")?;
@@ -121,6 +175,10 @@ impl Metrics {
             Opts::new("http_errors_total", "Total HTTP errors"),
             &["path", "status"],
         )?;
+        let api_requests_throttled = IntCounter::new(
+            "api_requests_throttled_total",
+            "API requests refused by the per-client rate limit",
+        )?;
 
         // Smart contract metrics
         let contracts_deployed =
@@ -144,16 +202,28 @@ impl Metrics {
             &["type"],
         )?;
         let db_key_count = IntGauge::new("db_key_count", "Number of keys in database")?;
+        let pruned_blocks = IntCounter::new("pruned_blocks_total", "Total historical blocks pruned")?;
+        let pruned_bytes =
+            IntCounter::new("pruned_bytes_total", "Total bytes reclaimed by pruning")?;
+        let state_cache_lookups = IntCounterVec::new(
+            Opts::new("state_cache_lookups_total", "Account cache lookups by result"),
+            &["result"],
+        )?;
 
         // Register all metrics
         registry.register(Box::new(blocks_produced.clone()))?;
         registry.register(Box::new(blocks_received.clone()))?;
+        registry.register(Box::new(stale_blocks.clone()))?;
         registry.register(Box::new(block_production_time.clone()))?;
+        registry.register(Box::new(block_stage_time.clone()))?;
+        registry.register(Box::new(block_propagation_time.clone()))?;
+        registry.register(Box::new(block_gas_used.clone()))?;
 
         registry.register(Box::new(transactions_submitted.clone()))?;
         registry.register(Box::new(transactions_processed.clone()))?;
         registry.register(Box::new(transactions_failed.clone()))?;
         registry.register(Box::new(mempool_size.clone()))?;
+        registry.register(Box::new(mempool_evictions.clone()))?;
 
         registry.register(Box::new(consensus_rounds.clone()))?;
         registry.register(Box::new(consensus_round_time.clone()))?;
@@ -164,6 +234,14 @@ impl Metrics {
         registry.register(Box::new(messages_sent.clone()))?;
         registry.register(Box::new(messages_received.clone()))?;
         registry.register(Box::new(peer_heights.clone()))?;
+        registry.register(Box::new(peer_penalties.clone()))?;
+        registry.register(Box::new(peer_bans.clone()))?;
+        registry.register(Box::new(banned_peers.clone()))?;
+
+        registry.register(Box::new(sync_target_height.clone()))?;
+        registry.register(Box::new(sync_blocks_received.clone()))?;
+        registry.register(Box::new(sync_blocks_applied.clone()))?;
+        registry.register(Box::new(syncing.clone()))?;
 
         registry.register(Box::new(chain_height.clone()))?;
         registry.register(Box::new(state_root_updates.clone()))?;
@@ -172,6 +250,7 @@ impl Metrics {
         registry.register(Box::new(http_requests.clone()))?;
         registry.register(Box::new(http_request_duration.clone()))?;
         registry.register(Box::new(http_errors.clone()))?;
+        registry.register(Box::new(api_requests_throttled.clone()))?;
 
         registry.register(Box::new(contracts_deployed.clone()))?;
         registry.register(Box::new(contract_invocations.clone()))?;
@@ -181,16 +260,24 @@ impl Metrics {
         registry.register(Box::new(db_operations.clone()))?;
         registry.register(Box::new(db_operation_time.clone()))?;
         registry.register(Box::new(db_key_count.clone()))?;
+        registry.register(Box::new(pruned_blocks.clone()))?;
+        registry.register(Box::new(pruned_bytes.clone()))?;
+        registry.register(Box::new(state_cache_lookups.clone()))?;
 
         Ok(Metrics {
             registry: Arc::new(registry),
             blocks_produced,
             blocks_received,
+            stale_blocks,
             block_production_time,
+            block_stage_time,
+            block_propagation_time,
+            block_gas_used,
             transactions_submitted,
             transactions_processed,
             transactions_failed,
             mempool_size,
+            mempool_evictions,
             consensus_rounds,
             consensus_round_time,
             pow_difficulty,
@@ -199,12 +286,20 @@ impl Metrics {
             messages_sent,
             messages_received,
             peer_heights,
+            peer_penalties,
+            peer_bans,
+            banned_peers,
+            sync_target_height,
+            sync_blocks_received,
+            sync_blocks_applied,
+            syncing,
             chain_height,
             state_root_updates,
             account_count,
             http_requests,
             http_request_duration,
             http_errors,
+            api_requests_throttled,
             contracts_deployed,
             contract_invocations,
             contract_execution_time,
@@ -212,9 +307,21 @@ impl Metrics {
             db_operations,
             db_operation_time,
             db_key_count,
+            pruned_blocks,
+            pruned_bytes,
+            state_cache_lookups,
         })
     }
 
+    /// Record one contract execution
+    pub fn record_contract_call(&self, contract: &str, elapsed: Duration, gas_used: u64) {
+        self.contract_invocations.inc();
+        self.contract_execution_time
+            .with_label_values(&[contract])
+            .observe(elapsed.as_secs_f64());
+        self.contract_gas_used.inc_by(gas_used as f64);
+    }
+
     /// Export metrics in Prometheus format
     pub fn export(&self) -> Result<String, prometheus::Error> {
         let encoder = TextEncoder::new();
@@ -275,4 +382,17 @@ mod tests {
         let output = metrics.export().unwrap();
         assert!(output.contains("block_production_time_seconds_bucket"));
     }
+
+    #[test]
+    fn test_contract_call_and_block_gas() {
+        let metrics = Metrics::new().unwrap();
+        metrics.record_contract_call("0xabc", Duration::from_millis(3), 500);
+        metrics.block_gas_used.with_label_values(&["imported"]).observe(42_000.0);
+        assert_eq!(metrics.contract_invocations.get(), 1);
+        assert_eq!(metrics.contract_gas_used.get(), 500.0);
+
+        let output = metrics.export().unwrap();
+        assert!(output.contains("contract_execution_time_seconds_count{contract=\"0xabc\"} 1"));
+        assert!(output.contains("block_gas_used_bucket{source=\"imported\",le=\"42000\"} 1"));
+    }
 }
@@ -0,0 +1,208 @@
+//! Metrics History
+//!
+//! Prometheus gauges only show the present. The history sampler records the
+//! chain height, processed transaction count, peer count and mempool depth
+//! at a fixed interval into a ring buffer of `capacity` slots in the
+//! database, overwriting the oldest sample once it is full. Samples keep the
+//! raw counters; block time and throughput are derived from the difference
+//! between consecutive samples when a range is queried, so downsampling
+//! several samples into one point stays exact.
+
+use crate::db::{Batch, Column, Db};
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Key of the next sample's sequence number
+const HEAD_KEY: &[u8] = b"history:head";
+const SAMPLE_PREFIX: &[u8] = b"history:sample:";
+
+/// Points returned for a range at most
+pub const MAX_POINTS: usize = 120;
+
+/// Node counters at one instant
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct MetricsSample {
+    /// Unix time in seconds
+    pub timestamp: u64,
+    pub chain_height: u64,
+    /// Transactions processed since the node started
+    pub transactions: u64,
+    pub peers: u64,
+    pub mempool: u64,
+}
+
+/// Downsampled metrics over one bucket of a queried range
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryPoint {
+    /// Unix time in seconds at the end of the bucket
+    pub timestamp: u64,
+    /// Mean time between blocks, absent if no block was produced
+    pub block_time_ms: Option<u64>,
+    pub transactions_per_sec: f64,
+    pub peers: f64,
+    pub mempool_depth: f64,
+}
+
+/// Ring buffer of samples in the node database
+pub struct MetricsHistory {
+    db: Arc<Db>,
+    capacity: u64,
+}
+
+impl MetricsHistory {
+    pub fn new(db: Arc<Db>, capacity: u64) -> Self {
+        Self { db, capacity: capacity.max(1) }
+    }
+
+    fn sample_key(slot: u64) -> Vec<u8> {
+        let mut key = SAMPLE_PREFIX.to_vec();
+        key.extend_from_slice(&slot.to_be_bytes());
+        key
+    }
+
+    /// Store `sample` in the next slot, replacing the oldest once full
+    pub fn record(&self, sample: MetricsSample) -> Result<(), String> {
+        let head = self
+            .db
+            .get_cf(Column::Metrics, HEAD_KEY)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0);
+        let bytes = bincode::encode_to_vec(sample, standard()).map_err(|e| e.to_string())?;
+        let mut batch = Batch::default();
+        batch.put(Column::Metrics, &Self::sample_key(head % self.capacity), &bytes);
+        batch.put(Column::Metrics, HEAD_KEY, &(head + 1).to_be_bytes());
+        self.db.write(batch);
+        Ok(())
+    }
+
+    /// Stored samples taken at or after `since`, oldest first
+    pub fn samples(&self, since: u64) -> Vec<MetricsSample> {
+        let mut samples: Vec<MetricsSample> = self
+            .db
+            .scan_prefix_cf(Column::Metrics, SAMPLE_PREFIX)
+            .into_iter()
+            .filter_map(|(_, bytes)| bincode::decode_from_slice(&bytes, standard()).ok().map(|(sample, _)| sample))
+            .filter(|sample: &MetricsSample| sample.timestamp >= since)
+            .collect();
+        samples.sort_by_key(|sample| sample.timestamp);
+        samples
+    }
+
+    /// Series over the `range_secs` before `now`, in at most `MAX_POINTS` points
+    pub fn series(&self, now: u64, range_secs: u64) -> Vec<HistoryPoint> {
+        downsample(&self.samples(now.saturating_sub(range_secs)), range_secs.div_ceil(MAX_POINTS as u64).max(1))
+    }
+}
+
+/// Combine the intervals between consecutive `samples` into buckets of `bucket_secs`
+fn downsample(samples: &[MetricsSample], bucket_secs: u64) -> Vec<HistoryPoint> {
+    #[derive(Default)]
+    struct Bucket {
+        end: u64,
+        elapsed: u64,
+        blocks: u64,
+        transactions: u64,
+        peers: u64,
+        mempool: u64,
+        count: u64,
+    }
+    let mut buckets: Vec<(u64, Bucket)> = Vec::new();
+    for pair in samples.windows(2) {
+        let (previous, sample) = (pair[0], pair[1]);
+        let index = sample.timestamp / bucket_secs;
+        if buckets.last().is_none_or(|(last, _)| *last != index) {
+            buckets.push((index, Bucket::default()));
+        }
+        let (_, bucket) = buckets.last_mut().expect("bucket just pushed");
+        bucket.end = sample.timestamp;
+        bucket.elapsed += sample.timestamp - previous.timestamp;
+        bucket.blocks += sample.chain_height.saturating_sub(previous.chain_height);
+        // The transaction counter restarts from zero with the node
+        bucket.transactions += match sample.transactions.checked_sub(previous.transactions) {
+            Some(processed) => processed,
+            None => sample.transactions,
+        };
+        bucket.peers += sample.peers;
+        bucket.mempool += sample.mempool;
+        bucket.count += 1;
+    }
+    buckets
+        .into_iter()
+        .map(|(_, bucket)| HistoryPoint {
+            timestamp: bucket.end,
+            block_time_ms: (bucket.blocks > 0).then(|| bucket.elapsed * 1000 / bucket.blocks),
+            transactions_per_sec: if bucket.elapsed == 0 { 0.0 } else { bucket.transactions as f64 / bucket.elapsed as f64 },
+            peers: bucket.peers as f64 / bucket.count as f64,
+            mempool_depth: bucket.mempool as f64 / bucket.count as f64,
+        })
+        .collect()
+}
+
+/// Parse a range such as "90s", "30m", "1h" or "7d" into seconds
+pub fn parse_range(range: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid range '{}': expected a number followed by s, m, h or d", range);
+    let split = range.len().checked_sub(1).filter(|&at| range.is_char_boundary(at)).ok_or_else(invalid)?;
+    let (amount, unit) = range.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => return Err(invalid()),
+    };
+    match amount.checked_mul(unit_secs) {
+        Some(0) | None => Err(invalid()),
+        Some(secs) => Ok(secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, chain_height: u64, transactions: u64) -> MetricsSample {
+        MetricsSample { timestamp, chain_height, transactions, peers: 4, mempool: 10 }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_latest_samples() {
+        let path = std::env::temp_dir().join(format!("aureon_metrics_history_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        {
+            let history = MetricsHistory::new(db.clone(), 3);
+            for timestamp in 1..=5 {
+                history.record(sample(timestamp * 10, timestamp, 0)).unwrap();
+            }
+            let timestamps: Vec<u64> = history.samples(0).iter().map(|sample| sample.timestamp).collect();
+            assert_eq!(timestamps, vec![30, 40, 50]);
+            assert_eq!(history.samples(45).len(), 1);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_downsample_derives_block_time_and_throughput() {
+        let samples = [sample(0, 10, 0), sample(10, 12, 50), sample(20, 14, 100), sample(30, 14, 20)];
+        let points = downsample(&samples, 20);
+        assert_eq!(points.len(), 2);
+        // 2 blocks and 50 transactions in 10 seconds
+        assert_eq!((points[0].timestamp, points[0].block_time_ms), (10, Some(5000)));
+        assert_eq!(points[0].transactions_per_sec, 5.0);
+        // 2 blocks in 20 seconds; the counter restarted, so 50 + 20 transactions
+        assert_eq!((points[1].timestamp, points[1].block_time_ms), (30, Some(10_000)));
+        assert_eq!(points[1].transactions_per_sec, 3.5);
+        assert_eq!(points[1].mempool_depth, 10.0);
+
+        assert_eq!(parse_range("1h"), Ok(3600));
+        assert_eq!(parse_range("30m"), Ok(1800));
+        assert!(parse_range("0d").is_err());
+        assert!(parse_range("h").is_err());
+        assert!(parse_range("5w").is_err());
+    }
+}
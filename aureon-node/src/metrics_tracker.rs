@@ -1,29 +1,56 @@
 /// Background task for periodically updating metrics based on system state
 use crate::metrics::Metrics;
+use crate::metrics_history::{MetricsHistory, MetricsSample};
 use crate::mempool::TransactionMempool;
+use crate::service_manager::Shutdown;
 use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub struct MetricsTracker;
 
 impl MetricsTracker {
-    /// Start a background task that periodically updates metrics
+    /// Start a background task that periodically updates metrics until
+    /// `shutdown` is triggered
     pub fn start_mempool_tracker(
         metrics: Arc<Metrics>,
         mempool: Arc<TransactionMempool>,
         interval_ms: u64,
-    ) {
+        shutdown: Shutdown,
+    ) -> JoinHandle<()> {
         thread::spawn(move || {
-            loop {
-                thread::sleep(Duration::from_millis(interval_ms));
-                
+            while !shutdown.wait_timeout(Duration::from_millis(interval_ms)) {
                 // Update mempool size metric
                 if let Ok(size) = mempool.size() {
                     metrics.mempool_size.set(size as i64);
                 }
             }
-        });
+        })
+    }
+
+    /// Start a background task that records a metrics sample into
+    /// `history` every `interval_secs` until `shutdown` is triggered
+    pub fn start_history_sampler(
+        metrics: Arc<Metrics>,
+        mempool: Arc<TransactionMempool>,
+        history: Arc<MetricsHistory>,
+        interval_secs: u64,
+        shutdown: Shutdown,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
+            while !shutdown.wait_timeout(Duration::from_secs(interval_secs)) {
+                let sample = MetricsSample {
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+                    chain_height: metrics.chain_height.get().max(0) as u64,
+                    transactions: metrics.transactions_processed.get(),
+                    peers: metrics.peers_connected.get().max(0) as u64,
+                    mempool: mempool.size().unwrap_or(0) as u64,
+                };
+                if let Err(e) = history.record(sample) {
+                    eprintln!("Warning: Failed to record metrics sample: {}", e);
+                }
+            }
+        })
     }
 }
 
@@ -36,7 +63,10 @@ mod tests {
         let metrics = Arc::new(Metrics::new().unwrap());
         let mempool = Arc::new(TransactionMempool::new());
         
-        // Just verify we can start without panicking
-        MetricsTracker::start_mempool_tracker(metrics, mempool, 1000);
+        // Verify the tracker starts and stops on shutdown
+        let shutdown = Shutdown::new();
+        let handle = MetricsTracker::start_mempool_tracker(metrics, mempool, 1000, shutdown.clone());
+        shutdown.trigger();
+        handle.join().unwrap();
     }
 }
@@ -1,3 +1,4 @@
+use crate::error_recovery::{HealthChecker, HealthStatus};
 use crate::metrics::Metrics;
 use axum::{
     extract::State,
@@ -7,7 +8,13 @@ use axum::{
     Json, Router,
 };
 use serde::Serialize;
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Blocks a node may trail its highest peer by before sync counts as degraded
+pub const MAX_SYNC_LAG: u64 = 10;
 
 /// Health check response
 #[derive(Debug, Serialize, Clone)]
@@ -17,6 +24,9 @@ pub struct HealthCheck {
     pub chain_height: u64,
     pub peers_connected: u64,
     pub mempool_size: u64,
+    /// Status of each tracked subsystem
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, HealthStatus>,
 }
 
 impl HealthCheck {
@@ -30,6 +40,7 @@ impl HealthCheck {
             chain_height,
             peers_connected,
             mempool_size,
+            components: BTreeMap::new(),
         }
     }
 
@@ -43,10 +54,102 @@ impl HealthCheck {
             chain_height: 0,
             peers_connected: 0,
             mempool_size: 0,
+            components: BTreeMap::new(),
+        }
+    }
+
+    /// Report subsystem statuses; the node is only as healthy as its worst subsystem
+    pub fn with_components(mut self, components: BTreeMap<String, HealthStatus>) -> Self {
+        match components.values().max() {
+            Some(HealthStatus::Unhealthy) => self.status = "unhealthy".to_string(),
+            Some(HealthStatus::Degraded) if self.status == "healthy" => self.status = "degraded".to_string(),
+            _ => {}
+        }
+        self.components = components;
+        self
+    }
+}
+
+/// Reports a subsystem's status whenever /health is queried
+pub type HealthProbe = Arc<dyn Fn() -> HealthStatus + Send + Sync>;
+
+/// A subsystem that records its own checks
+#[derive(Default)]
+struct Component {
+    checker: Arc<Mutex<HealthChecker>>,
+    /// Checks must be recorded at least this often for the subsystem to count as alive
+    max_age: Option<Duration>,
+}
+
+impl Component {
+    fn status(&self) -> HealthStatus {
+        let checker = self.checker.lock().unwrap();
+        let stale = self.max_age.is_some_and(|max_age| checker.last_check.elapsed().is_ok_and(|age| age > max_age));
+        if stale { HealthStatus::Unhealthy } else { checker.status }
+    }
+}
+
+/// Health of node subsystems, reported by /health, and readiness, reported by /ready
+#[derive(Clone, Default)]
+pub struct NodeHealth {
+    components: Arc<Mutex<BTreeMap<String, Component>>>,
+    probes: Arc<Mutex<BTreeMap<String, HealthProbe>>>,
+    synced: Arc<AtomicBool>,
+}
+
+impl NodeHealth {
+    /// Tracker for `name`, created on first use
+    pub fn component(&self, name: &str) -> Arc<Mutex<HealthChecker>> {
+        self.components.lock().unwrap().entry(name.to_string()).or_default().checker.clone()
+    }
+
+    /// Tracker for `name` that is unhealthy unless a check is recorded every `max_age`
+    pub fn heartbeat(&self, name: &str, max_age: Duration) -> Arc<Mutex<HealthChecker>> {
+        let mut components = self.components.lock().unwrap();
+        let component = components.entry(name.to_string()).or_default();
+        component.max_age = Some(max_age);
+        component.checker.clone()
+    }
+
+    /// Evaluate `probe` for `name` on every health query
+    pub fn add_probe(&self, name: &str, probe: HealthProbe) {
+        self.probes.lock().unwrap().insert(name.to_string(), probe);
+    }
+
+    /// Record that the initial sync finished, making the node ready
+    pub fn mark_synced(&self) {
+        self.synced.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.synced.load(Ordering::SeqCst)
+    }
+
+    /// Current status of every subsystem; one with both a tracker and a probe reports the worse
+    pub fn statuses(&self) -> BTreeMap<String, HealthStatus> {
+        let mut statuses: BTreeMap<String, HealthStatus> = self
+            .components
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, component)| (name.clone(), component.status()))
+            .collect();
+        let probes = self.probes.lock().unwrap().clone();
+        for (name, probe) in probes {
+            let status = probe();
+            statuses.entry(name).and_modify(|current| *current = status.max(*current)).or_insert(status);
         }
+        statuses
     }
 }
 
+/// Readiness response
+#[derive(Debug, Serialize)]
+pub struct ReadinessCheck {
+    pub ready: bool,
+    pub chain_height: u64,
+}
+
 /// Monitoring metrics summary
 #[derive(Debug, Serialize)]
 pub struct MetricsSummary {
@@ -59,17 +162,18 @@ pub struct MetricsSummary {
 }
 
 /// Create monitoring router with health checks and metrics endpoints
-pub fn monitoring_router(metrics: Arc<Metrics>) -> Router {
+pub fn monitoring_router(metrics: Arc<Metrics>, health: NodeHealth) -> Router {
     Router::new()
-        .route("/health", get(health_check).with_state(metrics.clone()))
+        .route("/health", get(health_check).with_state((metrics.clone(), health.clone())))
+        .route("/ready", get(readiness_check).with_state((metrics.clone(), health)))
         .route("/metrics", get(prometheus_metrics).with_state(metrics.clone()))
         .route("/metrics/summary", get(metrics_summary).with_state(metrics))
 }
 
-/// Health check endpoint
+/// Health check endpoint; 503 when any subsystem is unhealthy
 async fn health_check(
-    State(metrics): State<Arc<Metrics>>,
-) -> Result<Json<HealthCheck>, (StatusCode, String)> {
+    State((metrics, components)): State<(Arc<Metrics>, NodeHealth)>,
+) -> (StatusCode, Json<HealthCheck>) {
     // Get current metrics values
     let chain_height = metrics.chain_height.get() as u64;
     let peers_connected = metrics.peers_connected.get() as u64;
@@ -82,7 +186,19 @@ async fn health_check(
         HealthCheck::unhealthy()
     };
 
-    Ok(Json(health))
+    let health = health.with_components(components.statuses());
+    let status = if health.status == "unhealthy" { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (status, Json(health))
+}
+
+/// Readiness endpoint; 503 until the node has finished its initial sync
+async fn readiness_check(State((metrics, health)): State<(Arc<Metrics>, NodeHealth)>) -> (StatusCode, Json<ReadinessCheck>) {
+    let readiness = ReadinessCheck {
+        ready: health.is_synced(),
+        chain_height: metrics.chain_height.get() as u64,
+    };
+    let status = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(readiness))
 }
 
 /// Prometheus metrics endpoint
@@ -147,6 +263,56 @@ mod tests {
         assert_eq!(health.chain_height, 0);
     }
 
+    #[test]
+    fn test_degraded_component_degrades_health() {
+        let health = NodeHealth::default();
+        health.component("db").lock().unwrap().record_success();
+        health.component("sync").lock().unwrap().record_failure();
+
+        let check = HealthCheck::healthy(10, 3, 0).with_components(health.statuses());
+        assert_eq!(check.status, "degraded");
+        assert_eq!(check.components["sync"], HealthStatus::Degraded);
+
+        health.component("sync").lock().unwrap().record_success();
+        assert_eq!(HealthCheck::healthy(10, 3, 0).with_components(health.statuses()).status, "healthy");
+        assert_eq!(HealthCheck::unhealthy().with_components(health.statuses()).status, "unhealthy");
+
+        // A probe reports the worse of itself and the tracker of the same name
+        health.add_probe("sync", Arc::new(|| HealthStatus::Degraded));
+        health.add_probe("network", Arc::new(|| HealthStatus::Healthy));
+        let statuses = health.statuses();
+        assert_eq!((statuses["sync"], statuses["network"]), (HealthStatus::Degraded, HealthStatus::Healthy));
+    }
+
+    #[test]
+    fn test_stale_heartbeat_is_unhealthy() {
+        let health = NodeHealth::default();
+        let producer = health.heartbeat("producer", Duration::from_millis(20));
+        producer.lock().unwrap().record_success();
+        assert_eq!(health.statuses()["producer"], HealthStatus::Healthy);
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(health.statuses()["producer"], HealthStatus::Unhealthy);
+        producer.lock().unwrap().record_success();
+        assert_eq!(health.statuses()["producer"], HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_ready_after_initial_sync() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::Service;
+
+        let health = NodeHealth::default();
+        let app = monitoring_router(Arc::new(Metrics::new().unwrap()), health.clone());
+        let ready = || {
+            let mut app = app.clone();
+            async move { app.call(Request::builder().uri("/ready").body(Body::empty()).unwrap()).await.unwrap().status() }
+        };
+        assert_eq!(ready().await, StatusCode::SERVICE_UNAVAILABLE);
+        health.mark_synced();
+        assert_eq!(ready().await, StatusCode::OK);
+    }
+
     #[test]
     fn test_metrics_summary_creation() {
         let summary = MetricsSummary {
@@ -1,6 +1,9 @@
 use sha3::{Digest, Keccak256};
 use serde::{Serialize, Deserialize};
-use bincode::{Encode, encode_to_vec, config::standard};
+use bincode::{Decode, Encode, decode_from_slice, encode_to_vec, config::standard};
+
+/// Most memory decoding one proof node may claim
+pub const MAX_PROOF_NODE_SIZE: usize = 1024 * 1024;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode)]
 pub enum Node {
@@ -10,8 +13,57 @@ pub enum Node {
 }
 
 impl Node {
+    /// Hash of the node's proof form, which commits to its whole subtree
     pub fn hash(&self) -> Vec<u8> {
-        let encoded = encode_to_vec(self, standard()).unwrap();
-        Keccak256::digest(&encoded).to_vec()
+        self.to_proof_node().hash()
+    }
+
+    /// The node with its children replaced by their hashes
+    pub fn to_proof_node(&self) -> ProofNode {
+        match self {
+            Node::Branch(children, value) => ProofNode::Branch(
+                children
+                    .iter()
+                    .map(|child| child.as_ref().map(|child| child.hash()).unwrap_or_default())
+                    .collect(),
+                value.clone(),
+            ),
+            Node::Leaf(path, value) => ProofNode::Leaf(path.clone(), value.clone()),
+            Node::Extension(path, child) => ProofNode::Extension(path.clone(), child.hash()),
+        }
     }
-}
\ No newline at end of file
+}
+
+/// A trie node as carried in a Merkle proof
+///
+/// Children are referenced by hash, so a proof only needs the nodes on the
+/// path to a key and not the subtrees hanging off it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum ProofNode {
+    /// Hashes of the 16 children (empty where there is none) and the branch value
+    Branch(Vec<Vec<u8>>, Option<Vec<u8>>),
+    Leaf(Vec<u8>, Vec<u8>),
+    /// Shared path and the hash of the child below it
+    Extension(Vec<u8>, Vec<u8>),
+}
+
+impl ProofNode {
+    pub fn encode(&self) -> Vec<u8> {
+        encode_to_vec(self, standard()).unwrap()
+    }
+
+    /// Decode a node received in a proof, claiming at most
+    /// `MAX_PROOF_NODE_SIZE` of memory whatever its length prefixes say
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let (node, read) = decode_from_slice(bytes, standard().with_limit::<MAX_PROOF_NODE_SIZE>())
+            .map_err(|e| format!("Failed to decode proof node: {}", e))?;
+        if read != bytes.len() {
+            return Err("Trailing bytes after proof node".to_string());
+        }
+        Ok(node)
+    }
+
+    pub fn hash(&self) -> Vec<u8> {
+        Keccak256::digest(self.encode()).to_vec()
+    }
+}
@@ -1,5 +1,6 @@
-use super::node::Node;
-use super::util::nibble_key;
+use super::node::{Node, ProofNode};
+use super::util::{match_prefix, nibble_key, pack_nibbles};
+
 #[derive(Clone)]
 pub struct MerklePatriciaTrie {
     root: Option<Node>,
@@ -11,14 +12,45 @@ impl MerklePatriciaTrie {
     }
 
     pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        let _nibbles = nibble_key(&key);
-        self.root = Some(Node::Leaf(key, value));
+        let path = nibble_key(&key);
+        let root = self.root.take();
+        self.root = Some(Self::insert_at(root, &path, value));
+    }
+
+    /// Remove `key`, returning its value if it was present
+    ///
+    /// Nodes left with a single entry are merged into their parent, so the
+    /// trie, and its root, end up as if the key had never been inserted.
+    #[allow(dead_code)]
+    pub fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        let path = nibble_key(key);
+        let root = self.root.take()?;
+        let (root, removed) = Self::remove_at(root, &path);
+        self.root = root;
+        removed
     }
 
     pub fn get(&self, key: Vec<u8>) -> Option<&[u8]> {
-        match &self.root {
-            Some(Node::Leaf(k, v)) if *k == key => Some(v),
-            _ => None,
+        let path = nibble_key(&key);
+        let mut node = self.root.as_ref()?;
+        let mut path = path.as_slice();
+        loop {
+            match node {
+                Node::Leaf(leaf_path, value) => {
+                    return (leaf_path.as_slice() == path).then_some(value.as_slice());
+                }
+                Node::Extension(ext_path, child) => {
+                    path = path.strip_prefix(ext_path.as_slice())?;
+                    node = child;
+                }
+                Node::Branch(children, value) => match path.split_first() {
+                    None => return value.as_deref(),
+                    Some((index, rest)) => {
+                        node = children[*index as usize].as_deref()?;
+                        path = rest;
+                    }
+                },
+            }
         }
     }
 
@@ -28,4 +60,470 @@ impl MerklePatriciaTrie {
             None => vec![],
         }
     }
-}
\ No newline at end of file
+
+    /// Encoded proof nodes on the path from the root towards `key`
+    ///
+    /// For an absent key the path ends at the node where the lookup
+    /// diverges, which proves the absence.
+    pub fn generate_proof(&self, key: Vec<u8>) -> Vec<Vec<u8>> {
+        let path = nibble_key(&key);
+        let mut path = path.as_slice();
+        let mut proof = Vec::new();
+        let mut next = self.root.as_ref();
+        while let Some(node) = next {
+            proof.push(node.to_proof_node().encode());
+            next = match node {
+                Node::Leaf(..) => None,
+                Node::Extension(ext_path, child) => path.strip_prefix(ext_path.as_slice()).map(|rest| {
+                    path = rest;
+                    child.as_ref()
+                }),
+                Node::Branch(children, _) => path.split_first().and_then(|(index, rest)| {
+                    path = rest;
+                    children[*index as usize].as_deref()
+                }),
+            };
+        }
+        proof
+    }
+
+    /// Every key/value pair stored in the trie, in key order
+    pub fn entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.iter().map(|(key, value)| (key, value.to_vec())).collect()
+    }
+
+    /// Iterate the key/value pairs stored in the trie in key order,
+    /// without collecting them first
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { stack: self.root.iter().map(|root| (root, Vec::new())).collect() }
+    }
+
+    /// Insert `value` at nibble `path` below `node`, returning the new subtree
+    fn insert_at(node: Option<Node>, path: &[u8], value: Vec<u8>) -> Node {
+        match node {
+            None => Node::Leaf(path.to_vec(), value),
+            Some(Node::Leaf(leaf_path, leaf_value)) => {
+                if leaf_path == path {
+                    return Node::Leaf(leaf_path, value);
+                }
+                let common = match_prefix(&leaf_path, path);
+                let mut children = Self::empty_children();
+                let mut branch_value = None;
+                Self::branch_insert(&mut children, &mut branch_value, &leaf_path[common..], leaf_value);
+                Self::branch_insert(&mut children, &mut branch_value, &path[common..], value);
+                Self::with_prefix(&path[..common], Node::Branch(children, branch_value))
+            }
+            Some(Node::Extension(ext_path, child)) => {
+                let common = match_prefix(&ext_path, path);
+                if common == ext_path.len() {
+                    let child = Self::insert_at(Some(*child), &path[common..], value);
+                    return Node::Extension(ext_path, Box::new(child));
+                }
+                // Split the extension at the first diverging nibble
+                let mut children = Self::empty_children();
+                let mut branch_value = None;
+                let remainder = &ext_path[common + 1..];
+                let existing = Self::with_prefix(remainder, *child);
+                children[ext_path[common] as usize] = Some(Box::new(existing));
+                Self::branch_insert(&mut children, &mut branch_value, &path[common..], value);
+                Self::with_prefix(&path[..common], Node::Branch(children, branch_value))
+            }
+            Some(Node::Branch(mut children, mut branch_value)) => {
+                Self::branch_insert(&mut children, &mut branch_value, path, value);
+                Node::Branch(children, branch_value)
+            }
+        }
+    }
+
+    fn branch_insert(
+        children: &mut [Option<Box<Node>>; 16],
+        branch_value: &mut Option<Vec<u8>>,
+        path: &[u8],
+        value: Vec<u8>,
+    ) {
+        match path.split_first() {
+            None => *branch_value = Some(value),
+            Some((index, rest)) => {
+                let slot = &mut children[*index as usize];
+                let child = slot.take().map(|c| *c);
+                *slot = Some(Box::new(Self::insert_at(child, rest, value)));
+            }
+        }
+    }
+
+    /// Remove the value at nibble `path` below `node`, returning what is left
+    /// of the subtree and the removed value
+    fn remove_at(node: Node, path: &[u8]) -> (Option<Node>, Option<Vec<u8>>) {
+        match node {
+            Node::Leaf(leaf_path, value) if leaf_path == path => (None, Some(value)),
+            Node::Leaf(..) => (Some(node), None),
+            Node::Extension(ext_path, child) => {
+                let Some(rest) = path.strip_prefix(ext_path.as_slice()) else {
+                    return (Some(Node::Extension(ext_path, child)), None);
+                };
+                let (child, removed) = Self::remove_at(*child, rest);
+                (child.map(|child| Self::join_prefix(&ext_path, child)), removed)
+            }
+            Node::Branch(mut children, mut branch_value) => {
+                let removed = match path.split_first() {
+                    None => branch_value.take(),
+                    Some((index, rest)) => match children[*index as usize].take() {
+                        Some(child) => {
+                            let (child, removed) = Self::remove_at(*child, rest);
+                            children[*index as usize] = child.map(Box::new);
+                            removed
+                        }
+                        None => None,
+                    },
+                };
+                (Self::collapse_branch(children, branch_value), removed)
+            }
+        }
+    }
+
+    /// A branch that may have lost an entry, merged into a simpler node when
+    /// it no longer branches
+    fn collapse_branch(mut children: [Option<Box<Node>>; 16], branch_value: Option<Vec<u8>>) -> Option<Node> {
+        let occupied: Vec<usize> = (0..16).filter(|&index| children[index].is_some()).collect();
+        match (occupied.as_slice(), branch_value) {
+            ([], None) => None,
+            ([], Some(value)) => Some(Node::Leaf(vec![], value)),
+            (&[index], None) => {
+                let child = children[index].take().unwrap();
+                Some(Self::join_prefix(&[index as u8], *child))
+            }
+            (_, branch_value) => Some(Node::Branch(children, branch_value)),
+        }
+    }
+
+    /// Prepend `prefix` to the path of `node`, extending a branch
+    fn join_prefix(prefix: &[u8], node: Node) -> Node {
+        match node {
+            Node::Leaf(path, value) => Node::Leaf([prefix, &path].concat(), value),
+            Node::Extension(path, child) => Node::Extension([prefix, &path].concat(), child),
+            branch => Self::with_prefix(prefix, branch),
+        }
+    }
+
+    /// Wrap `node` in an extension for `prefix`, or return it as-is if empty
+    fn with_prefix(prefix: &[u8], node: Node) -> Node {
+        if prefix.is_empty() {
+            node
+        } else {
+            Node::Extension(prefix.to_vec(), Box::new(node))
+        }
+    }
+
+    fn empty_children() -> [Option<Box<Node>>; 16] {
+        Default::default()
+    }
+}
+
+/// Depth-first iterator over the entries of a trie
+pub struct Iter<'a> {
+    /// Nodes left to visit with the nibble path leading to them, next one last
+    stack: Vec<(&'a Node, Vec<u8>)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (Vec<u8>, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, path)) = self.stack.pop() {
+            match node {
+                Node::Leaf(leaf_path, value) => {
+                    return Some((pack_nibbles(&[path.as_slice(), leaf_path].concat()), value));
+                }
+                Node::Extension(ext_path, child) => self.stack.push((child, [path.as_slice(), ext_path].concat())),
+                Node::Branch(children, value) => {
+                    // A branch's own value sorts before every key below it
+                    for (index, child) in children.iter().enumerate().rev() {
+                        if let Some(child) = child {
+                            self.stack.push((child, [path.as_slice(), &[index as u8]].concat()));
+                        }
+                    }
+                    if let Some(value) = value {
+                        return Some((pack_nibbles(&path), value));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Check `proof` against `root_hash` and return the value it proves for
+/// `key`, or None if it proves the key absent
+///
+/// Used by light clients to verify state without trusting the serving node.
+#[allow(dead_code)]
+pub fn verify_proof(root_hash: &[u8], key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, String> {
+    if proof.is_empty() {
+        return if root_hash.is_empty() { Ok(None) } else { Err("Empty proof for a non-empty trie".to_string()) };
+    }
+    let path = nibble_key(key);
+    let mut path = path.as_slice();
+    let mut expected = root_hash.to_vec();
+    for (depth, encoded) in proof.iter().enumerate() {
+        let node = ProofNode::decode(encoded)?;
+        if node.hash() != expected {
+            return Err(format!("Proof node {} does not match the hash committed to it", depth));
+        }
+        let (value, child) = match node {
+            ProofNode::Leaf(leaf_path, value) => ((leaf_path == path).then_some(value), None),
+            ProofNode::Extension(ext_path, child) => match path.strip_prefix(ext_path.as_slice()) {
+                Some(rest) => {
+                    path = rest;
+                    (None, Some(child))
+                }
+                None => (None, None),
+            },
+            ProofNode::Branch(children, value) => match path.split_first() {
+                None => (value, None),
+                Some((index, rest)) => {
+                    path = rest;
+                    (None, children.get(*index as usize).filter(|child| !child.is_empty()).cloned())
+                }
+            },
+        };
+        match child {
+            Some(child) => expected = child,
+            None if depth + 1 == proof.len() => return Ok(value),
+            None => return Err("Proof continues past the end of the lookup".to_string()),
+        }
+    }
+    Err("Proof ends before the lookup does".to_string())
+}
+
+impl Default for MerklePatriciaTrie {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get_many() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"alice".to_vec(), b"100".to_vec());
+        trie.insert(b"alicia".to_vec(), b"200".to_vec());
+        trie.insert(b"bob".to_vec(), b"300".to_vec());
+        trie.insert(b"ali".to_vec(), b"400".to_vec());
+
+        assert_eq!(trie.get(b"alice".to_vec()), Some(&b"100"[..]));
+        assert_eq!(trie.get(b"alicia".to_vec()), Some(&b"200"[..]));
+        assert_eq!(trie.get(b"bob".to_vec()), Some(&b"300"[..]));
+        assert_eq!(trie.get(b"ali".to_vec()), Some(&b"400"[..]));
+        assert_eq!(trie.get(b"al".to_vec()), None);
+        assert_eq!(trie.get(b"carol".to_vec()), None);
+    }
+
+    #[test]
+    fn test_update_changes_root() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"alice".to_vec(), b"100".to_vec());
+        trie.insert(b"bob".to_vec(), b"50".to_vec());
+        let before = trie.root_hash();
+
+        trie.insert(b"alice".to_vec(), b"90".to_vec());
+        assert_ne!(before, trie.root_hash());
+        assert_eq!(trie.get(b"alice".to_vec()), Some(&b"90"[..]));
+        assert_eq!(trie.get(b"bob".to_vec()), Some(&b"50"[..]));
+    }
+
+    #[test]
+    fn test_root_independent_of_insert_order() {
+        let entries: Vec<(&[u8], &[u8])> = vec![
+            (b"alice", b"1"),
+            (b"alicia", b"2"),
+            (b"ali", b"3"),
+            (b"bob", b"4"),
+        ];
+
+        let mut forward = MerklePatriciaTrie::new();
+        for (k, v) in &entries {
+            forward.insert(k.to_vec(), v.to_vec());
+        }
+        let mut reverse = MerklePatriciaTrie::new();
+        for (k, v) in entries.iter().rev() {
+            reverse.insert(k.to_vec(), v.to_vec());
+        }
+
+        assert_eq!(forward.root_hash(), reverse.root_hash());
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let mut trie = MerklePatriciaTrie::new();
+        for (k, v) in [(&b"bob"[..], &b"4"[..]), (b"alice", b"1"), (b"ali", b"3"), (b"alicia", b"2")] {
+            trie.insert(k.to_vec(), v.to_vec());
+        }
+
+        let entries = trie.entries();
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, vec![&b"ali"[..], b"alice", b"alicia", b"bob"]);
+
+        let mut rebuilt = MerklePatriciaTrie::new();
+        for (k, v) in entries {
+            rebuilt.insert(k, v);
+        }
+        assert_eq!(rebuilt.root_hash(), trie.root_hash());
+
+        // The iterator is lazy and yields the same entries
+        let mut iter = trie.iter();
+        assert_eq!(iter.next(), Some((b"ali".to_vec(), &b"3"[..])));
+        assert_eq!(iter.count(), 3);
+        assert_eq!(MerklePatriciaTrie::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_proofs_verify_against_root() {
+        let mut trie = MerklePatriciaTrie::new();
+        for (k, v) in [(&b"bob"[..], &b"4"[..]), (b"alice", b"1"), (b"ali", b"3"), (b"alicia", b"2")] {
+            trie.insert(k.to_vec(), v.to_vec());
+        }
+        let root = trie.root_hash();
+
+        for key in [&b"ali"[..], b"alice", b"alicia", b"bob"] {
+            let proof = trie.generate_proof(key.to_vec());
+            assert_eq!(verify_proof(&root, key, &proof).unwrap().as_deref(), trie.get(key.to_vec()));
+        }
+        let absent = trie.generate_proof(b"carol".to_vec());
+        assert_eq!(verify_proof(&root, b"carol", &absent).unwrap(), None);
+
+        // A proof does not carry over to another key or a tampered value
+        let proof = trie.generate_proof(b"alice".to_vec());
+        assert!(verify_proof(&root, b"alicia", &proof).is_err());
+        let mut tampered = proof.clone();
+        let last = tampered.len() - 1;
+        tampered[last] = ProofNode::decode(&proof[last])
+            .map(|node| match node {
+                ProofNode::Leaf(path, _) => ProofNode::Leaf(path, b"9".to_vec()),
+                other => other,
+            })
+            .unwrap()
+            .encode();
+        assert!(verify_proof(&root, b"alice", &tampered).is_err());
+    }
+
+    #[test]
+    fn test_empty_root() {
+        let trie = MerklePatriciaTrie::new();
+        assert!(trie.root_hash().is_empty());
+        assert_eq!(trie.get(b"alice".to_vec()), None);
+    }
+
+    #[test]
+    fn test_remove_restores_root() {
+        let mut trie = MerklePatriciaTrie::new();
+        for (k, v) in [(&b"ali"[..], &b"3"[..]), (b"alice", b"1"), (b"bob", b"4")] {
+            trie.insert(k.to_vec(), v.to_vec());
+        }
+        let before = trie.root_hash();
+        trie.insert(b"alicia".to_vec(), b"2".to_vec());
+        assert_eq!(trie.remove(b"alicia"), Some(b"2".to_vec()));
+        assert_eq!(trie.root_hash(), before);
+        assert_eq!(trie.remove(b"alicia"), None);
+
+        // Removing a branch value folds the branch back into a leaf
+        assert_eq!(trie.remove(b"ali"), Some(b"3".to_vec()));
+        assert_eq!(trie.get(b"alice".to_vec()), Some(&b"1"[..]));
+        for key in [&b"alice"[..], b"bob"] {
+            trie.remove(key);
+        }
+        assert!(trie.root_hash().is_empty());
+    }
+
+    use proptest::collection::{btree_map, vec};
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
+
+    /// Short keys over a few byte values, so that they share prefixes and
+    /// exercise branches, extensions and branch values
+    fn key() -> impl Strategy<Value = Vec<u8>> {
+        vec(0u8..3, 0..4)
+    }
+
+    fn build(entries: &BTreeMap<Vec<u8>, Vec<u8>>) -> MerklePatriciaTrie {
+        let mut trie = MerklePatriciaTrie::new();
+        for (k, v) in entries {
+            trie.insert(k.clone(), v.clone());
+        }
+        trie
+    }
+
+    proptest! {
+        #[test]
+        fn prop_root_depends_only_on_contents(
+            entries in btree_map(key(), vec(any::<u8>(), 0..3), 0..24),
+            extra in btree_map(key(), vec(any::<u8>(), 0..3), 0..8),
+            rotation in any::<prop::sample::Index>(),
+            reverse in any::<bool>(),
+        ) {
+            let expected = build(&entries);
+
+            // Insert in another order, with extra keys that are removed again
+            // and values overwritten on the way
+            let mut ops: Vec<_> = entries.iter().chain(&extra).collect();
+            let mid = rotation.index(ops.len().max(1));
+            ops.rotate_left(mid);
+            if reverse {
+                ops.reverse();
+            }
+            let mut trie = MerklePatriciaTrie::new();
+            for (k, v) in ops {
+                trie.insert(k.clone(), vec![0xff; 4]);
+                trie.insert(k.clone(), v.clone());
+            }
+            for k in extra.keys().filter(|k| !entries.contains_key(*k)) {
+                prop_assert_eq!(trie.remove(k), Some(extra[k].clone()));
+            }
+            for (k, v) in entries.iter().filter(|(k, _)| extra.contains_key(*k)) {
+                trie.insert(k.clone(), v.clone());
+            }
+
+            prop_assert_eq!(trie.root_hash(), expected.root_hash());
+            prop_assert_eq!(trie.entries(), entries.clone().into_iter().collect::<Vec<_>>());
+            for k in extra.keys() {
+                prop_assert_eq!(trie.get(k.clone()), entries.get(k).map(Vec::as_slice));
+            }
+        }
+
+        #[test]
+        fn prop_proofs_match_contents(
+            entries in btree_map(key(), vec(any::<u8>(), 0..3), 0..24),
+            probes in vec(key(), 1..8),
+        ) {
+            let trie = build(&entries);
+            let root = trie.root_hash();
+            for probe in probes.iter().chain(entries.keys()) {
+                let proof = trie.generate_proof(probe.clone());
+                prop_assert_eq!(verify_proof(&root, probe, &proof).unwrap(), entries.get(probe).cloned());
+            }
+        }
+
+        #[test]
+        fn prop_untrusted_proofs_never_panic(
+            root in vec(any::<u8>(), 0..33),
+            key in key(),
+            proof in vec(vec(any::<u8>(), 0..48), 0..4),
+        ) {
+            let _ = verify_proof(&root, &key, &proof);
+            for node in &proof {
+                let _ = ProofNode::decode(node);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decoding_claims_bounded_memory() {
+        // A leaf whose path claims 2^62 bytes
+        let mut bytes = vec![1u8, 253];
+        bytes.extend_from_slice(&(1u64 << 62).to_le_bytes());
+        assert!(ProofNode::decode(&bytes).is_err());
+    }
+}
+
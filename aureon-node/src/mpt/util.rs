@@ -4,7 +4,13 @@ pub fn nibble_key(bytes: &[u8]) -> Vec<u8> {
         .collect()
 }
 
-#[allow(dead_code)]
+/// Inverse of `nibble_key` for paths with an even number of nibbles
+pub fn pack_nibbles(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
 pub fn match_prefix(a: &[u8], b: &[u8]) -> usize {
     a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
 }
\ No newline at end of file
@@ -9,6 +9,9 @@ use std::thread;
 use std::time::Duration;
 use std::net::SocketAddr;
 
+#[cfg(test)]
+pub mod simulation;
+
 /// Configuration for a test node instance
 #[derive(Clone, Debug)]
 pub struct TestNodeConfig {
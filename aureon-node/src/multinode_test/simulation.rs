@@ -0,0 +1,428 @@
+//! Deterministic Network Simulation
+//!
+//! Runs a cluster of simulated nodes in one thread on a virtual clock. Each
+//! node has its own database, state trie and PoS engine, proposes in the
+//! slots it leads, and takes blocks in through the real `BlockImporter`;
+//! blocks are gossiped and relayed, tips announced every slot, and nodes
+//! that fall behind download the missing range from the peer that announced
+//! it, as the syncer does. Messages travel over an in-memory network with a
+//! random latency and loss rate, and the network can be partitioned and
+//! healed. Every random choice comes from one seeded generator, so a seed
+//! replays the same run exactly.
+
+use crate::block_import::BlockImporter;
+use crate::chain_handle::ChainHandle;
+use crate::chain_store::GENESIS_HASH;
+use crate::consensus::pos::PoSConsensus;
+use crate::consensus::ConsensusEngine;
+use crate::db::Db;
+use crate::mpt::MerklePatriciaTrie;
+use crate::network::identity::NodeIdentity;
+use crate::network::MAX_SYNC_BLOCKS;
+use crate::randomness;
+use crate::state_processor::StateProcessor;
+use crate::types::Block;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Stake of every simulated validator
+const VALIDATOR_STAKE: u64 = 100;
+
+/// Distinguishes the databases of simulations running at the same time
+static SIMULATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Latency and loss of every link between two nodes
+#[derive(Debug, Clone, Copy)]
+pub struct LinkConditions {
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    /// Probability that a message is lost
+    pub drop_rate: f64,
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        LinkConditions { min_latency_ms: 10, max_latency_ms: 100, drop_rate: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    pub validators: usize,
+    /// Nodes that follow the chain without proposing
+    pub followers: usize,
+    pub slot_ms: u64,
+    pub link: LinkConditions,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig { seed: 0, validators: 4, followers: 0, slot_ms: 1000, link: LinkConditions::default() }
+    }
+}
+
+/// Message between simulated nodes
+#[derive(Debug, Clone)]
+pub enum SimMessage {
+    /// A block gossiped by its proposer or relayed by a peer
+    Block(Box<Block>),
+    /// Number of blocks in the sender's chain
+    Tip(u64),
+    /// Request for the blocks at heights `from..=to`
+    GetBlocks { from: u64, to: u64 },
+    Blocks(Vec<Block>),
+}
+
+#[derive(Debug)]
+enum Event {
+    Slot(u64),
+    Deliver { from: usize, to: usize, message: SimMessage },
+}
+
+struct SimNode {
+    name: String,
+    /// Proposer key, for validators
+    signer: Option<NodeIdentity>,
+    db: Arc<Db>,
+    path: PathBuf,
+    chain: ChainHandle,
+    trie: Arc<RwLock<MerklePatriciaTrie>>,
+    engine: Arc<Mutex<Box<dyn ConsensusEngine>>>,
+    importer: BlockImporter,
+}
+
+impl SimNode {
+    /// Build, seal and sign the block for `slot` on top of the local tip
+    fn propose(&self, slot: u64) -> Result<Block, String> {
+        let signer = self.signer.as_ref().ok_or("Only validators propose")?;
+        let tip = self.chain.head();
+        let parent_seed = randomness::seed_of(tip.as_ref());
+        let mut state = self.trie.read().unwrap().clone();
+        let pre_state_root = state.root_hash();
+        let post_state_root = StateProcessor::new(&self.db, &mut state).simulate_block(&self.name, &[]);
+        let mut engine = self.engine.lock().unwrap();
+        engine.update_tip(tip.as_ref());
+        let previous_hash = tip.map_or_else(|| GENESIS_HASH.to_string(), |tip| tip.hash);
+        let mut block = engine.produce_block(slot, previous_hash, vec![], pre_state_root, post_state_root);
+        drop(engine);
+        randomness::seal(&mut block, &parent_seed, Some(signer))?;
+        block.sign_proposal(&self.name, signer)?;
+        Ok(block)
+    }
+
+    fn is_leader(&self, slot: u64) -> bool {
+        self.signer.is_some() && self.engine.lock().unwrap().slot_leader(slot).as_deref() == Some(self.name.as_str())
+    }
+
+    /// Hashes of the local chain from genesis
+    fn hashes(&self) -> Vec<String> {
+        (0..self.chain.len())
+            .filter_map(|height| self.chain.get_block_by_height(height).ok().flatten())
+            .map(|block| block.hash)
+            .collect()
+    }
+}
+
+/// A cluster of simulated nodes and the network between them
+pub struct Simulation {
+    config: SimulationConfig,
+    nodes: Vec<SimNode>,
+    rng: ChaCha20Rng,
+    now: u64,
+    /// Pending events by delivery time, in the order they were scheduled
+    events: BTreeMap<(u64, u64), Event>,
+    scheduled: u64,
+    /// Side of the partition every node is on
+    sides: Vec<usize>,
+    /// Blocks the nodes rejected
+    pub rejected: u64,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Self {
+        let run = SIMULATIONS.fetch_add(1, Ordering::Relaxed);
+        let identities: Vec<NodeIdentity> =
+            (0..config.validators).map(|index| NodeIdentity::from_secret([index as u8 + 1; 32])).collect();
+        let stakes: HashMap<String, u64> =
+            (0..config.validators).map(|index| (format!("validator-{}", index), VALIDATOR_STAKE)).collect();
+
+        let names = (0..config.validators)
+            .map(|index| format!("validator-{}", index))
+            .chain((0..config.followers).map(|index| format!("follower-{}", index)));
+        let nodes = names
+            .enumerate()
+            .map(|(index, name)| {
+                let path = std::env::temp_dir()
+                    .join(format!("aureon_sim_{}_{}_{}_{}", std::process::id(), run, config.seed, index));
+                let _ = std::fs::remove_dir_all(&path);
+                let db = Arc::new(Db::open(path.to_str().unwrap()));
+                let chain = ChainHandle::open(db.clone()).unwrap();
+                let trie = Arc::new(RwLock::new(MerklePatriciaTrie::new()));
+                let engine: Box<dyn ConsensusEngine> = Box::new(PoSConsensus::new(stakes.clone()));
+                let engine = Arc::new(Mutex::new(engine));
+                let mut importer = BlockImporter::new(chain.clone(), trie.clone(), engine.clone());
+                for (validator, identity) in identities.iter().enumerate() {
+                    importer = importer.with_validator_key(&format!("validator-{}", validator), identity.public_key());
+                }
+                SimNode { name, signer: identities.get(index).cloned(), db, path, chain, trie, engine, importer }
+            })
+            .collect::<Vec<_>>();
+
+        let mut simulation = Simulation {
+            rng: ChaCha20Rng::seed_from_u64(config.seed),
+            sides: vec![0; nodes.len()],
+            config,
+            nodes,
+            now: 0,
+            events: BTreeMap::new(),
+            scheduled: 0,
+            rejected: 0,
+        };
+        simulation.schedule(simulation.config.slot_ms, Event::Slot(1));
+        simulation
+    }
+
+    pub fn set_link(&mut self, link: LinkConditions) {
+        self.config.link = link;
+    }
+
+    /// Cut `side` off from the other nodes; messages in flight across the
+    /// cut are lost too
+    pub fn partition(&mut self, side: &[usize]) {
+        for (index, node_side) in self.sides.iter_mut().enumerate() {
+            *node_side = side.contains(&index) as usize;
+        }
+    }
+
+    pub fn heal(&mut self) {
+        self.sides.iter_mut().for_each(|side| *side = 0);
+    }
+
+    /// Process events until virtual time reaches `until_ms`
+    pub fn run_until(&mut self, until_ms: u64) {
+        while let Some(entry) = self.events.first_entry() {
+            if entry.key().0 > until_ms {
+                break;
+            }
+            let ((time, _), event) = entry.remove_entry();
+            self.now = time;
+            match event {
+                Event::Slot(slot) => self.on_slot(slot),
+                Event::Deliver { from, to, message } => {
+                    if self.sides[from] == self.sides[to] {
+                        self.on_message(from, to, message);
+                    }
+                }
+            }
+        }
+        self.now = until_ms;
+    }
+
+    /// Run for `slots` more slots
+    pub fn run_slots(&mut self, slots: u64) {
+        self.run_until(self.now + slots * self.config.slot_ms);
+    }
+
+    /// Number of blocks in every node's chain
+    pub fn heights(&self) -> Vec<u64> {
+        self.nodes.iter().map(|node| node.chain.len()).collect()
+    }
+
+    /// Hashes of node `index`'s chain from genesis
+    pub fn chain(&self, index: usize) -> Vec<String> {
+        self.nodes[index].hashes()
+    }
+
+    /// Deepest disagreement between two nodes: blocks of the shorter chain
+    /// past the prefix they share (a node merely behind is not a fork)
+    pub fn fork_depth(&self) -> u64 {
+        let chains: Vec<Vec<String>> = self.nodes.iter().map(SimNode::hashes).collect();
+        let mut depth = 0;
+        for (index, a) in chains.iter().enumerate() {
+            for b in &chains[index + 1..] {
+                let shared = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+                depth = depth.max(a.len().min(b.len()) - shared);
+            }
+        }
+        depth as u64
+    }
+
+    /// Fail if two nodes disagree on more than the last `depth` blocks
+    pub fn check_forks(&self, depth: u64) -> Result<(), String> {
+        match self.fork_depth() {
+            found if found > depth => Err(format!("Nodes forked {} blocks deep at {}ms", found, self.now)),
+            _ => Ok(()),
+        }
+    }
+
+    fn schedule(&mut self, at: u64, event: Event) {
+        self.events.insert((at, self.scheduled), event);
+        self.scheduled += 1;
+    }
+
+    fn send(&mut self, from: usize, to: usize, message: SimMessage) {
+        let link = self.config.link;
+        if self.rng.gen_bool(link.drop_rate) {
+            return;
+        }
+        let latency = self.rng.gen_range(link.min_latency_ms..=link.max_latency_ms.max(link.min_latency_ms));
+        self.schedule(self.now + latency, Event::Deliver { from, to, message });
+    }
+
+    fn broadcast(&mut self, from: usize, message: SimMessage, except: Option<usize>) {
+        for to in 0..self.nodes.len() {
+            if to != from && Some(to) != except {
+                self.send(from, to, message.clone());
+            }
+        }
+    }
+
+    /// Announce every tip, then let the slot's leader propose
+    fn on_slot(&mut self, slot: u64) {
+        for index in 0..self.nodes.len() {
+            let height = self.nodes[index].chain.len();
+            self.broadcast(index, SimMessage::Tip(height), None);
+        }
+        for index in 0..self.nodes.len() {
+            let node = &self.nodes[index];
+            if !node.is_leader(slot) {
+                continue;
+            }
+            let imported = node.propose(slot).and_then(|block| node.importer.import(&block).map(|_| block));
+            match imported {
+                Ok(block) => self.broadcast(index, SimMessage::Block(Box::new(block)), None),
+                Err(_) => self.rejected += 1,
+            }
+        }
+        self.schedule((slot + 1) * self.config.slot_ms, Event::Slot(slot + 1));
+    }
+
+    fn on_message(&mut self, from: usize, to: usize, message: SimMessage) {
+        match message {
+            SimMessage::Block(block) => {
+                // Imported blocks are relayed; gossip of a known block stops here
+                if !self.nodes[to].chain.contains(&block.hash) && self.import(to, &block) {
+                    self.broadcast(to, SimMessage::Block(block), Some(from));
+                }
+            }
+            SimMessage::Tip(height) => {
+                let local = self.nodes[to].chain.len();
+                if height > local {
+                    let to_height = (height - 1).min(local + MAX_SYNC_BLOCKS - 1);
+                    self.send(to, from, SimMessage::GetBlocks { from: local, to: to_height });
+                }
+            }
+            SimMessage::GetBlocks { from: from_height, to: to_height } => {
+                let chain = &self.nodes[to].chain;
+                let blocks = (from_height..=to_height.min(from_height + MAX_SYNC_BLOCKS - 1))
+                    .map_while(|height| chain.get_block_by_height(height).ok().flatten())
+                    .collect();
+                self.send(to, from, SimMessage::Blocks(blocks));
+            }
+            SimMessage::Blocks(blocks) => {
+                // Later blocks do not connect once one is rejected
+                for block in blocks {
+                    if !self.nodes[to].chain.contains(&block.hash) && !self.import(to, &block) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn import(&mut self, index: usize, block: &Block) -> bool {
+        let imported = self.nodes[index].importer.import(block).is_ok();
+        if !imported {
+            self.rejected += 1;
+        }
+        imported
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        for node in self.nodes.drain(..) {
+            let path = node.path.clone();
+            drop(node);
+            let _ = std::fs::remove_dir_all(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lossy() -> LinkConditions {
+        LinkConditions { min_latency_ms: 20, max_latency_ms: 400, drop_rate: 0.1 }
+    }
+
+    #[test]
+    fn test_runs_replay_from_their_seed() {
+        let config = SimulationConfig { seed: 7, followers: 1, link: lossy(), ..Default::default() };
+        let mut first = Simulation::new(config.clone());
+        let mut second = Simulation::new(config);
+        first.run_slots(20);
+        second.run_slots(20);
+        assert!(first.heights()[0] > 10);
+        assert_eq!(first.heights(), second.heights());
+        assert_eq!(first.chain(4), second.chain(4));
+        assert_eq!(first.rejected, second.rejected);
+    }
+
+    #[test]
+    fn test_nodes_agree_over_a_lossy_network() {
+        for seed in 0..3 {
+            let config = SimulationConfig { seed, followers: 2, link: lossy(), ..Default::default() };
+            let mut simulation = Simulation::new(config);
+            for _ in 0..30 {
+                simulation.run_slots(1);
+                simulation.check_forks(0).unwrap();
+            }
+            let heights = simulation.heights();
+            assert!(heights.iter().all(|&height| height + 1 >= heights[0] && height >= 20), "{:?}", heights);
+        }
+    }
+
+    #[test]
+    fn test_cut_off_follower_catches_up_after_heal() {
+        let config = SimulationConfig { seed: 3, followers: 1, ..Default::default() };
+        let mut simulation = Simulation::new(config);
+        simulation.run_slots(5);
+        simulation.partition(&[4]);
+        simulation.run_slots(20);
+        let stalled = simulation.heights()[4];
+        assert!(simulation.heights()[0] > stalled + 10);
+
+        // Liveness: after the heal the follower syncs the missed range and follows again
+        simulation.heal();
+        simulation.set_link(lossy());
+        simulation.run_slots(5);
+        simulation.check_forks(0).unwrap();
+        // The latest block may still be in flight
+        let heights = simulation.heights();
+        assert!(heights[4] + 1 >= heights[0] && heights[4] > stalled + 10, "{:?}", heights);
+    }
+
+    #[test]
+    fn test_cut_off_validator_forks() {
+        let config = SimulationConfig { seed: 5, ..Default::default() };
+        let mut simulation = Simulation::new(config);
+        simulation.run_slots(5);
+        simulation.check_forks(0).unwrap();
+        simulation.partition(&[0]);
+        simulation.run_slots(30);
+        simulation.heal();
+        simulation.run_slots(5);
+
+        // Both sides kept proposing, and the fork choice keeps the branch a
+        // node saw first, so the cut-off validator never rejoins
+        assert!(simulation.check_forks(1).unwrap_err().contains("forked"));
+        assert_eq!(simulation.chain(1), simulation.chain(2));
+    }
+}
@@ -0,0 +1,190 @@
+//! Multisig Accounts
+//!
+//! An account becomes a multisig account by registering N Ed25519 public keys
+//! and a threshold M with a `RegisterMultisig` transaction. Its later
+//! transactions carry no public key: their `signature` field holds the
+//! bincode encoding of a list of `KeySignature`s, each made by one of the
+//! registered keys over the transaction's signing bytes, and at least M of
+//! them are required. The mempool checks them against the latest state and the
+//! state processor against the state the transaction executes on. Configs are
+//! stored in the state trie under `multisig:` keys; registering again replaces
+//! the config, which for a multisig account takes M of its current signers.
+
+use crate::token::TokenState;
+use crate::types::Transaction;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Serialize;
+
+/// Prefix of every key holding multisig state
+pub const STATE_PREFIX: &str = "multisig:";
+
+/// Maximum number of keys an account can register
+pub const MAX_SIGNERS: usize = 16;
+
+/// Keys allowed to sign for an account and how many must sign
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct MultisigConfig {
+    /// Ed25519 public keys, 32 bytes each
+    pub public_keys: Vec<Vec<u8>>,
+    pub threshold: u32,
+}
+
+/// Signature by the registered key at `key_index`
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct KeySignature {
+    pub key_index: u32,
+    pub signature: Vec<u8>,
+}
+
+fn config_key(account: &str) -> Vec<u8> {
+    format!("{}{}", STATE_PREFIX, account).into_bytes()
+}
+
+/// Multisig config registered by `account`, if any
+pub fn config<S: TokenState + ?Sized>(state: &S, account: &str) -> Option<MultisigConfig> {
+    state
+        .get(&config_key(account))
+        .and_then(|bytes| bincode::decode_from_slice(&bytes, standard()).ok())
+        .map(|(config, _)| config)
+}
+
+fn decode_signatures(bytes: &[u8]) -> Result<Vec<KeySignature>, String> {
+    bincode::decode_from_slice(bytes, standard())
+        .map(|(signatures, _)| signatures)
+        .map_err(|e| format!("Malformed multisig signatures: {}", e))
+}
+
+impl MultisigConfig {
+    /// Check the threshold and that the keys are distinct valid Ed25519 keys
+    pub fn validate(&self) -> Result<(), String> {
+        if self.public_keys.is_empty() || self.public_keys.len() > MAX_SIGNERS {
+            return Err(format!("A multisig account needs between 1 and {} keys", MAX_SIGNERS));
+        }
+        if self.threshold == 0 || self.threshold as usize > self.public_keys.len() {
+            return Err(format!(
+                "Threshold {} must be between 1 and the number of keys ({})",
+                self.threshold,
+                self.public_keys.len()
+            ));
+        }
+        for (index, key) in self.public_keys.iter().enumerate() {
+            Self::verifying_key(key)?;
+            if self.public_keys[..index].contains(key) {
+                return Err(format!("Key {} is registered twice", hex::encode(key)));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn verifying_key(key: &[u8]) -> Result<VerifyingKey, String> {
+        <[u8; 32]>::try_from(key)
+            .ok()
+            .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| format!("Invalid multisig public key {}", hex::encode(key)))
+    }
+
+    /// Check that `tx` carries at least `threshold` valid signatures by
+    /// distinct registered keys
+    pub fn verify(&self, tx: &Transaction) -> Result<(), String> {
+        if !tx.public_key.is_empty() {
+            return Err(format!("Transactions from multisig account {} carry no public key", tx.from));
+        }
+        let signatures = decode_signatures(&tx.signature)?;
+        let message = tx.signing_bytes();
+        let mut signers = Vec::new();
+        for partial in &signatures {
+            let key = self
+                .public_keys
+                .get(partial.key_index as usize)
+                .ok_or_else(|| format!("No multisig key at index {}", partial.key_index))?;
+            if signers.contains(&partial.key_index) {
+                return Err(format!("Key {} signed twice", partial.key_index));
+            }
+            let signature = Signature::from_slice(&partial.signature)
+                .map_err(|_| format!("Malformed signature by key {}", partial.key_index))?;
+            Self::verifying_key(key)?
+                .verify(&message, &signature)
+                .map_err(|_| format!("Invalid signature by key {}", partial.key_index))?;
+            signers.push(partial.key_index);
+        }
+        if signers.len() < self.threshold as usize {
+            return Err(format!(
+                "Multisig account {} needs {} signatures, got {}",
+                tx.from,
+                self.threshold,
+                signers.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check the multisig signatures of `tx` when its sender is a multisig account
+pub fn verify_transaction<S: TokenState + ?Sized>(state: &S, tx: &Transaction) -> Result<(), String> {
+    match config(state, &tx.from) {
+        Some(config) => config.verify(tx),
+        None => Ok(()),
+    }
+}
+
+/// Multisig registrations over a `TokenState`
+pub struct MultisigLedger<'s, S: TokenState + ?Sized> {
+    state: &'s mut S,
+}
+
+impl<'s, S: TokenState + ?Sized> MultisigLedger<'s, S> {
+    pub fn new(state: &'s mut S) -> Self {
+        MultisigLedger { state }
+    }
+
+    /// Make `account` a multisig account controlled by `config`
+    pub fn register(&mut self, account: &str, config: MultisigConfig) -> Result<(), String> {
+        config.validate()?;
+        let bytes = bincode::encode_to_vec(&config, standard()).expect("Multisig config encodes");
+        self.state.put(config_key(account), bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::collections::HashMap;
+
+    fn encode_signatures(signatures: &[KeySignature]) -> Vec<u8> {
+        bincode::encode_to_vec(signatures, standard()).unwrap()
+    }
+
+    fn partial(key: &SigningKey, key_index: u32, tx: &Transaction) -> KeySignature {
+        KeySignature { key_index, signature: key.sign(&tx.signing_bytes()).to_bytes().to_vec() }
+    }
+
+    #[test]
+    fn test_transactions_need_threshold_of_registered_signers() {
+        let keys: Vec<SigningKey> = (1..=3u8).map(|seed| SigningKey::from_bytes(&[seed; 32])).collect();
+        let public_keys = keys.iter().map(|key| key.verifying_key().to_bytes().to_vec()).collect();
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut ledger = MultisigLedger::new(&mut state);
+        let invalid = MultisigConfig { public_keys: vec![vec![1; 32]], threshold: 2 };
+        assert!(ledger.register("treasury", invalid).is_err());
+        ledger.register("treasury", MultisigConfig { public_keys, threshold: 2 }).unwrap();
+
+        let mut tx = Transaction::transfer("treasury".to_string(), "bob".to_string(), 10);
+        assert!(verify_transaction(&state, &tx).is_err());
+        tx.signature = encode_signatures(&[partial(&keys[0], 0, &tx)]);
+        assert!(verify_transaction(&state, &tx).unwrap_err().contains("needs 2 signatures, got 1"));
+        tx.signature = encode_signatures(&[partial(&keys[0], 0, &tx), partial(&keys[0], 0, &tx)]);
+        assert_eq!(verify_transaction(&state, &tx), Err("Key 0 signed twice".to_string()));
+        tx.signature = encode_signatures(&[partial(&keys[2], 0, &tx), partial(&keys[1], 1, &tx)]);
+        assert_eq!(verify_transaction(&state, &tx), Err("Invalid signature by key 0".to_string()));
+        tx.signature = encode_signatures(&[partial(&keys[2], 2, &tx), partial(&keys[0], 0, &tx)]);
+        assert!(verify_transaction(&state, &tx).is_ok());
+
+        // Accounts without a config are left to the single-key checks
+        tx.from = "alice".to_string();
+        assert!(verify_transaction(&state, &tx).is_ok());
+    }
+}
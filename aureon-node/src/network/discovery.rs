@@ -0,0 +1,133 @@
+//! Kademlia-style peer discovery
+//!
+//! Known peers are kept in k-buckets indexed by the length of the common
+//! prefix between their id and the local id. Nodes answer `FindNode`
+//! lookups with the closest peers they know by XOR distance, which lets a
+//! node reach the whole network from a handful of bootstrap peers.
+
+use super::identity::PeerId;
+use serde::{Deserialize, Serialize};
+
+/// Maximum peers per bucket
+pub const K: usize = 16;
+
+/// Number of peers queried per lookup round
+pub const ALPHA: usize = 3;
+
+/// A peer and the address it accepts connections on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub peer_id: PeerId,
+    pub address: String,
+}
+
+/// K-bucket routing table around the local peer id
+pub struct RoutingTable {
+    local: PeerId,
+    buckets: Vec<Vec<PeerRecord>>,
+}
+
+impl RoutingTable {
+    pub fn new(local: PeerId) -> Self {
+        RoutingTable {
+            local,
+            buckets: vec![Vec::new(); 256],
+        }
+    }
+
+    /// Bucket for `peer`: the number of leading bits shared with the local id
+    fn bucket_index(&self, peer: &PeerId) -> usize {
+        let distance = self.local.distance(peer);
+        let mut leading = 0;
+        for byte in distance {
+            if byte == 0 {
+                leading += 8;
+            } else {
+                leading += byte.leading_zeros() as usize;
+                break;
+            }
+        }
+        leading.min(255)
+    }
+
+    /// Add or refresh a peer; returns false if it was dropped
+    ///
+    /// Refreshed peers move to the tail of their bucket. When a bucket is
+    /// full the new peer is dropped, favouring long-lived peers.
+    pub fn insert(&mut self, record: PeerRecord) -> bool {
+        if record.peer_id == self.local {
+            return false;
+        }
+        let index = self.bucket_index(&record.peer_id);
+        let bucket = &mut self.buckets[index];
+        if let Some(position) = bucket.iter().position(|r| r.peer_id == record.peer_id) {
+            bucket.remove(position);
+        } else if bucket.len() >= K {
+            return false;
+        }
+        bucket.push(record);
+        true
+    }
+
+    pub fn remove(&mut self, peer: &PeerId) {
+        let index = self.bucket_index(peer);
+        self.buckets[index].retain(|r| r.peer_id != *peer);
+    }
+
+    pub fn get(&self, peer: &PeerId) -> Option<&PeerRecord> {
+        self.buckets[self.bucket_index(peer)].iter().find(|r| r.peer_id == *peer)
+    }
+
+    /// Up to `count` known peers closest to `target`
+    pub fn closest(&self, target: &PeerId, count: usize) -> Vec<PeerRecord> {
+        let mut records: Vec<&PeerRecord> = self.buckets.iter().flatten().collect();
+        records.sort_by_key(|r| r.peer_id.distance(target));
+        records.into_iter().take(count).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(Vec::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(peer_id: PeerId) -> PeerRecord {
+        PeerRecord {
+            peer_id,
+            address: "127.0.0.1:6000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_closest_orders_by_xor_distance() {
+        let local = PeerId::random();
+        let mut table = RoutingTable::new(local);
+        // Fewer than K peers, so no bucket can overflow
+        let mut peers: Vec<PeerId> = (0..10).map(|_| PeerId::random()).collect();
+        for peer in &peers {
+            assert!(table.insert(record(*peer)));
+        }
+        assert!(!table.insert(record(local)));
+
+        let target = PeerId::random();
+        peers.sort_by_key(|p| p.distance(&target));
+        let closest: Vec<PeerId> = table.closest(&target, ALPHA).into_iter().map(|r| r.peer_id).collect();
+        assert_eq!(closest, peers[..ALPHA]);
+    }
+
+    #[test]
+    fn test_insert_refresh_and_remove() {
+        let mut table = RoutingTable::new(PeerId::random());
+        let peer = PeerId::random();
+        assert!(table.insert(record(peer)));
+        assert!(table.insert(PeerRecord { peer_id: peer, address: "127.0.0.1:7000".into() }));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get(&peer).unwrap().address, "127.0.0.1:7000");
+
+        table.remove(&peer);
+        assert_eq!(table.len(), 0);
+    }
+}
@@ -0,0 +1,110 @@
+//! Topic-based gossip
+//!
+//! Messages are published on a topic and flooded by the origin to every
+//! connected peer subscribed to it. Relaying peers forward each message they
+//! have not seen before to a random subset of subscribed peers (the mesh),
+//! so messages reach the whole network without every node re-sending to
+//! every peer. Message ids are derived from the origin and its sequence
+//! number and remembered for a while to drop duplicates.
+
+use super::identity::PeerId;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+
+/// Number of peers a relayed message is forwarded to
+pub const MESH_DEGREE: usize = 6;
+
+/// Number of message ids remembered for duplicate suppression
+pub const SEEN_CACHE_SIZE: usize = 10_000;
+
+/// Gossip topics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Blocks,
+    Transactions,
+    Votes,
+    /// Peer status and block sync requests
+    Sync,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 4] = [Topic::Blocks, Topic::Transactions, Topic::Votes, Topic::Sync];
+}
+
+/// Id of the `seqno`-th message published by `source`
+pub fn message_id(source: &PeerId, seqno: u64) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    hasher.update(seqno.to_be_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Pick up to `degree` relay targets at random
+pub fn select_mesh(mut candidates: Vec<PeerId>, degree: usize) -> Vec<PeerId> {
+    candidates.shuffle(&mut rand::thread_rng());
+    candidates.truncate(degree);
+    candidates
+}
+
+/// Bounded set of recently seen message ids
+pub struct SeenCache {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize) -> Self {
+        SeenCache {
+            capacity,
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+        }
+    }
+
+    /// Remember `id`; returns false if it was already seen
+    pub fn insert(&mut self, id: &str) -> bool {
+        if self.ids.contains(id) {
+            return false;
+        }
+        if self.order.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+        self.order.push_back(id.to_string());
+        self.ids.insert(id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seen_cache_drops_duplicates_and_evicts() {
+        let mut seen = SeenCache::new(2);
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+        assert!(seen.insert("c"));
+        // "a" was evicted to make room for "c"
+        assert!(seen.insert("a"));
+    }
+
+    #[test]
+    fn test_message_ids_and_mesh() {
+        let source = PeerId::random();
+        assert_eq!(message_id(&source, 1), message_id(&source, 1));
+        assert_ne!(message_id(&source, 1), message_id(&source, 2));
+
+        let peers: Vec<PeerId> = (0..10).map(|_| PeerId::random()).collect();
+        let mesh = select_mesh(peers.clone(), MESH_DEGREE);
+        assert_eq!(mesh.len(), MESH_DEGREE);
+        assert!(mesh.iter().all(|p| peers.contains(p)));
+    }
+}
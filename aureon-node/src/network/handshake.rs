@@ -0,0 +1,103 @@
+//! Chain handshake
+//!
+//! Once the secure session is up, each side sends a PeerInfo signed with its
+//! identity key in its Hello frame. It names the chain the node follows, by
+//! chain id and genesis hash, and the protocol version it speaks. Peers that
+//! follow another chain, speak another protocol version, or send no valid
+//! signed PeerInfo are disconnected before any other frame is handled.
+
+use super::identity::{self, NodeIdentity, PeerId};
+use super::NetworkError;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Version of the peer protocol; peers must match it exactly
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const DOMAIN: &[u8] = b"aureon-peer-info/1";
+
+/// Chain a node follows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainInfo {
+    pub chain_id: String,
+    pub genesis_hash: String,
+}
+
+/// Node description signed by the node's identity key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedPeerInfo {
+    pub chain: ChainInfo,
+    pub protocol_version: u32,
+    pub node_id: String,
+    pub version: String,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedPeerInfo {
+    pub fn new(identity: &NodeIdentity, chain: ChainInfo, node_id: String, version: String) -> Self {
+        let mut info = SignedPeerInfo {
+            chain,
+            protocol_version: PROTOCOL_VERSION,
+            node_id,
+            version,
+            public_key: identity.public_key().as_bytes().to_vec(),
+            signature: vec![],
+        };
+        info.signature = identity.sign(&info.signing_bytes()).to_bytes().to_vec();
+        info
+    }
+
+    fn signing_bytes(&self) -> Vec<u8> {
+        let fields = (&self.chain, self.protocol_version, &self.node_id, &self.version, &self.public_key);
+        let mut bytes = DOMAIN.to_vec();
+        bytes.extend(serde_json::to_vec(&fields).expect("Peer info serializes to JSON"));
+        bytes
+    }
+
+    /// Check that `peer` signed the info and follows `chain` with this protocol version
+    pub fn verify(&self, peer: &PeerId, chain: &ChainInfo) -> Result<(), NetworkError> {
+        let key = <[u8; 32]>::try_from(self.public_key.as_slice())
+            .ok()
+            .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            .ok_or(NetworkError::InvalidPeerKey)?;
+        if PeerId::from_public_key(&key) != *peer {
+            return Err(NetworkError::WrongIdentity);
+        }
+        let signature = Signature::from_slice(&self.signature).map_err(|_| NetworkError::MalformedSignature)?;
+        identity::verify(&key, &self.signing_bytes(), &signature).map_err(|_| NetworkError::InvalidSignature)?;
+
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(NetworkError::ProtocolMismatch { theirs: self.protocol_version, ours: PROTOCOL_VERSION });
+        }
+        if self.chain != *chain {
+            return Err(NetworkError::ChainMismatch { theirs: self.chain.clone(), ours: chain.clone() });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_info_checks_signer_chain_and_version() {
+        let identity = NodeIdentity::generate();
+        let chain = ChainInfo { chain_id: "devnet".to_string(), genesis_hash: "abc".to_string() };
+        let info = SignedPeerInfo::new(&identity, chain.clone(), "node".to_string(), "1.0.0".to_string());
+        assert!(info.verify(&identity.peer_id(), &chain).is_ok());
+        assert!(info.verify(&PeerId::random(), &chain).is_err());
+
+        let other = ChainInfo { chain_id: "devnet".to_string(), genesis_hash: "def".to_string() };
+        assert!(info.verify(&identity.peer_id(), &other).unwrap_err().to_string().contains("genesis def"));
+
+        // Changing a signed field invalidates the signature
+        let mut tampered = info.clone();
+        tampered.chain = other.clone();
+        assert_eq!(tampered.verify(&identity.peer_id(), &other), Err(NetworkError::InvalidSignature));
+        let mut outdated = info;
+        outdated.protocol_version = PROTOCOL_VERSION + 1;
+        assert!(outdated.verify(&identity.peer_id(), &chain).is_err());
+    }
+}
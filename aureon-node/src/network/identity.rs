@@ -0,0 +1,151 @@
+//! Peer identity: each node holds a long-term Ed25519 key, and its peer id
+//! is the SHA-256 of the public key.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+/// 256-bit peer identifier, also the node's position in the discovery keyspace
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PeerId([u8; 32]);
+
+impl PeerId {
+    pub fn from_public_key(key: &VerifyingKey) -> Self {
+        PeerId(Sha256::digest(key.as_bytes()).into())
+    }
+
+    /// Random id, used as a discovery lookup target
+    pub fn random() -> Self {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        PeerId(bytes)
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        let bytes = hex::decode(s).map_err(|e| format!("Invalid peer id: {}", e))?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| "Invalid peer id: expected 32 bytes".to_string())?;
+        Ok(PeerId(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// XOR distance to `other`
+    pub fn distance(&self, other: &PeerId) -> [u8; 32] {
+        let mut distance = [0u8; 32];
+        for (i, byte) in distance.iter_mut().enumerate() {
+            *byte = self.0[i] ^ other.0[i];
+        }
+        distance
+    }
+}
+
+impl fmt::Display for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PeerId({})", &hex::encode(self.0)[..16])
+    }
+}
+
+/// Long-term signing key of the local node
+#[derive(Clone)]
+pub struct NodeIdentity {
+    signing_key: SigningKey,
+}
+
+impl NodeIdentity {
+    pub fn generate() -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::from_secret(secret)
+    }
+
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        NodeIdentity {
+            signing_key: SigningKey::from_bytes(&secret),
+        }
+    }
+
+    /// Load the key stored at `path`, generating and saving a new one if absent
+    pub fn load_or_generate(path: &str) -> Result<Self, String> {
+        if Path::new(path).exists() {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read node key {}: {}", path, e))?;
+            let secret: [u8; 32] = hex::decode(contents.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| format!("Invalid node key in {}", path))?;
+            return Ok(Self::from_secret(secret));
+        }
+
+        let identity = Self::generate();
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // Readable by the node's user only
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(hex::encode(identity.signing_key.to_bytes()).as_bytes()))
+            .map_err(|e| format!("Failed to write node key {}: {}", path, e))?;
+        Ok(identity)
+    }
+
+    pub fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    pub fn peer_id(&self) -> PeerId {
+        PeerId::from_public_key(&self.public_key())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        self.signing_key.sign(message)
+    }
+}
+
+/// Check `signature` over `message` by the holder of `key`
+pub fn verify(key: &VerifyingKey, message: &[u8], signature: &Signature) -> Result<(), String> {
+    key.verify(message, signature)
+        .map_err(|_| "Invalid peer signature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_round_trip() {
+        let path = std::env::temp_dir().join(format!("aureon_node_key_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let first = NodeIdentity::load_or_generate(path).unwrap();
+        let second = NodeIdentity::load_or_generate(path).unwrap();
+        assert_eq!(first.peer_id(), second.peer_id());
+        assert_eq!(PeerId::from_hex(&first.peer_id().to_string()).unwrap(), first.peer_id());
+
+        let signature = first.sign(b"hello");
+        assert!(verify(&second.public_key(), b"hello", &signature).is_ok());
+        assert!(verify(&NodeIdentity::generate().public_key(), b"hello", &signature).is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,4 +1,12 @@
-use crate::types::Block;
+use super::discovery::PeerRecord;
+use super::gossip::Topic;
+use super::handshake::SignedPeerInfo;
+use super::identity::PeerId;
+use crate::consensus::finality::Vote;
+use crate::shard_sync::{ShardStateRequest, ShardStateResponse};
+use crate::snapshot::StateSnapshot;
+use crate::state_compression::SignedCompressedState;
+use crate::types::{Block, Transaction};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -29,6 +37,12 @@ pub enum Message {
     SyncResponse {
         blocks: Vec<Block>,
     },
+    SyncSnapshotRequest,                   // Request the peer's latest state snapshot
+    SyncSnapshot(Box<StateSnapshot>),      // Response to SyncSnapshotRequest
+    GetCompressedState,                    // Request the peer's latest compressed state for light clients
+    CompressedState(Box<SignedCompressedState>), // Response to GetCompressedState
+    ShardStateRequest(ShardStateRequest),  // Request a chunk of a shard's state
+    ShardState(Box<ShardStateResponse>),   // Response to ShardStateRequest
     
     // Peer info
     PeerInfo {
@@ -36,7 +50,17 @@ pub enum Message {
         version: String,
         latest_block_height: u64,
     },
+
+    // Peer exchange
+    GetPeers,                              // Request the peer's known peers
+    Peers(Vec<PeerRecord>),                // Response to GetPeers
     
+    // Finality votes (prevote/precommit gossip)
+    Vote(Vote),
+
+    // Pending transaction gossiped between mempools
+    Transaction(Transaction),
+
     // Legacy transaction support
     Transactions(Vec<SerializableTransaction>),
 }
@@ -53,8 +77,67 @@ impl Message {
             Message::GetBlockResponse(_) => "GetBlockResponse",
             Message::SyncRequest { .. } => "SyncRequest",
             Message::SyncResponse { .. } => "SyncResponse",
+            Message::SyncSnapshotRequest => "SyncSnapshotRequest",
+            Message::SyncSnapshot(_) => "SyncSnapshot",
+            Message::GetCompressedState => "GetCompressedState",
+            Message::CompressedState(_) => "CompressedState",
+            Message::ShardStateRequest(_) => "ShardStateRequest",
+            Message::ShardState(_) => "ShardState",
             Message::PeerInfo { .. } => "PeerInfo",
+            Message::GetPeers => "GetPeers",
+            Message::Peers(_) => "Peers",
+            Message::Vote(_) => "Vote",
+            Message::Transaction(_) => "Transaction",
             Message::Transactions(_) => "Transactions",
         }
     }
-}
\ No newline at end of file
+
+    /// Gossip topic the message is published on
+    pub fn topic(&self) -> Topic {
+        match self {
+            Message::Block(_) | Message::NewBlock(_) => Topic::Blocks,
+            Message::Transaction(_) | Message::Transactions(_) => Topic::Transactions,
+            Message::Vote(_) => Topic::Votes,
+            _ => Topic::Sync,
+        }
+    }
+}
+
+/// Unit exchanged over an encrypted peer connection
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Frame {
+    /// First frame on a connection: the sender's dialable address, topics
+    /// and signed peer info
+    Hello {
+        listen_addr: Option<String>,
+        topics: Vec<Topic>,
+        #[serde(default)]
+        info: Option<SignedPeerInfo>,
+    },
+    /// Application message published by `source`
+    Gossip {
+        topic: Topic,
+        id: String,
+        source: PeerId,
+        message: Box<Message>,
+    },
+    /// Discovery lookup for the peers closest to `target`
+    FindNode { target: PeerId },
+    /// Discovery lookup response
+    Nodes(Vec<PeerRecord>),
+    /// Message for the receiving peer only, never relayed
+    Direct(Box<Message>),
+}
+
+impl Frame {
+    /// Frame type name for metrics: the message type of gossiped and direct messages
+    pub fn kind(&self) -> &str {
+        match self {
+            Frame::Hello { .. } => "Hello",
+            Frame::Gossip { message, .. } => message.message_type(),
+            Frame::FindNode { .. } => "FindNode",
+            Frame::Nodes(_) => "Nodes",
+            Frame::Direct(message) => message.message_type(),
+        }
+    }
+}
@@ -1,15 +1,163 @@
-use std::io::{BufRead, BufReader, Write};
-use std::net::{TcpListener, TcpStream};
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-use crate::types::Block;
+use serde::Serialize;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::mpsc as async_mpsc;
+use tokio::task::AbortHandle;
 
+use crate::consensus::finality::Vote;
+use crate::error::ErrorKind;
+use crate::metrics::Metrics;
+use crate::shard_coordinator::ShardId;
+use crate::shard_sync::{ShardStateExport, ShardStateRequest, ShardStateResponse};
+use crate::snapshot::StateSnapshot;
+use crate::state_compression::SignedCompressedState;
+use crate::types::{Block, Transaction};
+
+pub mod discovery;
+pub mod gossip;
+pub mod handshake;
+pub mod identity;
 mod message;
+pub mod peer_store;
+pub mod reputation;
+pub mod secure;
 pub use message::*;
 
+use discovery::{PeerRecord, RoutingTable, ALPHA, K};
+use gossip::{message_id, select_mesh, SeenCache, Topic, MESH_DEGREE, SEEN_CACHE_SIZE};
+use handshake::{ChainInfo, SignedPeerInfo};
+use identity::{NodeIdentity, PeerId};
+use secure::TransportKey;
+use peer_store::PeerStore;
+use reputation::{Misbehavior, ReputationTable};
+
+/// Errors of the peer-to-peer layer
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NetworkError {
+    /// The connection failed while reading or writing
+    #[error("{0}")]
+    Io(String),
+    #[error("Frame of {0} bytes exceeds limit")]
+    FrameTooLarge(usize),
+    #[error("Frame too short")]
+    FrameTooShort,
+    #[error("Frame authentication failed")]
+    FrameAuthentication,
+    /// The peer's side of the secure handshake is malformed
+    #[error("{0}")]
+    Handshake(String),
+    #[error("No signed peer info")]
+    MissingPeerInfo,
+    #[error("Invalid peer info public key")]
+    InvalidPeerKey,
+    #[error("Peer info signed by another identity")]
+    WrongIdentity,
+    #[error("Malformed peer info signature")]
+    MalformedSignature,
+    #[error("Invalid peer signature")]
+    InvalidSignature,
+    #[error("Protocol version {theirs} differs from ours ({ours})")]
+    ProtocolMismatch { theirs: u32, ours: u32 },
+    #[error(
+        "Peer follows chain '{}' (genesis {}), not '{}' (genesis {})",
+        .theirs.chain_id, .theirs.genesis_hash, .ours.chain_id, .ours.genesis_hash
+    )]
+    ChainMismatch { theirs: ChainInfo, ours: ChainInfo },
+    /// The local consumer of a message type has shut down
+    #[error("Subscriber closed: {0}")]
+    SubscriberClosed(String),
+    #[error("Invalid peer id: {0}")]
+    InvalidPeerId(String),
+}
+
+impl NetworkError {
+    /// Stable identifier of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            NetworkError::Io(_) => "network_io",
+            NetworkError::FrameTooLarge(_) => "frame_too_large",
+            NetworkError::FrameTooShort | NetworkError::FrameAuthentication => "invalid_frame",
+            NetworkError::Handshake(_) => "handshake_failed",
+            NetworkError::MissingPeerInfo
+            | NetworkError::InvalidPeerKey
+            | NetworkError::WrongIdentity
+            | NetworkError::MalformedSignature
+            | NetworkError::InvalidSignature => "invalid_peer_info",
+            NetworkError::ProtocolMismatch { .. } => "protocol_mismatch",
+            NetworkError::ChainMismatch { .. } => "chain_mismatch",
+            NetworkError::SubscriberClosed(_) => "subscriber_closed",
+            NetworkError::InvalidPeerId(_) => "invalid_peer_id",
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            NetworkError::InvalidPeerId(_) => ErrorKind::InvalidRequest,
+            NetworkError::Io(_) | NetworkError::SubscriberClosed(_) => ErrorKind::Unavailable,
+            _ => ErrorKind::Conflict,
+        }
+    }
+}
+
+impl From<std::io::Error> for NetworkError {
+    fn from(e: std::io::Error) -> Self {
+        NetworkError::Io(e.to_string())
+    }
+}
+
+/// Time allowed for dialing and the secure handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between discovery lookups
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default cap on simultaneous peer connections
+pub const DEFAULT_MAX_PEERS: usize = 25;
+
+/// Default number of connections kept by dialing known peers and DNS seeds
+pub const DEFAULT_TARGET_PEERS: usize = 8;
+
+/// Frames queued for a peer's writer before further frames to it are dropped
+const OUTGOING_QUEUE_SIZE: usize = 1024;
+
+/// Shared runtime driving all peer connections
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("aureon-p2p")
+            .enable_all()
+            .build()
+            .expect("Failed to start network runtime")
+    })
+}
+
+/// Most blocks served in reply to one sync request
+pub const MAX_SYNC_BLOCKS: u64 = 128;
+
+/// A gossiped block and the peer it came from
+pub type ReceivedBlock = (PeerId, Block);
+
+/// Blocks a peer sent in reply to a sync or block request
+pub type SyncedBlocks = (PeerId, Vec<Block>);
+
+/// A shard state chunk and the peer that served it
+pub type ReceivedShardState = (PeerId, ShardStateResponse);
+
+/// Looks up local blocks by height for peers that sync from this node
+pub type BlockSource = Arc<dyn Fn(u64) -> Option<Block> + Send + Sync>;
+
 /// Represents a connected peer
 #[derive(Clone, Debug)]
 pub struct Peer {
@@ -18,123 +166,776 @@ pub struct Peer {
     pub latest_block_height: u64,
 }
 
+/// A connected peer as reported to operators
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerSummary {
+    pub peer_id: String,
+    pub address: Option<String>,
+    pub version: String,
+    pub latest_block_height: u64,
+    pub score: i32,
+}
+
+/// An authenticated connection to a peer
+struct Connection {
+    /// Distinguishes replaced connections to the same peer
+    id: u64,
+    sender: async_mpsc::Sender<Frame>,
+    topics: HashSet<Topic>,
+    /// Remote address, banned along with the peer
+    address: Option<IpAddr>,
+    /// Whether the peer's Hello carried a valid signed PeerInfo for our chain
+    verified: bool,
+}
+
+impl Connection {
+    /// Queue `frame` for the peer; dropped if a slow peer's queue is full
+    fn send(&self, frame: Frame) -> bool {
+        match self.sender.try_send(frame) {
+            Ok(()) => true,
+            Err(TrySendError::Full(frame)) => {
+                eprintln!("[Network] Outgoing queue full, dropping {} frame", frame.kind());
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
 /// P2P Network manager for blockchain synchronization
+///
+/// Peers authenticate with their identity keys over an encrypted transport,
+/// application messages travel as gossip on per-kind topics, and new peers
+/// are found through Kademlia-style lookups.
+#[derive(Clone)]
 pub struct Network {
+    /// Connected peers keyed by peer id
     peers: Arc<Mutex<HashMap<String, Peer>>>,
-    peer_streams: Arc<Mutex<Vec<TcpStream>>>,
+    connections: Arc<Mutex<HashMap<PeerId, Connection>>>,
+    routing: Arc<Mutex<RoutingTable>>,
+    seen: Arc<Mutex<SeenCache>>,
+    /// Peers with a dial in progress
+    dialing: Arc<Mutex<HashSet<PeerId>>>,
+    next_seqno: Arc<AtomicU64>,
+    next_connection_id: Arc<AtomicU64>,
+    listen_addr: Arc<Mutex<Option<String>>>,
+    discovery_started: Arc<AtomicBool>,
+    /// Listener and discovery tasks, aborted on shutdown
+    tasks: Arc<Mutex<Vec<AbortHandle>>>,
+    /// Forwards finality votes received from peers
+    vote_sender: Arc<Mutex<Option<Sender<Vote>>>>,
+    /// Forwards pending transactions received from peers
+    tx_sender: Arc<Mutex<Option<Sender<Transaction>>>>,
+    /// Forwards blocks received from peers, with their source, for validation
+    block_sender: Arc<Mutex<Option<Sender<ReceivedBlock>>>>,
+    /// Forwards state snapshots received from peers
+    snapshot_sender: Arc<Mutex<Option<Sender<StateSnapshot>>>>,
+    /// Snapshot served to peers that fast-sync from this node
+    served_snapshot: Arc<Mutex<Option<StateSnapshot>>>,
+    /// Forwards compressed states received from peers
+    compressed_state_sender: Arc<Mutex<Option<Sender<SignedCompressedState>>>>,
+    /// Compressed state served to light clients
+    served_compressed_state: Arc<Mutex<Option<SignedCompressedState>>>,
+    /// Forwards shard state chunks peers sent, with their source
+    shard_state_sender: Arc<Mutex<Option<Sender<ReceivedShardState>>>>,
+    /// Shard states served to peers syncing a shard
+    served_shard_states: Arc<Mutex<HashMap<ShardId, ShardStateExport>>>,
+    /// Forwards blocks peers sent in reply to sync and block requests
+    sync_sender: Arc<Mutex<Option<Sender<SyncedBlocks>>>>,
+    /// Blocks served to peers that sync from this node
+    block_source: Arc<Mutex<Option<BlockSource>>>,
+    reputation: Arc<Mutex<ReputationTable>>,
+    metrics: Option<Arc<Metrics>>,
+    identity: Arc<NodeIdentity>,
+    /// Static Diffie-Hellman key of the secure transport
+    transport_key: Arc<TransportKey>,
+    /// Chain peers must follow to stay connected
+    chain: ChainInfo,
+    max_peers: usize,
+    /// Connections maintained by dialing known peers and DNS seeds
+    target_peers: usize,
+    /// Known good peers, saved across restarts
+    peer_store: Option<Arc<PeerStore>>,
+    /// DNS names resolving to peer addresses, as "host:port"
+    dns_seeds: Vec<String>,
+    /// Insist on encrypted sessions even with peers that would accept plaintext
+    require_encryption: bool,
     node_id: String,
     version: String,
 }
 
-impl Clone for Network {
-    fn clone(&self) -> Self {
-        Network {
-            peers: Arc::clone(&self.peers),
-            peer_streams: Arc::clone(&self.peer_streams),
-            node_id: self.node_id.clone(),
-            version: self.version.clone(),
-        }
+impl fmt::Debug for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Network")
+            .field("node_id", &self.node_id)
+            .field("peer_id", &self.peer_id())
+            .finish_non_exhaustive()
     }
 }
 
 impl Network {
-    /// Create a new network instance
+    /// Create a new network instance with a fresh identity key
     pub fn new(node_id: String, version: String) -> Self {
+        let identity = NodeIdentity::generate();
         Network {
             peers: Arc::new(Mutex::new(HashMap::new())),
-            peer_streams: Arc::new(Mutex::new(Vec::new())),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            routing: Arc::new(Mutex::new(RoutingTable::new(identity.peer_id()))),
+            seen: Arc::new(Mutex::new(SeenCache::new(SEEN_CACHE_SIZE))),
+            dialing: Arc::new(Mutex::new(HashSet::new())),
+            next_seqno: Arc::new(AtomicU64::new(0)),
+            next_connection_id: Arc::new(AtomicU64::new(0)),
+            listen_addr: Arc::new(Mutex::new(None)),
+            discovery_started: Arc::new(AtomicBool::new(false)),
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            vote_sender: Arc::new(Mutex::new(None)),
+            tx_sender: Arc::new(Mutex::new(None)),
+            block_sender: Arc::new(Mutex::new(None)),
+            snapshot_sender: Arc::new(Mutex::new(None)),
+            served_snapshot: Arc::new(Mutex::new(None)),
+            compressed_state_sender: Arc::new(Mutex::new(None)),
+            served_compressed_state: Arc::new(Mutex::new(None)),
+            shard_state_sender: Arc::new(Mutex::new(None)),
+            served_shard_states: Arc::new(Mutex::new(HashMap::new())),
+            sync_sender: Arc::new(Mutex::new(None)),
+            block_source: Arc::new(Mutex::new(None)),
+            reputation: Arc::new(Mutex::new(ReputationTable::default())),
+            metrics: None,
+            identity: Arc::new(identity),
+            transport_key: Arc::new(TransportKey::generate()),
+            chain: ChainInfo::default(),
+            max_peers: DEFAULT_MAX_PEERS,
+            target_peers: DEFAULT_TARGET_PEERS,
+            peer_store: None,
+            dns_seeds: vec![],
+            require_encryption: true,
             node_id,
             version,
         }
     }
 
+    /// Use a persistent identity key instead of a fresh one
+    pub fn with_identity(mut self, identity: NodeIdentity) -> Self {
+        self.routing = Arc::new(Mutex::new(RoutingTable::new(identity.peer_id())));
+        self.identity = Arc::new(identity);
+        self
+    }
+
+    /// Only stay connected to peers following `chain`
+    pub fn with_chain(mut self, chain: ChainInfo) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Cap the number of simultaneous peer connections
+    pub fn with_max_peers(mut self, max_peers: usize) -> Self {
+        self.max_peers = max_peers;
+        self
+    }
+
+    /// Keep dialing known peers while fewer than `target_peers` are connected
+    pub fn with_target_peers(mut self, target_peers: usize) -> Self {
+        self.target_peers = target_peers;
+        self
+    }
+
+    /// Save peers to `store` and reconnect to the stored ones
+    pub fn with_peer_store(mut self, store: PeerStore) -> Self {
+        self.peer_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Resolve `seeds` ("host:port") for peer addresses when too few peers are known
+    pub fn with_dns_seeds(mut self, seeds: Vec<String>) -> Self {
+        self.dns_seeds = seeds;
+        self
+    }
+
+    /// Whether sessions must be encrypted; when false, sessions with peers
+    /// that also allow it carry authenticated plaintext
+    pub fn with_encryption_required(mut self, required: bool) -> Self {
+        self.require_encryption = required;
+        self
+    }
+
+    /// Refuse the addresses of banned peers for `duration`
+    pub fn with_ban_duration(mut self, duration: Duration) -> Self {
+        self.reputation = Arc::new(Mutex::new(ReputationTable::new(duration)));
+        self
+    }
+
+    /// Record peer penalties and bans in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Get current node ID
     pub fn get_node_id(&self) -> String {
         self.node_id.clone()
     }
 
+    /// Peer id derived from the node's identity key
+    pub fn peer_id(&self) -> PeerId {
+        self.identity.peer_id()
+    }
+
+    /// Address the listener is bound to, once started
+    pub fn local_addr(&self) -> Option<String> {
+        self.listen_addr.lock().unwrap().clone()
+    }
+
     /// Start TCP listener for incoming connections
     pub fn start_listener(&self, address: &str) {
-        let listener = match TcpListener::bind(address) {
+        let listener = match std::net::TcpListener::bind(address) {
             Ok(l) => l,
             Err(e) => {
                 eprintln!("Failed to bind TCP listener on {}: {}", address, e);
                 return;
             }
         };
+        if let Ok(local) = listener.local_addr() {
+            *self.listen_addr.lock().unwrap() = Some(local.to_string());
+        }
+        let _ = listener.set_nonblocking(true);
 
-        let peer_streams = Arc::clone(&self.peer_streams);
-        let peers = Arc::clone(&self.peers);
+        let _guard = runtime().enter();
+        let listener = match TcpListener::from_std(listener) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Failed to register TCP listener on {}: {}", address, e);
+                return;
+            }
+        };
 
-        thread::spawn(move || {
-            println!("[Network] Listening on TCP socket");
-            for stream in listener.incoming() {
-                if let Ok(stream) = stream {
-                    if let Ok(peer_addr) = stream.peer_addr() {
+        let network = self.clone();
+        let task = runtime().spawn(async move {
+            println!("[Network] Listening on TCP socket as {}", network.peer_id());
+            loop {
+                match listener.accept().await {
+                    Ok((stream, peer_addr)) => {
                         println!("[Network] Incoming connection from {}", peer_addr);
+                        tokio::spawn(network.clone().run_connection(stream, false, None));
                     }
-                    
-                    peer_streams.lock().unwrap().push(stream.try_clone().unwrap());
-                    
-                    let peers_clone = Arc::clone(&peers);
-                    
-                    thread::spawn(move || {
-                        if let Ok(stream) = stream.try_clone() {
-                            let reader = BufReader::new(stream);
-                            for line in reader.lines() {
-                                if let Ok(line) = line {
-                                    if let Ok(message) = serde_json::from_str::<Message>(&line) {
-                                        println!("[Network] Received {}", message.message_type());
-                                        
-                                        // Handle PeerInfo updates
-                                        if let Message::PeerInfo { 
-                                            node_id, version, latest_block_height 
-                                        } = message {
-                                            let mut peers = peers_clone.lock().unwrap();
-                                            peers.insert(node_id.clone(), Peer {
-                                                node_id,
-                                                version,
-                                                latest_block_height,
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    });
+                    Err(e) => eprintln!("[Network] Accept failed: {}", e),
                 }
             }
         });
+        self.tasks.lock().unwrap().push(task.abort_handle());
+        self.start_discovery();
     }
 
     /// Connect to a peer
+    ///
+    /// When `peer_id` is a hex peer id, the connection is dropped unless the
+    /// remote proves that identity during the handshake.
     pub fn add_peer(&self, address: &str, peer_id: Option<String>) {
-        let peer_streams = Arc::clone(&self.peer_streams);
-        let peers = Arc::clone(&self.peers);
-        let address = address.to_string();
-        let peer_id = peer_id.unwrap_or_else(|| address.clone());
-
-        thread::spawn(move || {
-            match TcpStream::connect(&address) {
-                Ok(stream) => {
+        let expected = peer_id.and_then(|id| PeerId::from_hex(&id).ok());
+        self.dial(address.to_string(), expected);
+        self.start_discovery();
+    }
+
+    fn dial(&self, address: String, expected: Option<PeerId>) {
+        if let Some(peer) = expected
+            && !self.dialing.lock().unwrap().insert(peer)
+        {
+            return;
+        }
+        let network = self.clone();
+        runtime().spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, TcpStream::connect(&address)).await {
+                Ok(Ok(stream)) => {
                     println!("[Network] Connected to peer: {}", address);
-                    
-                    if let Ok(_) = stream.try_clone() {
-                        // Register as placeholder peer (will be updated with PeerInfo)
-                        let mut ps = peers.lock().unwrap();
-                        ps.insert(peer_id.clone(), Peer {
-                            node_id: peer_id,
-                            version: "unknown".to_string(),
-                            latest_block_height: 0,
-                        });
-                        drop(ps);
+                    network.clone().run_connection(stream, true, expected).await;
+                }
+                Ok(Err(e)) => eprintln!("[Network] Failed to connect to {}: {}", address, e),
+                Err(_) => eprintln!("[Network] Timed out connecting to {}", address),
+            }
+            if let Some(peer) = expected {
+                network.dialing.lock().unwrap().remove(&peer);
+            }
+        });
+    }
+
+    /// Secure a new connection, then serve it until it closes
+    async fn run_connection(self, stream: TcpStream, initiator: bool, expected: Option<PeerId>) {
+        let address = stream.peer_addr().ok().map(|addr| addr.ip());
+        if let Some(address) = address
+            && self.reputation.lock().unwrap().is_banned(address, Instant::now())
+        {
+            println!("[Network] Refused connection from banned address {}", address);
+            return;
+        }
+        let channel = match tokio::time::timeout(
+            HANDSHAKE_TIMEOUT,
+            secure::handshake(stream, &self.identity, &self.transport_key, initiator, self.require_encryption),
+        )
+        .await
+        {
+            Ok(Ok(channel)) => channel,
+            Ok(Err(e)) => {
+                eprintln!("[Network] Handshake failed: {}", e);
+                return;
+            }
+            Err(_) => {
+                eprintln!("[Network] Handshake timed out");
+                return;
+            }
+        };
+        let remote = channel.remote;
+        if remote == self.peer_id() {
+            return;
+        }
+        if expected.is_some_and(|peer| peer != remote) {
+            eprintln!("[Network] Peer identity mismatch: got {}", remote);
+            return;
+        }
+
+        // Register the connection. If both sides dialed each other, both keep
+        // the connection initiated by the lower peer id.
+        let (sender, mut outgoing) = async_mpsc::channel(OUTGOING_QUEUE_SIZE);
+        let connection_id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut connections = self.connections.lock().unwrap();
+            let initiated_by_lower = (self.peer_id() < remote) == initiator;
+            if connections.contains_key(&remote) && !initiated_by_lower {
+                return;
+            }
+            if !connections.contains_key(&remote) && connections.len() >= self.max_peers {
+                return;
+            }
+            connections.insert(
+                remote,
+                Connection {
+                    id: connection_id,
+                    sender: sender.clone(),
+                    // Assume every topic until the peer's Hello says otherwise
+                    topics: Topic::ALL.into_iter().collect(),
+                    address,
+                    verified: false,
+                },
+            );
+        }
+        self.peers.lock().unwrap().insert(
+            remote.to_string(),
+            Peer {
+                node_id: remote.to_string(),
+                version: "unknown".to_string(),
+                latest_block_height: 0,
+            },
+        );
+        let transport = if channel.encrypted { "encrypted" } else { "authenticated plaintext" };
+        println!("[Network] Secure session established with {} ({})", remote, transport);
+
+        self.update_peer_count();
+        let mut writer = channel.writer;
+        let metrics = self.metrics.clone();
+        tokio::spawn(async move {
+            while let Some(frame) = outgoing.recv().await {
+                let bytes = match serde_json::to_vec(&frame) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        eprintln!("[Network] Failed to serialize frame: {}", e);
+                        continue;
+                    }
+                };
+                if writer.write_frame(&bytes).await.is_err() {
+                    break;
+                }
+                if let Some(metrics) = &metrics {
+                    metrics.messages_sent.with_label_values(&[frame.kind()]).inc();
+                }
+            }
+            let _ = writer.close().await;
+        });
+
+        let info = SignedPeerInfo::new(&self.identity, self.chain.clone(), self.node_id.clone(), self.version.clone());
+        let _ = sender.try_send(Frame::Hello {
+            listen_addr: self.local_addr(),
+            topics: Topic::ALL.to_vec(),
+            info: Some(info),
+        });
+        let _ = sender.try_send(Frame::FindNode { target: self.peer_id() });
+        // The writer stops and closes the stream once the registered sender is dropped
+        drop(sender);
+
+        let mut reader = channel.reader;
+        while let Ok(bytes) = reader.read_frame().await {
+            match serde_json::from_slice::<Frame>(&bytes) {
+                Ok(frame) => {
+                    if let Some(metrics) = &self.metrics {
+                        metrics.messages_received.with_label_values(&[frame.kind()]).inc();
+                    }
+                    self.handle_frame(remote, frame);
+                }
+                Err(e) => {
+                    eprintln!("[Network] Malformed frame from {}: {}", remote, e);
+                    self.report_peer(&remote, Misbehavior::InvalidMessage);
+                }
+            }
+            // Stop reading once the peer is banned or replaced
+            if self.connections.lock().unwrap().get(&remote).is_none_or(|c| c.id != connection_id) {
+                break;
+            }
+        }
+
+        self.reputation.lock().unwrap().disconnected(&remote);
+        let mut connections = self.connections.lock().unwrap();
+        if connections.get(&remote).is_some_and(|c| c.id == connection_id) {
+            connections.remove(&remote);
+            drop(connections);
+            self.peers.lock().unwrap().remove(&remote.to_string());
+            self.update_peer_count();
+            println!("[Network] Peer {} disconnected", remote);
+        }
+    }
+
+    /// Publish the number of connected peers
+    fn update_peer_count(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.peers_connected.set(self.connections.lock().unwrap().len() as i64);
+        }
+    }
+
+    /// Route a frame received from a connected peer
+    fn handle_frame(&self, from: PeerId, frame: Frame) {
+        if self.reputation.lock().unwrap().record_message(&from, Instant::now()) {
+            self.report_peer(&from, Misbehavior::Spam);
+        }
+        let verified = self.connections.lock().unwrap().get(&from).is_some_and(|c| c.verified);
+        if !verified && !matches!(frame, Frame::Hello { .. }) {
+            eprintln!("[Network] Peer {} sent {} before its Hello", from, frame.kind());
+            self.disconnect_peer(&from);
+            return;
+        }
+        match frame {
+            Frame::Hello { listen_addr, topics, info } => {
+                let info = info
+                    .ok_or(NetworkError::MissingPeerInfo)
+                    .and_then(|info| info.verify(&from, &self.chain).map(|_| info));
+                let info = match info {
+                    Ok(info) => info,
+                    Err(e) => {
+                        eprintln!("[Network] Rejected handshake from {}: {}", from, e);
+                        self.disconnect_peer(&from);
+                        return;
+                    }
+                };
+                if let Some(connection) = self.connections.lock().unwrap().get_mut(&from) {
+                    connection.topics = topics.into_iter().collect();
+                    connection.verified = true;
+                }
+                if let Some(peer) = self.peers.lock().unwrap().get_mut(&from.to_string()) {
+                    peer.node_id = info.node_id;
+                    peer.version = info.version;
+                }
+                if let Some(address) = listen_addr {
+                    let record = PeerRecord { peer_id: from, address };
+                    if let Some(store) = &self.peer_store {
+                        store.save(&record);
+                    }
+                    self.routing.lock().unwrap().insert(record);
+                }
+            }
+            Frame::FindNode { target } => {
+                let closest = self.routing.lock().unwrap().closest(&target, K);
+                self.send_to(&from, Frame::Nodes(closest));
+            }
+            Frame::Nodes(records) => self.learn_peers(records),
+            Frame::Direct(message) => match *message {
+                Message::GetPeers => {
+                    let known = self.routing.lock().unwrap().closest(&from, K);
+                    self.send_to(&from, Frame::Direct(Box::new(Message::Peers(known))));
+                }
+                Message::Peers(records) => self.learn_peers(records),
+                Message::SyncSnapshotRequest => {
+                    let snapshot = self.served_snapshot.lock().unwrap().clone();
+                    if let Some(snapshot) = snapshot {
+                        let reply = Message::SyncSnapshot(Box::new(snapshot));
+                        self.send_to(&from, Frame::Direct(Box::new(reply)));
+                    }
+                }
+                Message::GetCompressedState => {
+                    let state = self.served_compressed_state.lock().unwrap().clone();
+                    if let Some(state) = state {
+                        let reply = Message::CompressedState(Box::new(state));
+                        self.send_to(&from, Frame::Direct(Box::new(reply)));
+                    }
+                }
+                Message::ShardStateRequest(request) => {
+                    let chunk = self
+                        .served_shard_states
+                        .lock()
+                        .unwrap()
+                        .get(&request.shard_id)
+                        .and_then(|state| state.serve(&request));
+                    if let Some(chunk) = chunk {
+                        self.send_to(&from, Frame::Direct(Box::new(Message::ShardState(Box::new(chunk)))));
+                    }
+                }
+                Message::ShardState(chunk) => {
+                    if let Some(sender) = self.shard_state_sender.lock().unwrap().as_ref() {
+                        let _ = sender.send((from, *chunk));
+                    }
+                }
+                Message::SyncRequest { from_height, to_height } => {
+                    let to_height = to_height.min(from_height.saturating_add(MAX_SYNC_BLOCKS - 1));
+                    let blocks = match self.block_source.lock().unwrap().as_ref() {
+                        Some(source) => (from_height..=to_height).map_while(|height| source(height)).collect(),
+                        None => vec![],
+                    };
+                    self.send_to(&from, Frame::Direct(Box::new(Message::SyncResponse { blocks })));
+                }
+                Message::GetBlock(height) => {
+                    let block = self.block_source.lock().unwrap().as_ref().and_then(|source| source(height));
+                    self.send_to(&from, Frame::Direct(Box::new(Message::GetBlockResponse(block))));
+                }
+                Message::SyncResponse { blocks } => self.deliver_synced(from, blocks),
+                Message::GetBlockResponse(block) => self.deliver_synced(from, block.into_iter().collect()),
+                message => self.deliver(&from, message),
+            },
+            Frame::Gossip { topic, id, source, message } => {
+                if !self.seen.lock().unwrap().insert(&id) {
+                    return;
+                }
+                // Blocks are relayed by their importer once validated
+                if topic != Topic::Blocks {
+                    self.relay(&from, &source, topic, &id, &message);
+                }
+                self.deliver(&source, *message);
+            }
+        }
+    }
+
+    /// Add peers learned from a lookup or peer exchange to the routing
+    /// table, dialing them while there is room
+    fn learn_peers(&self, records: Vec<PeerRecord>) {
+        for record in records {
+            if record.peer_id == self.peer_id() {
+                continue;
+            }
+            self.routing.lock().unwrap().insert(record.clone());
+            let connections = self.connections.lock().unwrap();
+            if !connections.contains_key(&record.peer_id) && connections.len() < self.max_peers {
+                drop(connections);
+                self.dial(record.address, Some(record.peer_id));
+            }
+        }
+    }
+
+    /// Hand a gossiped message to local consumers
+    fn deliver(&self, source: &PeerId, message: Message) {
+        println!("[Network] Received {}", message.message_type());
+        if let Message::Block(block) = message {
+            // Blocks go to the importer with their source, which is scored on the outcome
+            if let Some(sender) = self.block_sender.lock().unwrap().as_ref() {
+                let _ = sender.send((*source, block));
+            }
+            return;
+        }
+        if let Message::PeerInfo { node_id, version, latest_block_height } = &message
+            && let Some(peer) = self.peers.lock().unwrap().get_mut(&source.to_string())
+        {
+            peer.node_id = node_id.clone();
+            peer.version = version.clone();
+            peer.latest_block_height = *latest_block_height;
+        }
+        if let Err(e) = self.handle_message(message) {
+            eprintln!("[Network] Failed to handle message: {}", e);
+        }
+    }
+
+    /// Hand blocks a peer sent in reply to a request to the sync subscriber
+    fn deliver_synced(&self, from: PeerId, blocks: Vec<Block>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.sync_blocks_received.inc_by(blocks.len() as u64);
+        }
+        if let Some(sender) = self.sync_sender.lock().unwrap().as_ref() {
+            let _ = sender.send((from, blocks));
+        }
+    }
+
+    /// Forward a message received from `from` to a random subset of subscribers
+    fn relay(&self, from: &PeerId, source: &PeerId, topic: Topic, id: &str, message: &Message) {
+        let connections = self.connections.lock().unwrap();
+        let candidates: Vec<PeerId> = connections
+            .iter()
+            .filter(|(peer, c)| *peer != from && *peer != source && c.topics.contains(&topic))
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in select_mesh(candidates, MESH_DEGREE) {
+            let _ = connections[&peer].send(Frame::Gossip {
+                topic,
+                id: id.to_string(),
+                source: *source,
+                message: Box::new(message.clone()),
+            });
+        }
+    }
+
+    fn send_to(&self, peer: &PeerId, frame: Frame) {
+        if let Some(connection) = self.connections.lock().unwrap().get(peer) {
+            let _ = connection.send(frame);
+        }
+    }
+
+    /// While below the target peer count, dial stored and known peers, ask
+    /// connected peers for theirs, and fall back to DNS seeds
+    async fn maintain_peers(&self) {
+        let connected: HashSet<PeerId> = self.connections.lock().unwrap().keys().copied().collect();
+        let mut missing = self.target_peers.saturating_sub(connected.len());
+        if missing == 0 {
+            return;
+        }
+        for peer in &connected {
+            self.send_to(peer, Frame::Direct(Box::new(Message::GetPeers)));
+        }
+
+        let mut candidates = self.peer_store.as_ref().map(|store| store.load()).unwrap_or_default();
+        let known = self.routing.lock().unwrap().closest(&self.peer_id(), usize::MAX);
+        let mut addresses: HashSet<String> = known.iter().map(|record| record.address.clone()).collect();
+        addresses.extend(self.local_addr());
+        candidates.extend(known);
+        let mut tried = HashSet::new();
+        for record in candidates {
+            if missing == 0 {
+                return;
+            }
+            if !connected.contains(&record.peer_id) && tried.insert(record.peer_id) {
+                self.dial(record.address, Some(record.peer_id));
+                missing -= 1;
+            }
+        }
+
+        for seed in &self.dns_seeds {
+            match tokio::net::lookup_host(seed.as_str()).await {
+                Ok(resolved) => {
+                    for address in resolved.map(|address| address.to_string()) {
+                        if missing == 0 {
+                            return;
+                        }
+                        if addresses.insert(address.clone()) {
+                            self.dial(address, None);
+                            missing -= 1;
+                        }
+                    }
+                }
+                Err(e) => eprintln!("[Network] Failed to resolve DNS seed {}: {}", seed, e),
+            }
+        }
+    }
+
+    /// Periodically keep up the peer count and look up peers near the local
+    /// id and a random id
+    fn start_discovery(&self) {
+        if self.discovery_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let network = self.clone();
+        let task = runtime().spawn(async move {
+            let mut interval = tokio::time::interval(DISCOVERY_INTERVAL);
+            loop {
+                interval.tick().await;
+                network.maintain_peers().await;
+                for target in [network.peer_id(), PeerId::random()] {
+                    let connected: Vec<PeerId> = network.connections.lock().unwrap().keys().copied().collect();
+                    for peer in select_mesh(connected, ALPHA) {
+                        network.send_to(&peer, Frame::FindNode { target });
                     }
-                    
-                    peer_streams.lock().unwrap().push(stream);
                 }
-                Err(e) => eprintln!("[Network] Failed to connect to {}: {}", address, e),
             }
         });
+        self.tasks.lock().unwrap().push(task.abort_handle());
+    }
+
+    /// Stop accepting connections and discovering peers, and close every
+    /// peer connection
+    pub fn shutdown(&self) {
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        // Dropping a connection's sender stops its writer, which closes the stream
+        let closed = self.connections.lock().unwrap().drain().count();
+        self.peers.lock().unwrap().clear();
+        self.update_peer_count();
+        println!("[Network] Closed {} peer connections", closed);
+    }
+
+    /// Lower `peer`'s score for `misbehavior`, banning it at the threshold
+    pub fn report_peer(&self, peer: &PeerId, misbehavior: Misbehavior) {
+        let banned = self.reputation.lock().unwrap().penalize(peer, misbehavior);
+        if let Some(metrics) = &self.metrics {
+            metrics.peer_penalties.with_label_values(&[misbehavior.label()]).inc();
+        }
+        eprintln!("[Network] Penalized peer {} for {}", peer, misbehavior.label());
+        if banned {
+            self.ban_peer(peer);
+        }
+    }
+
+    /// Raise `peer`'s score after it sent a valid block
+    pub fn reward_peer(&self, peer: &PeerId) {
+        self.reputation.lock().unwrap().reward(peer);
+    }
+
+    /// Disconnect `peer` and refuse its address for the ban duration,
+    /// returning whether it was connected
+    pub fn ban_peer(&self, peer: &PeerId) -> bool {
+        let connection = self.connections.lock().unwrap().remove(peer);
+        self.peers.lock().unwrap().remove(&peer.to_string());
+        self.update_peer_count();
+        let connected = connection.is_some();
+        let banned = {
+            let mut reputation = self.reputation.lock().unwrap();
+            if let Some(address) = connection.and_then(|c| c.address) {
+                reputation.ban(peer, address, Instant::now());
+            }
+            reputation.banned_count()
+        };
+        self.routing.lock().unwrap().remove(peer);
+        if let Some(store) = &self.peer_store {
+            store.remove(peer);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.peer_bans.inc();
+            metrics.banned_peers.set(banned as i64);
+        }
+        println!("[Network] Banned peer {}", peer);
+        connected
+    }
+
+    /// Close the connection to `peer`, returning whether it was connected
+    pub fn disconnect_peer(&self, peer: &PeerId) -> bool {
+        // Dropping the connection's sender closes the stream
+        let connection = self.connections.lock().unwrap().remove(peer);
+        self.peers.lock().unwrap().remove(&peer.to_string());
+        self.update_peer_count();
+        if connection.is_some() {
+            println!("[Network] Disconnected peer {}", peer);
+        }
+        connection.is_some()
+    }
+
+    /// Connected peers with their addresses and reputation scores
+    pub fn peer_summaries(&self) -> Vec<PeerSummary> {
+        let connections = self.connections.lock().unwrap();
+        let peers = self.peers.lock().unwrap();
+        let reputation = self.reputation.lock().unwrap();
+        let mut summaries: Vec<PeerSummary> = connections
+            .iter()
+            .map(|(peer, connection)| {
+                let info = peers.get(&peer.to_string());
+                PeerSummary {
+                    peer_id: peer.to_string(),
+                    address: connection.address.map(|address| address.to_string()),
+                    version: info.map_or_else(|| "unknown".to_string(), |info| info.version.clone()),
+                    latest_block_height: info.map_or(0, |info| info.latest_block_height),
+                    score: reputation.score(peer),
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        summaries
     }
 
     /// Get number of connected peers
@@ -142,6 +943,16 @@ impl Network {
         self.peers.lock().unwrap().len()
     }
 
+    /// Connected peers with the latest block height they reported
+    pub fn sync_peers(&self) -> Vec<(PeerId, u64)> {
+        let connections = self.connections.lock().unwrap();
+        let peers = self.peers.lock().unwrap();
+        connections
+            .keys()
+            .map(|peer| (*peer, peers.get(&peer.to_string()).map_or(0, |info| info.latest_block_height)))
+            .collect()
+    }
+
     /// Get highest block height from peers
     pub fn get_highest_peer_height(&self) -> u64 {
         self.peers
@@ -153,38 +964,147 @@ impl Network {
             .unwrap_or(0)
     }
 
-    /// Broadcast message to all peers
+    /// Publish a message on its gossip topic to all subscribed peers
     pub fn broadcast(&self, message: &Message) {
-        let peer_streams = self.peer_streams.lock().unwrap();
-        let data = match serde_json::to_string(&message) {
-            Ok(d) => d,
-            Err(e) => {
-                eprintln!("[Network] Failed to serialize message: {}", e);
-                return;
-            }
-        };
+        let topic = message.topic();
+        let seqno = self.next_seqno.fetch_add(1, Ordering::Relaxed);
+        let id = message_id(&self.peer_id(), seqno);
+        self.seen.lock().unwrap().insert(&id);
 
-        for peer in peer_streams.iter() {
-            if let Ok(mut stream) = peer.try_clone() {
-                let _ = stream.write_all(data.as_bytes());
-                let _ = stream.write_all(b"\n");
-                let _ = stream.flush();
-            }
+        let connections = self.connections.lock().unwrap();
+        for connection in connections.values().filter(|c| c.topics.contains(&topic)) {
+            let _ = connection.send(Frame::Gossip {
+                topic,
+                id: id.clone(),
+                source: self.peer_id(),
+                message: Box::new(message.clone()),
+            });
         }
     }
 
     /// Broadcast a block to all peers
     pub fn broadcast_block(&self, block: &Block) {
         let message = Message::Block(block.clone());
-        println!("[Network] Broadcasting block");
+        tracing::info!(block_hash = %block.hash, peers = self.peer_count(), "broadcasting block");
         self.broadcast(&message);
     }
 
-    /// Request a specific block from peers
-    pub fn request_block(&self, height: u64) {
-        let message = Message::GetBlock(height);
-        println!("[Network] Requesting block #{}", height);
-        self.broadcast(&message);
+    /// Receive blocks gossiped by peers along with the peer that sent them;
+    /// blocks are not relayed until the subscriber validates and re-broadcasts them
+    /// Replaces any previous subscriber
+    pub fn subscribe_blocks(&self) -> Receiver<ReceivedBlock> {
+        let (sender, receiver) = mpsc::channel();
+        *self.block_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Gossip a finality vote to all peers
+    pub fn broadcast_vote(&self, vote: &Vote) {
+        self.broadcast(&Message::Vote(vote.clone()));
+    }
+
+    /// Receive finality votes gossiped by peers
+    /// Replaces any previous subscriber
+    pub fn subscribe_votes(&self) -> Receiver<Vote> {
+        let (sender, receiver) = mpsc::channel();
+        *self.vote_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Gossip a pending transaction to all peers
+    pub fn broadcast_transaction(&self, tx: &Transaction) {
+        tracing::debug!(peers = self.peer_count(), "broadcasting transaction");
+        self.broadcast(&Message::Transaction(tx.clone()));
+    }
+
+    /// Receive pending transactions gossiped by peers
+    /// Replaces any previous subscriber
+    pub fn subscribe_transactions(&self) -> Receiver<Transaction> {
+        let (sender, receiver) = mpsc::channel();
+        *self.tx_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Serve `snapshot` to peers that fast-sync from this node
+    pub fn serve_snapshot(&self, snapshot: StateSnapshot) {
+        *self.served_snapshot.lock().unwrap() = Some(snapshot);
+    }
+
+    /// Ask every connected peer for its latest state snapshot
+    pub fn request_snapshot(&self) {
+        let request = Message::SyncSnapshotRequest;
+        for connection in self.connections.lock().unwrap().values() {
+            let _ = connection.send(Frame::Direct(Box::new(request.clone())));
+        }
+    }
+
+    /// Receive state snapshots sent by peers
+    /// Replaces any previous subscriber
+    pub fn subscribe_snapshots(&self) -> Receiver<StateSnapshot> {
+        let (sender, receiver) = mpsc::channel();
+        *self.snapshot_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Serve `state` to peers syncing its shard, replacing the shard's previous state
+    #[allow(dead_code)]
+    pub fn serve_shard_state(&self, state: ShardStateExport) {
+        self.served_shard_states.lock().unwrap().insert(state.shard_id, state);
+    }
+
+    /// Ask `peer` for a chunk of a shard's state; false if it is not connected
+    pub fn request_shard_state(&self, peer: &PeerId, request: ShardStateRequest) -> bool {
+        self.request_from(peer, Message::ShardStateRequest(request))
+    }
+
+    /// Receive shard state chunks peers sent, with their source
+    /// Replaces any previous subscriber
+    pub fn subscribe_shard_states(&self) -> Receiver<ReceivedShardState> {
+        let (sender, receiver) = mpsc::channel();
+        *self.shard_state_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Serve `state` to light clients fetching compressed state from this node
+    pub fn serve_compressed_state(&self, state: SignedCompressedState) {
+        *self.served_compressed_state.lock().unwrap() = Some(state);
+    }
+
+    /// Ask every connected peer for its latest compressed state
+    #[allow(dead_code)]
+    pub fn request_compressed_state(&self) {
+        let request = Message::GetCompressedState;
+        for connection in self.connections.lock().unwrap().values() {
+            let _ = connection.send(Frame::Direct(Box::new(request.clone())));
+        }
+    }
+
+    /// Receive compressed states sent by peers
+    /// Replaces any previous subscriber
+    #[allow(dead_code)]
+    pub fn subscribe_compressed_states(&self) -> Receiver<SignedCompressedState> {
+        let (sender, receiver) = mpsc::channel();
+        *self.compressed_state_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Serve blocks from `source` to peers that sync from this node
+    pub fn serve_blocks(&self, source: BlockSource) {
+        *self.block_source.lock().unwrap() = Some(source);
+    }
+
+    /// Receive blocks peers sent in reply to sync and block requests
+    /// Replaces any previous subscriber
+    pub fn subscribe_synced_blocks(&self) -> Receiver<SyncedBlocks> {
+        let (sender, receiver) = mpsc::channel();
+        *self.sync_sender.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    /// Request a specific block from `peer`; false if it is not connected
+    pub fn request_block(&self, peer: &PeerId, height: u64) -> bool {
+        println!("[Network] Requesting block #{} from {}", height, peer);
+        self.request_from(peer, Message::GetBlock(height))
     }
 
     /// Broadcast peer info to all peers
@@ -197,14 +1117,24 @@ impl Network {
         self.broadcast(&message);
     }
 
-    /// Request block range for synchronization
-    pub fn request_sync(&self, from_height: u64, to_height: u64) {
+    /// Request a block range from `peer` for synchronization; false if it is not connected
+    pub fn request_sync(&self, peer: &PeerId, from_height: u64, to_height: u64) -> bool {
         let message = Message::SyncRequest {
             from_height,
             to_height,
         };
-        println!("[Network] Requesting sync blocks #{}-#{}", from_height, to_height);
-        self.broadcast(&message);
+        println!("[Network] Requesting sync blocks #{}-#{} from {}", from_height, to_height, peer);
+        if let Some(metrics) = &self.metrics {
+            metrics.sync_target_height.set(to_height as i64);
+        }
+        self.request_from(peer, message)
+    }
+
+    fn request_from(&self, peer: &PeerId, message: Message) -> bool {
+        match self.connections.lock().unwrap().get(peer) {
+            Some(connection) => connection.send(Frame::Direct(Box::new(message))),
+            None => false,
+        }
     }
 
     /// Listen on address (convenience method)
@@ -215,7 +1145,7 @@ impl Network {
 
     /// Handle incoming message (called by network listener)
     /// In a full implementation, this would route to appropriate handlers
-    pub fn handle_message(&self, message: Message) -> Result<(), String> {
+    pub fn handle_message(&self, message: Message) -> Result<(), NetworkError> {
         match message {
             Message::Ping => {
                 self.broadcast(&Message::Pong);
@@ -223,7 +1153,7 @@ impl Network {
             }
             Message::Pong => Ok(()), // Just for health checks
             Message::PeerInfo { node_id, version, latest_block_height } => {
-                // Update peer info (already done in listener)
+                // Update peer info (already recorded on delivery)
                 println!("[Network] Peer {} height: {}", node_id, latest_block_height);
                 Ok(())
             }
@@ -242,6 +1172,7 @@ impl Network {
                 Ok(())
             }
             Message::Block(block) => {
+                // Gossiped blocks reach the importer through `deliver`, which knows their source
                 println!("[Network] Received block broadcast, hash: {}", block.hash);
                 Ok(())
             }
@@ -250,7 +1181,34 @@ impl Network {
                 Ok(())
             }
             Message::SyncResponse { blocks } => {
-                println!("[Network] Received {} blocks for sync", blocks.len());
+                // Replies to this node's requests arrive as direct frames
+                println!("[Network] Received {} unrequested sync blocks", blocks.len());
+                Ok(())
+            }
+            Message::Vote(vote) => {
+                if let Some(sender) = self.vote_sender.lock().unwrap().as_ref() {
+                    sender.send(vote).map_err(|e| NetworkError::SubscriberClosed(e.to_string()))?;
+                }
+                Ok(())
+            }
+            Message::Transaction(tx) => {
+                if let Some(sender) = self.tx_sender.lock().unwrap().as_ref() {
+                    sender.send(tx).map_err(|e| NetworkError::SubscriberClosed(e.to_string()))?;
+                }
+                Ok(())
+            }
+            Message::SyncSnapshot(snapshot) => {
+                println!("[Network] Received state snapshot at height {}", snapshot.height);
+                if let Some(sender) = self.snapshot_sender.lock().unwrap().as_ref() {
+                    sender.send(*snapshot).map_err(|e| NetworkError::SubscriberClosed(e.to_string()))?;
+                }
+                Ok(())
+            }
+            Message::CompressedState(state) => {
+                println!("[Network] Received compressed state at height {}", state.snapshot.height);
+                if let Some(sender) = self.compressed_state_sender.lock().unwrap().as_ref() {
+                    sender.send(*state).map_err(|e| NetworkError::SubscriberClosed(e.to_string()))?;
+                }
                 Ok(())
             }
             _ => Ok(()),
@@ -289,11 +1247,343 @@ mod tests {
         assert_eq!(network.get_highest_peer_height(), 100);
     }
 
+    #[test]
+    fn test_full_outgoing_queue_drops_frames() {
+        let (sender, mut outgoing) = async_mpsc::channel(1);
+        let connection = Connection {
+            id: 0,
+            sender,
+            topics: HashSet::new(),
+            address: None,
+            verified: false,
+        };
+        assert!(connection.send(Frame::FindNode { target: PeerId::random() }));
+        assert!(!connection.send(Frame::FindNode { target: PeerId::random() }));
+        assert!(outgoing.try_recv().is_ok());
+        assert!(outgoing.try_recv().is_err());
+    }
+
     #[test]
     fn test_message_type_names() {
         assert_eq!(Message::Ping.message_type(), "Ping");
         assert_eq!(Message::Pong.message_type(), "Pong");
         assert_eq!(Message::GetBlock(1).message_type(), "GetBlock");
         assert_eq!(Message::GetBlockResponse(None).message_type(), "GetBlockResponse");
+        assert_eq!(Message::Vote(Vote::prevote("alice", 1, 0, None)).message_type(), "Vote");
+    }
+
+    #[test]
+    fn test_votes_forwarded_to_subscriber() {
+        let network = Network::new("node1".to_string(), "1.0.0".to_string());
+        let votes = network.subscribe_votes();
+
+        let vote = Vote::precommit("alice", 3, 0, Some("hash".to_string()));
+        network.handle_message(Message::Vote(vote.clone())).unwrap();
+        assert_eq!(votes.try_recv().unwrap(), vote);
+    }
+
+    #[test]
+    fn test_reported_peer_is_banned_with_metrics() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let network = Network::new("node1".to_string(), "1.0.0".to_string()).with_metrics(metrics.clone());
+        let peer = PeerId::random();
+
+        network.report_peer(&peer, Misbehavior::BadBlock);
+        assert_eq!(metrics.peer_bans.get(), 0);
+        network.report_peer(&peer, Misbehavior::BadBlock);
+        assert_eq!(metrics.peer_bans.get(), 1);
+        assert_eq!(metrics.peer_penalties.with_label_values(&["bad_block"]).get(), 2);
+    }
+
+    #[test]
+    fn test_transaction_gossip_converges_mempools() {
+        use crate::mempool::TransactionMempool;
+        use crate::types::TransactionPayload;
+
+        let left = Network::new("left".to_string(), "1.0.0".to_string());
+        let right = Network::new("right".to_string(), "1.0.0".to_string());
+        right.start_listener("127.0.0.1:0");
+        left.add_peer(&right.local_addr().unwrap(), None);
+        assert!(wait_until(|| left.peer_count() == 1 && right.peer_count() == 1));
+
+        let local = TransactionMempool::new().with_network(left.clone());
+        let remote = TransactionMempool::new().with_network(right.clone());
+        let incoming = right.subscribe_transactions();
+
+        let tx = Transaction {
+            chain_id: String::new(),
+            from: "alice".to_string(),
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Transfer { to: "bob".to_string(), amount: 5 },
+            signature: vec![],
+            public_key: vec![],
+        };
+        let hash = local.add_transaction(tx).unwrap();
+
+        // The peer admits the gossiped transaction once, under the same hash
+        let gossiped = incoming.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(remote.add_gossiped_transaction(gossiped.clone()).unwrap(), hash);
+        assert!(remote.contains(&hash).unwrap());
+        assert!(remote.add_gossiped_transaction(gossiped).is_err());
+
+        // Gossiped transactions are not echoed back to their origin
+        thread::sleep(Duration::from_millis(200));
+        assert!(incoming.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_message_topics() {
+        assert_eq!(Message::Block(crate::types::Block {
+            transactions: vec![],
+            previous_hash: String::new(),
+            nonce: 0,
+            hash: String::new(),
+            pre_state_root: vec![],
+            post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }).topic(), Topic::Blocks);
+        assert_eq!(Message::Vote(Vote::prevote("alice", 1, 0, None)).topic(), Topic::Votes);
+        assert_eq!(Message::GetBlock(1).topic(), Topic::Sync);
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) -> bool {
+        for _ in 0..100 {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn test_peer_and_message_metrics() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let left = Network::new("left".to_string(), "1.0.0".to_string()).with_metrics(metrics.clone());
+        let right = Network::new("right".to_string(), "1.0.0".to_string());
+        right.start_listener("127.0.0.1:0");
+        left.add_peer(&right.local_addr().unwrap(), None);
+        assert!(wait_until(|| metrics.peers_connected.get() == 1));
+
+        left.broadcast_vote(&Vote::prevote("alice", 1, 0, None));
+        assert!(wait_until(|| metrics.messages_sent.with_label_values(&["Vote"]).get() == 1));
+        assert!(wait_until(|| metrics.messages_received.with_label_values(&["Hello"]).get() == 1));
+
+        assert!(left.request_sync(&right.peer_id(), 1, 9));
+        assert_eq!(metrics.sync_target_height.get(), 9);
+        left.shutdown();
+        assert_eq!(metrics.peers_connected.get(), 0);
+    }
+
+    #[test]
+    fn test_list_and_disconnect_peers() {
+        let left = Network::new("left".to_string(), "1.0.0".to_string());
+        let right = Network::new("right".to_string(), "1.0.0".to_string());
+        right.start_listener("127.0.0.1:0");
+        left.add_peer(&right.local_addr().unwrap(), None);
+        assert!(wait_until(|| left.peer_count() == 1 && right.peer_count() == 1));
+
+        let peers = left.peer_summaries();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].peer_id, right.peer_id().to_string());
+        assert_eq!(peers[0].address.as_deref(), Some("127.0.0.1"));
+
+        assert!(left.disconnect_peer(&right.peer_id()));
+        assert!(!left.disconnect_peer(&right.peer_id()));
+        assert!(left.peer_summaries().is_empty());
+        assert!(wait_until(|| right.peer_count() == 0));
+    }
+
+    #[test]
+    fn test_shutdown_closes_listener_and_connections() {
+        let left = Network::new("left".to_string(), "1.0.0".to_string());
+        let right = Network::new("right".to_string(), "1.0.0".to_string());
+        right.start_listener("127.0.0.1:0");
+        let address = right.local_addr().unwrap();
+        left.add_peer(&address, None);
+        assert!(wait_until(|| left.peer_count() == 1 && right.peer_count() == 1));
+
+        right.shutdown();
+        assert_eq!(right.peer_count(), 0);
+        assert!(wait_until(|| left.peer_count() == 0));
+        assert!(wait_until(|| std::net::TcpStream::connect(&address).is_err()));
+    }
+
+    #[test]
+    fn test_discovery_and_vote_gossip() {
+        let hub = Network::new("hub".to_string(), "1.0.0".to_string());
+        let left = Network::new("left".to_string(), "1.0.0".to_string());
+        let right = Network::new("right".to_string(), "1.0.0".to_string());
+        for network in [&hub, &left, &right] {
+            network.start_listener("127.0.0.1:0");
+        }
+
+        // Both leaves only know the hub, and find each other through it
+        let hub_addr = hub.local_addr().unwrap();
+        left.add_peer(&hub_addr, Some(hub.peer_id().to_string()));
+        assert!(wait_until(|| hub.peer_count() == 1));
+        right.add_peer(&hub_addr, None);
+        assert!(wait_until(|| left.peer_count() == 2 && right.peer_count() == 2));
+
+        // A vote published once reaches every node exactly once
+        let hub_votes = hub.subscribe_votes();
+        let right_votes = right.subscribe_votes();
+        let vote = Vote::prevote("alice", 1, 0, Some("hash".to_string()));
+        left.broadcast_vote(&vote);
+        assert_eq!(right_votes.recv_timeout(Duration::from_secs(5)).unwrap(), vote);
+        assert_eq!(hub_votes.recv_timeout(Duration::from_secs(5)).unwrap(), vote);
+        thread::sleep(Duration::from_millis(200));
+        assert!(right_votes.try_recv().is_err());
+        assert!(hub_votes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_fast_sync_downloads_served_snapshot() {
+        use crate::mpt::MerklePatriciaTrie;
+        use crate::snapshot::{fast_sync, StateSnapshot};
+
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"Alice".to_vec(), 70u64.to_le_bytes().to_vec());
+        let block = Block {
+            transactions: vec![],
+            previous_hash: "GENESIS".to_string(),
+            nonce: 0,
+            hash: "h1".to_string(),
+            pre_state_root: vec![],
+            post_state_root: trie.root_hash(),
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        };
+
+        let server = Network::new("server".to_string(), "1.0.0".to_string());
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        server.start_listener("127.0.0.1:0");
+        server.serve_snapshot(StateSnapshot::capture(1000, &block, &trie));
+        client.add_peer(&server.local_addr().unwrap(), None);
+
+        let checkpoint = crate::config::TrustedCheckpoint {
+            height: 1000,
+            block_hash: "h1".to_string(),
+            state_root: hex::encode(trie.root_hash()),
+        };
+        let snapshot = fast_sync(&client, &checkpoint, Duration::from_secs(5)).unwrap();
+        assert_eq!(snapshot.height, 1000);
+        assert_eq!(snapshot.entries, trie.entries());
+    }
+
+    #[test]
+    fn test_shard_state_sync_downloads_chunks() {
+        use crate::shard_manager::ShardLedger;
+        use crate::shard_sync::{download_shard_state, ShardStateDownload, SHARD_STATE_CHUNK_SIZE};
+        use crate::types::Account;
+
+        let mut ledger = ShardLedger::new();
+        for i in 0..SHARD_STATE_CHUNK_SIZE + 1 {
+            let address = format!("account_{:04}", i);
+            let account = Account { address: address.clone(), balance: 5, nonce: 0, code: vec![], storage: HashMap::new() };
+            ledger.set_account(address, account);
+        }
+        let export = ShardStateExport::from_ledger(ShardId(1), &ledger);
+        let root = export.state_root.clone();
+
+        let server = Network::new("server".to_string(), "1.0.0".to_string());
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        server.start_listener("127.0.0.1:0");
+        server.serve_shard_state(export);
+        client.add_peer(&server.local_addr().unwrap(), None);
+
+        let path = std::env::temp_dir().join(format!("aureon_shard_sync_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let download = ShardStateDownload::new(ShardId(1), root.clone());
+        let synced = download_shard_state(&client, download, path, Duration::from_secs(10)).unwrap();
+        assert_eq!(synced.accounts, ledger.accounts);
+
+        // Nobody serves a state at an unknown root
+        let download = ShardStateDownload::new(ShardId(1), "unknown".to_string());
+        assert!(download_shard_state(&client, download, path, Duration::from_millis(500)).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_reconnects_to_stored_peers_and_dns_seeds() {
+        use crate::db::Db;
+
+        let path = std::env::temp_dir().join(format!("aureon_network_peers_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let stored = Network::new("stored".to_string(), "1.0.0".to_string());
+        let seed = Network::new("seed".to_string(), "1.0.0".to_string());
+        for network in [&stored, &seed] {
+            network.start_listener("127.0.0.1:0");
+        }
+        let store = PeerStore::new(db.clone());
+        store.save(&PeerRecord { peer_id: stored.peer_id(), address: stored.local_addr().unwrap() });
+
+        let node = Network::new("node".to_string(), "1.0.0".to_string())
+            .with_peer_store(PeerStore::new(db.clone()))
+            .with_dns_seeds(vec![seed.local_addr().unwrap()])
+            .with_target_peers(2);
+        node.start_listener("127.0.0.1:0");
+        assert!(wait_until(|| node.peer_count() == 2));
+        // The seed peer is stored once its Hello arrives
+        assert!(wait_until(|| store.load().len() == 2));
+
+        node.ban_peer(&seed.peer_id());
+        assert_eq!(store.load().len(), 1);
+        node.shutdown();
+        drop((store, node));
+        drop(db);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_disconnects_peers_on_another_chain() {
+        let chain = |genesis_hash: &str| ChainInfo {
+            chain_id: "devnet".to_string(),
+            genesis_hash: genesis_hash.to_string(),
+        };
+        let server = Network::new("server".to_string(), "1.0.0".to_string()).with_chain(chain("a"));
+        let same = Network::new("same".to_string(), "1.2.0".to_string()).with_chain(chain("a"));
+        let other = Network::new("other".to_string(), "1.0.0".to_string()).with_chain(chain("b"));
+        server.start_listener("127.0.0.1:0");
+        let address = server.local_addr().unwrap();
+
+        same.add_peer(&address, None);
+        assert!(wait_until(|| server.peer_summaries().first().is_some_and(|p| p.version == "1.2.0")));
+        other.add_peer(&address, None);
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(other.peer_count(), 0);
+        assert_eq!(server.peer_count(), 1);
+    }
+
+    #[test]
+    fn test_rejects_unexpected_identity() {
+        let server = Network::new("server".to_string(), "1.0.0".to_string());
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        server.start_listener("127.0.0.1:0");
+
+        client.add_peer(&server.local_addr().unwrap(), Some(PeerId::random().to_string()));
+        thread::sleep(Duration::from_millis(500));
+        assert_eq!(client.peer_count(), 0);
     }
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+//! Persistent Peer Store
+//!
+//! Peers this node completed a handshake with are saved, with the address
+//! they accept connections on, in the database's peers column. After a
+//! restart the node dials them before falling back to bootstrap peers and DNS
+//! seeds. Banned peers are forgotten.
+
+use super::discovery::PeerRecord;
+use super::identity::PeerId;
+use crate::db::{Column, Db};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Peers kept before the least recently seen are dropped
+pub const MAX_STORED_PEERS: usize = 256;
+
+#[derive(Serialize, Deserialize)]
+struct StoredPeer {
+    address: String,
+    /// Unix time of the last handshake
+    last_seen: u64,
+}
+
+/// Known good peers, keyed by peer id
+pub struct PeerStore {
+    db: Arc<Db>,
+}
+
+impl PeerStore {
+    pub fn new(db: Arc<Db>) -> Self {
+        PeerStore { db }
+    }
+
+    /// Remember `record` as seen now
+    pub fn save(&self, record: &PeerRecord) {
+        let last_seen = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let stored = StoredPeer { address: record.address.clone(), last_seen };
+        match serde_json::to_vec(&stored) {
+            Ok(value) => self.db.put_cf(Column::Peers, record.peer_id.as_bytes(), &value),
+            Err(e) => eprintln!("[Network] Failed to store peer {}: {}", record.peer_id, e),
+        }
+        let mut stored = self.entries();
+        if stored.len() > MAX_STORED_PEERS {
+            stored.sort_by_key(|(_, peer)| peer.last_seen);
+            for (peer_id, _) in &stored[..stored.len() - MAX_STORED_PEERS] {
+                self.remove(peer_id);
+            }
+        }
+    }
+
+    pub fn remove(&self, peer: &PeerId) {
+        self.db.delete_cf(Column::Peers, peer.as_bytes());
+    }
+
+    /// Stored peers, most recently seen first
+    pub fn load(&self) -> Vec<PeerRecord> {
+        let mut stored = self.entries();
+        stored.sort_by_key(|(_, peer)| std::cmp::Reverse(peer.last_seen));
+        stored
+            .into_iter()
+            .map(|(peer_id, peer)| PeerRecord { peer_id, address: peer.address })
+            .collect()
+    }
+
+    fn entries(&self) -> Vec<(PeerId, StoredPeer)> {
+        self.db
+            .scan_prefix_cf(Column::Peers, &[])
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let peer_id = PeerId::from_hex(&hex::encode(key)).ok()?;
+                Some((peer_id, serde_json::from_slice(&value).ok()?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_peers_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("aureon_peer_store_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let store = PeerStore::new(db.clone());
+        let first = PeerRecord { peer_id: PeerId::random(), address: "10.0.0.1:6000".to_string() };
+        let second = PeerRecord { peer_id: PeerId::random(), address: "10.0.0.2:6000".to_string() };
+        store.save(&first);
+        store.save(&second);
+        store.remove(&second.peer_id);
+        drop((store, db));
+
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        assert_eq!(PeerStore::new(db.clone()).load(), vec![first]);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
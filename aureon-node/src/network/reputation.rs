@@ -0,0 +1,178 @@
+//! Peer Reputation
+//!
+//! Every peer carries a score that starts at zero. Malformed frames, blocks
+//! that fail validation and message floods cost points, and blocks that
+//! import cleanly earn a point back. A peer whose score falls to
+//! `BAN_THRESHOLD` is disconnected and its address is refused until the ban
+//! expires, so a fresh identity from the same host does not get back in.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use super::identity::PeerId;
+use crate::network_security::ReputationScore;
+
+/// Score at which a peer is banned
+pub const BAN_THRESHOLD: i32 = -100;
+
+/// Highest score good behavior can earn
+const MAX_SCORE: i32 = 100;
+
+/// Messages a peer may send per second before it counts as spam
+pub const MAX_MESSAGES_PER_SECOND: u32 = 50;
+
+/// Default time a banned address is refused
+pub const DEFAULT_BAN_DURATION_SECS: u64 = 600;
+
+/// Peer behavior that lowers its score
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// A frame that could not be decoded
+    InvalidMessage,
+    /// A block that failed validation
+    BadBlock,
+    /// More messages than the per-second limit
+    Spam,
+}
+
+impl Misbehavior {
+    pub fn penalty(self) -> i32 {
+        match self {
+            Misbehavior::InvalidMessage => 20,
+            Misbehavior::BadBlock => 50,
+            Misbehavior::Spam => 25,
+        }
+    }
+
+    /// Metric label
+    pub fn label(self) -> &'static str {
+        match self {
+            Misbehavior::InvalidMessage => "invalid_message",
+            Misbehavior::BadBlock => "bad_block",
+            Misbehavior::Spam => "spam",
+        }
+    }
+}
+
+/// Scores, message rates and address bans of peers
+pub struct ReputationTable {
+    scores: HashMap<PeerId, i32>,
+    /// Start and message count of each peer's current one-second window
+    windows: HashMap<PeerId, (Instant, u32)>,
+    /// Banned addresses and when their ban expires
+    banned: HashMap<IpAddr, Instant>,
+    ban_duration: Duration,
+}
+
+impl ReputationTable {
+    pub fn new(ban_duration: Duration) -> Self {
+        ReputationTable {
+            scores: HashMap::new(),
+            windows: HashMap::new(),
+            banned: HashMap::new(),
+            ban_duration,
+        }
+    }
+
+    pub fn score(&self, peer: &PeerId) -> i32 {
+        self.scores.get(peer).copied().unwrap_or(0)
+    }
+
+    /// Reputation level of `peer`'s score
+    pub fn reputation(&self, peer: &PeerId) -> ReputationScore {
+        match self.score(peer) {
+            score if score <= BAN_THRESHOLD => ReputationScore::Banned,
+            score if score < 0 => ReputationScore::Untrusted,
+            score if score < 25 => ReputationScore::Neutral,
+            score if score < 75 => ReputationScore::Trusted,
+            _ => ReputationScore::Verified,
+        }
+    }
+
+    /// Lower `peer`'s score, returning true once it reaches the ban threshold
+    pub fn penalize(&mut self, peer: &PeerId, misbehavior: Misbehavior) -> bool {
+        let score = self.scores.entry(*peer).or_insert(0);
+        *score = (*score - misbehavior.penalty()).max(BAN_THRESHOLD);
+        *score <= BAN_THRESHOLD
+    }
+
+    /// Raise `peer`'s score by one point
+    pub fn reward(&mut self, peer: &PeerId) {
+        let score = self.scores.entry(*peer).or_insert(0);
+        *score = (*score + 1).min(MAX_SCORE);
+    }
+
+    /// Count a message from `peer`, returning true for the message that
+    /// first exceeds the per-second limit
+    pub fn record_message(&mut self, peer: &PeerId, now: Instant) -> bool {
+        let (start, count) = self.windows.entry(*peer).or_insert((now, 0));
+        if now.duration_since(*start) >= Duration::from_secs(1) {
+            *start = now;
+            *count = 0;
+        }
+        *count += 1;
+        *count == MAX_MESSAGES_PER_SECOND + 1
+    }
+
+    /// Refuse `peer`'s `address` until the ban duration has passed; the
+    /// peer starts over from a neutral score once the ban expires
+    pub fn ban(&mut self, peer: &PeerId, address: IpAddr, now: Instant) {
+        self.scores.remove(peer);
+        self.banned.insert(address, now + self.ban_duration);
+    }
+
+    /// Whether `address` is banned, lifting expired bans
+    pub fn is_banned(&mut self, address: IpAddr, now: Instant) -> bool {
+        self.banned.retain(|_, until| *until > now);
+        self.banned.contains_key(&address)
+    }
+
+    /// Number of addresses currently banned
+    pub fn banned_count(&self) -> usize {
+        self.banned.len()
+    }
+
+    /// Forget the message window of a disconnected peer
+    pub fn disconnected(&mut self, peer: &PeerId) {
+        self.windows.remove(peer);
+    }
+}
+
+impl Default for ReputationTable {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_BAN_DURATION_SECS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_misbehaving_peer_is_banned_until_expiry() {
+        let mut table = ReputationTable::new(Duration::from_secs(60));
+        let peer = PeerId::random();
+        let now = Instant::now();
+
+        table.reward(&peer);
+        assert_eq!(table.reputation(&peer), ReputationScore::Neutral);
+        assert!(!table.penalize(&peer, Misbehavior::BadBlock));
+        assert_eq!(table.reputation(&peer), ReputationScore::Untrusted);
+
+        // One spam penalty per flooded window
+        let flagged = (0..2 * MAX_MESSAGES_PER_SECOND).filter(|_| table.record_message(&peer, now)).count();
+        assert_eq!(flagged, 1);
+        assert!(!table.record_message(&peer, now + Duration::from_secs(1)));
+        assert!(!table.penalize(&peer, Misbehavior::BadBlock));
+        assert!(table.penalize(&peer, Misbehavior::Spam));
+        assert_eq!(table.score(&peer), BAN_THRESHOLD);
+
+        let address: IpAddr = "10.0.0.7".parse().unwrap();
+        table.ban(&peer, address, now);
+        assert_eq!(table.score(&peer), 0);
+        assert!(table.is_banned(address, now + Duration::from_secs(59)));
+        assert!(!table.is_banned(address, now + Duration::from_secs(60)));
+        assert_eq!(table.banned_count(), 0);
+    }
+}
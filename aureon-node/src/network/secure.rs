@@ -0,0 +1,411 @@
+//! Encrypted, authenticated transport between peers
+//!
+//! Sessions are set up with the Noise XX handshake
+//! (`Noise_XX_25519_ChaChaPoly_SHA256`): both sides exchange ephemeral and
+//! static X25519 keys, and each side's handshake payload carries its Ed25519
+//! identity key and a signature binding its static key to that identity, so
+//! the session is only established with the holder of the peer id.
+//!
+//! Frames are sequences of Noise transport messages, each sent as
+//! `len (u16 BE) || ciphertext`. The first message of a frame holds the
+//! frame length (u32 BE), and the rest hold its bytes in chunks that fit the
+//! Noise message size limit.
+//!
+//! Whether a session is encrypted is a policy on top of the handshake. Each
+//! side signs whether it requires encryption in its handshake payload; if
+//! neither does, as on a private network of trusted nodes, frames are sent as
+//! `len (u32 BE) || plaintext || HMAC-SHA256 tag`, keyed per direction from
+//! the handshake hash.
+
+use super::identity::{self, NodeIdentity, PeerId};
+use super::NetworkError;
+use ed25519_dalek::{Signature, VerifyingKey};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use snow::{Builder, HandshakeState, StatelessTransportState};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+const PROTOCOL: &[u8] = b"aureon-p2p/1";
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_SHA256";
+/// Largest Noise message, and the Poly1305 tag each one carries
+const NOISE_MAX_MESSAGE: usize = 65535;
+const NOISE_TAG_SIZE: usize = 16;
+/// Frame bytes carried by one Noise transport message
+const CHUNK_SIZE: usize = NOISE_MAX_MESSAGE - NOISE_TAG_SIZE;
+const TAG_SIZE: usize = 32;
+
+/// Largest accepted frame, to bound memory used by a single peer
+pub const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+fn noise_error(e: snow::Error) -> NetworkError {
+    NetworkError::Handshake(format!("Noise handshake failed: {}", e))
+}
+
+/// Static X25519 key of the Noise handshake, kept apart from the Ed25519 identity key
+#[derive(Clone)]
+pub struct TransportKey {
+    private: Vec<u8>,
+    public: Vec<u8>,
+}
+
+impl TransportKey {
+    pub fn generate() -> Self {
+        let keypair = Builder::new(NOISE_PARAMS.parse().unwrap())
+            .generate_keypair()
+            .expect("X25519 key generation cannot fail");
+        TransportKey { private: keypair.private, public: keypair.public }
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+}
+
+/// Message signed by an identity key to bind a static transport key to it,
+/// along with its encryption flag
+fn static_key_binding(static_key: &[u8], require_encryption: u8) -> Vec<u8> {
+    [PROTOCOL, b" static key:", static_key, &[require_encryption]].concat()
+}
+
+/// Frame protection and frame counter for one direction
+struct CipherState {
+    noise: Arc<StatelessTransportState>,
+    /// Authenticates frames once the session settles on plaintext
+    mac_key: [u8; 32],
+    /// Next Noise nonce, or plaintext frame number
+    counter: u64,
+    encrypt: bool,
+}
+
+impl CipherState {
+    fn new(noise: Arc<StatelessTransportState>, handshake_hash: &[u8], label: u8, encrypt: bool) -> Self {
+        let mut mac_key = [0u8; 32];
+        Hkdf::<Sha256>::new(None, handshake_hash)
+            .expand(&[label], &mut mac_key)
+            .expect("32 bytes is a valid HKDF output length");
+        CipherState { noise, mac_key, counter: 0, encrypt }
+    }
+
+    fn mac(&self, plaintext: &[u8]) -> Hmac<Sha256> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.mac_key).expect("HMAC accepts keys of any length");
+        mac.update(&self.counter.to_be_bytes());
+        mac.update(plaintext);
+        mac
+    }
+
+    /// Wire bytes of the frame carrying `plaintext`
+    fn seal(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let mut wire = Vec::with_capacity(plaintext.len() + TAG_SIZE + 4);
+        if !self.encrypt {
+            wire.extend_from_slice(&(plaintext.len() as u32 + TAG_SIZE as u32).to_be_bytes());
+            wire.extend_from_slice(plaintext);
+            wire.extend_from_slice(&self.mac(plaintext).finalize().into_bytes());
+            self.counter += 1;
+            return wire;
+        }
+
+        let header = (plaintext.len() as u32).to_be_bytes();
+        let mut message = vec![0u8; NOISE_MAX_MESSAGE];
+        for chunk in std::iter::once(&header[..]).chain(plaintext.chunks(CHUNK_SIZE)) {
+            let len = self
+                .noise
+                .write_message(self.counter, chunk, &mut message)
+                .expect("chunks fit a Noise message");
+            self.counter += 1;
+            wire.extend_from_slice(&(len as u16).to_be_bytes());
+            wire.extend_from_slice(&message[..len]);
+        }
+        wire
+    }
+
+    fn open_message(&mut self, message: &[u8]) -> Result<Vec<u8>, NetworkError> {
+        let mut plaintext = vec![0u8; message.len()];
+        let len = self
+            .noise
+            .read_message(self.counter, message, &mut plaintext)
+            .map_err(|_| NetworkError::FrameAuthentication)?;
+        self.counter += 1;
+        plaintext.truncate(len);
+        Ok(plaintext)
+    }
+
+    fn open_plaintext(&mut self, mut frame: Vec<u8>) -> Result<Vec<u8>, NetworkError> {
+        if frame.len() < TAG_SIZE {
+            return Err(NetworkError::FrameTooShort);
+        }
+        let tag = frame.split_off(frame.len() - TAG_SIZE);
+        self.mac(&frame)
+            .verify_slice(&tag)
+            .map_err(|_| NetworkError::FrameAuthentication)?;
+        self.counter += 1;
+        Ok(frame)
+    }
+}
+
+async fn read_noise_message<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, NetworkError> {
+    let len = stream.read_u16().await? as usize;
+    let mut message = vec![0u8; len];
+    stream.read_exact(&mut message).await?;
+    Ok(message)
+}
+
+async fn write_noise_message<S: AsyncWrite + Unpin>(stream: &mut S, message: &[u8]) -> Result<(), NetworkError> {
+    stream.write_u16(message.len() as u16).await?;
+    stream.write_all(message).await?;
+    Ok(stream.flush().await?)
+}
+
+/// Receiving half of an established channel
+pub struct SecureReader<S> {
+    stream: ReadHalf<S>,
+    cipher: CipherState,
+}
+
+impl<S: AsyncRead> SecureReader<S> {
+    pub async fn read_frame(&mut self) -> Result<Vec<u8>, NetworkError> {
+        if !self.cipher.encrypt {
+            let len = self.stream.read_u32().await? as usize;
+            if len > MAX_FRAME_SIZE + TAG_SIZE {
+                return Err(NetworkError::FrameTooLarge(len));
+            }
+            let mut frame = vec![0u8; len];
+            self.stream.read_exact(&mut frame).await?;
+            return self.cipher.open_plaintext(frame);
+        }
+
+        let header = self.cipher.open_message(&read_noise_message(&mut self.stream).await?)?;
+        let header: [u8; 4] = header.try_into().map_err(|_| NetworkError::FrameTooShort)?;
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_FRAME_SIZE {
+            return Err(NetworkError::FrameTooLarge(len));
+        }
+        let mut frame = Vec::with_capacity(len);
+        while frame.len() < len {
+            let chunk = self.cipher.open_message(&read_noise_message(&mut self.stream).await?)?;
+            if chunk.is_empty() || frame.len() + chunk.len() > len {
+                return Err(NetworkError::FrameAuthentication);
+            }
+            frame.extend_from_slice(&chunk);
+        }
+        Ok(frame)
+    }
+}
+
+/// Sending half of an established channel
+pub struct SecureWriter<S> {
+    stream: WriteHalf<S>,
+    cipher: CipherState,
+}
+
+impl<S: AsyncWrite> SecureWriter<S> {
+    pub async fn write_frame(&mut self, plaintext: &[u8]) -> Result<(), NetworkError> {
+        if plaintext.len() > MAX_FRAME_SIZE {
+            return Err(NetworkError::FrameTooLarge(plaintext.len()));
+        }
+        let wire = self.cipher.seal(plaintext);
+        self.stream.write_all(&wire).await?;
+        Ok(self.stream.flush().await?)
+    }
+
+    /// Close the sending direction, signalling end of stream to the peer
+    pub async fn close(&mut self) -> Result<(), NetworkError> {
+        Ok(self.stream.shutdown().await?)
+    }
+}
+
+/// Channel after a successful handshake
+pub struct SecureChannel<S> {
+    pub remote: PeerId,
+    /// Whether frames after the handshake are encrypted
+    pub encrypted: bool,
+    pub reader: SecureReader<S>,
+    pub writer: SecureWriter<S>,
+}
+
+/// Handshake payload: identity key || encryption flag
+///   || signature(static key binding)
+fn identity_payload(identity: &NodeIdentity, transport_key: &TransportKey, require_encryption: bool) -> Vec<u8> {
+    let flag = require_encryption as u8;
+    let mut payload = identity.public_key().to_bytes().to_vec();
+    payload.push(flag);
+    payload.extend_from_slice(&identity.sign(&static_key_binding(transport_key.public_key(), flag)).to_bytes());
+    payload
+}
+
+/// Check the remote's handshake payload against the static key it proved it
+/// holds, returning its identity key and encryption flag
+fn verify_identity_payload(noise: &HandshakeState, payload: &[u8]) -> Result<(VerifyingKey, bool), NetworkError> {
+    if payload.len() != 32 + 1 + 64 {
+        return Err(NetworkError::Handshake("Malformed identity proof".to_string()));
+    }
+    let remote_static = noise
+        .get_remote_static()
+        .ok_or_else(|| NetworkError::Handshake("Missing static key".to_string()))?;
+    let remote_identity = VerifyingKey::from_bytes(payload[..32].try_into().unwrap())
+        .map_err(|_| NetworkError::Handshake("Invalid identity key".to_string()))?;
+    let flag = payload[32];
+    let signature = Signature::from_bytes(payload[33..].try_into().unwrap());
+    identity::verify(&remote_identity, &static_key_binding(remote_static, flag), &signature)
+        .map_err(|_| NetworkError::InvalidSignature)?;
+    Ok((remote_identity, flag != 0))
+}
+
+/// Run the Noise XX handshake over `stream`, authenticating both ends; the
+/// session stays encrypted if either end sets `require_encryption`
+pub async fn handshake<S>(
+    mut stream: S,
+    identity: &NodeIdentity,
+    transport_key: &TransportKey,
+    initiator: bool,
+    require_encryption: bool,
+) -> Result<SecureChannel<S>, NetworkError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let builder = Builder::new(NOISE_PARAMS.parse().unwrap())
+        .local_private_key(&transport_key.private)
+        .prologue(PROTOCOL);
+    let mut noise = if initiator { builder.build_initiator() } else { builder.build_responder() }
+        .map_err(noise_error)?;
+    let payload = identity_payload(identity, transport_key, require_encryption);
+    let mut message = vec![0u8; NOISE_MAX_MESSAGE];
+    let mut received = vec![0u8; NOISE_MAX_MESSAGE];
+
+    // -> e
+    // <- e, ee, s, es, responder identity
+    // -> s, se, initiator identity
+    // The initiator checks the responder's identity before revealing its own.
+    let (remote_identity, remote_requires) = if initiator {
+        let len = noise.write_message(&[], &mut message).map_err(noise_error)?;
+        write_noise_message(&mut stream, &message[..len]).await?;
+        let len = noise
+            .read_message(&read_noise_message(&mut stream).await?, &mut received)
+            .map_err(noise_error)?;
+        let remote = verify_identity_payload(&noise, &received[..len])?;
+        let len = noise.write_message(&payload, &mut message).map_err(noise_error)?;
+        write_noise_message(&mut stream, &message[..len]).await?;
+        remote
+    } else {
+        noise
+            .read_message(&read_noise_message(&mut stream).await?, &mut received)
+            .map_err(noise_error)?;
+        let len = noise.write_message(&payload, &mut message).map_err(noise_error)?;
+        write_noise_message(&mut stream, &message[..len]).await?;
+        let len = noise
+            .read_message(&read_noise_message(&mut stream).await?, &mut received)
+            .map_err(noise_error)?;
+        verify_identity_payload(&noise, &received[..len])?
+    };
+
+    let encrypted = require_encryption || remote_requires;
+    let handshake_hash = noise.get_handshake_hash().to_vec();
+    let transport = Arc::new(noise.into_stateless_transport_mode().map_err(noise_error)?);
+    let (send_label, recv_label) = if initiator { (b'i', b'r') } else { (b'r', b'i') };
+    let (read_half, write_half) = tokio::io::split(stream);
+    Ok(SecureChannel {
+        remote: PeerId::from_public_key(&remote_identity),
+        encrypted,
+        reader: SecureReader {
+            stream: read_half,
+            cipher: CipherState::new(transport.clone(), &handshake_hash, recv_label, encrypted),
+        },
+        writer: SecureWriter {
+            stream: write_half,
+            cipher: CipherState::new(transport, &handshake_hash, send_label, encrypted),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn connect(
+        require: (bool, bool),
+    ) -> (SecureChannel<tokio::io::DuplexStream>, SecureChannel<tokio::io::DuplexStream>) {
+        let (a, b) = tokio::io::duplex(1 << 20);
+        let (alice, bob) = (NodeIdentity::generate(), NodeIdentity::generate());
+        let (alice_key, bob_key) = (TransportKey::generate(), TransportKey::generate());
+        let (left, right) = tokio::join!(
+            handshake(a, &alice, &alice_key, true, require.0),
+            handshake(b, &bob, &bob_key, false, require.1)
+        );
+        let (left, right) = (left.unwrap(), right.unwrap());
+        assert_eq!(left.remote, bob.peer_id());
+        assert_eq!(right.remote, alice.peer_id());
+        (left, right)
+    }
+
+    #[tokio::test]
+    async fn test_handshake_authenticates_peers() {
+        let (mut left, mut right) = connect((true, false)).await;
+        assert!(left.encrypted && right.encrypted);
+
+        left.writer.write_frame(b"ping").await.unwrap();
+        assert_eq!(right.reader.read_frame().await.unwrap(), b"ping");
+        right.writer.write_frame(b"pong").await.unwrap();
+        assert_eq!(left.reader.read_frame().await.unwrap(), b"pong");
+
+        // Frames larger than a Noise message are split and reassembled
+        let block = vec![7u8; 3 * CHUNK_SIZE + 5];
+        left.writer.write_frame(&block).await.unwrap();
+        assert_eq!(right.reader.read_frame().await.unwrap(), block);
+    }
+
+    #[tokio::test]
+    async fn test_frames_are_encrypted_and_authenticated() {
+        let (mut left, _right) = connect((true, true)).await;
+        let wire = left.writer.cipher.seal(b"block data");
+        assert!(!wire.windows(10).any(|w| w == b"block data"));
+
+        // Tampered frames fail authentication
+        let (mut left, mut right) = connect((true, true)).await;
+        let mut wire = left.writer.cipher.seal(b"vote");
+        let last = wire.len() - 1;
+        wire[last] ^= 1;
+        left.writer.stream.write_all(&wire).await.unwrap();
+        assert_eq!(right.reader.read_frame().await, Err(NetworkError::FrameAuthentication));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_identity_not_bound_to_static_key() {
+        let (a, b) = tokio::io::duplex(4096);
+        let (alice, bob, mallory) = (NodeIdentity::generate(), NodeIdentity::generate(), NodeIdentity::generate());
+        let (alice_key, bob_key) = (TransportKey::generate(), TransportKey::generate());
+
+        // Bob presents Mallory's identity key with his own signature
+        let mut forged = identity_payload(&bob, &bob_key, true);
+        forged[..32].copy_from_slice(&mallory.public_key().to_bytes());
+        let responder = async move {
+            let mut stream = b;
+            let mut noise = Builder::new(NOISE_PARAMS.parse().unwrap())
+                .local_private_key(&bob_key.private)
+                .prologue(PROTOCOL)
+                .build_responder()
+                .unwrap();
+            let (mut message, mut received) = (vec![0u8; NOISE_MAX_MESSAGE], vec![0u8; NOISE_MAX_MESSAGE]);
+            noise.read_message(&read_noise_message(&mut stream).await.unwrap(), &mut received).unwrap();
+            let len = noise.write_message(&forged, &mut message).unwrap();
+            write_noise_message(&mut stream, &message[..len]).await.unwrap();
+        };
+        let (result, _) = tokio::join!(handshake(a, &alice, &alice_key, true, true), responder);
+        assert!(matches!(result, Err(NetworkError::InvalidSignature)));
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_only_when_neither_side_requires_encryption() {
+        let (mut left, mut right) = connect((false, false)).await;
+        assert!(!left.encrypted && !right.encrypted);
+        left.writer.write_frame(b"ping").await.unwrap();
+        assert_eq!(right.reader.read_frame().await.unwrap(), b"ping");
+
+        // Plaintext frames are still authenticated
+        let mut wire = left.writer.cipher.seal(b"vote");
+        assert_eq!(&wire[4..8], b"vote");
+        wire[4] ^= 1;
+        left.writer.stream.write_all(&wire).await.unwrap();
+        assert_eq!(right.reader.read_frame().await, Err(NetworkError::FrameAuthentication));
+    }
+}
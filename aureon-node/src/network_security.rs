@@ -221,6 +221,11 @@ impl DdosProtection {
         self.whitelist.insert(peer_id.to_string());
     }
 
+    /// Whether `peer_id` bypasses rate limits
+    pub fn is_whitelisted(&self, peer_id: &str) -> bool {
+        self.whitelist.contains(peer_id)
+    }
+
     /// Get current request count for peer
     pub fn get_request_count(&self, peer_id: &str) -> usize {
         self.rate_limits.get(peer_id).copied().unwrap_or(0)
@@ -0,0 +1,260 @@
+//! Non-Fungible Tokens
+//!
+//! Collections of unique tokens, each with an owner and a metadata URI.
+//! Collections and tokens are stored in the state trie under `nft:` keys
+//! next to fungible token state, and are changed by the native `Nft`
+//! transaction payload. The trie has no deletes, so burning a token leaves
+//! an empty entry behind; its id is never minted again.
+
+use crate::token::TokenState;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Prefix of every key holding NFT state
+pub const STATE_PREFIX: &str = "nft:";
+const COLLECTION_PREFIX: &str = "nft:collection:";
+const TOKEN_PREFIX: &str = "nft:token:";
+
+/// Longest metadata URI accepted
+pub const MAX_URI_LEN: usize = 256;
+
+/// NFT operation carried by an `Nft` transaction, on behalf of its sender
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub enum NftOp {
+    /// Create a collection only the sender may mint into
+    CreateCollection { name: String, symbol: String, max_supply: Option<u64> },
+    /// Mint token `id` of `collection` to `to`
+    Mint { collection: String, id: u64, to: String, metadata_uri: String },
+    /// Transfer a token the sender owns
+    Transfer { collection: String, id: u64, to: String },
+    /// Destroy a token the sender owns
+    Burn { collection: String, id: u64 },
+}
+
+impl NftOp {
+    /// Collection and token id the operation changes, if it targets a token
+    pub fn token(&self) -> Option<(&str, u64)> {
+        match self {
+            NftOp::CreateCollection { .. } => None,
+            NftOp::Mint { collection, id, .. }
+            | NftOp::Transfer { collection, id, .. }
+            | NftOp::Burn { collection, id } => Some((collection, *id)),
+        }
+    }
+}
+
+/// Metadata of a collection
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    /// Account allowed to mint
+    pub creator: String,
+    /// Tokens currently in existence
+    pub supply: u64,
+    pub max_supply: Option<u64>,
+}
+
+/// A minted token
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Nft {
+    pub collection: String,
+    pub id: u64,
+    pub owner: String,
+    pub metadata_uri: String,
+}
+
+/// Id of the collection `creator` creates with `symbol`
+pub fn collection_id(creator: &str, symbol: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"nft:");
+    hasher.update(creator.as_bytes());
+    hasher.update(b":");
+    hasher.update(symbol.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn collection_key(collection: &str) -> Vec<u8> {
+    format!("{}{}", COLLECTION_PREFIX, collection).into_bytes()
+}
+
+fn token_key(collection: &str, id: u64) -> Vec<u8> {
+    format!("{}{}:{}", TOKEN_PREFIX, collection, id).into_bytes()
+}
+
+/// Decode a stored token; burned tokens decode to `None`
+pub fn decode_token(bytes: &[u8]) -> Option<Nft> {
+    bincode::decode_from_slice(bytes, standard()).ok().map(|(token, _)| token)
+}
+
+/// Whether a state key holds an NFT
+pub fn is_token_key(key: &[u8]) -> bool {
+    key.starts_with(TOKEN_PREFIX.as_bytes())
+}
+
+/// Read token `id` of `collection` through `get`
+pub fn token_at(get: impl Fn(&[u8]) -> Option<Vec<u8>>, collection: &str, id: u64) -> Option<Nft> {
+    get(&token_key(collection, id)).and_then(|bytes| decode_token(&bytes))
+}
+
+/// NFT operations over a `TokenState`
+///
+/// Every operation checks all of its conditions before writing, so a
+/// failed operation leaves state untouched.
+pub struct NftLedger<'s, S: TokenState + ?Sized> {
+    state: &'s mut S,
+}
+
+impl<'s, S: TokenState + ?Sized> NftLedger<'s, S> {
+    pub fn new(state: &'s mut S) -> Self {
+        NftLedger { state }
+    }
+
+    /// Apply `op` sent by `sender`
+    pub fn apply(&mut self, sender: &str, op: &NftOp) -> Result<(), String> {
+        match op {
+            NftOp::CreateCollection { name, symbol, max_supply } => {
+                self.create_collection(sender, name, symbol, *max_supply).map(|_| ())
+            }
+            NftOp::Mint { collection, id, to, metadata_uri } => self.mint(sender, collection, *id, to, metadata_uri),
+            NftOp::Transfer { collection, id, to } => self.transfer(sender, collection, *id, to),
+            NftOp::Burn { collection, id } => self.burn(sender, collection, *id),
+        }
+    }
+
+    pub fn collection(&self, collection: &str) -> Option<Collection> {
+        let bytes = self.state.get(&collection_key(collection))?;
+        bincode::decode_from_slice(&bytes, standard()).ok().map(|(info, _)| info)
+    }
+
+    pub fn token(&self, collection: &str, id: u64) -> Option<Nft> {
+        token_at(|key| self.state.get(key), collection, id)
+    }
+
+    /// Create a collection minted by `creator`, returning its id
+    pub fn create_collection(
+        &mut self,
+        creator: &str,
+        name: &str,
+        symbol: &str,
+        max_supply: Option<u64>,
+    ) -> Result<String, String> {
+        if symbol.is_empty() || symbol.len() > crate::token::MAX_SYMBOL_LEN {
+            return Err(format!("Collection symbol must be 1 to {} characters", crate::token::MAX_SYMBOL_LEN));
+        }
+        let id = collection_id(creator, symbol);
+        if self.collection(&id).is_some() {
+            return Err(format!("{} already created collection {}", creator, symbol));
+        }
+        self.write_collection(&Collection {
+            id: id.clone(),
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            creator: creator.to_string(),
+            supply: 0,
+            max_supply,
+        })?;
+        Ok(id)
+    }
+
+    pub fn mint(&mut self, caller: &str, collection: &str, id: u64, to: &str, metadata_uri: &str) -> Result<(), String> {
+        let mut info = self.existing_collection(collection)?;
+        if info.creator != caller {
+            return Err(format!("Only the creator of collection {} can mint", info.symbol));
+        }
+        if metadata_uri.len() > MAX_URI_LEN {
+            return Err(format!("Metadata URI longer than {} bytes", MAX_URI_LEN));
+        }
+        // Burned tokens leave their key behind, so their ids stay retired
+        if self.state.get(&token_key(collection, id)).is_some() {
+            return Err(format!("Token {} of collection {} was already minted", id, info.symbol));
+        }
+        if info.max_supply.is_some_and(|max| info.supply >= max) {
+            return Err(format!("Collection {} is fully minted", info.symbol));
+        }
+        info.supply += 1;
+        self.write_collection(&info)?;
+        self.write_token(&Nft {
+            collection: collection.to_string(),
+            id,
+            owner: to.to_string(),
+            metadata_uri: metadata_uri.to_string(),
+        })
+    }
+
+    pub fn transfer(&mut self, from: &str, collection: &str, id: u64, to: &str) -> Result<(), String> {
+        let mut token = self.owned(from, collection, id)?;
+        token.owner = to.to_string();
+        self.write_token(&token)
+    }
+
+    pub fn burn(&mut self, owner: &str, collection: &str, id: u64) -> Result<(), String> {
+        self.owned(owner, collection, id)?;
+        let mut info = self.existing_collection(collection)?;
+        info.supply -= 1;
+        self.write_collection(&info)?;
+        self.state.put(token_key(collection, id), Vec::new());
+        Ok(())
+    }
+
+    fn existing_collection(&self, collection: &str) -> Result<Collection, String> {
+        self.collection(collection).ok_or_else(|| format!("Collection {} not found", collection))
+    }
+
+    /// Token `id` of `collection`, provided `owner` owns it
+    fn owned(&self, owner: &str, collection: &str, id: u64) -> Result<Nft, String> {
+        let token = self
+            .token(collection, id)
+            .ok_or_else(|| format!("Token {} of collection {} not found", id, collection))?;
+        if token.owner != owner {
+            return Err(format!("{} does not own token {}", owner, id));
+        }
+        Ok(token)
+    }
+
+    fn write_collection(&mut self, info: &Collection) -> Result<(), String> {
+        let bytes =
+            bincode::encode_to_vec(info, standard()).map_err(|e| format!("Failed to encode collection: {}", e))?;
+        self.state.put(collection_key(&info.id), bytes);
+        Ok(())
+    }
+
+    fn write_token(&mut self, token: &Nft) -> Result<(), String> {
+        let bytes = bincode::encode_to_vec(token, standard()).map_err(|e| format!("Failed to encode NFT: {}", e))?;
+        self.state.put(token_key(&token.collection, token.id), bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_nft_lifecycle() {
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut ledger = NftLedger::new(&mut state);
+        let punks = ledger.create_collection("alice", "Punks", "PNK", Some(2)).unwrap();
+        assert!(ledger.create_collection("alice", "Punks again", "PNK", None).is_err());
+
+        ledger.mint("alice", &punks, 1, "bob", "ipfs://punk/1").unwrap();
+        assert!(ledger.mint("bob", &punks, 2, "bob", "ipfs://punk/2").is_err());
+        assert!(ledger.mint("alice", &punks, 1, "carol", "ipfs://punk/1").is_err());
+        ledger.mint("alice", &punks, 2, "alice", "ipfs://punk/2").unwrap();
+        assert!(ledger.mint("alice", &punks, 3, "alice", "ipfs://punk/3").is_err());
+
+        assert!(ledger.transfer("alice", &punks, 1, "carol").is_err());
+        ledger.transfer("bob", &punks, 1, "carol").unwrap();
+        assert_eq!(ledger.token(&punks, 1).unwrap().owner, "carol");
+
+        ledger.burn("carol", &punks, 1).unwrap();
+        assert_eq!(ledger.token(&punks, 1), None);
+        assert_eq!(ledger.collection(&punks).unwrap().supply, 1);
+        // A burned id is not minted again
+        assert!(ledger.mint("alice", &punks, 1, "alice", "ipfs://punk/1").is_err());
+    }
+}
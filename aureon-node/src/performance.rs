@@ -79,6 +79,13 @@ impl<K: Clone + Eq + std::hash::Hash, V: Clone> LruCache<K, V> {
         }
     }
 
+    /// Remove a value from cache
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let entry = self.data.remove(key)?;
+        self.access_order.retain(|k| k != key);
+        Some(entry.value)
+    }
+
     /// Get cache statistics
     pub fn size(&self) -> usize {
         self.data.len()
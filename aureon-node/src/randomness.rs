@@ -0,0 +1,131 @@
+//! Randomness Beacon
+//!
+//! Every block carries a random seed chained from its parent's. The proposer
+//! reveals its Ed25519 signature over the parent seed and the slot; Ed25519
+//! signatures are deterministic, so the reveal is the only valid one for the
+//! proposer's key, and nobody else can compute it before the block is out.
+//! The block's seed is the hash of the parent seed and the reveal, and blocks
+//! without a proposer hash the parent seed alone. Importers check both, PoS
+//! leader election draws from the seed of the chain tip, and contracts can
+//! read the seed of the block they run in.
+
+use crate::signer::{SignKind, SignRequest, ValidatorSigner};
+use crate::types::Block;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// Seed before the first block
+pub const GENESIS_SEED: [u8; 32] = [0; 32];
+
+const DOMAIN: &[u8] = b"aureon-randomness/1";
+
+fn reveal_bytes(parent_seed: &[u8; 32], slot: u64) -> Vec<u8> {
+    let mut bytes = DOMAIN.to_vec();
+    bytes.extend_from_slice(parent_seed);
+    bytes.extend_from_slice(&slot.to_le_bytes());
+    bytes
+}
+
+/// Seed following `parent_seed` for a block revealing `reveal`
+pub fn next_seed(parent_seed: &[u8; 32], reveal: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(parent_seed);
+    hasher.update(reveal);
+    hasher.finalize().into()
+}
+
+/// Seed of `block`, or the genesis seed when there is no block or it predates seeds
+pub fn seed_of(block: Option<&Block>) -> [u8; 32] {
+    block
+        .and_then(|block| block.random_seed.as_slice().try_into().ok())
+        .unwrap_or(GENESIS_SEED)
+}
+
+/// Set the reveal and seed of `block` on top of `parent_seed`, revealing
+/// through the proposer's `signer` when there is one
+///
+/// Must run before the proposal is signed, since the signature covers the seed.
+pub fn seal(block: &mut Block, parent_seed: &[u8; 32], signer: Option<&dyn ValidatorSigner>) -> Result<(), String> {
+    block.random_reveal = match signer {
+        Some(signer) => {
+            let request = SignRequest::new(SignKind::RandomReveal, block.slot, reveal_bytes(parent_seed, block.slot));
+            signer.sign(&request)?.to_bytes().to_vec()
+        }
+        None => vec![],
+    };
+    block.random_seed = next_seed(parent_seed, &block.random_reveal).to_vec();
+    Ok(())
+}
+
+/// Check that `block`'s seed follows `parent_seed`, revealed by the holder
+/// of `proposer_key` or, without one, by nobody
+pub fn verify(block: &Block, parent_seed: &[u8; 32], proposer_key: Option<&VerifyingKey>) -> Result<(), String> {
+    match proposer_key {
+        Some(key) => {
+            let reveal = Signature::from_slice(&block.random_reveal)
+                .map_err(|_| format!("Block {} has no valid randomness reveal", block.hash))?;
+            key.verify(&reveal_bytes(parent_seed, block.slot), &reveal)
+                .map_err(|_| format!("Invalid randomness reveal from {}", block.proposer))?;
+        }
+        None if !block.random_reveal.is_empty() => {
+            return Err(format!("Block {} reveals randomness without a proposer", block.hash));
+        }
+        None => {}
+    }
+    if block.random_seed != next_seed(parent_seed, &block.random_reveal) {
+        return Err(format!("Block {} random seed does not follow its parent's", block.hash));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    fn block(slot: u64) -> Block {
+        Block {
+            transactions: vec![],
+            previous_hash: "genesis".to_string(),
+            nonce: 0,
+            hash: "h".to_string(),
+            pre_state_root: vec![],
+            post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot,
+            proposer: "alice".to_string(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }
+    }
+
+    #[test]
+    fn test_seed_chain_is_verifiable_and_bound_to_proposer() {
+        let (alice, mallory) = (NodeIdentity::generate(), NodeIdentity::generate());
+        let mut first = block(1);
+        seal(&mut first, &GENESIS_SEED, Some(&alice)).unwrap();
+        assert!(verify(&first, &GENESIS_SEED, Some(&alice.public_key())).is_ok());
+        assert!(verify(&first, &GENESIS_SEED, Some(&mallory.public_key())).is_err());
+        assert!(verify(&first, &GENESIS_SEED, None).is_err());
+
+        // The seed depends on the parent seed and the slot
+        let parent = seed_of(Some(&first));
+        let mut second = block(2);
+        seal(&mut second, &parent, Some(&alice)).unwrap();
+        assert_ne!(second.random_seed, first.random_seed);
+        assert!(verify(&second, &GENESIS_SEED, Some(&alice.public_key())).is_err());
+        second.random_seed[0] ^= 1;
+        assert!(verify(&second, &parent, Some(&alice.public_key())).unwrap_err().contains("does not follow"));
+
+        let mut unsigned = block(3);
+        seal(&mut unsigned, &parent, None).unwrap();
+        assert!(verify(&unsigned, &parent, None).is_ok());
+        assert_eq!(seed_of(None), GENESIS_SEED);
+    }
+}
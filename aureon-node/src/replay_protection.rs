@@ -0,0 +1,67 @@
+//! Replay Protection
+//!
+//! Every signed transaction names the network it is meant for in its
+//! `chain_id`, which is part of the signing bytes. A node records its chain
+//! id in the state trie at genesis, and the mempool and state processor
+//! reject signed transactions naming another chain, so a transaction signed
+//! for one network cannot be replayed on another sharing its accounts.
+//! Unsigned transactions carry no chain id and are accepted as before.
+
+use crate::bridge::StateEntry;
+use crate::token::TokenState;
+use crate::types::Transaction;
+
+/// State key holding the chain id
+pub const STATE_KEY: &[u8] = b"chain:id";
+
+/// State entry recording `chain_id`, written at genesis
+pub fn genesis_state(chain_id: &str) -> Vec<StateEntry> {
+    vec![(STATE_KEY.to_vec(), chain_id.as_bytes().to_vec())]
+}
+
+/// Chain id recorded in `state`, if any
+pub fn chain_id<S: TokenState + ?Sized>(state: &S) -> Option<String> {
+    state.get(STATE_KEY).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reject `tx` if it is signed for a chain other than the one `state` belongs to
+pub fn verify_transaction<S: TokenState + ?Sized>(state: &S, tx: &Transaction) -> Result<(), String> {
+    let Some(chain_id) = chain_id(state) else {
+        return Ok(());
+    };
+    if tx.chain_id == chain_id || (tx.chain_id.is_empty() && tx.signature.is_empty()) {
+        return Ok(());
+    }
+    Err(format!("Transaction signed for chain '{}', not '{}'", tx.chain_id, chain_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_rejects_transactions_signed_for_other_chains() {
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = genesis_state("aureon-testnet").into_iter().collect();
+        let secret = [7u8; 32];
+        let mut tx = Transaction::transfer("alice".to_string(), "bob".to_string(), 10);
+        assert!(verify_transaction(&state, &tx).is_ok());
+
+        tx.chain_id = "aureon-mainnet".to_string();
+        tx.sign(&secret);
+        assert!(verify_transaction(&state, &tx).unwrap_err().contains("aureon-mainnet"));
+
+        tx.chain_id = String::new();
+        tx.sign(&secret);
+        assert!(verify_transaction(&state, &tx).is_err());
+
+        tx.chain_id = "aureon-testnet".to_string();
+        tx.sign(&secret);
+        assert!(verify_transaction(&state, &tx).is_ok());
+        assert_eq!(chain_id(&state).as_deref(), Some("aureon-testnet"));
+
+        state.remove(STATE_KEY);
+        tx.chain_id = "aureon-mainnet".to_string();
+        assert!(verify_transaction(&state, &tx).is_ok());
+    }
+}
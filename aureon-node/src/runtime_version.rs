@@ -0,0 +1,240 @@
+//! Runtime Versions
+//!
+//! Protocol rule changes are introduced as named forks that activate at
+//! block heights set in the chain spec, so every node of a network switches
+//! rules at the same block. A fork the spec does not schedule is active from
+//! genesis. Blocks using a feature before its fork activates are rejected.
+
+use crate::fee_market::{self, BASE_TX_GAS, SHIELDED_TX_GAS};
+use crate::types::{Block, Transaction, TransactionPayload};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Gas charged per byte of deployed contract code once `Fork::CodeGas` is active
+pub const CODE_BYTE_GAS: u64 = 200;
+
+/// Protocol forks, in the order they were introduced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Fork {
+    /// Fungible token and NFT payloads
+    Tokens,
+    /// Shielded transfers
+    Shielded,
+    /// Contract upgrade payloads
+    ContractUpgrades,
+    /// Stake delegation payloads
+    Delegation,
+    /// Contract deployments reserve gas for each byte of code
+    CodeGas,
+    /// Time-locked transfer payloads
+    TimeLocks,
+    /// Multisig account registrations
+    Multisig,
+    /// Cross-chain bridge attestations and burns
+    Bridge,
+    /// Bridge light client headers and proofs
+    BridgeLightClients,
+    /// State resurrection payloads
+    StorageRent,
+}
+
+impl Fork {
+    pub const ALL: [Fork; 10] = [
+        Fork::Tokens,
+        Fork::Shielded,
+        Fork::ContractUpgrades,
+        Fork::Delegation,
+        Fork::CodeGas,
+        Fork::TimeLocks,
+        Fork::Multisig,
+        Fork::Bridge,
+        Fork::BridgeLightClients,
+        Fork::StorageRent,
+    ];
+
+    /// Name of the fork in chain specs
+    pub fn name(self) -> &'static str {
+        match self {
+            Fork::Tokens => "tokens",
+            Fork::Shielded => "shielded",
+            Fork::ContractUpgrades => "contract_upgrades",
+            Fork::Delegation => "delegation",
+            Fork::CodeGas => "code_gas",
+            Fork::TimeLocks => "time_locks",
+            Fork::Multisig => "multisig",
+            Fork::Bridge => "bridge",
+            Fork::BridgeLightClients => "bridge_light_clients",
+            Fork::StorageRent => "storage_rent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Fork> {
+        Fork::ALL.into_iter().find(|fork| fork.name() == name)
+    }
+
+    /// Fork that introduced the payload of `tx`, if any
+    fn required_by(tx: &Transaction) -> Option<Fork> {
+        match &tx.payload {
+            TransactionPayload::Token(_) | TransactionPayload::Nft(_) => Some(Fork::Tokens),
+            TransactionPayload::ShieldedTransfer(_) => Some(Fork::Shielded),
+            TransactionPayload::ContractUpgrade { .. } => Some(Fork::ContractUpgrades),
+            TransactionPayload::Delegate { .. } | TransactionPayload::Undelegate { .. } => Some(Fork::Delegation),
+            TransactionPayload::TimeLockedTransfer { .. } => Some(Fork::TimeLocks),
+            TransactionPayload::RegisterMultisig { .. } => Some(Fork::Multisig),
+            TransactionPayload::BridgeAttest { .. } | TransactionPayload::BridgeBurn { .. } => Some(Fork::Bridge),
+            TransactionPayload::BridgeHeader { .. } | TransactionPayload::BridgeProve { .. } => {
+                Some(Fork::BridgeLightClients)
+            }
+            TransactionPayload::ResurrectState(_) => Some(Fork::StorageRent),
+            _ => None,
+        }
+    }
+}
+
+/// Intrinsic gas rules of a runtime version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasSchedule {
+    pub base_tx_gas: u64,
+    pub shielded_tx_gas: u64,
+    /// Gas per byte of deployed or upgraded contract code
+    pub code_byte_gas: u64,
+}
+
+impl GasSchedule {
+    /// Gas `tx` must reserve at least
+    pub fn intrinsic_gas(&self, tx: &Transaction) -> u64 {
+        match &tx.payload {
+            TransactionPayload::ContractDeploy { code, .. } | TransactionPayload::ContractUpgrade { code, .. } => {
+                self.base_tx_gas.saturating_add(self.code_byte_gas.saturating_mul(code.len() as u64))
+            }
+            TransactionPayload::ShieldedTransfer(_) => self.shielded_tx_gas,
+            _ => self.base_tx_gas,
+        }
+    }
+}
+
+/// Protocol rules in force at a block height
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeVersion {
+    /// Number of active forks; increases with every activation
+    pub spec_version: u32,
+    active: BTreeSet<Fork>,
+    pub gas: GasSchedule,
+}
+
+impl RuntimeVersion {
+    pub fn is_active(&self, fork: Fork) -> bool {
+        self.active.contains(&fork)
+    }
+
+    /// Check that `tx` only uses active features and reserves its intrinsic gas
+    pub fn check_transaction(&self, tx: &Transaction) -> Result<(), String> {
+        if let Some(fork) = Fork::required_by(tx)
+            && !self.is_active(fork)
+        {
+            return Err(format!("Transaction {} uses fork '{}' before it activates", tx.hash(), fork.name()));
+        }
+        let intrinsic = self.gas.intrinsic_gas(tx);
+        let gas = fee_market::gas_limit(tx);
+        if gas < intrinsic {
+            return Err(format!("Transaction {} gas limit {} below intrinsic gas {}", tx.hash(), gas, intrinsic));
+        }
+        Ok(())
+    }
+
+    pub fn check_block(&self, block: &Block) -> Result<(), String> {
+        block.transactions.iter().try_for_each(|tx| self.check_transaction(tx))
+    }
+}
+
+/// Activation heights of protocol forks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeSchedule {
+    activations: BTreeMap<Fork, u64>,
+}
+
+impl RuntimeSchedule {
+    /// Schedule the forks of a chain spec, given by name
+    pub fn from_forks(forks: &BTreeMap<String, u64>) -> Result<Self, String> {
+        let activations = forks
+            .iter()
+            .map(|(name, height)| match Fork::from_name(name) {
+                Some(fork) => Ok((fork, *height)),
+                None => Err(format!("Unknown fork '{}' in chain spec", name)),
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(RuntimeSchedule { activations })
+    }
+
+    /// Height `fork` activates at; 0 if it is not scheduled
+    pub fn activation_height(&self, fork: Fork) -> u64 {
+        self.activations.get(&fork).copied().unwrap_or(0)
+    }
+
+    /// Rules in force for the block at `height`
+    pub fn version_at(&self, height: u64) -> RuntimeVersion {
+        let active: BTreeSet<Fork> =
+            Fork::ALL.into_iter().filter(|fork| self.activation_height(*fork) <= height).collect();
+        let code_byte_gas = if active.contains(&Fork::CodeGas) { CODE_BYTE_GAS } else { 0 };
+        RuntimeVersion {
+            spec_version: active.len() as u32,
+            active,
+            gas: GasSchedule {
+                base_tx_gas: BASE_TX_GAS,
+                shielded_tx_gas: SHIELDED_TX_GAS,
+                code_byte_gas,
+            },
+        }
+    }
+
+    /// Check `block` against the rules in force at `height`
+    pub fn check_block(&self, height: u64, block: &Block) -> Result<(), String> {
+        self.version_at(height).check_block(block)
+    }
+
+    /// Forks that activate after genesis, by activation height
+    pub fn upcoming(&self) -> Vec<(u64, Fork)> {
+        let mut upcoming: Vec<_> = self
+            .activations
+            .iter()
+            .filter(|(_, height)| **height > 0)
+            .map(|(fork, height)| (*height, *fork))
+            .collect();
+        upcoming.sort();
+        upcoming
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(forks: &[(&str, u64)]) -> RuntimeSchedule {
+        let forks = forks.iter().map(|(name, height)| (name.to_string(), *height)).collect();
+        RuntimeSchedule::from_forks(&forks).unwrap()
+    }
+
+    #[test]
+    fn test_payloads_rejected_before_their_fork() {
+        let schedule = schedule(&[("delegation", 10)]);
+        let delegate = Transaction::delegate("alice".into(), "bob".into(), 5);
+        assert!(schedule.version_at(9).check_transaction(&delegate).is_err());
+        assert!(schedule.version_at(10).check_transaction(&delegate).is_ok());
+        assert_eq!(schedule.version_at(9).spec_version + 1, schedule.version_at(10).spec_version);
+        // Unscheduled forks are active from genesis
+        assert!(schedule.version_at(0).is_active(Fork::Tokens));
+        assert_eq!(schedule.upcoming(), vec![(10, Fork::Delegation)]);
+
+        let unknown = [("warp_drive".to_string(), 5)].into_iter().collect();
+        assert!(RuntimeSchedule::from_forks(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_code_gas_changes_intrinsic_gas() {
+        let schedule = schedule(&[("code_gas", 100)]);
+        let deploy = Transaction::deploy_contract("alice".into(), vec![0; 100], BASE_TX_GAS + 1000);
+        assert_eq!(schedule.version_at(99).gas.intrinsic_gas(&deploy), BASE_TX_GAS);
+        assert_eq!(schedule.version_at(100).gas.intrinsic_gas(&deploy), BASE_TX_GAS + 100 * CODE_BYTE_GAS);
+        assert!(schedule.version_at(99).check_transaction(&deploy).is_ok());
+        assert!(schedule.version_at(100).check_transaction(&deploy).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Node Service Lifecycle
+//!
+//! The node's long-running services (block producer, metrics tracker,
+//! network and API server) watch a shared `Shutdown` signal. On Ctrl+C or
+//! SIGTERM the `ServiceManager` raises it, waits for every service thread
+//! to finish its current iteration, and then runs the registered shutdown
+//! steps in order, so what is still held in memory reaches the database
+//! before the process exits.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Shutdown signal shared by the node's services
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<ShutdownState>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    triggered: AtomicBool,
+    /// Wakes threads sleeping in `wait_timeout`
+    lock: Mutex<()>,
+    condvar: Condvar,
+    /// Wakes tasks awaiting `wait`
+    notify: Notify,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every service to stop
+    pub fn trigger(&self) {
+        let _guard = self.inner.lock.lock().unwrap();
+        self.inner.triggered.store(true, Ordering::SeqCst);
+        self.inner.condvar.notify_all();
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.inner.triggered.load(Ordering::SeqCst)
+    }
+
+    /// Sleep for up to `timeout`, returning true as soon as shutdown is triggered
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let guard = self.inner.lock.lock().unwrap();
+        let (_guard, _) = self
+            .inner
+            .condvar
+            .wait_timeout_while(guard, timeout, |_| !self.is_triggered())
+            .unwrap();
+        self.is_triggered()
+    }
+
+    /// Resolve once shutdown is triggered
+    pub async fn wait(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if !self.is_triggered() {
+            notified.await;
+        }
+    }
+}
+
+/// A step run once all services have stopped
+type ShutdownStep = Box<dyn FnOnce() -> Result<(), String> + Send>;
+
+/// Owns the node's service threads and the steps that persist state on exit
+pub struct ServiceManager {
+    shutdown: Shutdown,
+    services: Vec<(String, JoinHandle<()>)>,
+    steps: Vec<(String, ShutdownStep)>,
+}
+
+impl ServiceManager {
+    pub fn new() -> Self {
+        ServiceManager {
+            shutdown: Shutdown::new(),
+            services: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Signal services started by this manager watch
+    pub fn shutdown_signal(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Track a service thread that stops once the shutdown signal is raised
+    pub fn register(&mut self, name: &str, handle: JoinHandle<()>) {
+        self.services.push((name.to_string(), handle));
+    }
+
+    /// Run `step` after every service has stopped, in registration order
+    pub fn on_shutdown(&mut self, name: &str, step: impl FnOnce() -> Result<(), String> + Send + 'static) {
+        self.steps.push((name.to_string(), Box::new(step)));
+    }
+
+    /// Raise the shutdown signal, wait for the services and run the shutdown steps
+    ///
+    /// Every step runs even if an earlier one fails; the failures are
+    /// reported together.
+    pub fn stop(self) -> Result<(), String> {
+        self.shutdown.trigger();
+        let mut errors = Vec::new();
+        for (name, handle) in self.services {
+            match handle.join() {
+                Ok(()) => println!("[Shutdown] Stopped {}", name),
+                Err(_) => errors.push(format!("{} panicked", name)),
+            }
+        }
+        for (name, step) in self.steps {
+            match step() {
+                Ok(()) => println!("[Shutdown] {}", name),
+                Err(e) => errors.push(format!("{}: {}", name, e)),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+/// Resolve on Ctrl+C, or SIGTERM on Unix
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => eprintln!("Warning: Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    #[test]
+    fn test_stop_joins_services_before_shutdown_steps() {
+        let mut manager = ServiceManager::new();
+        let iterations = Arc::new(AtomicUsize::new(0));
+        let shutdown = manager.shutdown_signal();
+        let counter = iterations.clone();
+        manager.register(
+            "worker",
+            thread::spawn(move || {
+                while !shutdown.wait_timeout(Duration::from_millis(10)) {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            }),
+        );
+
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let (seen, counter) = (steps.clone(), iterations.clone());
+        manager.on_shutdown("flush", move || {
+            seen.lock().unwrap().push(counter.load(Ordering::SeqCst));
+            Ok(())
+        });
+        manager.on_shutdown("commit", || Err("disk full".to_string()));
+
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(manager.stop().unwrap_err(), "commit: disk full");
+        // The worker had stopped before the flush step read its counter
+        assert_eq!(steps.lock().unwrap()[0], iterations.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_async_wait_resolves_on_trigger() {
+        let shutdown = Shutdown::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let waiter = shutdown.clone();
+        let task = runtime.spawn(async move { waiter.wait().await });
+        thread::sleep(Duration::from_millis(20));
+        shutdown.trigger();
+        runtime.block_on(task).unwrap();
+        assert!(shutdown.wait_timeout(Duration::ZERO));
+    }
+}
@@ -1,11 +1,22 @@
+//! Shard Assignment
+//!
+//! Every account has a position in a 64-bit key space, taken from the hash
+//! of its address, and each shard owns one contiguous range of that space.
+//! The validator set decides how many shards there are: at every epoch
+//! boundary the shard map is recomputed, and when the shard count changes
+//! the boundaries move and the key ranges between the old and new
+//! boundaries migrate to another shard.
+
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use std::collections::{BTreeMap, HashMap};
 
 /// Number of shards in the system
 /// This determines horizontal scalability - each shard handles independent accounts
 const NUM_SHARDS: u32 = 4;
 
 /// Represents a shard identifier (0 to NUM_SHARDS-1)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ShardId(pub u32);
 
 impl ShardId {
@@ -15,7 +26,7 @@ impl ShardId {
 }
 
 /// ShardCoordinator manages deterministic account-to-shard assignment
-/// Uses hash-modulo function to ensure consistent account placement across all nodes
+/// Splits the key space of address hashes evenly to ensure consistent account placement across all nodes
 #[derive(Debug, Clone)]
 pub struct ShardCoordinator {
     num_shards: u32,
@@ -36,7 +47,7 @@ impl ShardCoordinator {
         ShardCoordinator { num_shards }
     }
 
-    /// Get the shard ID for an account address using deterministic hash-modulo
+    /// Get the shard ID for an account address
     /// 
     /// Deterministic sharding ensures:
     /// - Same account always maps to same shard across all nodes
@@ -49,18 +60,19 @@ impl ShardCoordinator {
     /// # Returns
     /// ShardId - The shard this account belongs to
     pub fn get_shard(&self, account_address: &str) -> ShardId {
-        let mut hasher = Sha256::new();
-        hasher.update(account_address.as_bytes());
-        let hash = hasher.finalize();
-        
-        // Use first 8 bytes of hash as u64 for modulo
-        let hash_value = u64::from_le_bytes([
-            hash[0], hash[1], hash[2], hash[3],
-            hash[4], hash[5], hash[6], hash[7],
-        ]);
-        
-        let shard_idx = (hash_value % (self.num_shards as u64)) as u32;
-        ShardId(shard_idx)
+        self.shard_of_key(shard_key(account_address))
+    }
+
+    /// Shard owning `key`: the key space is split into `num_shards` equal ranges
+    pub fn shard_of_key(&self, key: u64) -> ShardId {
+        ShardId(((key as u128 * self.num_shards as u128) >> 64) as u32)
+    }
+
+    /// Range of keys owned by `shard`
+    pub fn range(&self, shard: ShardId) -> ShardRange {
+        let start = |index: u32| ((index as u128) << 64).div_ceil(self.num_shards as u128) as u64;
+        let end = if shard.0 + 1 >= self.num_shards { u64::MAX } else { start(shard.0 + 1) - 1 };
+        ShardRange { start: start(shard.0), end }
     }
 
     /// Validate that a shard ID is within valid range
@@ -103,6 +115,177 @@ impl Default for ShardCoordinator {
     }
 }
 
+/// Position of an account in the shard key space: the first 8 bytes of the
+/// SHA-256 of its address
+pub fn shard_key(account_address: &str) -> u64 {
+    let hash = Sha256::digest(account_address.as_bytes());
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+/// Contiguous range of shard keys, both ends included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ShardRange {
+    #[allow(dead_code)]
+    pub fn contains(&self, key: u64) -> bool {
+        self.start <= key && key <= self.end
+    }
+}
+
+/// Key range owned by another shard after the shard count changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeMigration {
+    pub from: ShardId,
+    pub to: ShardId,
+    pub range: ShardRange,
+}
+
+/// Key ranges whose shard under `new` differs from the one under `old`
+pub fn migrations(old: &ShardCoordinator, new: &ShardCoordinator) -> Vec<RangeMigration> {
+    let mut starts: Vec<u64> = old
+        .all_shards()
+        .into_iter()
+        .map(|shard| old.range(shard).start)
+        .chain(new.all_shards().into_iter().map(|shard| new.range(shard).start))
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+
+    let mut moved: Vec<RangeMigration> = Vec::new();
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(u64::MAX, |next| next - 1);
+        let (from, to) = (old.shard_of_key(start), new.shard_of_key(start));
+        if from == to {
+            continue;
+        }
+        match moved.last_mut() {
+            Some(last) if last.from == from && last.to == to && last.range.end.checked_add(1) == Some(start) => {
+                last.range.end = end;
+            }
+            _ => moved.push(RangeMigration { from, to, range: ShardRange { start, end } }),
+        }
+    }
+    moved
+}
+
+/// A shard as reported by `/shards`
+#[derive(Debug, Clone, Serialize)]
+pub struct ShardInfo {
+    pub shard: ShardId,
+    /// First key of the shard's range, hex-encoded
+    pub range_start: String,
+    /// Last key of the shard's range, hex-encoded
+    pub range_end: String,
+    pub validators: Vec<String>,
+}
+
+/// Shard layout of an epoch: the shards, the key range each owns and the
+/// validators assigned to each
+#[derive(Debug, Clone)]
+pub struct ShardMap {
+    epoch: u64,
+    coordinator: ShardCoordinator,
+    max_shards: u32,
+    validators_per_shard: u32,
+    validators: BTreeMap<ShardId, Vec<String>>,
+}
+
+impl ShardMap {
+    /// Fixed layout of `coordinator`'s shards, with no validators assigned yet
+    pub fn new(coordinator: ShardCoordinator) -> Self {
+        ShardMap {
+            epoch: 0,
+            max_shards: coordinator.num_shards(),
+            coordinator,
+            validators_per_shard: 0,
+            validators: BTreeMap::new(),
+        }
+    }
+
+    /// Run one shard per `count` validators, up to the coordinator's shard
+    /// count; 0 keeps the shard count fixed
+    pub fn with_validators_per_shard(mut self, count: u32) -> Self {
+        self.validators_per_shard = count;
+        self
+    }
+
+    /// Epoch the layout was computed for
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn coordinator(&self) -> &ShardCoordinator {
+        &self.coordinator
+    }
+
+    /// Validators assigned to `shard`
+    pub fn validators(&self, shard: ShardId) -> &[String] {
+        self.validators.get(&shard).map_or(&[], |validators| validators.as_slice())
+    }
+
+    /// Shard count for a set of `validator_count` validators
+    pub fn shard_count_for(&self, validator_count: usize) -> u32 {
+        if self.validators_per_shard == 0 {
+            return self.max_shards;
+        }
+        let count = validator_count / self.validators_per_shard as usize;
+        count.clamp(1, self.max_shards as usize) as u32
+    }
+
+    /// Recompute the layout for the validator set of `epoch`
+    ///
+    /// Validators are shuffled by a hash of the epoch and their name and
+    /// dealt to the shards in turn, so no shard keeps the same validators
+    /// for good. Returns the key ranges that changed shards, which is none
+    /// unless the shard count changed.
+    pub fn rebalance(&mut self, epoch: u64, validators: &HashMap<String, u64>) -> Vec<RangeMigration> {
+        let coordinator = ShardCoordinator::with_shard_count(self.shard_count_for(validators.len()));
+        let moved = migrations(&self.coordinator, &coordinator);
+
+        let mut shuffled: Vec<(Vec<u8>, &String)> = validators
+            .keys()
+            .map(|validator| {
+                let mut hasher = Sha256::new();
+                hasher.update(epoch.to_le_bytes());
+                hasher.update(validator.as_bytes());
+                (hasher.finalize().to_vec(), validator)
+            })
+            .collect();
+        shuffled.sort();
+        let mut assigned: BTreeMap<ShardId, Vec<String>> = BTreeMap::new();
+        for (i, (_, validator)) in shuffled.into_iter().enumerate() {
+            let shard = ShardId(i as u32 % coordinator.num_shards());
+            assigned.entry(shard).or_default().push(validator.clone());
+        }
+
+        self.epoch = epoch;
+        self.coordinator = coordinator;
+        self.validators = assigned;
+        moved
+    }
+
+    /// Every shard with its key range and validators
+    pub fn shards(&self) -> Vec<ShardInfo> {
+        self.coordinator
+            .all_shards()
+            .into_iter()
+            .map(|shard| {
+                let range = self.coordinator.range(shard);
+                ShardInfo {
+                    shard,
+                    range_start: format!("{:016x}", range.start),
+                    range_end: format!("{:016x}", range.end),
+                    validators: self.validators(shard).to_vec(),
+                }
+            })
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +422,85 @@ mod tests {
         
         assert_eq!(set.len(), 2);
     }
+    #[test]
+    fn test_shard_ranges_cover_the_key_space() {
+        for count in [1, 3, 4, 7] {
+            let coordinator = ShardCoordinator::with_shard_count(count);
+            let mut next = 0u64;
+            for shard in coordinator.all_shards() {
+                let range = coordinator.range(shard);
+                assert_eq!(range.start, next);
+                assert_eq!(coordinator.shard_of_key(range.start), shard);
+                assert_eq!(coordinator.shard_of_key(range.end), shard);
+                next = range.end.wrapping_add(1);
+            }
+            assert_eq!(coordinator.range(ShardId(count - 1)).end, u64::MAX);
+        }
+
+        let coordinator = ShardCoordinator::new();
+        for i in 0..50 {
+            let address = format!("account_{}", i);
+            let shard = coordinator.get_shard(&address);
+            assert!(coordinator.range(shard).contains(shard_key(&address)));
+        }
+    }
+
+    #[test]
+    fn test_migrations_move_ranges_between_boundaries() {
+        let four = ShardCoordinator::with_shard_count(4);
+        let two = ShardCoordinator::with_shard_count(2);
+        assert!(migrations(&four, &four).is_empty());
+
+        // Halving the shards folds shard 1 into 0, and shards 2 and 3 into 1
+        let moved = migrations(&four, &two);
+        let pairs: Vec<(u32, u32)> = moved.iter().map(|m| (m.from.0, m.to.0)).collect();
+        assert_eq!(pairs, vec![(1, 0), (2, 1), (3, 1)]);
+        assert_eq!(moved[0].range, four.range(ShardId(1)));
+
+        // Every key lands where the new layout puts it
+        let moved = migrations(&two, &ShardCoordinator::with_shard_count(3));
+        let three = ShardCoordinator::with_shard_count(3);
+        for i in 0..200 {
+            let key = shard_key(&format!("account_{}", i));
+            let migration = moved.iter().find(|m| m.range.contains(key));
+            match migration {
+                Some(m) => {
+                    assert_eq!((m.from, m.to), (two.shard_of_key(key), three.shard_of_key(key)));
+                }
+                None => assert_eq!(two.shard_of_key(key), three.shard_of_key(key)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_map_follows_the_validator_set() {
+        let validators = |count: usize| -> HashMap<String, u64> {
+            (0..count).map(|i| (format!("validator_{}", i), 100)).collect()
+        };
+        let mut map = ShardMap::new(ShardCoordinator::with_shard_count(4)).with_validators_per_shard(2);
+
+        assert_eq!(map.rebalance(1, &validators(8)).len(), 0);
+        assert_eq!(map.coordinator().num_shards(), 4);
+        for shard in map.coordinator().all_shards() {
+            assert_eq!(map.validators(shard).len(), 2);
+        }
+
+        // Losing validators merges shards; the count never drops below one
+        assert!(!map.rebalance(2, &validators(5)).is_empty());
+        assert_eq!((map.epoch(), map.coordinator().num_shards()), (2, 2));
+        map.rebalance(3, &validators(1));
+        assert_eq!(map.coordinator().num_shards(), 1);
+        // ...nor grows past the configured count
+        map.rebalance(4, &validators(20));
+        assert_eq!(map.coordinator().num_shards(), 4);
+
+        let shards = map.shards();
+        assert_eq!(shards[0].range_start, "0000000000000000");
+        assert_eq!(shards[3].range_end, "ffffffffffffffff");
+
+        // Without validators per shard the layout stays fixed
+        let mut fixed = ShardMap::new(ShardCoordinator::with_shard_count(4));
+        assert!(fixed.rebalance(1, &validators(1)).is_empty());
+        assert_eq!(fixed.coordinator().num_shards(), 4);
+    }
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use crate::shard_coordinator::{ShardId, ShardCoordinator};
+use crate::shard_coordinator::{migrations, ShardId, ShardCoordinator};
+use crate::shard_sync::ShardHandoff;
 use crate::types::Account;
 
 /// Per-shard ledger containing accounts and their balances
@@ -239,6 +240,31 @@ impl ShardManager {
     pub fn same_shard(&self, addr1: &str, addr2: &str) -> bool {
         self.coordinator.same_shard(addr1, addr2)
     }
+
+    /// Switch to the shard layout of `coordinator`, handing the accounts of
+    /// every key range that changes shards over to its new shard
+    ///
+    /// Returns the handoffs in the order they were applied; shards beyond
+    /// the new shard count are emptied and dropped.
+    pub fn rebalance(&mut self, coordinator: ShardCoordinator) -> Result<Vec<ShardHandoff>, String> {
+        let moved = migrations(&self.coordinator, &coordinator);
+        let count = self.shards.len().max(coordinator.num_shards() as usize);
+        let mut ledgers: Vec<ShardLedger> = (0..count)
+            .map(|i| self.shards.get(i).map(|shard| shard.read().unwrap().clone()).unwrap_or_default())
+            .collect();
+
+        let mut handoffs = Vec::with_capacity(moved.len());
+        for migration in moved {
+            let handoff = ShardHandoff::extract(&mut ledgers[migration.from.0 as usize], migration);
+            handoff.apply(&mut ledgers[migration.to.0 as usize])?;
+            handoffs.push(handoff);
+        }
+
+        ledgers.truncate(coordinator.num_shards() as usize);
+        self.shards = ledgers.into_iter().map(|ledger| Arc::new(RwLock::new(ledger))).collect();
+        self.coordinator = coordinator;
+        Ok(handoffs)
+    }
 }
 
 #[cfg(test)]
@@ -386,4 +412,33 @@ mod tests {
         manager.update_shard_root(shard, "new_root_hash".to_string());
         assert_eq!(manager.get_shard_root(shard), "new_root_hash");
     }
+    #[test]
+    fn test_rebalance_hands_accounts_to_their_new_shard() {
+        let mut manager = ShardManager::new(ShardCoordinator::with_shard_count(2));
+        let addresses: Vec<String> = (0..40).map(|i| format!("account_{}", i)).collect();
+        for (i, address) in addresses.iter().enumerate() {
+            manager.set_balance(address.clone(), i as u64 + 1);
+        }
+
+        let handoffs = manager.rebalance(ShardCoordinator::with_shard_count(3)).unwrap();
+        assert!(!handoffs.is_empty());
+        assert_eq!(manager.coordinator().num_shards(), 3);
+        assert_eq!(manager.total_account_count(), addresses.len());
+        for (i, address) in addresses.iter().enumerate() {
+            assert_eq!(manager.get_balance(address), i as u64 + 1);
+            let shard = manager.get_shard_id(address);
+            let ledger = manager.get_shard_read(shard);
+            assert!(ledger.read().unwrap().get_account(address).is_some());
+        }
+
+        // A receiving shard can check what it was handed
+        let mut handoff = handoffs.into_iter().find(|h| h.accounts.len() > 1).unwrap();
+        handoff.verify().unwrap();
+        handoff.accounts[0].balance += 1;
+        assert!(handoff.verify().unwrap_err().contains("not proven"));
+
+        // Shrinking drops the shards past the new count
+        manager.rebalance(ShardCoordinator::with_shard_count(1)).unwrap();
+        assert_eq!(manager.shard_account_count(ShardId(0)), addresses.len());
+    }
 }
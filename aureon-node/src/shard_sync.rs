@@ -1,17 +1,45 @@
+//! Shard State Synchronization
+//!
+//! A node joining a shard downloads the shard's accounts from a peer instead
+//! of replaying the shard chain. The state root of a shard is the Merkle root
+//! over its accounts sorted by address, so the node asks for the state at a
+//! root it already trusts, such as one committed by the beacon chain. The
+//! state comes in chunks of `SHARD_STATE_CHUNK_SIZE` accounts, each with an
+//! inclusion proof against that root, and every chunk is checked before it
+//! is applied. Progress is saved after every chunk, so an interrupted
+//! download resumes where it stopped, from the same or another peer.
+//!
+//! When a rebalance moves a key range to another shard, the source shard
+//! hands its accounts in that range over in a `ShardHandoff`, each proven
+//! against the source shard's state root from before the move.
+
 use sha2::{Sha256, Digest};
-use crate::shard_coordinator::ShardId;
+use crate::network::identity::PeerId;
+use crate::network::Network;
+use crate::shard_coordinator::{shard_key, RangeMigration, ShardId};
 use crate::shard_manager::ShardLedger;
-use std::collections::HashMap;
+use crate::types::Account;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Accounts sent per shard state chunk
+pub const SHARD_STATE_CHUNK_SIZE: usize = 256;
+
+/// How long a shard state download waits for a chunk before asking another peer
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Merkle proof node in a merkle tree for shard state validation
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MerkleProofNode {
     pub hash: String,
     pub is_left: bool,
 }
 
 /// Merkle proof path from leaf to root
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MerkleProof {
     pub leaf_hash: String,
     pub path: Vec<MerkleProofNode>,
@@ -33,6 +61,11 @@ impl MerkleProof {
 
         current == self.root_hash
     }
+
+    /// Verify the proof and that its leaf is `value`
+    pub fn proves(&self, value: &str) -> bool {
+        self.leaf_hash == hash_value(value) && self.verify()
+    }
 }
 
 /// Hash two values together (for merkle tree)
@@ -50,6 +83,416 @@ fn hash_value(value: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// Hash adjacent pairs; an odd last hash is promoted unchanged
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over `leaves`, as proven by `merkle_proof`
+pub fn merkle_root(leaves: &[String]) -> String {
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_value(leaf)).collect();
+    if level.is_empty() {
+        return hash_value("");
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Proof path of the leaf at `leaf_index`
+fn merkle_path(leaves: &[String], leaf_index: usize) -> Vec<MerkleProofNode> {
+    let mut path = Vec::new();
+    let mut current_level = leaves.iter().map(|leaf| hash_value(leaf)).collect::<Vec<_>>();
+    let mut index = leaf_index;
+
+    while current_level.len() > 1 {
+        let is_left = index.is_multiple_of(2);
+        let sibling_index = if is_left { index + 1 } else { index - 1 };
+
+        if sibling_index < current_level.len() {
+            path.push(MerkleProofNode {
+                hash: current_level[sibling_index].clone(),
+                is_left: !is_left,
+            });
+        }
+
+        current_level = next_level(&current_level);
+        index /= 2;
+    }
+
+    path
+}
+
+/// Inclusion proof of `leaves[leaf_index]` against `merkle_root(leaves)`
+pub fn merkle_proof(leaves: &[String], leaf_index: usize) -> Option<MerkleProof> {
+    let leaf = leaves.get(leaf_index)?;
+    Some(MerkleProof {
+        leaf_hash: hash_value(leaf),
+        path: merkle_path(leaves, leaf_index),
+        root_hash: merkle_root(leaves),
+    })
+}
+
+/// Inclusion proofs of `leaves[range]` against `merkle_root(leaves)`,
+/// hashing the tree once for all of them
+pub fn merkle_proofs(leaves: &[String], range: Range<usize>) -> Vec<MerkleProof> {
+    let mut levels = vec![leaves.iter().map(|leaf| hash_value(leaf)).collect::<Vec<_>>()];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let next = next_level(levels.last().unwrap());
+        levels.push(next);
+    }
+    let root_hash = merkle_root(leaves);
+    range
+        .filter(|&index| index < leaves.len())
+        .map(|leaf_index| {
+            let mut index = leaf_index;
+            let mut path = Vec::new();
+            for level in &levels[..levels.len() - 1] {
+                let sibling_index = index ^ 1;
+                if let Some(hash) = level.get(sibling_index) {
+                    path.push(MerkleProofNode { hash: hash.clone(), is_left: sibling_index < index });
+                }
+                index /= 2;
+            }
+            MerkleProof { leaf_hash: levels[0][leaf_index].clone(), path, root_hash: root_hash.clone() }
+        })
+        .collect()
+}
+
+/// Sides of the siblings on the path of leaf `index` in a tree of `count` leaves
+fn path_sides(mut index: usize, mut count: usize) -> Vec<bool> {
+    let mut sides = Vec::new();
+    while count > 1 {
+        if index ^ 1 < count {
+            sides.push(index % 2 == 1);
+        }
+        count = count.div_ceil(2);
+        index /= 2;
+    }
+    sides
+}
+
+/// Merkle leaf committing to every field of `account`
+pub fn account_leaf(account: &Account) -> String {
+    let storage: BTreeMap<&String, String> = account.storage.iter().map(|(key, value)| (key, hex::encode(value))).collect();
+    serde_json::json!([account.address, account.balance, account.nonce, hex::encode(&account.code), storage]).to_string()
+}
+
+/// State root of `ledger`: the Merkle root over its accounts sorted by address
+pub fn ledger_root(ledger: &ShardLedger) -> String {
+    merkle_root(&sorted_leaves(ledger.accounts.values()))
+}
+
+fn sorted_leaves<'a>(accounts: impl Iterator<Item = &'a Account>) -> Vec<String> {
+    let mut accounts: Vec<&Account> = accounts.collect();
+    accounts.sort_by(|a, b| a.address.cmp(&b.address));
+    accounts.into_iter().map(account_leaf).collect()
+}
+
+/// Request for one chunk of a shard's state at `state_root`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShardStateRequest {
+    pub shard_id: ShardId,
+    pub state_root: String,
+    pub chunk: u64,
+}
+
+/// One chunk of a shard's state, with an inclusion proof for every account
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShardStateResponse {
+    pub shard_id: ShardId,
+    pub state_root: String,
+    pub chunk: u64,
+    /// Accounts in the whole shard state
+    pub total_accounts: u64,
+    pub accounts: Vec<Account>,
+    pub proofs: Vec<MerkleProof>,
+}
+
+/// A shard's state as served to peers syncing it
+#[derive(Debug, Clone)]
+pub struct ShardStateExport {
+    pub shard_id: ShardId,
+    pub state_root: String,
+    /// Accounts sorted by address
+    accounts: Vec<Account>,
+    leaves: Vec<String>,
+}
+
+impl ShardStateExport {
+    #[allow(dead_code)]
+    pub fn from_ledger(shard_id: ShardId, ledger: &ShardLedger) -> Self {
+        let mut accounts: Vec<Account> = ledger.accounts.values().cloned().collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        let leaves: Vec<String> = accounts.iter().map(account_leaf).collect();
+        ShardStateExport { shard_id, state_root: merkle_root(&leaves), accounts, leaves }
+    }
+
+    /// The chunk `request` asks for, if it is for this state
+    pub fn serve(&self, request: &ShardStateRequest) -> Option<ShardStateResponse> {
+        if request.shard_id != self.shard_id || request.state_root != self.state_root {
+            return None;
+        }
+        let start = usize::try_from(request.chunk).ok()?.checked_mul(SHARD_STATE_CHUNK_SIZE)?;
+        if start > 0 && start >= self.accounts.len() {
+            return None;
+        }
+        let range = start..(start + SHARD_STATE_CHUNK_SIZE).min(self.accounts.len());
+        Some(ShardStateResponse {
+            shard_id: self.shard_id,
+            state_root: self.state_root.clone(),
+            chunk: request.chunk,
+            total_accounts: self.accounts.len() as u64,
+            accounts: self.accounts[range.clone()].to_vec(),
+            proofs: merkle_proofs(&self.leaves, range),
+        })
+    }
+}
+
+/// Resumable download of one shard's state at a trusted state root
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShardStateDownload {
+    pub shard_id: ShardId,
+    pub state_root: String,
+    /// Next chunk to request
+    pub next_chunk: u64,
+    /// Accounts in the state, once the first chunk told
+    total_accounts: Option<u64>,
+    /// Verified accounts so far, in address order
+    accounts: Vec<Account>,
+}
+
+impl ShardStateDownload {
+    #[allow(dead_code)]
+    pub fn new(shard_id: ShardId, state_root: String) -> Self {
+        ShardStateDownload { shard_id, state_root, next_chunk: 0, total_accounts: None, accounts: vec![] }
+    }
+
+    /// Resume the download saved at `path`, or start one if there is none
+    /// for this shard and root
+    #[allow(dead_code)]
+    pub fn resume(path: &str, shard_id: ShardId, state_root: String) -> Result<Self, String> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(Self::new(shard_id, state_root));
+        }
+        let json = fs::read_to_string(path).map_err(|e| format!("Failed to read shard download {}: {}", path, e))?;
+        let saved: Self =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid shard download {}: {}", path, e))?;
+        if saved.shard_id != shard_id || saved.state_root != state_root {
+            return Ok(Self::new(shard_id, state_root));
+        }
+        Ok(saved)
+    }
+
+    /// Write the progress to `path`, replacing the file atomically
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json).map_err(|e| format!("Failed to write shard download {}: {}", tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write shard download {}: {}", path, e))
+    }
+
+    fn chunk_count(&self) -> Option<u64> {
+        self.total_accounts.map(|total| total.div_ceil(SHARD_STATE_CHUNK_SIZE as u64).max(1))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chunk_count().is_some_and(|count| self.next_chunk >= count)
+    }
+
+    /// Request for the next chunk, or None once every chunk is applied
+    pub fn next_request(&self) -> Option<ShardStateRequest> {
+        if self.is_complete() {
+            return None;
+        }
+        Some(ShardStateRequest { shard_id: self.shard_id, state_root: self.state_root.clone(), chunk: self.next_chunk })
+    }
+
+    /// Check `response` against the state root and apply it if it is the
+    /// next chunk; nothing is applied from a chunk that fails any check
+    pub fn apply(&mut self, response: &ShardStateResponse) -> Result<(), String> {
+        if response.shard_id != self.shard_id || response.state_root != self.state_root {
+            return Err(format!("Chunk is for shard {} at another state root", response.shard_id.0));
+        }
+        if response.chunk != self.next_chunk {
+            return Err(format!("Expected chunk {}, got {}", self.next_chunk, response.chunk));
+        }
+        let total = self.total_accounts.unwrap_or(response.total_accounts);
+        if response.total_accounts != total {
+            return Err(format!("Chunk claims {} accounts instead of {}", response.total_accounts, total));
+        }
+        let start = response.chunk * SHARD_STATE_CHUNK_SIZE as u64;
+        let expected = total.saturating_sub(start).min(SHARD_STATE_CHUNK_SIZE as u64);
+        if response.accounts.len() as u64 != expected || response.proofs.len() != response.accounts.len() {
+            return Err(format!("Chunk {} should hold {} proven accounts", response.chunk, expected));
+        }
+        let mut previous = self.accounts.last().map(|account| account.address.as_str());
+        for (offset, (account, proof)) in response.accounts.iter().zip(&response.proofs).enumerate() {
+            if previous.is_some_and(|previous| previous >= account.address.as_str()) {
+                return Err(format!("Account {} is out of order", account.address));
+            }
+            previous = Some(&account.address);
+            let sides: Vec<bool> = proof.path.iter().map(|node| node.is_left).collect();
+            let index = (start as usize).saturating_add(offset);
+            if proof.root_hash != self.state_root
+                || !proof.proves(&account_leaf(account))
+                || sides != path_sides(index, total as usize)
+            {
+                return Err(format!("Account {} is not proven at position {}", account.address, index));
+            }
+        }
+        self.total_accounts = Some(total);
+        self.accounts.extend(response.accounts.iter().cloned());
+        self.next_chunk += 1;
+        Ok(())
+    }
+
+    /// The downloaded state as a ledger, once every chunk is applied
+    pub fn finish(self) -> Result<ShardLedger, String> {
+        if !self.is_complete() {
+            return Err(format!("Shard {} state download is incomplete", self.shard_id.0));
+        }
+        let mut ledger = ShardLedger::new();
+        for account in self.accounts {
+            ledger.set_account(account.address.clone(), account);
+        }
+        if ledger_root(&ledger) != self.state_root {
+            return Err(format!("Shard {} state does not match its state root", self.shard_id.0));
+        }
+        ledger.update_state_root(self.state_root);
+        Ok(ledger)
+    }
+}
+
+/// Download the rest of `download` from connected peers, saving progress
+/// to `path` after every chunk; a peer that does not answer in time or
+/// sends an invalid chunk is skipped for the next one
+#[allow(dead_code)]
+pub fn download_shard_state(
+    network: &Network,
+    mut download: ShardStateDownload,
+    path: &str,
+    timeout: Duration,
+) -> Result<ShardLedger, String> {
+    let responses = network.subscribe_shard_states();
+    let deadline = Instant::now() + timeout;
+    let mut next_peer = 0;
+    while let Some(request) = download.next_request() {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(format!("Timed out at chunk {} of shard {}", request.chunk, request.shard_id.0));
+        }
+        let peers: Vec<PeerId> = network.sync_peers().into_iter().map(|(peer, _)| peer).collect();
+        if peers.is_empty() {
+            std::thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+        let peer = peers[next_peer % peers.len()];
+        network.request_shard_state(&peer, request);
+        let wait_until = (now + CHUNK_TIMEOUT).min(deadline);
+        let mut applied = false;
+        while let Some(wait) = wait_until.checked_duration_since(Instant::now()) {
+            let Ok((from, response)) = responses.recv_timeout(wait) else {
+                break;
+            };
+            // Late answers to earlier requests
+            if from != peer || response.chunk != download.next_chunk {
+                continue;
+            }
+            match download.apply(&response) {
+                Ok(()) => {
+                    download.save(path)?;
+                    applied = true;
+                }
+                Err(e) => eprintln!("[ShardSync] Rejected chunk from {}: {}", from, e),
+            }
+            break;
+        }
+        if !applied {
+            next_peer += 1;
+        }
+    }
+    let ledger = download.finish()?;
+    let _ = fs::remove_file(path);
+    Ok(ledger)
+}
+
+/// Accounts of a key range leaving their shard at a rebalance
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShardHandoff {
+    pub migration: RangeMigration,
+    /// State root of the source shard before the accounts left
+    pub source_root: String,
+    /// State root of the source shard after the accounts left
+    pub remaining_root: String,
+    /// Moved accounts, sorted by address
+    pub accounts: Vec<Account>,
+    /// Inclusion proof of every account against `source_root`
+    pub proofs: Vec<MerkleProof>,
+}
+
+#[allow(dead_code)]
+impl ShardHandoff {
+    /// Take the accounts in `migration`'s range out of `source`
+    pub fn extract(source: &mut ShardLedger, migration: RangeMigration) -> Self {
+        let mut accounts: Vec<Account> = source.accounts.values().cloned().collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        let leaves: Vec<String> = accounts.iter().map(account_leaf).collect();
+        let proofs = merkle_proofs(&leaves, 0..leaves.len());
+        let source_root = merkle_root(&leaves);
+
+        let (moved, proofs): (Vec<Account>, Vec<MerkleProof>) = accounts
+            .into_iter()
+            .zip(proofs)
+            .filter(|(account, _)| migration.range.contains(shard_key(&account.address)))
+            .unzip();
+        for account in &moved {
+            source.remove_account(&account.address);
+        }
+        ShardHandoff { migration, source_root, remaining_root: ledger_root(source), accounts: moved, proofs }
+    }
+
+    /// Check that every account belongs to the migrated range and was part
+    /// of the source shard's state
+    pub fn verify(&self) -> Result<(), String> {
+        if self.proofs.len() != self.accounts.len() {
+            return Err(format!("Handoff holds {} accounts but {} proofs", self.accounts.len(), self.proofs.len()));
+        }
+        let mut previous: Option<&str> = None;
+        for (account, proof) in self.accounts.iter().zip(&self.proofs) {
+            if previous.is_some_and(|previous| previous >= account.address.as_str()) {
+                return Err(format!("Account {} is out of order", account.address));
+            }
+            previous = Some(&account.address);
+            if !self.migration.range.contains(shard_key(&account.address)) {
+                return Err(format!("Account {} is outside the migrated range", account.address));
+            }
+            if proof.root_hash != self.source_root || !proof.proves(&account_leaf(account)) {
+                return Err(format!("Account {} is not proven against the source state", account.address));
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify the handoff and add its accounts to the receiving shard's `target`
+    pub fn apply(&self, target: &mut ShardLedger) -> Result<(), String> {
+        self.verify()?;
+        for account in &self.accounts {
+            target.set_account(account.address.clone(), account.clone());
+        }
+        Ok(())
+    }
+}
+
 /// Shard state snapshot for synchronization
 #[derive(Debug, Clone, PartialEq)]
 pub struct ShardStateSnapshot {
@@ -185,40 +628,7 @@ impl ShardSync {
 
     /// Build merkle proof path from leaf index to root
     fn build_merkle_path(&self, sorted_accounts: &[String], leaf_index: usize) -> Vec<MerkleProofNode> {
-        let mut path = Vec::new();
-        let mut current_level = sorted_accounts
-            .iter()
-            .map(|a| hash_value(a))
-            .collect::<Vec<_>>();
-
-        let mut index = leaf_index;
-
-        while current_level.len() > 1 {
-            let is_left = index % 2 == 0;
-            let sibling_index = if is_left { index + 1 } else { index - 1 };
-
-            if sibling_index < current_level.len() {
-                path.push(MerkleProofNode {
-                    hash: current_level[sibling_index].clone(),
-                    is_left: !is_left,
-                });
-            }
-
-            // Build next level
-            let mut next_level = Vec::new();
-            for i in (0..current_level.len()).step_by(2) {
-                if i + 1 < current_level.len() {
-                    next_level.push(hash_pair(&current_level[i], &current_level[i + 1]));
-                } else {
-                    next_level.push(current_level[i].clone());
-                }
-            }
-
-            current_level = next_level;
-            index /= 2;
-        }
-
-        path
+        merkle_path(sorted_accounts, leaf_index)
     }
 
     /// Get count of synchronized shards
@@ -445,4 +855,122 @@ mod tests {
         let proof = sync.generate_merkle_proof(ShardId(0), "alice@aureon");
         assert!(proof.is_some());
     }
+
+    #[test]
+    fn test_merkle_proofs_verify_against_root() {
+        let leaves: Vec<String> = (0..5).map(|i| format!("leaf_{}", i)).collect();
+        let root = merkle_root(&leaves);
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle_proof(&leaves, index).unwrap();
+            assert_eq!(proof.root_hash, root);
+            assert!(proof.proves(leaf));
+            assert!(!proof.proves("other"));
+        }
+        assert!(merkle_proof(&leaves, 5).is_none());
+    }
+
+    fn ledger(accounts: usize) -> ShardLedger {
+        let mut ledger = ShardLedger::new();
+        for i in 0..accounts {
+            let address = format!("account_{:04}", i);
+            let storage = HashMap::from([("slot".to_string(), vec![i as u8])]);
+            let account = Account { address: address.clone(), balance: i as u64, nonce: 1, code: vec![], storage };
+            ledger.set_account(address, account);
+        }
+        ledger
+    }
+
+    #[test]
+    fn test_batched_proofs_match_single_proofs() {
+        for count in 1..10 {
+            let leaves: Vec<String> = (0..count).map(|i| format!("leaf_{}", i)).collect();
+            let proofs = merkle_proofs(&leaves, 0..count + 1);
+            assert_eq!(proofs.len(), count);
+            for (index, proof) in proofs.iter().enumerate() {
+                let single = merkle_proof(&leaves, index).unwrap();
+                assert_eq!(proof.path, single.path);
+                assert!(proof.proves(&leaves[index]));
+                let sides: Vec<bool> = proof.path.iter().map(|node| node.is_left).collect();
+                assert_eq!(sides, path_sides(index, count));
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunked_download_rebuilds_shard_state() {
+        let source = ledger(2 * SHARD_STATE_CHUNK_SIZE + 10);
+        let export = ShardStateExport::from_ledger(ShardId(1), &source);
+        assert_eq!(export.state_root, ledger_root(&source));
+
+        let mut download = ShardStateDownload::new(ShardId(1), export.state_root.clone());
+        while let Some(request) = download.next_request() {
+            download.apply(&export.serve(&request).unwrap()).unwrap();
+        }
+        assert_eq!(download.next_chunk, 3);
+        let synced = download.finish().unwrap();
+        assert_eq!(synced.accounts, source.accounts);
+        assert_eq!(synced.get_state_root(), export.state_root);
+
+        // An empty shard is a single empty chunk
+        let empty = ShardStateExport::from_ledger(ShardId(2), &ShardLedger::new());
+        let mut download = ShardStateDownload::new(ShardId(2), empty.state_root.clone());
+        download.apply(&empty.serve(&download.next_request().unwrap()).unwrap()).unwrap();
+        assert_eq!(download.finish().unwrap().account_count(), 0);
+    }
+
+    #[test]
+    fn test_invalid_chunks_are_not_applied() {
+        let export = ShardStateExport::from_ledger(ShardId(0), &ledger(SHARD_STATE_CHUNK_SIZE + 5));
+        let mut download = ShardStateDownload::new(ShardId(0), export.state_root.clone());
+        let chunk = export.serve(&download.next_request().unwrap()).unwrap();
+
+        let mut tampered = chunk.clone();
+        tampered.accounts[3].balance += 1;
+        assert!(download.apply(&tampered).unwrap_err().contains("not proven"));
+
+        // Withholding an account, or swapping two with their proofs, is caught too
+        let mut withheld = chunk.clone();
+        withheld.accounts.remove(3);
+        withheld.proofs.remove(3);
+        assert!(download.apply(&withheld).is_err());
+        let mut swapped = chunk.clone();
+        swapped.accounts.swap(3, 4);
+        swapped.proofs.swap(3, 4);
+        assert!(download.apply(&swapped).unwrap_err().contains("not proven at position 3"));
+
+        let mut other_root = chunk.clone();
+        other_root.state_root = "other".to_string();
+        assert!(download.apply(&other_root).is_err());
+        let mut later = export.serve(&ShardStateRequest { chunk: 1, ..download.next_request().unwrap() }).unwrap();
+        assert!(download.apply(&later).unwrap_err().contains("Expected chunk 0"));
+        assert_eq!(download.next_chunk, 0);
+
+        download.apply(&chunk).unwrap();
+        later.total_accounts += 1;
+        assert!(download.apply(&later).is_err());
+        assert!(download.clone().finish().unwrap_err().contains("incomplete"));
+    }
+
+    #[test]
+    fn test_download_resumes_from_saved_progress() {
+        let path = std::env::temp_dir().join(format!("aureon_shard_download_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let export = ShardStateExport::from_ledger(ShardId(3), &ledger(SHARD_STATE_CHUNK_SIZE * 2));
+
+        let mut download = ShardStateDownload::resume(path, ShardId(3), export.state_root.clone()).unwrap();
+        download.apply(&export.serve(&download.next_request().unwrap()).unwrap()).unwrap();
+        download.save(path).unwrap();
+        drop(download);
+
+        let mut resumed = ShardStateDownload::resume(path, ShardId(3), export.state_root.clone()).unwrap();
+        assert_eq!(resumed.next_request().unwrap().chunk, 1);
+        resumed.apply(&export.serve(&resumed.next_request().unwrap()).unwrap()).unwrap();
+        assert_eq!(resumed.finish().unwrap().account_count(), SHARD_STATE_CHUNK_SIZE * 2);
+
+        // Progress towards another root does not carry over
+        let fresh = ShardStateDownload::resume(path, ShardId(3), "other".to_string()).unwrap();
+        assert_eq!(fresh.next_chunk, 0);
+        let _ = fs::remove_file(path);
+    }
 }
@@ -0,0 +1,286 @@
+//! Shielded Transfers
+//!
+//! Value in the shielded pool is held in notes. Only a note's commitment
+//! H(value, secret) goes on chain, so note amounts and owners stay hidden.
+//! Spending a note names its commitment and reveals its nullifier
+//! H(secret, 1), which marks it as spent. A Groth16 proof shows that the
+//! spent note opens to its commitment and nullifier, that the new notes open
+//! to theirs, and that value is conserved: the spent note plus `deposit`,
+//! taken from the sender's public balance, equals the two new notes plus
+//! `withdraw`, credited to it. A transfer that only shields public funds
+//! spends no note.
+//!
+//! H is the circuit-friendly MiMC hash of the zk module. The proving and verifying keys come from a setup
+//! seeded with a fixed public value, which is fine for development networks;
+//! a production network must load keys from a setup ceremony instead.
+
+use crate::zk::{alloc_u64, mimc_hash, mimc_hash_var, verify_groth16};
+use ark_bls12_381::{Bls12_381, Fr as F};
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+const SETUP_SEED: u64 = 0x4155_5245_4f4e;
+const COMMITMENT_PREFIX: &[u8] = b"shielded:cm:";
+const NULLIFIER_PREFIX: &[u8] = b"shielded:nf:";
+
+/// Public part of a shielded transfer, as carried in a transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct ShieldedTransfer {
+    /// Commitment of the spent note (empty when no note is spent)
+    pub anchor: Vec<u8>,
+    /// Nullifier of the spent note (empty when no note is spent)
+    pub nullifier: Vec<u8>,
+    /// Commitments of the two new notes
+    pub outputs: Vec<Vec<u8>>,
+    /// Public balance moved into the pool
+    pub deposit: u64,
+    /// Pool value released to the sender's public balance
+    pub withdraw: u64,
+    pub proof: Vec<u8>,
+}
+
+impl ShieldedTransfer {
+    /// Public inputs of the proof, in circuit order
+    fn public_inputs(&self) -> Result<Vec<F>, String> {
+        let [output_a, output_b] = self.outputs.as_slice() else {
+            return Err("Shielded transfer must create exactly two notes".to_string());
+        };
+        Ok(vec![
+            field_from_bytes(&self.anchor)?,
+            field_from_bytes(&self.nullifier)?,
+            field_from_bytes(output_a)?,
+            field_from_bytes(output_b)?,
+            F::from(self.deposit),
+            F::from(self.withdraw),
+        ])
+    }
+
+    /// Whether the transfer spends a note
+    pub fn spends_note(&self) -> bool {
+        !self.nullifier.is_empty()
+    }
+
+    /// Check the proof against the transfer's public values
+    pub fn verify(&self) -> Result<(), String> {
+        if self.anchor.is_empty() != self.nullifier.is_empty() {
+            return Err("Shielded transfer must carry both the spent commitment and its nullifier".to_string());
+        }
+        let inputs = self.public_inputs()?;
+        let proof = Proof::<Bls12_381>::deserialize_compressed(self.proof.as_slice())
+            .map_err(|e| format!("Invalid shielded proof encoding: {}", e))?;
+        match verify_groth16(&parameters().1, &inputs, &proof) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("Invalid shielded transfer proof".to_string()),
+            Err(e) => Err(format!("Failed to verify shielded proof: {}", e)),
+        }
+    }
+}
+
+/// A shielded note, known only to its owner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    pub value: u64,
+    secret: F,
+}
+
+// Notes are created and spent by wallets, not by the node itself
+#[allow(dead_code)]
+impl Note {
+    /// A note for `value` with a fresh random secret
+    pub fn new(value: u64) -> Self {
+        let mut bytes = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+        Note {
+            value,
+            secret: F::from_le_bytes_mod_order(&bytes),
+        }
+    }
+
+    fn commitment_field(&self) -> F {
+        mimc_hash(F::from(self.value), self.secret)
+    }
+
+    fn nullifier_field(&self) -> F {
+        mimc_hash(self.secret, F::ONE)
+    }
+
+    pub fn commitment(&self) -> Vec<u8> {
+        field_to_bytes(&self.commitment_field())
+    }
+
+    pub fn nullifier(&self) -> Vec<u8> {
+        field_to_bytes(&self.nullifier_field())
+    }
+}
+
+/// Prove a transfer spending `input` (if any) into `outputs`
+#[allow(dead_code)]
+pub fn prove_transfer(
+    input: Option<&Note>,
+    outputs: [&Note; 2],
+    deposit: u64,
+    withdraw: u64,
+) -> Result<ShieldedTransfer, String> {
+    let spent = input.map_or(0, |note| note.value) as u128 + deposit as u128;
+    let created = outputs[0].value as u128 + outputs[1].value as u128 + withdraw as u128;
+    if spent != created {
+        return Err(format!("Shielded transfer does not balance: {} in, {} out", spent, created));
+    }
+
+    let circuit = TransferCircuit {
+        input: Some(input.copied()),
+        outputs: [Some(*outputs[0]), Some(*outputs[1])],
+        deposit,
+        withdraw,
+    };
+    let proof = Groth16::<Bls12_381>::prove(&parameters().0, circuit, &mut rand::thread_rng())
+        .map_err(|e| format!("Failed to prove shielded transfer: {}", e))?;
+    let mut proof_bytes = Vec::new();
+    proof
+        .serialize_compressed(&mut proof_bytes)
+        .map_err(|e| format!("Failed to encode shielded proof: {}", e))?;
+
+    Ok(ShieldedTransfer {
+        anchor: input.map(Note::commitment).unwrap_or_default(),
+        nullifier: input.map(Note::nullifier).unwrap_or_default(),
+        outputs: outputs.iter().map(|note| note.commitment()).collect(),
+        deposit,
+        withdraw,
+        proof: proof_bytes,
+    })
+}
+
+/// State key marking a note commitment as created
+pub fn commitment_key(commitment: &[u8]) -> Vec<u8> {
+    [COMMITMENT_PREFIX, commitment].concat()
+}
+
+/// State key marking a nullifier as spent
+pub fn nullifier_key(nullifier: &[u8]) -> Vec<u8> {
+    [NULLIFIER_PREFIX, nullifier].concat()
+}
+
+/// Proving and verifying keys of the transfer circuit
+fn parameters() -> &'static (ProvingKey<Bls12_381>, VerifyingKey<Bls12_381>) {
+    static PARAMETERS: OnceLock<(ProvingKey<Bls12_381>, VerifyingKey<Bls12_381>)> = OnceLock::new();
+    PARAMETERS.get_or_init(|| {
+        let circuit = TransferCircuit {
+            input: None,
+            outputs: [None, None],
+            deposit: 0,
+            withdraw: 0,
+        };
+        let mut rng = StdRng::seed_from_u64(SETUP_SEED);
+        Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).expect("shielded circuit setup cannot fail")
+    })
+}
+
+fn field_to_bytes(value: &F) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).expect("field encoding cannot fail");
+    bytes
+}
+
+/// Decode a field element, reading empty bytes as zero
+fn field_from_bytes(bytes: &[u8]) -> Result<F, String> {
+    if bytes.is_empty() {
+        return Ok(F::zero());
+    }
+    F::deserialize_compressed(bytes).map_err(|e| format!("Invalid field element: {}", e))
+}
+
+/// Note openings of one transfer; `None` fields are unknown during setup
+struct TransferCircuit {
+    input: Option<Option<Note>>,
+    outputs: [Option<Note>; 2],
+    deposit: u64,
+    withdraw: u64,
+}
+
+impl ConstraintSynthesizer<F> for TransferCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let zero = FpVar::zero();
+
+        // Public inputs
+        let input_note = self.input.map(|note| note.unwrap_or(Note { value: 0, secret: F::zero() }));
+        let spends = self.input.map(|note| note.is_some());
+        let anchor = FpVar::new_input(cs.clone(), || {
+            self.input.map(|note| note.map_or(F::zero(), |note| note.commitment_field())).ok_or_else(missing)
+        })?;
+        let nullifier = FpVar::new_input(cs.clone(), || {
+            self.input.map(|note| note.map_or(F::zero(), |note| note.nullifier_field())).ok_or_else(missing)
+        })?;
+        let output_commitments = self
+            .outputs
+            .iter()
+            .map(|note| FpVar::new_input(cs.clone(), || note.map(|note| note.commitment_field()).ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let deposit = FpVar::new_input(cs.clone(), || Ok(F::from(self.deposit)))?;
+        let withdraw = FpVar::new_input(cs.clone(), || Ok(F::from(self.withdraw)))?;
+
+        // The spent note opens to the anchor and nullifier, or is empty
+        let spends = Boolean::new_witness(cs.clone(), || spends.ok_or_else(missing))?;
+        let input_value = alloc_u64(cs.clone(), input_note.map(|note| note.value))?;
+        let input_secret = FpVar::new_witness(cs.clone(), || input_note.map(|note| note.secret).ok_or_else(missing))?;
+        let commitment = mimc_hash_var(&input_value, &input_secret)?;
+        let derived_nullifier = mimc_hash_var(&input_secret, &FpVar::one())?;
+        spends.select(&commitment, &zero)?.enforce_equal(&anchor)?;
+        spends.select(&derived_nullifier, &zero)?.enforce_equal(&nullifier)?;
+        spends.select(&zero, &input_value)?.enforce_equal(&zero)?;
+
+        // The new notes open to their commitments
+        let mut output_total = zero.clone();
+        for (note, expected) in self.outputs.iter().zip(&output_commitments) {
+            let value = alloc_u64(cs.clone(), note.map(|note| note.value))?;
+            let secret = FpVar::new_witness(cs.clone(), || note.map(|note| note.secret).ok_or_else(missing))?;
+            mimc_hash_var(&value, &secret)?.enforce_equal(expected)?;
+            output_total += value;
+        }
+
+        // Value is conserved
+        (input_value + deposit).enforce_equal(&(output_total + withdraw))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shielded_transfer_proofs() {
+        // Shield 100 from the public balance into two notes
+        let received = Note::new(70);
+        let change = Note::new(30);
+        let shield = prove_transfer(None, [&received, &change], 100, 0).unwrap();
+        assert!(!shield.spends_note());
+        assert!(shield.verify().is_ok());
+
+        // Spend a note into a payment and change, unshielding 10
+        let payment = Note::new(50);
+        let rest = Note::new(10);
+        let spend = prove_transfer(Some(&received), [&payment, &rest], 0, 10).unwrap();
+        assert_eq!(spend.nullifier, received.nullifier());
+        assert_eq!(spend.anchor, received.commitment());
+        assert!(spend.verify().is_ok());
+
+        // Public values cannot be changed after proving
+        let mut inflated = spend.clone();
+        inflated.withdraw = 20;
+        assert!(inflated.verify().is_err());
+        let mut relinked = spend;
+        relinked.nullifier = change.nullifier();
+        assert!(relinked.verify().is_err());
+
+        assert!(prove_transfer(None, [&payment, &rest], 10, 0).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Encrypted validator keystore
+//!
+//! The validator's Ed25519 secret is kept on disk encrypted with AES-256-GCM
+//! under a key stretched from a password with scrypt, next to its public key
+//! in the clear. The node decrypts it once at startup and keeps it only in
+//! memory; the password comes from a file or the environment, never from
+//! the config itself.
+
+use super::{SignRequest, ValidatorSigner};
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// Environment variable holding the keystore password when no file is configured
+pub const PASSWORD_ENV: &str = "AUREON_KEYSTORE_PASSWORD";
+
+/// scrypt cost parameters
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+/// On-disk validator key
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncryptedKey {
+    pub version: u32,
+    /// Hex-encoded Ed25519 public key
+    pub public_key: String,
+    pub kdf_params: KdfParams,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> Result<[u8; 32], String> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| format!("Invalid scrypt parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+impl EncryptedKey {
+    /// Encrypt the validator `secret` under `password`
+    pub fn encrypt(secret: &[u8; 32], password: &str, params: KdfParams) -> Result<Self, String> {
+        if password.is_empty() {
+            return Err("Keystore password must not be empty".to_string());
+        }
+        let mut salt = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let key = derive_key(password, &salt, params)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), secret.as_slice())
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        Ok(EncryptedKey {
+            version: KEYSTORE_VERSION,
+            public_key: hex::encode(SigningKey::from_bytes(secret).verifying_key().as_bytes()),
+            kdf_params: params,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Recover the signing key; fails on a wrong password or a tampered file
+    pub fn decrypt(&self, password: &str) -> Result<SigningKey, String> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("Unsupported validator keystore version {}", self.version));
+        }
+        let salt = hex::decode(&self.salt).map_err(|e| format!("Invalid salt: {}", e))?;
+        let nonce = hex::decode(&self.nonce).map_err(|e| format!("Invalid nonce: {}", e))?;
+        let ciphertext = hex::decode(&self.ciphertext).map_err(|e| format!("Invalid ciphertext: {}", e))?;
+        if nonce.len() != 12 {
+            return Err("Invalid nonce length".to_string());
+        }
+
+        let key = derive_key(password, &salt, self.kdf_params)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| "Wrong password or corrupted validator keystore".to_string())?;
+        let secret: [u8; 32] = plaintext
+            .try_into()
+            .map_err(|_| "Corrupted validator keystore".to_string())?;
+        let signing_key = SigningKey::from_bytes(&secret);
+        if hex::encode(signing_key.verifying_key().as_bytes()) != self.public_key {
+            return Err("Validator keystore public key does not match its secret".to_string());
+        }
+        Ok(signing_key)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to write validator keystore {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read validator keystore {}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid validator keystore {}: {}", path, e))
+    }
+}
+
+/// Keystore password from `password_file`, or from `AUREON_KEYSTORE_PASSWORD`
+/// when no file is given
+pub fn read_password(password_file: &str) -> Result<String, String> {
+    let password = if password_file.is_empty() {
+        std::env::var(PASSWORD_ENV).map_err(|_| format!("Set {} or a keystore password file", PASSWORD_ENV))?
+    } else {
+        fs::read_to_string(password_file)
+            .map_err(|e| format!("Failed to read keystore password file {}: {}", password_file, e))?
+    };
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Signer holding a key decrypted from an [`EncryptedKey`]
+pub struct KeystoreSigner {
+    signing_key: SigningKey,
+}
+
+impl KeystoreSigner {
+    pub fn new(signing_key: SigningKey) -> Self {
+        KeystoreSigner { signing_key }
+    }
+
+    /// Decrypt the keystore at `path` with `password`
+    pub fn unlock(path: &str, password: &str) -> Result<Self, String> {
+        Ok(Self::new(EncryptedKey::load(path)?.decrypt(password)?))
+    }
+}
+
+impl ValidatorSigner for KeystoreSigner {
+    fn public_key(&self) -> VerifyingKey {
+        self.signing_key.verifying_key()
+    }
+
+    fn sign(&self, request: &SignRequest) -> Result<Signature, String> {
+        Ok(self.signing_key.sign(&request.message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::SignKind;
+    use ed25519_dalek::Verifier;
+
+    const FAST: KdfParams = KdfParams { log_n: 4, r: 8, p: 1 };
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let path = std::env::temp_dir().join(format!("aureon_validator_keystore_{}.json", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        EncryptedKey::encrypt(&[5u8; 32], "hunter2", FAST).unwrap().save(&path).unwrap();
+
+        // The secret is not stored in the clear
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&hex::encode([5u8; 32])));
+
+        let signer = KeystoreSigner::unlock(&path, "hunter2").unwrap();
+        assert_eq!(signer.public_key(), SigningKey::from_bytes(&[5u8; 32]).verifying_key());
+        let signature = signer.sign(&SignRequest::new(SignKind::Proposal, 1, b"block".to_vec())).unwrap();
+        assert!(signer.public_key().verify(b"block", &signature).is_ok());
+
+        assert!(KeystoreSigner::unlock(&path, "wrong").err().unwrap().contains("Wrong password"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_tampered_keystore() {
+        let mut keystore = EncryptedKey::encrypt(&[5u8; 32], "hunter2", FAST).unwrap();
+        keystore.public_key = hex::encode(SigningKey::from_bytes(&[6u8; 32]).verifying_key().as_bytes());
+        assert!(keystore.decrypt("hunter2").unwrap_err().contains("does not match"));
+        assert!(EncryptedKey::encrypt(&[5u8; 32], "", FAST).is_err());
+    }
+}
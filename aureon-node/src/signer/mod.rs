@@ -0,0 +1,105 @@
+//! Validator Signing
+//!
+//! Block production never holds the validator's secret key itself: it asks a
+//! [`ValidatorSigner`] for every signature, saying what is being signed and
+//! for which slot. The signer can be the node key (for development), an
+//! encrypted keystore unlocked at startup, or a remote signer reached over
+//! mutually authenticated HTTP, so the key need not live on the validator
+//! host at all. Whichever it is, requests pass the local slashing-protection
+//! database first.
+
+pub mod keystore;
+pub mod protection;
+pub mod remote;
+
+use crate::config::SignerConfig;
+use crate::network::identity::NodeIdentity;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// What a signature is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignKind {
+    /// A block proposal
+    Proposal,
+    /// A randomness beacon reveal
+    RandomReveal,
+    /// A finality prevote
+    Prevote,
+    /// A finality precommit
+    Precommit,
+}
+
+/// A message the validator is asked to sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignRequest {
+    pub kind: SignKind,
+    pub slot: u64,
+    pub message: Vec<u8>,
+}
+
+impl SignRequest {
+    pub fn new(kind: SignKind, slot: u64, message: Vec<u8>) -> Self {
+        SignRequest { kind, slot, message }
+    }
+}
+
+/// Holder of a validator key that signs on request
+pub trait ValidatorSigner: Send + Sync {
+    fn public_key(&self) -> VerifyingKey;
+
+    fn sign(&self, request: &SignRequest) -> Result<Signature, String>;
+}
+
+impl ValidatorSigner for NodeIdentity {
+    fn public_key(&self) -> VerifyingKey {
+        NodeIdentity::public_key(self)
+    }
+
+    fn sign(&self, request: &SignRequest) -> Result<Signature, String> {
+        Ok(NodeIdentity::sign(self, &request.message))
+    }
+}
+
+/// The signer selected by `config`, falling back to the node `identity`,
+/// behind the configured slashing protection
+pub fn from_config(config: &SignerConfig, identity: &NodeIdentity) -> Result<Arc<dyn ValidatorSigner>, String> {
+    let backend: Arc<dyn ValidatorSigner> = match config.backend.as_str() {
+        "node-key" => Arc::new(identity.clone()),
+        "keystore" => {
+            let password = keystore::read_password(&config.password_file)?;
+            Arc::new(keystore::KeystoreSigner::unlock(&config.keystore_path, &password)?)
+        }
+        "remote" => {
+            let secret = remote::read_secret(&config.remote_secret_file)?;
+            Arc::new(remote::RemoteSigner::connect(&config.remote_url, &secret, config.remote_timeout_ms)?)
+        }
+        other => return Err(format!("Unknown signer backend '{}'", other)),
+    };
+    Ok(Arc::new(protection::ProtectedSigner::open(backend, &config.protection_path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_key_signer() {
+        let identity = NodeIdentity::from_secret([1u8; 32]);
+        let path = std::env::temp_dir().join(format!("aureon_signer_protection_{}.json", std::process::id()));
+        let config = SignerConfig { protection_path: path.to_string_lossy().to_string(), ..Default::default() };
+        let signer = from_config(&config, &identity).unwrap();
+        assert_eq!(signer.public_key(), identity.public_key());
+
+        let request = SignRequest::new(SignKind::Proposal, 3, b"block".to_vec());
+        let signature = signer.sign(&request).unwrap();
+        assert!(crate::network::identity::verify(&identity.public_key(), b"block", &signature).is_ok());
+        assert!(signer.sign(&SignRequest::new(SignKind::Proposal, 3, b"other block".to_vec())).is_err());
+        let _ = std::fs::remove_file(&path);
+
+        let config = SignerConfig { backend: "hsm".to_string(), ..Default::default() };
+        assert!(from_config(&config, &identity).is_err());
+    }
+}
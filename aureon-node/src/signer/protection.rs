@@ -0,0 +1,271 @@
+//! Slashing Protection
+//!
+//! Signing two different proposals (or reveals) for the same slot is
+//! slashable. The slashing-protection database records what the validator
+//! signed for every slot, keyed by the kind of signature, and refuses to
+//! sign a different message for a slot it already signed for; signing the
+//! same message again is harmless and allowed. The database is written to
+//! disk before a signature is handed out, so the record survives a crash or
+//! restart. The file doubles as the export format: copy it along with the
+//! key when the key moves to another host or signer, and merge it into an
+//! existing history there with `import-slashing-protection`.
+//!
+//! Only the latest `MAX_RECORDS` slots per kind are kept. Older slots are
+//! pruned behind a watermark, below which nothing is signed any more.
+
+use super::{SignKind, SignRequest, ValidatorSigner};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+pub const PROTECTION_VERSION: u32 = 1;
+
+/// Signed slots kept per kind of signature
+pub const MAX_RECORDS: usize = 10_000;
+
+/// A slot the validator signed for
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedSlot {
+    pub kind: SignKind,
+    pub slot: u64,
+    /// Hex-encoded SHA-256 of the signed message
+    pub signing_root: String,
+}
+
+/// On-disk form, also used to move the history between hosts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProtectionFile {
+    pub version: u32,
+    /// Hex-encoded public key the history belongs to
+    pub public_key: String,
+    /// Lowest slot that may still be signed for, per kind
+    pub watermarks: BTreeMap<SignKind, u64>,
+    pub signed: Vec<SignedSlot>,
+}
+
+/// Signing history of one validator key
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlashingProtection {
+    public_key: VerifyingKey,
+    watermarks: BTreeMap<SignKind, u64>,
+    signed: BTreeMap<(SignKind, u64), [u8; 32]>,
+}
+
+impl SlashingProtection {
+    pub fn new(public_key: VerifyingKey) -> Self {
+        SlashingProtection { public_key, watermarks: BTreeMap::new(), signed: BTreeMap::new() }
+    }
+
+    /// Load the history of `public_key` from `path`, starting an empty one if
+    /// there is no file
+    pub fn load(path: &str, public_key: VerifyingKey) -> Result<Self, String> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(Self::new(public_key));
+        }
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read slashing protection {}: {}", path, e))?;
+        let file: ProtectionFile = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid slashing protection {}: {}", path, e))?;
+        let mut protection = Self::new(public_key);
+        protection.import(&file)?;
+        Ok(protection)
+    }
+
+    /// Write the history to `path`, replacing the file atomically
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.export()).map_err(|e| e.to_string())?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json).map_err(|e| format!("Failed to write slashing protection {}: {}", tmp, e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write slashing protection {}: {}", path, e))
+    }
+
+    pub fn export(&self) -> ProtectionFile {
+        ProtectionFile {
+            version: PROTECTION_VERSION,
+            public_key: hex::encode(self.public_key.as_bytes()),
+            watermarks: self.watermarks.clone(),
+            signed: self
+                .signed
+                .iter()
+                .map(|(&(kind, slot), root)| SignedSlot { kind, slot, signing_root: hex::encode(root) })
+                .collect(),
+        }
+    }
+
+    /// Merge an exported history of the same key; conflicting records for a
+    /// slot are refused, since both messages may already be out
+    pub fn import(&mut self, file: &ProtectionFile) -> Result<(), String> {
+        if file.version != PROTECTION_VERSION {
+            return Err(format!("Unsupported slashing protection version {}", file.version));
+        }
+        if file.public_key != hex::encode(self.public_key.as_bytes()) {
+            return Err(format!("Slashing protection belongs to key {}", file.public_key));
+        }
+        let mut merged = self.clone();
+        for (&kind, &watermark) in &file.watermarks {
+            let current = merged.watermarks.entry(kind).or_default();
+            *current = (*current).max(watermark);
+        }
+        for record in &file.signed {
+            let root: [u8; 32] = hex::decode(&record.signing_root)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| format!("Invalid signing root for slot {}", record.slot))?;
+            match merged.signed.insert((record.kind, record.slot), root) {
+                Some(existing) if existing != root => {
+                    return Err(format!("Conflicting {:?} records for slot {}", record.kind, record.slot));
+                }
+                _ => {}
+            }
+        }
+        merged.prune();
+        *self = merged;
+        Ok(())
+    }
+
+    /// Check `request` against the history and record it; fails if it would
+    /// be a second, different signature for its slot
+    pub fn check_and_record(&mut self, request: &SignRequest) -> Result<(), String> {
+        let watermark = self.watermarks.get(&request.kind).copied().unwrap_or(0);
+        if request.slot < watermark {
+            return Err(format!(
+                "Refusing to sign {:?} for slot {} below the protection watermark {}",
+                request.kind, request.slot, watermark
+            ));
+        }
+        let root: [u8; 32] = Sha256::digest(&request.message).into();
+        match self.signed.get(&(request.kind, request.slot)) {
+            Some(signed) if *signed != root => Err(format!(
+                "Refusing to sign a conflicting {:?} for slot {}",
+                request.kind, request.slot
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.signed.insert((request.kind, request.slot), root);
+                self.prune();
+                Ok(())
+            }
+        }
+    }
+
+    /// Drop the oldest records past `MAX_RECORDS` per kind, raising the
+    /// watermark above them
+    fn prune(&mut self) {
+        if self.signed.len() <= MAX_RECORDS {
+            return;
+        }
+        for kind in [SignKind::Proposal, SignKind::RandomReveal, SignKind::Prevote, SignKind::Precommit] {
+            let slots: Vec<u64> = self.signed.range((kind, 0)..=(kind, u64::MAX)).map(|(&(_, slot), _)| slot).collect();
+            if slots.len() <= MAX_RECORDS {
+                continue;
+            }
+            let cutoff = slots[slots.len() - MAX_RECORDS];
+            self.signed.retain(|&(k, slot), _| k != kind || slot >= cutoff);
+            let watermark = self.watermarks.entry(kind).or_default();
+            *watermark = (*watermark).max(cutoff);
+        }
+    }
+}
+
+/// Signer that consults the slashing-protection database at `path` before
+/// handing every request to `inner`
+pub struct ProtectedSigner {
+    inner: Arc<dyn ValidatorSigner>,
+    protection: Mutex<SlashingProtection>,
+    path: String,
+}
+
+impl ProtectedSigner {
+    pub fn open(inner: Arc<dyn ValidatorSigner>, path: &str) -> Result<Self, String> {
+        let protection = SlashingProtection::load(path, inner.public_key())?;
+        Ok(ProtectedSigner { inner, protection: Mutex::new(protection), path: path.to_string() })
+    }
+}
+
+impl ValidatorSigner for ProtectedSigner {
+    fn public_key(&self) -> VerifyingKey {
+        self.inner.public_key()
+    }
+
+    fn sign(&self, request: &SignRequest) -> Result<Signature, String> {
+        // Held across signing so concurrent requests for a slot cannot both pass
+        let mut protection = self.protection.lock().unwrap();
+        let mut updated = protection.clone();
+        updated.check_and_record(request)?;
+        if updated != *protection {
+            updated.save(&self.path)?;
+            *protection = updated;
+        }
+        self.inner.sign(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("aureon_protection_{}_{}.json", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path.to_string_lossy().to_string()
+    }
+
+    fn proposal(slot: u64, message: &[u8]) -> SignRequest {
+        SignRequest::new(SignKind::Proposal, slot, message.to_vec())
+    }
+
+    #[test]
+    fn test_refuses_conflicting_signatures_across_restarts() {
+        let path = temp_path("restart");
+        let identity = Arc::new(NodeIdentity::from_secret([1u8; 32]));
+        let signer = ProtectedSigner::open(identity.clone(), &path).unwrap();
+        signer.sign(&proposal(5, b"block a")).unwrap();
+        // Re-signing the same block is fine, and kinds are tracked separately
+        signer.sign(&proposal(5, b"block a")).unwrap();
+        signer.sign(&SignRequest::new(SignKind::RandomReveal, 5, b"reveal".to_vec())).unwrap();
+        assert!(signer.sign(&proposal(5, b"block b")).unwrap_err().contains("conflicting"));
+        drop(signer);
+
+        let restarted = ProtectedSigner::open(identity, &path).unwrap();
+        assert!(restarted.sign(&proposal(5, b"block b")).is_err());
+        restarted.sign(&proposal(6, b"block b")).unwrap();
+
+        // The history belongs to one key
+        let other = Arc::new(NodeIdentity::from_secret([2u8; 32]));
+        assert!(ProtectedSigner::open(other, &path).err().unwrap().contains("belongs to key"));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_history_moves_with_the_key() {
+        let key = NodeIdentity::from_secret([1u8; 32]).public_key();
+        let mut old_host = SlashingProtection::new(key);
+        old_host.check_and_record(&proposal(5, b"block a")).unwrap();
+
+        let mut new_host = SlashingProtection::new(key);
+        new_host.check_and_record(&proposal(6, b"block c")).unwrap();
+        new_host.import(&old_host.export()).unwrap();
+        assert!(new_host.check_and_record(&proposal(5, b"block b")).is_err());
+        assert!(new_host.check_and_record(&proposal(6, b"block d")).is_err());
+
+        // Histories that already conflict cannot be merged
+        let mut diverged = SlashingProtection::new(key);
+        diverged.check_and_record(&proposal(5, b"block b")).unwrap();
+        assert!(new_host.import(&diverged.export()).unwrap_err().contains("Conflicting"));
+    }
+
+    #[test]
+    fn test_pruned_slots_stay_refused() {
+        let mut protection = SlashingProtection::new(NodeIdentity::from_secret([1u8; 32]).public_key());
+        for slot in 0..=MAX_RECORDS as u64 {
+            protection.check_and_record(&proposal(slot, b"block")).unwrap();
+        }
+        assert_eq!(protection.export().signed.len(), MAX_RECORDS);
+        assert!(protection.check_and_record(&proposal(0, b"block")).unwrap_err().contains("watermark"));
+        assert!(protection.check_and_record(&proposal(1, b"block")).is_ok());
+    }
+}
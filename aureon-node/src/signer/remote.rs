@@ -0,0 +1,343 @@
+//! Remote Signer Protocol
+//!
+//! A remote signer holds the validator key on a separate host and signs over
+//! HTTP. Both ends share a secret, and every message is authenticated with
+//! HMAC-SHA256 under it:
+//!
+//! - requests are `POST /v1/public-key` or `POST /v1/sign` with a JSON body,
+//!   a `x-aureon-signer-time` header (unix milliseconds) and an
+//!   `x-aureon-signer-auth` tag over the path, time and body. The signer
+//!   refuses requests with a bad tag or a time more than
+//!   `MAX_CLOCK_SKEW_MS` off its own clock.
+//! - responses carry an `x-aureon-signer-auth` tag over the request's tag and
+//!   the response body, so the node only accepts answers from a holder of the
+//!   secret given to this very request.
+//!
+//! The node pins the public key it is given at startup and checks every
+//! returned signature against it.
+//!
+//! The protocol runs over plain HTTP, not TLS. The tags make both ends
+//! mutually authenticated and stop a network attacker from forging or
+//! altering messages, but they do not hide them: anyone on the path sees
+//! the messages being signed and the signatures. Keep the signer on a
+//! private network, or reach it through a TLS tunnel, if that matters.
+
+use super::{SignKind, SignRequest, ValidatorSigner};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DOMAIN: &[u8] = b"aureon-remote-signer/1";
+
+const TIME_HEADER: &str = "x-aureon-signer-time";
+const AUTH_HEADER: &str = "x-aureon-signer-auth";
+
+const PUBLIC_KEY_PATH: &str = "/v1/public-key";
+const SIGN_PATH: &str = "/v1/sign";
+
+/// How far a request's time may be from the signer's clock
+pub const MAX_CLOCK_SKEW_MS: u64 = 30_000;
+
+/// Shortest accepted shared secret
+const MIN_SECRET_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct WireSignRequest {
+    kind: SignKind,
+    slot: u64,
+    /// Hex-encoded message
+    message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PublicKeyResponse {
+    public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn mac(secret: &[u8], kind: &[u8]) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(DOMAIN);
+    mac.update(kind);
+    mac
+}
+
+fn request_mac(secret: &[u8], path: &str, time: u64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = mac(secret, b"\nrequest\n");
+    mac.update(path.as_bytes());
+    mac.update(format!("\n{}\n", time).as_bytes());
+    mac.update(body);
+    mac
+}
+
+fn response_mac(secret: &[u8], request_tag: &[u8], body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = mac(secret, b"\nresponse\n");
+    mac.update(request_tag);
+    mac.update(body);
+    mac
+}
+
+fn request_tag(secret: &[u8], path: &str, time: u64, body: &[u8]) -> [u8; 32] {
+    request_mac(secret, path, time, body).finalize().into_bytes().into()
+}
+
+fn response_tag(secret: &[u8], request_tag: &[u8], body: &[u8]) -> [u8; 32] {
+    response_mac(secret, request_tag, body).finalize().into_bytes().into()
+}
+
+/// Shared secret stored hex-encoded in `path`
+pub fn read_secret(path: &str) -> Result<Vec<u8>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read remote signer secret {}: {}", path, e))?;
+    let secret = hex::decode(contents.trim()).map_err(|_| format!("Remote signer secret in {} must be hex", path))?;
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(format!("Remote signer secret must be at least {} bytes", MIN_SECRET_LEN));
+    }
+    Ok(secret)
+}
+
+/// Authenticated HTTP channel to a remote signer
+struct Connection {
+    url: String,
+    secret: Vec<u8>,
+    agent: ureq::Agent,
+}
+
+impl Connection {
+    /// Send an authenticated request and check the response is authentic
+    fn call<T: for<'de> Deserialize<'de>>(&self, path: &str, body: &[u8]) -> Result<T, String> {
+        let time = now_ms();
+        let tag = request_tag(&self.secret, path, time, body);
+        let result = self
+            .agent
+            .post(&format!("{}{}", self.url, path))
+            .set("content-type", "application/json")
+            .set(TIME_HEADER, &time.to_string())
+            .set(AUTH_HEADER, &hex::encode(tag))
+            .send_bytes(body);
+        let (status, response) = match result {
+            Ok(response) => (200, response),
+            Err(ureq::Error::Status(status, response)) => (status, response),
+            Err(e) => return Err(format!("Remote signer unreachable: {}", e)),
+        };
+
+        let auth = response.header(AUTH_HEADER).and_then(|auth| hex::decode(auth).ok());
+        let body = response
+            .into_string()
+            .map_err(|e| format!("Failed to read remote signer response: {}", e))?;
+        let authentic =
+            auth.is_some_and(|auth| response_mac(&self.secret, &tag, body.as_bytes()).verify_slice(&auth).is_ok());
+        if !authentic {
+            return Err(format!("Remote signer response (status {}) is not authenticated", status));
+        }
+        if status != 200 {
+            let error = serde_json::from_str::<ErrorResponse>(&body).map(|e| e.error).unwrap_or(body);
+            return Err(format!("Remote signer refused: {}", error));
+        }
+        serde_json::from_str(&body).map_err(|e| format!("Invalid remote signer response: {}", e))
+    }
+}
+
+/// Node side of the protocol: asks a remote signer for every signature
+pub struct RemoteSigner {
+    connection: Connection,
+    public_key: VerifyingKey,
+}
+
+impl RemoteSigner {
+    /// Connect to the signer at `url` and pin the public key it reports
+    pub fn connect(url: &str, secret: &[u8], timeout_ms: u64) -> Result<Self, String> {
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(format!("Remote signer secret must be at least {} bytes", MIN_SECRET_LEN));
+        }
+        let connection = Connection {
+            url: url.trim_end_matches('/').to_string(),
+            secret: secret.to_vec(),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_millis(timeout_ms)).build(),
+        };
+        let response: PublicKeyResponse = connection.call(PUBLIC_KEY_PATH, b"{}")?;
+        let public_key = hex::decode(&response.public_key)
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|key| VerifyingKey::from_bytes(&key).ok())
+            .ok_or_else(|| "Remote signer sent an invalid public key".to_string())?;
+        Ok(RemoteSigner { connection, public_key })
+    }
+}
+
+impl ValidatorSigner for RemoteSigner {
+    fn public_key(&self) -> VerifyingKey {
+        self.public_key
+    }
+
+    fn sign(&self, request: &SignRequest) -> Result<Signature, String> {
+        let body = serde_json::to_vec(&WireSignRequest {
+            kind: request.kind,
+            slot: request.slot,
+            message: hex::encode(&request.message),
+        })
+        .map_err(|e| e.to_string())?;
+        let response: SignResponse = self.connection.call(SIGN_PATH, &body)?;
+        let signature = hex::decode(&response.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| "Remote signer sent an invalid signature".to_string())?;
+        self.public_key
+            .verify(&request.message, &signature)
+            .map_err(|_| "Remote signer signature does not match its public key".to_string())?;
+        Ok(signature)
+    }
+}
+
+/// Signer side of the protocol
+#[derive(Clone)]
+struct SignerState {
+    signer: Arc<dyn ValidatorSigner>,
+    secret: Arc<Vec<u8>>,
+}
+
+impl SignerState {
+    /// Tag of the request if it is authentic and fresh
+    fn authenticate(&self, path: &str, headers: &HeaderMap, body: &[u8]) -> Option<[u8; 32]> {
+        let time: u64 = headers.get(TIME_HEADER)?.to_str().ok()?.parse().ok()?;
+        if now_ms().abs_diff(time) > MAX_CLOCK_SKEW_MS {
+            return None;
+        }
+        let auth = hex::decode(headers.get(AUTH_HEADER)?.to_str().ok()?).ok()?;
+        request_mac(&self.secret, path, time, body).verify_slice(&auth).ok()?;
+        Some(request_tag(&self.secret, path, time, body))
+    }
+
+    fn respond<T: Serialize>(&self, request_tag: &[u8], status: StatusCode, body: &T) -> Response {
+        let body = serde_json::to_vec(body).unwrap_or_default();
+        let auth = hex::encode(response_tag(&self.secret, request_tag, &body));
+        (status, [("content-type", "application/json".to_string()), (AUTH_HEADER, auth)], body).into_response()
+    }
+}
+
+async fn public_key_handler(State(state): State<SignerState>, headers: HeaderMap, body: Bytes) -> Response {
+    let Some(tag) = state.authenticate(PUBLIC_KEY_PATH, &headers, &body) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let public_key = hex::encode(state.signer.public_key().as_bytes());
+    state.respond(&tag, StatusCode::OK, &PublicKeyResponse { public_key })
+}
+
+async fn sign_handler(State(state): State<SignerState>, headers: HeaderMap, body: Bytes) -> Response {
+    let Some(tag) = state.authenticate(SIGN_PATH, &headers, &body) else {
+        return StatusCode::UNAUTHORIZED.into_response();
+    };
+    let request = match serde_json::from_slice::<WireSignRequest>(&body)
+        .map_err(|e| e.to_string())
+        .and_then(|wire| Ok(SignRequest::new(wire.kind, wire.slot, hex::decode(&wire.message).map_err(|e| e.to_string())?)))
+    {
+        Ok(request) => request,
+        Err(error) => return state.respond(&tag, StatusCode::BAD_REQUEST, &ErrorResponse { error }),
+    };
+    match state.signer.sign(&request) {
+        Ok(signature) => state.respond(&tag, StatusCode::OK, &SignResponse { signature: hex::encode(signature.to_bytes()) }),
+        Err(error) => state.respond(&tag, StatusCode::FORBIDDEN, &ErrorResponse { error }),
+    }
+}
+
+/// Routes of a remote signer signing with `signer` for clients holding `secret`
+pub fn router(signer: Arc<dyn ValidatorSigner>, secret: &[u8]) -> Router {
+    Router::new()
+        .route(PUBLIC_KEY_PATH, post(public_key_handler))
+        .route(SIGN_PATH, post(sign_handler))
+        .with_state(SignerState { signer, secret: Arc::new(secret.to_vec()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    const SECRET: [u8; 32] = [9u8; 32];
+
+    /// Serve `signer` on a local port, returning its URL and the runtime running it
+    fn serve(signer: Arc<dyn ValidatorSigner>, secret: &[u8]) -> (String, tokio::runtime::Runtime) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime.block_on(tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let app = router(signer, secret);
+        runtime.spawn(async move { axum::serve(listener, app).await });
+        (url, runtime)
+    }
+
+    #[test]
+    fn test_remote_signing() {
+        let identity = NodeIdentity::from_secret([1u8; 32]);
+        let (url, _runtime) = serve(Arc::new(identity.clone()), &SECRET);
+
+        let signer = RemoteSigner::connect(&url, &SECRET, 2_000).unwrap();
+        assert_eq!(ValidatorSigner::public_key(&signer), identity.public_key());
+        let signature = signer.sign(&SignRequest::new(SignKind::Proposal, 4, b"block".to_vec())).unwrap();
+        assert_eq!(signature, identity.sign(b"block"));
+    }
+
+    #[test]
+    fn test_mutual_authentication() {
+        let identity = NodeIdentity::from_secret([1u8; 32]);
+        let (url, _runtime) = serve(Arc::new(identity), &SECRET);
+
+        // The signer rejects clients without the secret...
+        let error = RemoteSigner::connect(&url, &[8u8; 32], 2_000).err().unwrap();
+        assert!(error.contains("not authenticated"), "{}", error);
+
+        // ...requests for another path, and stale requests
+        let state = SignerState { signer: Arc::new(NodeIdentity::generate()), secret: Arc::new(SECRET.to_vec()) };
+        let headers = |time: u64| {
+            let mut headers = HeaderMap::new();
+            headers.insert(TIME_HEADER, time.to_string().parse().unwrap());
+            headers.insert(AUTH_HEADER, hex::encode(request_tag(&SECRET, SIGN_PATH, time, b"{}")).parse().unwrap());
+            headers
+        };
+        assert!(state.authenticate(SIGN_PATH, &headers(now_ms()), b"{}").is_some());
+        assert!(state.authenticate(PUBLIC_KEY_PATH, &headers(now_ms()), b"{}").is_none());
+        assert!(state.authenticate(SIGN_PATH, &headers(now_ms() - 2 * MAX_CLOCK_SKEW_MS), b"{}").is_none());
+
+        // Responses are bound to their request, so they cannot be swapped
+        let tag = request_tag(&SECRET, SIGN_PATH, 1, b"{}");
+        assert_ne!(response_tag(&SECRET, &tag, b"{}"), response_tag(&SECRET, &[0u8; 32], b"{}"));
+    }
+
+    #[test]
+    fn test_refused_requests_are_reported() {
+        struct Refusing;
+        impl ValidatorSigner for Refusing {
+            fn public_key(&self) -> VerifyingKey {
+                NodeIdentity::from_secret([1u8; 32]).public_key()
+            }
+            fn sign(&self, _request: &SignRequest) -> Result<Signature, String> {
+                Err("slot already signed".to_string())
+            }
+        }
+        let (url, _runtime) = serve(Arc::new(Refusing), &SECRET);
+        let signer = RemoteSigner::connect(&url, &SECRET, 2_000).unwrap();
+        let error = signer.sign(&SignRequest::new(SignKind::RandomReveal, 4, b"seed".to_vec())).unwrap_err();
+        assert_eq!(error, "Remote signer refused: slot already signed");
+    }
+}
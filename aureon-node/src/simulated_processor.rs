@@ -1,5 +1,14 @@
+use crate::bridge::BridgeLedger;
+use crate::community_governance;
 use crate::db::SnapshotDb;
 use crate::mpt::MerklePatriciaTrie;
+use crate::shielded;
+use crate::storage_rent::{self, RentState};
+use crate::multisig::{self, MultisigConfig, MultisigLedger};
+use crate::replay_protection;
+use crate::nft::NftLedger;
+use crate::timelock::{TimeLock, TimeLockLedger};
+use crate::token::{TokenLedger, TokenState};
 use crate::types::{Transaction, TransactionPayload};
 
 pub struct SimulatedProcessor<'a> {
@@ -13,17 +22,22 @@ impl<'a> SimulatedProcessor<'a> {
     }
 
     /// Apply a single transaction to state
-    pub fn apply_transaction(&mut self, tx: &Transaction) {
+    /// Reverted transactions leave state untouched and return why they reverted
+    pub fn apply_transaction(&mut self, tx: &Transaction) -> Result<(), String> {
+        replay_protection::verify_transaction(self, tx)?;
+        multisig::verify_transaction(self, tx)?;
         match &tx.payload {
             TransactionPayload::Transfer { to, amount } => {
                 let from_balance = self.get_balance(&tx.from);
-                if from_balance >= *amount {
-                    let to_balance = self.get_balance(to);
-                    self.set_balance(&tx.from, from_balance - *amount);
-                    self.set_balance(to, to_balance + *amount);
+                if from_balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", from_balance, amount));
                 }
+                let to_balance = self.get_balance(to);
+                self.set_balance(&tx.from, from_balance - *amount);
+                self.set_balance(to, to_balance + *amount);
             }
-            TransactionPayload::ContractDeploy { code: _, gas_limit: _ } => {
+            TransactionPayload::ContractDeploy { code: _, gas_limit: _ }
+            | TransactionPayload::ContractUpgrade { .. } => {
                 // Placeholder
             }
             TransactionPayload::ContractCall {
@@ -36,19 +50,86 @@ impl<'a> SimulatedProcessor<'a> {
             }
             TransactionPayload::Stake { amount } => {
                 let balance = self.get_balance(&tx.from);
-                if balance >= *amount {
-                    self.set_balance(&tx.from, balance - *amount);
+                if balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", balance, amount));
                 }
+                self.set_balance(&tx.from, balance - *amount);
             }
             TransactionPayload::Unstake { amount } => {
                 let balance = self.get_balance(&tx.from);
                 self.set_balance(&tx.from, balance + *amount);
             }
+            TransactionPayload::Delegate { amount, .. } => {
+                let balance = self.get_balance(&tx.from);
+                if balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", balance, amount));
+                }
+                self.set_balance(&tx.from, balance - *amount);
+            }
+            TransactionPayload::Undelegate { .. } => {
+                // Placeholder
+            }
+            TransactionPayload::GovernanceVote { .. } => {
+                return Err(community_governance::VOTE_TRANSACTIONS_UNSUPPORTED.to_string());
+            }
+            TransactionPayload::ShieldedTransfer(transfer) => {
+                transfer.verify()?;
+                if transfer.spends_note() {
+                    if self.snapshot.get(&shielded::commitment_key(&transfer.anchor)).is_none() {
+                        return Err("Spent note commitment does not exist".to_string());
+                    }
+                    if self.snapshot.get(&shielded::nullifier_key(&transfer.nullifier)).is_some() {
+                        return Err("Note already spent".to_string());
+                    }
+                }
+                if transfer.outputs.iter().any(|output| self.snapshot.get(&shielded::commitment_key(output)).is_some()) {
+                    return Err("Note commitment already exists".to_string());
+                }
+                let balance = self.get_balance(&tx.from);
+                if balance < transfer.deposit {
+                    return Err(format!("Insufficient balance: {} < {}", balance, transfer.deposit));
+                }
+                self.set_balance(&tx.from, balance - transfer.deposit + transfer.withdraw);
+                if transfer.spends_note() {
+                    self.trie.insert(shielded::nullifier_key(&transfer.nullifier), vec![1]);
+                }
+                for output in &transfer.outputs {
+                    self.trie.insert(shielded::commitment_key(output), vec![1]);
+                }
+            }
+            TransactionPayload::Token(op) => TokenLedger::new(self).apply(&tx.from, op)?,
+            TransactionPayload::Nft(op) => NftLedger::new(self).apply(&tx.from, op)?,
+            TransactionPayload::TimeLockedTransfer { to, amount, unlock_block } => {
+                TimeLockLedger::new(self).lock(TimeLock {
+                    id: tx.hash(),
+                    from: tx.from.clone(),
+                    to: to.clone(),
+                    amount: *amount,
+                    unlock_block: *unlock_block,
+                })?
+            }
+            TransactionPayload::RegisterMultisig { public_keys, threshold } => {
+                let config = MultisigConfig { public_keys: public_keys.clone(), threshold: *threshold };
+                MultisigLedger::new(self).register(&tx.from, config)?
+            }
+            TransactionPayload::BridgeAttest { event, relayer, signature } => {
+                BridgeLedger::new(self).attest(event, *relayer, signature)?
+            }
+            TransactionPayload::BridgeBurn { chain, asset, to, amount } => {
+                BridgeLedger::new(self).burn(&tx.hash(), &tx.from, chain, asset, to, *amount)?
+            }
+            TransactionPayload::BridgeHeader { chain, header } => BridgeLedger::new(self).relay_header(chain, header)?,
+            TransactionPayload::BridgeProve { event, height, proof } => BridgeLedger::new(self).prove(event, *height, proof)?,
+            TransactionPayload::ResurrectState(witness) => {
+                storage_rent::resurrect(self, &tx.from, witness)?;
+            }
         }
+        Ok(())
     }
 
+    /// Balance of `account`, including the block's earlier transfers and fees
     pub fn get_balance(&self, account: &str) -> u64 {
-        if let Some(bytes) = self.snapshot.get(account.as_bytes()) {
+        if let Some(bytes) = self.trie.get(account.as_bytes().to_vec()) {
             u64::from_le_bytes(bytes.try_into().unwrap_or_default())
         } else {
             0
@@ -60,4 +141,26 @@ impl<'a> SimulatedProcessor<'a> {
         let value = balance.to_le_bytes().to_vec();
         self.trie.insert(key, value);
     }
-}
\ No newline at end of file
+}
+
+/// Token state is read from the trie, which holds the block's earlier writes
+impl TokenState for SimulatedProcessor<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.trie.get(key.to_vec()).map(<[u8]>::to_vec)
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.trie.insert(key, value);
+    }
+}
+
+/// Pruned entries are deleted from the trie
+impl RentState for SimulatedProcessor<'_> {
+    fn trie(&self) -> &MerklePatriciaTrie {
+        self.trie
+    }
+
+    fn delete(&mut self, key: &[u8]) {
+        self.trie.remove(key);
+    }
+}
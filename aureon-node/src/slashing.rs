@@ -0,0 +1,678 @@
+//! Validator Slashing
+//!
+//! Detects validator misbehavior from finality votes (double-signing and
+//! downtime) and queues the evidence for inclusion in blocks. Evidence is
+//! applied when a block carrying it is committed, so every node slashes the
+//! same offences at the same height: part of the offender's stake is burned
+//! and the rest is returned to the reward pool. Double-sign evidence is only
+//! accepted with both votes signed by the offender's key. Downtime evidence
+//! is recomputed from the commit signers this node recorded for the window,
+//! and each validator is punished at most once per window.
+
+use crate::config::SlashingConfig;
+use crate::consensus::finality::{Vote, VoteType};
+use crate::incentive_programs::{RewardDistributor, StakingSystem, UnbondingDelegation};
+use crate::types::{Transaction, TransactionPayload};
+use bincode::{Decode, Encode};
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Windows of commit signers kept to check downtime evidence against;
+/// older downtime evidence can no longer be verified
+const COMMIT_HISTORY_WINDOWS: u64 = 4;
+
+/// Kind of offence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Encode, Decode)]
+#[serde(rename_all = "snake_case")]
+pub enum Misbehavior {
+    DoubleSign,
+    Downtime,
+}
+
+/// Proof of a validator offence, recorded in blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum Evidence {
+    /// Two different votes for the same height, round and phase
+    DoubleSign { first: Vote, second: Vote },
+    /// Too few precommits over the last `window` finalized heights
+    Downtime {
+        validator: String,
+        height: u64,
+        missed: u64,
+        window: u64,
+    },
+}
+
+impl Evidence {
+    /// Offending validator
+    pub fn validator(&self) -> &str {
+        match self {
+            Evidence::DoubleSign { first, .. } => &first.validator,
+            Evidence::Downtime { validator, .. } => validator,
+        }
+    }
+
+    /// Height at which the offence happened
+    pub fn height(&self) -> u64 {
+        match self {
+            Evidence::DoubleSign { first, .. } => first.height,
+            Evidence::Downtime { height, .. } => *height,
+        }
+    }
+
+    pub fn misbehavior(&self) -> Misbehavior {
+        match self {
+            Evidence::DoubleSign { .. } => Misbehavior::DoubleSign,
+            Evidence::Downtime { .. } => Misbehavior::Downtime,
+        }
+    }
+
+    /// Check that the evidence is internally consistent, and that both
+    /// double-signed votes carry the offender's signature under `keys`
+    pub fn verify(&self, keys: &HashMap<String, VerifyingKey>) -> Result<(), String> {
+        match self {
+            Evidence::DoubleSign { first, second } => {
+                if first.validator != second.validator
+                    || first.chain_id != second.chain_id
+                    || first.height != second.height
+                    || first.round != second.round
+                    || first.vote_type != second.vote_type
+                {
+                    return Err("Double-sign evidence votes are not for the same slot".to_string());
+                }
+                if first.block_hash == second.block_hash {
+                    return Err("Double-sign evidence votes are identical".to_string());
+                }
+                let key = keys
+                    .get(&first.validator)
+                    .ok_or_else(|| format!("No key known for validator {}", first.validator))?;
+                first.verify(key)?;
+                second.verify(key)
+            }
+            Evidence::Downtime { missed, window, .. } => {
+                if *window == 0 || missed > window {
+                    return Err("Invalid downtime evidence window".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Key identifying the offence, so it is only punished once
+    fn key(&self) -> String {
+        match self {
+            Evidence::DoubleSign { first, .. } => format!(
+                "double_sign:{}:{}:{}:{:?}",
+                first.validator, first.height, first.round, first.vote_type
+            ),
+            Evidence::Downtime { validator, height, .. } => format!("downtime:{}:{}", validator, height),
+        }
+    }
+}
+
+/// Result of applying evidence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlashingEvent {
+    pub validator: String,
+    pub misbehavior: Misbehavior,
+    pub height: u64,
+    pub slashed: u128,
+    pub burned: u128,
+    pub redistributed: u128,
+}
+
+/// Misbehavior detection and stake penalties
+pub struct SlashingModule {
+    config: SlashingConfig,
+    /// The node's stakes, shared with the API
+    staking: Arc<Mutex<StakingSystem>>,
+    /// Keys votes in double-sign evidence are checked against
+    keys: HashMap<String, VerifyingKey>,
+    rewards: RewardDistributor,
+    /// First vote seen per validator and voting slot
+    seen_votes: HashMap<(String, u64, u32, VoteType), Vote>,
+    /// Recent commit participation per validator (true = signed)
+    participation: HashMap<String, VecDeque<bool>>,
+    /// Signers of the recent finalized heights
+    commits: BTreeMap<u64, HashSet<String>>,
+    /// Height of the last downtime punished per validator
+    last_downtime: HashMap<String, u64>,
+    /// Evidence detected locally, waiting to be included in a block
+    pending: Vec<Evidence>,
+    processed: HashSet<String>,
+    total_burned: u128,
+}
+
+impl SlashingModule {
+    /// Create a module slashing the stakes in `staking`, watching the
+    /// commit participation of everyone bonded there
+    pub fn new(config: SlashingConfig, staking: Arc<Mutex<StakingSystem>>) -> Self {
+        let participation = staking
+            .lock()
+            .unwrap()
+            .bonded_stakes()
+            .into_keys()
+            .map(|v| (v, VecDeque::new()))
+            .collect();
+        SlashingModule {
+            config,
+            staking,
+            keys: HashMap::new(),
+            rewards: RewardDistributor::new(0),
+            seen_votes: HashMap::new(),
+            participation,
+            commits: BTreeMap::new(),
+            last_downtime: HashMap::new(),
+            pending: Vec::new(),
+            processed: HashSet::new(),
+            total_burned: 0,
+        }
+    }
+
+    /// Check votes from `validator` against `key`
+    pub fn with_validator_key(mut self, validator: &str, key: VerifyingKey) -> Self {
+        self.keys.insert(validator.to_string(), key);
+        self
+    }
+
+    /// Inspect a vote for double-signing; votes without a valid signature
+    /// prove nothing and are ignored
+    pub fn observe_vote(&mut self, vote: &Vote) -> Option<Evidence> {
+        let key = self.keys.get(&vote.validator)?;
+        vote.verify(key).ok()?;
+        let slot = (vote.validator.clone(), vote.height, vote.round, vote.vote_type);
+        match self.seen_votes.get(&slot) {
+            None => {
+                self.seen_votes.insert(slot, vote.clone());
+                None
+            }
+            Some(first) if first.block_hash != vote.block_hash => {
+                let evidence = Evidence::DoubleSign {
+                    first: first.clone(),
+                    second: vote.clone(),
+                };
+                self.queue(evidence)
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Record which validators signed the commit for a finalized height
+    pub fn record_commit(&mut self, height: u64, signers: &[String]) -> Vec<Evidence> {
+        let window = self.config.downtime_window;
+        let max_missed = window.saturating_sub(self.config.min_signed_per_window);
+        let mut offenders = Vec::new();
+
+        for (validator, history) in self.participation.iter_mut() {
+            history.push_back(signers.contains(validator));
+            while history.len() as u64 > window {
+                history.pop_front();
+            }
+            let missed = history.iter().filter(|signed| !**signed).count() as u64;
+            if history.len() as u64 == window && missed > max_missed {
+                offenders.push(Evidence::Downtime {
+                    validator: validator.clone(),
+                    height,
+                    missed,
+                    window,
+                });
+                // Start a fresh window so one outage is only punished once
+                history.clear();
+            }
+        }
+
+        self.commits.insert(height, signers.iter().cloned().collect());
+        while self.commits.len() as u64 > window * COMMIT_HISTORY_WINDOWS {
+            self.commits.pop_first();
+        }
+
+        // Drop vote slots for heights that can no longer be double-signed usefully
+        self.seen_votes.retain(|(_, h, _, _), _| *h + window > height);
+
+        offenders.into_iter().filter_map(|e| self.queue(e)).collect()
+    }
+
+    fn queue(&mut self, evidence: Evidence) -> Option<Evidence> {
+        if self.processed.contains(&evidence.key()) || self.pending.contains(&evidence) {
+            return None;
+        }
+        self.pending.push(evidence.clone());
+        Some(evidence)
+    }
+
+    /// Evidence waiting for inclusion in the next block
+    pub fn take_pending_evidence(&mut self) -> Vec<Evidence> {
+        self.drop_unverifiable();
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Number of pieces of evidence waiting for inclusion
+    pub fn pending_evidence_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The oldest `max` pieces of evidence waiting for inclusion, leaving
+    /// the rest for later blocks
+    pub fn take_evidence(&mut self, max: usize) -> Vec<Evidence> {
+        self.drop_unverifiable();
+        let count = max.min(self.pending.len());
+        self.pending.drain(..count).collect()
+    }
+
+    /// Forget pending evidence that other blocks made stale, so blocks of
+    /// ours are not rejected for carrying it
+    fn drop_unverifiable(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        self.pending = pending.into_iter().filter(|evidence| self.check_evidence(evidence).is_ok()).collect();
+    }
+
+    /// Check `evidence` before it is applied: signatures, an offence not yet
+    /// punished, and for downtime the missed commits this node recorded
+    pub fn check_evidence(&self, evidence: &Evidence) -> Result<(), String> {
+        evidence.verify(&self.keys)?;
+        let key = evidence.key();
+        if self.processed.contains(&key) {
+            return Err(format!("Evidence already processed: {}", key));
+        }
+        if let Evidence::Downtime { validator, height, missed, window } = evidence {
+            self.check_downtime(validator, *height, *missed, *window)?;
+        }
+        Ok(())
+    }
+
+    fn check_downtime(&self, validator: &str, height: u64, missed: u64, window: u64) -> Result<(), String> {
+        if window != self.config.downtime_window {
+            return Err(format!(
+                "Downtime evidence window {} does not match the configured {}",
+                window, self.config.downtime_window
+            ));
+        }
+        if missed <= window.saturating_sub(self.config.min_signed_per_window) {
+            return Err(format!("Missing {} of {} commits is not downtime", missed, window));
+        }
+        if self.last_downtime.get(validator).is_some_and(|last| height < last + window) {
+            return Err(format!("{} was already punished for downtime in this window", validator));
+        }
+        let commits: Vec<_> = self.commits.range(..=height).rev().take(window as usize).collect();
+        if commits.first().map(|(h, _)| **h) != Some(height) || (commits.len() as u64) < window {
+            return Err(format!("No commits recorded for the window ending at height {}", height));
+        }
+        let recorded = commits.iter().filter(|(_, signers)| !signers.contains(validator)).count() as u64;
+        if recorded != missed {
+            return Err(format!("{} missed {} commits up to height {}, not {}", validator, recorded, height, missed));
+        }
+        Ok(())
+    }
+
+    /// Check every piece of evidence in a block, rejecting duplicates and
+    /// downtime punished twice within one window
+    pub fn check_block_evidence(&self, evidence: &[Evidence]) -> Result<(), String> {
+        let mut keys = HashSet::new();
+        let mut downtime = HashMap::new();
+        for evidence in evidence {
+            self.check_evidence(evidence)?;
+            if !keys.insert(evidence.key()) {
+                return Err(format!("Evidence included twice: {}", evidence.key()));
+            }
+            if let Evidence::Downtime { validator, height, window, .. } = evidence
+                && let Some(other) = downtime.insert(validator, *height)
+                && other.abs_diff(*height) < *window
+            {
+                return Err(format!("{} is punished for downtime twice in one window", validator));
+            }
+        }
+        Ok(())
+    }
+
+    /// Slash the offender named by `evidence` from a committed block
+    pub fn apply_evidence(&mut self, evidence: &Evidence) -> Result<SlashingEvent, String> {
+        self.check_evidence(evidence)?;
+        Ok(self.slash(evidence))
+    }
+
+    /// Slash the offender named by evidence of a block stored before a
+    /// restart, which was checked when the block was committed
+    ///
+    /// The commits downtime evidence was checked against are not persisted.
+    pub fn apply_stored_evidence(&mut self, evidence: &Evidence) -> Result<SlashingEvent, String> {
+        let key = evidence.key();
+        if self.processed.contains(&key) {
+            return Err(format!("Evidence already processed: {}", key));
+        }
+        Ok(self.slash(evidence))
+    }
+
+    fn slash(&mut self, evidence: &Evidence) -> SlashingEvent {
+        let key = evidence.key();
+
+        let penalty_bps = match evidence.misbehavior() {
+            Misbehavior::DoubleSign => self.config.double_sign_penalty_bps,
+            Misbehavior::Downtime => self.config.downtime_penalty_bps,
+        };
+        let slashed = self.staking.lock().unwrap().slash(evidence.validator(), penalty_bps);
+        let burned = slashed * self.config.burn_bps.min(10_000) as u128 / 10_000;
+        let redistributed = slashed - burned;
+
+        self.rewards.add_to_pool(redistributed);
+        self.total_burned += burned;
+        self.processed.insert(key);
+        if let Evidence::Downtime { validator, height, .. } = evidence {
+            self.last_downtime.insert(validator.clone(), *height);
+        }
+        // Evidence someone else included no longer needs a block of ours
+        self.pending.retain(|pending| pending != evidence);
+
+        SlashingEvent {
+            validator: evidence.validator().to_string(),
+            misbehavior: evidence.misbehavior(),
+            height: evidence.height(),
+            slashed,
+            burned,
+            redistributed,
+        }
+    }
+
+    /// Current bonded stake of a validator
+    pub fn stake_of(&self, validator: &str) -> u128 {
+        self.staking().get_staked_amount(validator)
+    }
+
+    /// Bonded stake of every staker, as used for validator set rotation
+    pub fn bonded_stakes(&self) -> HashMap<String, u128> {
+        self.staking().bonded_stakes()
+    }
+
+    /// Bond and unbond stake for the staking transactions in a block
+    pub fn apply_staking_transactions(&mut self, transactions: &[Transaction], height: u64) {
+        let mut staking = self.staking.lock().unwrap();
+        for tx in transactions {
+            match &tx.payload {
+                TransactionPayload::Stake { amount } => {
+                    staking.stake(tx.from.clone(), *amount as u128, 0, height);
+                    self.participation.entry(tx.from.clone()).or_default();
+                }
+                TransactionPayload::Unstake { amount } => {
+                    staking.withdraw(&tx.from, *amount as u128);
+                }
+                TransactionPayload::Delegate { validator, amount } => {
+                    if let Err(e) = staking.delegate(&tx.from, validator, *amount as u128) {
+                        tracing::warn!(delegator = %tx.from, error = %e, "ignoring delegation");
+                    }
+                }
+                TransactionPayload::Undelegate { validator, amount } => {
+                    if let Err(e) = staking.undelegate(&tx.from, validator, *amount as u128, height) {
+                        tracing::warn!(delegator = %tx.from, error = %e, "ignoring undelegation");
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Split the rewards `validators` earned over an epoch of `blocks` blocks with their delegators
+    pub fn distribute_epoch_rewards<'a>(&mut self, validators: impl IntoIterator<Item = &'a String>, blocks: u64) {
+        self.staking.lock().unwrap().distribute_epoch_rewards(validators, blocks);
+    }
+
+    /// Undelegated stake whose unbonding period has elapsed by `height`
+    pub fn release_unbonded_delegations(&mut self, height: u64) -> Vec<UnbondingDelegation> {
+        self.staking.lock().unwrap().release_unbonded(height)
+    }
+
+    /// Stakes, delegations and pending rewards
+    pub fn staking(&self) -> MutexGuard<'_, StakingSystem> {
+        self.staking.lock().unwrap()
+    }
+
+    /// Annual staking reward rate, as set by governance
+    pub fn set_reward_rate(&mut self, annual_reward_rate: f64) {
+        self.staking.lock().unwrap().set_reward_rate(annual_reward_rate);
+    }
+
+    /// Total stake burned by slashing
+    pub fn total_burned(&self) -> u128 {
+        self.total_burned
+    }
+
+    /// Slashed stake returned to the reward pool
+    pub fn reward_pool(&self) -> u128 {
+        self.rewards.get_reward_pool()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::identity::NodeIdentity;
+
+    fn config() -> SlashingConfig {
+        SlashingConfig {
+            double_sign_penalty_bps: 500,
+            downtime_penalty_bps: 100,
+            burn_bps: 5000,
+            downtime_window: 4,
+            min_signed_per_window: 2,
+        }
+    }
+
+    fn identity(validator: &str) -> NodeIdentity {
+        NodeIdentity::from_secret([validator.len() as u8; 32])
+    }
+
+    fn signed(vote: Vote) -> Vote {
+        let validator = vote.validator.clone();
+        vote.sign(&identity(&validator)).unwrap()
+    }
+
+    fn staking() -> Arc<Mutex<StakingSystem>> {
+        let mut staking = StakingSystem::new(0.0);
+        staking.stake("alice".to_string(), 10_000, 0, 0);
+        staking.stake("bob".to_string(), 10_000, 0, 0);
+        Arc::new(Mutex::new(staking))
+    }
+
+    fn module() -> SlashingModule {
+        SlashingModule::new(config(), staking())
+            .with_validator_key("alice", identity("alice").public_key())
+            .with_validator_key("bob", identity("bob").public_key())
+    }
+
+    #[test]
+    fn test_detects_double_sign() {
+        let mut slashing = module();
+        let first = signed(Vote::prevote("alice", 3, 0, Some("a".to_string())));
+        assert!(slashing.observe_vote(&first).is_none());
+        assert!(slashing.observe_vote(&first).is_none());
+
+        // Unsigned votes are no proof of anything
+        assert!(slashing.observe_vote(&Vote::prevote("alice", 3, 0, Some("c".to_string()))).is_none());
+
+        let second = signed(Vote::prevote("alice", 3, 0, Some("b".to_string())));
+        let evidence = slashing.observe_vote(&second).unwrap();
+        assert_eq!(evidence.validator(), "alice");
+        assert_eq!(evidence.misbehavior(), Misbehavior::DoubleSign);
+        assert_eq!(slashing.take_pending_evidence(), vec![evidence]);
+
+        // A different round is a separate slot
+        assert!(slashing.observe_vote(&signed(Vote::prevote("alice", 3, 1, Some("b".to_string())))).is_none());
+    }
+
+    #[test]
+    fn test_slash_burns_and_redistributes() {
+        let mut slashing = module();
+        let evidence = Evidence::DoubleSign {
+            first: signed(Vote::precommit("alice", 3, 0, Some("a".to_string()))),
+            second: signed(Vote::precommit("alice", 3, 0, Some("b".to_string()))),
+        };
+
+        let event = slashing.apply_evidence(&evidence).unwrap();
+        assert_eq!(event.slashed, 500);
+        assert_eq!(event.burned, 250);
+        assert_eq!(event.redistributed, 250);
+        assert_eq!(slashing.stake_of("alice"), 9_500);
+        assert_eq!(slashing.total_burned(), 250);
+        assert_eq!(slashing.reward_pool(), 250);
+
+        // The same offence is only punished once
+        assert!(slashing.apply_evidence(&evidence).is_err());
+    }
+
+    #[test]
+    fn test_slashes_the_shared_staking_state() {
+        let staking = staking();
+        let mut slashing =
+            SlashingModule::new(config(), staking.clone()).with_validator_key("alice", identity("alice").public_key());
+        let first = signed(Vote::precommit("alice", 3, 0, Some("a".to_string())));
+        slashing.observe_vote(&first);
+        let evidence = slashing.observe_vote(&signed(Vote::precommit("alice", 3, 0, Some("b".to_string())))).unwrap();
+
+        // Detection alone slashes nothing until the evidence is in a block
+        assert_eq!(staking.lock().unwrap().get_staked_amount("alice"), 10_000);
+        slashing.apply_evidence(&evidence).unwrap();
+        assert_eq!(staking.lock().unwrap().get_staked_amount("alice"), 9_500);
+        assert_eq!(slashing.pending_evidence_count(), 0);
+    }
+
+    #[test]
+    fn test_staking_transactions_change_bonded_stake() {
+        let mut slashing = module();
+        let txs = vec![
+            Transaction::stake("carol".to_string(), 5_000),
+            Transaction::stake("carol".to_string(), 1_000),
+            Transaction::unstake("alice".to_string(), 4_000),
+        ];
+        slashing.apply_staking_transactions(&txs, 7);
+
+        let bonded = slashing.bonded_stakes();
+        assert_eq!(bonded["carol"], 6_000);
+        assert_eq!(bonded["alice"], 6_000);
+        assert_eq!(bonded["bob"], 10_000);
+
+        slashing.apply_staking_transactions(&[Transaction::unstake("carol".to_string(), 10_000)], 8);
+        assert!(!slashing.bonded_stakes().contains_key("carol"));
+    }
+
+    #[test]
+    fn test_undelegated_stake_released_after_unbonding_period() {
+        use crate::db::Db;
+        use crate::mpt::MerklePatriciaTrie;
+        use crate::state_processor::{StateProcessor, TxExecution};
+
+        let path = std::env::temp_dir().join(format!("aureon_undelegate_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            processor.set_balance("carol", 1_000);
+            let mut staking = StakingSystem::new(0.0).with_delegation(10, 0);
+            staking.stake("bob".to_string(), 10_000, 0, 0);
+            let mut slashing = SlashingModule::new(config(), Arc::new(Mutex::new(staking)));
+
+            let delegate = Transaction::delegate("carol".into(), "bob".into(), 400);
+            assert!(processor.apply_transaction(&delegate, TxExecution::intrinsic(&delegate)).success);
+            slashing.apply_staking_transactions(&[delegate], 1);
+            assert_eq!(slashing.staking().get_delegated_amount("bob"), 400);
+
+            let undelegate = Transaction::undelegate("carol".into(), "bob".into(), 400);
+            assert!(processor.apply_transaction(&undelegate, TxExecution::intrinsic(&undelegate)).success);
+            slashing.apply_staking_transactions(&[undelegate], 5);
+            assert_eq!(slashing.staking().get_delegated_amount("bob"), 0);
+            assert_eq!(slashing.staking().unbonding_of("carol")[0].release_height, 15);
+
+            // The stake stays locked until the unbonding period ends
+            assert!(slashing.release_unbonded_delegations(14).is_empty());
+            assert_eq!(processor.get_balance("carol"), 600);
+            let released = slashing.release_unbonded_delegations(15);
+            assert_eq!((released.len(), released[0].amount), (1, 400));
+            processor.set_balance("carol", processor.get_balance("carol") + released[0].amount as u64);
+            assert_eq!(processor.get_balance("carol"), 1_000);
+            assert!(slashing.staking().unbonding_of("carol").is_empty());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_rejects_invalid_evidence() {
+        let mut slashing = module();
+        let evidence = Evidence::DoubleSign {
+            first: signed(Vote::precommit("alice", 3, 0, Some("a".to_string()))),
+            second: signed(Vote::precommit("bob", 3, 0, Some("b".to_string()))),
+        };
+        assert!(slashing.apply_evidence(&evidence).is_err());
+
+        // Votes made up by someone else, unsigned or signed with another key
+        let forged = Evidence::DoubleSign {
+            first: Vote::precommit("alice", 3, 0, Some("a".to_string())),
+            second: Vote::precommit("alice", 3, 0, Some("b".to_string())),
+        };
+        assert!(slashing.apply_evidence(&forged).is_err());
+        let forged = Evidence::DoubleSign {
+            first: signed(Vote::precommit("alice", 3, 0, Some("a".to_string()))),
+            second: Vote::precommit("alice", 3, 0, Some("b".to_string())).sign(&identity("bob")).unwrap(),
+        };
+        assert!(slashing.apply_evidence(&forged).is_err());
+        assert_eq!(slashing.stake_of("alice"), 10_000);
+    }
+
+    #[test]
+    fn test_detects_downtime() {
+        let mut slashing = module();
+        let alice_only = vec!["alice".to_string()];
+        let both = vec!["alice".to_string(), "bob".to_string()];
+
+        assert!(slashing.record_commit(1, &both).is_empty());
+        assert!(slashing.record_commit(2, &alice_only).is_empty());
+        assert!(slashing.record_commit(3, &alice_only).is_empty());
+        let evidence = slashing.record_commit(4, &alice_only);
+
+        assert_eq!(
+            evidence,
+            vec![Evidence::Downtime {
+                validator: "bob".to_string(),
+                height: 4,
+                missed: 3,
+                window: 4,
+            }]
+        );
+        let event = slashing.apply_evidence(&evidence[0]).unwrap();
+        assert_eq!(event.slashed, 100);
+        assert_eq!(slashing.stake_of("bob"), 9_900);
+    }
+
+    #[test]
+    fn test_downtime_evidence_must_match_recorded_commits() {
+        let mut slashing = module();
+        let alice_only = vec!["alice".to_string()];
+        let both = vec!["alice".to_string(), "bob".to_string()];
+        let downtime = |validator: &str, height, missed, window| Evidence::Downtime {
+            validator: validator.to_string(),
+            height,
+            missed,
+            window,
+        };
+        for height in 1..=3 {
+            slashing.record_commit(height, &both);
+        }
+        for height in 4..=8 {
+            slashing.record_commit(height, &alice_only);
+        }
+
+        // alice signed every commit; windows that were never finalized prove nothing
+        assert!(slashing.apply_evidence(&downtime("alice", 6, 4, 4)).unwrap_err().contains("missed 0"));
+        assert!(slashing.apply_evidence(&downtime("bob", 20, 4, 4)).is_err());
+        assert!(slashing.apply_evidence(&downtime("bob", 5, 2, 4)).is_err());
+        assert!(slashing.apply_evidence(&downtime("bob", 8, 5, 5)).unwrap_err().contains("window"));
+        assert_eq!(slashing.stake_of("alice"), 10_000);
+
+        // One outage is punished once per window, whatever height is named
+        let evidence = [downtime("bob", 7, 4, 4), downtime("bob", 8, 4, 4)];
+        assert!(slashing.check_block_evidence(&evidence).unwrap_err().contains("twice"));
+        slashing.apply_evidence(&evidence[0]).unwrap();
+        assert!(slashing.apply_evidence(&evidence[1]).unwrap_err().contains("already punished"));
+        assert_eq!(slashing.stake_of("bob"), 9_900);
+    }
+}
@@ -0,0 +1,224 @@
+//! State Snapshots
+//!
+//! Serializes the state trie at checkpoint heights so nodes do not have to
+//! replay the chain from genesis. A restarting node rebuilds its state from
+//! the latest local snapshot plus the blocks after it, and an empty node can
+//! fast-sync by downloading a snapshot from a peer and continuing from there.
+//!
+//! A peer serves both the snapshot and its checkpoint block, so a snapshot
+//! that matches its own block proves nothing on its own. Fast sync only
+//! accepts the snapshot named by a trusted checkpoint: the height, block hash
+//! and state root an operator took from a source other than the peers.
+
+use crate::config::TrustedCheckpoint;
+use crate::db::Db;
+use crate::mpt::MerklePatriciaTrie;
+use crate::network::Network;
+use crate::types::Block;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SNAPSHOT_KEY: &[u8] = b"snapshot:latest";
+
+/// How often a pending fast sync asks connected peers for a snapshot
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every state entry as of the checkpoint block at `height`
+#[derive(Serialize, Deserialize, Debug, Clone, Encode, Decode)]
+pub struct StateSnapshot {
+    pub height: u64,
+    pub block: Block,
+    pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl StateSnapshot {
+    /// Snapshot `trie` as the state after `block`
+    pub fn capture(height: u64, block: &Block, trie: &MerklePatriciaTrie) -> Self {
+        StateSnapshot {
+            height,
+            block: block.clone(),
+            entries: trie.entries(),
+        }
+    }
+
+    /// Rebuild the trie and check it against the checkpoint block's state root
+    pub fn verify(&self) -> Result<MerklePatriciaTrie, String> {
+        if self.block.post_state_root.is_empty() {
+            return Err(format!("Checkpoint block {} has no state root", self.block.hash));
+        }
+        let mut trie = MerklePatriciaTrie::new();
+        for (key, value) in &self.entries {
+            trie.insert(key.clone(), value.clone());
+        }
+        if trie.root_hash() != self.block.post_state_root {
+            return Err(format!("Snapshot at height {} does not match its state root", self.height));
+        }
+        Ok(trie)
+    }
+
+    /// Check the snapshot is the one `checkpoint` names, then rebuild its trie
+    pub fn verify_against(&self, checkpoint: &TrustedCheckpoint) -> Result<MerklePatriciaTrie, String> {
+        if self.height != checkpoint.height
+            || self.block.hash != checkpoint.block_hash
+            || hex::encode(&self.block.post_state_root) != checkpoint.state_root.to_lowercase()
+        {
+            return Err(format!(
+                "Snapshot at height {} (block {}) is not the trusted checkpoint at height {}",
+                self.height, self.block.hash, checkpoint.height
+            ));
+        }
+        self.verify()
+    }
+}
+
+/// Whether a snapshot should be taken after the block at `height`
+pub fn is_checkpoint(height: u64, interval: u64) -> bool {
+    interval > 0 && height.is_multiple_of(interval)
+}
+
+/// Latest snapshot persisted in the node database
+pub struct SnapshotStore<'a> {
+    db: &'a Db,
+}
+
+impl<'a> SnapshotStore<'a> {
+    pub fn new(db: &'a Db) -> Self {
+        Self { db }
+    }
+
+    /// Persist `snapshot`, replacing the previous one
+    pub fn save(&self, snapshot: &StateSnapshot) -> Result<(), String> {
+        let bytes = bincode::encode_to_vec(snapshot, standard())
+            .map_err(|e| format!("Failed to encode snapshot: {}", e))?;
+        self.db.put(SNAPSHOT_KEY, &bytes);
+        Ok(())
+    }
+
+    pub fn latest(&self) -> Result<Option<StateSnapshot>, String> {
+        let Some(bytes) = self.db.get(SNAPSHOT_KEY) else {
+            return Ok(None);
+        };
+        let (snapshot, _) = bincode::decode_from_slice(&bytes, standard())
+            .map_err(|e| format!("Failed to decode snapshot: {}", e))?;
+        Ok(Some(snapshot))
+    }
+}
+
+/// Download and verify a snapshot from connected peers
+///
+/// Peers are asked again every second until one answers with the snapshot
+/// of `checkpoint`, or `timeout` expires.
+pub fn fast_sync(network: &Network, checkpoint: &TrustedCheckpoint, timeout: Duration) -> Result<StateSnapshot, String> {
+    let snapshots = network.subscribe_snapshots();
+    let deadline = Instant::now() + timeout;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err("Timed out waiting for a snapshot".to_string());
+        }
+        if network.peer_count() == 0 {
+            thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+
+        network.request_snapshot();
+        if let Ok(snapshot) = snapshots.recv_timeout(REQUEST_INTERVAL.min(deadline - now)) {
+            match snapshot.verify_against(checkpoint) {
+                Ok(_) => return Ok(snapshot),
+                Err(e) => eprintln!("[Snapshot] Rejected snapshot: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(trie: &MerklePatriciaTrie) -> Block {
+        Block {
+            transactions: vec![],
+            previous_hash: "GENESIS".to_string(),
+            nonce: 0,
+            hash: "h1".to_string(),
+            pre_state_root: vec![],
+            post_state_root: trie.root_hash(),
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_verifies_against_state_root() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"Alice".to_vec(), 70u64.to_le_bytes().to_vec());
+        trie.insert(b"Bob".to_vec(), 30u64.to_le_bytes().to_vec());
+
+        let mut snapshot = StateSnapshot::capture(0, &checkpoint(&trie), &trie);
+        assert_eq!(snapshot.verify().unwrap().root_hash(), trie.root_hash());
+
+        snapshot.entries[0].1 = 1_000u64.to_le_bytes().to_vec();
+        assert!(snapshot.verify().is_err());
+    }
+
+    #[test]
+    fn test_snapshot_must_match_trusted_checkpoint() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"Alice".to_vec(), 70u64.to_le_bytes().to_vec());
+        let snapshot = StateSnapshot::capture(1000, &checkpoint(&trie), &trie);
+        let trusted = TrustedCheckpoint {
+            height: 1000,
+            block_hash: "h1".to_string(),
+            state_root: hex::encode(trie.root_hash()),
+        };
+        assert!(snapshot.verify_against(&trusted).is_ok());
+
+        // A forged state with a block claiming the same hash is self-consistent
+        // but does not match the trusted state root
+        let mut forged_trie = MerklePatriciaTrie::new();
+        forged_trie.insert(b"Mallory".to_vec(), 1_000_000u64.to_le_bytes().to_vec());
+        let forged = StateSnapshot::capture(1000, &checkpoint(&forged_trie), &forged_trie);
+        assert!(forged.verify().is_ok());
+        assert!(forged.verify_against(&trusted).is_err());
+
+        let other_height = TrustedCheckpoint { height: 2000, ..trusted };
+        assert!(snapshot.verify_against(&other_height).is_err());
+    }
+
+    #[test]
+    fn test_store_keeps_latest_snapshot() {
+        let path = std::env::temp_dir().join(format!("aureon_snapshot_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let store = SnapshotStore::new(&db);
+            assert!(store.latest().unwrap().is_none());
+
+            let mut trie = MerklePatriciaTrie::new();
+            trie.insert(b"Alice".to_vec(), 70u64.to_le_bytes().to_vec());
+            store.save(&StateSnapshot::capture(0, &checkpoint(&trie), &trie)).unwrap();
+            store.save(&StateSnapshot::capture(1000, &checkpoint(&trie), &trie)).unwrap();
+
+            let latest = store.latest().unwrap().unwrap();
+            assert_eq!(latest.height, 1000);
+            assert_eq!(latest.entries, trie.entries());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+        assert!(is_checkpoint(2000, 1000));
+        assert!(!is_checkpoint(2001, 1000));
+        assert!(!is_checkpoint(2000, 0));
+    }
+}
@@ -0,0 +1,195 @@
+//! Signed SPV Checkpoints
+//!
+//! Every N blocks a full node signs the light header of the block with its
+//! validator key. A checkpoint carrying signatures from more than two thirds
+//! of the stake of a validator set the SPV client trusts lets the client
+//! start its header chain there instead of verifying every header since
+//! genesis.
+
+use crate::light_block_header::LightBlockHeader;
+use crate::network::identity::NodeIdentity;
+use crate::snapshot;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Domain separator for checkpoint signatures
+const CHECKPOINT_DOMAIN: &[u8] = b"aureon-spv-checkpoint";
+
+/// A validator's signature over a checkpoint header
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointSignature {
+    pub validator: String,
+    pub signature: Vec<u8>,
+}
+
+/// Light header of a checkpoint block with the validator signatures over it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedCheckpoint {
+    pub header: LightBlockHeader,
+    pub signatures: Vec<CheckpointSignature>,
+}
+
+impl SignedCheckpoint {
+    pub fn new(header: LightBlockHeader) -> Self {
+        SignedCheckpoint {
+            header,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Bytes covered by the validator signatures
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = CHECKPOINT_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.header.height.to_le_bytes());
+        bytes.extend_from_slice(self.header.block_hash.as_bytes());
+        bytes
+    }
+
+    /// Add `validator`'s signature made with `identity`
+    pub fn sign(&mut self, validator: &str, identity: &NodeIdentity) {
+        let signature = identity.sign(&self.signing_bytes()).to_bytes().to_vec();
+        self.signatures.retain(|existing| existing.validator != validator);
+        self.signatures.push(CheckpointSignature {
+            validator: validator.to_string(),
+            signature,
+        });
+    }
+
+    /// Check the header and that a quorum of `validators` signed it
+    #[allow(dead_code)]
+    pub fn verify(&self, validators: &CheckpointValidators) -> Result<(), String> {
+        if !self.header.verify_hash() {
+            return Err(format!("Checkpoint header at height {} has an invalid hash", self.header.height));
+        }
+        let message = self.signing_bytes();
+        let mut signers = HashSet::new();
+        let mut signed_stake = 0u64;
+        for signature in &self.signatures {
+            let Some((key, stake)) = validators.validators.get(&signature.validator) else {
+                return Err(format!("Checkpoint signed by unknown validator {}", signature.validator));
+            };
+            let bytes: [u8; 64] = signature
+                .signature
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Checkpoint signature must be 64 bytes".to_string())?;
+            key.verify(&message, &Signature::from_bytes(&bytes))
+                .map_err(|_| format!("Invalid checkpoint signature from {}", signature.validator))?;
+            if signers.insert(signature.validator.as_str()) {
+                signed_stake += stake;
+            }
+        }
+        if signed_stake < validators.quorum() {
+            return Err(format!(
+                "Checkpoint signed by {} of the {} stake needed",
+                signed_stake,
+                validators.quorum()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Keys and stake of the validators an SPV client trusts to sign checkpoints
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointValidators {
+    validators: HashMap<String, (VerifyingKey, u64)>,
+}
+
+#[allow(dead_code)]
+impl CheckpointValidators {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_validator(mut self, validator: &str, key: VerifyingKey, stake: u64) -> Self {
+        self.validators.insert(validator.to_string(), (key, stake));
+        self
+    }
+
+    /// Key of `validator`, if trusted
+    pub fn key(&self, validator: &str) -> Option<&VerifyingKey> {
+        self.validators.get(validator).map(|(key, _)| key)
+    }
+
+    /// Stake needed to accept a checkpoint (strictly more than two thirds)
+    pub fn quorum(&self) -> u64 {
+        self.validators.values().map(|(_, stake)| stake).sum::<u64>() * 2 / 3 + 1
+    }
+}
+
+/// Builds the light header chain of produced blocks and signs a checkpoint
+/// every `interval` blocks
+pub struct CheckpointSigner {
+    interval: u64,
+    validator: String,
+    identity: NodeIdentity,
+    tip: Option<LightBlockHeader>,
+    latest: Option<SignedCheckpoint>,
+}
+
+impl CheckpointSigner {
+    pub fn new(interval: u64, validator: &str, identity: NodeIdentity) -> Self {
+        CheckpointSigner {
+            interval,
+            validator: validator.to_string(),
+            identity,
+            tip: None,
+            latest: None,
+        }
+    }
+
+    /// Extend the header chain with the block at `height`, returning the
+    /// checkpoint if the block is a checkpoint block
+    pub fn on_block(&mut self, height: u64, merkle_root: String, timestamp: u64) -> Option<&SignedCheckpoint> {
+        let prev_hash = self.tip.as_ref().map_or("0".to_string(), |tip| tip.block_hash.clone());
+        let header = LightBlockHeader::new(height, prev_hash, merkle_root, timestamp, 0, 0);
+        self.tip = Some(header.clone());
+        if !snapshot::is_checkpoint(height, self.interval) {
+            return None;
+        }
+        let mut checkpoint = SignedCheckpoint::new(header);
+        checkpoint.sign(&self.validator, &self.identity);
+        self.latest = Some(checkpoint);
+        self.latest.as_ref()
+    }
+
+    /// Most recently signed checkpoint
+    pub fn latest(&self) -> Option<&SignedCheckpoint> {
+        self.latest.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_needs_validator_quorum() {
+        let alice = NodeIdentity::from_secret([1u8; 32]);
+        let bob = NodeIdentity::from_secret([2u8; 32]);
+        let validators = CheckpointValidators::new()
+            .with_validator("alice", alice.public_key(), 100)
+            .with_validator("bob", bob.public_key(), 200);
+
+        let mut signer = CheckpointSigner::new(2, "alice", alice.clone());
+        assert!(signer.on_block(1, "root1".to_string(), 1000).is_none());
+        let mut checkpoint = signer.on_block(2, "root2".to_string(), 1001).unwrap().clone();
+        assert_eq!(checkpoint.header.height, 2);
+        assert!(checkpoint.verify(&validators).unwrap_err().contains("100 of the 201"));
+
+        checkpoint.sign("bob", &bob);
+        assert!(checkpoint.verify(&validators).is_ok());
+
+        // Signatures do not carry over to a different header
+        let mut forged = checkpoint.clone();
+        forged.header = LightBlockHeader::new(2, "0".to_string(), "other".to_string(), 1001, 0, 0);
+        assert!(forged.verify(&validators).is_err());
+
+        let mut impostor = checkpoint;
+        impostor.signatures[1].validator = "alice".to_string();
+        assert!(impostor.verify(&validators).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use crate::light_block_header::LightBlockHeader;
-use crate::merkle_tree::MerkleInclusionProof;
+use crate::merkle_tree::{hash_value, MerkleInclusionProof, MerkleMultiproof};
+use crate::spv_checkpoint::{CheckpointValidators, SignedCheckpoint};
 
 /// Result of SPV verification
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,6 +40,19 @@ impl SpvClient {
         }
     }
 
+    /// Create a client whose header chain starts at a signed checkpoint
+    /// instead of genesis; headers before it are never downloaded or verified
+    pub fn from_checkpoint(
+        confirmations_required: u64,
+        checkpoint: &SignedCheckpoint,
+        validators: &CheckpointValidators,
+    ) -> Result<Self, String> {
+        checkpoint.verify(validators)?;
+        let mut client = Self::new(confirmations_required);
+        client.add_header(checkpoint.header.clone());
+        Ok(client)
+    }
+
     /// Add a block header to the chain
     /// Returns true if header was added, false if it conflicts with chain
     pub fn add_header(&mut self, header: LightBlockHeader) -> bool {
@@ -133,6 +147,42 @@ impl SpvClient {
         VerificationResult::Valid
     }
 
+    /// Verify several transactions of one block with a single multiproof;
+    /// `tx_hashes` are the transactions' hashes in index order, as the block's
+    /// merkle root commits to them
+    pub fn verify_transactions(
+        &self,
+        block_hash: &str,
+        tx_hashes: &[String],
+        proof: &MerkleMultiproof,
+    ) -> VerificationResult {
+        let block_header = match self.get_header(block_hash) {
+            Some(h) => h,
+            None => return VerificationResult::Invalid,
+        };
+
+        if proof.merkle_root != block_header.merkle_root {
+            return VerificationResult::Invalid;
+        }
+
+        // Verify the proven leaves are these transactions, in order
+        let proven = proof.leaves.iter().zip(tx_hashes).all(|((_, leaf), tx_hash)| *leaf == hash_value(tx_hash));
+        if proof.leaves.len() != tx_hashes.len() || !proven {
+            return VerificationResult::Invalid;
+        }
+
+        if !proof.verify() {
+            return VerificationResult::MalformedProof;
+        }
+
+        let confirmations = self.get_confirmations(block_hash);
+        if confirmations < self.confirmations_required {
+            return VerificationResult::InsufficientConfirmations;
+        }
+
+        VerificationResult::Valid
+    }
+
     /// Get number of confirmations for a block
     /// Confirmations = height difference between latest and block + 1
     pub fn get_confirmations(&self, block_hash: &str) -> u64 {
@@ -217,6 +267,7 @@ impl SpvClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkle_tree::IncrementalMerkleTree;
 
     fn create_test_header(height: u64, prev_hash: String) -> LightBlockHeader {
         LightBlockHeader::new(
@@ -288,6 +339,22 @@ mod tests {
         assert_eq!(client.header_count(), 3);
     }
 
+    #[test]
+    fn test_spv_start_from_checkpoint() {
+        use crate::network::identity::NodeIdentity;
+
+        let validator = NodeIdentity::from_secret([3u8; 32]);
+        let validators = CheckpointValidators::new().with_validator("alice", validator.public_key(), 100);
+        let mut checkpoint = SignedCheckpoint::new(create_test_header(1000, "0xabc".to_string()));
+        assert!(SpvClient::from_checkpoint(1, &checkpoint, &validators).is_err());
+
+        checkpoint.sign("alice", &validator);
+        let mut client = SpvClient::from_checkpoint(1, &checkpoint, &validators).unwrap();
+        assert_eq!(client.chain_height(), 1000);
+        assert!(client.add_header(create_test_header(1001, checkpoint.header.block_hash.clone())));
+        assert!(client.verify_chain());
+    }
+
     #[test]
     fn test_spv_get_header() {
         let mut client = SpvClient::new(1);
@@ -351,6 +418,37 @@ mod tests {
         assert!(client.is_transaction_safe(&hash0));
     }
 
+    #[test]
+    fn test_spv_verify_transactions_with_multiproof() {
+        let tx_hashes: Vec<String> = (0..7).map(|i| format!("tx_{:03}", i)).collect();
+        let tree: IncrementalMerkleTree = tx_hashes.iter().cloned().collect();
+        let header0 = LightBlockHeader::new(0, "0x00".to_string(), tree.root().unwrap(), 1000, 1000, 0);
+        let hash0 = header0.block_hash.clone();
+
+        let mut client = SpvClient::new(2);
+        client.add_header(header0);
+
+        let proof = tree.multiproof(&[1, 4, 5]).unwrap();
+        let proven = vec![tx_hashes[1].clone(), tx_hashes[4].clone(), tx_hashes[5].clone()];
+        assert_eq!(
+            client.verify_transactions(&hash0, &proven, &proof),
+            VerificationResult::InsufficientConfirmations
+        );
+
+        client.add_header(create_test_header(1, hash0.clone()));
+        assert_eq!(client.verify_transactions(&hash0, &proven, &proof), VerificationResult::Valid);
+
+        let swapped = vec![tx_hashes[1].clone(), tx_hashes[5].clone(), tx_hashes[4].clone()];
+        assert_eq!(client.verify_transactions(&hash0, &swapped, &proof), VerificationResult::Invalid);
+
+        let mut truncated = proof.clone();
+        truncated.hashes.pop();
+        assert_eq!(
+            client.verify_transactions(&hash0, &proven, &truncated),
+            VerificationResult::MalformedProof
+        );
+    }
+
     #[test]
     fn test_spv_verify_chain() {
         let mut client = SpvClient::new(1);
@@ -0,0 +1,146 @@
+//! Historical State Archive
+//!
+//! An archive node keeps the state root committed by every block together
+//! with a per-account history of balance changes, so state can be queried
+//! as of any archived height instead of only at the latest root. Each
+//! balance change is stored with the balance it replaced, which lets a
+//! query before an account's first change answer with its prior balance.
+
+use crate::db::Db;
+use crate::types::Block;
+use bincode::config::standard;
+
+const ROOT_PREFIX: &[u8] = b"archive:root:";
+const BALANCE_PREFIX: &[u8] = b"archive:balance:";
+
+/// A balance change: block height, balance before and balance after
+type BalanceChange = (u64, u64, u64);
+
+/// Versioned state roots and balances on top of the node database
+pub struct StateArchive<'a> {
+    db: &'a Db,
+}
+
+impl<'a> StateArchive<'a> {
+    pub fn new(db: &'a Db) -> Self {
+        Self { db }
+    }
+
+    fn root_key(height: u64) -> Vec<u8> {
+        let mut key = ROOT_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    fn balance_key(address: &str) -> Vec<u8> {
+        let mut key = BALANCE_PREFIX.to_vec();
+        key.extend_from_slice(address.as_bytes());
+        key
+    }
+
+    fn history(&self, address: &str) -> Result<Vec<BalanceChange>, String> {
+        match self.db.get(&Self::balance_key(address)) {
+            Some(bytes) => bincode::decode_from_slice(&bytes, standard())
+                .map(|(history, _)| history)
+                .map_err(|e| format!("Failed to decode balance history of {}: {}", address, e)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Record the state after the block at `height`
+    ///
+    /// `before` holds the balances of the accounts the block touched as they
+    /// were before it was applied, and `balance` reads them after. Recording
+    /// a height again replaces what was recorded for it and every later one.
+    pub fn record_block(
+        &self,
+        height: u64,
+        state_root: &[u8],
+        before: &[(String, u64)],
+        balance: impl Fn(&str) -> u64,
+    ) -> Result<(), String> {
+        for (address, previous) in before {
+            let current = balance(address);
+            let mut history = self.history(address)?;
+            history.retain(|(changed_at, _, _)| *changed_at < height);
+            if current != *previous {
+                history.push((height, *previous, current));
+            }
+            let bytes = bincode::encode_to_vec(&history, standard())
+                .map_err(|e| format!("Failed to encode balance history of {}: {}", address, e))?;
+            self.db.put(&Self::balance_key(address), &bytes);
+        }
+        self.db.put(&Self::root_key(height), state_root);
+        Ok(())
+    }
+
+    /// State root committed after the block at `height`, if it was archived
+    pub fn state_root_at(&self, height: u64) -> Option<Vec<u8>> {
+        self.db.get(&Self::root_key(height))
+    }
+
+    /// Balance of `address` after the block at `height`
+    ///
+    /// `latest` is the account's current balance, which is also its balance
+    /// at any height if it never changed while the node was archiving.
+    pub fn balance_at(&self, address: &str, height: u64, latest: u64) -> Result<u64, String> {
+        if self.state_root_at(height).is_none() {
+            return Err(format!("No archived state at height {}", height));
+        }
+        let history = self.history(address)?;
+        let balance = match history.iter().rev().find(|(changed_at, _, _)| *changed_at <= height) {
+            Some((_, _, after)) => *after,
+            None => history.first().map_or(latest, |(_, before, _)| *before),
+        };
+        Ok(balance)
+    }
+}
+
+/// Accounts whose balance `block` can change
+pub fn touched_accounts(block: &Block) -> Vec<String> {
+    let mut accounts: Vec<String> = block
+        .transactions
+        .iter()
+        .flat_map(|tx| std::iter::once(tx.from.as_str()).chain(tx.recipient()))
+        .map(str::to_string)
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balances_at_archived_heights() {
+        let path = std::env::temp_dir().join(format!("aureon_archive_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let archive = StateArchive::new(&db);
+            let balances = |alice: u64, bob: u64| move |address: &str| if address == "alice" { alice } else { bob };
+            let touched = |alice: u64, bob: u64| vec![("alice".to_string(), alice), ("bob".to_string(), bob)];
+
+            archive.record_block(0, b"root0", &[], balances(100, 0)).unwrap();
+            archive.record_block(1, b"root1", &touched(100, 0), balances(60, 40)).unwrap();
+            archive.record_block(2, b"root2", &[], balances(60, 40)).unwrap();
+            archive.record_block(3, b"root3", &touched(60, 40), balances(50, 50)).unwrap();
+
+            assert_eq!(archive.state_root_at(1).as_deref(), Some(&b"root1"[..]));
+            assert_eq!(archive.balance_at("alice", 0, 50).unwrap(), 100);
+            assert_eq!(archive.balance_at("bob", 0, 50).unwrap(), 0);
+            assert_eq!(archive.balance_at("alice", 2, 50).unwrap(), 60);
+            assert_eq!(archive.balance_at("bob", 3, 50).unwrap(), 50);
+            assert_eq!(archive.balance_at("carol", 2, 7).unwrap(), 7);
+            assert!(archive.balance_at("alice", 4, 50).is_err());
+
+            // Re-recording a height replaces the history from it onwards
+            archive.record_block(1, b"root1", &touched(100, 0), balances(70, 30)).unwrap();
+            assert_eq!(archive.balance_at("alice", 3, 70).unwrap(), 70);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
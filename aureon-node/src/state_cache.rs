@@ -0,0 +1,76 @@
+//! Account Balance Cache
+//!
+//! Balances read or written through a `StateProcessor` are kept in an LRU
+//! cache shared by every processor, so block execution does not go to
+//! RocksDB for each balance lookup. Writes update the cache right away and
+//! reach the database when the block's batch is committed. Code that
+//! rewrites balances behind the processors' back, such as rebuilding state
+//! to reorganize the chain, must invalidate the cache.
+
+use crate::metrics::Metrics;
+use crate::performance::LruCache;
+use std::sync::{Arc, Mutex};
+
+/// Shared cache of account balances
+pub struct AccountCache {
+    balances: Mutex<LruCache<String, u64>>,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl AccountCache {
+    pub fn new(capacity: usize) -> Self {
+        AccountCache {
+            balances: Mutex::new(LruCache::new(capacity.max(1))),
+            metrics: None,
+        }
+    }
+
+    /// Count cache hits and misses in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Cached balance of `account`
+    pub fn get(&self, account: &str) -> Option<u64> {
+        let balance = self.balances.lock().unwrap().get(&account.to_string());
+        if let Some(metrics) = &self.metrics {
+            let result = if balance.is_some() { "hit" } else { "miss" };
+            metrics.state_cache_lookups.with_label_values(&[result]).inc();
+        }
+        balance
+    }
+
+    pub fn insert(&self, account: &str, balance: u64) {
+        self.balances.lock().unwrap().insert(account.to_string(), balance);
+    }
+
+    /// Forget the cached balance of `account`
+    pub fn remove(&self, account: &str) {
+        self.balances.lock().unwrap().remove(&account.to_string());
+    }
+
+    /// Forget every cached balance
+    pub fn invalidate(&self) {
+        self.balances.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_counts_hits_and_misses() {
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let cache = AccountCache::new(2).with_metrics(metrics.clone());
+        assert_eq!(cache.get("alice"), None);
+        cache.insert("alice", 10);
+        assert_eq!(cache.get("alice"), Some(10));
+        cache.invalidate();
+        assert_eq!(cache.get("alice"), None);
+
+        let lookups = |result| metrics.state_cache_lookups.with_label_values(&[result]).get();
+        assert_eq!((lookups("hit"), lookups("miss")), (1, 2));
+    }
+}
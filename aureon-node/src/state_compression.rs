@@ -2,12 +2,34 @@
 //! 
 //! Provides efficient compressed state representation for light client sync
 //! without requiring full account state storage.
-
+//!
+//! Full nodes compress the account balances of every checkpoint snapshot,
+//! attach a trie proof of each balance and sign the result. Light clients
+//! fetch it over the network with `GetCompressedState` and only accept it if
+//! it is signed by a validator they trust and every balance is proven against
+//! the state root of a header they already trust.
+
+use crate::light_block_header::LightBlockHeader;
+use crate::mpt::trie::verify_proof;
+use crate::network::identity::NodeIdentity;
+use crate::network::Network;
+use crate::snapshot::StateSnapshot;
+use crate::spv_checkpoint::CheckpointValidators;
+use ed25519_dalek::{Signature, Verifier};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::thread;
+use std::time::{Duration, Instant};
 use sha2::{Sha256, Digest};
 
+/// Domain separator for compressed state signatures
+const SIGNATURE_DOMAIN: &[u8] = b"aureon-compressed-state";
+
+/// How often a pending fetch asks connected peers for a compressed state
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Compressed account state for light clients
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressedAccount {
     /// Account address
     pub address: String,
@@ -60,7 +82,7 @@ impl CompressedAccount {
 }
 
 /// Compressed state snapshot for a specific block height
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressedStateSnapshot {
     /// Block height this snapshot represents
     pub height: u64,
@@ -153,6 +175,143 @@ impl CompressedStateSnapshot {
     }
 }
 
+/// Compressed balances of a checkpoint block, proven against its state root
+/// and signed by the validator serving them
+///
+/// The proofs cover the accounts included; an account left out is not
+/// proven absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedCompressedState {
+    /// Snapshot whose `state_root` is the hex root of the state trie
+    pub snapshot: CompressedStateSnapshot,
+    /// Trie proof of each account's balance, keyed by address
+    pub proofs: HashMap<String, Vec<Vec<u8>>>,
+    pub validator: String,
+    pub signature: Vec<u8>,
+}
+
+impl SignedCompressedState {
+    /// Compress the account balances of the checkpoint `snapshot`, signed by
+    /// `validator` with `identity`
+    pub fn from_snapshot(snapshot: &StateSnapshot, validator: &str, identity: &NodeIdentity) -> Result<Self, String> {
+        let trie = snapshot.verify()?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut compressed = CompressedStateSnapshot::new(
+            snapshot.height,
+            snapshot.block.hash.clone(),
+            hex::encode(trie.root_hash()),
+            timestamp,
+        );
+        let mut proofs = HashMap::new();
+        // Balances live under plain account keys; every other state has a prefix ending in ':'
+        for (key, value) in &snapshot.entries {
+            let (Ok(address), Ok(balance)) = (std::str::from_utf8(key), <[u8; 8]>::try_from(value.as_slice())) else {
+                continue;
+            };
+            if address.contains(':') {
+                continue;
+            }
+            compressed.add_account(CompressedAccount::new(
+                address.to_string(),
+                u64::from_le_bytes(balance),
+                0,
+                String::new(),
+                String::new(),
+            ));
+            proofs.insert(address.to_string(), trie.generate_proof(key.clone()));
+        }
+        let mut signed = SignedCompressedState {
+            snapshot: compressed,
+            proofs,
+            validator: validator.to_string(),
+            signature: Vec::new(),
+        };
+        signed.signature = identity.sign(&signed.signing_bytes()).to_bytes().to_vec();
+        Ok(signed)
+    }
+
+    /// Bytes covered by the validator signature
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNATURE_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.snapshot.height.to_le_bytes());
+        bytes.extend_from_slice(self.snapshot.block_hash.as_bytes());
+        bytes.extend_from_slice(self.snapshot.state_root.as_bytes());
+        bytes.extend_from_slice(&self.snapshot.timestamp.to_le_bytes());
+        bytes.extend_from_slice(self.snapshot.compute_state_root().as_bytes());
+        bytes
+    }
+
+    /// Check the signature against `validators` and every balance against
+    /// the state root of the trusted `header`
+    #[allow(dead_code)]
+    pub fn verify(&self, header: &LightBlockHeader, validators: &CheckpointValidators) -> Result<(), String> {
+        if !header.verify_hash() {
+            return Err(format!("Trusted header at height {} has an invalid hash", header.height));
+        }
+        if header.height != self.snapshot.height || header.state_root.is_empty() || header.state_root != self.snapshot.state_root {
+            return Err(format!(
+                "Compressed state at height {} does not match the state root of header {}",
+                self.snapshot.height, header.height
+            ));
+        }
+        let key = validators
+            .key(&self.validator)
+            .ok_or_else(|| format!("Compressed state signed by unknown validator {}", self.validator))?;
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|_| "Compressed state signature must be 64 bytes".to_string())?;
+        key.verify(&self.signing_bytes(), &signature)
+            .map_err(|_| format!("Invalid compressed state signature from {}", self.validator))?;
+
+        let root = hex::decode(&self.snapshot.state_root).map_err(|e| format!("Invalid state root: {}", e))?;
+        for (address, account) in &self.snapshot.accounts {
+            let proof = self.proofs.get(address).ok_or_else(|| format!("No proof for account {}", address))?;
+            let proven = verify_proof(&root, address.as_bytes(), proof)
+                .map_err(|e| format!("Invalid proof for account {}: {}", address, e))?;
+            if address != &account.address || proven != Some(account.balance.to_le_bytes().to_vec()) {
+                return Err(format!("Balance of account {} does not match its proof", address));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fetch a compressed state from connected peers that verifies against the
+/// trusted `header`
+///
+/// Peers are asked again every second until one answers with a valid
+/// compressed state, or `timeout` expires.
+#[allow(dead_code)]
+pub fn fetch(
+    network: &Network,
+    header: &LightBlockHeader,
+    validators: &CheckpointValidators,
+    timeout: Duration,
+) -> Result<SignedCompressedState, String> {
+    let states = network.subscribe_compressed_states();
+    let deadline = Instant::now() + timeout;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err("Timed out waiting for a compressed state".to_string());
+        }
+        if network.peer_count() == 0 {
+            thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+
+        network.request_compressed_state();
+        if let Ok(state) = states.recv_timeout(REQUEST_INTERVAL.min(deadline - now)) {
+            match state.verify(header, validators) {
+                Ok(()) => return Ok(state),
+                Err(e) => eprintln!("[SPV] Rejected compressed state: {}", e),
+            }
+        }
+    }
+}
+
 /// State compression manager for managing multiple snapshots
 pub struct StateCompressionManager {
     /// Current active snapshots (keyed by height)
@@ -515,4 +674,59 @@ mod tests {
         // Should be much less than 1% since we only have one account
         assert!(ratio < 0.1);
     }
+
+    #[test]
+    fn test_light_client_fetches_compressed_state_proven_against_trusted_header() {
+        use crate::mpt::MerklePatriciaTrie;
+        use crate::types::Block;
+
+        let mut trie = MerklePatriciaTrie::new();
+        trie.insert(b"alice".to_vec(), 70u64.to_le_bytes().to_vec());
+        trie.insert(b"bob".to_vec(), 30u64.to_le_bytes().to_vec());
+        trie.insert(b"token:AUR:alice".to_vec(), 5u64.to_le_bytes().to_vec());
+        let block = Block {
+            transactions: vec![],
+            previous_hash: "GENESIS".to_string(),
+            nonce: 0,
+            hash: "h10".to_string(),
+            pre_state_root: vec![],
+            post_state_root: trie.root_hash(),
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        };
+        let alice = NodeIdentity::from_secret([1u8; 32]);
+        let validators = CheckpointValidators::new().with_validator("alice", alice.public_key(), 100);
+        let state = SignedCompressedState::from_snapshot(&StateSnapshot::capture(10, &block, &trie), "alice", &alice).unwrap();
+        assert_eq!(state.snapshot.account_count(), 2);
+        assert_eq!(state.snapshot.get_account("alice").unwrap().balance, 70);
+
+        let server = Network::new("server".to_string(), "1.0.0".to_string());
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        server.start_listener("127.0.0.1:0");
+        server.serve_compressed_state(state.clone());
+        client.add_peer(&server.local_addr().unwrap(), None);
+        let header = LightBlockHeader::new(10, "h9".to_string(), "txs".to_string(), 1000, 0, 0)
+            .with_state_root(hex::encode(trie.root_hash()));
+        let fetched = fetch(&client, &header, &validators, Duration::from_secs(5)).unwrap();
+        assert_eq!(fetched.snapshot.get_account("bob").unwrap().balance, 30);
+
+        // Inflated balances, other state roots and unknown signers are rejected
+        let mut inflated = state.clone();
+        inflated.snapshot.accounts.get_mut("bob").unwrap().balance = 3000;
+        assert!(inflated.verify(&header, &validators).is_err());
+        let other = header.clone().with_state_root("00".repeat(32));
+        assert!(state.verify(&other, &validators).unwrap_err().contains("does not match the state root"));
+        let mallory = NodeIdentity::from_secret([2u8; 32]);
+        let untrusted = CheckpointValidators::new().with_validator("alice", mallory.public_key(), 100);
+        assert!(state.verify(&header, &untrusted).unwrap_err().contains("Invalid compressed state signature"));
+    }
 }
@@ -0,0 +1,168 @@
+//! Block State Diffs
+//!
+//! While a block is applied the state processor collects its state writes
+//! in one batch. Before the batch is committed, each written key is compared
+//! with its value before the block, giving the block's state diff: account
+//! balances, the nonces of the block's senders, and every other state entry
+//! (tokens, NFTs, multisig configs, bridge state...) as a storage change.
+//! Diffs are stored bincode-encoded by block hash, so indexers can follow
+//! state through `/block/:hash/state-diff` without re-executing blocks.
+
+use crate::db::{Column, Db};
+use crate::types::Block;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use std::collections::BTreeMap;
+
+/// State changes made by one block
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct StateDiff {
+    pub block_hash: String,
+    /// Accounts whose balance or nonce changed, by address
+    pub accounts: Vec<AccountDiff>,
+    /// Other changed state entries, by key
+    pub storage: Vec<StorageDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct AccountDiff {
+    pub address: String,
+    pub previous_balance: u64,
+    pub balance: u64,
+    /// Next nonce before and after the block, if the account sent transactions
+    pub nonce: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct StorageDiff {
+    pub key: Vec<u8>,
+    /// Value before the block, `None` if the entry did not exist
+    pub previous: Option<Vec<u8>>,
+    /// Value after the block, `None` if the entry was deleted
+    pub value: Option<Vec<u8>>,
+}
+
+/// Balances are stored under the bare address; every other state key is
+/// namespaced with a `prefix:`
+pub fn balance_address(key: &[u8]) -> Option<&str> {
+    std::str::from_utf8(key).ok().filter(|key| !key.is_empty() && !key.contains(':'))
+}
+
+fn balance(value: Option<&[u8]>) -> u64 {
+    value.and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes).unwrap_or(0)
+}
+
+impl StateDiff {
+    /// Diff of `block` from its state `writes` and the value each key had
+    /// before the block, as returned by `previous`
+    pub fn new<'w>(
+        block: &Block,
+        writes: impl Iterator<Item = (&'w [u8], Option<&'w [u8]>)>,
+        previous: impl Fn(&[u8]) -> Option<Vec<u8>>,
+    ) -> Self {
+        let mut accounts: BTreeMap<String, AccountDiff> = BTreeMap::new();
+        let mut storage = Vec::new();
+        for (key, value) in writes {
+            let before = previous(key);
+            if before.as_deref() == value {
+                continue;
+            }
+            match balance_address(key) {
+                Some(address) => {
+                    accounts.insert(
+                        address.to_string(),
+                        AccountDiff {
+                            address: address.to_string(),
+                            previous_balance: balance(before.as_deref()),
+                            balance: balance(value),
+                            nonce: None,
+                        },
+                    );
+                }
+                None => storage.push(StorageDiff { key: key.to_vec(), previous: before, value: value.map(<[u8]>::to_vec) }),
+            }
+        }
+        for tx in &block.transactions {
+            let account = accounts.entry(tx.from.clone()).or_insert_with(|| {
+                let balance = balance(previous(tx.from.as_bytes()).as_deref());
+                AccountDiff { address: tx.from.clone(), previous_balance: balance, balance, nonce: None }
+            });
+            let (first, _) = account.nonce.unwrap_or((tx.nonce, tx.nonce));
+            account.nonce = Some((first.min(tx.nonce), tx.nonce + 1));
+        }
+        StateDiff { block_hash: block.hash.clone(), accounts: accounts.into_values().collect(), storage }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, standard()).expect("state diff encoding cannot fail")
+    }
+}
+
+/// State diff stored when block `block_hash` was applied
+pub fn stored(db: &Db, block_hash: &str) -> Option<StateDiff> {
+    let bytes = db.get_cf(Column::StateDiffs, block_hash.as_bytes())?;
+    bincode::decode_from_slice(&bytes, standard()).ok().map(|(diff, _)| diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Transaction;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_diff_splits_balances_nonces_and_storage() {
+        let before: HashMap<Vec<u8>, Vec<u8>> = [
+            (b"alice".to_vec(), 100u64.to_le_bytes().to_vec()),
+            (b"carol".to_vec(), 5u64.to_le_bytes().to_vec()),
+            (b"token:GOLD:alice".to_vec(), vec![1]),
+        ]
+        .into_iter()
+        .collect();
+        let block = Block {
+            transactions: vec![
+                Transaction { nonce: 3, ..Transaction::transfer("alice".to_string(), "bob".to_string(), 60) },
+                Transaction { nonce: 4, ..Transaction::transfer("alice".to_string(), "bob".to_string(), 60) },
+                Transaction::transfer("carol".to_string(), "bob".to_string(), 50),
+            ],
+            previous_hash: "genesis".to_string(),
+            nonce: 0,
+            hash: "h1".to_string(),
+            pre_state_root: vec![],
+            post_state_root: vec![],
+            evidence: vec![],
+            logs_bloom: vec![],
+            logs_root: vec![],
+            state_proof: None,
+            slot: 0,
+            proposer: String::new(),
+            proposer_signature: vec![],
+            random_reveal: vec![],
+            random_seed: vec![],
+            ommers: vec![],
+            difficulty: 0,
+        };
+        let writes: Vec<(&[u8], Option<&[u8]>)> = vec![
+            (b"alice", Some(&[40, 0, 0, 0, 0, 0, 0, 0])),
+            (b"bob", Some(&[60, 0, 0, 0, 0, 0, 0, 0])),
+            (b"token:GOLD:alice", None),
+            (b"token:GOLD:bob", Some(&[1])),
+        ];
+        let diff = StateDiff::new(&block, writes.into_iter(), |key| before.get(key).cloned());
+
+        let accounts: Vec<_> = diff
+            .accounts
+            .iter()
+            .map(|account| (account.address.as_str(), account.previous_balance, account.balance, account.nonce))
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![("alice", 100, 40, Some((3, 5))), ("bob", 0, 60, None), ("carol", 5, 5, Some((0, 1)))]
+        );
+        assert_eq!(diff.storage.len(), 2);
+        assert_eq!(diff.storage[0].previous, Some(vec![1]));
+        assert_eq!(diff.storage[0].value, None);
+        let (decoded, _): (StateDiff, _) = bincode::decode_from_slice(&diff.encode(), standard()).unwrap();
+        assert_eq!(decoded, diff);
+    }
+}
@@ -1,37 +1,331 @@
-use crate::db::{Db, SnapshotDb};
+use crate::bridge::BridgeLedger;
+use crate::community_governance;
+use crate::db::{Batch, Column, Db, SnapshotDb};
+use crate::consensus::ommers::OmmerHeader;
+use crate::emission::EmissionSchedule;
 use crate::mpt::MerklePatriciaTrie;
-use crate::types::{Block, Transaction, TransactionPayload};
+use crate::fee_market::{self, BaseFeeSchedule};
+use crate::logs::Log;
+use crate::randomness;
+use crate::shielded::{self, ShieldedTransfer};
+use crate::multisig::{self, MultisigConfig, MultisigLedger};
+use crate::replay_protection;
+use crate::nft::NftLedger;
+use crate::timelock::{TimeLock, TimeLockLedger};
+use crate::token::{StateReader, TokenLedger, TokenState};
+use crate::types::{Block, Transaction, TransactionPayload, TransactionReceipt};
 use crate::simulated_processor::SimulatedProcessor;
+use crate::state_diff::StateDiff;
+use crate::state_cache::AccountCache;
+use crate::storage_rent::{self, RentState};
+use crate::wasm::host_functions::CodeReader;
+use crate::wasm::WasmRuntime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// State root, per-transaction receipts and state diff of an applied block
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockExecution {
+    pub state_root: Vec<u8>,
+    pub receipts: Vec<TransactionReceipt>,
+    pub diff: StateDiff,
+}
+
+/// Gas used and logs emitted running a transaction's code
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxExecution {
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    /// Why the code reverted, if it did
+    pub error: Option<String>,
+}
+
+impl TxExecution {
+    /// Execution of a transaction that runs no contract code
+    pub fn intrinsic(tx: &Transaction) -> Self {
+        Self { gas_used: fee_market::intrinsic_gas(tx), ..Default::default() }
+    }
+}
+
+/// Receipt of transaction `tx_hash` stored when its block was applied
+pub fn stored_receipt(db: &Db, tx_hash: &str) -> Option<TransactionReceipt> {
+    let bytes = db.get_cf(Column::Receipts, tx_hash.as_bytes())?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Outcome of dry-running a transaction against current state
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionSimulation {
+    pub receipt: TransactionReceipt,
+    /// Balance change of each account the transaction touched
+    pub balance_changes: BTreeMap<String, i128>,
+}
+
+/// Dry-run `tx` on a copy of `trie` and `db` without committing anything
+///
+/// With fee burning enabled the transaction pays fees at the current base
+/// fee; as no proposer is known, its tip leaves the sender without being
+/// credited to anyone.
+pub fn simulate_transaction(
+    db: &Db,
+    trie: &MerklePatriciaTrie,
+    emission: Option<EmissionSchedule>,
+    tx: &Transaction,
+) -> TransactionSimulation {
+    let mut temp_trie = trie.clone();
+    let mut temp_processor = SimulatedProcessor::new(SnapshotDb::new(db.snapshot()), &mut temp_trie);
+    let mut accounts = vec![tx.from.clone()];
+    accounts.extend(tx.recipient().filter(|to| *to != tx.from).map(str::to_string));
+    let before: Vec<u64> = accounts.iter().map(|account| temp_processor.get_balance(account)).collect();
+
+    let gas_used = fee_market::gas_limit(tx);
+    let mut receipt = TransactionReceipt {
+        tx_hash: tx.hash(),
+        success: true,
+        error: None,
+        gas_used,
+        fee_burned: 0,
+        tip: 0,
+        logs: vec![],
+        post_state_root: vec![],
+    };
+    let charged = match emission.and_then(|schedule| schedule.base_fee) {
+        Some(fees) => {
+            let base_fee = fees.base_fee(&temp_processor);
+            fees.charge(&mut temp_processor, tx, gas_used, base_fee, "").map(|payment| {
+                receipt.tip = gas_used.saturating_mul(tx.gas_price - base_fee);
+                receipt.fee_burned = payment.burned - receipt.tip;
+            })
+        }
+        None => Ok(()),
+    };
+    let result = match charged {
+        Ok(()) => temp_processor.apply_transaction(tx),
+        Err(e) => {
+            receipt.gas_used = 0;
+            Err(e)
+        }
+    };
+    receipt.success = result.is_ok();
+    receipt.error = result.err();
+
+    let balance_changes = accounts
+        .iter()
+        .zip(before)
+        .map(|(account, before)| (account.clone(), temp_processor.get_balance(account) as i128 - before as i128))
+        .filter(|(_, change)| *change != 0)
+        .collect();
+    receipt.post_state_root = temp_processor.trie.root_hash();
+    TransactionSimulation { receipt, balance_changes }
+}
 
 pub struct StateProcessor<'a> {
     pub db: &'a Db,
     pub trie: &'a mut MerklePatriciaTrie,
+    /// Block rewards minted after each block's transactions
+    pub emission: Option<EmissionSchedule>,
+    /// Database writes of the block being applied
+    batch: Option<Batch>,
+    /// Balances shared with other processors
+    cache: Option<Arc<AccountCache>>,
+    /// Deployed contract code and the token state contract calls read
+    contracts: Option<(CodeReader, StateReader)>,
 }
 
 impl<'a> StateProcessor<'a> {
     pub fn new(db: &'a Db, trie: &'a mut MerklePatriciaTrie) -> Self {
-        Self { db, trie }
+        Self { db, trie, emission: None, batch: None, cache: None, contracts: None }
     }
 
-    pub fn apply_block(&mut self, block: &Block) -> Vec<u8> {
+    /// Meter contract calls against the contracts `code` returns, reading token state through `state`
+    pub fn with_contracts(mut self, code: CodeReader, state: StateReader) -> Self {
+        self.contracts = Some((code, state));
+        self
+    }
+
+    /// Read and write account balances through `cache`
+    pub fn with_cache(mut self, cache: Option<Arc<AccountCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Mint block rewards to proposers following `schedule`
+    pub fn with_emission(mut self, schedule: Option<EmissionSchedule>) -> Self {
+        self.emission = schedule;
+        self
+    }
+
+    /// Apply every transaction of `block` and mint its reward
+    ///
+    /// With fee burning enabled every transaction first pays its fees, and
+    /// the base fee of the next block follows the gas the block used. With
+    /// storage rent enabled, rent is collected at the end of each rent
+    /// period and the witnesses of accounts pruned are kept. The block's
+    /// state changes, receipts and state diff are committed to the database
+    /// in one batch once the whole block has been applied.
+    ///
+    /// Receipts record the gas each transaction's code used and the logs it
+    /// emitted. The base fee follows the gas transactions paid for, which
+    /// every node agrees on whether or not it has the contracts' code.
+    pub fn apply_block(&mut self, block: &Block) -> BlockExecution {
+        self.batch = Some(Batch::default());
+        let fees = self.emission.and_then(|schedule| schedule.base_fee);
+        let base_fee = fees.map(|fees| fees.base_fee(self));
+        let random_seed = randomness::seed_of(Some(block));
+        let mut gas_paid = 0;
+        let mut receipts = Vec::with_capacity(block.transactions.len());
         for tx in &block.transactions {
-            self.apply_transaction(tx);
+            let execution = self.run_code(tx, random_seed);
+            let receipt = match (fees, base_fee) {
+                (Some(fees), Some(base_fee)) => {
+                    let (receipt, gas) = self.apply_paid_transaction(tx, execution, &fees, base_fee, &block.proposer);
+                    gas_paid += gas;
+                    receipt
+                }
+                _ => self.apply_transaction(tx, execution),
+            };
+            receipts.push(receipt);
+        }
+        let rent = self.emission.and_then(|schedule| schedule.rent);
+        if let Some(rent) = rent {
+            for (tx, _) in block.transactions.iter().zip(&receipts).filter(|(_, receipt)| receipt.success) {
+                rent.on_transaction(self, tx);
+            }
+        }
+        if let Some(schedule) = self.emission {
+            schedule.mint_ommer_rewards(self, &block.proposer, &block.ommers);
+            schedule.mint_block_reward(self, &block.proposer);
+        }
+        TimeLockLedger::new(self).finish_block();
+        if let Some(fees) = fees {
+            fees.finish_block(self, gas_paid);
+        }
+        let pruned = rent.map(|rent| rent.finish_block(self)).unwrap_or_default();
+        let mut batch = self.batch.take().unwrap_or_default();
+        for witness in &pruned {
+            batch.put(Column::ExpiredState, witness.account.as_bytes(), &witness.encode());
+        }
+        let diff = StateDiff::new(block, batch.entries(Column::State), |key| self.db.get(key));
+        batch.put(Column::StateDiffs, block.hash.as_bytes(), &diff.encode());
+        for receipt in &receipts {
+            if let Ok(bytes) = serde_json::to_vec(receipt) {
+                batch.put(Column::Receipts, receipt.tx_hash.as_bytes(), &bytes);
+            }
+        }
+        self.db.write(batch);
+        BlockExecution {
+            state_root: self.trie.root_hash(),
+            receipts,
+            diff,
+        }
+    }
+
+    /// Apply a single transaction to state, given the `execution` of its code
+    /// Reverted transactions leave state untouched but still get a receipt
+    pub fn apply_transaction(&mut self, tx: &Transaction, execution: TxExecution) -> TransactionReceipt {
+        let result = match execution.error {
+            Some(error) => Err(error),
+            None => self.execute(tx),
+        };
+        TransactionReceipt {
+            tx_hash: tx.hash(),
+            success: result.is_ok(),
+            logs: if result.is_ok() { execution.logs } else { vec![] },
+            error: result.err(),
+            gas_used: execution.gas_used,
+            fee_burned: 0,
+            tip: 0,
+            post_state_root: self.trie.root_hash(),
+        }
+    }
+
+    /// Run the contract code `tx` calls, without committing what it writes
+    ///
+    /// Calls to contracts whose code is unknown, and other transactions,
+    /// use their intrinsic gas.
+    fn run_code(&self, tx: &Transaction, random_seed: [u8; 32]) -> TxExecution {
+        let (Some((code, state)), TransactionPayload::ContractCall { contract_address, gas_limit, .. }) =
+            (&self.contracts, &tx.payload)
+        else {
+            return TxExecution::intrinsic(tx);
+        };
+        let Some(wasm) = code(contract_address) else {
+            return TxExecution::intrinsic(tx);
+        };
+        let result = WasmRuntime::new(&wasm).and_then(|runtime| {
+            runtime
+                .with_address(contract_address)
+                .with_token_state(state.clone())
+                .with_contracts(code.clone())
+                .with_random_seed(random_seed)
+                .execute_contract_with_context(*gas_limit, Default::default())
+        });
+        match result {
+            Ok(result) => TxExecution {
+                gas_used: result.gas_used,
+                error: (!result.success).then_some(result.output),
+                logs: result.logs,
+            },
+            Err(e) => TxExecution {
+                gas_used: *gas_limit,
+                logs: vec![],
+                error: Some(format!("Execution error: {}", e)),
+            },
+        }
+    }
+
+    /// Charge the fees of `tx` at `base_fee` and apply it, returning its
+    /// receipt and the gas it paid for
+    ///
+    /// Transactions that cannot pay their fees revert without using gas;
+    /// the fees of transactions reverting afterwards are kept.
+    fn apply_paid_transaction(
+        &mut self,
+        tx: &Transaction,
+        execution: TxExecution,
+        fees: &BaseFeeSchedule,
+        base_fee: u64,
+        proposer: &str,
+    ) -> (TransactionReceipt, u64) {
+        let gas = fee_market::gas_limit(tx);
+        match fees.charge(self, tx, gas, base_fee, proposer) {
+            Ok(payment) => {
+                let receipt = TransactionReceipt {
+                    fee_burned: payment.burned,
+                    tip: payment.tip,
+                    ..self.apply_transaction(tx, execution)
+                };
+                (receipt, gas)
+            }
+            Err(e) => (TransactionReceipt {
+                tx_hash: tx.hash(),
+                success: false,
+                error: Some(e),
+                gas_used: 0,
+                fee_burned: 0,
+                tip: 0,
+                logs: vec![],
+                post_state_root: self.trie.root_hash(),
+            }, 0),
         }
-        self.trie.root_hash()
     }
 
-    /// Apply a single transaction to state
-    pub fn apply_transaction(&mut self, tx: &Transaction) {
+    fn execute(&mut self, tx: &Transaction) -> Result<(), String> {
+        replay_protection::verify_transaction(self, tx)?;
+        multisig::verify_transaction(self, tx)?;
         match &tx.payload {
             TransactionPayload::Transfer { to, amount } => {
                 let from_balance = self.get_balance(&tx.from);
-                if from_balance >= *amount {
-                    let to_balance = self.get_balance(to);
-                    self.set_balance(&tx.from, from_balance - *amount);
-                    self.set_balance(to, to_balance + *amount);
+                if from_balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", from_balance, amount));
                 }
+                let to_balance = self.get_balance(to);
+                self.set_balance(&tx.from, from_balance - *amount);
+                self.set_balance(to, to_balance + *amount);
             }
-            TransactionPayload::ContractDeploy { code: _, gas_limit: _ } => {
+            TransactionPayload::ContractDeploy { code: _, gas_limit: _ }
+            | TransactionPayload::ContractUpgrade { .. } => {
                 // Contract deployment will be handled by upper layer
                 // This is a placeholder for now
             }
@@ -46,44 +340,735 @@ impl<'a> StateProcessor<'a> {
             }
             TransactionPayload::Stake { amount } => {
                 let balance = self.get_balance(&tx.from);
-                if balance >= *amount {
-                    // In a full implementation, this would transfer to staking pool
-                    self.set_balance(&tx.from, balance - *amount);
+                if balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", balance, amount));
                 }
+                // In a full implementation, this would transfer to staking pool
+                self.set_balance(&tx.from, balance - *amount);
             }
             TransactionPayload::Unstake { amount } => {
                 // In a full implementation, this would check staked amount
                 let balance = self.get_balance(&tx.from);
                 self.set_balance(&tx.from, balance + *amount);
             }
+            TransactionPayload::Delegate { amount, .. } => {
+                let balance = self.get_balance(&tx.from);
+                if balance < *amount {
+                    return Err(format!("Insufficient balance: {} < {}", balance, amount));
+                }
+                self.set_balance(&tx.from, balance - *amount);
+            }
+            TransactionPayload::Undelegate { .. } => {
+                // The staking module returns the stake once its unbonding period ends
+            }
+            TransactionPayload::GovernanceVote { .. } => {
+                return Err(community_governance::VOTE_TRANSACTIONS_UNSUPPORTED.to_string());
+            }
+            TransactionPayload::ShieldedTransfer(transfer) => self.apply_shielded(&tx.from, transfer)?,
+            TransactionPayload::Token(op) => TokenLedger::new(self).apply(&tx.from, op)?,
+            TransactionPayload::Nft(op) => NftLedger::new(self).apply(&tx.from, op)?,
+            TransactionPayload::TimeLockedTransfer { to, amount, unlock_block } => {
+                TimeLockLedger::new(self).lock(TimeLock {
+                    id: tx.hash(),
+                    from: tx.from.clone(),
+                    to: to.clone(),
+                    amount: *amount,
+                    unlock_block: *unlock_block,
+                })?
+            }
+            TransactionPayload::RegisterMultisig { public_keys, threshold } => {
+                let config = MultisigConfig { public_keys: public_keys.clone(), threshold: *threshold };
+                MultisigLedger::new(self).register(&tx.from, config)?
+            }
+            TransactionPayload::BridgeAttest { event, relayer, signature } => {
+                BridgeLedger::new(self).attest(event, *relayer, signature)?
+            }
+            TransactionPayload::BridgeBurn { chain, asset, to, amount } => {
+                BridgeLedger::new(self).burn(&tx.hash(), &tx.from, chain, asset, to, *amount)?
+            }
+            TransactionPayload::BridgeHeader { chain, header } => BridgeLedger::new(self).relay_header(chain, header)?,
+            TransactionPayload::BridgeProve { event, height, proof } => BridgeLedger::new(self).prove(event, *height, proof)?,
+            TransactionPayload::ResurrectState(witness) => {
+                storage_rent::resurrect(self, &tx.from, witness)?;
+            }
         }
+        Ok(())
     }
 
-    pub fn simulate_block(&self, transactions: &[Transaction]) -> Vec<u8> {
+    /// Verify a shielded transfer and record its nullifier and new commitments
+    fn apply_shielded(&mut self, from: &str, transfer: &ShieldedTransfer) -> Result<(), String> {
+        transfer.verify()?;
+        if transfer.spends_note() {
+            if self.read(&shielded::commitment_key(&transfer.anchor)).is_none() {
+                return Err("Spent note commitment does not exist".to_string());
+            }
+            if self.read(&shielded::nullifier_key(&transfer.nullifier)).is_some() {
+                return Err("Note already spent".to_string());
+            }
+        }
+        if transfer.outputs.iter().any(|output| self.read(&shielded::commitment_key(output)).is_some()) {
+            return Err("Note commitment already exists".to_string());
+        }
+        let balance = self.get_balance(from);
+        if balance < transfer.deposit {
+            return Err(format!("Insufficient balance: {} < {}", balance, transfer.deposit));
+        }
+
+        self.set_balance(from, balance - transfer.deposit + transfer.withdraw);
+        if transfer.spends_note() {
+            self.mark(shielded::nullifier_key(&transfer.nullifier));
+        }
+        for output in &transfer.outputs {
+            self.mark(shielded::commitment_key(output));
+        }
+        Ok(())
+    }
+
+    /// Record a set membership (commitment or nullifier) in state
+    fn mark(&mut self, key: Vec<u8>) {
+        self.write(key, vec![1]);
+    }
+
+    /// Read state, including writes of the block being applied
+    fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.batch.as_ref().and_then(|batch| batch.get(Column::State, key)) {
+            Some(pending) => pending.map(<[u8]>::to_vec),
+            None => self.db.get(key),
+        }
+    }
+
+    /// Write state to the trie and the database, or to the block batch
+    /// while a block is being applied
+    fn write(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        if let (Some(cache), Ok(account)) = (&self.cache, std::str::from_utf8(&key)) {
+            cache.remove(account);
+        }
+        match &mut self.batch {
+            Some(batch) => batch.put(Column::State, &key, &value),
+            None => self.db.put(&key, &value),
+        }
+        self.trie.insert(key, value);
+    }
+
+    /// Post-state root of a block of `transactions` proposed by `proposer`
+    pub fn simulate_block(&self, proposer: &str, transactions: &[Transaction]) -> Vec<u8> {
+        self.simulate_block_with_ommers(proposer, transactions, &[])
+    }
+
+    /// Post-state root of a block of `transactions` proposed by `proposer`
+    /// that includes `ommers`
+    pub fn simulate_block_with_ommers(&self, proposer: &str, transactions: &[Transaction], ommers: &[OmmerHeader]) -> Vec<u8> {
         let snapshot = self.db.snapshot();
         let snapshot_db = SnapshotDb::new(snapshot);
         let mut temp_trie = self.trie.clone();
         let mut temp_processor = SimulatedProcessor::new(snapshot_db, &mut temp_trie);
 
+        let fees = self.emission.and_then(|schedule| schedule.base_fee);
+        let base_fee = fees.map(|fees| fees.base_fee(&temp_processor));
+        let mut gas_used = 0;
+        let mut applied = Vec::new();
         for tx in transactions {
-            temp_processor.apply_transaction(tx);
+            if let (Some(fees), Some(base_fee)) = (fees, base_fee) {
+                let gas = fee_market::gas_limit(tx);
+                if fees.charge(&mut temp_processor, tx, gas, base_fee, proposer).is_err() {
+                    continue;
+                }
+                gas_used += gas;
+            }
+            if temp_processor.apply_transaction(tx).is_ok() {
+                applied.push(tx);
+            }
+        }
+        let rent = self.emission.and_then(|schedule| schedule.rent);
+        if let Some(rent) = rent {
+            for tx in applied {
+                rent.on_transaction(&mut temp_processor, tx);
+            }
+        }
+        if let Some(schedule) = self.emission {
+            schedule.mint_ommer_rewards(&mut temp_processor, proposer, ommers);
+            schedule.mint_block_reward(&mut temp_processor, proposer);
+        }
+        TimeLockLedger::new(&mut temp_processor).finish_block();
+        if let Some(fees) = fees {
+            fees.finish_block(&mut temp_processor, gas_used);
+        }
+        if let Some(rent) = rent {
+            rent.finish_block(&mut temp_processor);
         }
 
         temp_processor.trie.root_hash()
     }
 
     pub fn get_balance(&self, account: &str) -> u64 {
-        if let Some(bytes) = self.db.get(account.as_bytes()) {
+        if let Some(balance) = self.cache.as_ref().and_then(|cache| cache.get(account)) {
+            return balance;
+        }
+        let balance = if let Some(bytes) = self.read(account.as_bytes()) {
             u64::from_le_bytes(bytes.try_into().unwrap_or_default())
         } else {
             0
+        };
+        if let Some(cache) = &self.cache {
+            cache.insert(account, balance);
         }
+        balance
     }
 
     pub fn set_balance(&mut self, account: &str, balance: u64) {
-        let key = account.as_bytes().to_vec();
-        let value = balance.to_le_bytes().to_vec();
-        self.db.put(&key, &value);
-        self.trie.insert(key, value);
+        self.write(account.as_bytes().to_vec(), balance.to_le_bytes().to_vec());
+        if let Some(cache) = &self.cache {
+            cache.insert(account, balance);
+        }
+    }
+}
+
+/// Token state is stored in the database and the trie like account balances
+impl TokenState for StateProcessor<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.read(key)
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.write(key, value);
+    }
+}
+
+/// Pruned entries are deleted from the database and the trie alike
+impl RentState for StateProcessor<'_> {
+    fn trie(&self) -> &MerklePatriciaTrie {
+        self.trie
+    }
+
+    fn delete(&mut self, key: &[u8]) {
+        if let (Some(cache), Ok(account)) = (&self.cache, std::str::from_utf8(key)) {
+            cache.remove(account);
+        }
+        match &mut self.batch {
+            Some(batch) => batch.delete(Column::State, key),
+            None => self.db.delete(key),
+        }
+        self.trie.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::{self, TokenOp};
+
+    #[test]
+    fn test_apply_block_reports_reverted_transactions() {
+        let path = std::env::temp_dir().join(format!("aureon_receipts_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            processor.set_balance("alice", 100);
+
+            let block = Block {
+                transactions: vec![
+                    Transaction::transfer("alice".to_string(), "bob".to_string(), 60),
+                    Transaction::transfer("alice".to_string(), "bob".to_string(), 60),
+                ],
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h1".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: String::new(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            };
+            let execution = processor.apply_block(&block);
+            let [ok, reverted] = execution.receipts.as_slice() else {
+                panic!("expected one receipt per transaction");
+            };
+            assert!(ok.success && ok.error.is_none());
+            assert!(!reverted.success);
+            assert!(reverted.error.as_ref().unwrap().contains("Insufficient balance"));
+            assert_eq!(ok.gas_used, fee_market::BASE_TX_GAS);
+            // The reverted transaction leaves state untouched
+            assert_eq!(reverted.post_state_root, ok.post_state_root);
+            assert_eq!(processor.get_balance("alice"), 40);
+            assert_eq!(db.get(b"bob"), Some(60u64.to_le_bytes().to_vec()));
+            // Votes revert rather than succeed without reaching a tally
+            let vote = Transaction::governance_vote("alice".into(), 1, community_governance::VoteChoice::Yes);
+            let receipt = processor.apply_transaction(&vote, TxExecution::intrinsic(&vote));
+            assert_eq!(receipt.error.as_deref(), Some(community_governance::VOTE_TRANSACTIONS_UNSUPPORTED));
+            assert_eq!(stored_receipt(&db, &reverted.tx_hash).as_ref(), Some(reverted));
+            let diff = crate::state_diff::stored(&db, "h1").unwrap();
+            assert_eq!((diff.accounts[1].address.as_str(), diff.accounts[1].balance), ("bob", 60));
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_receipts_record_metered_gas_and_logs() {
+        let path = std::env::temp_dir().join(format!("aureon_metered_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let wasm = wat::parse_str(
+                r#"
+                (module
+                  (import "env" "emit_log" (func $emit (param i32 i32 i32 i32) (result i32)))
+                  (memory (export "memory") 1)
+                  (data (i32.const 0) "\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07hi")
+                  (func (export "run")
+                    (drop (call $emit (i32.const 0) (i32.const 1) (i32.const 32) (i32.const 2)))))
+                "#,
+            )
+            .unwrap();
+            let code: CodeReader = Arc::new(move |address: &str| (address == "0xlogger").then(|| wasm.clone()));
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie).with_contracts(code, Arc::new(|_: &[u8]| None));
+
+            let call = |address: &str| {
+                Transaction::call_contract("alice".into(), address.into(), "run".into(), vec![], 100_000)
+            };
+            let block = Block {
+                transactions: vec![call("0xlogger"), call("0xmissing")],
+                hash: "h1".to_string(),
+                ..Default::default()
+            };
+            let execution = processor.apply_block(&block);
+            let [metered, missing] = execution.receipts.as_slice() else {
+                panic!("expected one receipt per transaction");
+            };
+            assert!(metered.success);
+            assert!(metered.gas_used > 0 && metered.gas_used < 100_000);
+            assert_eq!(metered.logs, vec![Log::new("0xlogger", &[[7; 32]], b"hi")]);
+            // Calls to unknown contracts use only their intrinsic gas
+            assert_eq!(missing.gas_used, fee_market::BASE_TX_GAS);
+            assert!(missing.logs.is_empty());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_shielded_transfers_track_commitments_and_nullifiers() {
+        let path = std::env::temp_dir().join(format!("aureon_shielded_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            processor.set_balance("alice", 100);
+
+            let note = shielded::Note::new(80);
+            let change = shielded::Note::new(0);
+            let shield = shielded::prove_transfer(None, [&note, &change], 80, 0).unwrap();
+            let tx = Transaction::shielded_transfer("alice".into(), shield);
+            let receipt = processor.apply_transaction(&tx, TxExecution::intrinsic(&tx));
+            assert!(receipt.success, "{:?}", receipt.error);
+            assert_eq!(processor.get_balance("alice"), 20);
+
+            let paid = shielded::Note::new(50);
+            let rest = shielded::Note::new(25);
+            let spend = shielded::prove_transfer(Some(&note), [&paid, &rest], 0, 5).unwrap();
+            let tx = Transaction::shielded_transfer("bob".into(), spend);
+            assert!(processor.apply_transaction(&tx, TxExecution::intrinsic(&tx)).success);
+            assert_eq!(processor.get_balance("bob"), 5);
+
+            let replayed = processor.apply_transaction(&tx, TxExecution::intrinsic(&tx));
+            assert_eq!(replayed.error.as_deref(), Some("Note already spent"));
+            assert_eq!(processor.get_balance("bob"), 5);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_token_transactions_update_trie() {
+        let path = std::env::temp_dir().join(format!("aureon_tokens_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            let create = TokenOp::Create {
+                name: "Gold".into(),
+                symbol: "GLD".into(),
+                decimals: 2,
+                initial_supply: 1_000,
+                max_supply: None,
+            };
+            let tx = Transaction::token("alice".into(), create);
+            assert!(processor.apply_transaction(&tx, TxExecution::intrinsic(&tx)).success);
+            let root = processor.trie.root_hash();
+            let gold = token::token_id("alice", "GLD");
+
+            let transfer = TokenOp::Transfer { token: gold.clone(), to: "bob".into(), amount: 300 };
+            let tx = Transaction::token("alice".into(), transfer);
+            assert!(processor.apply_transaction(&tx, TxExecution::intrinsic(&tx)).success);
+            assert_ne!(processor.trie.root_hash(), root);
+
+            let overdraft = TokenOp::Transfer { token: gold.clone(), to: "carol".into(), amount: 301 };
+            let tx = Transaction::token("bob".into(), overdraft);
+            let receipt = processor.apply_transaction(&tx, TxExecution::intrinsic(&tx));
+            assert!(!receipt.success);
+            let ledger = TokenLedger::new(&mut processor);
+            assert_eq!(ledger.balance_of(&gold, "alice"), 700);
+            assert_eq!(ledger.balance_of(&gold, "bob"), 300);
+            assert_eq!(ledger.balance_of(&gold, "carol"), 0);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_block_reward_minted_to_proposer() {
+        let path = std::env::temp_dir().join(format!("aureon_emission_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let config = crate::config::EconomyConfig {
+                initial_block_reward: 50,
+                halving_interval: 10,
+                max_supply: 1_000,
+                ..Default::default()
+            };
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor =
+                StateProcessor::new(&db, &mut trie).with_emission(Some(EmissionSchedule::new(&config, 100)));
+            processor.set_balance("alice", 100);
+
+            let transactions = vec![Transaction::transfer("alice".to_string(), "bob".to_string(), 30)];
+            let simulated = processor.simulate_block("carol", &transactions);
+            let block = Block {
+                transactions,
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h1".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: "carol".to_string(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            };
+            let execution = processor.apply_block(&block);
+            assert_eq!(execution.state_root, simulated);
+            assert_eq!(processor.get_balance("carol"), 50);
+            assert_eq!(processor.emission.unwrap().status(&processor).total_supply, 150);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_base_fee_burned_and_tip_paid_to_proposer() {
+        let path = std::env::temp_dir().join(format!("aureon_base_fee_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let config = crate::config::EconomyConfig {
+                initial_block_reward: 0,
+                fee_burning: true,
+                initial_base_fee: 2,
+                target_block_gas: 4 * fee_market::BASE_TX_GAS,
+                ..Default::default()
+            };
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor =
+                StateProcessor::new(&db, &mut trie).with_emission(Some(EmissionSchedule::new(&config, 0)));
+            processor.set_balance("alice", 1_000_000);
+
+            let mut paying = Transaction::transfer("alice".to_string(), "bob".to_string(), 30);
+            paying.gas_price = 3;
+            let mut underpriced = Transaction::transfer("alice".to_string(), "bob".to_string(), 30);
+            underpriced.nonce = 1;
+            let transactions = vec![paying, underpriced];
+            let simulated = processor.simulate_block("carol", &transactions);
+            let block = Block {
+                transactions,
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h1".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: "carol".to_string(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            };
+            let execution = processor.apply_block(&block);
+            assert_eq!(execution.state_root, simulated);
+
+            let gas = fee_market::BASE_TX_GAS;
+            let (paid, skipped) = (&execution.receipts[0], &execution.receipts[1]);
+            assert!(paid.success);
+            assert_eq!((paid.fee_burned, paid.tip), (2 * gas, gas));
+            assert!(!skipped.success && skipped.error.as_deref().unwrap().contains("below base fee"));
+            assert_eq!(skipped.gas_used, 0);
+            assert_eq!(processor.get_balance("alice"), 1_000_000 - 30 - 3 * gas);
+            assert_eq!(processor.get_balance("carol"), gas);
+
+            // Below the target the base fee falls by at most an eighth, which rounds down to nothing at 2
+            let fees = processor.emission.unwrap().base_fee.unwrap().status(&processor);
+            assert_eq!((fees.base_fee, fees.total_burned), (2, 2 * gas));
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_simulate_transaction_reports_fees_without_committing() {
+        let path = std::env::temp_dir().join(format!("aureon_simulate_tx_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let config = crate::config::EconomyConfig { fee_burning: true, initial_base_fee: 2, ..Default::default() };
+            let emission = Some(EmissionSchedule::new(&config, 0));
+            let mut trie = MerklePatriciaTrie::new();
+            StateProcessor::new(&db, &mut trie).set_balance("alice", 1_000_000);
+            let root = trie.root_hash();
+
+            let mut tx = Transaction::transfer("alice".to_string(), "bob".to_string(), 30);
+            tx.gas_price = 3;
+            let simulation = simulate_transaction(&db, &trie, emission, &tx);
+            let gas = fee_market::BASE_TX_GAS;
+            assert!(simulation.receipt.success);
+            assert_eq!((simulation.receipt.fee_burned, simulation.receipt.tip), (2 * gas, gas));
+            assert_eq!(simulation.balance_changes["alice"], -30 - 3 * gas as i128);
+            assert_eq!(simulation.balance_changes["bob"], 30);
+            assert_eq!(trie.root_hash(), root);
+
+            let overdraft = Transaction { gas_price: 3, ..Transaction::transfer("alice".to_string(), "bob".to_string(), 2_000_000) };
+            let simulation = simulate_transaction(&db, &trie, emission, &overdraft);
+            assert!(simulation.receipt.error.unwrap().contains("Insufficient balance"));
+            assert_eq!(simulation.balance_changes["alice"], -3 * gas as i128);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_time_locked_transfer_released_at_unlock_block() {
+        let path = std::env::temp_dir().join(format!("aureon_timelock_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            processor.set_balance("alice", 100);
+
+            let block = |transactions: Vec<Transaction>| Block {
+                transactions,
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: String::new(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            };
+            let lock = Transaction {
+                payload: TransactionPayload::TimeLockedTransfer { to: "bob".to_string(), amount: 40, unlock_block: 2 },
+                ..Transaction::transfer("alice".to_string(), "bob".to_string(), 0)
+            };
+            // The escrow is credited once block 1 completes, so block 2 can spend it
+            for (height, transactions) in [(0, vec![lock]), (1, vec![]), (2, vec![])] {
+                let simulated = processor.simulate_block("", &transactions);
+                let execution = processor.apply_block(&block(transactions));
+                assert!(execution.receipts.iter().all(|receipt| receipt.success));
+                assert_eq!(execution.state_root, simulated);
+                let bob = if height < 1 { 0 } else { 40 };
+                assert_eq!((processor.get_balance("alice"), processor.get_balance("bob")), (60, bob));
+            }
+            assert!(crate::timelock::account_locks(&processor, "alice").is_empty());
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_storage_rent_prunes_and_resurrects_accounts() {
+        use crate::storage_rent::AccountRent;
+
+        let path = std::env::temp_dir().join(format!("aureon_storage_rent_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let config = crate::config::EconomyConfig {
+                initial_block_reward: 0,
+                storage_rent: true,
+                rent_per_byte: 1,
+                rent_period: 2,
+                rent_grace_period: 2,
+                ..Default::default()
+            };
+            let schedule = EmissionSchedule::new(&config, 0);
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie).with_emission(Some(schedule));
+            processor.set_balance("alice", 1_000);
+            processor.set_balance("bob", 1);
+
+            let block = |transactions: Vec<Transaction>| Block {
+                transactions,
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: String::new(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            };
+            // Bob cannot pay the 22 a period, expires at 2 and is pruned at 4
+            for _ in 0..4 {
+                let simulated = processor.simulate_block("", &[]);
+                assert_eq!(processor.apply_block(&block(vec![])).state_root, simulated);
+            }
+            let rent = schedule.rent.unwrap();
+            assert!(matches!(rent.status(&processor, "bob"), AccountRent::Pruned(_)));
+            assert_eq!(processor.get_balance("alice"), 1_000 - 2 * 26);
+            assert_eq!(processor.get_balance("bob"), 0);
+
+            let witness = crate::storage_rent::stored_witness(&db, "bob").unwrap();
+            let resurrect = Transaction {
+                payload: TransactionPayload::ResurrectState(witness),
+                ..Transaction::transfer("alice".to_string(), String::new(), 0)
+            };
+            let simulated = processor.simulate_block("", std::slice::from_ref(&resurrect));
+            let execution = processor.apply_block(&block(vec![resurrect]));
+            assert!(execution.receipts[0].success, "{:?}", execution.receipts[0].error);
+            assert_eq!(execution.state_root, simulated);
+            assert_eq!((processor.get_balance("alice"), processor.get_balance("bob")), (1_000 - 2 * 26 - 44, 1));
+            assert!(matches!(rent.status(&processor, "bob"), AccountRent::Active { .. }));
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_multisig_transactions_revert_without_threshold_signatures() {
+        use crate::multisig::KeySignature;
+        use bincode::config::standard;
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let path = std::env::temp_dir().join(format!("aureon_multisig_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let mut trie = MerklePatriciaTrie::new();
+            let mut processor = StateProcessor::new(&db, &mut trie);
+            processor.set_balance("treasury", 100);
+
+            let keys: Vec<SigningKey> = (1..=3u8).map(|seed| SigningKey::from_bytes(&[seed; 32])).collect();
+            let register = Transaction {
+                payload: TransactionPayload::RegisterMultisig {
+                    public_keys: keys.iter().map(|key| key.verifying_key().to_bytes().to_vec()).collect(),
+                    threshold: 2,
+                },
+                ..Transaction::transfer("treasury".to_string(), String::new(), 0)
+            };
+            let transfer = Transaction::transfer("treasury".to_string(), "bob".to_string(), 30);
+            let sign = |key_index: usize| KeySignature {
+                key_index: key_index as u32,
+                signature: keys[key_index].sign(&transfer.signing_bytes()).to_bytes().to_vec(),
+            };
+            let encode = |signatures: Vec<KeySignature>| bincode::encode_to_vec(signatures, standard()).unwrap();
+            let under_signed = Transaction { signature: encode(vec![sign(1)]), ..transfer.clone() };
+            let signed = Transaction { signature: encode(vec![sign(0), sign(2)]), ..transfer.clone() };
+
+            let transactions = vec![register, under_signed, signed];
+            let simulated = processor.simulate_block("", &transactions);
+            let execution = processor.apply_block(&Block {
+                transactions,
+                previous_hash: "genesis".to_string(),
+                nonce: 0,
+                hash: "h".to_string(),
+                pre_state_root: vec![],
+                post_state_root: vec![],
+                evidence: vec![],
+                logs_bloom: vec![],
+                logs_root: vec![],
+                state_proof: None,
+                slot: 0,
+                proposer: String::new(),
+                proposer_signature: vec![],
+                random_reveal: vec![],
+                random_seed: vec![],
+                ommers: vec![],
+                difficulty: 0,
+            });
+            let outcomes: Vec<bool> = execution.receipts.iter().map(|receipt| receipt.success).collect();
+            assert_eq!(outcomes, vec![true, false, true]);
+            assert!(execution.receipts[1].error.as_ref().unwrap().contains("needs 2 signatures, got 1"));
+            assert_eq!(execution.state_root, simulated);
+            assert_eq!((processor.get_balance("treasury"), processor.get_balance("bob")), (70, 30));
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn test_balances_read_through_cache() {
+        let path = std::env::temp_dir().join(format!("aureon_state_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Db::open(path.to_str().unwrap());
+        {
+            let cache = Arc::new(AccountCache::new(16));
+            let mut trie = MerklePatriciaTrie::new();
+            db.put(b"alice", &100u64.to_le_bytes());
+            let mut processor = StateProcessor::new(&db, &mut trie).with_cache(Some(cache.clone()));
+            assert_eq!(processor.get_balance("alice"), 100);
+            assert_eq!(cache.get("alice"), Some(100));
+
+            let tx = Transaction::transfer("alice".to_string(), "bob".to_string(), 30);
+            processor.apply_transaction(&tx, TxExecution::intrinsic(&tx));
+            assert_eq!((cache.get("alice"), cache.get("bob")), (Some(70), Some(30)));
+            // Writes bypassing the processor are not seen until the cache is invalidated
+            db.put(b"alice", &5u64.to_le_bytes());
+            assert_eq!(processor.get_balance("alice"), 70);
+            cache.invalidate();
+            assert_eq!(processor.get_balance("alice"), 5);
+        }
+        drop(db);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
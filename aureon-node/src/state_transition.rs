@@ -0,0 +1,377 @@
+//! State Transition Proofs
+//!
+//! A block producer can attach a Groth16 proof that applying the block's
+//! transfers to the balances of the accounts they touch yields the balances
+//! recorded alongside the proof. A validator that holds the pre-state reads
+//! those accounts' balances, checks the proof against them and writes the
+//! proven balances into a copy of its trie: the resulting root must match
+//! the block's post_state_root, and no transaction is executed. The circuit
+//! commits to the transactions with the zk module's MiMC hash rather than
+//! the trie's Keccak root, which would be far too costly to prove.
+//!
+//! Blocks of up to `MAX_TRANSFERS` plain transfers between up to
+//! `MAX_ACCOUNTS` accounts can be proven; anything else is validated by
+//! re-execution.
+
+use crate::multisig;
+use crate::replay_protection;
+use crate::state_processor::StateProcessor;
+use crate::timelock::TimeLockLedger;
+use crate::types::{Block, Transaction, TransactionPayload};
+use crate::zk::{alloc_u64, mimc_hash, mimc_hash_var, verify_groth16};
+use ark_bls12_381::{Bls12_381, Fr as F};
+use ark_ff::{Field, Zero};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Most transfers a provable block can hold
+pub const MAX_TRANSFERS: usize = 4;
+/// Most distinct accounts a provable block can touch
+pub const MAX_ACCOUNTS: usize = 8;
+
+const SETUP_SEED: u64 = 0x0053_5441_5445;
+
+/// A transfer between account slots: (from, to, amount)
+type SlotTransfer = (usize, usize, u64);
+
+/// Proof that a block's transfers produce `post_balances`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TransitionProof {
+    /// Balances of the touched accounts after the block, in order of first appearance
+    pub post_balances: Vec<u64>,
+    /// Whether each transfer was applied (false when it reverted)
+    pub applied: Vec<bool>,
+    pub proof: Vec<u8>,
+}
+
+impl TransitionProof {
+    /// Check the proof for `transactions` applied to the current `balance`
+    /// of each touched account, returning the balances to write
+    pub fn verify(&self, transactions: &[Transaction], balance: impl Fn(&str) -> u64) -> Result<Vec<(String, u64)>, String> {
+        let (accounts, transfers) = provable_transfers(transactions)?;
+        if self.post_balances.len() != accounts.len() || self.applied.len() != transfers.len() {
+            return Err("State transition proof does not match the block's transactions".to_string());
+        }
+        let pre: Vec<u64> = accounts.iter().map(|account| balance(account)).collect();
+        let inputs = public_inputs(&pre, &self.post_balances, &self.applied, &transfers);
+        let proof = Proof::<Bls12_381>::deserialize_compressed(self.proof.as_slice())
+            .map_err(|e| format!("Invalid state transition proof encoding: {}", e))?;
+        match verify_groth16(&parameters().1, &inputs, &proof) {
+            Ok(true) => {}
+            Ok(false) => return Err("Invalid state transition proof".to_string()),
+            Err(e) => return Err(format!("Failed to verify state transition proof: {}", e)),
+        }
+
+        // Only accounts of applied transfers are written, as execution would
+        let mut written = vec![false; accounts.len()];
+        for ((from, to, _), applied) in transfers.iter().zip(&self.applied) {
+            if *applied {
+                written[*from] = true;
+                written[*to] = true;
+            }
+        }
+        Ok(accounts
+            .into_iter()
+            .zip(self.post_balances.iter().copied())
+            .zip(written)
+            .filter_map(|(entry, written)| written.then_some(entry))
+            .collect())
+    }
+}
+
+/// Prove the transition of a block made of `transactions` on top of the
+/// current `balance` of each account
+pub fn prove_transition(transactions: &[Transaction], balance: impl Fn(&str) -> u64) -> Result<TransitionProof, String> {
+    let (accounts, transfers) = provable_transfers(transactions)?;
+    let pre: Vec<u64> = accounts.iter().map(|account| balance(account)).collect();
+    let (post, steps) = replay(padded(&pre), &padded_transfers(&transfers))
+        .ok_or_else(|| "Block transfers overflow a balance".to_string())?;
+
+    let circuit = TransitionCircuit {
+        pre: Some(padded(&pre)),
+        post: Some(post),
+        steps: Some(steps.clone()),
+    };
+    let proof = Groth16::<Bls12_381>::prove(&parameters().0, circuit, &mut rand::thread_rng())
+        .map_err(|e| format!("Failed to prove state transition: {}", e))?;
+    let mut proof_bytes = Vec::new();
+    proof
+        .serialize_compressed(&mut proof_bytes)
+        .map_err(|e| format!("Failed to encode state transition proof: {}", e))?;
+
+    Ok(TransitionProof {
+        post_balances: post[..accounts.len()].to_vec(),
+        applied: steps[..transfers.len()].iter().map(|step| step.applied).collect(),
+        proof: proof_bytes,
+    })
+}
+
+/// Post-state root of `block` from its transition proof, without executing it
+pub fn proven_state_root(block: &Block, state: &StateProcessor) -> Result<Vec<u8>, String> {
+    let proof = block
+        .state_proof
+        .as_ref()
+        .ok_or_else(|| "Block carries no state transition proof".to_string())?;
+    // The proof only covers balances; fees and transactions the processor
+    // would revert for missing multisig signatures or another chain's id
+    // cannot be proven
+    if state.emission.is_some_and(|schedule| schedule.base_fee.is_some()) {
+        return Err("Blocks paying base fees cannot be proven".to_string());
+    }
+    if state.emission.is_some_and(|schedule| schedule.rent.is_some()) {
+        return Err("Blocks collecting storage rent cannot be proven".to_string());
+    }
+    for tx in &block.transactions {
+        replay_protection::verify_transaction(&*state.trie, tx)?;
+        multisig::verify_transaction(&*state.trie, tx)?;
+    }
+    let balances = proof.verify(&block.transactions, |account| state.get_balance(account))?;
+    let mut trie = state.trie.clone();
+    for (account, balance) in balances {
+        trie.insert(account.into_bytes(), balance.to_le_bytes().to_vec());
+    }
+    if let Some(schedule) = state.emission {
+        schedule.mint_ommer_rewards(&mut trie, &block.proposer, &block.ommers);
+        schedule.mint_block_reward(&mut trie, &block.proposer);
+    }
+    TimeLockLedger::new(&mut trie).finish_block();
+    Ok(trie.root_hash())
+}
+
+/// The touched accounts in order of first appearance and the transfers
+/// between their slots, if the block is provable
+fn provable_transfers(transactions: &[Transaction]) -> Result<(Vec<String>, Vec<SlotTransfer>), String> {
+    if transactions.len() > MAX_TRANSFERS {
+        return Err(format!("Only blocks of up to {} transfers can be proven", MAX_TRANSFERS));
+    }
+    let mut accounts: Vec<String> = Vec::new();
+    let mut slot = |account: &str| match accounts.iter().position(|known| known == account) {
+        Some(index) => index,
+        None => {
+            accounts.push(account.to_string());
+            accounts.len() - 1
+        }
+    };
+    let mut transfers = Vec::new();
+    for tx in transactions {
+        let TransactionPayload::Transfer { to, amount } = &tx.payload else {
+            return Err("Only plain transfers can be proven".to_string());
+        };
+        if *to == tx.from {
+            return Err("Self-transfers cannot be proven".to_string());
+        }
+        transfers.push((slot(&tx.from), slot(to), *amount));
+    }
+    if accounts.len() > MAX_ACCOUNTS {
+        return Err(format!("Only blocks touching up to {} accounts can be proven", MAX_ACCOUNTS));
+    }
+    Ok((accounts, transfers))
+}
+
+fn padded(balances: &[u64]) -> [u64; MAX_ACCOUNTS] {
+    let mut slots = [0; MAX_ACCOUNTS];
+    slots[..balances.len()].copy_from_slice(balances);
+    slots
+}
+
+/// Transfers padded with no-op transfers to `MAX_TRANSFERS`
+fn padded_transfers(transfers: &[SlotTransfer]) -> Vec<SlotTransfer> {
+    let mut padded = transfers.to_vec();
+    padded.resize(MAX_TRANSFERS, (0, 0, 0));
+    padded
+}
+
+/// One transfer as the circuit sees it
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    transfer: SlotTransfer,
+    /// Sender's balance before the transfer
+    from_balance: u64,
+    applied: bool,
+}
+
+/// Apply `transfers` as execution would, skipping those the sender cannot
+/// cover; None if a balance overflows
+fn replay(mut balances: [u64; MAX_ACCOUNTS], transfers: &[SlotTransfer]) -> Option<([u64; MAX_ACCOUNTS], Vec<Step>)> {
+    let mut steps = Vec::new();
+    for &(from, to, amount) in transfers {
+        let from_balance = balances[from];
+        let applied = from_balance >= amount;
+        if applied {
+            balances[from] -= amount;
+            balances[to] = balances[to].checked_add(amount)?;
+        }
+        steps.push(Step {
+            transfer: (from, to, amount),
+            from_balance,
+            applied,
+        });
+    }
+    Some((balances, steps))
+}
+
+/// A transfer packed into one field element for the transaction commitment
+fn pack_transfer(from: F, to: F, amount: F) -> F {
+    from * F::from(2u64).pow([72]) + to * F::from(2u64).pow([64]) + amount
+}
+
+/// MiMC chain over the padded transfers
+fn transfers_commitment(transfers: &[SlotTransfer]) -> F {
+    padded_transfers(transfers).iter().fold(F::zero(), |acc, &(from, to, amount)| {
+        mimc_hash(pack_transfer(F::from(from as u64), F::from(to as u64), F::from(amount)), acc)
+    })
+}
+
+/// Public inputs in circuit order: pre balances, post balances, applied flags, transfer commitment
+fn public_inputs(pre: &[u64], post: &[u64], applied: &[bool], transfers: &[SlotTransfer]) -> Vec<F> {
+    let mut flags = applied.to_vec();
+    // Padding transfers move nothing and always apply
+    flags.resize(MAX_TRANSFERS, true);
+    padded(pre)
+        .iter()
+        .chain(padded(post).iter())
+        .map(|&balance| F::from(balance))
+        .chain(flags.into_iter().map(F::from))
+        .chain(std::iter::once(transfers_commitment(transfers)))
+        .collect()
+}
+
+/// Proving and verifying keys of the transition circuit
+fn parameters() -> &'static (ProvingKey<Bls12_381>, VerifyingKey<Bls12_381>) {
+    static PARAMETERS: OnceLock<(ProvingKey<Bls12_381>, VerifyingKey<Bls12_381>)> = OnceLock::new();
+    PARAMETERS.get_or_init(|| {
+        let circuit = TransitionCircuit {
+            pre: None,
+            post: None,
+            steps: None,
+        };
+        let mut rng = StdRng::seed_from_u64(SETUP_SEED);
+        Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).expect("transition circuit setup cannot fail")
+    })
+}
+
+/// Sequential application of `MAX_TRANSFERS` transfers to `MAX_ACCOUNTS`
+/// balance slots; `None` fields are unknown during setup
+struct TransitionCircuit {
+    pre: Option<[u64; MAX_ACCOUNTS]>,
+    post: Option<[u64; MAX_ACCOUNTS]>,
+    steps: Option<Vec<Step>>,
+}
+
+impl ConstraintSynthesizer<F> for TransitionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let zero = FpVar::zero();
+        let step = |index: usize| self.steps.as_ref().map(|steps| steps[index]);
+
+        // Public inputs
+        let mut balances = (0..MAX_ACCOUNTS)
+            .map(|slot| FpVar::new_input(cs.clone(), || self.pre.map(|pre| F::from(pre[slot])).ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let post = (0..MAX_ACCOUNTS)
+            .map(|slot| FpVar::new_input(cs.clone(), || self.post.map(|post| F::from(post[slot])).ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let applied = (0..MAX_TRANSFERS)
+            .map(|index| Boolean::new_input(cs.clone(), || step(index).map(|step| step.applied).ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitment = FpVar::new_input(cs.clone(), || {
+            let transfers = self.steps.as_ref().ok_or_else(missing)?.iter().map(|step| step.transfer);
+            Ok(transfers_commitment(&transfers.collect::<Vec<_>>()))
+        })?;
+
+        let mut running_commitment = zero.clone();
+        for (index, applied) in applied.iter().enumerate() {
+            let step = step(index);
+            let one_hot = |slot_of: fn(&Step) -> usize| -> Result<Vec<Boolean<F>>, SynthesisError> {
+                let selectors = (0..MAX_ACCOUNTS)
+                    .map(|slot| Boolean::new_witness(cs.clone(), || step.map(|step| slot_of(&step) == slot).ok_or_else(missing)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let count = selectors.iter().fold(zero.clone(), |sum, bit| sum + FpVar::from(bit.clone()));
+                count.enforce_equal(&FpVar::one())?;
+                Ok(selectors)
+            };
+            let from = one_hot(|step| step.transfer.0)?;
+            let to = one_hot(|step| step.transfer.1)?;
+            let amount = alloc_u64(cs.clone(), step.map(|step| step.transfer.2))?;
+
+            // The transfer applies exactly when the sender's balance covers it
+            let mut from_balance = zero.clone();
+            for (selector, balance) in from.iter().zip(&balances) {
+                from_balance += selector.select(balance, &zero)?;
+            }
+            let margin = alloc_u64(
+                cs.clone(),
+                step.map(|step| match step.applied {
+                    true => step.from_balance - step.transfer.2,
+                    false => step.transfer.2 - step.from_balance - 1,
+                }),
+            )?;
+            let expected = applied.select(&(&from_balance - &amount), &(&amount - &from_balance - FpVar::one()))?;
+            margin.enforce_equal(&expected)?;
+
+            let moved = applied.select(&amount, &zero)?;
+            for ((balance, debit), credit) in balances.iter_mut().zip(&from).zip(&to) {
+                *balance = &*balance - debit.select(&moved, &zero)? + credit.select(&moved, &zero)?;
+            }
+
+            let slot_index = |selectors: &[Boolean<F>]| {
+                selectors.iter().enumerate().fold(zero.clone(), |sum, (slot, bit)| {
+                    sum + FpVar::from(bit.clone()) * F::from(slot as u64)
+                })
+            };
+            let packed = slot_index(&from) * F::from(2u64).pow([72]) + slot_index(&to) * F::from(2u64).pow([64]) + &amount;
+            running_commitment = mimc_hash_var(&packed, &running_commitment)?;
+        }
+
+        for (balance, expected) in balances.iter().zip(&post) {
+            balance.enforce_equal(expected)?;
+        }
+        running_commitment.enforce_equal(&commitment)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_transition_proof_replaces_execution() {
+        let balances = HashMap::from([("alice", 100u64), ("bob", 5)]);
+        let balance = |account: &str| balances.get(account).copied().unwrap_or(0);
+        let transactions = vec![
+            Transaction::transfer("alice".into(), "bob".into(), 60),
+            Transaction::transfer("alice".into(), "carol".into(), 60),
+            Transaction::transfer("bob".into(), "carol".into(), 65),
+        ];
+
+        let proof = prove_transition(&transactions, balance).unwrap();
+        assert_eq!(proof.applied, vec![true, false, true]);
+        let written = proof.verify(&transactions, balance).unwrap();
+        assert_eq!(
+            written,
+            vec![("alice".to_string(), 40), ("bob".to_string(), 0), ("carol".to_string(), 65)]
+        );
+
+        // The proof is bound to the pre-state, the claimed result and the transactions
+        let richer = |account: &str| balance(account) + 1;
+        assert!(proof.verify(&transactions, richer).is_err());
+        let mut inflated = proof.clone();
+        inflated.post_balances[2] = 70;
+        assert!(inflated.verify(&transactions, balance).is_err());
+        let mut reordered = transactions.clone();
+        reordered.swap(0, 1);
+        assert!(proof.verify(&reordered, balance).is_err());
+
+        let stake = vec![Transaction::stake("alice".into(), 10)];
+        assert!(prove_transition(&stake, balance).is_err());
+    }
+}
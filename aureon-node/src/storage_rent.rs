@@ -0,0 +1,397 @@
+//! Storage Rent
+//!
+//! With storage rent enabled, accounts pay for the state they occupy:
+//! `rent_per_byte` per block for every byte of their balance entry and, for
+//! contracts, of their code. Every `rent_period` blocks the rent for the
+//! whole period is taken from each account's balance and burned. An account
+//! that cannot pay expires, owing the rent as arrears, and recovers by
+//! paying them at a later collection. An account still expired after the
+//! grace period is pruned: its entries are deleted from the state trie,
+//! leaving a tombstone with the state root they were last part of.
+//!
+//! The node pruning an account keeps the trie proofs of its entries as a
+//! resurrection witness. A `ResurrectState` transaction carrying the witness
+//! restores the entries once the proofs check out against the tombstone;
+//! its sender pays the arrears plus the rent for the time the state was
+//! pruned, so letting state lapse never saves rent. Rent state is kept in
+//! the state trie under `rent:` keys.
+
+use crate::config::EconomyConfig;
+use crate::contract_registry::contract_address;
+use crate::db::{Column, Db};
+use crate::emission;
+use crate::mpt::trie::verify_proof;
+use crate::mpt::MerklePatriciaTrie;
+use crate::state_diff::balance_address;
+use crate::token::TokenState;
+use crate::types::{Transaction, TransactionPayload};
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Prefix of every key holding rent state
+pub const STATE_PREFIX: &str = "rent:";
+const COLLECTED_KEY: &[u8] = b"rent:collected";
+const CODE_PREFIX: &str = "rent:code:";
+const EXPIRED_PREFIX: &str = "rent:expired:";
+const TOMBSTONE_PREFIX: &str = "rent:tombstone:";
+
+/// Bytes a balance entry occupies besides the address it is stored under
+const BALANCE_BYTES: u64 = 8;
+
+fn code_key(account: &str) -> Vec<u8> {
+    format!("{}{}", CODE_PREFIX, account).into_bytes()
+}
+
+fn expired_key(account: &str) -> Vec<u8> {
+    format!("{}{}", EXPIRED_PREFIX, account).into_bytes()
+}
+
+fn tombstone_key(account: &str) -> Vec<u8> {
+    format!("{}{}", TOMBSTONE_PREFIX, account).into_bytes()
+}
+
+fn decode_u64(bytes: &[u8]) -> u64 {
+    bytes.try_into().map(u64::from_le_bytes).unwrap_or(0)
+}
+
+fn read_u64<S: TokenState + ?Sized>(state: &S, key: &[u8]) -> u64 {
+    state.get(key).map_or(0, |bytes| decode_u64(&bytes))
+}
+
+fn read<S: TokenState + ?Sized, T: Decode<()>>(state: &S, key: &[u8]) -> Option<T> {
+    let bytes = state.get(key)?;
+    bincode::decode_from_slice(&bytes, standard()).ok().map(|(value, _)| value)
+}
+
+fn encode<T: Encode>(value: &T) -> Vec<u8> {
+    bincode::encode_to_vec(value, standard()).expect("Rent state encodes")
+}
+
+/// Rent owed by an account that could not pay it
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct Expiry {
+    /// Height of the first collection the account could not pay at
+    pub since: u64,
+    pub arrears: u64,
+}
+
+/// What is left in state of a pruned account
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct Tombstone {
+    pub pruned_at: u64,
+    /// State root right before the account's entries were deleted
+    pub state_root: Vec<u8>,
+    /// Rent owed when the account was pruned
+    pub arrears: u64,
+    /// Rent per block the pruned entries paid
+    pub rent_per_block: u64,
+}
+
+/// Pruned entries of an account with their proofs against the state root
+/// of its tombstone
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct ExpiredState {
+    pub account: String,
+    pub balance: u64,
+    /// Bytes of contract code the account paid rent for, 0 for plain accounts
+    pub code_size: u64,
+    /// Trie proof of the balance entry, or of its absence
+    pub balance_proof: Vec<Vec<u8>>,
+    /// Trie proof of the code size entry, or of its absence
+    pub code_proof: Vec<Vec<u8>>,
+}
+
+impl ExpiredState {
+    pub fn encode(&self) -> Vec<u8> {
+        encode(self)
+    }
+}
+
+/// Resurrection witness kept when `account` was pruned
+pub fn stored_witness(db: &Db, account: &str) -> Option<ExpiredState> {
+    let bytes = db.get_cf(Column::ExpiredState, account.as_bytes())?;
+    bincode::decode_from_slice(&bytes, standard()).ok().map(|(witness, _)| witness)
+}
+
+/// State the rent collector works on: besides reads and writes it deletes
+/// pruned entries, and reads the trie to find accounts and prove entries
+pub trait RentState: TokenState {
+    fn trie(&self) -> &MerklePatriciaTrie;
+    fn delete(&mut self, key: &[u8]);
+}
+
+impl RentState for MerklePatriciaTrie {
+    fn trie(&self) -> &MerklePatriciaTrie {
+        self
+    }
+
+    fn delete(&mut self, key: &[u8]) {
+        self.remove(key);
+    }
+}
+
+/// Rent status of an account
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AccountRent {
+    Active { rent_per_block: u64 },
+    Expired(Expiry),
+    Pruned(Tombstone),
+}
+
+/// Storage rent rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RentSchedule {
+    pub rent_per_byte: u64,
+    /// Blocks between collections
+    pub period: u64,
+    /// Blocks an account stays expired before it is pruned
+    pub grace_period: u64,
+}
+
+impl RentSchedule {
+    /// Rent rules of `config`, if storage rent is enabled
+    pub fn new(config: &EconomyConfig) -> Option<Self> {
+        config.storage_rent.then_some(RentSchedule {
+            rent_per_byte: config.rent_per_byte,
+            period: config.rent_period.max(1),
+            grace_period: config.rent_grace_period,
+        })
+    }
+
+    /// Rent per block of `account` holding `code_size` bytes of contract code
+    pub fn rent_per_block(&self, account: &str, code_size: u64) -> u64 {
+        (account.len() as u64 + BALANCE_BYTES + code_size).saturating_mul(self.rent_per_byte)
+    }
+
+    /// Rent status of `account` in `state`
+    pub fn status<S: TokenState + ?Sized>(&self, state: &S, account: &str) -> AccountRent {
+        if let Some(tombstone) = read(state, &tombstone_key(account)) {
+            return AccountRent::Pruned(tombstone);
+        }
+        match read(state, &expired_key(account)) {
+            Some(expiry) => AccountRent::Expired(expiry),
+            None => AccountRent::Active { rent_per_block: self.rent_per_block(account, read_u64(state, &code_key(account))) },
+        }
+    }
+
+    /// Total rent collected so far
+    pub fn collected<S: TokenState + ?Sized>(&self, state: &S) -> u64 {
+        read_u64(state, COLLECTED_KEY)
+    }
+
+    /// Track the code size of the contract a successful `tx` deploys or upgrades
+    pub fn on_transaction<S: TokenState + ?Sized>(&self, state: &mut S, tx: &Transaction) {
+        let (address, code) = match &tx.payload {
+            TransactionPayload::ContractDeploy { code, .. } => (contract_address(&tx.from, tx.nonce), code),
+            TransactionPayload::ContractUpgrade { contract_address, code, .. } => (contract_address.clone(), code),
+            _ => return,
+        };
+        state.put(code_key(&address), (code.len() as u64).to_le_bytes().to_vec());
+    }
+
+    /// Collect rent if the block just applied ends a rent period, returning
+    /// the resurrection witnesses of the accounts pruned
+    pub fn finish_block<S: RentState + ?Sized>(&self, state: &mut S) -> Vec<ExpiredState> {
+        let height = emission::height(state);
+        if height == 0 || !height.is_multiple_of(self.period) {
+            return Vec::new();
+        }
+
+        // Accounts by address, with the size of their code
+        let mut accounts: BTreeMap<String, u64> = BTreeMap::new();
+        for (key, value) in state.trie().iter() {
+            if let Some(address) = balance_address(&key) {
+                accounts.entry(address.to_string()).or_insert(0);
+            } else if let Some(address) = key.strip_prefix(CODE_PREFIX.as_bytes()) {
+                accounts.insert(String::from_utf8_lossy(address).into_owned(), decode_u64(value));
+            }
+        }
+
+        let mut collected = 0u64;
+        let mut pruned = Vec::new();
+        for (account, code_size) in accounts {
+            let expiry: Option<Expiry> = read(state, &expired_key(&account));
+            let due = self.rent_per_block(&account, code_size).saturating_mul(self.period);
+            let owed = due.saturating_add(expiry.map_or(0, |expiry| expiry.arrears));
+            if owed == 0 {
+                continue;
+            }
+            let balance = read_u64(state, account.as_bytes());
+            if balance >= owed {
+                state.put(account.as_bytes().to_vec(), (balance - owed).to_le_bytes().to_vec());
+                collected = collected.saturating_add(owed);
+                if expiry.is_some() {
+                    state.delete(&expired_key(&account));
+                }
+                continue;
+            }
+            match expiry {
+                Some(expiry) if height.saturating_sub(expiry.since) >= self.grace_period => {
+                    pruned.push(self.prune(state, &account, code_size, owed, height));
+                }
+                _ => {
+                    let since = expiry.map_or(height, |expiry| expiry.since);
+                    state.put(expired_key(&account), encode(&Expiry { since, arrears: owed }));
+                }
+            }
+        }
+        if collected > 0 {
+            let total = read_u64(state, COLLECTED_KEY).saturating_add(collected);
+            state.put(COLLECTED_KEY.to_vec(), total.to_le_bytes().to_vec());
+        }
+        pruned
+    }
+
+    /// Delete the entries of `account`, leaving a tombstone, and return
+    /// their proofs against the state root from before
+    fn prune<S: RentState + ?Sized>(
+        &self,
+        state: &mut S,
+        account: &str,
+        code_size: u64,
+        arrears: u64,
+        height: u64,
+    ) -> ExpiredState {
+        let trie = state.trie();
+        let witness = ExpiredState {
+            account: account.to_string(),
+            balance: read_u64(state, account.as_bytes()),
+            code_size,
+            balance_proof: trie.generate_proof(account.as_bytes().to_vec()),
+            code_proof: trie.generate_proof(code_key(account)),
+        };
+        let tombstone = Tombstone {
+            pruned_at: height,
+            state_root: trie.root_hash(),
+            arrears,
+            rent_per_block: self.rent_per_block(account, code_size),
+        };
+        state.delete(account.as_bytes());
+        state.delete(&code_key(account));
+        state.delete(&expired_key(account));
+        state.put(tombstone_key(account), encode(&tombstone));
+        witness
+    }
+}
+
+/// Restore the pruned state `witness` proves, charging `payer` the arrears
+/// and the rent for the blocks the state was pruned; returns the rent charged
+///
+/// Applies whether or not rent is collected any more, so state pruned
+/// before rent was turned off can still be restored.
+pub fn resurrect<S: RentState + ?Sized>(state: &mut S, payer: &str, witness: &ExpiredState) -> Result<u64, String> {
+    let account = witness.account.as_str();
+    let tombstone: Tombstone =
+        read(state, &tombstone_key(account)).ok_or_else(|| format!("No pruned state for {}", account))?;
+    let proven = |key: &[u8], proof: &[Vec<u8>]| {
+        verify_proof(&tombstone.state_root, key, proof).map(|value| value.map_or(0, |bytes| decode_u64(&bytes)))
+    };
+    if proven(account.as_bytes(), &witness.balance_proof)? != witness.balance
+        || proven(&code_key(account), &witness.code_proof)? != witness.code_size
+    {
+        return Err(format!("Proof does not match the pruned state of {}", account));
+    }
+
+    let pruned_for = emission::height(state).saturating_sub(tombstone.pruned_at);
+    let charge = tombstone.arrears.saturating_add(tombstone.rent_per_block.saturating_mul(pruned_for));
+    let payer_balance = read_u64(state, payer.as_bytes());
+    if payer_balance < charge {
+        return Err(format!("Insufficient balance: {} < {}", payer_balance, charge));
+    }
+    state.put(payer.as_bytes().to_vec(), (payer_balance - charge).to_le_bytes().to_vec());
+    let balance = read_u64(state, account.as_bytes());
+    state.put(account.as_bytes().to_vec(), (balance + witness.balance).to_le_bytes().to_vec());
+    if witness.code_size > 0 {
+        state.put(code_key(account), witness.code_size.to_le_bytes().to_vec());
+    }
+    state.delete(&tombstone_key(account));
+    let total = read_u64(state, COLLECTED_KEY).saturating_add(charge);
+    state.put(COLLECTED_KEY.to_vec(), total.to_le_bytes().to_vec());
+    Ok(charge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEDULE: RentSchedule = RentSchedule { rent_per_byte: 1, period: 10, grace_period: 20 };
+
+    /// Advance the emission height to `height` and run the collector
+    fn finish(trie: &mut MerklePatriciaTrie, height: u64) -> Vec<ExpiredState> {
+        trie.put(b"economy:height".to_vec(), height.to_le_bytes().to_vec());
+        SCHEDULE.finish_block(trie)
+    }
+
+    fn balance(trie: &MerklePatriciaTrie, account: &str) -> u64 {
+        read_u64(trie, account.as_bytes())
+    }
+
+    #[test]
+    fn test_rent_is_collected_every_period() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.put(b"alice".to_vec(), 1_000u64.to_le_bytes().to_vec());
+        let deploy = Transaction {
+            payload: TransactionPayload::ContractDeploy { code: vec![0; 100], gas_limit: 0 },
+            ..Transaction::transfer("alice".to_string(), String::new(), 0)
+        };
+        SCHEDULE.on_transaction(&mut trie, &deploy);
+        let contract = contract_address("alice", 0);
+        trie.put(contract.as_bytes().to_vec(), 10_000u64.to_le_bytes().to_vec());
+
+        assert!(finish(&mut trie, 9).is_empty());
+        assert_eq!(balance(&trie, "alice"), 1_000);
+        finish(&mut trie, 10);
+        // 5 address bytes and 8 balance bytes for 10 blocks
+        assert_eq!(balance(&trie, "alice"), 870);
+        let contract_rent = (contract.len() as u64 + 8 + 100) * 10;
+        assert_eq!(balance(&trie, &contract), 10_000 - contract_rent);
+        assert_eq!(SCHEDULE.collected(&trie), 130 + contract_rent);
+        assert_eq!(SCHEDULE.status(&trie, "alice"), AccountRent::Active { rent_per_block: 13 });
+    }
+
+    #[test]
+    fn test_unpaid_state_expires_then_is_pruned_and_resurrected() {
+        let mut trie = MerklePatriciaTrie::new();
+        trie.put(b"bob".to_vec(), 150u64.to_le_bytes().to_vec());
+        trie.put(b"carol".to_vec(), 100u64.to_le_bytes().to_vec());
+        trie.put(b"payer".to_vec(), 10_000u64.to_le_bytes().to_vec());
+
+        // bob owes 110 a period; carol's 130 exceed her balance
+        finish(&mut trie, 10);
+        assert_eq!(SCHEDULE.status(&trie, "carol"), AccountRent::Expired(Expiry { since: 10, arrears: 130 }));
+        assert_eq!(balance(&trie, "carol"), 100);
+
+        // Paying the arrears in the grace period recovers the account
+        trie.put(b"carol".to_vec(), 300u64.to_le_bytes().to_vec());
+        finish(&mut trie, 20);
+        assert_eq!(balance(&trie, "carol"), 40);
+        assert!(matches!(SCHEDULE.status(&trie, "carol"), AccountRent::Active { .. }));
+
+        // bob fell behind at 20 and is pruned once the grace period is over
+        assert!(finish(&mut trie, 30).is_empty());
+        let root_before = trie.root_hash();
+        let pruned = finish(&mut trie, 40);
+        let [witness] = pruned.as_slice() else { panic!("expected bob to be pruned, got {:?}", pruned) };
+        assert_eq!((witness.account.as_str(), witness.balance), ("bob", 40));
+        assert!(trie.get(b"bob".to_vec()).is_none());
+        let AccountRent::Pruned(tombstone) = SCHEDULE.status(&trie, "bob") else { panic!("bob is not pruned") };
+        assert_eq!(tombstone.arrears, 330);
+        assert_ne!(tombstone.state_root, root_before);
+
+        // A witness with another balance does not match the tombstone
+        let forged = ExpiredState { balance: 1_000, ..witness.clone() };
+        assert!(resurrect(&mut trie, "payer", &forged).unwrap_err().contains("does not match"));
+
+        // Resurrection pays the arrears and the rent for the pruned blocks
+        trie.put(b"economy:height".to_vec(), 45u64.to_le_bytes().to_vec());
+        assert_eq!(resurrect(&mut trie, "payer", witness).unwrap(), 330 + 11 * 5);
+        assert_eq!(balance(&trie, "bob"), 40);
+        // The payer paid its own rent at each of the four collections too
+        assert_eq!(balance(&trie, "payer"), 10_000 - 4 * 130 - 385);
+        assert!(matches!(SCHEDULE.status(&trie, "bob"), AccountRent::Active { .. }));
+        assert!(resurrect(&mut trie, "payer", witness).unwrap_err().contains("No pruned state"));
+    }
+}
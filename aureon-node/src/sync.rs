@@ -3,8 +3,23 @@
 
 use crate::types::Block;
 use crate::indexer::BlockchainIndexer;
+use crate::chain_handle::ChainHandle;
+use crate::metrics::Metrics;
+use crate::network::identity::PeerId;
+use crate::network::{Network, MAX_SYNC_BLOCKS};
+use crate::sync_client::SyncClient;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// Blocks requested from one peer at a time
+pub const SYNC_BATCH_SIZE: u64 = MAX_SYNC_BLOCKS;
+
+/// Block ranges downloaded from different peers at once
+pub const SYNC_PARALLEL_RANGES: usize = 4;
+
+/// Interval between checks of the peers' tips, and announcements of ours
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(5);
 
 /// Tracks synchronization state with peers
 #[derive(Clone, Debug)]
@@ -84,6 +99,95 @@ impl BlockSyncState {
     }
 }
 
+/// Downloads the blocks between the local tip and the highest tip peers
+/// report, several ranges in parallel, and applies them in order
+pub struct Syncer {
+    client: SyncClient,
+    network: Network,
+    chain: ChainHandle,
+    state: BlockSyncState,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl Syncer {
+    pub fn new(client: SyncClient, network: Network, chain: ChainHandle) -> Self {
+        Syncer {
+            client,
+            network,
+            chain,
+            state: BlockSyncState::new(),
+            metrics: None,
+        }
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Compare the local tip with the highest tip peers report
+    pub fn refresh(&mut self) -> &BlockSyncState {
+        let next_height = self.chain.len();
+        self.state.update_local_height(next_height.saturating_sub(1));
+        // Peers that disconnected no longer count
+        self.state.peer_max_height = self.network.get_highest_peer_height();
+        &self.state
+    }
+
+    /// Download and apply blocks until the local tip reaches the peers';
+    /// false if a round could not apply any block
+    ///
+    /// `apply` imports a block and reports whether it was accepted.
+    pub fn sync(&mut self, mut apply: impl FnMut(PeerId, Block) -> bool) -> bool {
+        loop {
+            let Some((from, to)) = self.refresh().get_sync_range() else {
+                self.set_syncing(false);
+                return true;
+            };
+            self.set_syncing(true);
+            let ranges = split_range(from, to, SYNC_BATCH_SIZE, SYNC_PARALLEL_RANGES);
+            let mut applied = 0;
+            'ranges: for (&(from, to), result) in ranges.iter().zip(self.client.fetch_ranges(&ranges)) {
+                let Ok((peer, blocks)) = result else {
+                    break;
+                };
+                let complete = blocks.len() as u64 == to - from + 1;
+                for block in blocks {
+                    if !apply(peer, block) {
+                        break 'ranges;
+                    }
+                    applied += 1;
+                }
+                // Later ranges do not connect to a partial one
+                if !complete {
+                    break;
+                }
+            }
+            if let Some(metrics) = &self.metrics {
+                metrics.sync_blocks_applied.inc_by(applied);
+            }
+            if applied == 0 {
+                return false;
+            }
+        }
+    }
+
+    fn set_syncing(&self, syncing: bool) {
+        if let Some(metrics) = &self.metrics {
+            metrics.syncing.set(syncing as i64);
+        }
+    }
+}
+
+/// Split `from..=to` into at most `count` consecutive ranges of at most `size` blocks
+fn split_range(from: u64, to: u64, size: u64, count: usize) -> Vec<(u64, u64)> {
+    (0..count as u64)
+        .map(|index| from + index * size)
+        .take_while(|start| *start <= to)
+        .map(|start| (start, (start + size - 1).min(to)))
+        .collect()
+}
+
 /// Block validator for sync operations
 pub struct BlockValidator;
 
@@ -139,6 +243,7 @@ impl BlockValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::Db;
 
     #[test]
     fn test_sync_state_creation() {
@@ -179,6 +284,59 @@ mod tests {
         assert!(state.add_pending_block(1, "peer3".to_string()).is_err());
     }
 
+    #[test]
+    fn test_split_range() {
+        assert_eq!(split_range(5, 20, 4, 3), vec![(5, 8), (9, 12), (13, 16)]);
+        assert_eq!(split_range(5, 10, 4, 3), vec![(5, 8), (9, 10)]);
+        assert_eq!(split_range(7, 7, 4, 3), vec![(7, 7)]);
+    }
+
+    #[test]
+    fn test_syncer_applies_ranges_from_peers_in_order() {
+        use crate::sync_client::tests::{chain, wait_until};
+
+        // Three peers serve the same 300-block chain; the syncer has its first block
+        let blocks = chain(300);
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        let servers: Vec<Network> = (0..3)
+            .map(|index| {
+                let server = Network::new(format!("server{}", index), "1.0.0".to_string());
+                let served = blocks.clone();
+                server.serve_blocks(Arc::new(move |height| served.get(height as usize).cloned()));
+                server.start_listener("127.0.0.1:0");
+                client.add_peer(&server.local_addr().unwrap(), None);
+                server
+            })
+            .collect();
+        assert!(wait_until(|| servers.iter().all(|server| server.peer_count() == 1)));
+        servers[0].broadcast_peer_info(299);
+        assert!(wait_until(|| client.get_highest_peer_height() == 299));
+
+        let path = std::env::temp_dir().join(format!("aureon_syncer_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let db = Arc::new(Db::open(path.to_str().unwrap()));
+        let chain = ChainHandle::open(db.clone()).unwrap();
+        chain.append(&blocks[0]).unwrap();
+
+        let metrics = Arc::new(Metrics::new().unwrap());
+        let mut syncer = Syncer::new(SyncClient::new(client.clone()), client, chain.clone()).with_metrics(metrics.clone());
+        let mut peers = std::collections::HashSet::new();
+        let synced = syncer.sync(|peer, block| {
+            peers.insert(peer);
+            chain.append(&block).is_ok()
+        });
+        assert!(synced);
+        assert_eq!(chain.len(), 300);
+        assert_eq!(metrics.sync_blocks_applied.get(), 299);
+        assert_eq!(metrics.syncing.get(), 0);
+        // Ranges were spread over several peers
+        assert!(peers.len() > 1);
+        drop(chain);
+        drop(syncer);
+        drop(db);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
     #[test]
     fn test_peer_height_updates() {
         let mut state = BlockSyncState::new();
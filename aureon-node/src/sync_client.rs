@@ -0,0 +1,315 @@
+//! Peer Sync Requests
+//!
+//! Each block range is requested from one peer, and several ranges can be
+//! downloaded from different peers at once. A request that times out or
+//! returns unusable blocks is retried with exponential backoff, preferring
+//! peers not yet tried. Every peer has its own circuit breaker: once it opens,
+//! requests go to other peers until its cooldown ends. Requests that exhaust
+//! their retries mark the sync component degraded in the node's health.
+
+use crate::error_recovery::{CircuitBreaker, HealthChecker, RecoveryContext, RecoveryError, RetryConfig};
+use crate::network::identity::PeerId;
+use crate::network::{Network, SyncedBlocks};
+use crate::types::Block;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time a peer has to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Consecutive failures that open a peer's breaker
+const BREAKER_FAILURE_THRESHOLD: u32 = 3;
+
+/// Time an open breaker keeps requests away from its peer
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Fetches blocks from peers with retries and per-peer circuit breakers
+///
+/// Subscribes to the network's synced blocks, replacing any previous subscriber.
+pub struct SyncClient {
+    network: Network,
+    responses: Mutex<Receiver<SyncedBlocks>>,
+    breakers: Mutex<HashMap<PeerId, CircuitBreaker>>,
+    retry: RetryConfig,
+    request_timeout: Duration,
+    /// Consecutive failures that open a peer's breaker
+    failure_threshold: u32,
+    cooldown: Duration,
+    health: Arc<Mutex<HealthChecker>>,
+}
+
+impl SyncClient {
+    pub fn new(network: Network) -> Self {
+        let responses = Mutex::new(network.subscribe_synced_blocks());
+        SyncClient {
+            network,
+            responses,
+            breakers: Mutex::new(HashMap::new()),
+            retry: RetryConfig::default(),
+            request_timeout: REQUEST_TIMEOUT,
+            failure_threshold: BREAKER_FAILURE_THRESHOLD,
+            cooldown: BREAKER_COOLDOWN,
+            health: Arc::new(Mutex::new(HealthChecker::default())),
+        }
+    }
+
+    /// Report sync failures to `health`
+    pub fn with_health(mut self, health: Arc<Mutex<HealthChecker>>) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Fetch the chained blocks from `from_height` up to `to_height`, with the peer that served them
+    ///
+    /// A peer may serve fewer blocks than asked for, but never none.
+    pub fn fetch_blocks(&self, from_height: u64, to_height: u64) -> Result<SyncedBlocks, RecoveryError> {
+        let mut context = RecoveryContext::new(self.retry.clone());
+        let mut tried = HashSet::new();
+        loop {
+            let error = match self.select_peer(&tried) {
+                Some(peer) => match self.request(peer, from_height, to_height) {
+                    Ok(blocks) => {
+                        self.breakers.lock().unwrap().entry(peer).or_insert_with(|| self.breaker()).record_success();
+                        self.health.lock().unwrap().record_success();
+                        return Ok((peer, blocks));
+                    }
+                    Err(e) => {
+                        eprintln!("[Sync] Request for blocks #{}-#{} failed: {}", from_height, to_height, e);
+                        self.breakers.lock().unwrap().entry(peer).or_insert_with(|| self.breaker()).record_failure();
+                        tried.insert(peer);
+                        e
+                    }
+                },
+                None if self.network.peer_count() == 0 => RecoveryError::TemporaryError("No connected peers".to_string()),
+                None => RecoveryError::CircuitBreakerOpen,
+            };
+            if !context.record_error(error) {
+                break;
+            }
+            thread::sleep(context.next_backoff());
+        }
+        self.health.lock().unwrap().record_failure();
+        Err(context.last_error().cloned().unwrap_or(RecoveryError::CircuitBreakerOpen))
+    }
+
+    /// Fetch several ranges at once, each from a different peer
+    ///
+    /// Ranges without a peer of their own, or whose peer fails, fall back to
+    /// `fetch_blocks`. Results are in the order of `ranges`.
+    pub fn fetch_ranges(&self, ranges: &[(u64, u64)]) -> Vec<Result<SyncedBlocks, RecoveryError>> {
+        let mut answers: HashMap<PeerId, Vec<Block>> = HashMap::new();
+        let mut assigned: Vec<Option<PeerId>> = vec![None; ranges.len()];
+        {
+            let responses = self.responses.lock().unwrap();
+            while responses.try_recv().is_ok() {}
+            for (slot, (peer, (from_height, to_height))) in
+                assigned.iter_mut().zip(self.select_peers(ranges.len()).into_iter().zip(ranges.iter().copied()))
+            {
+                if self.send(peer, from_height, to_height) {
+                    *slot = Some(peer);
+                }
+            }
+            let expected = assigned.iter().flatten().count();
+            let deadline = Instant::now() + self.request_timeout;
+            while answers.len() < expected {
+                match responses.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok((from, blocks)) if assigned.contains(&Some(from)) => {
+                        answers.entry(from).or_insert(blocks);
+                    }
+                    Ok(_) => continue,
+                    Err(_) => break,
+                }
+            }
+        }
+
+        ranges
+            .iter()
+            .zip(assigned)
+            .map(|(&(from_height, to_height), peer)| {
+                if let Some(peer) = peer {
+                    let answer = answers
+                        .remove(&peer)
+                        .ok_or_else(|| RecoveryError::TimeoutError(format!("Peer {} did not answer", peer)))
+                        .and_then(|blocks| Self::check_blocks(peer, from_height, blocks));
+                    let mut breakers = self.breakers.lock().unwrap();
+                    let breaker = breakers.entry(peer).or_insert_with(|| self.breaker());
+                    match answer {
+                        Ok(blocks) => {
+                            breaker.record_success();
+                            self.health.lock().unwrap().record_success();
+                            return Ok((peer, blocks));
+                        }
+                        Err(e) => {
+                            eprintln!("[Sync] Request for blocks #{}-#{} failed: {}", from_height, to_height, e);
+                            breaker.record_failure();
+                        }
+                    }
+                }
+                self.fetch_blocks(from_height, to_height)
+            })
+            .collect()
+    }
+
+    fn breaker(&self) -> CircuitBreaker {
+        CircuitBreaker::new(self.failure_threshold, 1, self.cooldown)
+    }
+
+    /// Peer whose breaker lets a request through, preferring untried and higher peers
+    fn select_peer(&self, tried: &HashSet<PeerId>) -> Option<PeerId> {
+        let mut candidates = self.network.sync_peers();
+        candidates.sort_by_key(|(peer, height)| (tried.contains(peer), Reverse(*height)));
+        let mut breakers = self.breakers.lock().unwrap();
+        candidates
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .find(|peer| breakers.entry(*peer).or_insert_with(|| self.breaker()).allow_request())
+    }
+
+    /// Up to `count` distinct peers whose breakers let a request through, highest first
+    fn select_peers(&self, count: usize) -> Vec<PeerId> {
+        let mut candidates = self.network.sync_peers();
+        candidates.sort_by_key(|(_, height)| Reverse(*height));
+        let mut breakers = self.breakers.lock().unwrap();
+        candidates
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .filter(|peer| breakers.entry(*peer).or_insert_with(|| self.breaker()).allow_request())
+            .take(count)
+            .collect()
+    }
+
+    /// Ask `peer` for the blocks; false if it is not connected
+    fn send(&self, peer: PeerId, from_height: u64, to_height: u64) -> bool {
+        if from_height == to_height {
+            self.network.request_block(&peer, from_height)
+        } else {
+            self.network.request_sync(&peer, from_height, to_height)
+        }
+    }
+
+    /// Ask `peer` for the blocks and wait for its answer
+    fn request(&self, peer: PeerId, from_height: u64, to_height: u64) -> Result<Vec<Block>, RecoveryError> {
+        let responses = self.responses.lock().unwrap();
+        // Drop late answers to requests that already timed out
+        while responses.try_recv().is_ok() {}
+        if !self.send(peer, from_height, to_height) {
+            return Err(RecoveryError::TemporaryError(format!("Peer {} is not connected", peer)));
+        }
+
+        let deadline = Instant::now() + self.request_timeout;
+        let blocks = loop {
+            match responses.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok((from, blocks)) if from == peer => break blocks,
+                Ok(_) => continue,
+                Err(_) => return Err(RecoveryError::TimeoutError(format!("Peer {} did not answer", peer))),
+            }
+        };
+        Self::check_blocks(peer, from_height, blocks)
+    }
+
+    /// Accept a non-empty run of chained blocks
+    fn check_blocks(peer: PeerId, from_height: u64, blocks: Vec<Block>) -> Result<Vec<Block>, RecoveryError> {
+        if blocks.is_empty() {
+            return Err(RecoveryError::TemporaryError(format!("Peer {} has no block #{}", peer, from_height)));
+        }
+        if blocks.windows(2).any(|pair| pair[1].previous_hash != pair[0].hash) {
+            return Err(RecoveryError::PermanentError(format!("Peer {} sent unchained blocks", peer)));
+        }
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::error_recovery::{CircuitState, HealthStatus};
+
+    pub(crate) fn chain(length: usize) -> Vec<Block> {
+        let mut previous_hash = "GENESIS".to_string();
+        (0..length)
+            .map(|height| {
+                let block = Block {
+                    transactions: vec![],
+                    previous_hash: previous_hash.clone(),
+                    nonce: 0,
+                    hash: format!("h{}", height),
+                    pre_state_root: vec![],
+                    post_state_root: vec![],
+                    evidence: vec![],
+                    logs_bloom: vec![],
+                    logs_root: vec![],
+                    state_proof: None,
+                    slot: 0,
+                    proposer: String::new(),
+                    proposer_signature: vec![],
+                    random_reveal: vec![],
+                    random_seed: vec![],
+                    ommers: vec![],
+                    difficulty: 0,
+                };
+                previous_hash = block.hash.clone();
+                block
+            })
+            .collect()
+    }
+
+    pub(crate) fn wait_until(condition: impl Fn() -> bool) -> bool {
+        for _ in 0..100 {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    fn fast_retry() -> RetryConfig {
+        RetryConfig { max_retries: 3, initial_backoff_ms: 10, max_backoff_ms: 50, backoff_multiplier: 2.0 }
+    }
+
+    #[test]
+    fn test_open_breaker_moves_requests_to_another_peer() {
+        let blocks = chain(3);
+        let good = Network::new("good".to_string(), "1.0.0".to_string());
+        good.serve_blocks(Arc::new(move |height| blocks.get(height as usize).cloned()));
+        // Serves no blocks at all, but claims the highest chain so it is asked first
+        let empty = Network::new("empty".to_string(), "1.0.0".to_string());
+        let client = Network::new("client".to_string(), "1.0.0".to_string());
+        for server in [&good, &empty] {
+            server.start_listener("127.0.0.1:0");
+            client.add_peer(&server.local_addr().unwrap(), None);
+        }
+        assert!(wait_until(|| good.peer_count() == 1 && empty.peer_count() == 1));
+        empty.broadcast_peer_info(100);
+        assert!(wait_until(|| client.get_highest_peer_height() == 100));
+
+        let mut sync = SyncClient::new(client.clone());
+        sync.retry = fast_retry();
+        sync.failure_threshold = 1;
+        let (peer, synced) = sync.fetch_blocks(0, 5).unwrap();
+        assert_eq!(peer, good.peer_id());
+        let hashes: Vec<_> = synced.iter().map(|block| block.hash.as_str()).collect();
+        assert_eq!(hashes, ["h0", "h1", "h2"]);
+        assert_eq!(sync.breakers.lock().unwrap()[&empty.peer_id()].state(), CircuitState::Open);
+
+        // Single blocks are fetched with GetBlock
+        let (peer, synced) = sync.fetch_blocks(1, 1).unwrap();
+        assert_eq!(peer, good.peer_id());
+        assert_eq!(synced[0].hash, "h1");
+        assert_eq!(sync.health.lock().unwrap().status, HealthStatus::Healthy);
+    }
+
+    #[test]
+    fn test_exhausted_retries_degrade_health() {
+        let health = Arc::new(Mutex::new(HealthChecker::default()));
+        let mut sync = SyncClient::new(Network::new("alone".to_string(), "1.0.0".to_string()))
+            .with_health(health.clone());
+        sync.retry = fast_retry();
+        assert!(matches!(sync.fetch_blocks(0, 10), Err(RecoveryError::TemporaryError(_))));
+        assert_eq!(health.lock().unwrap().status, HealthStatus::Degraded);
+    }
+}
@@ -0,0 +1,168 @@
+//! Time-Locked Transfers
+//!
+//! A `TimeLockedTransfer` moves funds out of its sender's balance into an
+//! escrow entry that is credited to the recipient once the chain reaches the
+//! unlock height. Escrow entries are stored in the state trie under
+//! `timelock:` keys, indexed by unlock height and by the accounts involved,
+//! so they are covered by the state root. The ledger counts applied blocks
+//! itself; at the end of each block it moves to the next height and releases
+//! the locks due at it. The trie has no deletes, so released indexes are left
+//! empty.
+
+use crate::token::TokenState;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::Serialize;
+
+/// Prefix of every key holding time-lock state
+pub const STATE_PREFIX: &str = "timelock:";
+const HEIGHT_KEY: &[u8] = b"timelock:height";
+const DUE_PREFIX: &str = "timelock:due:";
+const ACCOUNT_PREFIX: &str = "timelock:account:";
+
+/// Funds held in escrow until `unlock_block`
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TimeLock {
+    /// Hash of the transaction that created the lock
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub unlock_block: u64,
+}
+
+fn due_key(height: u64) -> Vec<u8> {
+    format!("{}{}", DUE_PREFIX, height).into_bytes()
+}
+
+fn account_key(account: &str) -> Vec<u8> {
+    format!("{}{}", ACCOUNT_PREFIX, account).into_bytes()
+}
+
+/// Height of the block being applied to `state`
+pub fn height<S: TokenState + ?Sized>(state: &S) -> u64 {
+    read_u64(state, HEIGHT_KEY)
+}
+
+/// Locks still in escrow that `account` sent or will receive
+pub fn account_locks<S: TokenState + ?Sized>(state: &S, account: &str) -> Vec<TimeLock> {
+    read_list(state, &account_key(account))
+}
+
+fn read_u64<S: TokenState + ?Sized>(state: &S, key: &[u8]) -> u64 {
+    state.get(key).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes).unwrap_or(0)
+}
+
+fn read_list<S: TokenState + ?Sized>(state: &S, key: &[u8]) -> Vec<TimeLock> {
+    state
+        .get(key)
+        .and_then(|bytes| bincode::decode_from_slice(&bytes, standard()).ok())
+        .map(|(locks, _)| locks)
+        .unwrap_or_default()
+}
+
+/// Time-lock escrow over a `TokenState`
+pub struct TimeLockLedger<'s, S: TokenState + ?Sized> {
+    state: &'s mut S,
+}
+
+impl<'s, S: TokenState + ?Sized> TimeLockLedger<'s, S> {
+    pub fn new(state: &'s mut S) -> Self {
+        TimeLockLedger { state }
+    }
+
+    /// Escrow `lock.amount` of the sender's balance until `lock.unlock_block`
+    pub fn lock(&mut self, lock: TimeLock) -> Result<(), String> {
+        let height = height(self.state);
+        if lock.unlock_block <= height {
+            return Err(format!("Unlock block {} is not after the current block {}", lock.unlock_block, height));
+        }
+        if lock.amount == 0 {
+            return Err("Time-locked amount must be positive".to_string());
+        }
+        let balance = read_u64(self.state, lock.from.as_bytes());
+        if balance < lock.amount {
+            return Err(format!("Insufficient balance: {} < {}", balance, lock.amount));
+        }
+
+        self.state.put(lock.from.as_bytes().to_vec(), (balance - lock.amount).to_le_bytes().to_vec());
+        let mut due = read_list(self.state, &due_key(lock.unlock_block));
+        due.push(lock.clone());
+        self.write_list(due_key(lock.unlock_block), &due);
+        for account in Self::accounts(&lock) {
+            let mut locks = read_list(self.state, &account_key(account));
+            locks.push(lock.clone());
+            self.write_list(account_key(account), &locks);
+        }
+        Ok(())
+    }
+
+    /// Move to the next height and credit the locks due at it to their
+    /// recipients, returning the released locks
+    pub fn finish_block(&mut self) -> Vec<TimeLock> {
+        let height = height(self.state) + 1;
+        self.state.put(HEIGHT_KEY.to_vec(), height.to_le_bytes().to_vec());
+        let due = read_list(self.state, &due_key(height));
+        if due.is_empty() {
+            return due;
+        }
+        self.write_list(due_key(height), &[]);
+        for lock in &due {
+            let balance = read_u64(self.state, lock.to.as_bytes());
+            self.state.put(lock.to.as_bytes().to_vec(), (balance + lock.amount).to_le_bytes().to_vec());
+            for account in Self::accounts(lock) {
+                let mut locks = read_list(self.state, &account_key(account));
+                locks.retain(|pending| pending.id != lock.id);
+                self.write_list(account_key(account), &locks);
+            }
+        }
+        due
+    }
+
+    fn accounts(lock: &TimeLock) -> Vec<&str> {
+        let mut accounts = vec![lock.from.as_str(), lock.to.as_str()];
+        accounts.dedup();
+        accounts
+    }
+
+    fn write_list(&mut self, key: Vec<u8>, locks: &[TimeLock]) {
+        let bytes = bincode::encode_to_vec(locks, standard()).expect("Time locks encode");
+        self.state.put(key, bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lock(id: &str, amount: u64, unlock_block: u64) -> TimeLock {
+        TimeLock { id: id.to_string(), from: "alice".to_string(), to: "bob".to_string(), amount, unlock_block }
+    }
+
+    fn balance(state: &HashMap<Vec<u8>, Vec<u8>>, account: &str) -> u64 {
+        state.get(account.as_bytes()).map_or(0, |bytes| u64::from_le_bytes(bytes.clone().try_into().unwrap()))
+    }
+
+    #[test]
+    fn test_escrow_released_at_unlock_height() {
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        state.insert(b"alice".to_vec(), 100u64.to_le_bytes().to_vec());
+        let mut ledger = TimeLockLedger::new(&mut state);
+
+        ledger.lock(lock("a", 30, 2)).unwrap();
+        ledger.lock(lock("b", 20, 3)).unwrap();
+        assert!(ledger.lock(lock("c", 100, 3)).unwrap_err().contains("Insufficient balance"));
+        assert!(ledger.lock(lock("d", 1, 0)).is_err());
+        assert_eq!(account_locks(&*ledger.state, "bob").len(), 2);
+
+        assert!(ledger.finish_block().is_empty());
+        assert_eq!(ledger.finish_block(), vec![lock("a", 30, 2)]);
+        assert_eq!(account_locks(&*ledger.state, "alice"), vec![lock("b", 20, 3)]);
+        // Unlock heights must lie ahead of the current block
+        assert!(ledger.lock(lock("e", 1, 2)).is_err());
+        assert_eq!(ledger.finish_block().len(), 1);
+        assert!(account_locks(&*ledger.state, "bob").is_empty());
+        assert_eq!((balance(&state, "alice"), balance(&state, "bob")), (50, 50));
+    }
+}
@@ -0,0 +1,342 @@
+//! Fungible Tokens
+//!
+//! User-created fungible tokens living alongside the native balance. Each
+//! token's metadata, balances and allowances are stored in the state trie
+//! under `token:` keys, so they are covered by the state root like native
+//! balances. The same ledger backs the native `Token` transaction payload
+//! and the token host functions WASM contracts call.
+
+use crate::mpt::MerklePatriciaTrie;
+use bincode::config::standard;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Prefix of every key holding token state
+pub const STATE_PREFIX: &str = "token:";
+const INFO_PREFIX: &str = "token:info:";
+const BALANCE_PREFIX: &str = "token:balance:";
+const ALLOWANCE_PREFIX: &str = "token:allowance:";
+
+/// Longest token symbol accepted
+pub const MAX_SYMBOL_LEN: usize = 12;
+
+/// Token operation carried by a `Token` transaction, on behalf of its sender
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub enum TokenOp {
+    /// Create a token owned by the sender, minting `initial_supply` to it
+    Create {
+        name: String,
+        symbol: String,
+        decimals: u8,
+        initial_supply: u64,
+        /// Cap on the total supply, if any
+        max_supply: Option<u64>,
+    },
+    /// Mint new tokens; only the token's owner may mint
+    Mint { token: String, to: String, amount: u64 },
+    /// Destroy tokens held by the sender
+    Burn { token: String, amount: u64 },
+    Transfer { token: String, to: String, amount: u64 },
+    /// Allow `spender` to transfer up to `amount` of the sender's tokens
+    Approve { token: String, spender: String, amount: u64 },
+    /// Transfer `owner`'s tokens within the allowance granted to the sender
+    TransferFrom { token: String, owner: String, to: String, amount: u64 },
+}
+
+/// Metadata of a token
+#[derive(Serialize, Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TokenInfo {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Account allowed to mint
+    pub owner: String,
+    pub total_supply: u64,
+    pub max_supply: Option<u64>,
+}
+
+/// Id of the token `creator` creates with `symbol`
+pub fn token_id(creator: &str, symbol: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(creator.as_bytes());
+    hasher.update(b":");
+    hasher.update(symbol.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn info_key(token: &str) -> Vec<u8> {
+    format!("{}{}", INFO_PREFIX, token).into_bytes()
+}
+
+fn balance_key(token: &str, account: &str) -> Vec<u8> {
+    format!("{}{}:{}", BALANCE_PREFIX, token, account).into_bytes()
+}
+
+fn allowance_key(token: &str, owner: &str, spender: &str) -> Vec<u8> {
+    format!("{}{}:{}:{}", ALLOWANCE_PREFIX, token, owner, spender).into_bytes()
+}
+
+/// Key-value state the ledger reads and writes
+pub trait TokenState {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>);
+}
+
+impl TokenState for HashMap<Vec<u8>, Vec<u8>> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        HashMap::get(self, key).cloned()
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.insert(key, value);
+    }
+}
+
+impl TokenState for MerklePatriciaTrie {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        MerklePatriciaTrie::get(self, key.to_vec()).map(<[u8]>::to_vec)
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.insert(key, value);
+    }
+}
+
+/// Reads node state for a `TokenOverlay`
+pub type StateReader = Arc<dyn Fn(&[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Token state seen by a contract: reads fall through to the node's state,
+/// writes are collected in `changes`
+#[derive(Clone, Default)]
+pub struct TokenOverlay {
+    base: Option<StateReader>,
+    pub changes: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl TokenOverlay {
+    pub fn new(base: Option<StateReader>) -> Self {
+        TokenOverlay { base, changes: HashMap::new() }
+    }
+}
+
+impl TokenState for TokenOverlay {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.changes.get(key) {
+            Some(value) => Some(value.clone()),
+            None => self.base.as_ref().and_then(|read| read(key)),
+        }
+    }
+
+    fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.changes.insert(key, value);
+    }
+}
+
+/// Token operations over a `TokenState`
+///
+/// Every operation checks all of its conditions before writing, so a
+/// failed operation leaves state untouched.
+pub struct TokenLedger<'s, S: TokenState + ?Sized> {
+    state: &'s mut S,
+}
+
+impl<'s, S: TokenState + ?Sized> TokenLedger<'s, S> {
+    pub fn new(state: &'s mut S) -> Self {
+        TokenLedger { state }
+    }
+
+    /// Apply `op` sent by `sender`
+    pub fn apply(&mut self, sender: &str, op: &TokenOp) -> Result<(), String> {
+        match op {
+            TokenOp::Create { name, symbol, decimals, initial_supply, max_supply } => self
+                .create(sender, name, symbol, *decimals, *initial_supply, *max_supply)
+                .map(|_| ()),
+            TokenOp::Mint { token, to, amount } => self.mint(sender, token, to, *amount),
+            TokenOp::Burn { token, amount } => self.burn(sender, token, *amount),
+            TokenOp::Transfer { token, to, amount } => self.transfer(sender, token, to, *amount),
+            TokenOp::Approve { token, spender, amount } => self.approve(sender, token, spender, *amount),
+            TokenOp::TransferFrom { token, owner, to, amount } => {
+                self.transfer_from(sender, token, owner, to, *amount)
+            }
+        }
+    }
+
+    pub fn info(&self, token: &str) -> Option<TokenInfo> {
+        let bytes = self.state.get(&info_key(token))?;
+        bincode::decode_from_slice(&bytes, standard()).ok().map(|(info, _)| info)
+    }
+
+    pub fn balance_of(&self, token: &str, account: &str) -> u64 {
+        self.read_u64(&balance_key(token, account))
+    }
+
+    pub fn allowance(&self, token: &str, owner: &str, spender: &str) -> u64 {
+        self.read_u64(&allowance_key(token, owner, spender))
+    }
+
+    /// Create a token owned by `creator` and mint `initial_supply` to it, returning its id
+    pub fn create(
+        &mut self,
+        creator: &str,
+        name: &str,
+        symbol: &str,
+        decimals: u8,
+        initial_supply: u64,
+        max_supply: Option<u64>,
+    ) -> Result<String, String> {
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN {
+            return Err(format!("Token symbol must be 1 to {} characters", MAX_SYMBOL_LEN));
+        }
+        if max_supply.is_some_and(|max| initial_supply > max) {
+            return Err("Initial supply exceeds max supply".to_string());
+        }
+        let id = token_id(creator, symbol);
+        if self.info(&id).is_some() {
+            return Err(format!("{} already created token {}", creator, symbol));
+        }
+        let info = TokenInfo {
+            id: id.clone(),
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+            owner: creator.to_string(),
+            total_supply: initial_supply,
+            max_supply,
+        };
+        self.write_info(&info)?;
+        self.write_u64(balance_key(&id, creator), initial_supply);
+        Ok(id)
+    }
+
+    pub fn mint(&mut self, caller: &str, token: &str, to: &str, amount: u64) -> Result<(), String> {
+        let mut info = self.existing(token)?;
+        if info.owner != caller {
+            return Err(format!("Only the owner of token {} can mint", info.symbol));
+        }
+        info.total_supply = info
+            .total_supply
+            .checked_add(amount)
+            .filter(|supply| info.max_supply.is_none_or(|max| *supply <= max))
+            .ok_or_else(|| format!("Minting {} would exceed the supply of token {}", amount, info.symbol))?;
+        let balance = self.balance_of(token, to);
+        self.write_info(&info)?;
+        self.write_u64(balance_key(token, to), balance + amount);
+        Ok(())
+    }
+
+    pub fn burn(&mut self, holder: &str, token: &str, amount: u64) -> Result<(), String> {
+        let mut info = self.existing(token)?;
+        let balance = self.balance_of(token, holder);
+        if balance < amount {
+            return Err(format!("Insufficient {} balance: {} < {}", info.symbol, balance, amount));
+        }
+        info.total_supply -= amount;
+        self.write_info(&info)?;
+        self.write_u64(balance_key(token, holder), balance - amount);
+        Ok(())
+    }
+
+    pub fn transfer(&mut self, from: &str, token: &str, to: &str, amount: u64) -> Result<(), String> {
+        let info = self.existing(token)?;
+        let from_balance = self.balance_of(token, from);
+        if from_balance < amount {
+            return Err(format!("Insufficient {} balance: {} < {}", info.symbol, from_balance, amount));
+        }
+        self.write_u64(balance_key(token, from), from_balance - amount);
+        let to_balance = self.balance_of(token, to);
+        self.write_u64(balance_key(token, to), to_balance + amount);
+        Ok(())
+    }
+
+    pub fn approve(&mut self, owner: &str, token: &str, spender: &str, amount: u64) -> Result<(), String> {
+        self.existing(token)?;
+        self.write_u64(allowance_key(token, owner, spender), amount);
+        Ok(())
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        spender: &str,
+        token: &str,
+        owner: &str,
+        to: &str,
+        amount: u64,
+    ) -> Result<(), String> {
+        let allowance = self.allowance(token, owner, spender);
+        if allowance < amount {
+            return Err(format!("Allowance of {} exceeded: {} < {}", spender, allowance, amount));
+        }
+        self.transfer(owner, token, to, amount)?;
+        self.write_u64(allowance_key(token, owner, spender), allowance - amount);
+        Ok(())
+    }
+
+    fn existing(&self, token: &str) -> Result<TokenInfo, String> {
+        self.info(token).ok_or_else(|| format!("Token {} not found", token))
+    }
+
+    fn write_info(&mut self, info: &TokenInfo) -> Result<(), String> {
+        let bytes = bincode::encode_to_vec(info, standard()).map_err(|e| format!("Failed to encode token: {}", e))?;
+        self.state.put(info_key(&info.id), bytes);
+        Ok(())
+    }
+
+    fn read_u64(&self, key: &[u8]) -> u64 {
+        self.state
+            .get(key)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0)
+    }
+
+    fn write_u64(&mut self, key: Vec<u8>, value: u64) {
+        self.state.put(key, value.to_le_bytes().to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_lifecycle() {
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut ledger = TokenLedger::new(&mut state);
+        let id = ledger.create("alice", "Gold", "GLD", 2, 1_000, Some(1_500)).unwrap();
+        assert!(ledger.create("alice", "Gold again", "GLD", 2, 0, None).is_err());
+
+        ledger.transfer("alice", &id, "bob", 300).unwrap();
+        assert!(ledger.transfer("bob", &id, "carol", 301).is_err());
+        assert!(ledger.mint("bob", &id, "bob", 1).is_err());
+        assert!(ledger.mint("alice", &id, "bob", 501).is_err());
+        ledger.mint("alice", &id, "bob", 500).unwrap();
+        ledger.burn("bob", &id, 100).unwrap();
+
+        assert_eq!(ledger.balance_of(&id, "alice"), 700);
+        assert_eq!(ledger.balance_of(&id, "bob"), 700);
+        assert_eq!(ledger.info(&id).unwrap().total_supply, 1_400);
+    }
+
+    #[test]
+    fn test_transfer_from_spends_allowance() {
+        let mut state: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let mut ledger = TokenLedger::new(&mut state);
+        let id = ledger.create("alice", "Gold", "GLD", 0, 100, None).unwrap();
+        ledger
+            .apply("alice", &TokenOp::Approve { token: id.clone(), spender: "dex".into(), amount: 40 })
+            .unwrap();
+
+        let spend = |amount| TokenOp::TransferFrom { token: id.clone(), owner: "alice".into(), to: "bob".into(), amount };
+        ledger.apply("dex", &spend(30)).unwrap();
+        assert!(ledger.apply("dex", &spend(11)).is_err());
+        assert!(ledger.apply("bob", &spend(1)).is_err());
+
+        assert_eq!(ledger.allowance(&id, "alice", "dex"), 10);
+        assert_eq!(ledger.balance_of(&id, "bob"), 30);
+        assert_eq!(ledger.balance_of(&id, "alice"), 70);
+    }
+}
@@ -1,5 +1,28 @@
 use serde::{Serialize, Deserialize};
 use bincode::{Encode, Decode};
+use bincode::config::standard;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use crate::bridge::BridgeEvent;
+use crate::community_governance::VoteChoice;
+use crate::consensus::ommers::OmmerHeader;
+use crate::light_block_header::LightBlockHeader;
+use crate::logs::Log;
+use crate::merkle_tree::{IncrementalMerkleTree, MerkleProofElement};
+use crate::shielded::ShieldedTransfer;
+use crate::signer::{SignKind, SignRequest, ValidatorSigner};
+use crate::slashing::Evidence;
+use crate::storage_rent::ExpiredState;
+use crate::state_transition::TransitionProof;
+use crate::nft::NftOp;
+use crate::token::TokenOp;
+
+/// Domain separator for block proposer signatures
+const BLOCK_PROPOSAL_DOMAIN: &[u8] = b"aureon-block-proposal";
+
+/// Most memory decoding a transaction may claim, which bounds what a length
+/// prefix in untrusted bytes can make the node allocate
+pub const MAX_TRANSACTION_SIZE: usize = 1024 * 1024;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Encode, Decode)]
 pub enum TransactionPayload {
@@ -28,10 +51,78 @@ pub enum TransactionPayload {
     Unstake {
         amount: u64,
     },
+    /// Vote on a governance proposal
+    GovernanceVote {
+        proposal_id: u64,
+        choice: VoteChoice,
+    },
+    /// Transfer between shielded notes, proven with a zk-SNARK
+    ShieldedTransfer(ShieldedTransfer),
+    /// Replace the code of a contract the sender administers, keeping its address
+    ContractUpgrade {
+        contract_address: String,
+        code: Vec<u8>,  // WASM bytecode
+        gas_limit: u64,
+    },
+    /// Create, mint, burn, transfer or approve a fungible token
+    Token(TokenOp),
+    /// Create an NFT collection, or mint, transfer or burn one of its tokens
+    Nft(NftOp),
+    /// Delegate stake to a validator
+    Delegate {
+        validator: String,
+        amount: u64,
+    },
+    /// Start unbonding stake delegated to a validator
+    Undelegate {
+        validator: String,
+        amount: u64,
+    },
+    /// Move funds to escrow, credited to `to` once the chain reaches `unlock_block`
+    TimeLockedTransfer {
+        to: String,
+        amount: u64,
+        unlock_block: u64,
+    },
+    /// Make the sender a multisig account: its transactions then need
+    /// `threshold` signatures by the given Ed25519 public keys
+    RegisterMultisig {
+        public_keys: Vec<Vec<u8>>,
+        threshold: u32,
+    },
+    /// Attestation by the bridge relayer key at index `relayer` to an event
+    /// on an external chain
+    BridgeAttest {
+        event: BridgeEvent,
+        relayer: u32,
+        signature: Vec<u8>,
+    },
+    /// Burn wrapped `asset` of `chain` to release it to `to` on that chain
+    BridgeBurn {
+        chain: String,
+        asset: String,
+        to: String,
+        amount: u64,
+    },
+    /// Next header of external `chain` for the bridge's light client
+    BridgeHeader {
+        chain: String,
+        header: LightBlockHeader,
+    },
+    /// Merkle proof that `event` is in the verified external header at `height`
+    BridgeProve {
+        event: BridgeEvent,
+        height: u64,
+        proof: Vec<MerkleProofElement>,
+    },
+    /// Restore state pruned for unpaid storage rent from its trie proofs
+    ResurrectState(ExpiredState),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Encode, Decode)]
 pub struct Transaction {
+    /// Network the transaction is signed for; empty for unsigned transactions
+    pub chain_id: String,
     pub from: String,
     pub nonce: u64,
     pub gas_price: u64,
@@ -41,9 +132,19 @@ pub struct Transaction {
 }
 
 impl Transaction {
+    /// Account or contract the transaction is addressed to, if any
+    pub fn recipient(&self) -> Option<&str> {
+        match &self.payload {
+            TransactionPayload::Transfer { to, .. } | TransactionPayload::TimeLockedTransfer { to, .. } => Some(to),
+            TransactionPayload::ContractCall { contract_address, .. } => Some(contract_address),
+            _ => None,
+        }
+    }
+
     /// Helper to create a simple transfer (backward compat)
     pub fn transfer(from: String, to: String, amount: u64) -> Self {
         Self {
+            chain_id: String::new(),
             from,
             nonce: 0,
             gas_price: 1,
@@ -56,6 +157,7 @@ impl Transaction {
     /// Helper to create a contract deployment
     pub fn deploy_contract(from: String, code: Vec<u8>, gas_limit: u64) -> Self {
         Self {
+            chain_id: String::new(),
             from,
             nonce: 0,
             gas_price: 1,
@@ -65,6 +167,19 @@ impl Transaction {
         }
     }
 
+    /// Helper to create a contract upgrade
+    pub fn upgrade_contract(from: String, contract_address: String, code: Vec<u8>, gas_limit: u64) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::ContractUpgrade { contract_address, code, gas_limit },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
     /// Helper to create a contract call
     pub fn call_contract(
         from: String,
@@ -74,6 +189,7 @@ impl Transaction {
         gas_limit: u64,
     ) -> Self {
         Self {
+            chain_id: String::new(),
             from,
             nonce: 0,
             gas_price: 1,
@@ -91,6 +207,7 @@ impl Transaction {
     /// Helper to create a stake transaction
     pub fn stake(from: String, amount: u64) -> Self {
         Self {
+            chain_id: String::new(),
             from,
             nonce: 0,
             gas_price: 1,
@@ -99,9 +216,174 @@ impl Transaction {
             public_key: vec![],
         }
     }
+
+    /// Helper to create an unstake transaction
+    pub fn unstake(from: String, amount: u64) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Unstake { amount },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create a delegation
+    pub fn delegate(from: String, validator: String, amount: u64) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Delegate { validator, amount },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create an undelegation
+    pub fn undelegate(from: String, validator: String, amount: u64) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Undelegate { validator, amount },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create a governance vote
+    pub fn governance_vote(from: String, proposal_id: u64, choice: VoteChoice) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::GovernanceVote { proposal_id, choice },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create a token operation
+    pub fn token(from: String, op: TokenOp) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Token(op),
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create an NFT operation
+    pub fn nft(from: String, op: NftOp) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::Nft(op),
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Helper to create a shielded transfer
+    pub fn shielded_transfer(from: String, transfer: ShieldedTransfer) -> Self {
+        Self {
+            chain_id: String::new(),
+            from,
+            nonce: 0,
+            gas_price: 1,
+            payload: TransactionPayload::ShieldedTransfer(transfer),
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Canonical binary encoding (bincode, standard configuration), the
+    /// basis for transaction hashes and signatures
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, standard()).expect("transaction encoding cannot fail")
+    }
+
+    /// Decode a transaction from its canonical encoding
+    ///
+    /// Other encodings of the same transaction, such as integers in more
+    /// bytes than needed, are refused, so every transaction has exactly one
+    /// accepted form. Decoding allocates at most `MAX_TRANSACTION_SIZE`.
+    pub fn from_canonical_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let (tx, read): (Self, usize) =
+            bincode::decode_from_slice(bytes, standard().with_limit::<MAX_TRANSACTION_SIZE>())
+                .map_err(|e| format!("Failed to decode transaction: {}", e))?;
+        if read != bytes.len() {
+            return Err("Trailing bytes after transaction".to_string());
+        }
+        if tx.canonical_bytes() != bytes {
+            return Err("Transaction is not canonically encoded".to_string());
+        }
+        Ok(tx)
+    }
+
+    /// Bytes covered by the sender's signature: the canonical encoding,
+    /// chain id included, with an empty signature field
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature = vec![];
+        unsigned.canonical_bytes()
+    }
+
+    /// Hex-encoded SHA-256 of the canonical encoding
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.canonical_bytes()))
+    }
+
+    /// Sign with the Ed25519 secret key, setting `public_key` and `signature`
+    pub fn sign(&mut self, secret_key: &[u8; 32]) {
+        let signing_key = SigningKey::from_bytes(secret_key);
+        self.public_key = signing_key.verifying_key().to_bytes().to_vec();
+        self.signature = signing_key.sign(&self.signing_bytes()).to_bytes().to_vec();
+    }
+
+    /// Check the Ed25519 signature over `signing_bytes`
+    pub fn verify_signature(&self) -> Result<(), String> {
+        let public_key: [u8; 32] = self
+            .public_key
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Public key must be 32 bytes".to_string())?;
+        let signature: [u8; 64] = self
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Signature must be 64 bytes".to_string())?;
+        let verifying_key = VerifyingKey::from_bytes(&public_key)
+            .map_err(|e| format!("Invalid public key: {}", e))?;
+        verifying_key
+            .verify(&self.signing_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| "Invalid transaction signature".to_string())
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Merkle root over the hashes of `transactions` (empty when there are none)
+pub fn tx_root(transactions: &[Transaction]) -> Vec<u8> {
+    transactions
+        .iter()
+        .map(Transaction::hash)
+        .collect::<IncrementalMerkleTree>()
+        .root()
+        .and_then(|root| hex::decode(root).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Encode, Decode)]
 pub struct Block {
     pub transactions: Vec<Transaction>,
     pub previous_hash: String,
@@ -109,6 +391,92 @@ pub struct Block {
     pub hash: String,
     pub pre_state_root: Vec<u8>,
     pub post_state_root: Vec<u8>,
+    /// Validator misbehavior evidence included by the producer
+    #[serde(default)]
+    pub evidence: Vec<Evidence>,
+    /// Bloom filter over the addresses and topics of logs emitted in the block
+    #[serde(default)]
+    pub logs_bloom: Vec<u8>,
+    /// Merkle root over the block's logs (empty when there are none)
+    #[serde(default)]
+    pub logs_root: Vec<u8>,
+    /// Proof of the block's balance transition, when the producer made one
+    #[serde(default)]
+    pub state_proof: Option<TransitionProof>,
+    /// Consensus slot the block was proposed in
+    #[serde(default)]
+    pub slot: u64,
+    /// Validator that proposed the block (empty when unsigned)
+    #[serde(default)]
+    pub proposer: String,
+    /// Proposer's Ed25519 signature over `proposal_bytes`
+    #[serde(default)]
+    pub proposer_signature: Vec<u8>,
+    /// Proposer's signature over the parent seed and slot (empty when unsigned)
+    #[serde(default)]
+    pub random_reveal: Vec<u8>,
+    /// Randomness beacon seed following the parent block's
+    #[serde(default)]
+    pub random_seed: Vec<u8>,
+    /// Stale PoW blocks included as ommers
+    #[serde(default)]
+    pub ommers: Vec<OmmerHeader>,
+    /// Hashes the PoW block took on average to mine, retargeted from its
+    /// parent's (0 for other engines)
+    #[serde(default)]
+    pub difficulty: u64,
+}
+
+impl Block {
+    /// Bytes covered by the proposer signature: slot, block hash, proposer
+    /// and random seed
+    pub fn proposal_bytes(&self) -> Vec<u8> {
+        let mut bytes = BLOCK_PROPOSAL_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(self.hash.as_bytes());
+        bytes.extend_from_slice(self.proposer.as_bytes());
+        bytes.extend_from_slice(&self.random_seed);
+        bytes
+    }
+
+    /// Record `proposer` as the block's proposer, signed by its `signer`
+    pub fn sign_proposal(&mut self, proposer: &str, signer: &dyn ValidatorSigner) -> Result<(), String> {
+        self.proposer = proposer.to_string();
+        let request = SignRequest::new(SignKind::Proposal, self.slot, self.proposal_bytes());
+        self.proposer_signature = signer.sign(&request)?.to_bytes().to_vec();
+        Ok(())
+    }
+
+    /// Check the proposer signature against the proposer's `key`
+    pub fn verify_proposal(&self, key: &VerifyingKey) -> Result<(), String> {
+        let signature: [u8; 64] = self
+            .proposer_signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Proposer signature must be 64 bytes".to_string())?;
+        key.verify(&self.proposal_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| format!("Invalid proposer signature from {}", self.proposer))
+    }
+}
+
+/// Outcome of executing a transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransactionReceipt {
+    pub tx_hash: String,
+    /// False when the transaction was included but reverted
+    pub success: bool,
+    /// Why the transaction reverted
+    pub error: Option<String>,
+    pub gas_used: u64,
+    /// Base fee burned by the transaction
+    #[serde(default)]
+    pub fee_burned: u64,
+    /// Tip the transaction paid to the block's proposer
+    #[serde(default)]
+    pub tip: u64,
+    pub logs: Vec<Log>,
+    /// State root after the transaction
+    pub post_state_root: Vec<u8>,
 }
 
 /// Represents an account in shard state
@@ -119,4 +487,183 @@ pub struct Account {
     pub nonce: u64,
     pub code: Vec<u8>,
     pub storage: std::collections::HashMap<String, Vec<u8>>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_round_trip_and_hash() {
+        let tx = Transaction::governance_vote("alice".into(), 7, VoteChoice::Yes);
+        let decoded = Transaction::from_canonical_bytes(&tx.canonical_bytes()).unwrap();
+        assert_eq!(decoded.canonical_bytes(), tx.canonical_bytes());
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_ne!(tx.hash(), Transaction::governance_vote("alice".into(), 7, VoteChoice::No).hash());
+
+        let mut trailing = tx.canonical_bytes();
+        trailing.push(0);
+        assert!(Transaction::from_canonical_bytes(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_tx_root_commits_to_transactions() {
+        let a = Transaction::transfer("alice".into(), "bob".into(), 1);
+        let b = Transaction::transfer("bob".into(), "carol".into(), 2);
+        assert!(tx_root(&[]).is_empty());
+        assert_eq!(tx_root(&[a.clone(), b.clone()]).len(), 32);
+        assert_ne!(tx_root(&[a.clone(), b.clone()]), tx_root(&[b, a]));
+    }
+
+    #[test]
+    fn test_proposer_signature_covers_slot() {
+        use crate::consensus::{pos::PoSConsensus, ConsensusEngine};
+        use crate::network::identity::NodeIdentity;
+        let identity = NodeIdentity::from_secret([3u8; 32]);
+        let mut block = PoSConsensus::new(Default::default()).produce_block(5, "GENESIS".to_string(), vec![], vec![], vec![]);
+        block.sign_proposal("alice", &identity).unwrap();
+        assert!(block.verify_proposal(&identity.public_key()).is_ok());
+        assert!(block.verify_proposal(&NodeIdentity::from_secret([4u8; 32]).public_key()).is_err());
+
+        block.slot = 6;
+        assert!(block.verify_proposal(&identity.public_key()).is_err());
+    }
+
+    #[test]
+    fn test_signature_covers_payload() {
+        let mut tx = Transaction::stake("alice".into(), 500);
+        tx.sign(&[7u8; 32]);
+        assert!(tx.verify_signature().is_ok());
+
+        tx.payload = TransactionPayload::Stake { amount: 5_000 };
+        assert_eq!(tx.verify_signature().unwrap_err(), "Invalid transaction signature");
+    }
+
+    #[test]
+    fn test_cli_encoding_matches_signing_bytes() {
+        use crate::cli_transaction;
+        use crate::multisig::MultisigConfig;
+        use ed25519_dalek::SigningKey;
+
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let mut cli = cli_transaction::Transaction::transfer("aureon-local".into(), "alice".into(), "bob".into(), 10, 3, 2);
+        cli.public_key = key.verifying_key().to_bytes().to_vec();
+        let mut tx = Transaction {
+            chain_id: "aureon-local".into(),
+            from: "alice".into(),
+            nonce: 3,
+            gas_price: 2,
+            payload: TransactionPayload::Transfer { to: "bob".into(), amount: 10 },
+            signature: vec![],
+            public_key: cli.public_key.clone(),
+        };
+        assert_eq!(cli.canonical_bytes(), tx.signing_bytes());
+
+        cli.sign(&key);
+        tx.signature = cli.signature.clone();
+        assert!(tx.verify_signature().is_ok());
+        assert_eq!(cli.canonical_bytes(), tx.canonical_bytes());
+        assert_eq!(cli.hash(), tx.hash());
+
+        // Partial signatures combined by the CLI verify against a multisig account
+        let keys = [SigningKey::from_bytes(&[1u8; 32]), SigningKey::from_bytes(&[2u8; 32])];
+        let config = MultisigConfig {
+            public_keys: keys.iter().map(|key| key.verifying_key().to_bytes().to_vec()).collect(),
+            threshold: 2,
+        };
+        let partials = [cli.sign_partial(&keys[1], 1), cli.sign_partial(&keys[0], 0)];
+        cli.combine(&partials);
+        tx.public_key = cli.public_key.clone();
+        tx.signature = cli.signature.clone();
+        assert!(config.verify(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_only_canonical_encodings_decode() {
+        let bytes = Transaction::stake("alice".into(), 5).canonical_bytes();
+        // The nonce (0) spelled as a two-byte varint
+        let position = 1 + 1 + "alice".len();
+        assert_eq!(bytes[position], 0);
+        let padded = [&bytes[..position], &[251, 0, 0], &bytes[position + 1..]].concat();
+        assert!(Transaction::from_canonical_bytes(&padded).unwrap_err().contains("not canonically encoded"));
+
+        // A length prefix claiming far more than the limit allows
+        let mut huge = vec![253];
+        huge.extend_from_slice(&(1u64 << 62).to_le_bytes());
+        assert!(Transaction::from_canonical_bytes(&huge).is_err());
+    }
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn payload() -> impl Strategy<Value = TransactionPayload> {
+        prop_oneof![
+            (".{0,12}", any::<u64>()).prop_map(|(to, amount)| TransactionPayload::Transfer { to, amount }),
+            (vec(any::<u8>(), 0..64), any::<u64>())
+                .prop_map(|(code, gas_limit)| TransactionPayload::ContractDeploy { code, gas_limit }),
+            (".{0,12}", ".{0,12}", vec(vec(any::<u8>(), 0..8), 0..4), any::<u64>()).prop_map(
+                |(contract_address, function, args, gas_limit)| TransactionPayload::ContractCall {
+                    contract_address,
+                    function,
+                    args,
+                    gas_limit,
+                }
+            ),
+            any::<u64>().prop_map(|amount| TransactionPayload::Stake { amount }),
+            (any::<u64>(), prop_oneof![Just(VoteChoice::Yes), Just(VoteChoice::No), Just(VoteChoice::Abstain)])
+                .prop_map(|(proposal_id, choice)| TransactionPayload::GovernanceVote { proposal_id, choice }),
+            (".{0,12}", any::<u64>(), any::<u64>()).prop_map(|(to, amount, unlock_block)| {
+                TransactionPayload::TimeLockedTransfer { to, amount, unlock_block }
+            }),
+            (vec(vec(any::<u8>(), 32), 0..4), any::<u32>())
+                .prop_map(|(public_keys, threshold)| TransactionPayload::RegisterMultisig { public_keys, threshold }),
+        ]
+    }
+
+    fn transaction() -> impl Strategy<Value = Transaction> {
+        (".{0,8}", ".{0,12}", any::<u64>(), any::<u64>(), payload()).prop_map(
+            |(chain_id, from, nonce, gas_price, payload)| Transaction {
+                chain_id,
+                from,
+                nonce,
+                gas_price,
+                payload,
+                signature: vec![],
+                public_key: vec![],
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn prop_canonical_round_trip(tx in transaction()) {
+            let bytes = tx.canonical_bytes();
+            let decoded = Transaction::from_canonical_bytes(&bytes).unwrap();
+            prop_assert_eq!(decoded.canonical_bytes(), bytes);
+            prop_assert_eq!(decoded.hash(), tx.hash());
+        }
+
+        #[test]
+        fn prop_arbitrary_bytes_decode_only_canonically(bytes in vec(any::<u8>(), 0..96)) {
+            if let Ok(tx) = Transaction::from_canonical_bytes(&bytes) {
+                prop_assert_eq!(tx.canonical_bytes(), bytes);
+            }
+        }
+
+        #[test]
+        fn prop_tampered_signed_transactions_are_refused(
+            tx in transaction(),
+            secret in any::<[u8; 32]>(),
+            index in any::<prop::sample::Index>(),
+            flip in 1u8..,
+        ) {
+            let mut tx = tx;
+            tx.sign(&secret);
+            let mut bytes = tx.canonical_bytes();
+            let index = index.index(bytes.len());
+            bytes[index] ^= flip;
+            if let Ok(tampered) = Transaction::from_canonical_bytes(&bytes) {
+                prop_assert!(tampered.verify_signature().is_err());
+            }
+        }
+    }
+}
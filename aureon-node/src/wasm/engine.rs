@@ -1,12 +1,24 @@
-use wasmtime::{Engine, Store, Module, Linker};
-use super::gas_meter::GasMeter;
-use super::host_functions::{HostFunctions, WasmContext};
+use wasmtime::{Config, Engine, Store, Module, Linker};
+use super::gas_meter::{is_out_of_gas, GasMeter};
+use super::host_functions::{CodeReader, HostFunctions, WasmContext};
+use crate::logs::Log;
+use crate::token::{StateReader, TokenOverlay};
 use crate::types::Transaction;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 pub struct WasmRuntime {
     engine: Engine,
     module: Module,
+    address: String,
+    /// Node state the token host functions read through to
+    token_state: Option<StateReader>,
+    /// Randomness beacon seed exposed to the contract
+    random_seed: [u8; 32],
+    /// Code of the contracts this one may call
+    contracts: Option<CodeReader>,
+    /// Input the contract is called with
+    input: Vec<u8>,
 }
 
 pub struct ContractExecutionResult {
@@ -15,13 +27,59 @@ pub struct ContractExecutionResult {
     pub output: String,
     pub state_changes: HashMap<String, u64>, // Balance changes
     pub storage_changes: HashMap<String, Vec<u8>>, // Storage changes
+    pub logs: Vec<Log>, // Logs emitted by the contract
+    pub token_changes: HashMap<Vec<u8>, Vec<u8>>, // Token state changes
+    /// Bytes the contract returned through `set_output`
+    pub return_data: Vec<u8>,
 }
 
 impl WasmRuntime {
     pub fn new(wasm_bytes: &[u8]) -> anyhow::Result<Self> {
-        let engine = Engine::default();
+        let engine = Engine::new(&Self::config())?;
         let module = Module::from_binary(&engine, wasm_bytes)?;
-        Ok(Self { engine, module })
+        Ok(Self { engine, module, address: String::new(), token_state: None, random_seed: [0; 32], contracts: None, input: Vec::new() })
+    }
+
+    /// Record `address` as the emitter of the contract's logs
+    pub fn with_address(mut self, address: &str) -> Self {
+        self.address = address.to_string();
+        self
+    }
+
+    /// Let the token host functions read token state through `reader`
+    pub fn with_token_state(mut self, reader: StateReader) -> Self {
+        self.token_state = Some(reader);
+        self
+    }
+
+    /// Expose the random seed of the block being executed to the contract
+    pub fn with_random_seed(mut self, seed: [u8; 32]) -> Self {
+        self.random_seed = seed;
+        self
+    }
+
+    /// Let the contract call the contracts `reader` finds code for
+    pub fn with_contracts(mut self, reader: CodeReader) -> Self {
+        self.contracts = Some(reader);
+        self
+    }
+
+    /// Call the contract with `input`, e.g. a call encoded from its ABI
+    pub fn with_input(mut self, input: Vec<u8>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Engine settings for deterministic, fuel-metered execution
+    ///
+    /// Every wasm instruction costs one unit of fuel and host functions
+    /// charge their cost from the same fuel, so gas usage is identical on
+    /// every node.
+    fn config() -> Config {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.cranelift_nan_canonicalization(true);
+        config
     }
 
     pub fn execute_contract(
@@ -30,6 +88,7 @@ impl WasmRuntime {
         gas_limit: u64,
     ) -> anyhow::Result<String> {
         let mut store = Store::new(&self.engine, GasMeter::new(gas_limit));
+        store.add_fuel(gas_limit)?;
         let mut linker = Linker::new(&self.engine);
 
         // Register host functions with gas metering
@@ -43,7 +102,11 @@ impl WasmRuntime {
         // Call the run function in WASM
         run_func.call(&mut store, &[], &mut [])?;
 
-        println!("Gas used: {}", store.data().gas_used());
+        println!(
+            "Gas used: {} ({} in host calls)",
+            store.fuel_consumed().unwrap_or(0),
+            store.data().gas_used()
+        );
 
         Ok("Contract executed successfully".to_string())
     }
@@ -54,36 +117,406 @@ impl WasmRuntime {
         gas_limit: u64,
         initial_balances: HashMap<String, u64>,
     ) -> anyhow::Result<ContractExecutionResult> {
-        let context = WasmContext::new();
+        let mut context = WasmContext::new();
+        context.address = self.address.clone();
+        context.tokens = Arc::new(Mutex::new(TokenOverlay::new(self.token_state.clone())));
+        context.random_seed = self.random_seed;
+        context.contracts = self.contracts.clone();
+        context.input = self.input.clone();
+        context.call_stack = vec![self.address.clone()];
         
         // Initialize balances
         for (address, balance) in initial_balances {
             context.set_balance(&address, balance);
         }
 
-        let mut store = Store::new(&self.engine, (GasMeter::new(gas_limit), context.clone()));
-        let mut linker = Linker::new(&self.engine);
+        execute(&self.engine, &self.module, context, gas_limit)
+    }
 
-        // Register enhanced host functions with context
-        HostFunctions::register_with_context(&mut linker)?;
+    /// Smallest gas limit of at most `cap` the contract runs successfully with
+    ///
+    /// The gas metered by a run at `cap` is tried first; should the contract
+    /// fail with exactly that limit, a binary search between the two finds the
+    /// smallest one that works. `None` when the contract fails even at `cap`.
+    pub fn estimate_gas(&self, cap: u64) -> anyhow::Result<Option<u64>> {
+        let succeeds = |gas_limit| {
+            self.execute_contract_with_context(gas_limit, HashMap::new())
+                .map(|result| result.success)
+        };
+        let metered = self.execute_contract_with_context(cap, HashMap::new())?;
+        if !metered.success {
+            return Ok(None);
+        }
+        if succeeds(metered.gas_used)? {
+            return Ok(Some(metered.gas_used));
+        }
+        // `low` always fails and `high` always succeeds
+        let (mut low, mut high) = (metered.gas_used, cap);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if succeeds(mid)? {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(Some(high))
+    }
+}
 
-        let instance = linker.instantiate(&mut store, &self.module)?;
+/// Run `module` with `context` and at most `gas_limit` gas
+///
+/// Also runs the callees of contract-to-contract calls, each in its own store.
+pub(super) fn execute(
+    engine: &Engine,
+    module: &Module,
+    context: WasmContext,
+    gas_limit: u64,
+) -> anyhow::Result<ContractExecutionResult> {
+    let mut store = Store::new(engine, (GasMeter::new(gas_limit), context));
+    store.add_fuel(gas_limit)?;
+    let mut linker = Linker::new(engine);
 
-        let run_func = instance.get_func(&mut store, "run")
-            .ok_or_else(|| anyhow::anyhow!("Function 'run' not found"))?;
+    // Register enhanced host functions with context
+    HostFunctions::register_with_context(&mut linker)?;
 
-        // Call the run function
-        run_func.call(&mut store, &[], &mut [])?;
+    let instance = linker.instantiate(&mut store, module)?;
 
-        let (gas_meter, context) = store.into_data();
-        let gas_used = gas_meter.gas_used();
+    let run_func = instance.get_func(&mut store, "run")
+        .ok_or_else(|| anyhow::anyhow!("Function 'run' not found"))?;
 
-        Ok(ContractExecutionResult {
-            success: true,
+    // Call the run function; a trap aborts the call and its state changes are dropped
+    if let Err(e) = run_func.call(&mut store, &[], &mut []) {
+        let (gas_used, output) = if is_out_of_gas(&e) {
+            (gas_limit, "Out of gas".to_string())
+        } else {
+            (store.fuel_consumed().unwrap_or(0).min(gas_limit), format!("Execution trapped: {}", e))
+        };
+        return Ok(ContractExecutionResult {
+            success: false,
             gas_used,
-            output: "Contract executed successfully".to_string(),
-            state_changes: context.balances.lock().unwrap().clone(),
-            storage_changes: context.storage.lock().unwrap().clone(),
-        })
+            output,
+            state_changes: HashMap::new(),
+            storage_changes: HashMap::new(),
+            logs: Vec::new(),
+            token_changes: HashMap::new(),
+            return_data: Vec::new(),
+        });
+    }
+
+    let gas_used = store.fuel_consumed().unwrap_or(0);
+    let (_, context) = store.into_data();
+
+    Ok(ContractExecutionResult {
+        success: true,
+        gas_used,
+        output: "Contract executed successfully".to_string(),
+        state_changes: context.balances.lock().unwrap().clone(),
+        storage_changes: context.storage.lock().unwrap().clone(),
+        logs: context.logs.lock().unwrap().clone(),
+        token_changes: context.tokens.lock().unwrap().changes.clone(),
+        return_data: context.output.lock().unwrap().clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::TokenLedger;
+    use crate::wasm::host_functions::MAX_CALL_DEPTH;
+
+    const COUNTER: &str = r#"
+        (module
+          (import "env" "storage_write" (func $write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "countdone")
+          (func (export "run") (local $i i32)
+            (local.set $i (i32.const 100))
+            (loop $l
+              (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+              (br_if $l (local.get $i)))
+            (drop (call $write (i32.const 0) (i32.const 5) (i32.const 5) (i32.const 4)))))
+    "#;
+
+    fn runtime(source: &str) -> WasmRuntime {
+        WasmRuntime::new(&wat::parse_str(source).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_gas_used_is_deterministic() {
+        let first = runtime(COUNTER).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        let second = runtime(COUNTER).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+
+        assert!(first.success);
+        // Loop instructions plus the 30 gas storage_write charge
+        assert!(first.gas_used > 100 * 3 + 30);
+        assert_eq!(first.gas_used, second.gas_used);
+        assert_eq!(first.storage_changes.get("count"), Some(&b"done".to_vec()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_out_of_gas_reverts_changes() {
+        let full = runtime(COUNTER).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        let limit = full.gas_used - 1;
+
+        let result = runtime(COUNTER).execute_contract_with_context(limit, HashMap::new()).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.gas_used, limit);
+        assert!(result.storage_changes.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_gas_is_smallest_working_limit() {
+        let estimate = runtime(COUNTER).estimate_gas(100_000).unwrap().unwrap();
+        assert!(runtime(COUNTER).execute_contract_with_context(estimate, HashMap::new()).unwrap().success);
+        assert!(!runtime(COUNTER).execute_contract_with_context(estimate - 1, HashMap::new()).unwrap().success);
+        assert_eq!(runtime(COUNTER).estimate_gas(estimate - 1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_emitted_logs_are_returned() {
+        let source = r#"
+            (module
+              (import "env" "emit_log" (func $emit (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07\07hi")
+              (func (export "run")
+                (drop (call $emit (i32.const 0) (i32.const 1) (i32.const 32) (i32.const 2)))))
+        "#;
+        let result = runtime(source)
+            .with_address("0xtoken")
+            .execute_contract_with_context(100_000, HashMap::new())
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.logs, vec![Log::new("0xtoken", &[[7; 32]], b"hi")]);
+
+        // Logs are dropped with the rest of the state when execution traps afterwards
+        let trapping = source.replace("(i32.const 2)))))", "(i32.const 2))) unreachable))");
+        let result = runtime(&trapping).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert!(!result.success);
+        assert!(result.logs.is_empty());
+
+        // Negative counts trap instead of wrapping around to huge sizes
+        let args = "(i32.const 0) (i32.const 1) (i32.const 32) (i32.const 2)";
+        for negative_args in [
+            "(i32.const 0) (i32.const -1) (i32.const 32) (i32.const 2)",
+            "(i32.const 0) (i32.const 1) (i32.const 32) (i32.const -1)",
+        ] {
+            let negative = source.replace(args, negative_args);
+            let result = runtime(&negative).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+            assert!(!result.success);
+            assert!(result.logs.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_token_host_functions_act_as_contract() {
+        let mut state = HashMap::new();
+        let gold = TokenLedger::new(&mut state).create("0xvault", "Gold", "GLD", 0, 100, None).unwrap();
+        let source = format!(
+            r#"
+            (module
+              (import "env" "token_transfer" (func $transfer (param i32 i32 i32 i32 i64) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "{}bob")
+              (func (export "run")
+                (drop (call $transfer (i32.const 0) (i32.const 64) (i32.const 64) (i32.const 3) (i64.const 40)))))
+        "#,
+            gold
+        );
+        let base = state.clone();
+        let result = runtime(&source)
+            .with_address("0xvault")
+            .with_token_state(Arc::new(move |key: &[u8]| base.get(key).cloned()))
+            .execute_contract_with_context(100_000, HashMap::new())
+            .unwrap();
+        assert!(result.success);
+
+        state.extend(result.token_changes);
+        let ledger = TokenLedger::new(&mut state);
+        assert_eq!(ledger.balance_of(&gold, "0xvault"), 60);
+        assert_eq!(ledger.balance_of(&gold, "bob"), 40);
+    }
+
+    #[test]
+    fn test_contracts_read_block_random_seed() {
+        let source = r#"
+            (module
+              (import "env" "random_seed" (func $seed (param i32)))
+              (import "env" "storage_write" (func $write (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "seed")
+              (func (export "run")
+                (call $seed (i32.const 16))
+                (drop (call $write (i32.const 0) (i32.const 4) (i32.const 16) (i32.const 32)))))
+        "#;
+        let result = runtime(source)
+            .with_random_seed([9; 32])
+            .execute_contract_with_context(100_000, HashMap::new())
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.storage_changes.get("seed"), Some(&vec![9; 32]));
+    }
+
+    /// Contract calling `next` with input "hi", trapping if the call fails
+    fn forwarding(next: &str) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"
+            (module
+              (import "env" "call" (func $call (param i32 i32 i32 i32 i64) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "hi{}")
+              (func (export "run")
+                (if (call $call (i32.const 2) (i32.const {}) (i32.const 0) (i32.const 2) (i64.const 50000))
+                  (then unreachable))))
+        "#,
+            next,
+            next.len()
+        ))
+        .unwrap()
+    }
+
+    /// Runtime of contract `start` able to call `contracts`
+    fn calling(contracts: HashMap<String, Vec<u8>>, start: &str) -> WasmRuntime {
+        WasmRuntime::new(&contracts[start])
+            .unwrap()
+            .with_address(start)
+            .with_contracts(Arc::new(move |address: &str| contracts.get(address).cloned()))
+    }
+
+    #[test]
+    fn test_nested_calls_merge_logs_and_propagate_failure() {
+        let emitter = wat::parse_str(
+            r#"
+            (module
+              (import "env" "input" (func $input (param i32 i32) (result i32)))
+              (import "env" "emit_log" (func $emit (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (func (export "run")
+                (drop (call $emit (i32.const 0) (i32.const 0) (i32.const 0) (call $input (i32.const 0) (i32.const 64))))))
+        "#,
+        )
+        .unwrap();
+        let chain = |length: usize| {
+            let mut contracts: HashMap<String, Vec<u8>> = (1..length)
+                .map(|i| (format!("0xc{}", i), forwarding(&format!("0xc{}", i + 1))))
+                .collect();
+            contracts.insert(format!("0xc{}", length), emitter.clone());
+            calling(contracts, "0xc1")
+        };
+
+        let result = chain(MAX_CALL_DEPTH).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs, vec![Log::new(&format!("0xc{}", MAX_CALL_DEPTH), &[], b"hi")]);
+        // Callers only pay for the gas their callees used, not all they forwarded
+        assert!(result.gas_used < 50_000);
+
+        // One call too deep fails, and so does every caller above it
+        let result = chain(MAX_CALL_DEPTH + 1).execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert!(!result.success);
+        assert!(result.logs.is_empty());
+    }
+
+    #[test]
+    fn test_contracts_cannot_be_reentered() {
+        let contracts = HashMap::from([
+            ("0xa".to_string(), forwarding("0xb")),
+            ("0xb".to_string(), forwarding("0xa")),
+        ]);
+        let result = calling(contracts, "0xa").execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("trapped"));
+    }
+
+    #[test]
+    fn test_contracts_read_their_address_and_caller() {
+        let reporter = wat::parse_str(
+            r#"
+            (module
+              (import "env" "caller" (func $caller (param i32 i32) (result i32)))
+              (import "env" "address" (func $address (param i32 i32) (result i32)))
+              (import "env" "emit_log" (func $emit (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (func (export "run")
+                (drop (call $emit (i32.const 0) (i32.const 0) (i32.const 0) (call $caller (i32.const 0) (i32.const 64))))
+                (drop (call $emit (i32.const 0) (i32.const 0) (i32.const 64) (call $address (i32.const 64) (i32.const 64))))))
+        "#,
+        )
+        .unwrap();
+        let contracts = HashMap::from([("0xa".to_string(), forwarding("0xb")), ("0xb".to_string(), reporter)]);
+
+        let result = calling(contracts.clone(), "0xa").execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs, vec![Log::new("0xb", &[], b"0xa"), Log::new("0xb", &[], b"0xb")]);
+
+        // Called by a transaction, the contract has no caller
+        let result = calling(contracts, "0xb").execute_contract_with_context(100_000, HashMap::new()).unwrap();
+        assert_eq!(result.logs, vec![Log::new("0xb", &[], b""), Log::new("0xb", &[], b"0xb")]);
+    }
+
+    #[test]
+    fn test_contracts_return_output_for_their_input() {
+        let echo = r#"
+            (module
+              (import "env" "input" (func $input (param i32 i32) (result i32)))
+              (import "env" "set_output" (func $output (param i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "run")
+                (call $output (i32.const 0) (call $input (i32.const 0) (i32.const 64)))))
+        "#;
+        let result = runtime(echo)
+            .with_input(b"ping".to_vec())
+            .execute_contract_with_context(100_000, HashMap::new())
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.return_data, b"ping");
+    }
+
+    /// Example contract of the contract SDK, built by `make contracts`
+    fn sdk_example(name: &str) -> Vec<u8> {
+        let path = format!(
+            "{}/../target/wasm32-unknown-unknown/release/examples/{}.wasm",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {} (run `make contracts`)", path, e))
+    }
+
+    #[test]
+    #[ignore = "needs the contract SDK examples built by `make contracts`"]
+    fn test_sdk_examples_run() {
+        let result = WasmRuntime::new(&sdk_example("counter"))
+            .unwrap()
+            .with_address("0xcounter")
+            .execute_contract_with_context(1_000_000, HashMap::new())
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        assert_eq!(result.storage_changes.get("count"), Some(&1u64.to_le_bytes().to_vec()));
+        let mut incremented = [0; 32];
+        incremented[..11].copy_from_slice(b"Incremented");
+        assert_eq!(result.logs, vec![Log::new("0xcounter", &[incremented, [0; 32]], &1u64.to_le_bytes())]);
+
+        let result = WasmRuntime::new(&sdk_example("token_vault"))
+            .unwrap()
+            .with_address("0xvault")
+            .execute_contract_with_context(1_000_000, HashMap::new())
+            .unwrap();
+        assert!(result.success, "{}", result.output);
+        let token = String::from_utf8(hex::decode(&result.logs[0].data).unwrap()).unwrap();
+        let mut state = result.token_changes;
+        let ledger = TokenLedger::new(&mut state);
+        assert_eq!(ledger.balance_of(&token, "treasury"), 250);
+        assert_eq!(ledger.balance_of(&token, "0xvault"), 750);
+    }
+
+    #[test]
+    fn test_infinite_loop_runs_out_of_gas() {
+        let result = runtime(r#"(module (func (export "run") (loop $l (br $l))))"#)
+            .execute_contract_with_context(10_000, HashMap::new())
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.gas_used, 10_000);
+        assert_eq!(result.output, "Out of gas");
+    }
+}
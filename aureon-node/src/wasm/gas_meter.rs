@@ -1,3 +1,6 @@
+use wasmtime::{Caller, Trap};
+
+/// Gas charged by host functions on top of per-instruction fuel
 #[derive(Debug)]
 pub struct GasMeter {
     limit: u64,
@@ -26,4 +29,39 @@ impl GasMeter {
     pub fn gas_remaining(&self) -> u64 {
         self.limit - self.used
     }
-}
\ No newline at end of file
+}
+
+/// Access to the gas meter held in a store's data
+pub trait HasGasMeter {
+    fn gas_meter(&mut self) -> &mut GasMeter;
+}
+
+impl HasGasMeter for GasMeter {
+    fn gas_meter(&mut self) -> &mut GasMeter {
+        self
+    }
+}
+
+impl<C> HasGasMeter for (GasMeter, C) {
+    fn gas_meter(&mut self) -> &mut GasMeter {
+        &mut self.0
+    }
+}
+
+/// Charge a host function's cost against the store's fuel, so host calls
+/// and wasm instructions draw from the same gas budget
+pub fn charge<T: HasGasMeter>(caller: &mut Caller<'_, T>, amount: u64) -> anyhow::Result<()> {
+    caller
+        .consume_fuel(amount)
+        .map_err(|_| anyhow::Error::new(Trap::OutOfFuel))?;
+    caller
+        .data_mut()
+        .gas_meter()
+        .consume(amount)
+        .map_err(|_| anyhow::Error::new(Trap::OutOfFuel))
+}
+
+/// Whether an execution error was caused by running out of gas
+pub fn is_out_of_gas(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel)
+}
@@ -1,13 +1,40 @@
-use wasmtime::{Caller, Linker};
-use super::gas_meter::GasMeter;
+use wasmtime::{Caller, Linker, Module};
+use super::engine;
+use super::gas_meter::{charge, GasMeter};
+use crate::logs::{Log, MAX_TOPICS, TOPIC_SIZE};
+use crate::token::{TokenLedger, TokenOverlay, TokenState};
 use std::collections::HashMap;
 use std::sync::{Mutex, Arc};
 
+/// Reads the code of a deployed contract by its address
+pub type CodeReader = Arc<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Most contracts a chain of nested calls may have executing at once
+pub const MAX_CALL_DEPTH: usize = 8;
+
+/// Gas a contract call costs on top of the gas the callee uses
+pub const CALL_GAS: u64 = 100;
+
 /// Context passed to WASM runtime for host function access
 #[derive(Clone)]
 pub struct WasmContext {
     pub balances: Arc<Mutex<HashMap<String, u64>>>,
     pub storage: Arc<Mutex<HashMap<String, Vec<u8>>>>, // contract storage key-value
+    /// Address of the executing contract, recorded on emitted logs
+    pub address: String,
+    pub logs: Arc<Mutex<Vec<Log>>>,
+    /// Token state the token host functions operate on, as the executing contract
+    pub tokens: Arc<Mutex<TokenOverlay>>,
+    /// Randomness beacon seed of the block the contract runs in
+    pub random_seed: [u8; 32],
+    /// Input the executing contract was called with
+    pub input: Vec<u8>,
+    /// Code of the contracts the executing contract may call
+    pub contracts: Option<CodeReader>,
+    /// Contracts currently executing, outermost caller first
+    pub call_stack: Vec<String>,
+    /// Bytes the executing contract returns
+    pub output: Arc<Mutex<Vec<u8>>>,
 }
 
 impl WasmContext {
@@ -15,6 +42,14 @@ impl WasmContext {
         Self {
             balances: Arc::new(Mutex::new(HashMap::new())),
             storage: Arc::new(Mutex::new(HashMap::new())),
+            address: String::new(),
+            logs: Arc::new(Mutex::new(Vec::new())),
+            tokens: Arc::new(Mutex::new(TokenOverlay::default())),
+            random_seed: [0; 32],
+            input: Vec::new(),
+            contracts: None,
+            call_stack: Vec::new(),
+            output: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -27,13 +62,59 @@ impl WasmContext {
     }
 }
 
+/// Read a UTF-8 string argument from the contract's memory
+fn read_string<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+    let mut buffer = vec![0u8; len as usize];
+    memory.read(&*caller, ptr as usize, &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Read a byte string argument from the contract's memory
+fn read_bytes<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> anyhow::Result<Vec<u8>> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+    let mut buffer = vec![0u8; len as usize];
+    memory.read(&*caller, ptr as usize, &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Write up to `max_len` bytes of `bytes` to the contract's memory, returning their full length
+fn write_truncated<T>(caller: &mut Caller<'_, T>, out_ptr: i32, max_len: i32, bytes: &[u8]) -> anyhow::Result<i32> {
+    let len = bytes.len().min(max_len.max(0) as usize);
+    let memory = caller
+        .get_export("memory")
+        .and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+    memory.write(&mut *caller, out_ptr as usize, &bytes[..len])?;
+    Ok(bytes.len() as i32)
+}
+
+/// Run a token operation as the executing contract; 0 on success, 1 if it was rejected
+fn with_ledger(
+    caller: &Caller<'_, (GasMeter, WasmContext)>,
+    op: impl FnOnce(&mut TokenLedger<TokenOverlay>, &str) -> Result<(), String>,
+) -> i32 {
+    let context = caller.data().1.clone();
+    let mut tokens = context.tokens.lock().unwrap();
+    match op(&mut TokenLedger::new(&mut *tokens), &context.address) {
+        Ok(()) => 0,
+        Err(_) => 1,
+    }
+}
+
 pub struct HostFunctions;
 
 impl HostFunctions {
     pub fn register(linker: &mut Linker<GasMeter>) -> anyhow::Result<()> {
         // Log host function: charges 10 gas units
         linker.func_wrap("env", "log", |mut caller: Caller<'_, GasMeter>, ptr: i32, len: i32| {
-            caller.data_mut().consume(10).map_err(|e| anyhow::anyhow!(e))?;
+            charge(&mut caller, 10)?;
             let memory = caller.get_export("memory")
                 .and_then(|e| e.into_memory())
                 .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
@@ -56,10 +137,7 @@ impl HostFunctions {
             "env",
             "log",
             |mut caller: Caller<'_, (GasMeter, WasmContext)>, ptr: i32, len: i32| {
-                {
-                    let data = caller.data_mut();
-                    data.0.consume(10).map_err(|e| anyhow::anyhow!(e))?;
-                }
+                charge(&mut caller, 10)?;
 
                 let memory = caller
                     .get_export("memory")
@@ -81,11 +159,8 @@ impl HostFunctions {
             |mut caller: Caller<'_, (GasMeter, WasmContext)>,
              addr_ptr: i32,
              addr_len: i32| {
-                let context = {
-                    let data = caller.data_mut();
-                    data.0.consume(20).map_err(|e| anyhow::anyhow!(e))?;
-                    data.1.clone()
-                };
+                charge(&mut caller, 20)?;
+                let context = caller.data().1.clone();
 
                 let memory = caller
                     .get_export("memory")
@@ -113,11 +188,8 @@ impl HostFunctions {
              to_ptr: i32,
              to_len: i32,
              amount: u64| {
-                let context = {
-                    let data = caller.data_mut();
-                    data.0.consume(50).map_err(|e| anyhow::anyhow!(e))?;
-                    data.1.clone()
-                };
+                charge(&mut caller, 50)?;
+                let context = caller.data().1.clone();
 
                 let memory = caller
                     .get_export("memory")
@@ -160,11 +232,8 @@ impl HostFunctions {
              key_len: i32,
              value_ptr: i32,
              value_max_len: i32| {
-                let context = {
-                    let data = caller.data_mut();
-                    data.0.consume(15).map_err(|e| anyhow::anyhow!(e))?;
-                    data.1.clone()
-                };
+                charge(&mut caller, 15)?;
+                let context = caller.data().1.clone();
 
                 let memory = caller
                     .get_export("memory")
@@ -203,11 +272,8 @@ impl HostFunctions {
              key_len: i32,
              value_ptr: i32,
              value_len: i32| {
-                let context = {
-                    let data = caller.data_mut();
-                    data.0.consume(30).map_err(|e| anyhow::anyhow!(e))?;
-                    data.1.clone()
-                };
+                charge(&mut caller, 30)?;
+                let context = caller.data().1.clone();
 
                 let memory = caller
                     .get_export("memory")
@@ -229,6 +295,344 @@ impl HostFunctions {
             },
         )?;
 
+        // emit_log(topics_ptr: i32, topic_count: i32, data_ptr: i32, data_len: i32) -> i32
+        // Topics are consecutive 32-byte words. Returns 0 on success, 1 if there are too many topics
+        // Charges 40 gas plus 10 per topic and 1 per data byte; traps on negative counts
+        linker.func_wrap(
+            "env",
+            "emit_log",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             topics_ptr: i32,
+             topic_count: i32,
+             data_ptr: i32,
+             data_len: i32| {
+                let (Ok(topic_count), Ok(data_len)) = (usize::try_from(topic_count), usize::try_from(data_len)) else {
+                    anyhow::bail!("emit_log called with a negative topic count or data length");
+                };
+                let cost = (topic_count as u64)
+                    .checked_mul(10)
+                    .and_then(|cost| cost.checked_add(40))
+                    .and_then(|cost| cost.checked_add(data_len as u64))
+                    .ok_or_else(|| anyhow::anyhow!("emit_log cost overflows"))?;
+                charge(&mut caller, cost)?;
+                if topic_count > MAX_TOPICS {
+                    return Ok(1i32);
+                }
+                let context = caller.data().1.clone();
+
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(|e| e.into_memory())
+                    .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+
+                // Read topics
+                let mut topics = vec![[0u8; TOPIC_SIZE]; topic_count];
+                for (i, topic) in topics.iter_mut().enumerate() {
+                    memory.read(&caller, topics_ptr as usize + i * TOPIC_SIZE, topic)?;
+                }
+
+                // Read data
+                let mut data = vec![0u8; data_len];
+                memory.read(&caller, data_ptr as usize, &mut data)?;
+
+                context.logs.lock().unwrap().push(Log::new(&context.address, &topics, &data));
+                Ok(0i32)
+            },
+        )?;
+
+        Self::register_calls(linker)?;
+        Self::register_tokens(linker)
+    }
+
+    /// Contract-to-contract call host functions
+    ///
+    /// A callee runs with its own address and sees the caller's uncommitted
+    /// token changes; its own changes and logs are merged into the caller's
+    /// only if it succeeds. Calls deeper than `MAX_CALL_DEPTH`, and calls to a
+    /// contract that is already executing further up the call stack, fail
+    /// without running anything, so contracts cannot be re-entered.
+    fn register_calls(linker: &mut Linker<(GasMeter, WasmContext)>) -> anyhow::Result<()> {
+        // call(address_ptr, address_len, input_ptr, input_len, gas: u64) -> i32
+        // Runs the contract at address with at most `gas` of the caller's gas,
+        // which pays for the gas the callee used. Returns 0 on success, 1 if the
+        // call was rejected or the callee failed. Charges 100 gas
+        linker.func_wrap(
+            "env",
+            "call",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             address_ptr: i32,
+             address_len: i32,
+             input_ptr: i32,
+             input_len: i32,
+             gas: u64| {
+                charge(&mut caller, CALL_GAS)?;
+                let address = read_string(&mut caller, address_ptr, address_len)?;
+                let input = read_bytes(&mut caller, input_ptr, input_len)?;
+                let context = caller.data().1.clone();
+                if context.call_stack.len() >= MAX_CALL_DEPTH || context.call_stack.contains(&address) {
+                    return Ok(1i32);
+                }
+                let Some(code) = context.contracts.as_ref().and_then(|read| read(&address)) else {
+                    return Ok(1i32);
+                };
+                let Ok(module) = Module::from_binary(caller.engine(), &code) else {
+                    return Ok(1i32);
+                };
+
+                let parent = context.tokens.clone();
+                let mut callee = WasmContext::new();
+                callee.address = address.clone();
+                callee.tokens = Arc::new(Mutex::new(TokenOverlay::new(Some(Arc::new(move |key: &[u8]| {
+                    parent.lock().unwrap().get(key)
+                })))));
+                callee.random_seed = context.random_seed;
+                callee.input = input;
+                callee.contracts = context.contracts.clone();
+                callee.call_stack = [context.call_stack.clone(), vec![address]].concat();
+
+                // Forward no more gas than the caller has left; the unused rest stays with it
+                let gas = gas.min(caller.consume_fuel(0)?);
+                let engine = caller.engine().clone();
+                let result = engine::execute(&engine, &module, callee, gas)?;
+                charge(&mut caller, result.gas_used)?;
+                if !result.success {
+                    return Ok(1i32);
+                }
+                let mut tokens = context.tokens.lock().unwrap();
+                for (key, value) in result.token_changes {
+                    tokens.put(key, value);
+                }
+                context.logs.lock().unwrap().extend(result.logs);
+                Ok(0i32)
+            },
+        )?;
+
+        // input(out_ptr: i32, max_len: i32) -> i32
+        // Writes up to max_len bytes of the call input and returns its full length. Charges 10 gas
+        linker.func_wrap(
+            "env",
+            "input",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, out_ptr: i32, max_len: i32| {
+                charge(&mut caller, 10)?;
+                let input = caller.data().1.input.clone();
+                let len = input.len().min(max_len.max(0) as usize);
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(|e| e.into_memory())
+                    .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+                memory.write(&mut caller, out_ptr as usize, &input[..len])?;
+                Ok(input.len() as i32)
+            },
+        )?;
+
+        // set_output(ptr: i32, len: i32)
+        // Sets the bytes the contract returns, replacing any set before.
+        // Charges 10 gas plus 1 per byte
+        linker.func_wrap(
+            "env",
+            "set_output",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, ptr: i32, len: i32| {
+                charge(&mut caller, 10 + len.max(0) as u64)?;
+                let output = read_bytes(&mut caller, ptr, len)?;
+                *caller.data().1.output.lock().unwrap() = output;
+                Ok(())
+            },
+        )?;
+
+        // address(out_ptr: i32, max_len: i32) -> i32
+        // Writes up to max_len bytes of the executing contract's address and
+        // returns its full length. Charges 10 gas
+        linker.func_wrap(
+            "env",
+            "address",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, out_ptr: i32, max_len: i32| {
+                charge(&mut caller, 10)?;
+                let address = caller.data().1.address.clone();
+                write_truncated(&mut caller, out_ptr, max_len, address.as_bytes())
+            },
+        )?;
+
+        // caller(out_ptr: i32, max_len: i32) -> i32
+        // Like address, for the contract that called the executing one; the
+        // length is 0 when a transaction called it. Charges 10 gas
+        linker.func_wrap(
+            "env",
+            "caller",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, out_ptr: i32, max_len: i32| {
+                charge(&mut caller, 10)?;
+                let stack = &caller.data().1.call_stack;
+                let calling = stack.len().checked_sub(2).map(|i| stack[i].clone()).unwrap_or_default();
+                write_truncated(&mut caller, out_ptr, max_len, calling.as_bytes())
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Token host functions; the executing contract is the sender of every operation
+    ///
+    /// Operations return 0 on success and 1 if the ledger rejected them.
+    fn register_tokens(linker: &mut Linker<(GasMeter, WasmContext)>) -> anyhow::Result<()> {
+        // token_create(name_ptr, name_len, symbol_ptr, symbol_len, decimals: i32, initial_supply: u64, id_ptr: i32) -> i32
+        // Writes the 64-byte hex token id to id_ptr. Charges 100 gas
+        linker.func_wrap(
+            "env",
+            "token_create",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             name_ptr: i32,
+             name_len: i32,
+             symbol_ptr: i32,
+             symbol_len: i32,
+             decimals: i32,
+             initial_supply: u64,
+             id_ptr: i32| {
+                charge(&mut caller, 100)?;
+                let name = read_string(&mut caller, name_ptr, name_len)?;
+                let symbol = read_string(&mut caller, symbol_ptr, symbol_len)?;
+                let Ok(decimals) = u8::try_from(decimals) else {
+                    return Ok(1i32);
+                };
+                let mut id = String::new();
+                let status = with_ledger(&caller, |ledger, sender| {
+                    id = ledger.create(sender, &name, &symbol, decimals, initial_supply, None)?;
+                    Ok(())
+                });
+                if status == 0 {
+                    let memory = caller
+                        .get_export("memory")
+                        .and_then(|e| e.into_memory())
+                        .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+                    memory.write(&mut caller, id_ptr as usize, id.as_bytes())?;
+                }
+                Ok(status)
+            },
+        )?;
+
+        // random_seed(out_ptr: i32)
+        // Writes the 32-byte random seed of the current block. Charges 20 gas
+        linker.func_wrap(
+            "env",
+            "random_seed",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, out_ptr: i32| {
+                charge(&mut caller, 20)?;
+                let seed = caller.data().1.random_seed;
+                let memory = caller
+                    .get_export("memory")
+                    .and_then(|e| e.into_memory())
+                    .ok_or_else(|| anyhow::anyhow!("failed to find memory"))?;
+                memory.write(&mut caller, out_ptr as usize, &seed)?;
+                Ok(())
+            },
+        )?;
+
+        // token_balance(token_ptr, token_len, account_ptr, account_len) -> u64
+        // Charges 20 gas
+        linker.func_wrap(
+            "env",
+            "token_balance",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             token_ptr: i32,
+             token_len: i32,
+             account_ptr: i32,
+             account_len: i32| {
+                charge(&mut caller, 20)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                let account = read_string(&mut caller, account_ptr, account_len)?;
+                let context = caller.data().1.clone();
+                let mut tokens = context.tokens.lock().unwrap();
+                Ok(TokenLedger::new(&mut *tokens).balance_of(&token, &account))
+            },
+        )?;
+
+        // token_transfer(token_ptr, token_len, to_ptr, to_len, amount: u64) -> i32
+        // Charges 50 gas
+        linker.func_wrap(
+            "env",
+            "token_transfer",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             token_ptr: i32,
+             token_len: i32,
+             to_ptr: i32,
+             to_len: i32,
+             amount: u64| {
+                charge(&mut caller, 50)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                let to = read_string(&mut caller, to_ptr, to_len)?;
+                Ok(with_ledger(&caller, |ledger, sender| ledger.transfer(sender, &token, &to, amount)))
+            },
+        )?;
+
+        // token_approve(token_ptr, token_len, spender_ptr, spender_len, amount: u64) -> i32
+        // Charges 50 gas
+        linker.func_wrap(
+            "env",
+            "token_approve",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             token_ptr: i32,
+             token_len: i32,
+             spender_ptr: i32,
+             spender_len: i32,
+             amount: u64| {
+                charge(&mut caller, 50)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                let spender = read_string(&mut caller, spender_ptr, spender_len)?;
+                Ok(with_ledger(&caller, |ledger, sender| ledger.approve(sender, &token, &spender, amount)))
+            },
+        )?;
+
+        // token_transfer_from(token_ptr, token_len, owner_ptr, owner_len, to_ptr, to_len, amount: u64) -> i32
+        // Charges 60 gas
+        linker.func_wrap(
+            "env",
+            "token_transfer_from",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             token_ptr: i32,
+             token_len: i32,
+             owner_ptr: i32,
+             owner_len: i32,
+             to_ptr: i32,
+             to_len: i32,
+             amount: u64| {
+                charge(&mut caller, 60)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                let owner = read_string(&mut caller, owner_ptr, owner_len)?;
+                let to = read_string(&mut caller, to_ptr, to_len)?;
+                Ok(with_ledger(&caller, |ledger, sender| {
+                    ledger.transfer_from(sender, &token, &owner, &to, amount)
+                }))
+            },
+        )?;
+
+        // token_mint(token_ptr, token_len, to_ptr, to_len, amount: u64) -> i32
+        // Only succeeds for tokens the contract owns. Charges 50 gas
+        linker.func_wrap(
+            "env",
+            "token_mint",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>,
+             token_ptr: i32,
+             token_len: i32,
+             to_ptr: i32,
+             to_len: i32,
+             amount: u64| {
+                charge(&mut caller, 50)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                let to = read_string(&mut caller, to_ptr, to_len)?;
+                Ok(with_ledger(&caller, |ledger, sender| ledger.mint(sender, &token, &to, amount)))
+            },
+        )?;
+
+        // token_burn(token_ptr, token_len, amount: u64) -> i32
+        // Charges 50 gas
+        linker.func_wrap(
+            "env",
+            "token_burn",
+            |mut caller: Caller<'_, (GasMeter, WasmContext)>, token_ptr: i32, token_len: i32, amount: u64| {
+                charge(&mut caller, 50)?;
+                let token = read_string(&mut caller, token_ptr, token_len)?;
+                Ok(with_ledger(&caller, |ledger, sender| ledger.burn(sender, &token, amount)))
+            },
+        )?;
+
         Ok(())
     }
 }
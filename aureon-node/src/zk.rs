@@ -1,10 +1,16 @@
 use ark_bls12_381::{Bls12_381, Fr as F};
+use ark_ff::{Field, PrimeField};
 use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey, PreparedVerifyingKey};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use ark_r1cs_std::{prelude::*, fields::fp::FpVar};
 use ark_snark::SNARK;
 use ark_std::rand::{RngCore, CryptoRng};
 use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// Rounds of the MiMC permutation
+const MIMC_ROUNDS: usize = 110;
 
 pub struct MyCircuit {
     pub a: Option<F>,
@@ -63,6 +69,48 @@ pub fn verify_groth16(
     Ok(result)
 }
 
+fn mimc_round_constants() -> &'static [F] {
+    static CONSTANTS: OnceLock<Vec<F>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        (0..MIMC_ROUNDS)
+            .map(|round| F::from_le_bytes_mod_order(&Sha256::digest(format!("aureon-mimc-{}", round))))
+            .collect()
+    })
+}
+
+/// MiMC hash of `message` under `key` (x^5 rounds in Miyaguchi-Preneel
+/// mode), cheap to prove in a circuit unlike SHA-256 or Keccak
+pub fn mimc_hash(message: F, key: F) -> F {
+    let mut x = message;
+    for constant in mimc_round_constants() {
+        x = (x + key + constant).pow([5]);
+    }
+    x + key + message
+}
+
+/// In-circuit `mimc_hash`
+pub fn mimc_hash_var(message: &FpVar<F>, key: &FpVar<F>) -> Result<FpVar<F>, SynthesisError> {
+    let mut x = message.clone();
+    for constant in mimc_round_constants() {
+        let t = &x + key + *constant;
+        let t2 = t.square()?;
+        x = t2.square()? * &t;
+    }
+    Ok(x + key + message)
+}
+
+/// Witness a u64 as 64 bits, so values cannot wrap around the field
+pub fn alloc_u64(cs: ConstraintSystemRef<F>, value: Option<u64>) -> Result<FpVar<F>, SynthesisError> {
+    let bits = (0..64)
+        .map(|bit| {
+            Boolean::new_witness(cs.clone(), || {
+                value.map(|value| (value >> bit) & 1 == 1).ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)
+}
+
 pub fn generate_and_verify_proof(a: i32, b: i32) -> Result<()> {
     use ark_std::rand::thread_rng;
     
@@ -0,0 +1,131 @@
+//! Node API client
+//!
+//! Minimal blocking client for the aureon-node HTTP API, used to look up
+//! account nonces, submit signed transactions, poll for their inclusion and
+//! query the node's status.
+
+use crate::transaction::Transaction;
+use serde::Deserialize;
+use serde_json::Value;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize)]
+struct NonceResponse {
+    nonce: u64,
+}
+
+#[derive(Deserialize)]
+struct TransactionResponse {
+    status: String,
+    message: String,
+}
+
+/// Block a transaction was included in
+#[derive(Debug, Clone)]
+pub struct Inclusion {
+    pub block_hash: String,
+    pub block_number: u64,
+}
+
+pub struct NodeClient {
+    base_url: String,
+    /// API token sent as a bearer token, needed for admin endpoints
+    token: Option<String>,
+}
+
+impl NodeClient {
+    pub fn new(base_url: &str) -> Self {
+        NodeClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// GET `path` as JSON; error statuses with a JSON body, such as /health
+    /// answering 503, are returned like successful responses
+    pub fn get(&self, path: &str) -> Result<Value, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut request = ureq::get(&url);
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        let response = match request.call() {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => return Err(format!("Request to {} failed: {}", url, e)),
+        };
+        response.into_json().map_err(|e| format!("Invalid response from {}: {}", url, e))
+    }
+
+    /// Next nonce the node will accept from `address`
+    pub fn next_nonce(&self, address: &str) -> Result<u64, String> {
+        let body = self.get(&format!("/nonce/{}", address))?;
+        if let Some(error) = body.get("error") {
+            return Err(format!("Node error: {}", error));
+        }
+        let response: NonceResponse = serde_json::from_value(body).map_err(|e| e.to_string())?;
+        Ok(response.nonce)
+    }
+
+    /// Chain id transactions sent to this node must be signed for; empty
+    /// if the node predates replay protection
+    pub fn chain_id(&self) -> Result<String, String> {
+        let body = self.get("/chain/head")?;
+        if let Some(error) = body.get("error") {
+            return Err(format!("Node error: {}", error));
+        }
+        Ok(body["chain_id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Submit a signed transfer to /submit-signed-tx
+    pub fn submit_signed(&self, tx: &Transaction) -> Result<String, String> {
+        self.submit("/submit-signed-tx", tx.signed_request())
+    }
+
+    /// Submit the canonical encoding of any signed transaction to /submit-raw-tx
+    pub fn submit_raw(&self, tx: &Transaction) -> Result<String, String> {
+        self.submit("/submit-raw-tx", serde_json::json!({ "tx": hex::encode(tx.canonical_bytes()) }))
+    }
+
+    fn submit(&self, path: &str, body: Value) -> Result<String, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response: TransactionResponse = ureq::post(&url)
+            .send_json(body)
+            .map_err(|e| format!("Request to {} failed: {}", url, e))?
+            .into_json()
+            .map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+        if response.status != "success" {
+            return Err(response.message);
+        }
+        Ok(response.message)
+    }
+
+    /// Poll the indexer until `tx_hash` is in a block or `timeout` expires
+    pub fn wait_for_inclusion(&self, tx_hash: &str, timeout: Duration) -> Result<Option<Inclusion>, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let body = self.get(&format!("/tx/{}", tx_hash))?;
+            if let (Some(block_hash), Some(block_number)) = (body["block_hash"].as_str(), body["block_number"].as_u64()) {
+                return Ok(Some(Inclusion {
+                    block_hash: block_hash.to_string(),
+                    block_number,
+                }));
+            }
+            if Instant::now() + POLL_INTERVAL > deadline {
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
@@ -1,8 +1,108 @@
-use sha2::{Sha256, Digest};
+use bip39::Mnemonic;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Sha256, Sha512, Digest};
+
+/// Coin type used in Aureon derivation paths (m/44'/COIN_TYPE'/account'/0'/0')
+pub const COIN_TYPE: u32 = 9999;
+
+const HARDENED: u32 = 0x8000_0000;
 
 pub fn derive_address_from_seed(seed: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(seed.as_bytes());
     let result = hasher.finalize();
     hex::encode(&result[..20]) // Truncated hash
-}
\ No newline at end of file
+}
+
+/// Address of an account key: truncated SHA-256 of the public key
+pub fn address_from_public_key(public_key: &VerifyingKey) -> String {
+    let result = Sha256::digest(public_key.as_bytes());
+    hex::encode(&result[..20])
+}
+
+/// Generate a new 24-word BIP-39 mnemonic
+pub fn generate_mnemonic() -> Mnemonic {
+    let mut entropy = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy).expect("32 bytes is a valid entropy length")
+}
+
+/// Parse a BIP-39 mnemonic, checking its word list and checksum
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, String> {
+    Mnemonic::parse(phrase).map_err(|e| format!("Invalid mnemonic: {}", e))
+}
+
+/// Derivation path of the `account`-th key
+pub fn derivation_path(account: u32) -> String {
+    format!("m/44'/{}'/{}'/0'/0'", COIN_TYPE, account)
+}
+
+/// Derive the `account`-th signing key from a mnemonic (SLIP-10, Ed25519)
+pub fn derive_signing_key(mnemonic: &Mnemonic, account: u32) -> Result<SigningKey, String> {
+    if account >= HARDENED {
+        return Err(format!("Account index {} out of range", account));
+    }
+    let seed = mnemonic.to_seed("");
+    let secret = slip10_derive(&seed, &[44, COIN_TYPE, account, 0, 0]);
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// SLIP-10 Ed25519 derivation; every index is hardened
+fn slip10_derive(seed: &[u8], path: &[u32]) -> [u8; 32] {
+    let digest = hmac_sha512(b"ed25519 seed", seed);
+    let (mut key, mut chain_code) = split_digest(&digest);
+    for index in path {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&key);
+        data.extend_from_slice(&(index | HARDENED).to_be_bytes());
+        (key, chain_code) = split_digest(&hmac_sha512(&chain_code, &data));
+    }
+    key
+}
+
+fn split_digest(digest: &[u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    key.copy_from_slice(&digest[..32]);
+    chain_code.copy_from_slice(&digest[32..]);
+    (key, chain_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vectors() {
+        // SLIP-10 test vector 1 for ed25519
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(slip10_derive(&seed, &[])),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(slip10_derive(&seed, &[0])),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
+
+    #[test]
+    fn test_accounts_derive_distinct_keys() {
+        let mnemonic = generate_mnemonic();
+        assert_eq!(mnemonic.word_count(), 24);
+        let restored = parse_mnemonic(&mnemonic.to_string()).unwrap();
+
+        let first = derive_signing_key(&mnemonic, 0).unwrap();
+        assert_eq!(first.to_bytes(), derive_signing_key(&restored, 0).unwrap().to_bytes());
+        assert_ne!(first.to_bytes(), derive_signing_key(&mnemonic, 1).unwrap().to_bytes());
+        assert!(parse_mnemonic("abandon abandon abandon").is_err());
+    }
+}
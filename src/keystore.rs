@@ -0,0 +1,200 @@
+//! Encrypted wallet keystore
+//!
+//! Each wallet is a JSON file holding its mnemonic encrypted with AES-256-GCM
+//! under a key stretched from the user's password with scrypt. The address
+//! of the first account is stored in the clear so wallets can be listed
+//! without unlocking them.
+
+use crate::crypto::{address_from_public_key, derivation_path, derive_signing_key, parse_mnemonic};
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use bip39::Mnemonic;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// scrypt cost parameters
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CryptoSection {
+    pub kdf: String,
+    pub kdf_params: KdfParams,
+    pub salt: String,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// On-disk wallet file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Keystore {
+    pub version: u32,
+    pub name: String,
+    /// Address of account 0
+    pub address: String,
+    pub derivation_path: String,
+    pub crypto: CryptoSection,
+}
+
+fn derive_key(password: &str, salt: &[u8], params: KdfParams) -> Result<[u8; 32], String> {
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+        .map_err(|e| format!("Invalid scrypt parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+impl Keystore {
+    /// Encrypt `mnemonic` under `password`
+    pub fn encrypt(name: &str, mnemonic: &Mnemonic, password: &str, params: KdfParams) -> Result<Self, String> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(format!("Invalid wallet name '{}'", name));
+        }
+        let mut salt = [0u8; 32];
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let key = derive_key(password, &salt, params)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), mnemonic.to_string().as_bytes())
+            .map_err(|_| "Encryption failed".to_string())?;
+
+        let public_key = derive_signing_key(mnemonic, 0)?.verifying_key();
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            name: name.to_string(),
+            address: address_from_public_key(&public_key),
+            derivation_path: derivation_path(0),
+            crypto: CryptoSection {
+                kdf: "scrypt".to_string(),
+                kdf_params: params,
+                salt: hex::encode(salt),
+                cipher: "aes-256-gcm".to_string(),
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        })
+    }
+
+    /// Recover the mnemonic; fails on a wrong password or a tampered file
+    pub fn decrypt(&self, password: &str) -> Result<Mnemonic, String> {
+        if self.version != KEYSTORE_VERSION || self.crypto.kdf != "scrypt" || self.crypto.cipher != "aes-256-gcm" {
+            return Err(format!("Unsupported keystore format in wallet '{}'", self.name));
+        }
+        let salt = hex::decode(&self.crypto.salt).map_err(|e| format!("Invalid salt: {}", e))?;
+        let nonce = hex::decode(&self.crypto.nonce).map_err(|e| format!("Invalid nonce: {}", e))?;
+        let ciphertext = hex::decode(&self.crypto.ciphertext).map_err(|e| format!("Invalid ciphertext: {}", e))?;
+        if nonce.len() != 12 {
+            return Err("Invalid nonce length".to_string());
+        }
+
+        let key = derive_key(password, &salt, self.crypto.kdf_params)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())?;
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| "Wrong password or corrupted keystore".to_string())?;
+        let phrase = String::from_utf8(plaintext).map_err(|_| "Corrupted keystore".to_string())?;
+        parse_mnemonic(&phrase)
+    }
+
+    pub fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{}.json", name))
+    }
+
+    /// Write the wallet to `dir`, refusing to overwrite an existing one
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = Self::path(dir, &self.name);
+        if path.exists() {
+            return Err(format!("Wallet '{}' already exists", self.name));
+        }
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn load(dir: &Path, name: &str) -> Result<Self, String> {
+        let path = Self::path(dir, name);
+        let json = fs::read_to_string(&path).map_err(|_| format!("Wallet '{}' not found", name))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid wallet file {}: {}", path.display(), e))
+    }
+
+    /// Every wallet in `dir`, sorted by name
+    pub fn list(dir: &Path) -> Result<Vec<Self>, String> {
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let mut wallets = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            if let Some(wallet) = fs::read_to_string(&path).ok().and_then(|json| serde_json::from_str(&json).ok()) {
+                wallets.push(wallet);
+            }
+        }
+        wallets.sort_by(|a: &Keystore, b| a.name.cmp(&b.name));
+        Ok(wallets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_mnemonic;
+
+    const FAST: KdfParams = KdfParams { log_n: 4, r: 8, p: 1 };
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let mnemonic = generate_mnemonic();
+        let keystore = Keystore::encrypt("alice", &mnemonic, "hunter2", FAST).unwrap();
+        assert!(!keystore.crypto.ciphertext.contains(&hex::encode(mnemonic.to_string())));
+
+        assert_eq!(keystore.decrypt("hunter2").unwrap(), mnemonic);
+        assert!(keystore.decrypt("wrong").is_err());
+
+        let mut ciphertext = hex::decode(&keystore.crypto.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let mut tampered = keystore.clone();
+        tampered.crypto.ciphertext = hex::encode(ciphertext);
+        assert!(tampered.decrypt("hunter2").is_err());
+    }
+
+    #[test]
+    fn test_save_load_and_list() {
+        let dir = std::env::temp_dir().join(format!("aureon_keystore_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let bob = Keystore::encrypt("bob", &generate_mnemonic(), "pw", FAST).unwrap();
+        let alice = Keystore::encrypt("alice", &generate_mnemonic(), "pw", FAST).unwrap();
+        bob.save(&dir).unwrap();
+        alice.save(&dir).unwrap();
+        assert!(alice.save(&dir).is_err());
+
+        assert_eq!(Keystore::load(&dir, "alice").unwrap().address, alice.address);
+        let names: Vec<String> = Keystore::list(&dir).unwrap().into_iter().map(|w| w.name).collect();
+        assert_eq!(names, vec!["alice", "bob"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
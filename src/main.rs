@@ -1,19 +1,28 @@
-mod genesis;
 mod crypto;
+mod client;
+mod keystore;
+mod transaction;
 mod token;
 mod staking;
 mod state;
+mod node_status;
 
 use clap::{Parser, Subcommand};
-use crypto::derive_address_from_seed;
-use genesis::GenesisBlock;
+use ed25519_dalek::SigningKey;
+use client::NodeClient;
+use crypto::{address_from_public_key, derive_address_from_seed, derive_signing_key, generate_mnemonic, parse_mnemonic};
+use keystore::{KdfParams, Keystore};
+use node_status::{NodeInspection, NodeStatus};
+use transaction::{KeySignature, Transaction};
+use aureon_core::chain_spec::ChainSpec;
 use token::mint_initial_supply;
 use staking::apply_reward;
 use state::State;
 
 use std::fs::File;
 use std::io::Write;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Aureon CLI
 #[derive(Parser)]
@@ -31,13 +40,297 @@ enum Commands {
         #[arg(short, long)]
         seed: String,
     },
-    /// Create the genesis block and simulate reward logic
+    /// Write a chain spec for a new network and simulate reward logic
     InitGenesis {
         #[arg(short, long)]
         chain_id: String,
         #[arg(short, long)]
         validators: Vec<String>,
     },
+    /// Manage encrypted HD wallets
+    Wallet {
+        /// Directory holding the wallet files
+        #[arg(long, default_value = "keystore")]
+        keystore: String,
+        #[command(subcommand)]
+        command: WalletCommands,
+    },
+    /// Build, sign and submit transactions
+    Tx {
+        #[command(subcommand)]
+        command: TxCommands,
+    },
+    /// Query a running node
+    Node {
+        /// Node API endpoint
+        #[arg(long, default_value = "http://127.0.0.1:8080", global = true)]
+        node: String,
+        /// Print JSON instead of a table
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        command: NodeCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum NodeCommands {
+    /// Show the chain head, sync state, peer count and mempool
+    Status {
+        /// Also show the stake and slashing record of this validator
+        #[arg(long)]
+        validator: Option<String>,
+    },
+    /// Show the status along with subsystem health, metrics and peers
+    Inspect {
+        #[arg(long)]
+        validator: Option<String>,
+        /// API token allowed to list peers; defaults to AUREON_API_TOKEN
+        #[arg(long)]
+        token: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TxCommands {
+    /// Sign a transfer with a local wallet and submit it to a node
+    Transfer {
+        /// Wallet to sign with
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: u64,
+        /// Node API endpoint
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        node: String,
+        #[arg(long, default_value = "keystore")]
+        keystore: String,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value_t = 1)]
+        gas_price: u64,
+        /// Seconds to wait for the transaction to be included
+        #[arg(long, default_value_t = 30)]
+        wait: u64,
+    },
+    /// Combine partial signatures of a multisig account's transfer and submit it
+    Multisig {
+        /// Multisig account sending the transfer
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        nonce: u64,
+        #[arg(long, default_value_t = 1)]
+        gas_price: u64,
+        /// Partial signature from `wallet sign-multisig`, as <key_index>:<hex signature>
+        #[arg(long = "signature", required = true)]
+        signatures: Vec<KeySignature>,
+        /// Node API endpoint
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        node: String,
+        /// Seconds to wait for the transaction to be included
+        #[arg(long, default_value_t = 30)]
+        wait: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum WalletCommands {
+    /// Create a wallet from a new mnemonic
+    Create {
+        name: String,
+    },
+    /// Import a wallet from an existing mnemonic
+    Import {
+        name: String,
+    },
+    /// List wallets and their addresses
+    List,
+    /// Sign a transfer with a wallet account
+    SignTx {
+        name: String,
+        /// Chain the transfer is for, as reported by the node's /chain/head
+        #[arg(long)]
+        chain_id: String,
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long, default_value_t = 0)]
+        nonce: u64,
+        #[arg(long, default_value_t = 1)]
+        gas_price: u64,
+        /// HD account index to sign with
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+    },
+    /// Sign a multisig account's transfer as one of its keys
+    SignMultisig {
+        name: String,
+        /// Chain the transfer is for, as reported by the node's /chain/head
+        #[arg(long)]
+        chain_id: String,
+        /// Multisig account sending the transfer
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: String,
+        #[arg(long)]
+        amount: u64,
+        #[arg(long)]
+        nonce: u64,
+        #[arg(long, default_value_t = 1)]
+        gas_price: u64,
+        /// Index of the wallet's key among the account's registered keys
+        #[arg(long)]
+        key_index: u32,
+        /// HD account index to sign with
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+    },
+}
+
+/// Read a password from AUREON_WALLET_PASSWORD or the terminal
+fn read_password(confirm: bool) -> Result<String, String> {
+    if let Ok(password) = std::env::var("AUREON_WALLET_PASSWORD") {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("Wallet password: ").map_err(|e| e.to_string())?;
+    if confirm && rpassword::prompt_password("Repeat password: ").map_err(|e| e.to_string())? != password {
+        return Err("Passwords do not match".to_string());
+    }
+    Ok(password)
+}
+
+/// Decrypt a wallet and derive the signing key and address of `account`
+fn unlock_account(keystore_dir: &Path, name: &str, account: u32) -> Result<(SigningKey, String), String> {
+    let mnemonic = Keystore::load(keystore_dir, name)?.decrypt(&read_password(false)?)?;
+    let signing_key = derive_signing_key(&mnemonic, account)?;
+    let address = format!("0x{}", address_from_public_key(&signing_key.verifying_key()));
+    Ok((signing_key, address))
+}
+
+fn run_wallet(keystore_dir: &Path, command: &WalletCommands) -> Result<(), String> {
+    match command {
+        WalletCommands::Create { name } => {
+            let mnemonic = generate_mnemonic();
+            let wallet = Keystore::encrypt(name, &mnemonic, &read_password(true)?, KdfParams::default())?;
+            let path = wallet.save(keystore_dir)?;
+            println!("🔐 Wallet '{}' written to {}", name, path.display());
+            println!("📬 Address: 0x{}", wallet.address);
+            println!("📝 Recovery phrase (write it down, it is not shown again):\n{}", mnemonic);
+        }
+        WalletCommands::Import { name } => {
+            let phrase = rpassword::prompt_password("Recovery phrase: ").map_err(|e| e.to_string())?;
+            let mnemonic = parse_mnemonic(phrase.trim())?;
+            let wallet = Keystore::encrypt(name, &mnemonic, &read_password(true)?, KdfParams::default())?;
+            let path = wallet.save(keystore_dir)?;
+            println!("🔐 Wallet '{}' imported to {}", name, path.display());
+            println!("📬 Address: 0x{}", wallet.address);
+        }
+        WalletCommands::List => {
+            let wallets = Keystore::list(keystore_dir)?;
+            if wallets.is_empty() {
+                println!("No wallets in {}", keystore_dir.display());
+            }
+            for wallet in wallets {
+                println!("{}\t0x{}\t{}", wallet.name, wallet.address, wallet.derivation_path);
+            }
+        }
+        WalletCommands::SignTx { name, chain_id, to, amount, nonce, gas_price, account } => {
+            let (signing_key, from) = unlock_account(keystore_dir, name, *account)?;
+            let mut tx = Transaction::transfer(chain_id.clone(), from, to.clone(), *amount, *nonce, *gas_price);
+            tx.sign(&signing_key);
+            let signed = serde_json::json!({
+                "hash": tx.hash(),
+                // Body for POST /submit-raw-tx
+                "raw": { "tx": hex::encode(tx.canonical_bytes()) },
+                // Body for POST /submit-signed-tx
+                "signed": tx.signed_request(),
+            });
+            println!("{}", serde_json::to_string_pretty(&signed).map_err(|e| e.to_string())?);
+        }
+        WalletCommands::SignMultisig { name, chain_id, from, to, amount, nonce, gas_price, key_index, account } => {
+            let (signing_key, signer) = unlock_account(keystore_dir, name, *account)?;
+            let tx = Transaction::transfer(chain_id.clone(), from.clone(), to.clone(), *amount, *nonce, *gas_price);
+            let partial = serde_json::json!({
+                "signer": signer,
+                // Value for `tx multisig --signature`
+                "signature": tx.sign_partial(&signing_key, *key_index).to_string(),
+            });
+            println!("{}", serde_json::to_string_pretty(&partial).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(())
+}
+
+fn run_tx(command: &TxCommands) -> Result<(), String> {
+    match command {
+        TxCommands::Transfer { from, to, amount, node, keystore, account, gas_price, wait } => {
+            let (signing_key, address) = unlock_account(Path::new(keystore), from, *account)?;
+            let client = NodeClient::new(node);
+            let nonce = client.next_nonce(&address)?;
+
+            let mut tx = Transaction::transfer(client.chain_id()?, address.clone(), to.clone(), *amount, nonce, *gas_price);
+            tx.sign(&signing_key);
+            let tx_hash = tx.hash();
+            println!("📤 Sending {} from {} to {} (nonce {})", amount, address, to, nonce);
+            println!("✅ {}", client.submit_signed(&tx)?);
+            println!("🔗 Transaction hash: {}", tx_hash);
+
+            wait_for_inclusion(&client, &tx_hash, *wait)?;
+        }
+        TxCommands::Multisig { from, to, amount, nonce, gas_price, signatures, node, wait } => {
+            let client = NodeClient::new(node);
+            let mut tx = Transaction::transfer(client.chain_id()?, from.clone(), to.clone(), *amount, *nonce, *gas_price);
+            tx.combine(signatures);
+            let tx_hash = tx.hash();
+            println!("📤 Sending {} from multisig {} to {} ({} signatures)", amount, from, to, signatures.len());
+            println!("✅ {}", client.submit_raw(&tx)?);
+            println!("🔗 Transaction hash: {}", tx_hash);
+            wait_for_inclusion(&client, &tx_hash, *wait)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_node(node: &str, json: bool, command: &NodeCommands) -> Result<(), String> {
+    let (json_output, table) = match command {
+        NodeCommands::Status { validator } => {
+            let status = NodeStatus::fetch(&NodeClient::new(node), validator.as_deref())?;
+            (serde_json::to_string_pretty(&status), status.render())
+        }
+        NodeCommands::Inspect { validator, token } => {
+            let token = token.clone().or_else(|| std::env::var("AUREON_API_TOKEN").ok());
+            let list_peers = token.is_some();
+            let client = NodeClient::new(node).with_token(token);
+            let inspection = NodeInspection::fetch(&client, validator.as_deref(), list_peers)?;
+            (serde_json::to_string_pretty(&inspection), inspection.render())
+        }
+    };
+    if json {
+        println!("{}", json_output.map_err(|e| e.to_string())?);
+    } else {
+        print!("{}", table);
+    }
+    Ok(())
+}
+
+fn wait_for_inclusion(client: &NodeClient, tx_hash: &str, wait: u64) -> Result<(), String> {
+    match client.wait_for_inclusion(tx_hash, Duration::from_secs(wait))? {
+        Some(inclusion) => println!(
+            "📦 Included in block {} ({})",
+            inclusion.block_number, inclusion.block_hash
+        ),
+        None => println!("⏳ Pending: not yet included in a block"),
+    }
+    Ok(())
 }
 
 fn main() {
@@ -52,19 +345,24 @@ fn main() {
         Commands::InitGenesis { chain_id, validators } => {
             let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
 
-            // Write Genesis Block
-            let balances = validators.iter().map(|v| (v.clone(), 1_000_000u64)).collect();
-            let genesis = GenesisBlock {
+            // Write Chain Spec, with mainnet consensus parameters
+            let genesis = ChainSpec {
                 chain_id: chain_id.clone(),
                 timestamp,
-                initial_validators: validators.clone(),
-                initial_balances: balances,
-                nonce: 0,
+                consensus: ChainSpec::builtin("mainnet").unwrap().consensus,
+                validators: validators.iter().map(|v| (v.clone(), 1_000_000u64)).collect(),
+                allocations: validators.iter().map(|v| (v.clone(), 1_000_000u64)).collect(),
+                forks: Default::default(),
             };
+            if let Err(e) = genesis.validate() {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
             let json = serde_json::to_string_pretty(&genesis).unwrap();
             let mut file = File::create("genesis.json").unwrap();
             file.write_all(json.as_bytes()).unwrap();
-            println!("✅ Genesis block written to genesis.json");
+            println!("✅ Chain spec written to genesis.json");
+            println!("🧬 Genesis hash: {}", genesis.genesis_hash());
 
             // Initialize in-memory state
             let mut state = State::new();
@@ -82,5 +380,26 @@ fn main() {
             println!("🟢 Final balances: {:#?}", state.balances);
             println!("💸 Total Supply: {}", state.total_supply);
         }
+
+        Commands::Wallet { keystore, command } => {
+            if let Err(e) = run_wallet(Path::new(keystore), command) {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Tx { command } => {
+            if let Err(e) = run_tx(command) {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Node { node, json, command } => {
+            if let Err(e) = run_node(node, *json, command) {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,315 @@
+//! Node status
+//!
+//! Collects a running node's chain head, sync state, peers, mempool and,
+//! optionally, a validator's standing from its REST API, and renders them
+//! as tables for the `node status` and `node inspect` commands.
+
+use crate::client::NodeClient;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Summary printed by `node status`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+    pub node: String,
+    /// "healthy", "degraded" or "unhealthy", as reported by /health
+    pub health: String,
+    pub best_block_number: u64,
+    pub best_block_hash: String,
+    pub finalized_block_number: Option<u64>,
+    /// Whether the node finished its initial sync
+    pub synced: bool,
+    pub peers: u64,
+    pub mempool: MempoolStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator: Option<ValidatorStatus>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MempoolStatus {
+    pub pending: u64,
+    pub queued: u64,
+    pub capacity: u64,
+    pub utilization_percent: f64,
+    pub min_gas_price: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ValidatorStatus {
+    pub address: String,
+    pub self_stake: u64,
+    pub delegated: u64,
+    pub delegators: usize,
+    pub slashing_events: u64,
+}
+
+/// Status plus the subsystem and peer details printed by `node inspect`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NodeInspection {
+    #[serde(flatten)]
+    pub status: NodeStatus,
+    /// Health of each subsystem, such as block production and sync
+    pub components: BTreeMap<String, String>,
+    /// Counters from /metrics/summary
+    pub metrics: BTreeMap<String, u64>,
+    /// Connected peers; listing them needs an API token with node management rights
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_list: Option<Vec<PeerStatus>>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PeerStatus {
+    pub peer_id: String,
+    pub address: Option<String>,
+    pub version: String,
+    pub latest_block_height: u64,
+    pub score: i64,
+}
+
+/// Fail with the node's message if `body` is an error response
+fn check(body: Value) -> Result<Value, String> {
+    if body["status"] == "error" {
+        return Err(format!("Node error: {}", body["message"].as_str().unwrap_or("unknown error")));
+    }
+    if let Some(error) = body.get("error") {
+        return Err(format!("Node error: {}", error));
+    }
+    Ok(body)
+}
+
+impl NodeStatus {
+    /// Query the node behind `client`, including `validator`'s standing if given
+    pub fn fetch(client: &NodeClient, validator: Option<&str>) -> Result<Self, String> {
+        let validator = match validator {
+            Some(address) => Some((
+                address,
+                check(client.get(&format!("/validator/{}/delegations", address))?)?,
+                check(client.get(&format!("/slashing/{}", address))?)?,
+            )),
+            None => None,
+        };
+        Ok(NodeStatus::from_responses(
+            client.base_url(),
+            &check(client.get("/chain/head")?)?,
+            &client.get("/health")?,
+            &client.get("/ready")?,
+            &check(client.get("/mempool")?)?,
+            validator.as_ref().map(|(address, delegations, slashing)| ValidatorStatus {
+                address: address.to_string(),
+                self_stake: delegations["self_stake"].as_u64().unwrap_or(0),
+                delegated: delegations["delegated"].as_u64().unwrap_or(0),
+                delegators: delegations["delegations"].as_array().map_or(0, Vec::len),
+                slashing_events: slashing["count"].as_u64().unwrap_or(0),
+            }),
+        ))
+    }
+
+    fn from_responses(
+        node: &str,
+        head: &Value,
+        health: &Value,
+        ready: &Value,
+        mempool: &Value,
+        validator: Option<ValidatorStatus>,
+    ) -> Self {
+        NodeStatus {
+            node: node.to_string(),
+            health: health["status"].as_str().unwrap_or("unknown").to_string(),
+            best_block_number: head["best_block_number"].as_u64().unwrap_or(0),
+            best_block_hash: head["best_block_hash"].as_str().unwrap_or_default().to_string(),
+            finalized_block_number: head["finalized_block_number"].as_u64(),
+            synced: ready["ready"].as_bool().unwrap_or(false),
+            peers: health["peers_connected"].as_u64().unwrap_or(0),
+            mempool: MempoolStatus {
+                pending: mempool["pending_transactions"].as_u64().unwrap_or(0),
+                queued: mempool["queued_transactions"].as_u64().unwrap_or(0),
+                capacity: mempool["max_capacity"].as_u64().unwrap_or(0),
+                utilization_percent: mempool["utilization_percent"].as_f64().unwrap_or(0.0),
+                min_gas_price: mempool["min_gas_price"].as_u64().unwrap_or(0),
+            },
+            validator,
+        }
+    }
+
+    fn rows(&self) -> Vec<(String, String)> {
+        let mut rows = vec![
+            ("Node".to_string(), self.node.clone()),
+            ("Health".to_string(), self.health.clone()),
+            ("Chain head".to_string(), format!("#{} {}", self.best_block_number, self.best_block_hash)),
+            (
+                "Finalized".to_string(),
+                self.finalized_block_number.map_or_else(|| "none".to_string(), |number| format!("#{}", number)),
+            ),
+            ("Sync".to_string(), if self.synced { "synced" } else { "syncing" }.to_string()),
+            ("Peers".to_string(), self.peers.to_string()),
+            (
+                "Mempool".to_string(),
+                format!(
+                    "{} pending, {} queued ({:.1}% of {})",
+                    self.mempool.pending, self.mempool.queued, self.mempool.utilization_percent, self.mempool.capacity
+                ),
+            ),
+            ("Min gas price".to_string(), self.mempool.min_gas_price.to_string()),
+        ];
+        if let Some(validator) = &self.validator {
+            rows.push(("Validator".to_string(), validator.address.clone()));
+            rows.push((
+                "Stake".to_string(),
+                format!(
+                    "{} self + {} delegated ({} delegators)",
+                    validator.self_stake, validator.delegated, validator.delegators
+                ),
+            ));
+            rows.push(("Slashing events".to_string(), validator.slashing_events.to_string()));
+        }
+        rows
+    }
+
+    /// Human-readable table of the status
+    pub fn render(&self) -> String {
+        table(&["Field", "Value"], &self.rows().into_iter().map(|(k, v)| vec![k, v]).collect::<Vec<_>>())
+    }
+}
+
+impl NodeInspection {
+    /// Query the node's status and, with an API token, its peers
+    pub fn fetch(client: &NodeClient, validator: Option<&str>, list_peers: bool) -> Result<Self, String> {
+        let status = NodeStatus::fetch(client, validator)?;
+        let health = client.get("/health")?;
+        let components = health["components"]
+            .as_object()
+            .map(|components| {
+                components
+                    .iter()
+                    .map(|(name, status)| (name.clone(), status.as_str().unwrap_or("unknown").to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let metrics = client
+            .get("/metrics/summary")?
+            .as_object()
+            .map(|metrics| metrics.iter().filter_map(|(name, value)| Some((name.clone(), value.as_u64()?))).collect())
+            .unwrap_or_default();
+        let peer_list = if list_peers {
+            let peers = check(client.get("/admin/peers")?)?;
+            Some(
+                peers["peers"]
+                    .as_array()
+                    .map(|peers| {
+                        peers
+                            .iter()
+                            .map(|peer| PeerStatus {
+                                peer_id: peer["peer_id"].as_str().unwrap_or_default().to_string(),
+                                address: peer["address"].as_str().map(str::to_string),
+                                version: peer["version"].as_str().unwrap_or("unknown").to_string(),
+                                latest_block_height: peer["latest_block_height"].as_u64().unwrap_or(0),
+                                score: peer["score"].as_i64().unwrap_or(0),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            )
+        } else {
+            None
+        };
+        Ok(NodeInspection { status, components, metrics, peer_list })
+    }
+
+    /// Human-readable tables of the status, subsystems, metrics and peers
+    pub fn render(&self) -> String {
+        let mut rows = self.status.rows();
+        rows.extend(self.components.iter().map(|(name, status)| (format!("Component {}", name), status.clone())));
+        rows.extend(self.metrics.iter().map(|(name, value)| (format!("Metric {}", name), value.to_string())));
+        let mut output = table(&["Field", "Value"], &rows.into_iter().map(|(k, v)| vec![k, v]).collect::<Vec<_>>());
+        match &self.peer_list {
+            Some(peers) if peers.is_empty() => output.push_str("\nNo connected peers\n"),
+            Some(peers) => {
+                let peer_rows: Vec<Vec<String>> = peers
+                    .iter()
+                    .map(|peer| {
+                        vec![
+                            peer.peer_id.clone(),
+                            peer.address.clone().unwrap_or_else(|| "-".to_string()),
+                            peer.version.clone(),
+                            peer.latest_block_height.to_string(),
+                            peer.score.to_string(),
+                        ]
+                    })
+                    .collect();
+                output.push('\n');
+                output.push_str(&table(&["Peer", "Address", "Version", "Height", "Score"], &peer_rows));
+            }
+            None => output.push_str("\nPass --token to list peers\n"),
+        }
+        output
+    }
+}
+
+/// Left-aligned table with a header row and a separator
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|column| column.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &Vec<String>| {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let header: Vec<String> = header.iter().map(|column| column.to_string()).collect();
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    [&header, &separator].into_iter().chain(rows).map(line).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn status() -> NodeStatus {
+        NodeStatus::from_responses(
+            "http://127.0.0.1:8080",
+            &json!({ "chain_name": "Aureon", "best_block_number": 42, "best_block_hash": "0xabc", "finalized_block_number": 40 }),
+            &json!({ "status": "degraded", "chain_height": 42, "peers_connected": 3, "mempool_size": 5 }),
+            &json!({ "ready": false, "chain_height": 42 }),
+            &json!({ "status": "ok", "pending_transactions": 5, "queued_transactions": 1, "max_capacity": 200, "utilization_percent": 2.5, "min_gas_price": 1 }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_status_parsed_from_api_responses() {
+        let status = status();
+        assert_eq!(status.best_block_number, 42);
+        assert_eq!(status.finalized_block_number, Some(40));
+        assert!(!status.synced);
+        assert_eq!(status.peers, 3);
+        assert_eq!(status.mempool.queued, 1);
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["mempool"]["pending"], 5);
+        assert!(json.get("validator").is_none());
+        assert!(check(json!({ "status": "error", "message": "boom" })).unwrap_err().contains("boom"));
+    }
+
+    #[test]
+    fn test_render_aligns_rows() {
+        let mut status = status();
+        status.validator = Some(ValidatorStatus {
+            address: "0xval".to_string(),
+            self_stake: 1000,
+            delegated: 250,
+            delegators: 2,
+            slashing_events: 0,
+        });
+        let rendered = status.render();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "Field            Value");
+        assert!(lines[1].starts_with("---------------  -----"));
+        assert!(rendered.contains("Chain head       #42 0xabc\n"));
+        assert!(rendered.contains("Sync             syncing\n"));
+        assert!(rendered.contains("Stake            1000 self + 250 delegated (2 delegators)\n"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Node transaction encoding
+//!
+//! Mirrors the transaction type of aureon-node so the CLI can sign
+//! transactions offline. The canonical encoding is bincode (standard
+//! configuration) of the fields in declaration order, so field and variant
+//! order here must match the node's `types::Transaction`.
+//! aureon-node compiles this file into its tests to check the two agree.
+
+use bincode::config::standard;
+use bincode::Encode;
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Transaction payload; only transfers are built by the CLI
+///
+/// `Transfer` is the first variant of the node's payload enum, so its
+/// variant index matches.
+#[derive(Debug, Clone, Encode)]
+pub enum TransactionPayload {
+    Transfer { to: String, amount: u64 },
+}
+
+/// Signature by one key of a multisig account, encoded like the node's
+/// `multisig::KeySignature`
+///
+/// Written as `<key_index>:<hex signature>` on the command line.
+#[derive(Debug, Clone, PartialEq, Encode)]
+pub struct KeySignature {
+    pub key_index: u32,
+    pub signature: Vec<u8>,
+}
+
+impl fmt::Display for KeySignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.key_index, hex::encode(&self.signature))
+    }
+}
+
+impl FromStr for KeySignature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (index, signature) = s.split_once(':').ok_or("Expected <key_index>:<hex signature>")?;
+        Ok(KeySignature {
+            key_index: index.parse().map_err(|_| format!("Invalid key index '{}'", index))?,
+            signature: hex::decode(signature).map_err(|_| "Signature must be hex".to_string())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Encode)]
+pub struct Transaction {
+    /// Chain the transaction is signed for, so other networks reject it
+    pub chain_id: String,
+    pub from: String,
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub payload: TransactionPayload,
+    pub signature: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+impl Transaction {
+    pub fn transfer(chain_id: String, from: String, to: String, amount: u64, nonce: u64, gas_price: u64) -> Self {
+        Transaction {
+            chain_id,
+            from,
+            nonce,
+            gas_price,
+            payload: TransactionPayload::Transfer { to, amount },
+            signature: vec![],
+            public_key: vec![],
+        }
+    }
+
+    /// Canonical binary encoding, as accepted by the node's /submit-raw-tx
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, standard()).expect("transaction encoding cannot fail")
+    }
+
+    /// Hex-encoded SHA-256 of the canonical encoding
+    pub fn hash(&self) -> String {
+        hex::encode(Sha256::digest(self.canonical_bytes()))
+    }
+
+    /// Sign the canonical encoding with an empty signature field
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        self.public_key = signing_key.verifying_key().to_bytes().to_vec();
+        self.signature = vec![];
+        self.signature = signing_key.sign(&self.canonical_bytes()).to_bytes().to_vec();
+    }
+
+    /// Sign as the multisig key at `key_index`, leaving the transaction unchanged
+    ///
+    /// Transactions from multisig accounts are signed with empty public key
+    /// and signature fields.
+    pub fn sign_partial(&self, signing_key: &SigningKey, key_index: u32) -> KeySignature {
+        let mut unsigned = self.clone();
+        unsigned.public_key = vec![];
+        unsigned.signature = vec![];
+        let signature = signing_key.sign(&unsigned.canonical_bytes()).to_bytes().to_vec();
+        KeySignature { key_index, signature }
+    }
+
+    /// Combine partial signatures into the signature field of a multisig
+    /// transaction, keeping one signature per key
+    pub fn combine(&mut self, signatures: &[KeySignature]) {
+        let mut signatures = signatures.to_vec();
+        signatures.sort_by_key(|partial| partial.key_index);
+        signatures.dedup_by_key(|partial| partial.key_index);
+        self.public_key = vec![];
+        self.signature = bincode::encode_to_vec(&signatures, standard()).expect("signature encoding cannot fail");
+    }
+
+    /// JSON body for the node's /submit-signed-tx
+    pub fn signed_request(&self) -> serde_json::Value {
+        let TransactionPayload::Transfer { to, amount } = &self.payload;
+        serde_json::json!({
+            "chain_id": self.chain_id,
+            "from": self.from,
+            "to": to,
+            "amount": amount,
+            "nonce": self.nonce,
+            "gas_price": self.gas_price,
+            "public_key": hex::encode(&self.public_key),
+            "signature": hex::encode(&self.signature),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signature, Verifier};
+
+    #[test]
+    fn test_signature_covers_unsigned_encoding() {
+        let key = SigningKey::from_bytes(&[5u8; 32]);
+        let mut tx = Transaction::transfer("aureon-local".into(), "alice".into(), "bob".into(), 10, 0, 1);
+        tx.sign(&key);
+
+        let mut unsigned = tx.clone();
+        unsigned.signature = vec![];
+        let signature = Signature::from_slice(&tx.signature).unwrap();
+        assert!(key.verifying_key().verify(&unsigned.canonical_bytes(), &signature).is_ok());
+        assert_eq!(tx.hash().len(), 64);
+    }
+
+    #[test]
+    fn test_combined_partial_signatures_decode_per_key() {
+        let keys = [SigningKey::from_bytes(&[1u8; 32]), SigningKey::from_bytes(&[2u8; 32])];
+        let mut tx = Transaction::transfer("aureon-local".into(), "treasury".into(), "bob".into(), 10, 3, 1);
+        let partials: Vec<KeySignature> = vec![tx.sign_partial(&keys[1], 1), tx.sign_partial(&keys[0], 0)];
+        let unsigned = tx.canonical_bytes();
+
+        let parsed: KeySignature = partials[0].to_string().parse().unwrap();
+        assert_eq!(parsed, partials[0]);
+        tx.combine(&[parsed, partials[1].clone(), partials[1].clone()]);
+
+        // Vec length, then (key index, signature) per key in index order
+        assert_eq!(tx.signature.len(), 1 + 2 * (1 + 1 + 64));
+        assert_eq!((tx.signature[0], tx.signature[1], tx.signature[67]), (2, 0, 1));
+        let signature = Signature::from_slice(&tx.signature[3..67]).unwrap();
+        assert!(keys[0].verifying_key().verify(&unsigned, &signature).is_ok());
+    }
+}